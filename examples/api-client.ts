@@ -0,0 +1,65 @@
+// Minimal alternative frontend for Dictation HUD, e.g. as a starting point for a Stream Deck
+// property inspector or a custom HUD. It runs inside a Tauri webview of the app and talks to the
+// backend through the same commands and events the bundled UI uses; their payloads are described
+// by the schema from `get_api_schema` (or `cargo run --example dump_api_schema`).
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+// The schema version this client was written against
+const SUPPORTED_API_VERSION = 2;
+
+// Payload of the `hud-event` event, tagged by `type` (only the kinds this client handles)
+type HudEvent =
+  | { v: number; type: 'start'; session_id: number }
+  | { v: number; type: 'stop' }
+  | { v: number; type: 'badge'; msg: string; kind: 'error' | 'secure' }
+  | { v: number; type: 'error'; code: string; message: string };
+
+interface Word {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence?: number | null;
+  estimated?: boolean;
+}
+
+interface Transcript {
+  session_id: number | null;
+  provider: string;
+  language: string | null;
+  segments: { text: string; start_ms?: number | null; end_ms?: number | null; confidence?: number | null; words?: Word[] }[];
+  refined: string | null;
+}
+
+export async function connect(handlers: {
+  onStart?: (sessionId: number) => void;
+  onStop?: () => void;
+  onBadge?: (text: string, kind: 'error' | 'secure') => void;
+  onError?: (code: string, message: string) => void;
+}): Promise<UnlistenFn> {
+  const schema = await invoke<{ version: number; events: Record<string, unknown> }>('get_api_schema');
+  if (schema.version !== SUPPORTED_API_VERSION) {
+    throw new Error(`Dictation HUD API v${schema.version}, this client supports v${SUPPORTED_API_VERSION}`);
+  }
+
+  return listen<HudEvent>('hud-event', (e) => {
+    const ev = e.payload;
+    switch (ev.type) {
+      case 'start': return handlers.onStart?.(ev.session_id);
+      case 'stop': return handlers.onStop?.();
+      case 'badge': return handlers.onBadge?.(ev.msg, ev.kind);
+      case 'error': return handlers.onError?.(ev.code, ev.message);
+    }
+  });
+}
+
+export const start = () => invoke<void>('start_dictation');
+export const stop = () => invoke<void>('trigger_stop_dictation');
+export const isActive = () => invoke<boolean>('is_dictation_active');
+
+// Refine and insert text produced elsewhere (e.g. your own STT), as the bundled HUD does on stop
+export async function insert(text: string, provider = 'external'): Promise<boolean> {
+  let transcript: Transcript = { session_id: null, provider, language: null, segments: [{ text }], refined: null };
+  transcript = await invoke<Transcript>('refine_text', { transcript, openrouterKey: null });
+  return invoke<boolean>('insert_text', { transcript });
+}
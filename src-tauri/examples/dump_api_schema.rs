@@ -0,0 +1,6 @@
+// Print the event/command schema as JSON:
+//   cargo run --example dump_api_schema > api-schema.json
+fn main() {
+  let doc = dictation_hud::api_schema::document();
+  println!("{}", serde_json::to_string_pretty(&doc).expect("schema serializes"));
+}
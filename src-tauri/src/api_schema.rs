@@ -0,0 +1,551 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Map, Value};
+
+use crate::{audio_device, backup, config_profiles, connectivity, cost, cues, failures, focus_probe, focused_monitor, history, hud_event, jobs, journal, key_inspect, last_audio, learning, maintenance, meeting, network_monitor, pack, pack_store, paste_confirm, pipeline, profiles, prompt, quota, session_metrics, startup, stt_options, transcript, usage, watch_folder, window_target};
+
+// Machine-readable contract for everything outside the bundled UI may rely on: the events the
+// backend emits and the commands a frontend can invoke, with JSON Schemas for their payloads.
+// Bump API_VERSION whenever a listed name or payload changes incompatibly; adding optional
+// fields or new entries does not need a bump.
+
+pub const API_VERSION: u32 = 2;
+
+/// Argument objects of the listed commands. Tauri passes arguments by camelCase name.
+#[allow(dead_code)]
+mod args {
+  use super::*;
+
+  #[derive(JsonSchema)]
+  pub struct NoArgs {}
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "lowercase")]
+  pub enum RecordingState {
+    Recording,
+    Stopping,
+    Inactive,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct SetRecordingActive {
+    pub new_state: RecordingState,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct RefineText {
+    pub transcript: transcript::Transcript,
+    /// Overrides the stored OpenRouter key
+    pub openrouter_key: Option<String>,
+    /// Overrides the stored MegaLLM key
+    pub megallm_key: Option<String>,
+    /// "openrouter" | "megallm"; defaults to the configured provider
+    pub provider: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct TestPipeline {
+    pub sample_text: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct FinishHudMove {
+    pub save: bool,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct PreviewCue {
+    pub cue: cues::Cue,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct TranscriptArg {
+    pub transcript: transcript::Transcript,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetRefusalPatterns {
+    /// Extra phrases that mark an AI reply as a refusal (case-insensitive substrings)
+    pub added: Vec<String>,
+    /// Built-in phrases to stop flagging
+    pub removed: Vec<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct DictionaryWord {
+    pub word: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ReportCorrection {
+    /// The text as inserted
+    pub original: String,
+    /// The text as the user kept it
+    pub corrected: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct CorrectionSuggestion {
+    pub from: String,
+    pub to: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct PackToggle {
+    /// Built-in pack id, e.g. "medical"
+    pub name: String,
+    /// App profile to enable it for; every app when omitted
+    pub app: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetAudioDevice {
+    /// Device id from `list_audio_devices`; the system default when omitted
+    pub id: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ReportAudioDevices {
+    pub devices: Vec<audio_device::AudioDevice>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct AudioDeviceChanged {
+    pub label: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetDeviceNoiseMode {
+    pub id: String,
+    /// Omit to follow the global noise suppression setting
+    pub mode: Option<audio_device::NoiseMode>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetCustomPack {
+    pub pack: pack::Pack,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct SetJournalTarget {
+    /// File path with `{{date}}`, `{{date:FORMAT}}` (strftime), `{{time}}` and a leading `~`;
+    /// empty or omitted turns the journal off
+    pub path_template: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetLanguage {
+    /// "auto" or a code such as "en", "bn" or "en-US"
+    pub code: String,
+    /// "deepgram" | "elevenlabs"; defaults to the active provider
+    pub provider: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SttProviderArg {
+    /// "deepgram" | "elevenlabs"; defaults to the active provider
+    pub provider: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetSttOptions {
+    pub options: stt_options::SttOptions,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct UsageRange {
+    /// "today" | "7d" | "30d" | "90d" | "all"; defaults to "30d"
+    pub range: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct NetworkRecheck {
+    /// Probe now instead of returning the last result
+    pub recheck: Option<bool>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct CostMonths {
+    /// How many months back; all when omitted
+    pub months: Option<u32>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct SetJournalTemplate {
+    /// Block per dictation with `{{date}}`, `{{time}}`, `{{app}}` and `{{text}}` (date and time
+    /// take a strftime format: `{{time:%H%M}}`); empty restores the default time heading
+    pub template: Option<String>,
+    /// Written at the top of a new journal file, same placeholders
+    pub front_matter: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct TestJournalTemplate {
+    /// Sample dictation; a stock sentence when omitted
+    pub text: Option<String>,
+    /// Templates to try instead of the saved ones
+    pub template: Option<String>,
+    pub front_matter: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SaveLastAudio {
+    /// Where to write the WAV file; defaults to the Downloads folder
+    pub path: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct RetranscribeLastAudio {
+    /// "deepgram" | "elevenlabs"; defaults to the configured provider
+    pub provider: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct TranscribeFile {
+    pub path: String,
+    /// "deepgram" | "elevenlabs"; defaults to the configured provider
+    pub provider: Option<String>,
+    /// Insert the text like a dictation instead of only returning it
+    pub paste: Option<bool>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct IsFolder {
+    pub path: String,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct TranscribeFolder {
+    pub path: String,
+    /// Defaults to the AI refinement setting
+    pub refine: Option<bool>,
+    /// "txt" | "md"; defaults to the watch folder's format
+    pub output_format: Option<String>,
+    /// Include subfolders
+    pub recursive: Option<bool>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct AnswerPasteConfirmation {
+    pub answer: paste_confirm::Reply,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ListHistory {
+    /// Defaults to 100
+    pub limit: Option<usize>,
+    /// Only entries whose text or app contains every word of this, ignoring case
+    pub query: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct DeleteHistory {
+    /// History entry ids from `list_history`
+    pub ids: Vec<u64>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ExportTranscript {
+    /// History entry id from `list_history`
+    pub id: u64,
+    /// "txt" | "md" | "json" | "srt" | "vtt"
+    pub format: String,
+    /// Where to write the file; defaults to the Downloads folder
+    pub path: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ExportPack {
+    /// Where to write the file; defaults to the Downloads folder
+    pub path: Option<String>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct ImportPack {
+    pub path: String,
+    /// Defaults to "ask": change nothing when entries conflict, just report them
+    pub on_conflict: Option<pack::OnConflict>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ExportSettings {
+    /// Where to write the file; defaults to the Downloads folder
+    pub path: Option<String>,
+    /// Include the API keys, encrypted with this passphrase
+    pub passphrase: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetProxy {
+    pub proxy: crate::proxy::ProxyPrefs,
+    /// Omit to keep the saved password, "" to remove it
+    pub password: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ProfileName {
+    pub name: String,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ImportSettings {
+    pub path: String,
+    /// Needed to restore API keys from a backup that has them
+    pub passphrase: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct JobId {
+    pub id: u64,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct UseTake {
+    /// Position in `list_takes`
+    pub index: usize,
+  }
+
+  #[derive(JsonSchema)]
+  #[serde(rename_all = "camelCase")]
+  pub struct InsertText {
+    pub transcript: transcript::Transcript,
+    /// Put the text on the clipboard without pasting
+    pub copy_only: Option<bool>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetBehavior {
+    /// Partial prefs; keys may be snake_case or camelCase
+    pub args: Map<String, Value>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct SetPasteTarget {
+    /// Window id from `list_windows`; null clears the target
+    pub id: Option<u64>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ReplayMacro {
+    /// File name (or path) of a macro in the `macros` folder; defaults to the newest one
+    pub path: Option<String>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct RecentLogs {
+    /// Defaults to 500
+    pub lines: Option<usize>,
+  }
+
+  #[derive(JsonSchema)]
+  pub struct ReportFailure {
+    pub kind: failures::FailureKind,
+    pub detail: Option<String>,
+  }
+}
+
+struct Builder {
+  gen: SchemaGenerator,
+  events: Map<String, Value>,
+  commands: Map<String, Value>,
+}
+
+impl Builder {
+  fn schema<T: JsonSchema>(&mut self) -> Value {
+    serde_json::to_value(self.gen.subschema_for::<T>()).unwrap_or(Value::Null)
+  }
+
+  /// `target` is the window label the event is sent to; None means every window
+  fn event<T: JsonSchema>(&mut self, name: &str, target: Option<&str>, description: &str) {
+    let payload = self.schema::<T>();
+    self.events.insert(name.into(), json!({ "target": target, "description": description, "payload": payload }));
+  }
+
+  fn command<A: JsonSchema, R: JsonSchema>(&mut self, name: &str, description: &str) {
+    let args = self.schema::<A>();
+    let returns = self.schema::<R>();
+    self.commands.insert(name.into(), json!({ "description": description, "args": args, "returns": returns }));
+  }
+}
+
+/// The full event and command schema
+pub fn document() -> Value {
+  let mut b = Builder {
+    gen: SchemaSettings::draft07().into_generator(),
+    events: Map::new(),
+    commands: Map::new(),
+  };
+
+  b.event::<hud_event::Envelope<'static>>(
+    hud_event::EVENT_NAME,
+    Some("hud"),
+    "Everything the backend reports to the HUD; `type` selects the variant, `v` is API_VERSION",
+  );
+  b.event::<jobs::Job>(
+    jobs::EVENT_NAME,
+    None,
+    "A file transcription job was queued or changed status",
+  );
+  b.event::<jobs::BatchProgress>(
+    jobs::BATCH_EVENT_NAME,
+    None,
+    "A folder batch was queued or one of its jobs changed status",
+  );
+  b.event::<meeting::MeetingUpdate>(
+    meeting::EVENT_NAME,
+    Some("meeting"),
+    "A speaker paragraph of the meeting transcript was added or grew",
+  );
+  b.event::<history::HistoryUpdate>(
+    history::EVENT_NAME,
+    None,
+    "A dictation was added to the history, or entries were deleted or purged",
+  );
+  b.event::<connectivity::NetworkStatus>(
+    network_monitor::EVENT_NAME,
+    None,
+    "The providers became unreachable (offline mode: AI refinement is skipped) or reachable again",
+  );
+  b.event::<config_profiles::ConfigProfiles>(
+    config_profiles::EVENT_NAME,
+    None,
+    "The active configuration profile changed; prefs, keys and hotkeys should be read again",
+  );
+  b.event::<usage::PipelineTiming>(
+    session_metrics::TIMING_EVENT,
+    None,
+    "A dictation was inserted; milliseconds from the hotkey to each step of its pipeline",
+  );
+
+  use args::*;
+  b.command::<NoArgs, ()>("start_dictation", "Start a session (same as pressing the hotkey)");
+  b.command::<NoArgs, ()>("trigger_stop_dictation", "Ask the HUD to stop the running session");
+  b.command::<NoArgs, ()>("stop_dictation", "Hide the HUD");
+  b.command::<NoArgs, ()>("retake_dictation", "Set the current utterance aside and start a fresh take");
+  b.command::<NoArgs, ()>("pause_dictation", "Put the recording on hold; audio is dropped until it is resumed");
+  b.command::<NoArgs, ()>("resume_dictation", "Resume a paused recording");
+  b.command::<NoArgs, bool>("toggle_pause_dictation", "Pause or resume the recording (pause hotkey); true if it is now paused");
+  b.command::<TranscriptArg, usize>("save_take", "Keep a take of the current session; returns how many it has");
+  b.command::<NoArgs, Vec<transcript::Transcript>>("list_takes", "Takes of the latest session, oldest first");
+  b.command::<UseTake, bool>("use_take", "Refine and insert an earlier take; true if it was pasted");
+  b.command::<NoArgs, bool>("is_dictation_active", "Whether a session is starting, recording, paused or stopping");
+  b.command::<SetRecordingActive, ()>("set_recording_active", "Move the session state machine");
+  b.command::<NoArgs, bool>("probe_text_accepting", "Whether the focused element looks like it takes text");
+  b.command::<RefineText, transcript::Transcript>("refine_text", "Run the refinement pipeline; the result is in `refined`");
+  b.command::<TestPipeline, pipeline::Trace>("test_pipeline", "Run sample text through the pipeline (refinement included) and return each stage's output");
+  b.command::<NoArgs, crate::ScriptStatus>("get_script_status", "Path of the script stage's transform.rhai, whether it exists and its parse error");
+  b.command::<NoArgs, ()>("start_hud_move", "Show the HUD on its own so it can be dragged to a new spot");
+  b.command::<FinishHudMove, ()>("finish_hud_move", "End HUD move mode, saving the spot for its monitor (hud_position becomes \"custom\") when `save`");
+  b.command::<NoArgs, Vec<focused_monitor::MonitorInfo>>("list_monitors", "Connected monitors, with the indices monitor_policy uses");
+  b.command::<PreviewCue, ()>("preview_cue", "Play a sound cue (start, stop, error) at the volume pref");
+  b.command::<NoArgs, crate::PluginList>("list_plugins", "Plugin files in the plugins folder with their hooks, load errors and whether they are enabled");
+  b.command::<TranscriptArg, Vec<transcript::Word>>("transcript_words", "Per-word timing, estimated where the provider gave none");
+  b.command::<InsertText, bool>("insert_text", "Insert the transcript's output text; true if it was pasted");
+  b.command::<ReplayMacro, ()>("replay_macro", "Replay a keystroke macro saved by the `macro` insert mode");
+  b.command::<NoArgs, paste_confirm::ConfirmStatus>("get_paste_confirmation", "Whether the next auto-paste needs confirming");
+  b.command::<NoArgs, paste_confirm::Decision>("confirm_paste", "Wait for Enter (paste) or Esc (copy only, or discard when previewing)");
+  b.command::<AnswerPasteConfirmation, ()>("answer_paste_confirmation", "Answer a pending paste confirmation from the HUD: paste, cancel, or edit the preview first");
+  b.command::<NoArgs, prompt::RefusalPatterns>("get_refusal_patterns", "Built-in refusal phrases, user additions and removals, and the merged list");
+  b.command::<SetRefusalPatterns, prompt::RefusalPatterns>("set_refusal_patterns", "Replace the user's refusal phrase additions and removals");
+  b.command::<NoArgs, Vec<String>>("list_dictionary", "Personal dictionary: names and terms boosted in Deepgram and kept verbatim by refinement");
+  b.command::<DictionaryWord, Vec<String>>("add_dictionary_word", "Add a dictionary entry (replacing one that differs only in case); returns the dictionary");
+  b.command::<DictionaryWord, Vec<String>>("remove_dictionary_word", "Remove a dictionary entry; returns the dictionary");
+  b.command::<NoArgs, Vec<String>>("stt_keywords", "Dictionary entries plus the active packs' vocabulary, as sent to Deepgram");
+  b.command::<ReportCorrection, usize>("report_correction", "Record the word replacements between inserted and kept text (when learning is on)");
+  b.command::<NoArgs, Vec<learning::Suggestion>>("get_correction_suggestions", "Repeated corrections offered as dictionary entries or replacement rules");
+  b.command::<CorrectionSuggestion, Vec<learning::Suggestion>>("accept_correction_suggestion", "Add a suggestion to the dictionary or the custom pack rules");
+  b.command::<CorrectionSuggestion, Vec<learning::Suggestion>>("dismiss_correction_suggestion", "Stop offering a suggestion");
+  b.command::<NoArgs, Vec<audio_device::AudioDevice>>("list_audio_devices", "Input devices last reported by the HUD, with the one in use marked");
+  b.command::<NoArgs, Option<String>>("get_audio_device", "Id of the chosen input device; null follows the system default");
+  b.command::<SetAudioDevice, ()>("set_audio_device", "Choose the input device; a running recording switches to it");
+  b.command::<ReportAudioDevices, ()>("report_audio_devices", "Tell the backend which input devices exist (sent by the HUD)");
+  b.command::<AudioDeviceChanged, ()>("audio_device_changed", "The HUD moved a running recording to another device");
+  b.command::<NoArgs, audio_device::NoiseMode>("get_noise_mode", "Noise suppression for the input device in use (off, browser or rnnoise)");
+  b.command::<SetDeviceNoiseMode, ()>("set_device_noise_mode", "Set noise suppression for one input device");
+  b.command::<NoArgs, Vec<pack_store::PackInfo>>("list_packs", "Built-in domain packs and where they are enabled");
+  b.command::<PackToggle, Vec<pack_store::PackInfo>>("enable_pack", "Enable a built-in pack for every app or one app profile");
+  b.command::<PackToggle, Vec<pack_store::PackInfo>>("disable_pack", "Disable a built-in pack for every app or one app profile");
+  b.command::<NoArgs, pack::Pack>("get_custom_pack", "The user's custom symbols, snippets, rules and prompt additions");
+  b.command::<SetCustomPack, pack::Pack>("set_custom_pack", "Replace the custom pack");
+  b.command::<ExportPack, String>("export_pack", "Write the custom pack to a shareable file; returns its path");
+  b.command::<ImportPack, pack::ImportReport>("import_pack", "Merge a pack file into the custom pack, resolving conflicts per `onConflict`");
+  b.command::<ExportSettings, String>("export_settings", "Back up prefs, prompts, packs, dictionary and app profiles to one file, with the API keys encrypted when a passphrase is given; returns its path");
+  b.command::<NoArgs, Vec<quota::ProviderUsage>>("get_provider_usage", "Remaining credit per STT provider with a key (Deepgram balance, ElevenLabs allowance); notifies when one drops below low_credit_usd / low_credit_percent");
+  b.command::<NoArgs, crate::ProxyStatus>("get_proxy", "Proxy prefs for provider requests and the STT WebSocket (the password is never returned)");
+  b.command::<SetProxy, crate::ProxyStatus>("set_proxy", "Save and apply proxy prefs: mode off, system or manual (http/socks5 URL, username, bypass list)");
+  b.command::<ProfileName, config_profiles::ConfigProfiles>("switch_profile", "Switch to a named configuration profile (keys, providers, prompts, behavior), creating it from the current settings if new");
+  b.command::<NoArgs, config_profiles::ConfigProfiles>("list_config_profiles", "Configuration profiles and the active one");
+  b.command::<ProfileName, config_profiles::ConfigProfiles>("delete_config_profile", "Delete a configuration profile other than the active one");
+  b.command::<ImportSettings, backup::ImportSummary>("import_settings", "Restore a settings backup; API keys need the passphrase it was exported with");
+  b.command::<NoArgs, crate::BehaviorPrefs>("get_behavior", "Current behavior prefs");
+  b.command::<SetBehavior, crate::BehaviorPrefs>("set_behavior", "Update behavior prefs and return all of them");
+  b.command::<NoArgs, Vec<window_target::WindowInfo>>("list_windows", "Windows that can be chosen as paste target");
+  b.command::<SetPasteTarget, Option<window_target::WindowInfo>>("set_paste_target", "Pin insertion to a window");
+  b.command::<NoArgs, Option<window_target::WindowInfo>>("get_paste_target", "The pinned paste target, if any");
+  b.command::<NoArgs, Option<profiles::ActiveApp>>("get_active_app", "The application in the foreground");
+  b.command::<NoArgs, failures::FailureStats>("get_failure_stats", "Local failure counters by category");
+  b.command::<ReportFailure, ()>("report_failure", "Count a failure observed by a frontend");
+  b.command::<UsageRange, usage::UsageStats>("get_usage_stats", "Words, speaking rate, streaks and provider error rates over a range");
+  b.command::<NetworkRecheck, connectivity::NetworkStatus>("get_network_status", "Whether the providers can be reached; while offline, refinement falls back to local cleanup");
+  b.command::<CostMonths, cost::CostSummary>("get_cost_summary", "Estimated STT and AI cost per month (list prices), newest first");
+  b.command::<SetSttOptions, ()>("set_stt_options", "Save provider connection options (Deepgram model, smart_format, punctuate, endpointing, interim results; ElevenLabs realtime model; stt_endpoint_override for a self-hosted Deepgram)");
+  b.command::<SetLanguage, String>("set_language", "Set an STT provider's language; returns it normalized (\"en-US\" -> \"en\")");
+  b.command::<SttProviderArg, String>("get_language", "An STT provider's language, \"auto\" when the provider detects it");
+  b.command::<NoArgs, stt_options::SttOptions>("get_stt_options", "Provider connection options, defaults filled in");
+  b.command::<NoArgs, ()>("mark_first_partial", "The HUD showed the session's first words (for pipeline timing)");
+  b.command::<RecentLogs, String>("get_recent_logs", "The newest lines of the log files, for bug reports");
+  b.command::<ListHistory, Vec<history::HistoryItem>>("list_history", "Dictation history, newest first, optionally filtered by a search query");
+  b.command::<DeleteHistory, usize>("delete_history", "Delete history entries; returns how many were removed");
+  b.command::<NoArgs, ()>("open_history_window", "Show the history window, creating it on first use");
+  b.command::<ExportTranscript, String>("export_transcript", "Export a history entry as text, Markdown, JSON or SRT/VTT subtitles; returns the file path");
+  b.command::<SetJournalTarget, Option<String>>("set_journal_target", "Set the journal file template; returns the file today's dictations go to");
+  b.command::<NoArgs, Option<String>>("get_journal_target", "The journal file template, if set");
+  b.command::<SetJournalTemplate, ()>("set_journal_template", "Set the journal block and front matter templates");
+  b.command::<NoArgs, journal::Templates>("get_journal_template", "The journal block and front matter templates");
+  b.command::<TestJournalTemplate, journal::Preview>("test_journal_template", "Preview what a dictation would add to the journal file, without writing it");
+  b.command::<NoArgs, String>("get_control_api_token", "Token for the local HTTP control API, created on first use");
+  b.command::<NoArgs, String>("regenerate_control_api_token", "Replace the control API token; returns the new one");
+  b.command::<NoArgs, String>("start_meeting", "Begin meeting transcription; returns the transcript file path");
+  b.command::<NoArgs, Option<String>>("stop_meeting", "End meeting transcription; returns the transcript file path");
+  b.command::<NoArgs, meeting::MeetingStatus>("get_meeting_status", "Whether a meeting is running, its file and paragraphs so far");
+  b.command::<NoArgs, ()>("open_meeting_window", "Show the meeting transcription window");
+  b.command::<NoArgs, Option<last_audio::LastAudioInfo>>("last_audio_info", "Length of the last dictation's kept audio, if any");
+  b.command::<SaveLastAudio, String>("save_last_audio", "Save the last dictation's audio as WAV; returns its path");
+  b.command::<RetranscribeLastAudio, String>("retranscribe_last_audio", "Transcribe the last dictation's audio again and add it to history");
+  b.command::<NoArgs, String>("export_diagnostics", "Save a diagnostics zip (keys redacted) to Downloads; returns its path");
+  b.command::<NoArgs, Option<String>>("spend_ledger_path", "Path of the spend ledger file; null while the ledger is off");
+  b.command::<NoArgs, watch_folder::WatchStatus>("watch_folder_status", "The folder being watched for audio files");
+  b.command::<NoArgs, maintenance::MaintenanceStatus>("get_maintenance_status", "Schedule, last run and last report of the daily maintenance task");
+  b.command::<NoArgs, maintenance::MaintenanceReport>("run_maintenance", "Run maintenance now; fails while a run is in progress");
+  b.command::<TranscribeFile, String>("transcribe_file", "Transcribe (and refine, when on) an audio file; returns the text and adds it to history");
+  b.command::<IsFolder, bool>("is_folder", "Whether a path (e.g. one dropped on a window) is a folder");
+  b.command::<TranscribeFolder, jobs::BatchProgress>("transcribe_folder", "Queue every audio file in a folder as one batch; transcripts are written next to the files");
+  b.command::<NoArgs, Vec<jobs::Job>>("list_jobs", "File transcription jobs, oldest first; kept across restarts");
+  b.command::<JobId, jobs::Job>("cancel_job", "Cancel a queued or running transcription job");
+  b.command::<NoArgs, Vec<key_inspect::KeyInspection>>("inspect_keys", "Per provider: key prefix, length and whether a live test accepts it");
+  b.command::<NoArgs, startup::StartupMetrics>("get_startup_metrics", "Startup phase timings");
+  b.command::<NoArgs, focus_probe::FocusKind>("get_focus_kind", "What the focused element accepts (same probe as dictation start)");
+  b.command::<NoArgs, Value>("get_api_schema", "This document");
+
+  json!({
+    "$schema": "http://json-schema.org/draft-07/schema#",
+    "title": "Dictation HUD API",
+    "version": API_VERSION,
+    "errors": "Commands that fail reject with a string message",
+    "events": b.events,
+    "commands": b.commands,
+    "definitions": b.gen.definitions(),
+  })
+}
@@ -0,0 +1,142 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+
+use crate::hud_event::{emit_hud_event, HudEvent};
+
+// Microphone selection. Audio is captured in the HUD webview, so the backend only knows the input
+// devices the HUD reports (from `enumerateDevices`, refreshed when devices come and go). The
+// chosen device id is stored in prefs.json; the HUD opens it, and follows the system default when
+// none is chosen or the chosen one is unplugged. When the device in use changes mid-recording the
+// HUD moves the running stream to the new one and reports it here, which shows a badge.
+// Noise suppression can be set per device (a headset needs less than a laptop mic in a café);
+// devices without their own setting follow the global prefs.
+
+const PREFS_STORE: &str = "prefs.json";
+const K_AUDIO_DEVICE: &str = "audio_device";
+const K_DEVICE_NOISE: &str = "device_noise";
+
+/// How noise is suppressed for a device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseMode {
+  Off,
+  /// The webview's `noiseSuppression` constraint; what it does depends on the platform
+  Browser,
+  /// RNNoise in the backend; audio goes through the relay
+  Rnnoise,
+}
+
+impl NoiseMode {
+  /// From the global `noise_suppression` and `rnnoise` prefs
+  pub fn from_prefs(noise_suppression: bool, rnnoise: bool) -> Self {
+    match (noise_suppression, rnnoise) {
+      (false, _) => NoiseMode::Off,
+      (true, false) => NoiseMode::Browser,
+      (true, true) => NoiseMode::Rnnoise,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct AudioDevice {
+  pub id: String,
+  pub label: String,
+  /// The system default input
+  #[serde(default)]
+  pub is_default: bool,
+  /// The device dictation uses (set by `list_audio_devices`)
+  #[serde(default)]
+  pub selected: bool,
+  /// Noise suppression chosen for this device; None follows the global setting
+  #[serde(default)]
+  pub noise_mode: Option<NoiseMode>,
+}
+
+static DEVICES: Mutex<Vec<AudioDevice>> = Mutex::new(Vec::new());
+
+/// Replace the known devices with the HUD's latest list
+pub fn set_devices(devices: Vec<AudioDevice>) {
+  *DEVICES.lock().unwrap() = devices;
+}
+
+/// Id of the chosen device; None follows the system default
+pub fn selected(app: &AppHandle) -> Option<String> {
+  let store = app.store(PREFS_STORE).ok()?;
+  store.get(K_AUDIO_DEVICE)?.as_str().map(|s| s.to_string()).filter(|s| !s.is_empty())
+}
+
+/// Input devices last reported by the HUD, with the one in use marked
+pub fn list(app: &AppHandle) -> Vec<AudioDevice> {
+  let chosen = selected(app);
+  let mut devices = DEVICES.lock().unwrap().clone();
+  let chosen_present = chosen.as_ref().is_some_and(|id| devices.iter().any(|d| &d.id == id));
+  let noise = device_noise(app);
+  for d in &mut devices {
+    d.selected = if chosen_present { chosen.as_ref() == Some(&d.id) } else { d.is_default };
+    d.noise_mode = noise.get(&d.id).copied();
+  }
+  devices
+}
+
+fn device_noise(app: &AppHandle) -> HashMap<String, NoiseMode> {
+  app
+    .store(PREFS_STORE)
+    .ok()
+    .and_then(|s| s.get(K_DEVICE_NOISE))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+/// Noise suppression for the device in use: its own setting, else `global`
+pub fn noise_mode(app: &AppHandle, global: NoiseMode) -> NoiseMode {
+  let in_use = list(app).into_iter().find(|d| d.selected);
+  in_use.and_then(|d| d.noise_mode).unwrap_or(global)
+}
+
+/// Set a device's noise suppression (None to follow the global setting)
+pub fn set_noise_mode(app: &AppHandle, id: &str, mode: Option<NoiseMode>) -> Result<(), String> {
+  let mut noise = device_noise(app);
+  match mode {
+    Some(mode) => noise.insert(id.to_string(), mode),
+    None => noise.remove(id),
+  };
+  let store = app.store(PREFS_STORE).map_err(|e| e.to_string())?;
+  store.set(K_DEVICE_NOISE, serde_json::to_value(&noise).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  info!("Noise suppression for {} set to {:?}", id, mode);
+  Ok(())
+}
+
+/// Choose the input device (None for the system default) and switch a running recording to it
+pub fn select(app: &AppHandle, id: Option<String>) -> Result<(), String> {
+  let id = id.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+  if let Some(id) = &id {
+    let devices = DEVICES.lock().unwrap();
+    // Before the HUD has reported anything there is nothing to check against
+    if !devices.is_empty() && !devices.iter().any(|d| &d.id == id) {
+      return Err(format!("Unknown audio device: {}", id));
+    }
+  }
+  let store = app.store(PREFS_STORE).map_err(|e| e.to_string())?;
+  match &id {
+    Some(id) => store.set(K_AUDIO_DEVICE, id.clone()),
+    None => {
+      store.delete(K_AUDIO_DEVICE);
+    }
+  }
+  store.save().map_err(|e| e.to_string())?;
+  info!("Audio device set to {}", id.as_deref().unwrap_or("system default"));
+  emit_hud_event(app, HudEvent::AudioDevice { id });
+  Ok(())
+}
+
+/// The HUD moved a running recording to another device (docked, undocked, unplugged)
+pub fn changed(app: &AppHandle, label: &str) {
+  info!("Audio device changed mid-recording: {}", label);
+  emit_hud_event(app, HudEvent::badge(format!("Microphone changed: {}", label)));
+}
@@ -0,0 +1,107 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+
+use crate::{history, pack_store};
+use crate::settings_bundle::{self, Bundle};
+
+// Backup and restore of the settings as one file (the format is in settings_bundle.rs). Restoring
+// writes each entry in the backup over the current one and leaves entries it doesn't have alone,
+// so a backup from an older version doesn't reset settings added since.
+
+const PREFS_STORE: &str = "prefs.json";
+const PACKS_STORE: &str = "packs.json";
+const CORRECTIONS_STORE: &str = "corrections.json";
+
+/// Extension of settings backup files
+pub const BACKUP_EXTENSION: &str = "dhsettings.json";
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ImportSummary {
+  /// Prefs entries restored (behavior, prompts, dictionary, app profiles, hotkeys, ...)
+  pub prefs: usize,
+  pub packs: usize,
+  pub corrections: usize,
+  /// API keys restored
+  pub keys: usize,
+  /// The backup has API keys but no passphrase was given, so the current keys were kept
+  pub keys_skipped: bool,
+}
+
+fn entries(app: &AppHandle, store: &str) -> Result<Map<String, Value>, String> {
+  let store = app.store(store).map_err(|e| e.to_string())?;
+  Ok(store.entries().into_iter().collect())
+}
+
+fn restore(app: &AppHandle, store: &str, entries: Map<String, Value>) -> Result<usize, String> {
+  if entries.is_empty() {
+    return Ok(0);
+  }
+  let store = app.store(store).map_err(|e| e.to_string())?;
+  let count = entries.len();
+  for (key, value) in entries {
+    store.set(key, value);
+  }
+  store.save().map_err(|e| e.to_string())?;
+  Ok(count)
+}
+
+/// Write the settings to `path`, or to the Downloads folder; with a passphrase the API keys are
+/// included, encrypted. Returns the file path.
+pub fn export(app: &AppHandle, path: Option<&Path>, passphrase: Option<&str>) -> Result<PathBuf, String> {
+  let prefs = entries(app, PREFS_STORE)?;
+  let (_, secrets) = settings_bundle::split_prefs(prefs.clone());
+  let version = app.package_info().version.to_string();
+  let mut bundle = Bundle::new(&version, history::now_secs(), prefs, entries(app, PACKS_STORE)?, entries(app, CORRECTIONS_STORE)?);
+  if let Some(passphrase) = passphrase.filter(|p| !p.is_empty()) {
+    bundle.keys = Some(settings_bundle::seal(&secrets, passphrase)?);
+  }
+  let path = match path {
+    Some(p) => p.to_path_buf(),
+    None => {
+      let dir = app.path().download_dir().or_else(|_| app.path().home_dir()).map_err(|e| e.to_string())?;
+      let date = chrono::Local::now().format("%Y-%m-%d");
+      dir.join(format!("dictation-hud-settings-{}.{}", date, BACKUP_EXTENSION))
+    }
+  };
+  let json = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+  std::fs::write(&path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+  info!("Exported settings to {}{}", path.display(), if bundle.keys.is_some() { " with API keys" } else { "" });
+  Ok(path)
+}
+
+/// Restore the settings backup at `path`. The API keys in it are restored only with the right
+/// passphrase; a wrong one fails before anything is changed.
+pub fn import(app: &AppHandle, path: &Path, passphrase: Option<&str>) -> Result<ImportSummary, String> {
+  let json = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+  let bundle = Bundle::from_json(&json)?;
+  let passphrase = passphrase.filter(|p| !p.is_empty());
+  let secrets = match (&bundle.keys, passphrase) {
+    (Some(sealed), Some(passphrase)) => settings_bundle::open(sealed, passphrase)?,
+    _ => Map::new(),
+  };
+  // Machine-local entries and plain-text keys in a hand-edited file are ignored
+  let (prefs, _) = settings_bundle::split_prefs(bundle.prefs);
+  let summary = ImportSummary {
+    prefs: restore(app, PREFS_STORE, prefs)?,
+    packs: restore(app, PACKS_STORE, bundle.packs)?,
+    corrections: restore(app, CORRECTIONS_STORE, bundle.corrections)?,
+    keys: restore(app, PREFS_STORE, secrets)?,
+    keys_skipped: bundle.keys.is_some() && passphrase.is_none(),
+  };
+  pack_store::invalidate();
+  info!(
+    "Imported settings from {} (exported by {}): {} prefs, {} pack entries, {} correction entries, {} API keys",
+    path.display(),
+    bundle.app_version,
+    summary.prefs,
+    summary.packs,
+    summary.corrections,
+    summary.keys
+  );
+  Ok(summary)
+}
@@ -0,0 +1,126 @@
+// Domain packs shipped with the app. They use the same format as user packs (see pack.rs) and
+// are off until enabled, globally or for one app profile. Enabled packs are layered under the
+// user's custom pack, so the user's own entries win. Their symbols and rules fire on any dictation,
+// so they only take phrases nobody says in passing ("p r n", "section sign"); abbreviating
+// ordinary words ("as needed", "versus") is left to the model through the prompt additions.
+
+use crate::pack::{Mapping, Pack};
+
+pub struct BuiltinPack {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub vocabulary: &'static [&'static str],
+    pub symbols: &'static [(&'static str, &'static str)],
+    pub rules: &'static [(&'static str, &'static str)],
+    pub prompt_additions: &'static [&'static str],
+}
+
+pub const BUILTIN_PACKS: &[BuiltinPack] = &[
+    BuiltinPack {
+        id: "medical",
+        name: "Medical",
+        description: "Clinical notes: drug and condition names, units and common abbreviations",
+        vocabulary: &[
+            "acetaminophen", "amoxicillin", "atorvastatin", "metformin", "lisinopril", "levothyroxine", "ibuprofen",
+            "tachycardia", "bradycardia", "hypertension", "hyperlipidemia", "dyspnea", "edema", "COPD", "CHF", "HIPAA",
+        ],
+        symbols: &[("degrees celsius", "°C"), ("micrograms", "µg"), ("plus minus", "±")],
+        rules: &[("milligrams", "mg"), ("milliliters", "mL"), ("b i d", "BID"), ("t i d", "TID"), ("p r n", "PRN")],
+        prompt_additions: &[
+            "The text is a clinical note. Keep drug names, doses and units exactly as dictated; never guess or round a number.",
+            "Keep standard medical abbreviations (BID, PRN, BP, HR) in capitals, and use them for dosing instructions such as \"twice daily\" or \"as needed\".",
+        ],
+    },
+    BuiltinPack {
+        id: "legal",
+        name: "Legal",
+        description: "Contracts and filings: Latin terms, section references and defined terms",
+        vocabulary: &["voir dire", "habeas corpus", "res judicata", "amicus curiae", "pro bono", "estoppel", "tortfeasor", "indemnitor"],
+        symbols: &[("section sign", "§"), ("paragraph sign", "¶"), ("section symbol", "§")],
+        rules: &[("id est", "i.e."), ("exempli gratia", "e.g.")],
+        prompt_additions: &[
+            "The text is legal writing. Keep capitalized defined terms (\"the Agreement\", \"the Parties\") capitalized and do not paraphrase clauses.",
+            "Write \"versus\" in case names as \"v.\" (\"Smith v. Jones\").",
+            "Write section references as dictated, e.g. \"§ 2(b)\", and keep Latin terms unchanged.",
+        ],
+    },
+    BuiltinPack {
+        id: "software",
+        name: "Software engineering",
+        description: "Developer chat and docs: product names, acronyms and symbols",
+        vocabulary: &[
+            "Kubernetes", "PostgreSQL", "TypeScript", "JavaScript", "GitHub", "GraphQL", "OAuth", "Tauri", "Rust", "nginx", "Redis", "Docker",
+        ],
+        symbols: &[("fat arrow sign", "=>"), ("double colon sign", "::"), ("not equal sign", "!=")],
+        rules: &[
+            ("java script", "JavaScript"),
+            ("type script", "TypeScript"),
+            ("git hub", "GitHub"),
+            ("postgres", "Postgres"),
+        ],
+        prompt_additions: &[
+            "The text is about software. Put file names, commands and identifiers in `backticks` when they are dictated as code.",
+        ],
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static BuiltinPack> {
+    BUILTIN_PACKS.iter().find(|p| p.id.eq_ignore_ascii_case(id.trim()))
+}
+
+fn mappings(list: &[(&str, &str)]) -> Vec<Mapping> {
+    list.iter().map(|(from, to)| Mapping::new(from, to)).collect()
+}
+
+impl BuiltinPack {
+    pub fn to_pack(&self) -> Pack {
+        Pack {
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            vocabulary: self.vocabulary.iter().map(|w| w.to_string()).collect(),
+            symbols: mappings(self.symbols),
+            rules: mappings(self.rules),
+            prompt_additions: self.prompt_additions.iter().map(|p| p.to_string()).collect(),
+            ..Pack::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_packs_are_valid() {
+        for builtin in BUILTIN_PACKS {
+            let pack = builtin.to_pack().normalized().unwrap();
+            assert!(!pack.is_empty(), "{} is empty", builtin.id);
+            // Duplicate spoken phrases would make the pack conflict with itself
+            for list in [&pack.symbols, &pack.rules] {
+                for (i, m) in list.iter().enumerate() {
+                    assert!(!list[..i].iter().any(|e| e.from.eq_ignore_ascii_case(&m.from)), "{}: duplicate {}", builtin.id, m.from);
+                }
+            }
+        }
+        assert_eq!(find(" Legal ").map(|p| p.id), Some("legal"));
+        assert!(find("astrology").is_none());
+    }
+
+    #[test]
+    fn test_software_rules() {
+        let pack = find("software").unwrap().to_pack();
+        assert_eq!(pack.apply_text("port the java script to type script"), "port the JavaScript to TypeScript");
+    }
+
+    #[test]
+    fn test_ordinary_speech_is_left_alone() {
+        let medical = find("medical").unwrap().to_pack();
+        assert_eq!(medical.apply_text("call me as needed, my heart rate is fine"), "call me as needed, my heart rate is fine");
+        assert_eq!(medical.apply_text("5 milligrams p r n"), "5 mg PRN");
+        let legal = find("legal").unwrap().to_pack();
+        assert_eq!(legal.apply_text("cats versus dogs, et cetera"), "cats versus dogs, et cetera");
+        let software = find("software").unwrap().to_pack();
+        assert!(software.symbols.iter().all(|m| m.from.ends_with(" sign")));
+    }
+}
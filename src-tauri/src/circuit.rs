@@ -0,0 +1,115 @@
+// Per-provider circuit breaker for AI refinement.
+// After `FAILURE_THRESHOLD` consecutive failures a provider is skipped for `COOLDOWN`, so an
+// outage costs one timeout per cooldown instead of one per dictation. When the cooldown ends a
+// single trial request is let through: success closes the circuit, failure reopens it.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const FAILURE_THRESHOLD: u32 = 3;
+pub const COOLDOWN: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Breaker {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+static BREAKERS: Mutex<BTreeMap<String, Breaker>> = Mutex::new(BTreeMap::new());
+
+/// Whether a request to `provider` should be attempted now
+pub fn allow(provider: &str, now: Instant) -> bool {
+    let mut breakers = BREAKERS.lock().unwrap();
+    let Some(b) = breakers.get_mut(provider) else { return true };
+    match b.open_until {
+        Some(until) if now < until => false,
+        Some(_) => {
+            // Half-open: this request is the trial; further ones wait for its result
+            b.open_until = Some(now + COOLDOWN);
+            b.consecutive_failures = FAILURE_THRESHOLD - 1;
+            true
+        }
+        None => true,
+    }
+}
+
+pub fn record_success(provider: &str) {
+    reset(provider);
+}
+
+/// Close the circuit, e.g. before a manual connection test
+pub fn reset(provider: &str) {
+    BREAKERS.lock().unwrap().remove(provider);
+}
+
+/// Count a failure; returns true when this failure opened the circuit
+pub fn record_failure(provider: &str, now: Instant) -> bool {
+    let mut breakers = BREAKERS.lock().unwrap();
+    let b = breakers.entry(provider.to_string()).or_default();
+    b.consecutive_failures += 1;
+    if b.consecutive_failures >= FAILURE_THRESHOLD {
+        b.open_until = Some(now + COOLDOWN);
+        return true;
+    }
+    false
+}
+
+/// Time left until `provider` is tried again, if its circuit is open
+pub fn remaining(provider: &str, now: Instant) -> Option<Duration> {
+    let breakers = BREAKERS.lock().unwrap();
+    breakers
+        .get(provider)
+        .and_then(|b| b.open_until)
+        .filter(|until| *until > now)
+        .map(|until| until - now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_recovers() {
+        let p = "test-open";
+        let t0 = Instant::now();
+        assert!(allow(p, t0));
+        assert!(!record_failure(p, t0));
+        assert!(!record_failure(p, t0));
+        assert!(record_failure(p, t0));
+        assert!(!allow(p, t0 + Duration::from_secs(1)));
+        assert!(remaining(p, t0).is_some());
+
+        // Cooldown over: one trial goes through, the next waits for its outcome
+        let t1 = t0 + COOLDOWN + Duration::from_secs(1);
+        assert!(allow(p, t1));
+        assert!(!allow(p, t1));
+        record_success(p);
+        assert!(allow(p, t1));
+        assert_eq!(remaining(p, t1), None);
+    }
+
+    #[test]
+    fn test_failed_trial_reopens() {
+        let p = "test-trial";
+        let t0 = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD {
+            record_failure(p, t0);
+        }
+        let t1 = t0 + COOLDOWN + Duration::from_secs(1);
+        assert!(allow(p, t1));
+        assert!(record_failure(p, t1));
+        assert!(!allow(p, t1 + Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_success_resets_count() {
+        let p = "test-reset";
+        let t0 = Instant::now();
+        record_failure(p, t0);
+        record_failure(p, t0);
+        record_success(p);
+        assert!(!record_failure(p, t0));
+        assert!(allow(p, t0));
+    }
+}
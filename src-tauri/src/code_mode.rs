@@ -0,0 +1,151 @@
+// Code dictation formatter.
+// Handles spoken casing commands ("camel case user id" -> "userId") so identifiers
+// can be dictated word by word. Runs after symbol replacement and before AI refinement.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Casing {
+    Camel,
+    Pascal,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+}
+
+/// Casing commands: (spoken phrase, casing)
+/// Longer phrases are listed first so "screaming snake case" wins over "snake case".
+pub const CASING_COMMANDS: &[(&str, Casing)] = &[
+    ("screaming snake case", Casing::ScreamingSnake),
+    ("constant case", Casing::ScreamingSnake),
+    ("camel case", Casing::Camel),
+    ("camelcase", Casing::Camel),
+    ("pascal case", Casing::Pascal),
+    ("pascalcase", Casing::Pascal),
+    ("snake case", Casing::Snake),
+    ("snakecase", Casing::Snake),
+    ("kebab case", Casing::Kebab),
+    ("kebabcase", Casing::Kebab),
+];
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Join words into a single identifier using the given casing
+pub fn apply_casing(words: &[String], casing: Casing) -> String {
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+    match casing {
+        Casing::Camel => lower
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.clone() } else { capitalize(w) })
+            .collect(),
+        Casing::Pascal => lower.iter().map(|w| capitalize(w)).collect(),
+        Casing::Snake => lower.join("_"),
+        Casing::ScreamingSnake => lower.join("_").to_uppercase(),
+        Casing::Kebab => lower.join("-"),
+    }
+}
+
+/// Returns (casing, number of tokens consumed) if a casing command starts at `tokens[i]`
+fn match_command(tokens: &[&str], i: usize) -> Option<(Casing, usize)> {
+    for (phrase, casing) in CASING_COMMANDS {
+        let words: Vec<&str> = phrase.split(' ').collect();
+        if i + words.len() > tokens.len() {
+            continue;
+        }
+        let matches = words.iter().enumerate().all(|(k, w)| {
+            // STT providers often add commas around command words ("Camel case, user id")
+            tokens[i + k].trim_matches(',').eq_ignore_ascii_case(w)
+        });
+        if matches {
+            return Some((*casing, words.len()));
+        }
+    }
+    None
+}
+
+/// Replace spoken casing commands with formatted identifiers.
+/// A command consumes the following plain words until punctuation, a symbol, or another command.
+pub fn apply_casing_commands(text: &str) -> String {
+    let mut out: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let tokens: Vec<&str> = line.split(' ').filter(|t| !t.is_empty()).collect();
+        let mut result: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let Some((casing, consumed)) = match_command(&tokens, i) else {
+                result.push(tokens[i].to_string());
+                i += 1;
+                continue;
+            };
+
+            let mut j = i + consumed;
+            let mut words: Vec<String> = Vec::new();
+            let mut trailing = String::new();
+            while j < tokens.len() && match_command(&tokens, j).is_none() {
+                let token = tokens[j].trim_start_matches(',');
+                let split = token.find(|c: char| !c.is_alphanumeric()).unwrap_or(token.len());
+                let (word, rest) = token.split_at(split);
+                if word.is_empty() {
+                    break;
+                }
+                words.push(word.to_string());
+                j += 1;
+                if !rest.is_empty() {
+                    trailing = rest.to_string();
+                    break;
+                }
+            }
+
+            if words.is_empty() {
+                // Nothing to format; keep the spoken command as-is
+                result.push(tokens[i].to_string());
+                i += 1;
+                continue;
+            }
+            result.push(apply_casing(&words, casing) + trailing.as_str());
+            i = j;
+        }
+        out.push(result.join(" "));
+    }
+    out.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_casing_styles() {
+        assert_eq!(apply_casing_commands("camel case user id"), "userId");
+        assert_eq!(apply_casing_commands("pascal case user id"), "UserId");
+        assert_eq!(apply_casing_commands("snake case user id"), "user_id");
+        assert_eq!(apply_casing_commands("screaming snake case max retries"), "MAX_RETRIES");
+        assert_eq!(apply_casing_commands("kebab case main nav"), "main-nav");
+    }
+
+    #[test]
+    fn test_stops_at_symbols() {
+        // Symbol replacement runs first, so "open paren" is already "("
+        assert_eq!(apply_casing_commands("call camel case get user by id("), "call getUserById(");
+        assert_eq!(apply_casing_commands("let snake case user id = 5"), "let user_id = 5");
+    }
+
+    #[test]
+    fn test_stt_noise() {
+        assert_eq!(apply_casing_commands("Camel case, User ID."), "userId.");
+        assert_eq!(apply_casing_commands("camel case"), "camel case");
+    }
+
+    #[test]
+    fn test_consecutive_commands() {
+        assert_eq!(
+            apply_casing_commands("snake case user id camel case user name"),
+            "user_id userName"
+        );
+    }
+}
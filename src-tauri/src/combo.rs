@@ -0,0 +1,154 @@
+// Hotkey combo helpers: normalize captured keys into the "Ctrl+Shift+Alt+H" form the
+// global-shortcut plugin accepts, and flag combos the OS (or most apps) already use.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+}
+
+/// Display/registration name, matching the default hotkeys ("Control" and "Cmd" on macOS)
+pub fn modifier_name(m: Modifier, mac: bool) -> &'static str {
+    match (m, mac) {
+        (Modifier::Ctrl, true) => "Control",
+        (Modifier::Ctrl, false) => "Ctrl",
+        (Modifier::Shift, _) => "Shift",
+        (Modifier::Alt, _) => "Alt",
+        (Modifier::Super, true) => "Cmd",
+        (Modifier::Super, false) => "Super",
+    }
+}
+
+fn parse_modifier(part: &str) -> Option<Modifier> {
+    match part.to_ascii_lowercase().as_str() {
+        "ctrl" | "control" => Some(Modifier::Ctrl),
+        "shift" => Some(Modifier::Shift),
+        "alt" | "option" => Some(Modifier::Alt),
+        "super" | "cmd" | "command" | "meta" | "win" => Some(Modifier::Super),
+        _ => None,
+    }
+}
+
+/// Build a combo string with modifiers in a stable order (Ctrl, Shift, Alt, Super)
+pub fn format_combo(mods: &[Modifier], key: &str, mac: bool) -> String {
+    let mut mods = mods.to_vec();
+    mods.sort();
+    mods.dedup();
+    let mut parts: Vec<&str> = mods.iter().map(|m| modifier_name(*m, mac)).collect();
+    parts.push(key);
+    parts.join("+")
+}
+
+/// Split a combo into its modifiers and main key (upper-cased), accepting common aliases
+pub fn parse_combo(combo: &str) -> (Vec<Modifier>, Option<String>) {
+    let mut mods = Vec::new();
+    let mut key = None;
+    for part in combo.split('+').map(str::trim).filter(|p| !p.is_empty()) {
+        match parse_modifier(part) {
+            Some(m) => mods.push(m),
+            None => key = Some(part.to_ascii_uppercase()),
+        }
+    }
+    mods.sort();
+    mods.dedup();
+    (mods, key)
+}
+
+/// Combos reserved by the OS or near-universal app shortcuts: (os, combo, what it does)
+const RESERVED: &[(&str, &str, &str)] = &[
+    ("windows", "Alt+Tab", "switches windows"),
+    ("windows", "Alt+F4", "closes the active window"),
+    ("windows", "Ctrl+Alt+Delete", "opens the security screen"),
+    ("windows", "Ctrl+Shift+Escape", "opens Task Manager"),
+    ("windows", "Super+L", "locks the computer"),
+    ("windows", "Super+D", "shows the desktop"),
+    ("windows", "Super+E", "opens File Explorer"),
+    ("windows", "Super+R", "opens the Run dialog"),
+    ("windows", "Super+Space", "switches keyboard layout"),
+    ("windows", "Super+H", "starts Windows voice typing"),
+    ("macos", "Super+Space", "opens Spotlight"),
+    ("macos", "Super+Tab", "switches apps"),
+    ("macos", "Super+Q", "quits the active app"),
+    ("macos", "Super+W", "closes the active window"),
+    ("macos", "Super+H", "hides the active app"),
+    ("macos", "Super+M", "minimizes the active window"),
+    ("macos", "Ctrl+Space", "switches input source"),
+    ("macos", "Shift+Super+3", "takes a screenshot"),
+    ("macos", "Shift+Super+4", "takes a screenshot"),
+    ("macos", "Shift+Super+5", "opens screenshot tools"),
+    ("macos", "Ctrl+Super+Q", "locks the screen"),
+    ("linux", "Alt+Tab", "switches windows"),
+    ("linux", "Alt+F2", "opens the run dialog"),
+    ("linux", "Alt+F4", "closes the active window"),
+    ("linux", "Ctrl+Alt+Delete", "logs out or reboots"),
+    ("linux", "Ctrl+Alt+T", "opens a terminal"),
+    ("linux", "Super+L", "locks the screen"),
+    ("linux", "Super+Space", "switches keyboard layout"),
+];
+
+/// Keys that, with Ctrl (Cmd on macOS) alone, are editing shortcuts in nearly every app
+const EDITING_KEYS: &[&str] = &["A", "C", "F", "N", "O", "P", "S", "T", "V", "W", "X", "Y", "Z"];
+
+/// Human-readable warnings for combos that are likely to be taken or awkward
+pub fn reserved_warnings(combo: &str, os: &str) -> Vec<String> {
+    let (mods, key) = parse_combo(combo);
+    let mut warnings = Vec::new();
+    let Some(key) = key else {
+        warnings.push("A hotkey needs a non-modifier key".to_string());
+        return warnings;
+    };
+
+    if mods.is_empty() || mods == [Modifier::Shift] {
+        warnings.push(format!("\"{}\" has no Ctrl/Alt/{} modifier and will fire while you type", combo, if os == "macos" { "Cmd" } else { "Super" }));
+    }
+
+    for (reserved_os, reserved, what) in RESERVED {
+        if *reserved_os != os {
+            continue;
+        }
+        let (r_mods, r_key) = parse_combo(reserved);
+        if r_mods == mods && r_key.as_deref() == Some(key.as_str()) {
+            warnings.push(format!("{} is reserved by the system: it {}", combo, what));
+        }
+    }
+
+    let primary = if os == "macos" { Modifier::Super } else { Modifier::Ctrl };
+    if mods == [primary] && EDITING_KEYS.contains(&key.as_str()) {
+        warnings.push(format!("{} is a standard editing shortcut in most apps", combo));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_combo_orders_modifiers() {
+        let mods = [Modifier::Alt, Modifier::Ctrl, Modifier::Shift, Modifier::Ctrl];
+        assert_eq!(format_combo(&mods, "H", false), "Ctrl+Shift+Alt+H");
+        assert_eq!(format_combo(&mods, "H", true), "Control+Shift+Alt+H");
+        assert_eq!(format_combo(&[Modifier::Super], "K", true), "Cmd+K");
+    }
+
+    #[test]
+    fn test_parse_combo_aliases() {
+        let (mods, key) = parse_combo("Control+Option+cmd+k");
+        assert_eq!(mods, vec![Modifier::Ctrl, Modifier::Alt, Modifier::Super]);
+        assert_eq!(key.as_deref(), Some("K"));
+    }
+
+    #[test]
+    fn test_reserved_warnings() {
+        assert!(reserved_warnings("Ctrl+Shift+Alt+H", "windows").is_empty());
+        assert_eq!(reserved_warnings("Alt+Tab", "windows").len(), 1);
+        assert_eq!(reserved_warnings("Cmd+Space", "macos").len(), 1);
+        assert_eq!(reserved_warnings("Ctrl+V", "linux").len(), 1);
+        assert_eq!(reserved_warnings("Shift+A", "windows").len(), 1);
+        // Same combo is only reserved on the OS that owns it
+        assert!(reserved_warnings("Ctrl+Alt+T", "windows").is_empty());
+        assert!(!reserved_warnings("Ctrl+Shift", "windows").is_empty());
+    }
+}
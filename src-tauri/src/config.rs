@@ -1,13 +1,21 @@
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
+use tracing::debug;
+
+use crate::stt_options::{self, SttOptions};
 
 const K_OPENROUTER: &str = "openrouter_key";
 const K_DEEPGRAM: &str = "deepgram_key";
 const K_MEGALLM: &str = "megallm_key";
 const K_ELEVENLABS: &str = "elevenlabs_key";
 const K_MEGALLM_MODEL: &str = "megallm_model";
+const K_STT_OPTIONS: &str = "stt_options";
+/// Provider -> language; replaces the single `language` pref
+const K_STT_LANGUAGES: &str = "stt_languages";
 
 fn env_default(key: &str) -> Option<String> {
+  // Named profiles are isolated environments; never leak the shared .env keys into them
+  if crate::instance::profile().is_some() { return None; }
   // Load .env once
   let _ = dotenvy::dotenv();
   std::env::var(key).ok().filter(|s| !s.is_empty())
@@ -21,107 +29,107 @@ pub async fn set_openrouter_key(app: &AppHandle, key: &str) -> anyhow::Result<()
 }
 
 pub async fn get_openrouter_key(app: &AppHandle) -> Option<String> {
-  eprintln!("?? Getting OpenRouter key...");
+  debug!("Getting OpenRouter key...");
   let store = app.store("prefs.json").ok();
   let stored = store.as_ref().and_then(|s| s.get(K_OPENROUTER).and_then(|v| v.as_str().map(|s| s.to_string())));
 
   if let Some(ref key) = stored {
-    eprintln!("? OpenRouter key found in store: {}...", &key[..key.len().min(10)]);
+    debug!("OpenRouter key found in store");
     Some(key.clone())
   } else {
-    eprintln!("?? No OpenRouter key in store, checking environment...");
+    debug!("No OpenRouter key in store, checking environment...");
     let env_key = env_default("OPENROUTER_API_KEY");
     if let Some(ref key) = env_key {
-      eprintln!("? OpenRouter key found in environment: {}...", &key[..key.len().min(10)]);
+      debug!("OpenRouter key found in environment");
     } else {
-      eprintln!("? No OpenRouter key in environment either");
+      debug!("No OpenRouter key in environment either");
     }
     env_key
   }
 }
 
 pub async fn set_megallm_key(app: &AppHandle, key: &str) -> anyhow::Result<()> {
-  eprintln!("?? Saving MegaLLM key to store...");
+  debug!("Saving MegaLLM key to store...");
   let store = app.store("prefs.json")?;
   store.set(K_MEGALLM, key);
   store.save()?;
-  eprintln!("? MegaLLM key saved");
+  debug!("MegaLLM key saved");
   Ok(())
 }
 
 pub async fn get_megallm_key(app: &AppHandle) -> Option<String> {
-  eprintln!("?? Getting MegaLLM key...");
+  debug!("Getting MegaLLM key...");
   let store = app.store("prefs.json").ok();
   let stored = store.as_ref().and_then(|s| s.get(K_MEGALLM).and_then(|v| v.as_str().map(|s| s.to_string())));
 
   if let Some(ref key) = stored {
-    eprintln!("? MegaLLM key found in store: {}...", &key[..key.len().min(10)]);
+    debug!("MegaLLM key found in store");
     Some(key.clone())
   } else {
-    eprintln!("?? No MegaLLM key in store, checking environment...");
+    debug!("No MegaLLM key in store, checking environment...");
     let env_key = env_default("MEGALLM_API_KEY");
     if let Some(ref key) = env_key {
-      eprintln!("? MegaLLM key found in environment: {}...", &key[..key.len().min(10)]);
+      debug!("MegaLLM key found in environment");
     } else {
-      eprintln!("? No MegaLLM key in environment either");
+      debug!("No MegaLLM key in environment either");
     }
     env_key
   }
 }
 
 pub async fn set_deepgram_key(app: &AppHandle, key: &str) -> anyhow::Result<()> {
-  eprintln!("?? Saving Deepgram key to store...");
+  debug!("Saving Deepgram key to store...");
   let store = app.store("prefs.json")?;
   store.set(K_DEEPGRAM, key);
   store.save()?;
-  eprintln!("? Deepgram key saved");
+  debug!("Deepgram key saved");
   Ok(())
 }
 
 pub async fn get_deepgram_key(app: &AppHandle) -> Option<String> {
-  eprintln!("?? Getting Deepgram key...");
+  debug!("Getting Deepgram key...");
   let store = app.store("prefs.json").ok();
   let stored = store.as_ref().and_then(|s| s.get(K_DEEPGRAM).and_then(|v| v.as_str().map(|s| s.to_string())));
 
   if let Some(ref key) = stored {
-    eprintln!("? Deepgram key found in store: {}...", &key[..key.len().min(10)]);
+    debug!("Deepgram key found in store");
     Some(key.clone())
   } else {
-    eprintln!("?? No Deepgram key in store, checking environment...");
+    debug!("No Deepgram key in store, checking environment...");
     let env_key = env_default("DEEPGRAM_API_KEY");
     if let Some(ref key) = env_key {
-      eprintln!("? Deepgram key found in environment: {}...", &key[..key.len().min(10)]);
+      debug!("Deepgram key found in environment");
     } else {
-      eprintln!("? No Deepgram key in environment either");
+      debug!("No Deepgram key in environment either");
     }
     env_key
   }
 }
 
 pub async fn set_elevenlabs_key(app: &AppHandle, key: &str) -> anyhow::Result<()> {
-  eprintln!("?? Saving ElevenLabs key to store...");
+  debug!("Saving ElevenLabs key to store...");
   let store = app.store("prefs.json")?;
   store.set(K_ELEVENLABS, key);
   store.save()?;
-  eprintln!("? ElevenLabs key saved");
+  debug!("ElevenLabs key saved");
   Ok(())
 }
 
 pub async fn get_elevenlabs_key(app: &AppHandle) -> Option<String> {
-  eprintln!("?? Getting ElevenLabs key...");
+  debug!("Getting ElevenLabs key...");
   let store = app.store("prefs.json").ok();
   let stored = store.as_ref().and_then(|s| s.get(K_ELEVENLABS).and_then(|v| v.as_str().map(|s| s.to_string())));
 
   if let Some(ref key) = stored {
-    eprintln!("? ElevenLabs key found in store: {}...", &key[..key.len().min(10)]);
+    debug!("ElevenLabs key found in store");
     Some(key.clone())
   } else {
-    eprintln!("?? No ElevenLabs key in store, checking environment...");
+    debug!("No ElevenLabs key in store, checking environment...");
     let env_key = env_default("ELEVENLABS_API_KEY");
     if let Some(ref key) = env_key {
-      eprintln!("? ElevenLabs key found in environment: {}...", &key[..key.len().min(10)]);
+      debug!("ElevenLabs key found in environment");
     } else {
-      eprintln!("? No ElevenLabs key in environment either");
+      debug!("No ElevenLabs key in environment either");
     }
     env_key
   }
@@ -151,14 +159,45 @@ pub async fn get_megallm_model(app: &AppHandle) -> Option<String> {
   store.get(K_MEGALLM_MODEL).and_then(|v| v.as_str().map(|s| s.to_string()))
 }
 
-pub async fn set_language(app: &AppHandle, code: &str) -> anyhow::Result<()> {
+/// The single language pref from before each provider had its own
+fn legacy_language(app: &AppHandle) -> Option<String> {
+  let store = app.store("prefs.json").ok()?;
+  store.get("language").and_then(|v| v.as_str().map(|s| s.to_string()))
+}
+
+/// Save the language of one STT provider, normalized (see `stt_options::normalize_language`)
+pub async fn set_stt_language(app: &AppHandle, provider: &str, code: &str) -> anyhow::Result<String> {
+  let code = stt_options::normalize_language(code).map_err(anyhow::Error::msg)?;
   let store = app.store("prefs.json")?;
-  store.set("language", code);
+  let mut languages = store.get(K_STT_LANGUAGES).filter(|v| v.is_object()).unwrap_or_else(|| serde_json::json!({}));
+  languages[provider] = serde_json::json!(code);
+  store.set(K_STT_LANGUAGES, languages);
+  store.save()?;
+  Ok(code)
+}
+
+/// Language of one STT provider. Before it has its own, Deepgram keeps using the old global
+/// language and ElevenLabs detects it, as they did.
+pub async fn get_stt_language(app: &AppHandle, provider: &str) -> String {
+  let store = app.store("prefs.json").ok();
+  let own = store.as_ref().and_then(|s| s.get(K_STT_LANGUAGES)).and_then(|v| v[provider].as_str().map(str::to_string));
+  if let Some(code) = own {
+    return code;
+  }
+  match provider {
+    "deepgram" => legacy_language(app).and_then(|l| stt_options::normalize_language(&l).ok()).unwrap_or_else(|| "en".into()),
+    _ => stt_options::AUTO_LANGUAGE.into(),
+  }
+}
+
+pub async fn set_stt_options(app: &AppHandle, options: &SttOptions) -> anyhow::Result<()> {
+  let store = app.store("prefs.json")?;
+  store.set(K_STT_OPTIONS, serde_json::to_value(options)?);
   store.save()?;
   Ok(())
 }
 
-pub async fn get_language(app: &AppHandle) -> Option<String> {
-  let store = app.store("prefs.json").ok()?;
-  store.get("language").and_then(|v| v.as_str().map(|s| s.to_string()))
+pub async fn get_stt_options(app: &AppHandle) -> SttOptions {
+  let store = app.store("prefs.json").ok();
+  store.and_then(|s| s.get(K_STT_OPTIONS)).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
 }
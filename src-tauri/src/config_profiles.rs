@@ -0,0 +1,200 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::settings_bundle::LOCAL_KEYS;
+
+// Named configuration profiles ("Work", "Personal", "Streaming"): complete sets of prefs (API keys,
+// providers, prompts, behavior, hotkeys) that the running app switches between. prefs.json always
+// holds the active profile, so the rest of the app reads it as usual; switching saves prefs.json
+// to the outgoing profile's store file and loads the incoming one's in its place. Entries that
+// describe the machine (devices, HUD positions) stay put. Unlike `--profile` instances
+// (instance.rs) these share one app, history and tray.
+
+const PREFS_STORE: &str = "prefs.json";
+const INDEX_STORE: &str = "config_profiles.json";
+const K_ACTIVE: &str = "active";
+const K_NAMES: &str = "names";
+/// Store files of the profiles, one per name
+const PROFILES_DIR: &str = "config-profiles";
+
+/// What the settings are called before any profile is created
+pub const DEFAULT_PROFILE: &str = "Default";
+
+pub const EVENT_NAME: &str = "config-profile-changed";
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ConfigProfiles {
+  pub active: String,
+  pub names: Vec<String>,
+}
+
+/// File-name form of a profile name; two names with the same slug can't both exist
+fn slug(name: &str) -> String {
+  // Full Unicode lowercasing, or "Ärger" and "ärger" would share a file on case-insensitive disks
+  let slug: String = name
+    .chars()
+    .flat_map(|c| if c.is_alphanumeric() { c.to_lowercase().collect() } else { vec!['-'] })
+    .collect();
+  slug.trim_matches('-').to_string()
+}
+
+/// An existing profile other than `name` whose store file `name` would share
+fn clash<'a>(names: &'a [String], name: &str) -> Option<&'a String> {
+  names.iter().find(|n| *n != name && slug(n) == slug(name))
+}
+
+fn store_path(name: &str) -> String {
+  format!("{}/{}.json", PROFILES_DIR, slug(name))
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+  let name = name.trim();
+  name.chars().count() <= 40 && !name.chars().any(char::is_control) && !slug(name).is_empty()
+}
+
+pub fn list(app: &AppHandle) -> ConfigProfiles {
+  let store = app.store(INDEX_STORE).ok();
+  let get = |key: &str| store.as_ref().and_then(|s| s.get(key));
+  let active = get(K_ACTIVE).and_then(|v| v.as_str().map(str::to_string)).unwrap_or_else(|| DEFAULT_PROFILE.into());
+  let mut names: Vec<String> = get(K_NAMES).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default();
+  if !names.contains(&active) {
+    names.insert(0, active.clone());
+  }
+  ConfigProfiles { active, names }
+}
+
+fn save_index(app: &AppHandle, profiles: &ConfigProfiles) -> Result<(), String> {
+  let store = app.store(INDEX_STORE).map_err(|e| e.to_string())?;
+  store.set(K_ACTIVE, profiles.active.clone());
+  store.set(K_NAMES, serde_json::to_value(&profiles.names).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())
+}
+
+/// prefs.json without the machine-local entries
+fn current_prefs(app: &AppHandle) -> Result<Map<String, Value>, String> {
+  let store = app.store(PREFS_STORE).map_err(|e| e.to_string())?;
+  Ok(store.entries().into_iter().filter(|(k, _)| !LOCAL_KEYS.contains(&k.as_str())).collect())
+}
+
+fn save_profile(app: &AppHandle, name: &str, prefs: &Map<String, Value>) -> Result<(), String> {
+  let store = app.store(store_path(name)).map_err(|e| e.to_string())?;
+  store.clear();
+  for (key, value) in prefs {
+    store.set(key.clone(), value.clone());
+  }
+  store.save().map_err(|e| e.to_string())
+}
+
+fn load_profile(app: &AppHandle, name: &str) -> Result<Map<String, Value>, String> {
+  let store = app.store(store_path(name)).map_err(|e| e.to_string())?;
+  Ok(store.entries().into_iter().collect())
+}
+
+/// Make `name` the active profile; a name that doesn't exist yet is created as a copy of the
+/// current settings. Returns whether it was created.
+pub fn switch(app: &AppHandle, name: &str) -> Result<bool, String> {
+  let name = name.trim();
+  if !is_valid_name(name) {
+    return Err("Profile names need a letter or digit and at most 40 characters".into());
+  }
+  let mut profiles = list(app);
+  if profiles.active == name {
+    return Ok(false);
+  }
+  if let Some(other) = clash(&profiles.names, name) {
+    return Err(format!("\"{}\" is too close to the existing profile \"{}\"", name, other));
+  }
+  let outgoing = current_prefs(app)?;
+  save_profile(app, &profiles.active, &outgoing)?;
+  let created = !profiles.names.iter().any(|n| n == name);
+  let incoming = if created { outgoing } else { load_profile(app, name)? };
+
+  let prefs = app.store(PREFS_STORE).map_err(|e| e.to_string())?;
+  for key in prefs.keys() {
+    if !LOCAL_KEYS.contains(&key.as_str()) {
+      prefs.delete(key);
+    }
+  }
+  for (key, value) in incoming {
+    prefs.set(key, value);
+  }
+  prefs.save().map_err(|e| e.to_string())?;
+
+  info!("Switched configuration profile from \"{}\" to \"{}\"{}", profiles.active, name, if created { " (new)" } else { "" });
+  if created {
+    profiles.names.push(name.to_string());
+    save_profile(app, name, &current_prefs(app)?)?;
+  }
+  profiles.active = name.to_string();
+  save_index(app, &profiles)?;
+  if let Err(e) = app.emit(EVENT_NAME, &profiles) {
+    warn!("Failed to emit profile change: {}", e);
+  }
+  Ok(created)
+}
+
+/// Remove a profile that isn't active, with its store file
+pub fn delete(app: &AppHandle, name: &str) -> Result<(), String> {
+  let mut profiles = list(app);
+  if profiles.active == name {
+    return Err("Switch to another profile before deleting this one".into());
+  }
+  if !profiles.names.iter().any(|n| n == name) {
+    return Err(format!("No profile named \"{}\"", name));
+  }
+  profiles.names.retain(|n| n != name);
+  save_index(app, &profiles)?;
+  if let Ok(store) = app.store(store_path(name)) {
+    store.clear();
+    let _ = store.save();
+  }
+  if let Ok(dir) = app.path().app_data_dir() {
+    let _ = std::fs::remove_file(dir.join(store_path(name)));
+  }
+  info!("Deleted configuration profile \"{}\"", name);
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_slug() {
+    assert_eq!(slug("Work"), "work");
+    assert_eq!(slug("  Side project! "), "side-project");
+    assert_eq!(slug("../etc/passwd"), "etc-passwd");
+    assert_eq!(slug("a\\b:c"), "a-b-c");
+    assert_eq!(slug("Ärger Café"), "ärger-café");
+  }
+
+  #[test]
+  fn test_is_valid_name() {
+    assert!(is_valid_name("Work"));
+    assert!(is_valid_name(" Streaming 2 "));
+    assert!(is_valid_name("日本語"));
+    assert!(!is_valid_name(""));
+    assert!(!is_valid_name("   "));
+    assert!(!is_valid_name("/"));
+    assert!(!is_valid_name("../.."));
+    assert!(!is_valid_name("Work\nHome"));
+    assert!(is_valid_name(&"x".repeat(40)));
+    assert!(!is_valid_name(&"x".repeat(41)));
+    assert!(is_valid_name(&"é".repeat(40)));
+  }
+
+  #[test]
+  fn test_clash() {
+    let names: Vec<String> = ["Default", "Work", "Ärger"].map(String::from).to_vec();
+    assert_eq!(clash(&names, "Work"), None);
+    assert_eq!(clash(&names, "Personal"), None);
+    assert_eq!(clash(&names, "work").map(String::as_str), Some("Work"));
+    assert_eq!(clash(&names, "WORK!").map(String::as_str), Some("Work"));
+    assert_eq!(clash(&names, "ärger").map(String::as_str), Some("Ärger"));
+    assert_eq!(clash(&names, "de/fault"), None);
+  }
+}
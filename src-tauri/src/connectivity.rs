@@ -0,0 +1,93 @@
+// Whether the providers can be reached at all, so a dictation made without a connection degrades
+// to local cleanup instead of failing on timeouts. One failed probe isn't enough to call it
+// offline (a dropped packet, a slow proxy); one successful probe is enough to call it online
+// again. Probing and the `network-status` event are in network_monitor.rs.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Consecutive failed probes before going offline
+pub const OFFLINE_AFTER: u32 = 2;
+/// Time between probes while online
+pub const ONLINE_INTERVAL: Duration = Duration::from_secs(60);
+/// Time between probes while offline, so coming back is noticed soon
+pub const OFFLINE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Payload of the `network-status` event
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+pub struct NetworkStatus {
+    pub online: bool,
+    /// When `online` last changed (Unix seconds)
+    pub since: u64,
+    /// When the last probe finished (Unix seconds; 0 before the first)
+    pub checked_at: u64,
+}
+
+/// Probe results folded into an online/offline state
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Monitor {
+    status: NetworkStatus,
+    failures: u32,
+}
+
+impl Monitor {
+    /// Taken as online until a probe says otherwise
+    pub fn new(now: u64) -> Self {
+        Monitor { status: NetworkStatus { online: true, since: now, checked_at: 0 }, failures: 0 }
+    }
+
+    pub fn status(&self) -> NetworkStatus {
+        self.status
+    }
+
+    /// Count a probe; returns true when this probe changed `online`
+    pub fn record(&mut self, reachable: bool, now: u64) -> bool {
+        self.status.checked_at = now;
+        self.failures = if reachable { 0 } else { self.failures + 1 };
+        let online = reachable || (self.status.online && self.failures < OFFLINE_AFTER);
+        if online == self.status.online {
+            return false;
+        }
+        self.status.online = online;
+        self.status.since = now;
+        true
+    }
+
+    pub fn interval(&self) -> Duration {
+        if self.status.online { ONLINE_INTERVAL } else { OFFLINE_INTERVAL }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_goes_offline_after_repeated_failures() {
+        let mut m = Monitor::new(100);
+        assert!(m.status().online);
+        assert!(!m.record(false, 110));
+        assert!(m.status().online);
+        assert!(m.record(false, 120));
+        assert_eq!(m.status(), NetworkStatus { online: false, since: 120, checked_at: 120 });
+        assert_eq!(m.interval(), OFFLINE_INTERVAL);
+        assert!(!m.record(false, 135));
+        assert_eq!(m.status().since, 120);
+    }
+
+    #[test]
+    fn test_one_success_is_back_online() {
+        let mut m = Monitor::new(0);
+        m.record(false, 10);
+        m.record(false, 20);
+        assert!(m.record(true, 35));
+        assert_eq!(m.status(), NetworkStatus { online: true, since: 35, checked_at: 35 });
+        assert_eq!(m.interval(), ONLINE_INTERVAL);
+        // A success in between starts the count over
+        m.record(false, 95);
+        m.record(true, 155);
+        assert!(!m.record(false, 215));
+        assert!(m.status().online);
+    }
+}
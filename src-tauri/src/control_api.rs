@@ -0,0 +1,263 @@
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::{debug, info, warn};
+
+use crate::history;
+
+// Local HTTP control API, off by default: Stream Deck buttons, AutoHotkey scripts and foot-pedal
+// software drive dictation through `/start`, `/stop`, `/toggle`, `/status` and `/last-transcript`
+// on 127.0.0.1. Every request carries the token from Settings, as `Authorization: Bearer <token>`
+// or `?token=<token>` for tools that can only open a URL. Requests whose Host isn't a loopback
+// name are refused, so a web page can't reach the server through DNS rebinding.
+
+pub const DEFAULT_PORT: u16 = 47_821;
+const TOKEN_KEY: &str = "control_api_token";
+/// Requests are a line and a few headers; anything bigger is not one of ours
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Port and task of the running server
+static SERVER: Mutex<Option<(u16, JoinHandle<()>)>> = Mutex::new(None);
+
+/// The token clients must send, created on first use
+pub fn token(app: &AppHandle) -> Result<String, String> {
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  if let Some(token) = store.get(TOKEN_KEY).and_then(|v| v.as_str().map(str::to_string)).filter(|t| !t.is_empty()) {
+    return Ok(token);
+  }
+  regenerate_token(app)
+}
+
+/// Replace the token, so clients holding the old one are locked out
+pub fn regenerate_token(app: &AppHandle) -> Result<String, String> {
+  let mut bytes = [0u8; 24];
+  getrandom::getrandom(&mut bytes).map_err(|e| e.to_string())?;
+  let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set(TOKEN_KEY, json!(token));
+  store.save().map_err(|e| e.to_string())?;
+  info!("Control API token regenerated");
+  Ok(token)
+}
+
+/// Start, restart or stop the server to match prefs
+pub fn configure(app: &AppHandle, enabled: bool, port: u16) {
+  let mut server = SERVER.lock().unwrap();
+  match server.as_ref() {
+    Some((running, _)) if enabled && *running == port => return,
+    Some(_) => {
+      let (old, task) = server.take().unwrap();
+      task.abort();
+      info!("Control API on port {} stopped", old);
+    }
+    None if !enabled => return,
+    None => {}
+  }
+  if enabled {
+    let app = app.clone();
+    *server = Some((port, tauri::async_runtime::spawn(serve(app, port))));
+  }
+}
+
+async fn serve(app: AppHandle, port: u16) {
+  let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+    Ok(l) => l,
+    Err(e) => {
+      warn!("Control API could not listen on 127.0.0.1:{}: {}", port, e);
+      return;
+    }
+  };
+  info!("Control API listening on 127.0.0.1:{}", port);
+  loop {
+    match listener.accept().await {
+      Ok((stream, _)) => {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+          if let Err(e) = handle(app, stream).await {
+            debug!("Control API connection failed: {}", e);
+          }
+        });
+      }
+      Err(e) => warn!("Control API accept failed: {}", e),
+    }
+  }
+}
+
+struct Request {
+  method: String,
+  path: String,
+  query: Option<String>,
+  host: Option<String>,
+  bearer: Option<String>,
+}
+
+/// Request line and the headers we use, once the header block is complete
+fn parse_request(raw: &str) -> Option<Request> {
+  let head = raw.split("\r\n\r\n").next()?;
+  let mut lines = head.split("\r\n");
+  let mut parts = lines.next()?.split(' ');
+  let method = parts.next()?.to_string();
+  let target = parts.next()?;
+  let (path, query) = match target.split_once('?') {
+    Some((p, q)) => (p.to_string(), Some(q.to_string())),
+    None => (target.to_string(), None),
+  };
+  let (mut host, mut bearer) = (None, None);
+  for line in lines {
+    let Some((name, value)) = line.split_once(':') else { continue };
+    let value = value.trim();
+    if name.eq_ignore_ascii_case("host") {
+      host = Some(value.to_string());
+    } else if name.eq_ignore_ascii_case("authorization") {
+      bearer = value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+    }
+  }
+  Some(Request { method, path, query, host, bearer })
+}
+
+fn query_token(query: Option<&str>) -> Option<&str> {
+  query?.split('&').find_map(|pair| pair.strip_prefix("token="))
+}
+
+/// Compare a sent token with ours in constant time: every byte of `expected` is looked at
+/// whatever `given` holds, so the response time doesn't tell how much of a guess was right
+fn token_matches(given: &str, expected: &str) -> bool {
+  let given = given.as_bytes();
+  let mut diff = given.len() ^ expected.len();
+  for (i, b) in expected.bytes().enumerate() {
+    diff |= (given.get(i).copied().unwrap_or(0) ^ b) as usize;
+  }
+  diff == 0
+}
+
+fn is_loopback_host(host: &str) -> bool {
+  let name = match host.rsplit_once(':') {
+    Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+    _ => host,
+  };
+  matches!(name, "127.0.0.1" | "localhost" | "[::1]")
+}
+
+async fn handle(app: AppHandle, mut stream: TcpStream) -> std::io::Result<()> {
+  let mut buf = Vec::new();
+  let mut chunk = [0u8; 1024];
+  while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+    let n = stream.read(&mut chunk).await?;
+    if n == 0 || buf.len() + n > MAX_REQUEST_BYTES {
+      return respond(&mut stream, 400, json!({ "error": "Bad request" })).await;
+    }
+    buf.extend_from_slice(&chunk[..n]);
+  }
+  let Some(req) = parse_request(&String::from_utf8_lossy(&buf)) else {
+    return respond(&mut stream, 400, json!({ "error": "Bad request" })).await;
+  };
+  if !req.host.as_deref().is_some_and(is_loopback_host) {
+    return respond(&mut stream, 403, json!({ "error": "Forbidden host" })).await;
+  }
+  let expected = match token(&app) {
+    Ok(t) => t,
+    Err(e) => return respond(&mut stream, 500, json!({ "error": e })).await,
+  };
+  let given = req.bearer.as_deref().or_else(|| query_token(req.query.as_deref()));
+  if !given.is_some_and(|given| token_matches(given, &expected)) {
+    return respond(&mut stream, 401, json!({ "error": "Missing or wrong token" })).await;
+  }
+  if req.method != "GET" && req.method != "POST" {
+    return respond(&mut stream, 405, json!({ "error": "Use GET or POST" })).await;
+  }
+  info!("Control API: {} {}", req.method, req.path);
+  let (status, body) = route(&app, &req.path).await;
+  respond(&mut stream, status, body).await
+}
+
+async fn route(app: &AppHandle, path: &str) -> (u16, Value) {
+  let done = |result: Result<(), String>| match result {
+    Ok(()) => (200, json!({ "ok": true })),
+    Err(e) => (409, json!({ "ok": false, "error": e })),
+  };
+  match path.trim_end_matches('/') {
+    "/start" => done(crate::start_dictation(app.clone()).await),
+    "/stop" => done(crate::stop_running_dictation(app).await),
+    "/toggle" => done(crate::toggle_dictation(app.clone()).await),
+    "/status" => (200, json!({ "state": crate::dictation_state_name(), "meeting": crate::meeting::is_active() })),
+    "/last-transcript" => match history::list(app, 1).into_iter().next() {
+      Some(item) => (200, json!(item)),
+      None => (404, json!({ "error": "No dictation yet" })),
+    },
+    _ => (404, json!({ "error": "Unknown endpoint" })),
+  }
+}
+
+async fn respond(stream: &mut TcpStream, status: u16, body: Value) -> std::io::Result<()> {
+  let reason = match status {
+    200 => "OK",
+    400 => "Bad Request",
+    401 => "Unauthorized",
+    403 => "Forbidden",
+    404 => "Not Found",
+    405 => "Method Not Allowed",
+    409 => "Conflict",
+    _ => "Internal Server Error",
+  };
+  let body = body.to_string();
+  let head = format!(
+    "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+    status,
+    reason,
+    body.len()
+  );
+  stream.write_all(head.as_bytes()).await?;
+  stream.write_all(body.as_bytes()).await?;
+  stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_request() {
+    let raw = "GET /dictation/start?token=abc&x=1 HTTP/1.1\r\nHost: 127.0.0.1:47821\r\nauthorization: Bearer  s3cret \r\n\r\nbody";
+    let req = parse_request(raw).unwrap();
+    assert_eq!(req.method, "GET");
+    assert_eq!(req.path, "/dictation/start");
+    assert_eq!(req.query.as_deref(), Some("token=abc&x=1"));
+    assert_eq!(req.host.as_deref(), Some("127.0.0.1:47821"));
+    assert_eq!(req.bearer.as_deref(), Some("s3cret"));
+
+    let req = parse_request("POST /status HTTP/1.1\r\nAuthorization: Basic xyz\r\n\r\n").unwrap();
+    assert_eq!((req.query, req.host, req.bearer), (None, None, None));
+    assert!(parse_request("GET").is_none());
+  }
+
+  #[test]
+  fn test_query_token() {
+    assert_eq!(query_token(Some("token=abc")), Some("abc"));
+    assert_eq!(query_token(Some("x=1&token=abc")), Some("abc"));
+    assert_eq!(query_token(Some("mytoken=abc")), None);
+    assert_eq!(query_token(None), None);
+  }
+
+  #[test]
+  fn test_token_matches() {
+    assert!(token_matches("abc123", "abc123"));
+    assert!(!token_matches("abc124", "abc123"));
+    assert!(!token_matches("abc", "abc123"));
+    assert!(!token_matches("abc1234", "abc123"));
+    assert!(!token_matches("", "abc123"));
+  }
+
+  #[test]
+  fn test_is_loopback_host() {
+    for host in ["127.0.0.1:47821", "127.0.0.1", "localhost", "localhost:47821", "[::1]", "[::1]:47821"] {
+      assert!(is_loopback_host(host), "{}", host);
+    }
+    for host in ["evil.com", "127.0.0.1.evil.com", "localhost.evil.com:47821", "127.0.0.2", ""] {
+      assert!(!is_loopback_host(host), "{}", host);
+    }
+  }
+}
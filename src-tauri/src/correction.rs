@@ -0,0 +1,162 @@
+// Spoken corrections: "meet at 3pm tomorrow, correction: 4pm" becomes "meet at 4pm tomorrow".
+// The phrase after the trigger replaces the most similar run of words (same length) in the
+// utterance before it; when nothing is similar enough it replaces the utterance's last words.
+// Only "correction" followed by ':', ',' or '.' triggers, so "a correction to the invoice" stays.
+
+/// Minimum similarity (0..1) for a fuzzy match; below it the last words are replaced
+const MIN_SIMILARITY: f64 = 0.5;
+
+fn normalize(words: &[&str]) -> String {
+    words
+        .iter()
+        .map(|w| w.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// 1.0 for identical strings, 0.0 for nothing in common
+fn similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+fn is_trigger(word: &str) -> bool {
+    let Some(stripped) = word.strip_suffix([':', ',', '.']) else { return false };
+    stripped.eq_ignore_ascii_case("correction")
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.ends_with(['.', '!', '?'])
+}
+
+/// Start of the utterance that ends at `end` (exclusive): just after the previous sentence end
+fn utterance_start(words: &[&str], end: usize) -> usize {
+    // The word right before the trigger may end the utterance being corrected ("John. Correction:")
+    let search_end = end.saturating_sub(1);
+    words[..search_end].iter().rposition(|w| ends_sentence(w)).map(|i| i + 1).unwrap_or(0)
+}
+
+/// Index in `region` where the replacement of `n` words should go
+fn best_window(region: &[&str], replacement: &[&str]) -> usize {
+    let n = replacement.len().min(region.len());
+    let target = normalize(replacement);
+    let mut best = (MIN_SIMILARITY, None);
+    for start in 0..=region.len() - n {
+        let score = similarity(&normalize(&region[start..start + n]), &target);
+        // >= so the latest of equally good windows wins
+        if score >= best.0 {
+            best = (score, Some(start));
+        }
+    }
+    best.1.unwrap_or(region.len() - n)
+}
+
+/// Apply every spoken correction in `text`
+pub fn apply_corrections(text: &str) -> String {
+    let mut words: Vec<String> = text.split_whitespace().map(|w| w.to_string()).collect();
+    let mut changed = false;
+    let mut i = 0;
+    while i < words.len() {
+        if i == 0 || !is_trigger(&words[i]) {
+            i += 1;
+            continue;
+        }
+        let refs: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+        // The replacement runs to the end of its sentence or the next trigger
+        let phrase_start = i + 1;
+        let phrase_end = match refs[phrase_start..].iter().position(|w| is_trigger(w) || ends_sentence(w)) {
+            Some(p) if is_trigger(refs[phrase_start + p]) => phrase_start + p,
+            Some(p) => phrase_start + p + 1,
+            None => refs.len(),
+        };
+        if phrase_end == phrase_start {
+            i += 1;
+            continue;
+        }
+        let mut replacement: Vec<String> = refs[phrase_start..phrase_end].iter().map(|w| w.to_string()).collect();
+        let terminator = replacement.last().and_then(|w| w.chars().last()).filter(|c| matches!(c, '.' | '!' | '?'));
+        if let Some(last) = replacement.last_mut() {
+            *last = last.trim_end_matches(['.', '!', '?']).to_string();
+        }
+        let start = utterance_start(&refs, i);
+        let region = &refs[start..i];
+        let replacement_refs: Vec<&str> = replacement.iter().map(|s| s.as_str()).collect();
+        let at = start + best_window(region, &replacement_refs);
+        let n = replacement.len().min(region.len());
+
+        let mut rebuilt: Vec<String> = refs[..at].iter().map(|w| w.to_string()).collect();
+        rebuilt.extend(replacement);
+        rebuilt.extend(refs[at + n..i].iter().map(|w| w.to_string()));
+        // Punctuation the pause before the trigger left behind gives way to the phrase's own
+        if let Some(last) = rebuilt.last_mut() {
+            let trimmed = last.trim_end_matches([',', ';', ':', '.', '!', '?']).to_string();
+            *last = match terminator {
+                Some(t) => format!("{}{}", trimmed, t),
+                None => trimmed,
+            };
+        }
+        let next = rebuilt.len();
+        rebuilt.extend(refs[phrase_end..].iter().map(|w| w.to_string()));
+        words = rebuilt;
+        changed = true;
+        i = next;
+    }
+    if changed { words.join(" ") } else { text.to_string() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_match_in_middle() {
+        assert_eq!(apply_corrections("Meet at 3pm tomorrow, correction: 4pm."), "Meet at 4pm tomorrow.");
+        assert_eq!(apply_corrections("Send it to John, correction, to Jane."), "Send it to Jane.");
+    }
+
+    #[test]
+    fn test_falls_back_to_last_words() {
+        assert_eq!(apply_corrections("The total is fifty, correction: sixty"), "The total is sixty");
+    }
+
+    #[test]
+    fn test_only_last_utterance_is_searched() {
+        assert_eq!(
+            apply_corrections("Call John today. Email Jon later. Correction: Jane."),
+            "Call John today. Email Jane later."
+        );
+    }
+
+    #[test]
+    fn test_text_after_correction_is_kept() {
+        assert_eq!(
+            apply_corrections("Book the red room, correction: blue room. Thanks."),
+            "Book the blue room. Thanks."
+        );
+    }
+
+    #[test]
+    fn test_ordinary_use_is_not_a_trigger() {
+        let text = "We need a correction to the invoice.";
+        assert_eq!(apply_corrections(text), text);
+        assert_eq!(apply_corrections("Correction: nothing before"), "Correction: nothing before");
+    }
+}
@@ -0,0 +1,176 @@
+// Estimated cost of each dictation, for pay-as-you-go keys: the recorded audio at the STT
+// provider's list price per minute, plus what the session's AI requests cost (as OpenRouter
+// reports it, or from token counts with `ledger::estimate_cost`). Each estimate is kept with its
+// history entry; running totals per calendar month are kept in stats.json (session_metrics.rs)
+// so they outlive history retention. List prices change, so these are estimates, not invoices.
+
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Approximate streaming list prices in USD per minute of audio: provider, model prefix ("" for
+/// any model), price
+const STT_PRICES: &[(&str, &str, f64)] = &[("deepgram", "nova-3", 0.0077), ("deepgram", "nova-2", 0.0058), ("elevenlabs", "", 0.0067)];
+
+/// List price per minute of audio for `provider` with `model`; None when unknown
+pub fn stt_rate(provider: &str, model: &str) -> Option<f64> {
+    if provider == "demo" {
+        return Some(0.0);
+    }
+    let model = model.to_lowercase();
+    STT_PRICES.iter().find(|(p, m, _)| *p == provider && model.starts_with(m)).map(|(_, _, price)| *price)
+}
+
+/// What one dictation is estimated to have cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct DictationCost {
+    /// Speech-to-text; None when the price is unknown (self-hosted endpoint, unlisted model)
+    pub stt_usd: Option<f64>,
+    /// AI refinement and translation; None when no priced AI request was made
+    pub ai_usd: Option<f64>,
+}
+
+impl DictationCost {
+    pub fn new(duration_ms: u64, stt_per_min: Option<f64>, ai_usd: Option<f64>) -> Self {
+        DictationCost { stt_usd: stt_per_min.map(|rate| rate * duration_ms as f64 / 60_000.0), ai_usd }
+    }
+
+    pub fn total_usd(&self) -> f64 {
+        self.stt_usd.unwrap_or(0.0) + self.ai_usd.unwrap_or(0.0)
+    }
+}
+
+/// Running totals of one calendar month
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct MonthlyCost {
+    pub dictations: u32,
+    /// Recorded audio in milliseconds, so short dictations add up
+    #[serde(default)]
+    pub audio_ms: u64,
+    pub stt_usd: f64,
+    pub ai_usd: f64,
+    /// Dictations whose STT cost couldn't be estimated (left out of `stt_usd`)
+    pub unpriced: u32,
+}
+
+impl MonthlyCost {
+    pub fn add(&mut self, duration_ms: u64, cost: &DictationCost) {
+        self.dictations += 1;
+        self.audio_ms += duration_ms;
+        match cost.stt_usd {
+            Some(usd) => self.stt_usd += usd,
+            None => self.unpriced += 1,
+        }
+        self.ai_usd += cost.ai_usd.unwrap_or(0.0);
+    }
+
+    pub fn total_usd(&self) -> f64 {
+        self.stt_usd + self.ai_usd
+    }
+}
+
+/// Monthly totals by "YYYY-MM"
+pub type MonthlyCosts = BTreeMap<String, MonthlyCost>;
+
+pub fn month_key(day: NaiveDate) -> String {
+    day.format("%Y-%m").to_string()
+}
+
+/// Monthly totals as saved in stats.json. Totals saved before audio was kept in milliseconds
+/// have whole `audio_secs` instead.
+pub fn parse_costs(mut value: serde_json::Value) -> MonthlyCosts {
+    if let Some(months) = value.as_object_mut() {
+        for month in months.values_mut().filter_map(|m| m.as_object_mut()) {
+            if let (None, Some(secs)) = (month.get("audio_ms"), month.get("audio_secs").and_then(|s| s.as_u64())) {
+                month.insert("audio_ms".into(), (secs * 1000).into());
+            }
+        }
+    }
+    serde_json::from_value(value).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct MonthSummary {
+    /// "YYYY-MM"
+    pub month: String,
+    #[serde(flatten)]
+    pub totals: MonthlyCost,
+    pub total_usd: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct CostSummary {
+    /// Newest first
+    pub months: Vec<MonthSummary>,
+    /// Over the months listed
+    pub total_usd: f64,
+}
+
+/// The newest `months` months of `costs` (all of them for 0)
+pub fn summarize(costs: &MonthlyCosts, months: usize) -> CostSummary {
+    let take = if months == 0 { costs.len() } else { months };
+    let months: Vec<MonthSummary> = costs
+        .iter()
+        .rev()
+        .take(take)
+        .map(|(month, totals)| MonthSummary { month: month.clone(), totals: totals.clone(), total_usd: totals.total_usd() })
+        .collect();
+    let total_usd = months.iter().map(|m| m.total_usd).sum();
+    CostSummary { months, total_usd }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_stt_rate() {
+        assert_eq!(stt_rate("deepgram", "nova-3-general"), Some(0.0077));
+        assert_eq!(stt_rate("deepgram", "nova-2"), Some(0.0058));
+        assert_eq!(stt_rate("elevenlabs", "scribe_v2_realtime"), Some(0.0067));
+        assert_eq!(stt_rate("deepgram", "whisper"), None);
+        assert_eq!(stt_rate("demo", ""), Some(0.0));
+    }
+
+    #[test]
+    fn test_dictation_cost() {
+        let cost = DictationCost::new(90_000, Some(0.0058), Some(0.0002));
+        assert!(close(cost.stt_usd.unwrap(), 0.0087));
+        assert!(close(cost.total_usd(), 0.0089));
+        assert_eq!(DictationCost::new(90_000, None, None).total_usd(), 0.0);
+    }
+
+    #[test]
+    fn test_monthly_totals() {
+        let mut costs = MonthlyCosts::new();
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        costs.entry(month_key(day("2026-09-30"))).or_default().add(60_000, &DictationCost::new(60_000, Some(0.006), Some(0.001)));
+        let october = costs.entry(month_key(day("2026-10-02"))).or_default();
+        october.add(120_500, &DictationCost::new(120_500, None, Some(0.002)));
+        october.add(30_000, &DictationCost::new(30_000, Some(0.006), None));
+        assert_eq!((october.dictations, october.audio_ms, october.unpriced), (2, 150_500, 1));
+
+        let summary = summarize(&costs, 0);
+        assert_eq!(summary.months.iter().map(|m| m.month.as_str()).collect::<Vec<_>>(), vec!["2026-10", "2026-09"]);
+        assert!(close(summary.months[0].total_usd, 0.005));
+        assert!(close(summary.total_usd, 0.012));
+        assert_eq!(summarize(&costs, 1).months.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_costs() {
+        let saved = serde_json::json!({
+            "2026-09": { "dictations": 3, "audio_secs": 95, "stt_usd": 0.01, "ai_usd": 0.0, "unpriced": 0 },
+            "2026-10": { "dictations": 1, "audio_ms": 4_250, "stt_usd": 0.0, "ai_usd": 0.0, "unpriced": 1 },
+        });
+        let costs = parse_costs(saved);
+        assert_eq!(costs["2026-09"].audio_ms, 95_000);
+        assert_eq!(costs["2026-10"].audio_ms, 4_250);
+        assert_eq!(parse_costs(serde_json::json!("garbage")), MonthlyCosts::new());
+    }
+}
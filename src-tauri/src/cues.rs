@@ -0,0 +1,67 @@
+// Audio cues for dictation events, synthesized rather than shipped as files: a short rising beep
+// when the microphone goes live, a two-note chime when recording stops and a low buzz on errors,
+// for when the HUD is on another monitor and easy to miss.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cue {
+    Start,
+    Stop,
+    Error,
+}
+
+/// (frequency Hz, duration ms) of each note
+fn notes(cue: Cue) -> &'static [(f32, u32)] {
+    match cue {
+        Cue::Start => &[(660.0, 60), (990.0, 90)],
+        Cue::Stop => &[(880.0, 90), (660.0, 140)],
+        Cue::Error => &[(220.0, 120), (0.0, 40), (220.0, 160)],
+    }
+}
+
+/// Mono samples of `cue` at SAMPLE_RATE, peaking at `volume` (0..=1)
+pub fn samples(cue: Cue, volume: f32) -> Vec<f32> {
+    let volume = volume.clamp(0.0, 1.0);
+    let mut out = Vec::new();
+    for &(freq, ms) in notes(cue) {
+        let len = (SAMPLE_RATE * ms / 1000) as usize;
+        // 5 ms fades so notes don't click
+        let fade = (SAMPLE_RATE / 200) as usize;
+        for i in 0..len {
+            if freq == 0.0 {
+                out.push(0.0);
+                continue;
+            }
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let phase = (t * freq * std::f32::consts::TAU).sin();
+            // The error buzz is a softened square wave, harsher than the sine beeps
+            let wave = if cue == Cue::Error { (phase * 3.0).clamp(-1.0, 1.0) * 0.8 } else { phase };
+            let envelope = (i.min(len - 1 - i) as f32 / fade as f32).min(1.0);
+            out.push(wave * envelope * volume);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples() {
+        let start = samples(Cue::Start, 0.5);
+        assert_eq!(start.len(), (SAMPLE_RATE * 150 / 1000) as usize);
+        assert!(start.iter().all(|s| s.abs() <= 0.5));
+        assert!(start.iter().any(|s| s.abs() > 0.45));
+        // Notes start and end silent
+        assert_eq!(start[0], 0.0);
+        assert!(start.last().unwrap().abs() < 0.01);
+        assert!(samples(Cue::Error, 0.0).iter().all(|s| *s == 0.0));
+        assert_ne!(samples(Cue::Stop, 1.0), samples(Cue::Start, 1.0));
+    }
+}
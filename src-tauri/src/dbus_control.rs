@@ -0,0 +1,89 @@
+// Linux D-Bus control service, mirroring what the tray offers so GNOME/KDE shortcuts and scripts
+// can drive dictation:
+//
+//   busctl --user call org.dictationhud.Control /org/dictationhud/Control org.dictationhud.Control Toggle
+//
+// Methods Start, Stop and Toggle; property State and signal StateChanged carry "inactive" |
+// "starting" | "recording" | "paused" | "stopping".
+
+#[cfg(all(target_os = "linux", feature = "linux-dbus"))]
+mod platform {
+  use std::sync::OnceLock;
+  use tauri::AppHandle;
+  use tracing::{info, warn};
+  use zbus::{interface, Connection, SignalContext};
+
+  const NAME: &str = "org.dictationhud.Control";
+  const PATH: &str = "/org/dictationhud/Control";
+
+  static CONNECTION: OnceLock<Connection> = OnceLock::new();
+
+  struct Control {
+    app: AppHandle,
+  }
+
+  fn failed(e: String) -> zbus::fdo::Error {
+    zbus::fdo::Error::Failed(e)
+  }
+
+  #[interface(name = "org.dictationhud.Control")]
+  impl Control {
+    async fn start(&self) -> zbus::fdo::Result<()> {
+      crate::start_dictation(self.app.clone()).await.map_err(failed)
+    }
+
+    async fn stop(&self) -> zbus::fdo::Result<()> {
+      crate::stop_running_dictation(&self.app).await.map_err(failed)
+    }
+
+    async fn toggle(&self) -> zbus::fdo::Result<()> {
+      crate::toggle_dictation(self.app.clone()).await.map_err(failed)
+    }
+
+    #[zbus(property, name = "State")]
+    fn current_state(&self) -> String {
+      crate::dictation_state_name().to_string()
+    }
+
+    #[zbus(signal)]
+    async fn state_changed(ctxt: &SignalContext<'_>, state: &str) -> zbus::Result<()>;
+  }
+
+  /// Claim the bus name on the session bus; a second profile keeps running without it
+  pub fn spawn(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+      let built = async {
+        zbus::connection::Builder::session()?.name(NAME)?.serve_at(PATH, Control { app })?.build().await
+      };
+      match built.await {
+        Ok(conn) => {
+          info!("D-Bus control service up as {}", NAME);
+          let _ = CONNECTION.set(conn);
+        }
+        Err(e) => warn!("D-Bus control service unavailable: {}", e),
+      }
+    });
+  }
+
+  pub fn state_changed(state: &'static str) {
+    let Some(conn) = CONNECTION.get() else { return };
+    tauri::async_runtime::spawn(async move {
+      let sent = async {
+        let iface = conn.object_server().interface::<_, Control>(PATH).await?;
+        Control::state_changed(iface.signal_context(), state).await?;
+        iface.get().await.current_state_changed(iface.signal_context()).await
+      };
+      if let Err(e) = sent.await {
+        warn!("D-Bus StateChanged failed: {}", e);
+      }
+    });
+  }
+}
+
+#[cfg(not(all(target_os = "linux", feature = "linux-dbus")))]
+mod platform {
+  pub fn spawn(_app: tauri::AppHandle) {}
+  pub fn state_changed(_state: &'static str) {}
+}
+
+pub use platform::{spawn, state_changed};
@@ -0,0 +1,69 @@
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+// `dictation-hud://` links for launchers and automation (Raycast, PowerToys Run, browser
+// bookmarks): `start`, `stop`, `toggle` and `settings` go through the same handlers as the tray.
+// A second launch for a link is folded into the running instance by the single-instance plugin.
+
+pub const SCHEME: &str = "dictation-hud";
+
+/// The action named by a link: `dictation-hud://start`, `dictation-hud:start/` and the like
+fn action(url: &str) -> Option<String> {
+  let rest = url.strip_prefix(SCHEME)?.strip_prefix(':')?;
+  let name = rest.trim_start_matches('/').split(['/', '?', '#']).next()?;
+  (!name.is_empty()).then(|| name.to_ascii_lowercase())
+}
+
+pub fn is_link(arg: &str) -> bool {
+  arg.starts_with(SCHEME) && arg[SCHEME.len()..].starts_with(':')
+}
+
+pub fn handle(app: &AppHandle, url: &str) {
+  let Some(action) = action(url) else {
+    warn!("Ignoring deep link without an action: {}", url);
+    return;
+  };
+  info!("Deep link: {}", action);
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    let result = match action.as_str() {
+      "start" => crate::start_dictation(app.clone()).await,
+      "stop" => crate::stop_running_dictation(&app).await,
+      "toggle" => crate::toggle_dictation(app.clone()).await,
+      "settings" => {
+        if let Some(w) = app.get_webview_window("settings") {
+          let _ = w.show();
+          let _ = w.set_focus();
+        }
+        Ok(())
+      }
+      other => Err(format!("Unknown deep link action: {}", other)),
+    };
+    if let Err(e) = result {
+      warn!("Deep link {} failed: {}", action, e);
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_action() {
+    assert_eq!(action("dictation-hud://start").as_deref(), Some("start"));
+    assert_eq!(action("dictation-hud:toggle/").as_deref(), Some("toggle"));
+    assert_eq!(action("dictation-hud://Settings?tab=general#top").as_deref(), Some("settings"));
+    assert_eq!(action("dictation-hud:///stop/now").as_deref(), Some("stop"));
+  }
+
+  #[test]
+  fn test_action_rejects_other_links() {
+    assert_eq!(action("dictation-hud://"), None);
+    assert_eq!(action("dictation-hud:?start"), None);
+    assert_eq!(action("dictation-hudx://start"), None);
+    assert_eq!(action("https://start"), None);
+    assert!(is_link("dictation-hud://start"));
+    assert!(!is_link("dictation-hudx://start"));
+  }
+}
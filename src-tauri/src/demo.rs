@@ -0,0 +1,62 @@
+// Demo mode: a scripted stand-in for STT and AI refinement.
+// Lets new users try the whole HUD -> refine -> paste flow (and check their hotkey and
+// paste setup) before entering any API keys. Nothing here touches the network.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Scripted "transcripts". They include fillers and spoken symbols so the demo shows what
+/// the symbol layer and refinement do.
+pub const DEMO_SCRIPT: &[&str] = &[
+    "um so this is dictation hud running in demo mode",
+    "hey team quick update the build is green and uh we ship on friday",
+    "send the report to alex at sign example dot com",
+    "once you add your api keys this text will come from your own voice",
+];
+
+const FILLERS: &[&str] = &["um", "uh", "erm", "hmm"];
+
+static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+/// Next scripted transcript, cycling through the script
+pub fn next_transcript() -> &'static str {
+    let i = NEXT.fetch_add(1, Ordering::Relaxed);
+    DEMO_SCRIPT[i % DEMO_SCRIPT.len()]
+}
+
+/// Rule-based stand-in for AI refinement: drop fillers, capitalize, end with a period
+pub fn fake_refine(text: &str) -> String {
+    let words: Vec<&str> = text
+        .split_whitespace()
+        .filter(|w| !FILLERS.contains(&w.trim_matches(',').to_lowercase().as_str()))
+        .collect();
+    let mut out = words.join(" ");
+    if let Some(first) = out.chars().next() {
+        out = first.to_uppercase().collect::<String>() + &out[first.len_utf8()..];
+    }
+    if !out.is_empty() && !out.ends_with(['.', '!', '?']) {
+        out.push('.');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_refine() {
+        assert_eq!(fake_refine("um so this is a demo"), "So this is a demo.");
+        assert_eq!(fake_refine("uh, we ship on friday"), "We ship on friday.");
+        assert_eq!(fake_refine("done?"), "Done?");
+        assert_eq!(fake_refine(""), "");
+    }
+
+    #[test]
+    fn test_script_cycles() {
+        let first = next_transcript();
+        for _ in 1..DEMO_SCRIPT.len() {
+            next_transcript();
+        }
+        assert_eq!(next_transcript(), first);
+    }
+}
@@ -0,0 +1,93 @@
+// RNNoise noise suppression (nnnoiseless) for audio relayed to the backend, so suppression is the
+// same whatever the provider or webview. RNNoise works on 10ms frames at 48kHz while the relay
+// carries 16kHz, so audio is upsampled 3x, denoised and brought back down; a partial frame waits
+// for the next chunk, which delays the audio by at most 10ms.
+
+use nnnoiseless::DenoiseState;
+
+/// Samples per RNNoise frame (10ms at 48kHz)
+const FRAME: usize = DenoiseState::FRAME_SIZE;
+/// 48kHz / 16kHz
+const RATIO: usize = 3;
+
+pub struct Denoiser {
+    state: Box<DenoiseState<'static>>,
+    /// Upsampled audio not yet making up a whole frame
+    pending: Vec<f32>,
+    /// Last input sample, to interpolate across chunk boundaries
+    last: f32,
+    out_frame: Vec<f32>,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Denoiser { state: DenoiseState::new(), pending: Vec::with_capacity(FRAME * 2), last: 0.0, out_frame: vec![0.0; FRAME] }
+    }
+}
+
+/// 16kHz samples to 48kHz by linear interpolation, continuing from `last`
+fn upsample(samples: &[f32], last: f32, out: &mut Vec<f32>) {
+    let mut prev = last;
+    for &s in samples {
+        for k in 1..=RATIO {
+            out.push(prev + (s - prev) * k as f32 / RATIO as f32);
+        }
+        prev = s;
+    }
+}
+
+/// 48kHz samples to 16kHz by averaging each group of three (a crude low-pass, enough for speech)
+fn downsample(samples: &[f32]) -> impl Iterator<Item = f32> + '_ {
+    samples.chunks(RATIO).map(|c| c.iter().sum::<f32>() / c.len() as f32)
+}
+
+impl Denoiser {
+    /// Denoise little-endian 16-bit PCM at 16kHz; returns the audio of every completed frame
+    pub fn process(&mut self, pcm: &[u8]) -> Vec<u8> {
+        // RNNoise expects samples in the i16 range, not -1..1
+        let samples: Vec<f32> = pcm.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32).collect();
+        upsample(&samples, self.last, &mut self.pending);
+        if let Some(&s) = samples.last() {
+            self.last = s;
+        }
+
+        let frames = self.pending.len() / FRAME;
+        let mut out = Vec::with_capacity(frames * FRAME / RATIO * 2);
+        for i in 0..frames {
+            self.state.process_frame(&mut self.out_frame, &self.pending[i * FRAME..(i + 1) * FRAME]);
+            for s in downsample(&self.out_frame) {
+                out.extend_from_slice(&(s.round().clamp(i16::MIN as f32, i16::MAX as f32) as i16).to_le_bytes());
+            }
+        }
+        self.pending.drain(..frames * FRAME);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resampling_round_trip() {
+        let input: Vec<f32> = (0..160).map(|i| (i as f32 * 0.1).sin() * 1000.0).collect();
+        let mut up = Vec::new();
+        upsample(&input, input[0], &mut up);
+        assert_eq!(up.len(), input.len() * RATIO);
+        let down: Vec<f32> = downsample(&up).collect();
+        assert_eq!(down.len(), input.len());
+        // Averaging interpolated points lags slightly, but stays close on a smooth signal
+        assert!(down.iter().zip(&input).all(|(d, i)| (d - i).abs() < 100.0));
+    }
+
+    #[test]
+    fn test_output_follows_whole_frames() {
+        let mut denoiser = Denoiser::default();
+        let chunk = vec![0u8; 100 * 2];
+        // 100 samples upsample to 300, short of one 480-sample frame
+        assert!(denoiser.process(&chunk).is_empty());
+        // 600 in total: one frame out (160 samples), 120 carried over
+        assert_eq!(denoiser.process(&chunk).len(), FRAME / RATIO * 2);
+        assert_eq!(denoiser.pending.len(), 600 - FRAME);
+    }
+}
@@ -0,0 +1,100 @@
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::{failures, history, instance, latency, logging, redact, startup};
+
+// One zip with what a bug report needs: recent logs, settings with secrets masked, system and
+// monitor info, provider latencies and failure counters. Dictation history is left out; the logs
+// can still contain dictated text, which the bundled README points out.
+
+const LOG_LINES: usize = 5000;
+
+fn system_info(app: &AppHandle) -> Value {
+  let monitors: Vec<Value> = app
+    .available_monitors()
+    .unwrap_or_default()
+    .iter()
+    .map(|m| {
+      json!({
+        "name": m.name(),
+        "width": m.size().width,
+        "height": m.size().height,
+        "x": m.position().x,
+        "y": m.position().y,
+        "scale_factor": m.scale_factor(),
+      })
+    })
+    .collect();
+  json!({
+    "app_version": app.package_info().version.to_string(),
+    "tauri_version": tauri::VERSION,
+    "os": std::env::consts::OS,
+    "os_family": std::env::consts::FAMILY,
+    "arch": std::env::consts::ARCH,
+    "session_type": std::env::var("XDG_SESSION_TYPE").ok(),
+    "profile": instance::profile(),
+    "monitors": monitors,
+    "startup": startup::metrics(),
+  })
+}
+
+fn sanitized_prefs(app: &AppHandle) -> Value {
+  let Ok(store) = app.store("prefs.json") else { return Value::Null };
+  let mut prefs = Value::Object(store.entries().into_iter().collect::<Map<String, Value>>());
+  redact::redact_secrets(&mut prefs);
+  prefs
+}
+
+fn failure_info(app: &AppHandle) -> Value {
+  let stats = failures::stats(app);
+  let last_error = stats
+    .categories
+    .iter()
+    .filter(|(_, c)| c.last_at.is_some())
+    .max_by_key(|(_, c)| c.last_at)
+    .map(|(kind, c)| json!({ "kind": kind, "at": c.last_at, "detail": c.last_detail }));
+  json!({ "last_error": last_error, "stats": stats })
+}
+
+fn pretty(value: &Value) -> String {
+  serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+/// Write the bundle to the Downloads folder and return its path
+pub fn export(app: &AppHandle) -> Result<PathBuf, String> {
+  let dir = app.path().download_dir().or_else(|_| app.path().home_dir()).map_err(|e| e.to_string())?;
+  let path = dir.join(format!("dictation-hud-diagnostics-{}.zip", history::now_secs()));
+  let logs = logging::recent(app, LOG_LINES).unwrap_or_else(|e| format!("(no logs: {})", e));
+
+  let files = [
+    (
+      "README.txt",
+      "Dictation HUD diagnostics.\nAPI keys and tokens in prefs.json are redacted. logs.txt may contain dictated text; \
+       review it before sharing.\n"
+        .to_string(),
+    ),
+    ("system.json", pretty(&system_info(app))),
+    ("prefs.json", pretty(&sanitized_prefs(app))),
+    ("latencies.json", pretty(&json!(latency::summary()))),
+    ("failures.json", pretty(&failure_info(app))),
+    ("logs.txt", logs),
+  ];
+
+  let file = File::create(&path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+  let mut zip = ZipWriter::new(file);
+  let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+  for (name, contents) in files {
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    zip.write_all(contents.as_bytes()).map_err(|e| e.to_string())?;
+  }
+  zip.finish().map_err(|e| e.to_string())?;
+  info!("Diagnostics written to {}", path.display());
+  Ok(path)
+}
@@ -0,0 +1,87 @@
+// Personal dictionary: names, product names and acronyms the user wants spelled their way.
+// Entries are sent to Deepgram as keywords, so recognition favors them, and appended to the
+// refinement prompt with the packs' spellings (`Pack::prompt_suffix`), so the model doesn't
+// "correct" them afterwards.
+
+use std::sync::Mutex;
+
+/// Longest entry kept; anything longer is a sentence, not a word
+pub const MAX_ENTRY_LEN: usize = 64;
+/// Deepgram limits the request URL; the first this many entries are sent as keywords
+pub const MAX_STT_KEYWORDS: usize = 100;
+
+static WORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+pub fn set_words(words: Vec<String>) {
+    *WORDS.lock().unwrap() = words;
+}
+
+pub fn words() -> Vec<String> {
+    WORDS.lock().unwrap().clone()
+}
+
+/// Collapse whitespace; None if the entry is empty or too long
+pub fn normalize(word: &str) -> Option<String> {
+    let word = word.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!word.is_empty() && word.chars().count() <= MAX_ENTRY_LEN).then_some(word)
+}
+
+/// Add `word`, replacing an entry that differs only in case (the new spelling wins)
+pub fn add(words: &mut Vec<String>, word: &str) -> Result<(), String> {
+    let word = normalize(word).ok_or_else(|| format!("Dictionary entries must be 1-{} characters", MAX_ENTRY_LEN))?;
+    match words.iter_mut().find(|w| w.eq_ignore_ascii_case(&word)) {
+        Some(existing) => *existing = word,
+        None => words.push(word),
+    }
+    Ok(())
+}
+
+/// Remove `word` (case-insensitive); false if it wasn't there
+pub fn remove(words: &mut Vec<String>, word: &str) -> bool {
+    let word = word.trim();
+    let before = words.len();
+    words.retain(|w| !w.eq_ignore_ascii_case(word));
+    words.len() != before
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(b as char),
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+/// `&keywords=...` query parameters for a Deepgram request (empty without entries)
+pub fn deepgram_params(words: &[String]) -> String {
+    words.iter().take(MAX_STT_KEYWORDS).map(|w| format!("&keywords={}", percent_encode(w))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_and_remove() {
+        let mut words = Vec::new();
+        add(&mut words, "  Tanjim ").unwrap();
+        add(&mut words, "Dictation   HUD").unwrap();
+        add(&mut words, "tanjim").unwrap();
+        assert_eq!(words, vec!["tanjim", "Dictation HUD"]);
+        assert!(add(&mut words, " ").is_err());
+        assert!(add(&mut words, &"x".repeat(MAX_ENTRY_LEN + 1)).is_err());
+        assert!(remove(&mut words, "TANJIM"));
+        assert!(!remove(&mut words, "Tanjim"));
+        assert_eq!(words, vec!["Dictation HUD"]);
+    }
+
+    #[test]
+    fn test_deepgram_params() {
+        let words = vec!["Tanjim".to_string(), "Dictation HUD".to_string(), "C++".to_string()];
+        assert_eq!(deepgram_params(&words), "&keywords=Tanjim&keywords=Dictation%20HUD&keywords=C%2B%2B");
+        assert_eq!(deepgram_params(&[]), "");
+    }
+}
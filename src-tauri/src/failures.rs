@@ -0,0 +1,110 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::history::now_secs;
+
+// Local-only failure counters, so users can tell whether problems come from
+// focus probing, pasting, the network, or the model. Nothing here leaves the machine.
+
+const STATS_STORE: &str = "stats.json";
+const K_FAILURES: &str = "failures";
+const K_SINCE: &str = "failures_since";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+  /// Dictation refused to start because no text field was focused
+  NoFocus,
+  /// Dictation refused to start in a password field or while secure keyboard entry was on
+  SecureField,
+  /// Auto-paste keystroke could not be sent; text was left on the clipboard
+  PasteFailed,
+  /// Provider request exceeded its timeout
+  ProviderTimeout,
+  /// Provider unreachable (DNS, TLS, connection reset)
+  ProviderNetwork,
+  /// Provider answered with a non-success HTTP status
+  ProviderHttp,
+  /// AI output looked like a refusal/rewrite and raw text was used instead
+  RefusalFallback,
+  /// STT WebSocket failed to connect or dropped (reported by the HUD)
+  SttConnection,
+  /// The window a dictation started in closed before its text could be pasted
+  TargetClosed,
+  /// The watchdog reset a dictation stuck in Starting or Stopping
+  StuckState,
+}
+
+impl FailureKind {
+  pub fn parse(s: &str) -> Option<Self> {
+    serde_json::from_value(serde_json::Value::String(s.to_string())).ok()
+  }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct FailureCount {
+  pub count: u64,
+  pub last_at: Option<u64>,
+  pub last_detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct FailureStats {
+  /// Unix timestamp (seconds) counting started or was last reset
+  pub since: Option<u64>,
+  pub total: u64,
+  pub categories: BTreeMap<FailureKind, FailureCount>,
+}
+
+fn load(app: &AppHandle) -> BTreeMap<FailureKind, FailureCount> {
+  let Ok(store) = app.store(STATS_STORE) else { return BTreeMap::new() };
+  store.get(K_FAILURES).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+/// Count one failure; storage errors are logged and otherwise ignored
+pub fn record(app: &AppHandle, kind: FailureKind, detail: impl Into<String>) {
+  let detail = detail.into();
+  info!("Failure recorded: {:?} ({})", kind, detail);
+  crate::feedback::failed(app, kind, &detail);
+  let _stats = crate::session_metrics::lock_stats();
+  let mut counts = load(app);
+  let entry = counts.entry(kind).or_default();
+  entry.count += 1;
+  entry.last_at = Some(now_secs());
+  entry.last_detail = Some(detail.chars().take(200).collect());
+  let result = (|| -> anyhow::Result<()> {
+    let store = app.store(STATS_STORE)?;
+    if store.get(K_SINCE).is_none() { store.set(K_SINCE, now_secs()); }
+    store.set(K_FAILURES, serde_json::to_value(&counts)?);
+    store.save()?;
+    Ok(())
+  })();
+  if let Err(e) = result { warn!("Failed to persist failure stats: {}", e); }
+}
+
+/// Record a failed provider request under the matching category and return its message
+pub fn record_request_error(app: &AppHandle, provider: &str, e: reqwest::Error) -> String {
+  let kind = if e.is_timeout() { FailureKind::ProviderTimeout } else { FailureKind::ProviderNetwork };
+  let msg = e.to_string();
+  record(app, kind, format!("{}: {}", provider, msg));
+  msg
+}
+
+pub fn stats(app: &AppHandle) -> FailureStats {
+  let categories = load(app);
+  let since = app.store(STATS_STORE).ok().and_then(|s| s.get(K_SINCE)).and_then(|v| v.as_u64());
+  FailureStats { since, total: categories.values().map(|c| c.count).sum(), categories }
+}
+
+pub fn reset(app: &AppHandle) -> anyhow::Result<()> {
+  let _stats = crate::session_metrics::lock_stats();
+  let store = app.store(STATS_STORE)?;
+  store.set(K_FAILURES, serde_json::json!({}));
+  store.set(K_SINCE, now_secs());
+  store.save()?;
+  Ok(())
+}
@@ -0,0 +1,116 @@
+use rodio::{buffer::SamplesBuffer, OutputStream, Sink};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+use tracing::{debug, warn};
+
+use crate::cues::{self, Cue};
+use crate::failures::FailureKind;
+
+// Sound and notification feedback outside the HUD: the cues (cues.rs) for recording started,
+// recording stopped and errors, and an OS notification for errors, each behind its own pref.
+// Playback opens the default output device for the length of the cue on a thread of its own.
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+pub fn init(app: AppHandle) {
+  let _ = APP.set(app);
+}
+
+/// The stored behavior prefs, read raw so this works from any thread without the command layer
+fn behavior(app: &AppHandle) -> serde_json::Value {
+  app.store("prefs.json").ok().and_then(|s| s.get("behavior")).unwrap_or_default()
+}
+
+fn flag(behavior: &serde_json::Value, key: &str) -> bool {
+  behavior.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+fn volume(behavior: &serde_json::Value) -> f32 {
+  behavior.get("sound_volume").and_then(|v| v.as_u64()).unwrap_or(60).min(100) as f32 / 100.0
+}
+
+fn pref_key(cue: Cue) -> &'static str {
+  match cue {
+    Cue::Start => "sound_start",
+    Cue::Stop => "sound_stop",
+    Cue::Error => "sound_error",
+  }
+}
+
+/// Play `cue` at `volume` (0..=1) without blocking the caller
+pub fn play(cue: Cue, volume: f32) {
+  std::thread::spawn(move || {
+    let played = (|| -> Result<(), String> {
+      let (_stream, handle) = OutputStream::try_default().map_err(|e| e.to_string())?;
+      let sink = Sink::try_new(&handle).map_err(|e| e.to_string())?;
+      sink.append(SamplesBuffer::new(1, cues::SAMPLE_RATE, cues::samples(cue, volume)));
+      sink.sleep_until_end();
+      Ok(())
+    })();
+    if let Err(e) = played {
+      warn!("Could not play {:?} cue: {}", cue, e);
+    }
+  });
+}
+
+/// Play `cue` if its pref is on
+fn cue(app: &AppHandle, cue: Cue) {
+  let behavior = behavior(app);
+  if flag(&behavior, pref_key(cue)) {
+    debug!("Playing {:?} cue", cue);
+    play(cue, volume(&behavior));
+  }
+}
+
+/// Dictation state transition (names as in `DictationState::name`); the microphone going live
+/// and recording ending get their cues
+pub fn state_changed(from: &'static str, to: &'static str) {
+  let Some(app) = APP.get() else { return };
+  let app = app.clone();
+  let which = match (from, to) {
+    ("starting", "recording") => Cue::Start,
+    ("recording" | "paused", "stopping") => Cue::Stop,
+    _ => return,
+  };
+  // Off the caller's thread: it holds the recording state lock
+  tauri::async_runtime::spawn(async move { cue(&app, which) });
+}
+
+/// What a notification says about a failure; None for kinds that aren't worth interrupting for
+fn summary(kind: FailureKind) -> Option<&'static str> {
+  Some(match kind {
+    FailureKind::NoFocus => "No text field was focused, so dictation didn't start",
+    FailureKind::SecureField => "Dictation is off in password fields",
+    FailureKind::PasteFailed => "Paste failed; the text is on the clipboard",
+    FailureKind::ProviderTimeout => "Refinement timed out",
+    FailureKind::ProviderNetwork => "Refinement provider unreachable",
+    FailureKind::ProviderHttp => "Refinement provider returned an error",
+    FailureKind::SttConnection => "Speech-to-text connection failed",
+    FailureKind::TargetClosed => "Target window closed; the text is on the clipboard",
+    FailureKind::StuckState => "Dictation got stuck and was reset",
+    // The raw transcript was used; the dictation still went through
+    FailureKind::RefusalFallback => return None,
+  })
+}
+
+/// A failure was recorded: the error cue and notification, when on
+pub fn failed(app: &AppHandle, kind: FailureKind, detail: &str) {
+  let Some(title) = summary(kind) else { return };
+  let behavior = behavior(app);
+  if flag(&behavior, "sound_error") {
+    play(Cue::Error, volume(&behavior));
+  }
+  if flag(&behavior, "notify_errors") {
+    let shown = app.notification().builder().title(title).body(detail).show();
+    if let Err(e) = shown {
+      warn!("Could not show a notification: {}", e);
+    }
+  }
+}
+
+/// Play `cue` at the volume pref, whether or not it is enabled (Settings preview)
+pub fn preview(app: &AppHandle, cue: Cue) {
+  play(cue, volume(&behavior(app)));
+}
@@ -0,0 +1,359 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+// Accessibility-based check of what currently has keyboard focus, used to decide whether a
+// dictation can be pasted anywhere before the HUD shows up. Only answers "not editable" when
+// it is confident: apps with poor accessibility support report Unknown and dictation proceeds.
+
+/// What the focused UI element accepts, as far as the platform accessibility API can tell
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusKind {
+  /// An editable text field or document
+  Editable,
+  /// A control that doesn't take text (button, list, desktop) or a read-only field
+  NotEditable,
+  /// A password field, or secure keyboard entry is on: synthetic keystrokes won't arrive
+  Secure,
+  /// No usable accessibility information
+  Unknown,
+}
+
+impl FocusKind {
+  /// Dictation may start: the field takes text, or we can't tell
+  pub fn accepts_text(self) -> bool {
+    matches!(self, FocusKind::Editable | FocusKind::Unknown)
+  }
+}
+
+/// Whether this build has an accessibility probe (otherwise `focused_element` is always Unknown)
+pub fn available() -> bool {
+  platform::AVAILABLE
+}
+
+/// Inspect the focused element. Blocking; call from a worker thread.
+pub fn focused_element() -> FocusKind {
+  platform::focused_element()
+}
+
+#[cfg(all(target_os = "windows", feature = "windows-uia"))]
+mod platform {
+  use super::FocusKind;
+  use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED};
+  use windows::Win32::UI::Accessibility::*;
+
+  pub const AVAILABLE: bool = true;
+
+  // Control types that never take typed text. Anything else without text/value patterns
+  // (panes, custom and group controls of self-drawn apps) stays Unknown.
+  const NON_TEXT_CONTROLS: &[UIA_CONTROLTYPE_ID] = &[
+    UIA_ButtonControlTypeId, UIA_SplitButtonControlTypeId, UIA_CheckBoxControlTypeId, UIA_RadioButtonControlTypeId,
+    UIA_HyperlinkControlTypeId, UIA_ImageControlTypeId, UIA_ListControlTypeId, UIA_ListItemControlTypeId,
+    UIA_MenuControlTypeId, UIA_MenuBarControlTypeId, UIA_MenuItemControlTypeId, UIA_TabControlTypeId,
+    UIA_TabItemControlTypeId, UIA_TreeControlTypeId, UIA_TreeItemControlTypeId, UIA_ToolBarControlTypeId,
+    UIA_ScrollBarControlTypeId, UIA_SliderControlTypeId, UIA_ProgressBarControlTypeId, UIA_TitleBarControlTypeId,
+    UIA_HeaderItemControlTypeId,
+  ];
+
+  unsafe fn bool_prop(el: &IUIAutomationElement, id: UIA_PROPERTY_ID) -> Option<bool> {
+    el.GetCurrentPropertyValue(id).ok().and_then(|v| bool::try_from(&v).ok())
+  }
+
+  pub fn focused_element() -> FocusKind {
+    unsafe {
+      // COM is initialized per thread; S_FALSE/RPC_E_CHANGED_MODE just mean it already is
+      let _ = CoInitializeEx(None, COINIT_MULTITHREADED);
+      let automation: IUIAutomation = match CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER) {
+        Ok(a) => a,
+        Err(e) => {
+          tracing::warn!("UI Automation unavailable: {}", e);
+          return FocusKind::Unknown;
+        }
+      };
+      let Ok(el) = automation.GetFocusedElement() else { return FocusKind::Unknown };
+
+      let control_type = el
+        .GetCurrentPropertyValue(UIA_ControlTypePropertyId)
+        .ok()
+        .and_then(|v| i32::try_from(&v).ok())
+        .map(UIA_CONTROLTYPE_ID);
+      let has_text = bool_prop(&el, UIA_IsTextPatternAvailablePropertyId).unwrap_or(false);
+      let has_value = bool_prop(&el, UIA_IsValuePatternAvailablePropertyId).unwrap_or(false);
+      let read_only = has_value && bool_prop(&el, UIA_ValueIsReadOnlyPropertyId).unwrap_or(false);
+      let password = bool_prop(&el, UIA_IsPasswordPropertyId).unwrap_or(false);
+      tracing::info!(
+        "UIA focus: control type {:?}, text pattern {}, value pattern {} (read-only {}, password {})",
+        control_type.map(|c| c.0), has_text, has_value, read_only, password
+      );
+
+      let text_control = matches!(control_type, Some(c) if c == UIA_EditControlTypeId || c == UIA_DocumentControlTypeId);
+      if password {
+        FocusKind::Secure
+      } else if read_only {
+        FocusKind::NotEditable
+      } else if has_value || has_text || text_control {
+        // Read-only documents (e.g. a PDF viewer) also expose TextPattern; without a value
+        // pattern there is no way to tell, so give them the benefit of the doubt
+        FocusKind::Editable
+      } else if matches!(control_type, Some(c) if NON_TEXT_CONTROLS.contains(&c)) {
+        FocusKind::NotEditable
+      } else {
+        FocusKind::Unknown
+      }
+    }
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::FocusKind;
+  use std::ffi::{c_char, c_void, CStr, CString};
+
+  // Accessibility (AX) API over raw FFI; the focused element comes from the system-wide
+  // element. Needs the Accessibility permission, which auto-paste already requires.
+
+  type CFTypeRef = *const c_void;
+  type CFStringRef = *const c_void;
+  type AXUIElementRef = *const c_void;
+  type AXError = i32;
+
+  const AX_SUCCESS: AXError = 0;
+  const UTF8: u32 = 0x0800_0100;
+
+  #[link(name = "ApplicationServices", kind = "framework")]
+  extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+    fn AXUIElementCreateSystemWide() -> AXUIElementRef;
+    fn AXUIElementCopyAttributeValue(element: AXUIElementRef, attribute: CFStringRef, value: *mut CFTypeRef) -> AXError;
+    fn AXUIElementIsAttributeSettable(element: AXUIElementRef, attribute: CFStringRef, settable: *mut u8) -> AXError;
+  }
+
+  #[link(name = "CoreFoundation", kind = "framework")]
+  extern "C" {
+    fn CFStringCreateWithCString(alloc: *const c_void, c_str: *const c_char, encoding: u32) -> CFStringRef;
+    fn CFStringGetCString(s: CFStringRef, buffer: *mut c_char, size: isize, encoding: u32) -> u8;
+    fn CFGetTypeID(cf: CFTypeRef) -> usize;
+    fn CFStringGetTypeID() -> usize;
+    fn CFRelease(cf: CFTypeRef);
+  }
+
+  #[link(name = "Carbon", kind = "framework")]
+  extern "C" {
+    /// True while any app (a password field, Terminal's Secure Keyboard Entry) holds secure input
+    fn IsSecureEventInputEnabled() -> u8;
+  }
+
+  pub const AVAILABLE: bool = true;
+
+  const EDITABLE_ROLES: &[&str] = &["AXTextField", "AXTextArea", "AXComboBox", "AXSearchField"];
+  // Roles that never take typed text; anything else unrecognized (web areas, groups) stays Unknown
+  const NON_TEXT_ROLES: &[&str] = &[
+    "AXButton", "AXCheckBox", "AXRadioButton", "AXPopUpButton", "AXMenuButton", "AXLink", "AXImage",
+    "AXList", "AXTable", "AXOutline", "AXRow", "AXCell", "AXMenu", "AXMenuBar", "AXMenuItem",
+    "AXTabGroup", "AXToolbar", "AXScrollBar", "AXSlider", "AXProgressIndicator", "AXStaticText",
+  ];
+
+  /// Owned CF object, released on drop
+  struct Cf(CFTypeRef);
+
+  impl Drop for Cf {
+    fn drop(&mut self) {
+      if !self.0.is_null() { unsafe { CFRelease(self.0) } }
+    }
+  }
+
+  fn cf_string(s: &str) -> Cf {
+    let c = CString::new(s).unwrap_or_default();
+    Cf(unsafe { CFStringCreateWithCString(std::ptr::null(), c.as_ptr(), UTF8) })
+  }
+
+  unsafe fn copy_attribute(el: AXUIElementRef, name: &str) -> Option<Cf> {
+    let attr = cf_string(name);
+    let mut value: CFTypeRef = std::ptr::null();
+    if AXUIElementCopyAttributeValue(el, attr.0, &mut value) != AX_SUCCESS || value.is_null() {
+      return None;
+    }
+    Some(Cf(value))
+  }
+
+  unsafe fn string_attribute(el: AXUIElementRef, name: &str) -> Option<String> {
+    let value = copy_attribute(el, name)?;
+    if CFGetTypeID(value.0) != CFStringGetTypeID() {
+      return None;
+    }
+    let mut buf = [0 as c_char; 128];
+    if CFStringGetCString(value.0, buf.as_mut_ptr(), buf.len() as isize, UTF8) == 0 {
+      return None;
+    }
+    Some(CStr::from_ptr(buf.as_ptr()).to_string_lossy().into_owned())
+  }
+
+  unsafe fn value_settable(el: AXUIElementRef) -> bool {
+    let attr = cf_string("AXValue");
+    let mut settable = 0u8;
+    AXUIElementIsAttributeSettable(el, attr.0, &mut settable) == AX_SUCCESS && settable != 0
+  }
+
+  pub fn focused_element() -> FocusKind {
+    unsafe {
+      if IsSecureEventInputEnabled() != 0 {
+        tracing::info!("Secure keyboard entry is active");
+        return FocusKind::Secure;
+      }
+      if !AXIsProcessTrusted() {
+        tracing::warn!("Accessibility permission not granted; can't inspect the focused element");
+        return FocusKind::Unknown;
+      }
+      let system = Cf(AXUIElementCreateSystemWide());
+      let Some(el) = copy_attribute(system.0, "AXFocusedUIElement") else { return FocusKind::Unknown };
+
+      let role = string_attribute(el.0, "AXRole");
+      let subrole = string_attribute(el.0, "AXSubrole");
+      let settable = value_settable(el.0);
+      tracing::info!("AX focus: role {:?}, subrole {:?}, value settable {}", role, subrole, settable);
+
+      let role = role.as_deref().unwrap_or("");
+      if subrole.as_deref() == Some("AXSecureTextField") {
+        FocusKind::Secure
+      } else if settable || EDITABLE_ROLES.contains(&role) {
+        FocusKind::Editable
+      } else if NON_TEXT_ROLES.contains(&role) {
+        FocusKind::NotEditable
+      } else {
+        FocusKind::Unknown
+      }
+    }
+  }
+}
+
+#[cfg(all(target_os = "linux", feature = "linux-atspi"))]
+mod platform {
+  use super::FocusKind;
+  use serde::de::DeserializeOwned;
+  use std::collections::HashMap;
+  use zbus::blocking::Connection;
+  use zbus::zvariant::{DynamicType, OwnedObjectPath};
+
+  // AT-SPI2 over D-Bus: find the active window, then the focused accessible inside it.
+  // Only sees apps that registered with the accessibility bus (GTK, Qt, browsers and
+  // Electron with accessibility on); everything else comes back Unknown.
+
+  pub const AVAILABLE: bool = true;
+
+  /// An accessible: (bus name, object path)
+  type Obj = (String, OwnedObjectPath);
+
+  const ACCESSIBLE: &str = "org.a11y.atspi.Accessible";
+  const COLLECTION: &str = "org.a11y.atspi.Collection";
+  const EDITABLE_TEXT: &str = "org.a11y.atspi.EditableText";
+
+  // AtspiStateType bits and AtspiRole values
+  const STATE_ACTIVE: u32 = 1;
+  const STATE_EDITABLE: u32 = 7;
+  const STATE_FOCUSED: u32 = 12;
+  const STATE_SHOWING: u32 = 25;
+  const ROLE_PASSWORD_TEXT: u32 = 40;
+  const ROLE_TERMINAL: u32 = 60;
+  // Check box, icon, image, list, list item, menu, menu bar, menu item, page tab, page tab
+  // list, push button, radio button, scroll bar, slider, table, table cell, toggle button,
+  // tool bar, tree, tree table, link, tree item, list box
+  const NON_TEXT_ROLES: &[u32] = &[7, 26, 27, 31, 32, 33, 34, 35, 37, 38, 43, 44, 48, 51, 55, 56, 62, 63, 65, 66, 88, 91, 98];
+  /// Upper bound for the fallback tree walk, for apps without the Collection interface
+  const MAX_WALK: usize = 2000;
+
+  fn call<B, R>(conn: &Connection, obj: &Obj, iface: &str, method: &str, body: &B) -> zbus::Result<R>
+  where
+    B: serde::Serialize + DynamicType,
+    R: DeserializeOwned + zbus::zvariant::Type,
+  {
+    let reply = conn.call_method(Some(obj.0.as_str()), obj.1.as_str(), Some(iface), method, body)?;
+    reply.body().deserialize::<R>()
+  }
+
+  fn children(conn: &Connection, obj: &Obj) -> Vec<Obj> {
+    call(conn, obj, ACCESSIBLE, "GetChildren", &()).unwrap_or_default()
+  }
+
+  fn has_state(conn: &Connection, obj: &Obj, state: u32) -> bool {
+    let bits: Vec<u32> = call(conn, obj, ACCESSIBLE, "GetState", &()).unwrap_or_default();
+    bits.get((state / 32) as usize).map(|w| w & (1 << (state % 32)) != 0).unwrap_or(false)
+  }
+
+  fn a11y_bus() -> zbus::Result<Connection> {
+    let session = Connection::session()?;
+    let reply = session.call_method(Some("org.a11y.Bus"), "/org/a11y/bus", Some("org.a11y.Bus"), "GetAddress", &())?;
+    let address: String = reply.body().deserialize()?;
+    zbus::blocking::connection::Builder::address(address.as_str())?.build()
+  }
+
+  fn active_window(conn: &Connection) -> Option<Obj> {
+    let root: Obj = ("org.a11y.atspi.Registry".into(), OwnedObjectPath::try_from("/org/a11y/atspi/accessible/root").ok()?);
+    children(conn, &root)
+      .iter()
+      .flat_map(|app| children(conn, app))
+      .find(|w| has_state(conn, w, STATE_ACTIVE))
+  }
+
+  /// One Collection query for a focused descendant
+  fn focused_by_collection(conn: &Connection, window: &Obj) -> zbus::Result<Option<Obj>> {
+    const MATCH_ALL: i32 = 1;
+    let mut states = [0i32; 2];
+    states[0] = 1 << STATE_FOCUSED;
+    let rule = (states.to_vec(), MATCH_ALL, HashMap::<String, String>::new(), MATCH_ALL, Vec::<i32>::new(), MATCH_ALL, Vec::<String>::new(), MATCH_ALL, false);
+    let found: Vec<Obj> = call(conn, window, COLLECTION, "GetMatches", &(rule, 0u32, 1i32, true))?;
+    Ok(found.into_iter().next())
+  }
+
+  /// Breadth-first walk over showing descendants
+  fn focused_by_walk(conn: &Connection, window: &Obj) -> Option<Obj> {
+    let mut queue = std::collections::VecDeque::from(children(conn, window));
+    let mut seen = 0;
+    while let Some(obj) = queue.pop_front() {
+      seen += 1;
+      if seen > MAX_WALK { break; }
+      if has_state(conn, &obj, STATE_FOCUSED) { return Some(obj); }
+      if has_state(conn, &obj, STATE_SHOWING) { queue.extend(children(conn, &obj)); }
+    }
+    None
+  }
+
+  pub fn focused_element() -> FocusKind {
+    let conn = match a11y_bus() {
+      Ok(c) => c,
+      Err(e) => {
+        tracing::warn!("AT-SPI bus unavailable: {}", e);
+        return FocusKind::Unknown;
+      }
+    };
+    let Some(window) = active_window(&conn) else { return FocusKind::Unknown };
+    let focused = focused_by_collection(&conn, &window).ok().flatten().or_else(|| focused_by_walk(&conn, &window));
+    let Some(el) = focused else { return FocusKind::Unknown };
+
+    let role: u32 = call(&conn, &el, ACCESSIBLE, "GetRole", &()).unwrap_or(0);
+    let interfaces: Vec<String> = call(&conn, &el, ACCESSIBLE, "GetInterfaces", &()).unwrap_or_default();
+    let editable_text = interfaces.iter().any(|i| i == EDITABLE_TEXT);
+    let editable = editable_text && has_state(&conn, &el, STATE_EDITABLE);
+    tracing::info!("AT-SPI focus: role {}, EditableText {}, editable {}", role, editable_text, editable);
+
+    if role == ROLE_PASSWORD_TEXT {
+      FocusKind::Secure
+    } else if editable || role == ROLE_TERMINAL {
+      // Terminals expose Text but not EditableText, yet take typed input
+      FocusKind::Editable
+    } else if NON_TEXT_ROLES.contains(&role) || editable_text {
+      // EditableText without the editable state is a read-only field
+      FocusKind::NotEditable
+    } else {
+      FocusKind::Unknown
+    }
+  }
+}
+
+#[cfg(not(any(all(target_os = "windows", feature = "windows-uia"), target_os = "macos", all(target_os = "linux", feature = "linux-atspi"))))]
+mod platform {
+  use super::FocusKind;
+
+  pub const AVAILABLE: bool = false;
+
+  pub fn focused_element() -> FocusKind { FocusKind::Unknown }
+}
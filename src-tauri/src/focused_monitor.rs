@@ -0,0 +1,230 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Monitor};
+
+// Which monitor the HUD appears on, per the `monitor_policy` pref: the primary monitor, the one
+// with the mouse cursor, the one with the focused window, or a fixed monitor by index. The cursor
+// position comes from Win32 on Windows (so the HUD keeps clear of the taskbar) and from Tauri
+// elsewhere; the focused window from Win32 on Windows, System Events on macOS and xdotool on X11.
+// Those two run a helper process, so the HUD first appears on the cursor's monitor and moves once
+// the helper answers (`deferred`). Wayland doesn't show native windows to other apps; there the
+// HUD stays on the cursor's monitor (or the primary one) and says so once.
+
+pub const POLICIES: &[&str] = &["primary", "monitor-with-cursor", "monitor-of-focused-window"];
+
+/// (left, top, width, height) in physical pixels
+pub type Rect = (i32, i32, u32, u32);
+
+/// A named policy, or a monitor index ("0", "1", ...)
+pub fn is_valid_policy(policy: &str) -> bool {
+  POLICIES.contains(&policy) || policy.parse::<usize>().is_ok()
+}
+
+fn rect(monitor: &Monitor) -> Rect {
+  let (pos, size) = (monitor.position(), monitor.size());
+  (pos.x, pos.y, size.width, size.height)
+}
+
+fn monitor_at(app: &AppHandle, x: f64, y: f64) -> Option<Rect> {
+  app.monitor_from_point(x, y).ok().flatten().map(|m| rect(&m))
+}
+
+fn cursor_area(app: &AppHandle) -> Option<Rect> {
+  platform::cursor_area().or_else(|| {
+    let at = app.cursor_position().ok()?;
+    monitor_at(app, at.x, at.y)
+  })
+}
+
+/// The area to show the HUD in right away under `policy`: `work_area`, except that where finding
+/// the focused window runs a helper process it is the cursor's monitor until that answers
+pub fn immediate_area(app: &AppHandle, policy: &str) -> Option<Rect> {
+  if deferred(policy) { cursor_area(app) } else { work_area(app, policy) }
+}
+
+/// Whether `work_area` for `policy` is too slow to wait for when the HUD appears
+pub fn deferred(policy: &str) -> bool {
+  policy == "monitor-of-focused-window" && platform::PROBE_RUNS_HELPER
+}
+
+/// The area to place the HUD in under `policy`; None means the primary monitor. May block on a
+/// helper process, see `deferred`.
+pub fn work_area(app: &AppHandle, policy: &str) -> Option<Rect> {
+  match policy {
+    "primary" => None,
+    "monitor-with-cursor" => cursor_area(app),
+    "monitor-of-focused-window" => platform::focused_window_area(app).or_else(|| cursor_area(app)),
+    // A monitor that has since been unplugged falls back to the primary one
+    index => {
+      let index: usize = index.parse().ok()?;
+      app.available_monitors().ok()?.get(index).map(rect)
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MonitorInfo {
+  /// What a `monitor_policy` of this monitor's index is written as
+  pub index: usize,
+  pub name: Option<String>,
+  pub width: u32,
+  pub height: u32,
+  pub primary: bool,
+}
+
+/// Connected monitors, in the order `monitor_policy` indices refer to
+pub fn list(app: &AppHandle) -> Vec<MonitorInfo> {
+  let primary = app.primary_monitor().ok().flatten().map(|m| rect(&m));
+  app
+    .available_monitors()
+    .unwrap_or_default()
+    .iter()
+    .enumerate()
+    .map(|(index, m)| MonitorInfo {
+      index,
+      name: m.name().cloned(),
+      width: m.size().width,
+      height: m.size().height,
+      primary: Some(rect(m)) == primary,
+    })
+    .collect()
+}
+
+#[cfg(all(target_os = "windows", feature = "windows-monitor"))]
+mod platform {
+  use super::Rect;
+  use tauri::AppHandle;
+  use windows::Win32::Foundation::{POINT, RECT};
+  use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromPoint, MonitorFromWindow, HMONITOR, MONITORINFO, MONITOR_DEFAULTTONEAREST, MONITOR_DEFAULTTONULL};
+  use windows::Win32::UI::WindowsAndMessaging::{GetCursorPos, GetForegroundWindow};
+
+  pub const PROBE_RUNS_HELPER: bool = false;
+
+  /// Work area (the monitor minus the taskbar) of `hmon`
+  fn work_area(hmon: HMONITOR) -> Option<Rect> {
+    if hmon.is_invalid() {
+      return None;
+    }
+    let mut info = MONITORINFO { cbSize: std::mem::size_of::<MONITORINFO>() as u32, ..Default::default() };
+    if !unsafe { GetMonitorInfoW(hmon, &mut info) }.as_bool() {
+      return None;
+    }
+    let RECT { left, top, right, bottom } = info.rcWork;
+    Some((left, top, (right - left) as u32, (bottom - top) as u32))
+  }
+
+  pub fn cursor_area() -> Option<Rect> {
+    let mut pt = POINT { x: 0, y: 0 };
+    unsafe { GetCursorPos(&mut pt) }.ok()?;
+    work_area(unsafe { MonitorFromPoint(pt, MONITOR_DEFAULTTONEAREST) })
+  }
+
+  pub fn focused_window_area(_app: &AppHandle) -> Option<Rect> {
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd.0.is_null() {
+      return None;
+    }
+    work_area(unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONULL) })
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::{rect, Rect};
+  use std::process::Command;
+  use tauri::AppHandle;
+
+  pub const PROBE_RUNS_HELPER: bool = true;
+
+  pub fn cursor_area() -> Option<Rect> {
+    None
+  }
+
+  /// Monitor of the frontmost app's front window. System Events reports the window in points
+  /// from the top left of the main screen, so monitors are compared in logical coordinates.
+  pub fn focused_window_area(app: &AppHandle) -> Option<Rect> {
+    let script = "tell application \"System Events\" to get {position, size} of front window of (first application process whose frontmost is true)";
+    let out = Command::new("osascript").arg("-e").arg(script).output().ok()?;
+    if !out.status.success() {
+      return None;
+    }
+    let raw = String::from_utf8_lossy(&out.stdout);
+    let nums: Vec<f64> = raw.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+    let [x, y, w, h] = nums[..] else { return None };
+    let (cx, cy) = (x + w / 2.0, y + h / 2.0);
+    app.available_monitors().ok()?.iter().find_map(|m| {
+      let scale = m.scale_factor();
+      let (left, top) = (m.position().x as f64 / scale, m.position().y as f64 / scale);
+      let (width, height) = (m.size().width as f64 / scale, m.size().height as f64 / scale);
+      (cx >= left && cx < left + width && cy >= top && cy < top + height).then(|| rect(m))
+    })
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+  use super::{monitor_at, Rect};
+  use crate::hud_event::{emit_hud_event, HudEvent};
+  use std::process::Command;
+  use std::sync::Once;
+  use tauri::AppHandle;
+  use tracing::warn;
+
+  pub const PROBE_RUNS_HELPER: bool = true;
+
+  static WAYLAND_WARNED: Once = Once::new();
+
+  pub fn cursor_area() -> Option<Rect> {
+    None
+  }
+
+  /// Monitor of the active X11 window (XWayland windows only on Wayland)
+  pub fn focused_window_area(app: &AppHandle) -> Option<Rect> {
+    let out = Command::new("xdotool").args(["getactivewindow", "getwindowgeometry", "--shell"]).output().ok().filter(|o| o.status.success());
+    let Some(out) = out else {
+      if crate::wayland_input::is_wayland_session() {
+        WAYLAND_WARNED.call_once(|| {
+          warn!("Wayland doesn't expose the focused window; placing the HUD by the cursor instead");
+          emit_hud_event(app, HudEvent::badge("Wayland hides the focused window; the HUD follows the mouse instead"));
+        });
+      }
+      return None;
+    };
+    // Lines like "X=1920", "Y=40", "WIDTH=1280", "HEIGHT=720"
+    let raw = String::from_utf8_lossy(&out.stdout);
+    let value = |key: &str| raw.lines().find_map(|l| l.strip_prefix(key)?.strip_prefix('=')?.trim().parse::<f64>().ok());
+    let (x, y, w, h) = (value("X")?, value("Y")?, value("WIDTH")?, value("HEIGHT")?);
+    monitor_at(app, x + w / 2.0, y + h / 2.0)
+  }
+}
+
+#[cfg(not(any(all(target_os = "windows", feature = "windows-monitor"), target_os = "macos", target_os = "linux")))]
+mod platform {
+  use super::Rect;
+  use tauri::AppHandle;
+
+  pub const PROBE_RUNS_HELPER: bool = false;
+
+  pub fn cursor_area() -> Option<Rect> {
+    None
+  }
+
+  pub fn focused_window_area(_app: &AppHandle) -> Option<Rect> {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_is_valid_policy() {
+    for policy in ["primary", "monitor-with-cursor", "monitor-of-focused-window", "0", "3"] {
+      assert!(is_valid_policy(policy), "{}", policy);
+    }
+    for policy in ["", "cursor", "Primary", "-1", "1.5", " 1", "monitor-2"] {
+      assert!(!is_valid_policy(policy), "{}", policy);
+    }
+  }
+}
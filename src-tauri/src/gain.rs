@@ -0,0 +1,185 @@
+// Input level checks and the software gain stage for audio relayed to the backend. While the user
+// speaks, chunks are checked for clipping and for speech that stays very quiet; either one, once
+// it persists, becomes a warning that says what to change. With auto-gain on, the relayed audio is
+// scaled toward a target speech level, never so far that it clips.
+
+use crate::vad::Frame;
+
+/// Samples at or above this magnitude count as clipped
+const CLIP_SAMPLE: i32 = 32_000;
+/// A chunk with more clipped samples than this (1%) is clipping
+const CLIP_RATIO: f32 = 0.01;
+/// Speech frames in clipping chunks before warning (500ms)
+const CLIPPED_FRAMES_WARN: u32 = 10;
+/// Speech averaging below this is too quiet to transcribe well
+const QUIET_DB: f32 = -40.0;
+/// Quiet speech frames before warning (2s)
+const QUIET_FRAMES_WARN: u32 = 40;
+/// Speech level auto-gain aims for
+const TARGET_DB: f32 = -20.0;
+const MIN_GAIN: f32 = 0.25;
+const MAX_GAIN: f32 = 8.0;
+/// Share of the distance to the target covered per chunk, so the gain doesn't pump
+const GAIN_STEP: f32 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelWarning {
+    Clipping,
+    TooQuiet,
+}
+
+impl LevelWarning {
+    pub fn code(self) -> &'static str {
+        match self {
+            LevelWarning::Clipping => "clipping",
+            LevelWarning::TooQuiet => "too_quiet",
+        }
+    }
+
+    pub fn message(self, auto_gain: bool) -> &'static str {
+        match (self, auto_gain) {
+            (LevelWarning::Clipping, _) => "Your mic is clipping: lower its input gain in the system sound settings",
+            (LevelWarning::TooQuiet, false) => "Your mic is very quiet: raise its input gain or turn on auto-gain",
+            (LevelWarning::TooQuiet, true) => "Your mic is very quiet even with auto-gain: raise its input gain or move closer",
+        }
+    }
+}
+
+fn samples(pcm: &[u8]) -> impl Iterator<Item = i16> + '_ {
+    pcm.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]))
+}
+
+/// Scale little-endian 16-bit PCM, saturating at full scale
+pub fn apply_gain(pcm: &[u8], gain: f32) -> Vec<u8> {
+    samples(pcm)
+        .flat_map(|s| ((s as f32 * gain).round().clamp(i16::MIN as f32, i16::MAX as f32) as i16).to_le_bytes())
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct LevelCheck {
+    auto_gain: bool,
+    gain: f32,
+    clipped_frames: u32,
+    quiet_frames: u32,
+    warned_clipping: bool,
+    warned_quiet: bool,
+}
+
+impl LevelCheck {
+    pub fn new(auto_gain: bool) -> Self {
+        LevelCheck { auto_gain, gain: 1.0, clipped_frames: 0, quiet_frames: 0, warned_clipping: false, warned_quiet: false }
+    }
+
+    pub fn is_auto(&self) -> bool {
+        self.auto_gain
+    }
+
+    /// Gain to apply to the relayed audio (1.0 with auto-gain off)
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Check a chunk of raw input and the VAD frames it produced; returns a warning the first time
+    /// a problem has lasted long enough
+    pub fn observe(&mut self, pcm: &[u8], frames: &[Frame]) -> Option<LevelWarning> {
+        let speech: Vec<f32> = frames.iter().filter(|f| f.speech).map(|f| f.db).collect();
+        if speech.is_empty() {
+            return None;
+        }
+        let count = pcm.len() / 2;
+        let peak = samples(pcm).map(|s| (s as i32).abs()).max().unwrap_or(0);
+        let clipped = samples(pcm).filter(|s| (*s as i32).abs() >= CLIP_SAMPLE).count();
+        let mean_db = speech.iter().sum::<f32>() / speech.len() as f32;
+
+        if count > 0 && clipped as f32 / count as f32 > CLIP_RATIO {
+            self.clipped_frames += speech.len() as u32;
+        }
+        if mean_db < QUIET_DB {
+            self.quiet_frames += speech.len() as u32;
+        }
+        if self.auto_gain {
+            let error_db = TARGET_DB - (mean_db + 20.0 * self.gain.log10());
+            let mut gain = (self.gain * 10f32.powf(error_db * GAIN_STEP / 20.0)).clamp(MIN_GAIN, MAX_GAIN);
+            // Never push the loudest sample into clipping
+            if peak > 0 {
+                gain = gain.min(CLIP_SAMPLE as f32 / peak as f32).max(MIN_GAIN);
+            }
+            self.gain = gain;
+        }
+
+        if !self.warned_clipping && self.clipped_frames >= CLIPPED_FRAMES_WARN {
+            self.warned_clipping = true;
+            return Some(LevelWarning::Clipping);
+        }
+        // Auto-gain fixes quiet input until it runs out of headroom
+        let can_boost = self.auto_gain && self.gain < MAX_GAIN;
+        if !self.warned_quiet && !can_boost && self.quiet_frames >= QUIET_FRAMES_WARN {
+            self.warned_quiet = true;
+            return Some(LevelWarning::TooQuiet);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vad::rms_db;
+
+    fn tone(amplitude: i16, ms: usize) -> Vec<u8> {
+        (0..16 * ms).flat_map(|i| (if i % 2 == 0 { amplitude } else { -amplitude }).to_le_bytes()).collect()
+    }
+
+    fn speech_frames(pcm: &[u8]) -> Vec<Frame> {
+        pcm.chunks(1600).map(|c| Frame { db: rms_db(c), speech: true }).collect()
+    }
+
+    #[test]
+    fn test_apply_gain_saturates() {
+        let out = apply_gain(&tone(20_000, 1), 2.0);
+        assert_eq!(i16::from_le_bytes([out[0], out[1]]), i16::MAX);
+        assert_eq!(i16::from_le_bytes([out[2], out[3]]), i16::MIN);
+        assert_eq!(apply_gain(&tone(100, 1), 0.5)[..2], 50i16.to_le_bytes());
+    }
+
+    #[test]
+    fn test_clipping_warns_once() {
+        let mut check = LevelCheck::new(false);
+        let chunk = tone(i16::MAX, 250);
+        let warnings: Vec<_> = (0..4).filter_map(|_| check.observe(&chunk, &speech_frames(&chunk))).collect();
+        assert_eq!(warnings, vec![LevelWarning::Clipping]);
+        // Silence is never judged
+        assert_eq!(LevelCheck::new(false).observe(&chunk, &[Frame { db: 0.0, speech: false }]), None);
+    }
+
+    #[test]
+    fn test_quiet_input() {
+        let chunk = tone(200, 250); // about -44 dBFS
+        let mut check = LevelCheck::new(false);
+        let warnings: Vec<_> = (0..10).filter_map(|_| check.observe(&chunk, &speech_frames(&chunk))).collect();
+        assert_eq!(warnings, vec![LevelWarning::TooQuiet]);
+
+        // Auto-gain boosts it instead of warning, until it hits the maximum
+        let mut check = LevelCheck::new(true);
+        for _ in 0..10 {
+            assert_eq!(check.observe(&chunk, &speech_frames(&chunk)), None);
+        }
+        assert!(check.gain() > 2.0);
+        let faint = tone(5, 250);
+        assert_eq!((0..200).find_map(|_| check.observe(&faint, &speech_frames(&faint))), Some(LevelWarning::TooQuiet));
+        assert_eq!(check.gain(), MAX_GAIN);
+    }
+
+    #[test]
+    fn test_auto_gain_avoids_clipping() {
+        let mut check = LevelCheck::new(true);
+        // Quiet on average but with one loud peak
+        let mut chunk = tone(300, 250);
+        chunk[..2].copy_from_slice(&16_000i16.to_le_bytes());
+        for _ in 0..50 {
+            check.observe(&chunk, &speech_frames(&chunk));
+        }
+        assert!(check.gain() <= 2.0 && check.gain() > 1.0);
+    }
+}
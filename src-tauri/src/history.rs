@@ -0,0 +1,264 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::subtitle;
+use crate::transcript::{Transcript, Word};
+use crate::usage::SessionMetrics;
+
+// Dictation history kept in its own store file so prefs.json stays small.
+// Entries are appended newest-last and capped at MAX_ENTRIES. Entries keep the provider's word
+// timing when there was any, so they can be exported as subtitles. Every change is reported as a
+// `history-updated` event so an open history window stays current without polling.
+
+pub const EVENT_NAME: &str = "history-updated";
+const WINDOW_LABEL: &str = "history";
+const HISTORY_STORE: &str = "history.json";
+const K_ENTRIES: &str = "entries";
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+  pub id: u64,
+  /// Unix timestamp (seconds)
+  pub created_at: u64,
+  /// Profile key of the app the text was inserted into
+  #[serde(default)]
+  pub app: Option<String>,
+  pub text: String,
+  #[serde(default)]
+  pub pasted: bool,
+  /// Spoken words with their timing (before refinement); empty when the provider sent none
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub words: Vec<Word>,
+  /// Duration, speaking rate and pipeline timing, for dictations (not takes or files)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub metrics: Option<SessionMetrics>,
+}
+
+/// A history entry without its word timing, for lists
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct HistoryItem {
+  pub id: u64,
+  /// Unix timestamp (seconds)
+  pub created_at: u64,
+  pub app: Option<String>,
+  pub text: String,
+  pub pasted: bool,
+  /// Word timing was kept, so subtitles follow the actual speech
+  pub timed: bool,
+  /// Estimated provider cost in USD, for dictations recorded with one
+  pub cost_usd: Option<f64>,
+}
+
+impl From<&HistoryEntry> for HistoryItem {
+  fn from(e: &HistoryEntry) -> Self {
+    let cost_usd = e.metrics.as_ref().and_then(|m| m.cost).map(|c| c.total_usd());
+    HistoryItem { id: e.id, created_at: e.created_at, app: e.app.clone(), text: e.text.clone(), pasted: e.pasted, timed: !e.words.is_empty(), cost_usd }
+  }
+}
+
+/// What changed in the history, as sent with EVENT_NAME
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HistoryUpdate {
+  Added { item: HistoryItem },
+  /// Deleted by hand or purged by maintenance
+  Removed { ids: Vec<u64> },
+}
+
+fn notify(app: &AppHandle, update: HistoryUpdate) {
+  if let Err(e) = app.emit(EVENT_NAME, &update) {
+    warn!("Failed to emit history update: {}", e);
+  }
+}
+
+pub fn now_secs() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+pub fn now_millis() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+pub fn load_entries(app: &AppHandle) -> Vec<HistoryEntry> {
+  let Ok(store) = app.store(HISTORY_STORE) else { return Vec::new() };
+  store.get(K_ENTRIES).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+fn save_entries(app: &AppHandle, entries: &[HistoryEntry]) -> anyhow::Result<()> {
+  let store = app.store(HISTORY_STORE)?;
+  store.set(K_ENTRIES, serde_json::to_value(entries)?);
+  store.save()?;
+  Ok(())
+}
+
+/// Append a finished dictation and return the stored entry
+pub fn record(app: &AppHandle, text: &str, app_key: Option<String>, pasted: bool) -> anyhow::Result<HistoryEntry> {
+  record_entry(app, new_entry(text.to_string(), app_key, pasted))
+}
+
+/// Append a finished dictation with the word timing of its transcript
+pub fn record_transcript(
+  app: &AppHandle,
+  transcript: &Transcript,
+  app_key: Option<String>,
+  pasted: bool,
+  metrics: Option<SessionMetrics>,
+) -> anyhow::Result<HistoryEntry> {
+  let words = transcript.words();
+  // Timing estimated from the speaking rate can be rebuilt from the text at export
+  let words = if words.iter().any(|w| !w.estimated) { words } else { Vec::new() };
+  record_entry(app, HistoryEntry { words, metrics, ..new_entry(transcript.output_text(), app_key, pasted) })
+}
+
+fn new_entry(text: String, app_key: Option<String>, pasted: bool) -> HistoryEntry {
+  HistoryEntry { id: now_millis(), created_at: now_secs(), app: app_key, text, pasted, words: Vec::new(), metrics: None }
+}
+
+fn record_entry(app: &AppHandle, entry: HistoryEntry) -> anyhow::Result<HistoryEntry> {
+  let mut entries = load_entries(app);
+  entries.push(entry.clone());
+  if entries.len() > MAX_ENTRIES {
+    let excess = entries.len() - MAX_ENTRIES;
+    entries.drain(..excess);
+  }
+  save_entries(app, &entries)?;
+  notify(app, HistoryUpdate::Added { item: HistoryItem::from(&entry) });
+  Ok(entry)
+}
+
+/// Drop entries created before `cutoff` (Unix seconds); returns how many were removed
+pub fn purge_before(app: &AppHandle, cutoff: u64) -> anyhow::Result<usize> {
+  let mut entries = load_entries(app);
+  let ids: Vec<u64> = entries.iter().filter(|e| e.created_at < cutoff).map(|e| e.id).collect();
+  if !ids.is_empty() {
+    entries.retain(|e| e.created_at >= cutoff);
+    save_entries(app, &entries)?;
+    notify(app, HistoryUpdate::Removed { ids: ids.clone() });
+  }
+  Ok(ids.len())
+}
+
+/// Drop the entries with these ids; returns how many were removed
+pub fn delete(app: &AppHandle, ids: &[u64]) -> anyhow::Result<usize> {
+  let mut entries = load_entries(app);
+  let removed: Vec<u64> = entries.iter().filter(|e| ids.contains(&e.id)).map(|e| e.id).collect();
+  if !removed.is_empty() {
+    entries.retain(|e| !ids.contains(&e.id));
+    save_entries(app, &entries)?;
+    info!("Deleted {} history entries", removed.len());
+    notify(app, HistoryUpdate::Removed { ids: removed.clone() });
+  }
+  Ok(removed.len())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppUsage {
+  pub app: String,
+  pub sessions: u32,
+  pub words: u64,
+  /// Unix timestamp (seconds) of the most recent dictation into this app
+  pub last_used: u64,
+  pub has_profile: bool,
+}
+
+/// Summarize which apps are dictated into most, most-used first
+pub fn app_usage(entries: &[HistoryEntry], profiled: &[String]) -> Vec<AppUsage> {
+  let mut by_app: HashMap<String, AppUsage> = HashMap::new();
+  for e in entries {
+    let Some(app) = &e.app else { continue };
+    let usage = by_app.entry(app.clone()).or_insert_with(|| AppUsage {
+      app: app.clone(),
+      sessions: 0,
+      words: 0,
+      last_used: 0,
+      has_profile: profiled.contains(app),
+    });
+    usage.sessions += 1;
+    usage.words += e.text.split_whitespace().count() as u64;
+    usage.last_used = usage.last_used.max(e.created_at);
+  }
+  let mut list: Vec<AppUsage> = by_app.into_values().collect();
+  list.sort_by(|a, b| b.sessions.cmp(&a.sessions).then(b.last_used.cmp(&a.last_used)));
+  list
+}
+
+pub fn list(app: &AppHandle, limit: usize) -> Vec<HistoryItem> {
+  search(app, "", limit)
+}
+
+/// Newest entries whose text or app contains every word of `query`, ignoring case; an empty
+/// query matches everything
+pub fn search(app: &AppHandle, query: &str, limit: usize) -> Vec<HistoryItem> {
+  let terms: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+  load_entries(app)
+    .iter()
+    .rev()
+    .filter(|e| {
+      let haystack = format!("{} {}", e.text, e.app.as_deref().unwrap_or("")).to_lowercase();
+      terms.iter().all(|t| haystack.contains(t.as_str()))
+    })
+    .take(limit)
+    .map(HistoryItem::from)
+    .collect()
+}
+
+/// Show the history window, creating it on first use
+pub fn open_window(app: &AppHandle) -> Result<(), String> {
+  if let Some(win) = app.get_webview_window(WINDOW_LABEL) {
+    let _ = win.show();
+    let _ = win.set_focus();
+    return Ok(());
+  }
+  WebviewWindowBuilder::new(app, WINDOW_LABEL, WebviewUrl::App("index.html#/history".into()))
+    .title("Dictation History")
+    .inner_size(720.0, 560.0)
+    .center()
+    .build()
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
+
+/// Export formats for `export`, by file extension
+pub const EXPORT_FORMATS: &[&str] = &["txt", "md", "json", "srt", "vtt"];
+
+/// An entry in one of EXPORT_FORMATS. Subtitles use the kept word timing, or timing estimated
+/// from the text when there is none; they show the words as spoken, before refinement.
+pub fn render(entry: &HistoryEntry, format: &str) -> Result<String, String> {
+  let words = || if entry.words.is_empty() { Transcript::from_text(&entry.text, "").words() } else { entry.words.clone() };
+  Ok(match format {
+    "txt" => format!("{}\n", entry.text.trim()),
+    "md" => {
+      let title: Vec<&str> = entry.text.split_whitespace().take(8).collect();
+      let source = entry.app.as_deref().map(|a| format!("\n\n_Dictated into {}_", a)).unwrap_or_default();
+      format!("# {}\n\n{}{}\n", title.join(" "), entry.text.trim(), source)
+    }
+    "json" => serde_json::to_string_pretty(entry).map_err(|e| e.to_string())?,
+    "srt" => subtitle::srt(&subtitle::cues(&words())),
+    "vtt" => subtitle::vtt(&subtitle::cues(&words())),
+    other => return Err(format!("Unknown export format: {} (use {})", other, EXPORT_FORMATS.join(", "))),
+  })
+}
+
+/// Write entry `id` to `path`, or to the Downloads folder; returns the file path
+pub fn export(app: &AppHandle, id: u64, format: &str, path: Option<&Path>) -> Result<PathBuf, String> {
+  let entries = load_entries(app);
+  let entry = entries.iter().find(|e| e.id == id).ok_or_else(|| format!("No history entry {}", id))?;
+  let contents = render(entry, format)?;
+  let path = match path {
+    Some(p) => p.to_path_buf(),
+    None => {
+      let dir = app.path().download_dir().or_else(|_| app.path().home_dir()).map_err(|e| e.to_string())?;
+      dir.join(format!("dictation-{}.{}", id, format))
+    }
+  };
+  std::fs::write(&path, contents).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+  info!("Exported history entry {} as {} to {}", id, format, path.display());
+  Ok(path)
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use tauri::AppHandle;
 use tauri_plugin_store::StoreExt;
 // shortcut registration is handled on the frontend via the JS plugin
@@ -18,3 +19,66 @@ pub fn get_hotkey(app: &AppHandle) -> String {
   let store = match app.store("prefs.json") { Ok(s) => s, Err(_) => return if cfg!(target_os = "macos") {"Control+Shift+Alt+H".into()} else {"Ctrl+Shift+Alt+H".into()} };
   store.get("hotkey").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_else(|| if cfg!(target_os = "macos") {"Control+Shift+Alt+H".into()} else {"Ctrl+Shift+Alt+H".into()})
 }
+
+const DEFAULT_CODE_MODE_HOTKEY: &str = if cfg!(target_os = "macos") { "Control+Shift+Alt+C" } else { "Ctrl+Shift+Alt+C" };
+
+pub fn set_code_mode_hotkey(app: &AppHandle, combo: &str) -> Result<(), String> {
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?; store.set("code_mode_hotkey", combo); store.save().map_err(|e| e.to_string())?; Ok(())
+}
+
+pub fn get_code_mode_hotkey(app: &AppHandle) -> String {
+  let store = match app.store("prefs.json") { Ok(s) => s, Err(_) => return DEFAULT_CODE_MODE_HOTKEY.into() };
+  store.get("code_mode_hotkey").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_else(|| DEFAULT_CODE_MODE_HOTKEY.into())
+}
+
+const DEFAULT_RETAKE_HOTKEY: &str = if cfg!(target_os = "macos") { "Control+Shift+Alt+R" } else { "Ctrl+Shift+Alt+R" };
+
+pub fn set_retake_hotkey(app: &AppHandle, combo: &str) -> Result<(), String> {
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?; store.set("retake_hotkey", combo); store.save().map_err(|e| e.to_string())?; Ok(())
+}
+
+pub fn get_retake_hotkey(app: &AppHandle) -> String {
+  let store = match app.store("prefs.json") { Ok(s) => s, Err(_) => return DEFAULT_RETAKE_HOTKEY.into() };
+  store.get("retake_hotkey").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_else(|| DEFAULT_RETAKE_HOTKEY.into())
+}
+
+const DEFAULT_PAUSE_HOTKEY: &str = if cfg!(target_os = "macos") { "Control+Shift+Alt+P" } else { "Ctrl+Shift+Alt+P" };
+
+pub fn set_pause_hotkey(app: &AppHandle, combo: &str) -> Result<(), String> {
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?; store.set("pause_hotkey", combo); store.save().map_err(|e| e.to_string())?; Ok(())
+}
+
+pub fn get_pause_hotkey(app: &AppHandle) -> String {
+  let store = match app.store("prefs.json") { Ok(s) => s, Err(_) => return DEFAULT_PAUSE_HOTKEY.into() };
+  store.get("pause_hotkey").and_then(|v| v.as_str().map(|s| s.to_string())).unwrap_or_else(|| DEFAULT_PAUSE_HOTKEY.into())
+}
+
+/// Default shortcut for a selection action ("summarize" -> Ctrl+Shift+Alt+S)
+fn default_selection_hotkey(action: &str) -> String {
+  let key = match action { "summarize" => "S", "formal" => "F", "grammar" => "G", _ => return String::new() };
+  if cfg!(target_os = "macos") { format!("Control+Shift+Alt+{}", key) } else { format!("Ctrl+Shift+Alt+{}", key) }
+}
+
+/// Shortcut per selection action; an empty string means the action has no shortcut
+pub fn get_selection_hotkeys(app: &AppHandle) -> BTreeMap<String, String> {
+  let saved: BTreeMap<String, String> = app
+    .store("prefs.json")
+    .ok()
+    .and_then(|s| s.get("selection_hotkeys"))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default();
+  crate::prompt::SELECTION_ACTIONS
+    .iter()
+    .map(|(id, _, _)| (id.to_string(), saved.get(*id).cloned().unwrap_or_else(|| default_selection_hotkey(id))))
+    .collect()
+}
+
+pub fn set_selection_hotkey(app: &AppHandle, action: &str, combo: &str) -> Result<(), String> {
+  let mut hotkeys = get_selection_hotkeys(app);
+  if !hotkeys.contains_key(action) { return Err(format!("Unknown selection action: {}", action)); }
+  hotkeys.insert(action.to_string(), combo.trim().to_string());
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set("selection_hotkeys", serde_json::to_value(&hotkeys).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  Ok(())
+}
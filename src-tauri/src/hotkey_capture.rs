@@ -0,0 +1,180 @@
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+use tokio::sync::oneshot;
+
+use crate::combo::{self, Modifier};
+
+/// Result of a guided capture (or validation) of a hotkey combo
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedHotkey {
+  /// Normalized combo, e.g. "Ctrl+Shift+Alt+H"
+  pub combo: String,
+  /// Whether the combo can be registered as a global shortcut right now
+  pub registrable: bool,
+  /// Why it can't be registered (or a note when Dictation HUD already uses it)
+  pub conflict: Option<String>,
+  /// Combos commonly reserved by the OS or apps
+  pub warnings: Vec<String>,
+}
+
+// Capture state: modifiers currently held, and where to send the finished combo.
+// The raw key listener only acts on events while a capture is in progress.
+struct Capture {
+  held: BTreeSet<Modifier>,
+  done: Option<oneshot::Sender<Option<String>>>,
+}
+
+static CAPTURE: Mutex<Option<Capture>> = Mutex::new(None);
+#[allow(dead_code)]
+static LISTEN_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+#[cfg(feature = "native-input")]
+mod listener {
+  use super::*;
+  use rdev::{EventType, Key};
+  use std::sync::Once;
+
+  static START: Once = Once::new();
+
+  /// rdev::listen never returns, so one listener thread serves every capture
+  pub fn ensure_started() {
+    START.call_once(|| {
+      std::thread::spawn(|| {
+        if let Err(e) = rdev::listen(on_event) {
+          tracing::warn!("Raw key listener failed: {:?}", e);
+          *LISTEN_ERROR.lock().unwrap() = Some(format!("{:?}", e));
+        }
+      });
+    });
+  }
+
+  fn modifier_of(key: Key) -> Option<Modifier> {
+    match key {
+      Key::ControlLeft | Key::ControlRight => Some(Modifier::Ctrl),
+      Key::ShiftLeft | Key::ShiftRight => Some(Modifier::Shift),
+      Key::Alt | Key::AltGr => Some(Modifier::Alt),
+      Key::MetaLeft | Key::MetaRight => Some(Modifier::Super),
+      _ => None,
+    }
+  }
+
+  /// Key name in the form the global-shortcut plugin parses
+  fn key_name(key: Key) -> Option<String> {
+    let debug = format!("{:?}", key);
+    let name = match key {
+      Key::Return | Key::KpReturn => "Enter".to_string(),
+      Key::UpArrow => "Up".to_string(),
+      Key::DownArrow => "Down".to_string(),
+      Key::LeftArrow => "Left".to_string(),
+      Key::RightArrow => "Right".to_string(),
+      Key::BackQuote => "Backquote".to_string(),
+      Key::SemiColon => "Semicolon".to_string(),
+      Key::BackSlash | Key::IntlBackslash => "Backslash".to_string(),
+      Key::LeftBracket => "BracketLeft".to_string(),
+      Key::RightBracket => "BracketRight".to_string(),
+      Key::Dot => "Period".to_string(),
+      Key::Unknown(_) | Key::Function => return None,
+      _ => {
+        if let Some(letter) = debug.strip_prefix("Key") {
+          letter.to_string()
+        } else if let Some(digit) = debug.strip_prefix("Num").filter(|d| d.len() == 1) {
+          digit.to_string()
+        } else {
+          debug
+        }
+      }
+    };
+    Some(name)
+  }
+
+  fn on_event(event: rdev::Event) {
+    let mut guard = CAPTURE.lock().unwrap();
+    let Some(cap) = guard.as_mut() else { return };
+    match event.event_type {
+      EventType::KeyPress(key) => {
+        if let Some(m) = modifier_of(key) {
+          cap.held.insert(m);
+          return;
+        }
+        // Escape on its own cancels the capture
+        let result = if key == Key::Escape && cap.held.is_empty() {
+          None
+        } else {
+          let Some(name) = key_name(key) else { return };
+          let mods: Vec<Modifier> = cap.held.iter().copied().collect();
+          Some(combo::format_combo(&mods, &name, cfg!(target_os = "macos")))
+        };
+        if let Some(done) = cap.done.take() {
+          let _ = done.send(result);
+        }
+        *guard = None;
+      }
+      EventType::KeyRelease(key) => {
+        if let Some(m) = modifier_of(key) {
+          cap.held.remove(&m);
+        }
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Listen for the next key combo pressed anywhere and return it normalized and validated
+#[cfg(feature = "native-input")]
+pub async fn capture(app: &AppHandle, timeout: Duration) -> Result<CapturedHotkey, String> {
+  if let Some(e) = LISTEN_ERROR.lock().unwrap().clone() {
+    return Err(format!("Key capture unavailable: {}", e));
+  }
+  listener::ensure_started();
+  let (tx, rx) = oneshot::channel();
+  *CAPTURE.lock().unwrap() = Some(Capture { held: BTreeSet::new(), done: Some(tx) });
+
+  let combo = match tokio::time::timeout(timeout, rx).await {
+    Ok(Ok(Some(combo))) => combo,
+    Ok(_) => return Err("Capture cancelled".into()),
+    Err(_) => {
+      *CAPTURE.lock().unwrap() = None;
+      return Err(match LISTEN_ERROR.lock().unwrap().clone() {
+        Some(e) => format!("Key capture unavailable: {}", e),
+        None => "No key combo pressed".into(),
+      });
+    }
+  };
+  tracing::info!("Captured hotkey: {}", combo);
+  Ok(validate(app, &combo))
+}
+
+#[cfg(not(feature = "native-input"))]
+pub async fn capture(_app: &AppHandle, _timeout: Duration) -> Result<CapturedHotkey, String> {
+  Err("Key capture needs the native-input feature".into())
+}
+
+/// Check that a combo parses and can be registered, and collect reserved-combo warnings
+pub fn validate(app: &AppHandle, combo: &str) -> CapturedHotkey {
+  let warnings = combo::reserved_warnings(combo, std::env::consts::OS);
+  let shortcut = match combo.parse::<Shortcut>() {
+    Ok(s) => s,
+    Err(e) => {
+      return CapturedHotkey { combo: combo.to_string(), registrable: false, conflict: Some(format!("Not a valid shortcut: {}", e)), warnings };
+    }
+  };
+
+  let gs = app.global_shortcut();
+  let (registrable, conflict) = if gs.is_registered(shortcut) {
+    (true, Some("Already used by Dictation HUD".to_string()))
+  } else {
+    // A trial registration is the only reliable way to see if another app holds the combo
+    match gs.register(shortcut) {
+      Ok(()) => {
+        let _ = gs.unregister(shortcut);
+        (true, None)
+      }
+      Err(e) => (false, Some(format!("Taken by another application ({})", e))),
+    }
+  };
+  CapturedHotkey { combo: combo.to_string(), registrable, conflict, warnings }
+}
@@ -0,0 +1,173 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{info, warn};
+
+use crate::proxy::{self, ProxyPrefs, ProxyUrl, Scheme};
+
+// One HTTP client for all provider calls, so repeat dictations reuse pooled keep-alive
+// connections instead of paying DNS and a TLS handshake every time. Timeouts differ per
+// provider, so callers set them on each request. The client is rebuilt when the proxy prefs
+// change; the STT WebSocket gets its proxied TCP connection from `tunnel`.
+
+/// How long an idle pooled connection is kept around
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+static CLIENT: Mutex<Option<reqwest::Client>> = Mutex::new(None);
+/// Proxy for WebSocket connections: the address and the hosts that skip it
+static WS_PROXY: Mutex<Option<(ProxyUrl, Vec<String>)>> = Mutex::new(None);
+
+fn build(prefs: &ProxyPrefs, password: Option<&str>) -> Result<reqwest::Client, String> {
+  let builder = reqwest::Client::builder().pool_idle_timeout(POOL_IDLE_TIMEOUT);
+  let builder = match prefs.mode.as_str() {
+    "off" => builder.no_proxy(),
+    "manual" => {
+      let mut p = reqwest::Proxy::all(prefs.url.trim()).map_err(|e| e.to_string())?;
+      if !prefs.username.is_empty() {
+        p = p.basic_auth(&prefs.username, password.unwrap_or(""));
+      }
+      p = p.no_proxy(reqwest::NoProxy::from_string(&prefs.bypass.join(",")));
+      builder.proxy(p)
+    }
+    // reqwest follows the OS proxy settings and the *_PROXY variables by default
+    _ => builder,
+  };
+  builder.build().map_err(|e| e.to_string())
+}
+
+/// The proxy in the OS network settings, which reqwest reads for HTTP requests when the
+/// environment names none
+#[cfg(target_os = "macos")]
+fn os_proxy() -> Option<(String, Vec<String>)> {
+  let out = std::process::Command::new("scutil").arg("--proxy").output().ok()?;
+  proxy::from_scutil(&String::from_utf8_lossy(&out.stdout))
+}
+
+#[cfg(target_os = "windows")]
+fn os_proxy() -> Option<(String, Vec<String>)> {
+  use std::os::windows::process::CommandExt;
+  const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+  let out = std::process::Command::new("reg")
+    .args(["query", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Internet Settings"])
+    .creation_flags(CREATE_NO_WINDOW)
+    .output()
+    .ok()?;
+  let settings = String::from_utf8_lossy(&out.stdout);
+  if proxy::reg_value(&settings, "ProxyEnable")? != "0x1" {
+    return None;
+  }
+  proxy::from_windows(&proxy::reg_value(&settings, "ProxyServer")?, &proxy::reg_value(&settings, "ProxyOverride").unwrap_or_default())
+}
+
+/// Elsewhere the environment is the system setting
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_proxy() -> Option<(String, Vec<String>)> {
+  None
+}
+
+/// The WebSocket proxy for `prefs`, None to connect directly
+fn ws_proxy(prefs: &ProxyPrefs, password: Option<&str>) -> Option<(ProxyUrl, Vec<String>)> {
+  let (url, bypass) = match prefs.mode.as_str() {
+    "manual" => (prefs.url.clone(), prefs.bypass.clone()),
+    "system" => proxy::from_env(|name| std::env::var(name).ok()).or_else(os_proxy)?,
+    _ => return None,
+  };
+  let mut url = proxy::parse_url(&url).map_err(|e| warn!("Ignoring proxy for WebSockets: {}", e)).ok()?;
+  if !prefs.username.is_empty() && prefs.mode == "manual" {
+    url.auth = Some((prefs.username.clone(), password.unwrap_or("").to_string()));
+  }
+  Some((url, bypass))
+}
+
+/// Apply proxy prefs to provider requests and WebSocket connections
+pub fn configure(prefs: &ProxyPrefs, password: Option<&str>) -> Result<(), String> {
+  let client = build(prefs, password)?;
+  *CLIENT.lock().unwrap() = Some(client);
+  *WS_PROXY.lock().unwrap() = ws_proxy(prefs, password);
+  info!("Proxy mode: {}{}", prefs.mode, if prefs.mode == "manual" { format!(" ({})", prefs.url.trim()) } else { String::new() });
+  Ok(())
+}
+
+pub fn shared() -> reqwest::Client {
+  CLIENT
+    .lock()
+    .unwrap()
+    .get_or_insert_with(|| build(&ProxyPrefs::default(), None).unwrap_or_else(|_| reqwest::Client::new()))
+    .clone()
+}
+
+/// Read a CONNECT response up to the blank line that ends its head
+async fn read_head(stream: &mut TcpStream) -> Result<String, String> {
+  let mut head = Vec::new();
+  let mut byte = [0u8; 1];
+  while !head.ends_with(b"\r\n\r\n") {
+    if head.len() > 8192 || stream.read(&mut byte).await.map_err(|e| e.to_string())? == 0 {
+      return Err("Proxy closed the connection".into());
+    }
+    head.push(byte[0]);
+  }
+  Ok(String::from_utf8_lossy(&head).into_owned())
+}
+
+async fn socks5_handshake(stream: &mut TcpStream, proxy: &ProxyUrl, host: &str, port: u16) -> Result<(), String> {
+  let io = |e: std::io::Error| e.to_string();
+  stream.write_all(&proxy::socks5_greeting(proxy.auth.is_some())).await.map_err(io)?;
+  let mut choice = [0u8; 2];
+  stream.read_exact(&mut choice).await.map_err(io)?;
+  match choice[1] {
+    0 => {}
+    2 => {
+      let (user, pass) = proxy.auth.as_ref().ok_or("SOCKS5 proxy wants a username and password")?;
+      stream.write_all(&proxy::socks5_auth(user, pass)).await.map_err(io)?;
+      let mut status = [0u8; 2];
+      stream.read_exact(&mut status).await.map_err(io)?;
+      if status[1] != 0 {
+        return Err("SOCKS5 proxy authentication failed".into());
+      }
+    }
+    _ => return Err("SOCKS5 proxy accepts none of our authentication methods".into()),
+  }
+  stream.write_all(&proxy::socks5_connect(host, port)).await.map_err(io)?;
+  let mut reply = [0u8; 4];
+  stream.read_exact(&mut reply).await.map_err(io)?;
+  if reply[1] != 0 {
+    return Err(proxy::socks5_error(reply[1]));
+  }
+  // Skip the bound address the reply ends with
+  let skip = match reply[3] {
+    1 => 4,
+    4 => 16,
+    3 => {
+      let mut len = [0u8; 1];
+      stream.read_exact(&mut len).await.map_err(io)?;
+      len[0] as usize
+    }
+    _ => return Err("SOCKS5 proxy sent a bad reply".into()),
+  };
+  let mut rest = vec![0u8; skip + 2];
+  stream.read_exact(&mut rest).await.map_err(io)?;
+  Ok(())
+}
+
+/// A TCP connection to `host:port` through the configured proxy, ready for TLS; None when the
+/// connection should go direct
+pub async fn tunnel(host: &str, port: u16) -> Result<Option<TcpStream>, String> {
+  let Some((proxy, bypass)) = WS_PROXY.lock().unwrap().clone() else { return Ok(None) };
+  if proxy::bypasses(&bypass, host) {
+    return Ok(None);
+  }
+  let mut stream = TcpStream::connect((proxy.host.as_str(), proxy.port))
+    .await
+    .map_err(|e| format!("Could not reach the proxy {}:{}: {}", proxy.host, proxy.port, e))?;
+  match proxy.scheme {
+    Scheme::Http => {
+      let auth = proxy.auth.as_ref().map(|(u, p)| (u.as_str(), p.as_str()));
+      stream.write_all(proxy::connect_request(host, port, auth).as_bytes()).await.map_err(|e| e.to_string())?;
+      proxy::connect_succeeded(&read_head(&mut stream).await?)?;
+    }
+    Scheme::Https => return Err(proxy::HTTPS_UNSUPPORTED.into()),
+    Scheme::Socks5 => socks5_handshake(&mut stream, &proxy, host, port).await?,
+  }
+  Ok(Some(stream))
+}
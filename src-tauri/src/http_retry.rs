@@ -0,0 +1,148 @@
+// Retries for provider HTTP calls: rate limits (429), server errors (5xx), timeouts and failed
+// connections are retried with exponential backoff and jitter; everything else (bad key, bad
+// request) fails at once. The policy comes from behavior prefs and applies process-wide.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tracing::info;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Policy {
+    /// Total tries, including the first (1 disables retrying)
+    pub attempts: u32,
+    /// Delay before the first retry; doubled for each further one
+    pub backoff: Duration,
+    /// Upper bound for a single delay, also for a server's Retry-After
+    pub max_backoff: Duration,
+    /// Random spread around each delay, in percent
+    pub jitter_pct: u32,
+}
+
+pub const DEFAULT_POLICY: Policy = Policy {
+    attempts: 3,
+    backoff: Duration::from_millis(500),
+    max_backoff: Duration::from_secs(8),
+    jitter_pct: 20,
+};
+
+static POLICY: Mutex<Policy> = Mutex::new(DEFAULT_POLICY);
+
+pub fn configure(policy: Policy) {
+    *POLICY.lock().unwrap() = policy;
+}
+
+pub fn policy() -> Policy {
+    *POLICY.lock().unwrap()
+}
+
+/// A retry about to happen, as reported to the HUD
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Retry {
+    pub provider: String,
+    /// The attempt that is about to start (2 for the first retry)
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    /// "HTTP 429", "timeout", ...
+    pub reason: String,
+}
+
+/// Whether a response status is worth another try
+pub fn is_retryable_status(status: u16) -> bool {
+    status == 429 || (500..600).contains(&status)
+}
+
+fn is_retryable_error(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_connect()
+}
+
+/// Delay before retry number `retry` (1-based). `noise` in 0..=1 picks the point in the jitter
+/// window; `retry_after` is what the server asked for, if anything.
+pub fn delay(policy: &Policy, retry: u32, noise: f64, retry_after: Option<Duration>) -> Duration {
+    if let Some(wait) = retry_after {
+        return wait.min(policy.max_backoff);
+    }
+    let base = policy.backoff.saturating_mul(1 << retry.saturating_sub(1).min(16)).min(policy.max_backoff);
+    let spread = base.as_secs_f64() * policy.jitter_pct.min(100) as f64 / 100.0;
+    let jittered = base.as_secs_f64() + spread * (2.0 * noise.clamp(0.0, 1.0) - 1.0);
+    Duration::from_secs_f64(jittered.max(0.0)).min(policy.max_backoff)
+}
+
+fn noise() -> f64 {
+    let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.subsec_nanos()).unwrap_or(0);
+    nanos as f64 / 1_000_000_000.0
+}
+
+fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let secs = resp.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim().parse::<u64>().ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+/// Send the request made by `build` (called again for every attempt), retrying transient
+/// failures under the current policy. `on_retry` runs before each wait.
+pub async fn send(
+    provider: &str,
+    build: impl Fn() -> reqwest::RequestBuilder,
+    mut on_retry: impl FnMut(&Retry),
+) -> Result<reqwest::Response, reqwest::Error> {
+    let policy = policy();
+    let max_attempts = policy.attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let result = build().send().await;
+        let (reason, server_wait) = match &result {
+            Ok(resp) if is_retryable_status(resp.status().as_u16()) => (format!("HTTP {}", resp.status().as_u16()), retry_after(resp)),
+            Err(e) if is_retryable_error(e) => (if e.is_timeout() { "timeout" } else { "connection failed" }.to_string(), None),
+            _ => return result,
+        };
+        if attempt >= max_attempts {
+            return result;
+        }
+        let wait = delay(&policy, attempt, noise(), server_wait);
+        attempt += 1;
+        let retry = Retry { provider: provider.to_string(), attempt, max_attempts, delay_ms: wait.as_millis() as u64, reason };
+        info!("{}: {}, retrying in {}ms (attempt {}/{})", provider, retry.reason, retry.delay_ms, attempt, max_attempts);
+        on_retry(&retry);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NO_JITTER: Policy = Policy { jitter_pct: 0, ..DEFAULT_POLICY };
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(is_retryable_status(429));
+        assert!(is_retryable_status(500));
+        assert!(is_retryable_status(503));
+        assert!(!is_retryable_status(400));
+        assert!(!is_retryable_status(401));
+        assert!(!is_retryable_status(200));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        assert_eq!(delay(&NO_JITTER, 1, 0.5, None), Duration::from_millis(500));
+        assert_eq!(delay(&NO_JITTER, 2, 0.5, None), Duration::from_millis(1000));
+        assert_eq!(delay(&NO_JITTER, 3, 0.5, None), Duration::from_millis(2000));
+        assert_eq!(delay(&NO_JITTER, 30, 0.5, None), NO_JITTER.max_backoff);
+    }
+
+    #[test]
+    fn test_jitter_window() {
+        let p = DEFAULT_POLICY;
+        assert_eq!(delay(&p, 1, 0.0, None), Duration::from_millis(400));
+        assert_eq!(delay(&p, 1, 1.0, None), Duration::from_millis(600));
+    }
+
+    #[test]
+    fn test_retry_after_is_capped() {
+        assert_eq!(delay(&NO_JITTER, 1, 0.5, Some(Duration::from_secs(2))), Duration::from_secs(2));
+        assert_eq!(delay(&NO_JITTER, 1, 0.5, Some(Duration::from_secs(600))), NO_JITTER.max_backoff);
+    }
+}
@@ -0,0 +1,92 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::warn;
+
+use crate::{http_retry, stt};
+
+// Everything the backend tells the HUD goes out as one `hud-event` event whose payload is tagged
+// by `type` and carries the API version in `v`, so frontends switch on a closed set of shapes
+// instead of subscribing to loose event names.
+
+pub const EVENT_NAME: &str = "hud-event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BadgeKind {
+  Error,
+  Secure,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HudEvent {
+  /// A session started (hotkey, tray, command)
+  Start { session_id: u64 },
+  /// Stop recording and finish the session (hotkey, tray, max duration)
+  Stop,
+  /// Short status message
+  Badge { msg: String, kind: BadgeKind },
+  /// Input level of the microphone (dBFS), for every 50ms of audio relayed to the backend
+  Level { db: f32 },
+  /// The backend's voice activity detection started or stopped hearing speech
+  SpeechActive { active: bool },
+  /// The input keeps clipping or stays very quiet while the user speaks (`code` is "clipping" or
+  /// "too_quiet"); `message` says what to change
+  LevelWarning { code: String, message: String },
+  /// The input device was changed in Settings; a running recording switches to it (None: default)
+  AudioDevice { id: Option<String> },
+  /// Interim transcript from the backend STT relay
+  Partial(stt::TranscriptEvent),
+  /// Final transcript segment from the backend STT relay
+  Final(stt::TranscriptEvent),
+  /// The session was aborted; `code` is stable, `message` is for people
+  Error { code: String, message: String },
+  /// The target window closed before insertion; the text was kept on the clipboard
+  TargetClosed { window: String },
+  /// Backend STT relay connection status
+  RelayStatus(stt::StatusEvent),
+  /// Backend STT relay link quality changed
+  RelayQuality(stt::QualityEvent),
+  /// A refinement request failed transiently and is about to be retried
+  RefineRetrying(http_retry::Retry),
+  /// Set the current utterance aside and start a fresh take (retake hotkey)
+  Retake,
+  /// The recording was paused (pause hotkey, `pause_dictation`) or resumed
+  Paused { paused: bool },
+  /// A paste preview is being edited: the HUD takes focus so the text can be edited, and answers
+  /// with `answer_paste_confirmation`
+  PasteEdit,
+  /// The HUD is shown to be dragged to a new spot (`active`), or move mode ended
+  MoveMode { active: bool },
+}
+
+impl HudEvent {
+  pub fn badge(msg: impl Into<String>) -> Self {
+    HudEvent::Badge { msg: msg.into(), kind: BadgeKind::Error }
+  }
+
+  pub fn error(code: &str, message: impl Into<String>) -> Self {
+    HudEvent::Error { code: code.to_string(), message: message.into() }
+  }
+
+  pub fn transcript(event: stt::TranscriptEvent) -> Self {
+    if event.is_final { HudEvent::Final(event) } else { HudEvent::Partial(event) }
+  }
+}
+
+/// Wire format of `hud-event`
+#[derive(Serialize, JsonSchema)]
+pub struct Envelope<'a> {
+  /// API version (see `api_schema::API_VERSION`)
+  pub v: u32,
+  #[serde(flatten)]
+  pub event: &'a HudEvent,
+}
+
+pub fn emit_hud_event(app: &AppHandle, event: HudEvent) {
+  let envelope = Envelope { v: crate::api_schema::API_VERSION, event: &event };
+  if let Err(e) = app.emit_to("hud", EVENT_NAME, envelope) {
+    warn!("Failed to emit {:?}: {}", event, e);
+  }
+}
@@ -0,0 +1,97 @@
+// Where the HUD goes on its monitor: one of a few anchored spots, or a custom spot the user
+// dragged it to in move mode. Custom spots are saved per monitor as an offset from the monitor's
+// top left corner, so the HUD lands in the same place on each screen whatever the layout.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const HUD_POSITIONS: &[&str] = &["bottom-center", "top-center", "bottom-right", "custom"];
+
+/// Gap between the HUD and the edge it is anchored to, in physical pixels
+const MARGIN: i32 = 60;
+
+/// A rectangle in physical desktop coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Area {
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        x >= self.x && y >= self.y && x < self.x + self.width as i32 && y < self.y + self.height as i32
+    }
+
+    pub fn center(&self) -> (i32, i32) {
+        (self.x + self.width as i32 / 2, self.y + self.height as i32 / 2)
+    }
+}
+
+/// A dragged HUD's top left corner relative to its monitor's
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Offset {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Key custom positions are saved under: the monitor's name, or its geometry when it has none
+pub fn monitor_key(name: Option<&str>, monitor: Area) -> String {
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => name.to_string(),
+        None => format!("{}x{}@{},{}", monitor.width, monitor.height, monitor.x, monitor.y),
+    }
+}
+
+/// Offset of a HUD at `at` on `monitor`
+pub fn offset_in(monitor: Area, at: (i32, i32)) -> Offset {
+    Offset { x: at.0 - monitor.x, y: at.1 - monitor.y }
+}
+
+/// Top left corner for a `hud`-sized HUD at `position` within `area` (the monitor's work area, or
+/// the whole monitor); "custom" uses `custom` on `monitor` and falls back to bottom-center when
+/// nothing was saved for it. The HUD is kept inside `area`.
+pub fn place(position: &str, area: Area, monitor: Area, hud: (u32, u32), custom: Option<Offset>) -> (i32, i32) {
+    let (w, h) = (hud.0 as i32, hud.1 as i32);
+    let center_x = area.x + (area.width as i32 - w) / 2;
+    let bottom_y = area.y + area.height as i32 - h - MARGIN;
+    let (x, y) = match (position, custom) {
+        ("top-center", _) => (center_x, area.y + MARGIN),
+        ("bottom-right", _) => (area.x + area.width as i32 - w - MARGIN, bottom_y),
+        ("custom", Some(offset)) => (monitor.x + offset.x, monitor.y + offset.y),
+        _ => (center_x, bottom_y),
+    };
+    let max_x = (area.x + area.width as i32 - w).max(area.x);
+    let max_y = (area.y + area.height as i32 - h).max(area.y);
+    (x.clamp(area.x, max_x), y.clamp(area.y, max_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Area = Area { x: 1920, y: 0, width: 2560, height: 1440 };
+    const WORK: Area = Area { x: 1920, y: 0, width: 2560, height: 1400 };
+
+    #[test]
+    fn test_anchored_positions() {
+        assert_eq!(place("bottom-center", WORK, SCREEN, (600, 120), None), (1920 + 980, 1400 - 180));
+        assert_eq!(place("top-center", WORK, SCREEN, (600, 120), None), (1920 + 980, 60));
+        assert_eq!(place("bottom-right", WORK, SCREEN, (600, 120), None), (1920 + 2560 - 660, 1220));
+        // Nothing saved for this monitor yet
+        assert_eq!(place("custom", WORK, SCREEN, (600, 120), None), place("bottom-center", WORK, SCREEN, (600, 120), None));
+    }
+
+    #[test]
+    fn test_custom_position() {
+        let offset = offset_in(SCREEN, (2020, 300));
+        assert_eq!(offset, Offset { x: 100, y: 300 });
+        assert_eq!(place("custom", WORK, SCREEN, (600, 120), Some(offset)), (2020, 300));
+        // Dragged partly off screen, or saved on a larger monitor: pulled back inside
+        assert_eq!(place("custom", WORK, SCREEN, (600, 120), Some(Offset { x: 2500, y: -40 })), (1920 + 1960, 0));
+        assert_eq!(monitor_key(Some("DELL U2720Q"), SCREEN), "DELL U2720Q");
+        assert_eq!(monitor_key(None, SCREEN), "2560x1440@1920,0");
+        assert!(SCREEN.contains(SCREEN.center()));
+    }
+}
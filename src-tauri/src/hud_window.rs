@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, Monitor, WebviewWindow, WebviewWindowBuilder};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::hud_event::{emit_hud_event, HudEvent};
+use crate::hud_position::{self, Area, Offset};
+
+// Low-memory mode: the HUD webview is destroyed after sitting idle and rebuilt from its
+// tauri.conf.json entry on demand. A rebuilt webview has to load the page and register its
+// event listeners before the start event can be emitted, so the HUD reports in via `hud_ready`.
+//
+// Placement follows the `hud_position` pref (hud_position.rs); in move mode the HUD is shown on its
+// own to be dragged, and where it is dropped is saved for its monitor.
+
+const HUD_LABEL: &str = "hud";
+const READY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Custom HUD spots by monitor key, in prefs.json
+const K_HUD_POSITIONS: &str = "hud_positions";
+
+static READY: AtomicBool = AtomicBool::new(false);
+static READY_NOTIFY: Notify = Notify::const_new();
+static LAST_USED: Mutex<Option<Instant>> = Mutex::new(None);
+static MOVING: AtomicBool = AtomicBool::new(false);
+// Serializes creation so a pre-warm and start_dictation don't both build the window
+static CREATING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Called by the HUD page once its listeners are registered
+pub fn mark_ready() {
+  READY.store(true, Ordering::SeqCst);
+  READY_NOTIFY.notify_waiters();
+}
+
+/// Record HUD activity; the idle timer counts from the last call
+pub fn touch() {
+  *LAST_USED.lock().unwrap() = Some(Instant::now());
+}
+
+/// Get the HUD window, recreating it if it was unloaded, and wait until its page is listening
+pub async fn ensure_hud(app: &AppHandle) -> Result<WebviewWindow, String> {
+  touch();
+  let win = {
+    let _guard = CREATING.lock().await;
+    match app.get_webview_window(HUD_LABEL) {
+      Some(w) => w,
+      None => create(app)?,
+    }
+  };
+  // Register for the notification before checking the flag, so a ready signal in between isn't lost
+  let notified = READY_NOTIFY.notified();
+  if !READY.load(Ordering::SeqCst) {
+    info!("Waiting for HUD page to load...");
+    if tokio::time::timeout(READY_TIMEOUT, notified).await.is_err() {
+      // Carry on: the page may already be listening even if the ready call was lost
+      warn!("HUD did not report ready within {:?}", READY_TIMEOUT);
+    }
+  }
+  Ok(win)
+}
+
+/// Start loading the HUD early (on hotkey down) without waiting for it
+pub fn prewarm(app: &AppHandle) {
+  if app.get_webview_window(HUD_LABEL).is_some() {
+    touch();
+    return;
+  }
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = ensure_hud(&app).await {
+      warn!("HUD pre-warm failed: {}", e);
+    }
+  });
+}
+
+/// Build the HUD from its config entry so every window flag matches a cold start
+fn create(app: &AppHandle) -> Result<WebviewWindow, String> {
+  info!("Recreating HUD window...");
+  let config = app
+    .config()
+    .app
+    .windows
+    .iter()
+    .find(|w| w.label == HUD_LABEL)
+    .cloned()
+    .ok_or("hud-window-config-missing")?;
+  READY.store(false, Ordering::SeqCst);
+  let win = WebviewWindowBuilder::from_config(app, &config)
+    .map_err(|e| e.to_string())?
+    .build()
+    .map_err(|e| e.to_string())?;
+  apply_flags(&win);
+  Ok(win)
+}
+
+/// Flags the HUD needs regardless of how the platform applied the config
+pub fn apply_flags(win: &WebviewWindow) {
+  let _ = win.hide();
+  let _ = win.set_decorations(false);
+  let _ = win.set_always_on_top(true);
+  let _ = win.set_skip_taskbar(true);
+}
+
+/// Destroy the HUD if it is hidden and has been idle for at least `idle`
+pub fn unload_if_idle(app: &AppHandle, idle: Duration) {
+  let Some(win) = app.get_webview_window(HUD_LABEL) else { return };
+  if win.is_visible().unwrap_or(true) {
+    return;
+  }
+  let idle_for = LAST_USED.lock().unwrap().map(|t| t.elapsed()).unwrap_or(Duration::MAX);
+  if idle_for < idle {
+    return;
+  }
+  // A creation in progress means a dictation is about to start
+  let Ok(_guard) = CREATING.try_lock() else { return };
+  info!("HUD idle for {}s, unloading webview to free memory", idle_for.as_secs());
+  READY.store(false, Ordering::SeqCst);
+  if let Err(e) = win.destroy() {
+    warn!("Failed to unload HUD: {}", e);
+  }
+}
+
+fn monitor_area(monitor: &Monitor) -> Area {
+  let (pos, size) = (monitor.position(), monitor.size());
+  Area { x: pos.x, y: pos.y, width: size.width, height: size.height }
+}
+
+fn monitor_key(monitor: &Monitor) -> String {
+  hud_position::monitor_key(monitor.name().map(String::as_str), monitor_area(monitor))
+}
+
+fn saved_offsets(app: &AppHandle) -> BTreeMap<String, Offset> {
+  let Some(saved) = app.store("prefs.json").ok().and_then(|s| s.get(K_HUD_POSITIONS)) else { return BTreeMap::new() };
+  serde_json::from_value(saved).unwrap_or_default()
+}
+
+/// Move the HUD to `position` on the monitor whose work area is `work_area` ((left, top, width,
+/// height)), or on the primary monitor when that is None
+pub fn place(app: &AppHandle, win: &WebviewWindow, position: &str, work_area: Option<(i32, i32, u32, u32)>) {
+  let work_area = work_area.map(|(x, y, width, height)| Area { x, y, width, height });
+  let monitor = work_area
+    .and_then(|area| win.available_monitors().ok()?.into_iter().find(|m| monitor_area(m).contains(area.center())))
+    .or_else(|| win.primary_monitor().ok().flatten());
+  let Some(monitor) = monitor else {
+    warn!("Could not find a monitor for the HUD, using its last position");
+    return;
+  };
+  let screen = monitor_area(&monitor);
+  let custom = if position == "custom" { saved_offsets(app).remove(&monitor_key(&monitor)) } else { None };
+  let size = win.outer_size().map(|s| (s.width, s.height)).unwrap_or((600, 120));
+  let (x, y) = hud_position::place(position, work_area.unwrap_or(screen), screen, size, custom);
+  info!("Positioning HUD {} at x:{}, y:{} (monitor {})", position, x, y, monitor_key(&monitor));
+  let _ = win.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+}
+
+pub fn is_moving() -> bool {
+  MOVING.load(Ordering::SeqCst)
+}
+
+/// Show the HUD on its own so it can be dragged; `finish_move` ends it
+pub async fn start_move(app: &AppHandle, position: &str, work_area: Option<(i32, i32, u32, u32)>) -> Result<(), String> {
+  let win = ensure_hud(app).await?;
+  MOVING.store(true, Ordering::SeqCst);
+  place(app, &win, position, work_area);
+  let _ = win.show();
+  // Dragging needs the HUD to take the mouse, and Enter/Esc need focus
+  let _ = win.set_focus();
+  emit_hud_event(app, HudEvent::MoveMode { active: true });
+  info!("HUD move mode on");
+  Ok(())
+}
+
+/// Leave move mode, saving where the HUD was dropped for its monitor when `save`; returns the
+/// monitor key it was saved under
+pub fn finish_move(app: &AppHandle, save: bool) -> Result<Option<String>, String> {
+  if !MOVING.swap(false, Ordering::SeqCst) {
+    return Ok(None);
+  }
+  emit_hud_event(app, HudEvent::MoveMode { active: false });
+  let Some(win) = app.get_webview_window(HUD_LABEL) else { return Ok(None) };
+  let _ = win.hide();
+  touch();
+  if !save {
+    info!("HUD move mode cancelled");
+    return Ok(None);
+  }
+  let at = win.outer_position().map_err(|e| e.to_string())?;
+  let monitor = win.current_monitor().map_err(|e| e.to_string())?.ok_or("The HUD is on no monitor")?;
+  let key = monitor_key(&monitor);
+  let mut offsets = saved_offsets(app);
+  offsets.insert(key.clone(), hud_position::offset_in(monitor_area(&monitor), (at.x, at.y)));
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set(K_HUD_POSITIONS, serde_json::to_value(&offsets).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  info!("HUD position saved for monitor {}", key);
+  Ok(Some(key))
+}
@@ -0,0 +1,89 @@
+// Named instance profiles (`--profile work`).
+// Each profile runs as its own app instance: the bundle identifier gets a profile suffix, so
+// stores (keys, prefs, hotkeys, history), webview data and the single-instance lock are all
+// separate, and the tray shows which profile it belongs to.
+
+use std::sync::OnceLock;
+use tracing::{info, warn};
+
+static PROFILE: OnceLock<Option<String>> = OnceLock::new();
+
+/// Profile names end up in the identifier and paths, so keep them to a safe charset
+pub fn is_valid_profile(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= 32
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Find `--profile NAME` or `--profile=NAME` in command-line arguments
+pub fn parse_profile(args: &[String]) -> Result<Option<String>, String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let value = if arg == "--profile" {
+            iter.next().cloned().ok_or("--profile needs a name")?
+        } else if let Some(v) = arg.strip_prefix("--profile=") {
+            v.to_string()
+        } else {
+            continue;
+        };
+        let value = value.trim().to_lowercase();
+        if !is_valid_profile(&value) {
+            return Err(format!("Invalid profile name \"{}\": use letters, digits, - and _", value));
+        }
+        // "default" is the unnamed profile
+        return Ok(if value == "default" { None } else { Some(value) });
+    }
+    Ok(None)
+}
+
+/// Read the profile from this process's arguments (once, at startup)
+pub fn init_from_args() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let profile = parse_profile(&args).unwrap_or_else(|e| {
+        warn!("{}; using the default profile", e);
+        None
+    });
+    if let Some(p) = &profile {
+        info!("Running with profile \"{}\"", p);
+    }
+    let _ = PROFILE.set(profile);
+}
+
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().and_then(|p| p.as_deref())
+}
+
+/// Bundle identifier for the running profile ("com.example.app" -> "com.example.app.work")
+pub fn scoped_identifier(base: &str) -> String {
+    match profile() {
+        Some(p) => format!("{}.{}", base, p),
+        None => base.to_string(),
+    }
+}
+
+/// Name shown in the tray tooltip
+pub fn display_name() -> String {
+    match profile() {
+        Some(p) => format!("Dictation HUD ({})", p),
+        None => "Dictation HUD".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_profile() {
+        assert_eq!(parse_profile(&args(&["--profile", "Work"])), Ok(Some("work".to_string())));
+        assert_eq!(parse_profile(&args(&["--minimized", "--profile=client_a"])), Ok(Some("client_a".to_string())));
+        assert_eq!(parse_profile(&args(&["--profile", "default"])), Ok(None));
+        assert_eq!(parse_profile(&args(&[])), Ok(None));
+        assert!(parse_profile(&args(&["--profile"])).is_err());
+        assert!(parse_profile(&args(&["--profile", "../etc"])).is_err());
+    }
+}
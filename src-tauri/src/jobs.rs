@@ -0,0 +1,483 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::history::{now_millis, now_secs};
+use crate::stt;
+
+// Queue for file transcription (watch folder, batch). Jobs run with bounded concurrency, can be
+// cancelled, and are kept in their own store so a large batch survives a restart: jobs that were
+// running when the app quit are queued again on the next start. A batch is the jobs queued for
+// one folder; every change to one of them also reports the batch's totals. The store is written
+// shortly after a change rather than on each one, so queueing a large folder is one write.
+
+pub const EVENT_NAME: &str = "job-progress";
+pub const BATCH_EVENT_NAME: &str = "batch-progress";
+const JOBS_STORE: &str = "jobs.json";
+const K_JOBS: &str = "jobs";
+/// Finished jobs kept for the list; the oldest are dropped first, except those of a batch that is
+/// still running, which its totals are counted from
+const MAX_FINISHED: usize = 200;
+/// Wait after a change before writing the store, so a burst of changes is one write
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+pub const DEFAULT_CONCURRENCY: usize = 2;
+pub const MAX_CONCURRENCY: usize = 8;
+
+static CONCURRENCY: AtomicUsize = AtomicUsize::new(DEFAULT_CONCURRENCY);
+static SAVE_PENDING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+  Queued,
+  Transcribing,
+  Refining,
+  Done,
+  Failed,
+  Cancelled,
+}
+
+impl JobStatus {
+  pub fn is_finished(self) -> bool {
+    matches!(self, JobStatus::Done | JobStatus::Failed | JobStatus::Cancelled)
+  }
+}
+
+/// One audio file to transcribe; also the payload of `job-progress`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Job {
+  pub id: u64,
+  /// The audio file
+  pub file: String,
+  pub refine: bool,
+  /// "txt" | "md"
+  pub output_format: String,
+  pub status: JobStatus,
+  /// Path of the written transcript once done
+  #[serde(default)]
+  pub output: Option<String>,
+  #[serde(default)]
+  pub error: Option<String>,
+  /// Unix timestamp (seconds)
+  pub created_at: u64,
+  /// Id of the folder batch this job belongs to
+  #[serde(default)]
+  pub batch: Option<u64>,
+}
+
+/// Totals of a folder batch; also the payload of `batch-progress`
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct BatchProgress {
+  pub id: u64,
+  pub total: usize,
+  pub queued: usize,
+  pub running: usize,
+  pub done: usize,
+  pub failed: usize,
+  pub cancelled: usize,
+  /// Every job has finished
+  pub finished: bool,
+  /// The job whose change caused this report
+  pub job: Option<Job>,
+}
+
+#[derive(Default)]
+struct Queue {
+  jobs: Vec<Job>,
+  running: HashMap<u64, tauri::async_runtime::JoinHandle<()>>,
+}
+
+impl Queue {
+  /// Add `job` under an id no other job has, dropping the oldest finished jobs beyond
+  /// `MAX_FINISHED` that aren't part of a running batch; returns the job as added
+  fn add(&mut self, mut job: Job) -> Job {
+    // Ids are millisecond timestamps; keep them unique when several files arrive together
+    while self.jobs.iter().any(|j| j.id == job.id) {
+      job.id += 1;
+    }
+    self.jobs.push(job.clone());
+    let running_batches: HashSet<u64> = self.jobs.iter().filter(|j| !j.status.is_finished()).filter_map(|j| j.batch).collect();
+    let droppable = |j: &Job| j.status.is_finished() && !j.batch.is_some_and(|b| running_batches.contains(&b));
+    let finished = self.jobs.iter().filter(|j| droppable(j)).count();
+    if finished > MAX_FINISHED {
+      let mut excess = finished - MAX_FINISHED;
+      self.jobs.retain(|j| {
+        if excess > 0 && droppable(j) {
+          excess -= 1;
+          return false;
+        }
+        true
+      });
+    }
+    job
+  }
+
+  /// A job for `file` that is queued, running or has finished without failing
+  fn has_job_for(&self, file: &str) -> bool {
+    self.jobs.iter().any(|j| j.file == file && j.status != JobStatus::Failed)
+  }
+
+  /// The next queued job to start, if fewer than `limit` are running
+  fn next_to_start(&self, limit: usize) -> Option<u64> {
+    if self.running.len() >= limit {
+      return None;
+    }
+    self.jobs.iter().find(|j| j.status == JobStatus::Queued && !self.running.contains_key(&j.id)).map(|j| j.id)
+  }
+}
+
+/// Queue the jobs that were still queued or running when the app quit again; returns how many
+fn requeue_unfinished(jobs: &mut [Job]) -> usize {
+  let mut requeued = 0;
+  for job in jobs.iter_mut().filter(|j| !j.status.is_finished()) {
+    job.status = JobStatus::Queued;
+    requeued += 1;
+  }
+  requeued
+}
+
+static QUEUE: Mutex<Option<Queue>> = Mutex::new(None);
+
+fn with_queue<R>(f: impl FnOnce(&mut Queue) -> R) -> R {
+  let mut guard = QUEUE.lock().unwrap();
+  f(guard.get_or_insert_with(Queue::default))
+}
+
+pub fn configure(concurrency: usize) {
+  CONCURRENCY.store(concurrency.clamp(1, MAX_CONCURRENCY), Ordering::Relaxed);
+}
+
+fn save(app: &AppHandle, jobs: &[Job]) {
+  let result = app.store(JOBS_STORE).map_err(|e| e.to_string()).and_then(|store| {
+    store.set(K_JOBS, serde_json::to_value(jobs).map_err(|e| e.to_string())?);
+    store.save().map_err(|e| e.to_string())
+  });
+  if let Err(e) = result {
+    warn!("Failed to save transcription jobs: {}", e);
+  }
+}
+
+/// Write the store once the changes of the next `SAVE_DELAY` are in
+fn save_soon(app: &AppHandle) {
+  if SAVE_PENDING.swap(true, Ordering::Relaxed) {
+    return;
+  }
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(SAVE_DELAY).await;
+    // Cleared before the copy, so a change made after it schedules another write
+    SAVE_PENDING.store(false, Ordering::Relaxed);
+    let jobs = with_queue(|q| q.jobs.clone());
+    save(&app, &jobs);
+  });
+}
+
+/// Update a job, persist the queue and report the change
+fn update(app: &AppHandle, id: u64, f: impl FnOnce(&mut Job)) -> Option<Job> {
+  let job = with_queue(|q| {
+    let job = q.jobs.iter_mut().find(|j| j.id == id)?;
+    f(job);
+    Some(job.clone())
+  })?;
+  save_soon(app);
+  if let Err(e) = app.emit(EVENT_NAME, &job) {
+    warn!("Failed to emit job progress: {}", e);
+  }
+  if let Some(progress) = job.batch.and_then(|b| batch_progress(b, Some(job.clone()))) {
+    if let Err(e) = app.emit(BATCH_EVENT_NAME, &progress) {
+      warn!("Failed to emit batch progress: {}", e);
+    }
+  }
+  Some(job)
+}
+
+/// Totals for batch `id`, None once none of its jobs are left in the list
+pub fn batch_progress(id: u64, job: Option<Job>) -> Option<BatchProgress> {
+  with_queue(|q| {
+    let jobs: Vec<&Job> = q.jobs.iter().filter(|j| j.batch == Some(id)).collect();
+    if jobs.is_empty() {
+      return None;
+    }
+    let count = |status: JobStatus| jobs.iter().filter(|j| j.status == status).count();
+    Some(BatchProgress {
+      id,
+      total: jobs.len(),
+      queued: count(JobStatus::Queued),
+      running: count(JobStatus::Transcribing) + count(JobStatus::Refining),
+      done: count(JobStatus::Done),
+      failed: count(JobStatus::Failed),
+      cancelled: count(JobStatus::Cancelled),
+      finished: jobs.iter().all(|j| j.status.is_finished()),
+      job,
+    })
+  })
+}
+
+/// Load jobs left from the previous run and start the queued ones
+pub fn restore(app: &AppHandle) {
+  let Ok(store) = app.store(JOBS_STORE) else { return };
+  let mut jobs: Vec<Job> = store.get(K_JOBS).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default();
+  let requeued = requeue_unfinished(&mut jobs);
+  if requeued > 0 {
+    info!("Resuming {} transcription jobs from the last run", requeued);
+  }
+  with_queue(|q| q.jobs = jobs);
+  pump(app);
+}
+
+pub fn list() -> Vec<Job> {
+  with_queue(|q| q.jobs.clone())
+}
+
+/// Whether `file` already has a job (so the watch folder doesn't queue it twice); a failed job
+/// doesn't count, so the file is tried again
+pub fn has_job_for(file: &Path) -> bool {
+  let file = file.to_string_lossy();
+  with_queue(|q| q.has_job_for(&file))
+}
+
+pub fn enqueue(app: &AppHandle, file: &Path, refine: bool, output_format: &str) -> Job {
+  let job = push(app, file, refine, output_format, None);
+  pump(app);
+  job
+}
+
+/// Add a job to the list without starting it
+fn push(app: &AppHandle, file: &Path, refine: bool, output_format: &str, batch: Option<u64>) -> Job {
+  let job = Job {
+    id: now_millis(),
+    file: file.to_string_lossy().into_owned(),
+    refine,
+    output_format: output_format.to_string(),
+    status: JobStatus::Queued,
+    output: None,
+    error: None,
+    created_at: now_secs(),
+    batch,
+  };
+  let job = with_queue(|q| q.add(job));
+  info!("Queued transcription job {} for {}", job.id, job.file);
+  save_soon(app);
+  let _ = app.emit(EVENT_NAME, &job);
+  job
+}
+
+/// Whether `audio` already has a transcript next to it
+pub fn has_output(audio: &Path) -> bool {
+  output_path(audio, "txt").exists() || output_path(audio, "md").exists()
+}
+
+/// Audio files in `dir`, and in its subfolders with `recursive`
+fn audio_files(dir: &Path, recursive: bool, out: &mut Vec<PathBuf>) {
+  let Ok(entries) = std::fs::read_dir(dir) else { return };
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if path.is_dir() {
+      if recursive {
+        audio_files(&path, recursive, out);
+      }
+    } else if stt::audio_content_type(&path).is_some() {
+      out.push(path);
+    }
+  }
+}
+
+/// Queue every audio file in `dir` as one batch. Files that already have a transcript next to
+/// them, or a job, are skipped, so a batch can be run again after adding files.
+pub fn enqueue_folder(app: &AppHandle, dir: &Path, refine: bool, output_format: &str, recursive: bool) -> Result<BatchProgress, String> {
+  if !dir.is_dir() {
+    return Err(format!("Not a folder: {}", dir.display()));
+  }
+  let mut files = Vec::new();
+  audio_files(dir, recursive, &mut files);
+  files.sort();
+  files.retain(|f| !has_output(f) && !has_job_for(f));
+  if files.is_empty() {
+    return Err(format!("No audio files left to transcribe in {}", dir.display()));
+  }
+  let id = now_millis();
+  for file in &files {
+    push(app, file, refine, output_format, Some(id));
+  }
+  info!("Queued batch {} with {} files from {}", id, files.len(), dir.display());
+  let progress = batch_progress(id, None).ok_or("Batch vanished")?;
+  let _ = app.emit(BATCH_EVENT_NAME, &progress);
+  pump(app);
+  Ok(progress)
+}
+
+/// Cancel a queued or running job
+pub fn cancel(app: &AppHandle, id: u64) -> Result<Job, String> {
+  let handle = with_queue(|q| {
+    let job = q.jobs.iter().find(|j| j.id == id).ok_or_else(|| format!("No job {}", id))?;
+    if job.status.is_finished() {
+      return Err(format!("Job {} already finished", id));
+    }
+    Ok(q.running.remove(&id))
+  })?;
+  if let Some(handle) = handle {
+    handle.abort();
+  }
+  info!("Cancelled transcription job {}", id);
+  let job = update(app, id, |j| j.status = JobStatus::Cancelled).ok_or_else(|| format!("No job {}", id))?;
+  pump(app);
+  Ok(job)
+}
+
+/// Start queued jobs while there is room
+fn pump(app: &AppHandle) {
+  let limit = CONCURRENCY.load(Ordering::Relaxed);
+  with_queue(|q| {
+    while let Some(id) = q.next_to_start(limit) {
+      let app = app.clone();
+      // The task can't finish before it's registered: finishing needs the queue lock held here
+      let handle = tauri::async_runtime::spawn(async move {
+        let result = run(&app, id).await;
+        with_queue(|q| q.running.remove(&id));
+        match result {
+          Ok(out) => {
+            info!("Transcription job {} wrote {}", id, out.display());
+            update(&app, id, |j| {
+              j.status = JobStatus::Done;
+              j.output = Some(out.to_string_lossy().into_owned());
+            });
+          }
+          Err(e) => {
+            warn!("Transcription job {} failed: {}", id, e);
+            update(&app, id, |j| {
+              j.status = JobStatus::Failed;
+              j.error = Some(e);
+            });
+          }
+        }
+        pump(&app);
+      });
+      q.running.insert(id, handle);
+    }
+  });
+}
+
+/// Where the transcript of `audio` goes
+pub fn output_path(audio: &Path, format: &str) -> PathBuf {
+  audio.with_extension(if format == "md" { "md" } else { "txt" })
+}
+
+/// Markdown form of a transcript: title, source and text
+fn markdown(audio: &Path, text: &str) -> String {
+  let title = audio.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+  let source = audio.file_name().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+  format!("# {}\n\n_Transcribed from `{}`_\n\n{}\n", title, source, text.trim())
+}
+
+/// Transcribe an audio file (see `stt::AUDIO_FILE_TYPES`) in one request
+pub async fn transcribe_path(audio: &Path, cfg: &stt::RelayConfig) -> Result<String, String> {
+  let content_type = stt::audio_content_type(audio).ok_or("Not an audio file")?;
+  let name = audio.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_else(|| "audio".into());
+  let bytes = std::fs::read(audio).map_err(|e| format!("Could not read {}: {}", audio.display(), e))?;
+  let text = stt::transcribe_file(cfg, bytes, content_type, &name).await?;
+  if text.trim().is_empty() {
+    return Err("No speech detected".into());
+  }
+  Ok(text)
+}
+
+async fn run(app: &AppHandle, id: u64) -> Result<PathBuf, String> {
+  let job = update(app, id, |j| j.status = JobStatus::Transcribing).ok_or("Job vanished")?;
+  let audio = PathBuf::from(&job.file);
+  let cfg = crate::stt_config(app).await?;
+  let mut text = transcribe_path(&audio, &cfg).await?;
+  if job.refine {
+    update(app, id, |j| j.status = JobStatus::Refining);
+    match crate::refine_impl(text.clone(), app.clone(), None, None, None).await {
+      Ok(refined) => text = refined,
+      Err(e) => warn!("Job {}: refinement failed for {}, keeping the raw transcript: {}", id, audio.display(), e),
+    }
+  }
+
+  let out = output_path(&audio, &job.output_format);
+  let contents = if job.output_format == "md" { markdown(&audio, &text) } else { format!("{}\n", text.trim()) };
+  std::fs::write(&out, contents).map_err(|e| e.to_string())?;
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn job(id: u64, file: &str, status: JobStatus) -> Job {
+    Job { id, file: file.into(), refine: false, output_format: "txt".into(), status, output: None, error: None, created_at: 0, batch: None }
+  }
+
+  #[test]
+  fn test_add_keeps_ids_unique() {
+    let mut q = Queue::default();
+    assert_eq!(q.add(job(5, "a.wav", JobStatus::Queued)).id, 5);
+    assert_eq!(q.add(job(5, "b.wav", JobStatus::Queued)).id, 6);
+    assert_eq!(q.add(job(5, "c.wav", JobStatus::Queued)).id, 7);
+  }
+
+  #[test]
+  fn test_add_drops_oldest_finished() {
+    let mut q = Queue::default();
+    q.add(job(1, "running.wav", JobStatus::Transcribing));
+    for i in 0..MAX_FINISHED as u64 + 2 {
+      q.add(job(10 + i, "done.wav", JobStatus::Done));
+    }
+    assert_eq!(q.jobs.iter().filter(|j| j.status.is_finished()).count(), MAX_FINISHED);
+    assert_eq!(q.jobs[0].id, 1);
+    assert_eq!(q.jobs[1].id, 12);
+  }
+
+  #[test]
+  fn test_add_keeps_running_batches_whole() {
+    let mut q = Queue::default();
+    let batched = |id: u64, status: JobStatus| Job { batch: Some(7), ..job(id, "batch.wav", status) };
+    q.add(batched(1000, JobStatus::Queued));
+    for i in 0..MAX_FINISHED as u64 + 5 {
+      q.add(batched(i, JobStatus::Done));
+    }
+    q.add(job(2000, "single.wav", JobStatus::Done));
+    // The batch still runs, so its finished jobs stay for its totals
+    assert_eq!(q.jobs.iter().filter(|j| j.batch == Some(7)).count(), MAX_FINISHED + 6);
+
+    q.jobs.iter_mut().find(|j| j.id == 1000).unwrap().status = JobStatus::Done;
+    q.add(job(3000, "later.wav", JobStatus::Done));
+    assert_eq!(q.jobs.iter().filter(|j| j.status.is_finished()).count(), MAX_FINISHED);
+    assert_eq!(q.jobs.last().unwrap().id, 3000);
+  }
+
+  #[test]
+  fn test_failed_jobs_dont_block_a_retry() {
+    let mut q = Queue::default();
+    q.add(job(1, "failed.wav", JobStatus::Failed));
+    q.add(job(2, "cancelled.wav", JobStatus::Cancelled));
+    q.add(job(3, "queued.wav", JobStatus::Queued));
+    assert!(!q.has_job_for("failed.wav"));
+    assert!(q.has_job_for("cancelled.wav"));
+    assert!(q.has_job_for("queued.wav"));
+    assert!(!q.has_job_for("new.wav"));
+  }
+
+  #[test]
+  fn test_next_to_start_and_requeue() {
+    let mut q = Queue::default();
+    q.add(job(1, "a.wav", JobStatus::Done));
+    q.add(job(2, "b.wav", JobStatus::Refining));
+    q.add(job(3, "c.wav", JobStatus::Queued));
+    assert_eq!(q.next_to_start(2), Some(3));
+    assert_eq!(q.next_to_start(0), None);
+
+    // Jobs running when the app quit go back to the queue; finished ones stay as they are
+    assert_eq!(requeue_unfinished(&mut q.jobs), 2);
+    let statuses: Vec<JobStatus> = q.jobs.iter().map(|j| j.status).collect();
+    assert_eq!(statuses, vec![JobStatus::Done, JobStatus::Queued, JobStatus::Queued]);
+    assert_eq!(q.next_to_start(2), Some(2));
+  }
+}
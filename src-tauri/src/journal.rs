@@ -0,0 +1,205 @@
+// Journal mode: refined dictations are appended to a file, e.g. an Obsidian daily note. The target
+// is a path template: `{{date}}` (2024-05-31), `{{date:FORMAT}}` with a strftime format
+// (`{{date:%Y/%m-%B}}`), `{{time}}` (14-05) and a leading `~` for the home directory. Each
+// dictation becomes a block rendered from a template with `{{date}}`, `{{time}}`, `{{app}}` and
+// `{{text}}`; a file the journal creates starts with the front matter template, so notes land in
+// a vault with their metadata.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// "off" keeps pasting only; "also" pastes and appends; "only" appends instead of pasting
+pub const JOURNAL_MODES: &[&str] = &["off", "also", "only"];
+
+/// A time heading over the text
+pub const DEFAULT_BLOCK: &str = "## {{time}}\n\n{{text}}\n";
+
+/// How dictations are written into the journal file
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Templates {
+    /// Block per dictation; DEFAULT_BLOCK when None
+    #[serde(default)]
+    pub block: Option<String>,
+    /// Written at the top of a file the journal creates, e.g. YAML front matter
+    #[serde(default)]
+    pub front_matter: Option<String>,
+}
+
+/// What the next dictation would add to the journal
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Preview {
+    /// The file it would go to; None while no journal file is set
+    pub path: Option<String>,
+    /// Text appended to that file, front matter included when the file is new
+    pub addition: String,
+}
+
+/// One dictation going into the journal
+pub struct Entry<'a> {
+    pub text: &'a str,
+    /// App the dictation was for, when known
+    pub app: Option<&'a str>,
+    pub now: NaiveDateTime,
+}
+
+fn format_date(now: &NaiveDateTime, format: &str) -> Result<String, String> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    // Formatting an invalid specifier panics, so reject it up front
+    if items.iter().any(|i| matches!(i, Item::Error)) {
+        return Err(format!("Invalid date format: {}", format));
+    }
+    Ok(now.format_with_items(items.into_iter()).to_string())
+}
+
+/// Replace `{{name}}` and `{{name:arg}}` placeholders; `value` returns None for unknown names
+pub fn fill(template: &str, mut value: impl FnMut(&str, Option<&str>) -> Result<Option<String>, String>) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            return Err("Unclosed {{ in template".into());
+        };
+        let inner = &after[..end];
+        let (name, arg) = match inner.split_once(':') {
+            Some((n, a)) => (n.trim(), Some(a)),
+            None => (inner.trim(), None),
+        };
+        match value(name, arg)? {
+            Some(v) => out.push_str(&v),
+            None => return Err(format!("Unknown placeholder {{{{{}}}}}", name)),
+        }
+        rest = &after[end + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// The file a path template points to at `now`
+pub fn expand_path(template: &str, now: &NaiveDateTime, home: &Path) -> Result<PathBuf, String> {
+    let filled = fill(template.trim(), |name, arg| match name {
+        "date" => format_date(now, arg.unwrap_or("%Y-%m-%d")).map(Some),
+        "time" => format_date(now, arg.unwrap_or("%H-%M")).map(Some),
+        _ => Ok(None),
+    })?;
+    let path = match filled.strip_prefix('~') {
+        Some(rest) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(filled),
+    };
+    if !path.is_absolute() {
+        return Err("Journal path must be absolute or start with ~".into());
+    }
+    Ok(path)
+}
+
+impl Templates {
+    /// The block and front matter for `entry`
+    pub fn render(&self, entry: &Entry) -> Result<(String, Option<String>), String> {
+        let block = render(self.block.as_deref().unwrap_or(DEFAULT_BLOCK), entry)?;
+        let front_matter = self.front_matter.as_deref().map(|fm| render(fm, entry)).transpose()?;
+        Ok((block, front_matter))
+    }
+}
+
+/// Render a block or front matter template for `entry`
+pub fn render(template: &str, entry: &Entry) -> Result<String, String> {
+    fill(template, |name, arg| match name {
+        "date" => format_date(&entry.now, arg.unwrap_or("%Y-%m-%d")).map(Some),
+        "time" => format_date(&entry.now, arg.unwrap_or("%H:%M")).map(Some),
+        "app" => Ok(Some(entry.app.unwrap_or("unknown").to_string())),
+        "text" => Ok(Some(entry.text.trim().to_string())),
+        _ => Ok(None),
+    })
+}
+
+/// What appending `block` adds to a file that currently holds `existing` (None: no file yet).
+/// A new or empty file starts with the front matter; blocks are kept a blank line apart.
+pub fn addition(existing: Option<&str>, block: &str, front_matter: Option<&str>) -> String {
+    let existing = existing.unwrap_or("");
+    let mut out = String::new();
+    if existing.trim().is_empty() {
+        if let Some(fm) = front_matter.map(str::trim).filter(|fm| !fm.is_empty()) {
+            out.push_str(fm);
+            out.push_str("\n\n");
+        }
+    } else if !existing.ends_with("\n\n") {
+        out.push_str(if existing.ends_with('\n') { "\n" } else { "\n\n" });
+    }
+    out.push_str(block.trim_start_matches('\n'));
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+/// Append `block` to `path` (see `addition`), creating the file and its folders as needed
+pub fn append(path: &Path, block: &str, front_matter: Option<&str>) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let existing = std::fs::read_to_string(path).ok();
+    let addition = addition(existing.as_deref(), block, front_matter);
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(addition.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 31).unwrap().and_hms_opt(14, 5, 0).unwrap()
+    }
+
+    #[test]
+    fn test_expand_path() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            expand_path("~/Vault/Daily/{{date}}.md", &now(), home).unwrap(),
+            PathBuf::from("/home/me/Vault/Daily/2024-05-31.md")
+        );
+        assert_eq!(
+            expand_path("/notes/{{date:%Y/%B}}/{{time}}.md", &now(), home).unwrap(),
+            PathBuf::from("/notes/2024/May/14-05.md")
+        );
+        assert!(expand_path("notes/{{date}}.md", &now(), home).is_err());
+        assert!(expand_path("/notes/{{week}}.md", &now(), home).is_err());
+        assert!(expand_path("/notes/{{date:%Q}}.md", &now(), home).is_err());
+        assert!(expand_path("/notes/{{date.md", &now(), home).is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let entry = Entry { text: " Call Sam back. ", app: Some("Slack"), now: now() };
+        assert_eq!(render(DEFAULT_BLOCK, &entry).unwrap(), "## 14:05\n\nCall Sam back.\n");
+        assert_eq!(
+            render("- {{date:%d/%m}} {{time:%H%M}} ({{app}}): {{text}}", &entry).unwrap(),
+            "- 31/05 1405 (Slack): Call Sam back."
+        );
+        let unknown = Entry { app: None, ..entry };
+        assert_eq!(render("{{app}}", &unknown).unwrap(), "unknown");
+        assert!(render("{{mood}}", &unknown).is_err());
+    }
+
+    #[test]
+    fn test_addition_and_append() {
+        let fm = Some("---\ndate: 2024-05-31\n---");
+        assert_eq!(addition(None, "## 14:05\n\nHi\n", fm), "---\ndate: 2024-05-31\n---\n\n## 14:05\n\nHi\n");
+        assert_eq!(addition(Some("# Notes"), "Hi", fm), "\n\nHi\n");
+        assert_eq!(addition(Some("# Notes\n"), "Hi\n", None), "\nHi\n");
+        assert_eq!(addition(Some("# Notes\n\n"), "\nHi\n", None), "Hi\n");
+
+        let path = std::env::temp_dir().join(format!("journal-test-{}", std::process::id())).join("day.md");
+        let _ = std::fs::remove_file(&path);
+        append(&path, "one\n", fm).unwrap();
+        append(&path, "two\n", fm).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "---\ndate: 2024-05-31\n---\n\none\n\ntwo\n");
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+}
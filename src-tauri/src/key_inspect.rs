@@ -0,0 +1,165 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::{config, http_client, redact, stt_options};
+
+// Key troubleshooting without leaking secrets: for each provider, which key is in use (by its
+// first few characters and length) and whether the provider accepts it right now. Keys are also
+// checked here before they are saved.
+
+const DETAIL_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyStatus {
+  /// No key stored or in the environment
+  Missing,
+  /// The provider accepted the key
+  Valid,
+  /// The provider rejected the key, or could not be reached
+  Invalid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct KeyInspection {
+  pub provider: String,
+  /// First characters of the key, never the whole key
+  pub prefix: Option<String>,
+  pub length: usize,
+  pub status: KeyStatus,
+  /// Why the live test failed, with the key masked out
+  pub detail: Option<String>,
+}
+
+async fn inspect<F, Fut>(provider: &str, key: Option<String>, test: F) -> KeyInspection
+where
+  F: FnOnce(String) -> Fut,
+  Fut: Future<Output = Result<(), String>>,
+{
+  let Some(key) = key.filter(|k| !k.is_empty()) else {
+    return KeyInspection { provider: provider.into(), prefix: None, length: 0, status: KeyStatus::Missing, detail: None };
+  };
+  let prefix = redact::key_prefix(&key);
+  let length = key.chars().count();
+  let (status, detail) = match test(key.clone()).await {
+    Ok(()) => (KeyStatus::Valid, None),
+    // Provider errors can echo the request back; never let the key through
+    Err(e) => (KeyStatus::Invalid, Some(e.replace(&key, "[key]").chars().take(DETAIL_CHARS).collect())),
+  };
+  info!("Key check {}: {:?} ({}…, {} chars)", provider, status, prefix, length);
+  KeyInspection { provider: provider.into(), prefix: Some(prefix), length, status, detail }
+}
+
+/// Outcome of checking a key before saving it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyCheckStatus {
+  /// The provider accepted the key
+  Ok,
+  /// The provider refused the key (HTTP 401)
+  Unauthorized,
+  /// The provider could not be reached, so the key is unverified
+  NetworkError,
+  /// The provider answered with another error (a key without permission for the check, rate
+  /// limit, outage)
+  ProviderError,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct KeyCheck {
+  pub provider: String,
+  pub status: KeyCheckStatus,
+  /// Whether the key was stored; refused keys are not
+  pub saved: bool,
+  /// What went wrong, with the key masked out
+  pub detail: Option<String>,
+}
+
+/// Deepgram's projects list, on the self-hosted endpoint when one is set
+pub fn deepgram_request(key: &str, endpoint: &str) -> reqwest::RequestBuilder {
+  let (base, _) = stt_options::deepgram_bases(endpoint);
+  http_client::shared().get(format!("{}/v1/projects", base)).header("authorization", format!("Token {}", key))
+}
+
+/// Whether Deepgram's answer to `deepgram_request` accepts the key. A self-hosted server has no
+/// projects API; answering without refusing the key is enough there.
+pub fn deepgram_accepted(endpoint: &str, code: reqwest::StatusCode) -> bool {
+  code.is_success() || (!endpoint.is_empty() && code == reqwest::StatusCode::NOT_FOUND)
+}
+
+/// Check `key` for `provider` with one lightweight authenticated request: OpenRouter's key
+/// endpoint, the MegaLLM model list, the Deepgram projects list (on the self-hosted endpoint
+/// when one is set) and an ElevenLabs single-use token
+pub async fn check_key(app: &AppHandle, provider: &str, key: &str) -> KeyCheck {
+  let client = http_client::shared();
+  let endpoint = config::get_stt_options(app).await.stt_endpoint_override;
+  let request = match provider {
+    "openrouter" => client.get("https://openrouter.ai/api/v1/key").bearer_auth(key),
+    "megallm" => client.get("https://ai.megallm.io/v1/models").bearer_auth(key),
+    "deepgram" => deepgram_request(key, &endpoint),
+    "elevenlabs" => client.post("https://api.elevenlabs.io/v1/single-use-token/realtime_scribe").header("xi-api-key", key).header("content-length", "0").body(""),
+    other => {
+      return KeyCheck { provider: other.into(), status: KeyCheckStatus::ProviderError, saved: false, detail: Some(format!("Unknown provider {}", other)) };
+    }
+  };
+  let mask = |e: String| -> String { e.replace(key, "[key]").chars().take(DETAIL_CHARS).collect() };
+  let (status, detail) = match request.timeout(Duration::from_secs(5)).send().await {
+    Err(e) => (KeyCheckStatus::NetworkError, Some(mask(e.to_string()))),
+    Ok(resp) => {
+      let code = resp.status();
+      let accepted = if provider == "deepgram" { deepgram_accepted(&endpoint, code) } else { code.is_success() };
+      if accepted {
+        (KeyCheckStatus::Ok, None)
+      } else {
+        let body = resp.text().await.unwrap_or_default();
+        // A 403 can be a valid key without access to the endpoint checked (a scoped Deepgram key),
+        // so the key is still saved and only flagged
+        let status = if code == reqwest::StatusCode::UNAUTHORIZED { KeyCheckStatus::Unauthorized } else { KeyCheckStatus::ProviderError };
+        (status, Some(mask(format!("HTTP {} - {}", code, body))))
+      }
+    }
+  };
+  info!("Key check before saving {}: {:?} ({}…)", provider, status, redact::key_prefix(key));
+  KeyCheck { provider: provider.into(), status, saved: false, detail }
+}
+
+/// GET `url` with `header: value` and succeed on a 2xx
+async fn probe(url: &str, header: &str, value: String) -> Result<(), String> {
+  let resp = http_client::shared()
+    .get(url)
+    .timeout(Duration::from_secs(5))
+    .header(header, value)
+    .send()
+    .await
+    .map_err(|e| e.to_string())?;
+  let status = resp.status();
+  if status.is_success() {
+    return Ok(());
+  }
+  let body = resp.text().await.unwrap_or_default();
+  Err(format!("HTTP {} - {}", status, body))
+}
+
+/// Inspect every provider's key, testing the present ones in parallel
+pub async fn inspect_all(app: &AppHandle) -> Vec<KeyInspection> {
+  let (openrouter, megallm, deepgram, elevenlabs) = tokio::join!(
+    config::get_openrouter_key(app),
+    config::get_megallm_key(app),
+    config::get_deepgram_key(app),
+    config::get_elevenlabs_key(app),
+  );
+  let results = tokio::join!(
+    // Key metadata endpoint: checks the key without spending tokens on a completion
+    inspect("openrouter", openrouter, |k| probe("https://openrouter.ai/api/v1/key", "authorization", format!("Bearer {}", k))),
+    inspect("megallm", megallm, |k| async move { crate::list_megallm_models(app.clone(), Some(k)).await.map(|_| ()) }),
+    // Against the self-hosted endpoint when one is set
+    inspect("deepgram", deepgram, |k| async move { crate::test_deepgram(app.clone(), Some(k), None).await }),
+    // Same lightweight check as the Settings test button
+    inspect("elevenlabs", elevenlabs, |k| async move { crate::create_elevenlabs_token(app.clone(), Some(k)).await.map(|_| ()) }),
+  );
+  vec![results.0, results.1, results.2, results.3]
+}
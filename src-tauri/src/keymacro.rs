@@ -0,0 +1,226 @@
+// Keystroke macros built from dictation: spoken key names become key presses and everything else
+// is typed, so "tab tab John Smith enter" moves two fields on, fills in a name and submits.
+// Words that are also ordinary English ("up", "end", "return", ...) only count as keys after
+// "press"; any key can be repeated with "twice" or "<n> times".
+
+use serde::{Deserialize, Serialize};
+
+pub const MACRO_VERSION: u32 = 1;
+const MAX_REPEAT: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacroKey {
+    Enter,
+    Tab,
+    Escape,
+    Backspace,
+    Delete,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Space,
+}
+
+impl MacroKey {
+    /// XKB keysym name, as wtype expects
+    pub fn xkb_name(self) -> &'static str {
+        match self {
+            MacroKey::Enter => "Return",
+            MacroKey::Tab => "Tab",
+            MacroKey::Escape => "Escape",
+            MacroKey::Backspace => "BackSpace",
+            MacroKey::Delete => "Delete",
+            MacroKey::Up => "Up",
+            MacroKey::Down => "Down",
+            MacroKey::Left => "Left",
+            MacroKey::Right => "Right",
+            MacroKey::Home => "Home",
+            MacroKey::End => "End",
+            MacroKey::PageUp => "Prior",
+            MacroKey::PageDown => "Next",
+            MacroKey::Space => "space",
+        }
+    }
+
+    /// Linux evdev keycode, as ydotool expects
+    pub fn evdev_code(self) -> u16 {
+        match self {
+            MacroKey::Enter => 28,
+            MacroKey::Tab => 15,
+            MacroKey::Escape => 1,
+            MacroKey::Backspace => 14,
+            MacroKey::Delete => 111,
+            MacroKey::Up => 103,
+            MacroKey::Down => 108,
+            MacroKey::Left => 105,
+            MacroKey::Right => 106,
+            MacroKey::Home => 102,
+            MacroKey::End => 107,
+            MacroKey::PageUp => 104,
+            MacroKey::PageDown => 109,
+            MacroKey::Space => 57,
+        }
+    }
+}
+
+/// (spoken form, key, recognized without "press")
+const SPOKEN_KEYS: &[(&str, MacroKey, bool)] = &[
+    ("page up", MacroKey::PageUp, true),
+    ("page down", MacroKey::PageDown, true),
+    ("up arrow", MacroKey::Up, true),
+    ("arrow up", MacroKey::Up, true),
+    ("down arrow", MacroKey::Down, true),
+    ("arrow down", MacroKey::Down, true),
+    ("left arrow", MacroKey::Left, true),
+    ("arrow left", MacroKey::Left, true),
+    ("right arrow", MacroKey::Right, true),
+    ("arrow right", MacroKey::Right, true),
+    ("back space", MacroKey::Backspace, true),
+    ("tab", MacroKey::Tab, true),
+    ("enter", MacroKey::Enter, true),
+    ("escape", MacroKey::Escape, true),
+    ("backspace", MacroKey::Backspace, true),
+    ("return", MacroKey::Enter, false),
+    ("delete", MacroKey::Delete, false),
+    ("up", MacroKey::Up, false),
+    ("down", MacroKey::Down, false),
+    ("left", MacroKey::Left, false),
+    ("right", MacroKey::Right, false),
+    ("home", MacroKey::Home, false),
+    ("end", MacroKey::End, false),
+    ("space", MacroKey::Space, false),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Text(String),
+    Key(MacroKey),
+}
+
+/// A replayable macro as saved to disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyMacro {
+    pub version: u32,
+    /// The dictation it was built from
+    pub source: String,
+    pub steps: Vec<Step>,
+}
+
+fn normalize(word: &str) -> String {
+    word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase()
+}
+
+/// Key spoken at the start of `words`, and how many words it took
+fn match_key(words: &[String], pressed: bool) -> Option<(MacroKey, usize)> {
+    SPOKEN_KEYS.iter().find_map(|(spoken, key, bare)| {
+        let parts: Vec<&str> = spoken.split(' ').collect();
+        let matches = words.len() >= parts.len() && parts.iter().zip(words).all(|(p, w)| p == w);
+        (matches && (pressed || *bare)).then_some((*key, parts.len()))
+    })
+}
+
+fn number(word: &str) -> Option<usize> {
+    const WORDS: &[&str] = &["one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten"];
+    word.parse().ok().or_else(|| WORDS.iter().position(|w| *w == word).map(|i| i + 1))
+}
+
+/// Repeat count spoken at the start of `words`, and how many words it took
+fn match_repeat(words: &[String]) -> Option<(usize, usize)> {
+    match words.first().map(|w| w.as_str()) {
+        Some("twice") => Some((2, 1)),
+        Some("thrice") => Some((3, 1)),
+        Some(w) => {
+            let n = number(w)?;
+            (words.get(1).map(|t| t.as_str()) == Some("times")).then_some((n.clamp(1, MAX_REPEAT), 2))
+        }
+        None => None,
+    }
+}
+
+fn flush_text(steps: &mut Vec<Step>, words: &mut Vec<&str>) {
+    let text = words.join(" ");
+    words.clear();
+    // Refinement punctuates the whole dictation; a form field wants just the words
+    let text = text.trim_end_matches([',', '.', ';', ':']).trim();
+    if !text.is_empty() {
+        steps.push(Step::Text(text.to_string()));
+    }
+}
+
+/// Turn dictated text into macro steps
+pub fn parse(text: &str) -> KeyMacro {
+    let original: Vec<&str> = text.split_whitespace().collect();
+    let words: Vec<String> = original.iter().map(|w| normalize(w)).collect();
+    let mut steps = Vec::new();
+    let mut pending: Vec<&str> = Vec::new();
+    let mut i = 0;
+    while i < words.len() {
+        let pressed = words[i] == "press";
+        let start = if pressed { i + 1 } else { i };
+        if let Some((key, used)) = match_key(&words[start..], pressed) {
+            flush_text(&mut steps, &mut pending);
+            i = start + used;
+            let (count, used) = match_repeat(&words[i..]).unwrap_or((1, 0));
+            i += used;
+            steps.extend(std::iter::repeat_n(Step::Key(key), count));
+            continue;
+        }
+        pending.push(original[i]);
+        i += 1;
+    }
+    flush_text(&mut steps, &mut pending);
+    KeyMacro { version: MACRO_VERSION, source: text.to_string(), steps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Step {
+        Step::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_form_fill() {
+        let m = parse("Tab, tab, John Smith. Enter.");
+        assert_eq!(m.steps, vec![Step::Key(MacroKey::Tab), Step::Key(MacroKey::Tab), text("John Smith"), Step::Key(MacroKey::Enter)]);
+    }
+
+    #[test]
+    fn test_common_words_need_press() {
+        let m = parse("I will be home at the end of the day");
+        assert_eq!(m.steps, vec![text("I will be home at the end of the day")]);
+        let m = parse("press end press up");
+        assert_eq!(m.steps, vec![Step::Key(MacroKey::End), Step::Key(MacroKey::Up)]);
+    }
+
+    #[test]
+    fn test_repeat_and_multi_word_keys() {
+        let m = parse("page down three times arrow up twice");
+        let mut expected = vec![Step::Key(MacroKey::PageDown); 3];
+        expected.extend(vec![Step::Key(MacroKey::Up); 2]);
+        assert_eq!(m.steps, expected);
+        let m = parse("tab 100 times");
+        assert_eq!(m.steps.len(), MAX_REPEAT);
+    }
+
+    #[test]
+    fn test_press_without_key_is_text() {
+        let m = parse("press the button");
+        assert_eq!(m.steps, vec![text("press the button")]);
+    }
+
+    #[test]
+    fn test_serialized_form() {
+        let m = parse("tab hello");
+        let json = serde_json::to_value(&m.steps).unwrap();
+        assert_eq!(json, serde_json::json!([{ "key": "tab" }, { "text": "hello" }]));
+    }
+}
@@ -0,0 +1,209 @@
+// Per-utterance language for sessions that switch languages (e.g. English and Bangla). Each
+// segment is tagged with the language the provider reported or, failing that, the one its script
+// implies; Latin script falls back to the session's primary language. Consecutive segments in the
+// same language form a run, and each run is refined on its own so the mix survives refinement.
+// With the STT language on "auto", `detect` picks the language of a whole dictation the same way,
+// with common function words telling apart the Latin-script languages.
+
+use crate::transcript::Segment;
+
+/// Unicode blocks of scripts that identify a language well enough, with its ISO 639-1 code
+const SCRIPTS: &[(u32, u32, &str)] = &[
+    (0x0980, 0x09FF, "bn"),
+    (0x0900, 0x097F, "hi"),
+    (0x0A00, 0x0A7F, "pa"),
+    (0x0B80, 0x0BFF, "ta"),
+    (0x0600, 0x06FF, "ar"),
+    (0x0590, 0x05FF, "he"),
+    (0x0400, 0x04FF, "ru"),
+    (0x0370, 0x03FF, "el"),
+    (0x0E00, 0x0E7F, "th"),
+    (0xAC00, 0xD7AF, "ko"),
+    (0x3040, 0x30FF, "ja"),
+    (0x4E00, 0x9FFF, "zh"),
+];
+
+const NAMES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("bn", "Bangla"),
+    ("hi", "Hindi"),
+    ("pa", "Punjabi"),
+    ("ta", "Tamil"),
+    ("ar", "Arabic"),
+    ("he", "Hebrew"),
+    ("ru", "Russian"),
+    ("el", "Greek"),
+    ("th", "Thai"),
+    ("ko", "Korean"),
+    ("ja", "Japanese"),
+    ("zh", "Chinese"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("pt", "Portuguese"),
+    ("it", "Italian"),
+];
+
+/// Frequent short words of Latin-script languages; a dictation is in the one whose words it uses most
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "to", "of", "you", "that", "it", "with", "this", "have", "for", "not"]),
+    ("es", &["el", "la", "los", "las", "que", "y", "es", "de", "en", "por", "para", "con", "una", "pero", "muy"]),
+    ("fr", &["le", "la", "les", "et", "est", "que", "de", "des", "une", "pour", "avec", "pas", "je", "vous", "nous"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "ein", "eine", "mit", "zu", "auf", "wir", "sie", "auch"]),
+    ("pt", &["o", "os", "as", "que", "e", "é", "de", "não", "uma", "para", "com", "em", "você", "mas", "muito"]),
+    ("it", &["il", "lo", "gli", "che", "e", "è", "di", "non", "una", "per", "con", "sono", "ma", "anche", "molto"]),
+];
+/// Function words needed before trusting the Latin-script guess
+const MIN_FUNCTION_WORDS: usize = 2;
+
+/// English name of a language code, or the code itself
+pub fn name(code: &str) -> &str {
+    NAMES.iter().find(|(c, _)| c.eq_ignore_ascii_case(code)).map(|(_, n)| *n).unwrap_or(code)
+}
+
+/// Primary subtag of a language code ("en-US" -> "en")
+pub fn primary_subtag(code: &str) -> String {
+    code.split(['-', '_']).next().unwrap_or(code).trim().to_lowercase()
+}
+
+/// Language implied by the script most letters of `text` are in; None for Latin or no letters
+pub fn detect_script(text: &str) -> Option<&'static str> {
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+    let mut latin = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        let cp = c as u32;
+        match SCRIPTS.iter().find(|(lo, hi, _)| (*lo..=*hi).contains(&cp)) {
+            Some((_, _, code)) => match counts.iter_mut().find(|(l, _)| l == code) {
+                Some((_, n)) => *n += 1,
+                None => counts.push((code, 1)),
+            },
+            None => latin += 1,
+        }
+    }
+    counts.into_iter().filter(|(_, n)| *n > latin).max_by_key(|(_, n)| *n).map(|(code, _)| code)
+}
+
+/// Language of a whole dictation: its script, or for Latin script the language whose function
+/// words it uses most; None when there is too little to tell
+pub fn detect(text: &str) -> Option<&'static str> {
+    if let Some(code) = detect_script(text) {
+        return Some(code);
+    }
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+        .collect();
+    let score = |list: &[&str]| words.iter().filter(|w| list.contains(&w.as_str())).count();
+    let mut best: Option<(&'static str, usize)> = None;
+    for (code, list) in FUNCTION_WORDS {
+        let n = score(list);
+        // Ties keep the earlier (more common) language
+        if n >= MIN_FUNCTION_WORDS && best.is_none_or(|(_, b)| n > b) {
+            best = Some((code, n));
+        }
+    }
+    best.map(|(code, _)| code)
+}
+
+/// Language the provider tagged most of the text with, if it tagged any
+pub fn dominant(segments: &[Segment]) -> Option<String> {
+    let mut chars: Vec<(String, usize)> = Vec::new();
+    for seg in segments {
+        let Some(lang) = seg.language.as_deref().map(primary_subtag) else { continue };
+        match chars.iter_mut().find(|(l, _)| *l == lang) {
+            Some((_, n)) => *n += seg.text.len(),
+            None => chars.push((lang, seg.text.len())),
+        }
+    }
+    chars.into_iter().max_by_key(|(_, n)| *n).map(|(lang, _)| lang)
+}
+
+/// Fill in the language of segments the provider didn't tag
+pub fn tag_segments(segments: &mut [Segment], primary: &str) {
+    for seg in segments.iter_mut().filter(|s| s.language.is_none()) {
+        seg.language = Some(detect_script(&seg.text).map(str::to_string).unwrap_or_else(|| primary.to_string()));
+    }
+}
+
+/// Consecutive segments in the same language, joined: (language, text)
+pub fn runs(segments: &[Segment]) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = Vec::new();
+    for seg in segments {
+        let text = seg.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let lang = seg.language.as_deref().map(primary_subtag).unwrap_or_default();
+        match out.last_mut() {
+            Some((l, t)) if *l == lang => {
+                t.push(' ');
+                t.push_str(text);
+            }
+            _ => out.push((lang, text.to_string())),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(text: &str) -> Segment {
+        Segment { text: text.to_string(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_detect_script() {
+        assert_eq!(detect_script("আমি ভালো আছি"), Some("bn"));
+        assert_eq!(detect_script("hello there"), None);
+        // Mostly Bangla with an English word stays Bangla, and the other way round
+        assert_eq!(detect_script("আমার meeting আছে আজকে"), Some("bn"));
+        assert_eq!(detect_script("the ভাত was great today"), None);
+        assert_eq!(detect_script("123 !"), None);
+    }
+
+    #[test]
+    fn test_runs_group_languages() {
+        let mut segments = vec![seg("Hi team,"), seg("quick update."), seg("আজকে মিটিং হবে না"), seg("Thanks!")];
+        segments[1].language = Some("en-US".into());
+        tag_segments(&mut segments, "en");
+        assert_eq!(segments[2].language.as_deref(), Some("bn"));
+        assert_eq!(
+            runs(&segments),
+            vec![
+                ("en".to_string(), "Hi team, quick update.".to_string()),
+                ("bn".to_string(), "আজকে মিটিং হবে না".to_string()),
+                ("en".to_string(), "Thanks!".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(detect("the meeting is moved to Friday and I have the notes"), Some("en"));
+        assert_eq!(detect("la reunión es el viernes y tengo las notas"), Some("es"));
+        assert_eq!(detect("ich habe die Notizen und das ist nicht alles"), Some("de"));
+        assert_eq!(detect("nous avons les notes pour la réunion"), Some("fr"));
+        assert_eq!(detect("আমি ভালো আছি"), Some("bn"));
+        // A single word is not enough to go on
+        assert_eq!(detect("Kubernetes"), None);
+    }
+
+    #[test]
+    fn test_dominant() {
+        let mut segments = vec![seg("Hola a todos, la reunión es mañana"), seg("ok"), seg("untagged")];
+        segments[0].language = Some("es-419".into());
+        segments[1].language = Some("en".into());
+        assert_eq!(dominant(&segments).as_deref(), Some("es"));
+        assert_eq!(dominant(&[seg("nothing tagged")]), None);
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(name("bn"), "Bangla");
+        assert_eq!(name("xx"), "xx");
+        assert_eq!(primary_subtag("pt_BR"), "pt");
+    }
+}
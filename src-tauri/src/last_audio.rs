@@ -0,0 +1,72 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tracing::info;
+
+use crate::recording::{self, Recording};
+use crate::{history, stt};
+
+// The audio of the latest dictation, so a provider outage or a dropped socket doesn't lose it.
+// Relay sessions record what the HUD sends them; in direct mode the HUD sends a copy of the
+// audio just for this. It can be saved as a WAV file or sent to a provider again.
+
+static LAST_AUDIO: Mutex<Recording> = Mutex::new(Recording::new(recording::MAX_BYTES));
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct LastAudioInfo {
+  pub duration_ms: u64,
+  /// The start was dropped to stay under the 10 minute cap
+  pub truncated: bool,
+}
+
+/// Start recording a new dictation, dropping the previous one
+pub fn begin() {
+  LAST_AUDIO.lock().unwrap().clear();
+}
+
+/// Add linear16 PCM at `stt::SAMPLE_RATE`
+pub fn push(bytes: &[u8]) {
+  LAST_AUDIO.lock().unwrap().push(bytes);
+}
+
+pub fn info() -> Option<LastAudioInfo> {
+  let rec = LAST_AUDIO.lock().unwrap();
+  (!rec.is_empty()).then(|| LastAudioInfo { duration_ms: rec.duration_ms(stt::SAMPLE_RATE), truncated: rec.is_truncated() })
+}
+
+fn wav() -> Result<Vec<u8>, String> {
+  let mut rec = LAST_AUDIO.lock().unwrap();
+  if rec.is_empty() {
+    return Err("No recording to use: dictate something first".into());
+  }
+  Ok(recording::wav(rec.pcm(), stt::SAMPLE_RATE))
+}
+
+/// Write the last recording to `path`, or to the Downloads folder; returns the file path
+pub fn save(app: &AppHandle, path: Option<&Path>) -> Result<PathBuf, String> {
+  let wav = wav()?;
+  let path = match path {
+    Some(p) => p.to_path_buf(),
+    None => {
+      let dir = app.path().download_dir().or_else(|_| app.path().home_dir()).map_err(|e| e.to_string())?;
+      dir.join(format!("dictation-hud-recording-{}.wav", history::now_secs()))
+    }
+  };
+  std::fs::write(&path, wav).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+  info!("Saved last recording to {}", path.display());
+  Ok(path)
+}
+
+/// Transcribe the last recording in one request and keep the result in history
+pub async fn retranscribe(app: &AppHandle, cfg: stt::RelayConfig) -> Result<String, String> {
+  let wav = wav()?;
+  let text = stt::transcribe_file(&cfg, wav, "audio/wav", "recording.wav").await?;
+  if text.is_empty() {
+    return Err("No speech found in the last recording".into());
+  }
+  info!("Re-transcribed last recording with {} ({} chars)", cfg.provider.as_str(), text.len());
+  history::record(app, &text, None, false).map_err(|e| e.to_string())?;
+  Ok(text)
+}
@@ -0,0 +1,88 @@
+// Recent request latencies per provider, kept in memory for the diagnostics bundle.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Samples kept per provider
+pub const KEEP: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    ms: u64,
+    ok: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub provider: String,
+    pub samples: usize,
+    pub failures: usize,
+    pub last_ms: u64,
+    pub avg_ms: u64,
+    pub max_ms: u64,
+}
+
+static SAMPLES: Mutex<BTreeMap<String, VecDeque<Sample>>> = Mutex::new(BTreeMap::new());
+
+/// Note how long a request to `provider` took and whether it succeeded
+pub fn record(provider: &str, elapsed: Duration, ok: bool) {
+    let mut samples = SAMPLES.lock().unwrap();
+    let queue = samples.entry(provider.to_string()).or_default();
+    if queue.len() == KEEP {
+        queue.pop_front();
+    }
+    queue.push_back(Sample { ms: elapsed.as_millis() as u64, ok });
+}
+
+fn summarize(provider: &str, samples: &VecDeque<Sample>) -> Option<LatencySummary> {
+    let last = samples.back()?;
+    let total: u64 = samples.iter().map(|s| s.ms).sum();
+    Some(LatencySummary {
+        provider: provider.to_string(),
+        samples: samples.len(),
+        failures: samples.iter().filter(|s| !s.ok).count(),
+        last_ms: last.ms,
+        avg_ms: total / samples.len() as u64,
+        max_ms: samples.iter().map(|s| s.ms).max().unwrap_or(0),
+    })
+}
+
+/// One summary per provider that was called since startup
+pub fn summary() -> Vec<LatencySummary> {
+    let samples = SAMPLES.lock().unwrap();
+    samples.iter().filter_map(|(provider, queue)| summarize(provider, queue)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        record("test-summary", Duration::from_millis(100), true);
+        record("test-summary", Duration::from_millis(300), false);
+        let s = summary().into_iter().find(|s| s.provider == "test-summary").unwrap();
+        assert_eq!(s.samples, 2);
+        assert_eq!(s.failures, 1);
+        assert_eq!(s.last_ms, 300);
+        assert_eq!(s.avg_ms, 200);
+        assert_eq!(s.max_ms, 300);
+    }
+
+    #[test]
+    fn test_keeps_latest() {
+        for ms in 0..(KEEP as u64 + 5) {
+            record("test-keep", Duration::from_millis(ms), true);
+        }
+        let s = summary().into_iter().find(|s| s.provider == "test-keep").unwrap();
+        assert_eq!(s.samples, KEEP);
+        assert_eq!(s.last_ms, KEEP as u64 + 4);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(summarize("none", &VecDeque::new()), None);
+    }
+}
@@ -0,0 +1,161 @@
+// Learning from corrections: the refined text is diffed word by word against what the user
+// kept, and a replacement seen often enough becomes a suggestion, either a dictionary entry (the
+// same word, spelled or capitalized differently) or a replacement rule.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Times a correction must be seen before it is suggested
+pub const SUGGEST_AFTER: u32 = 3;
+/// Longer edits are rewrites, not corrections of a misheard term
+const MAX_SPAN_WORDS: usize = 4;
+/// Corrections kept; the least recently seen are dropped first
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LearnedCorrection {
+    pub from: String,
+    pub to: String,
+    pub count: u32,
+    /// Unix timestamp (seconds)
+    pub last_seen: u64,
+    /// The user turned the suggestion down; it is not offered again
+    #[serde(default)]
+    pub dismissed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionKind {
+    /// Add `to` to the dictionary
+    Dictionary,
+    /// Add a replacement rule `from` -> `to`
+    Rule,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Suggestion {
+    pub from: String,
+    pub to: String,
+    pub count: u32,
+    pub kind: SuggestionKind,
+}
+
+/// Words with surrounding punctuation removed, so "Tanjim," and "Tanjim." match
+fn words(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+/// Replaced word spans between `original` and `corrected` (longest common subsequence alignment).
+/// Pure insertions and deletions are left out; only "said X, meant Y" edits are useful.
+pub fn diff_spans(original: &str, corrected: &str) -> Vec<(String, String)> {
+    let (a, b) = (words(original), words(corrected));
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut spans = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let (mut from, mut to): (Vec<&str>, Vec<&str>) = (Vec::new(), Vec::new());
+    let mut flush = |from: &mut Vec<&str>, to: &mut Vec<&str>| {
+        if !from.is_empty() && !to.is_empty() && from.len() <= MAX_SPAN_WORDS && to.len() <= MAX_SPAN_WORDS {
+            spans.push((from.join(" "), to.join(" ")));
+        }
+        from.clear();
+        to.clear();
+    };
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            flush(&mut from, &mut to);
+            i += 1;
+            j += 1;
+        } else if j < b.len() && (i == a.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            to.push(b[j]);
+            j += 1;
+        } else {
+            from.push(a[i]);
+            i += 1;
+        }
+    }
+    flush(&mut from, &mut to);
+    spans
+}
+
+/// Count the corrections between `original` and `corrected`; returns how many were found
+pub fn record(entries: &mut Vec<LearnedCorrection>, original: &str, corrected: &str, now: u64) -> usize {
+    let spans = diff_spans(original, corrected);
+    for (from, to) in &spans {
+        match entries.iter_mut().find(|e| e.from.eq_ignore_ascii_case(from) && e.to == *to) {
+            Some(e) => {
+                e.count += 1;
+                e.last_seen = now;
+            }
+            None => entries.push(LearnedCorrection { from: from.clone(), to: to.clone(), count: 1, last_seen: now, dismissed: false }),
+        }
+    }
+    if entries.len() > MAX_ENTRIES {
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_seen));
+        entries.truncate(MAX_ENTRIES);
+    }
+    spans.len()
+}
+
+/// Corrections seen at least `SUGGEST_AFTER` times and not dismissed, most frequent first
+pub fn suggestions(entries: &[LearnedCorrection]) -> Vec<Suggestion> {
+    let mut out: Vec<Suggestion> = entries
+        .iter()
+        .filter(|e| e.count >= SUGGEST_AFTER && !e.dismissed)
+        .map(|e| Suggestion {
+            from: e.from.clone(),
+            to: e.to.clone(),
+            count: e.count,
+            kind: if e.from.eq_ignore_ascii_case(&e.to) { SuggestionKind::Dictionary } else { SuggestionKind::Rule },
+        })
+        .collect();
+    out.sort_by_key(|s| std::cmp::Reverse(s.count));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_spans() {
+        assert_eq!(
+            diff_spans("Ask tan jim about the mega LLM invoice.", "Ask Tanjim about the MegaLLM invoice."),
+            vec![("tan jim".to_string(), "Tanjim".to_string()), ("mega LLM".to_string(), "MegaLLM".to_string())]
+        );
+        // Punctuation-only edits, insertions and deletions are not corrections
+        assert!(diff_spans("Hello, world", "Hello world!").is_empty());
+        assert!(diff_spans("send it today", "send it to Bob today").is_empty());
+        // Rewrites are too long to learn from
+        assert!(diff_spans("a b c d e f", "u v w x y z").is_empty());
+    }
+
+    #[test]
+    fn test_suggestions_after_repeats() {
+        let mut entries = Vec::new();
+        for now in 0..2 {
+            record(&mut entries, "thanks tanjim", "thanks Tanjim", now);
+        }
+        assert!(suggestions(&entries).is_empty());
+        record(&mut entries, "ping tanjim", "ping Tanjim", 2);
+        for now in 0..4 {
+            record(&mut entries, "use java script", "use JavaScript", now);
+        }
+        let s = suggestions(&entries);
+        assert_eq!(s.len(), 2);
+        assert_eq!((s[0].to.as_str(), s[0].kind, s[0].count), ("JavaScript", SuggestionKind::Rule, 4));
+        assert_eq!((s[1].to.as_str(), s[1].kind), ("Tanjim", SuggestionKind::Dictionary));
+
+        entries.iter_mut().for_each(|e| e.dismissed = true);
+        assert!(suggestions(&entries).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+// Optional append-only spend ledger: one line per AI request with provider, model, tokens,
+// estimated cost and latency, written as CSV or JSON Lines to the app data dir so spend can be
+// audited with a spreadsheet or `jq`. Off by default; the format comes from behavior prefs.
+
+use serde::Serialize;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Jsonl,
+}
+
+impl Format {
+    /// "csv" or "jsonl"; anything else (normally "off") disables the ledger
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "csv" => Some(Format::Csv),
+            "jsonl" => Some(Format::Jsonl),
+            _ => None,
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Csv => "spend.csv",
+            Format::Jsonl => "spend.jsonl",
+        }
+    }
+}
+
+pub const LEDGER_FORMATS: &[&str] = &["off", "csv", "jsonl"];
+
+static FORMAT: Mutex<Option<Format>> = Mutex::new(None);
+
+pub fn configure(format: Option<Format>) {
+    *FORMAT.lock().unwrap() = format;
+}
+
+pub fn format() -> Option<Format> {
+    *FORMAT.lock().unwrap()
+}
+
+/// Approximate list prices in USD per million tokens (input, output), matched against the model
+/// id without its vendor prefix. More specific names come first.
+const PRICES: &[(&str, f64, f64)] = &[
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4-turbo", 10.00, 30.00),
+    ("gpt-4", 30.00, 60.00),
+    ("gpt-3.5-turbo", 0.50, 1.50),
+    ("gpt-oss-120b", 0.10, 0.50),
+    ("gpt-oss-20b", 0.05, 0.20),
+    ("claude-3.5-haiku", 0.80, 4.00),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("claude-3.5-sonnet", 3.00, 15.00),
+    ("claude-3.7-sonnet", 3.00, 15.00),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-flash-1.5", 0.075, 0.30),
+    ("llama-3.1-8b", 0.02, 0.05),
+    ("llama-3.3-70b", 0.12, 0.30),
+];
+
+/// Estimated cost of a request; None for models without a known price
+pub fn estimate_cost(model: &str, prompt_tokens: u64, completion_tokens: u64) -> Option<f64> {
+    if model.ends_with(":free") {
+        return Some(0.0);
+    }
+    let name = model.rsplit('/').next().unwrap_or(model).to_lowercase();
+    let (_, input, output) = PRICES.iter().find(|(prefix, _, _)| name.starts_with(prefix))?;
+    Some((prompt_tokens as f64 * input + completion_tokens as f64 * output) / 1_000_000.0)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    /// Unix timestamp (seconds)
+    pub timestamp: u64,
+    pub provider: String,
+    pub model: String,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+    /// Reported by the provider when it says, otherwise estimated from list prices
+    pub cost_usd: Option<f64>,
+    /// Whether `cost_usd` came from the provider
+    pub cost_reported: bool,
+    pub latency_ms: u64,
+}
+
+impl Entry {
+    /// Build an entry from an OpenAI-style chat completion response (`usage` object)
+    pub fn from_response(timestamp: u64, provider: &str, model: &str, response: &serde_json::Value, latency_ms: u64) -> Self {
+        let usage = &response["usage"];
+        let prompt_tokens = usage["prompt_tokens"].as_u64();
+        let completion_tokens = usage["completion_tokens"].as_u64();
+        let reported = usage["cost"].as_f64();
+        let cost_usd = reported.or_else(|| estimate_cost(model, prompt_tokens?, completion_tokens.unwrap_or(0)));
+        // Prefer the model the provider actually routed to
+        let model = response["model"].as_str().filter(|m| !m.is_empty()).unwrap_or(model);
+        Entry {
+            timestamp,
+            provider: provider.to_string(),
+            model: model.to_string(),
+            prompt_tokens,
+            completion_tokens,
+            cost_usd,
+            cost_reported: reported.is_some(),
+            latency_ms,
+        }
+    }
+}
+
+const CSV_HEADER: &str = "timestamp,provider,model,prompt_tokens,completion_tokens,cost_usd,cost_reported,latency_ms";
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn opt<T: ToString>(v: Option<T>) -> String {
+    v.map(|v| v.to_string()).unwrap_or_default()
+}
+
+/// One line of the ledger, without the trailing newline
+pub fn format_line(format: Format, entry: &Entry) -> String {
+    match format {
+        Format::Jsonl => serde_json::to_string(entry).unwrap_or_default(),
+        Format::Csv => [
+            entry.timestamp.to_string(),
+            csv_field(&entry.provider),
+            csv_field(&entry.model),
+            opt(entry.prompt_tokens),
+            opt(entry.completion_tokens),
+            opt(entry.cost_usd.map(|c| format!("{:.6}", c))),
+            entry.cost_reported.to_string(),
+            entry.latency_ms.to_string(),
+        ]
+        .join(","),
+    }
+}
+
+/// Append an entry to the ledger file in `dir`, writing the CSV header to a new file
+pub fn append(dir: &Path, format: Format, entry: &Entry) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format.file_name());
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let mut out = String::new();
+    if format == Format::Csv && file.metadata()?.len() == 0 {
+        out.push_str(CSV_HEADER);
+        out.push('\n');
+    }
+    out.push_str(&format_line(format, entry));
+    out.push('\n');
+    file.write_all(out.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry() -> Entry {
+        Entry {
+            timestamp: 1700000000,
+            provider: "openrouter".into(),
+            model: "openai/gpt-4o-mini".into(),
+            prompt_tokens: Some(1000),
+            completion_tokens: Some(200),
+            cost_usd: Some(0.00027),
+            cost_reported: false,
+            latency_ms: 850,
+        }
+    }
+
+    #[test]
+    fn test_estimate_cost() {
+        assert_eq!(estimate_cost("openai/gpt-oss-20b:free", 5000, 5000), Some(0.0));
+        let cost = estimate_cost("openai/gpt-4o-mini", 1_000_000, 1_000_000).unwrap();
+        assert!((cost - 0.75).abs() < 1e-9);
+        // gpt-4o-mini must not be priced as gpt-4o, nor gpt-4o as gpt-4
+        assert!(estimate_cost("gpt-4o", 1_000_000, 0).unwrap() < 3.0);
+        assert_eq!(estimate_cost("some/unknown-model", 10, 10), None);
+    }
+
+    #[test]
+    fn test_from_response_prefers_reported_cost() {
+        let resp = json!({"model": "openai/gpt-4o-mini-2024", "usage": {"prompt_tokens": 1000, "completion_tokens": 200, "cost": 0.5}});
+        let e = Entry::from_response(1, "openrouter", "openai/gpt-4o-mini", &resp, 10);
+        assert_eq!(e.cost_usd, Some(0.5));
+        assert!(e.cost_reported);
+        assert_eq!(e.model, "openai/gpt-4o-mini-2024");
+
+        let resp = json!({"usage": {"prompt_tokens": 1000, "completion_tokens": 200}});
+        let e = Entry::from_response(1, "megallm", "gpt-4o-mini", &resp, 10);
+        assert!(!e.cost_reported);
+        assert!((e.cost_usd.unwrap() - 0.00027).abs() < 1e-12);
+
+        let e = Entry::from_response(1, "megallm", "gpt-4o-mini", &json!({}), 10);
+        assert_eq!((e.prompt_tokens, e.cost_usd), (None, None));
+    }
+
+    #[test]
+    fn test_csv_line() {
+        assert_eq!(format_line(Format::Csv, &entry()), "1700000000,openrouter,openai/gpt-4o-mini,1000,200,0.000270,false,850");
+        let e = Entry { model: "a,\"b\"".into(), cost_usd: None, ..entry() };
+        assert_eq!(format_line(Format::Csv, &e), "1700000000,openrouter,\"a,\"\"b\"\"\",1000,200,,false,850");
+    }
+
+    #[test]
+    fn test_append_writes_header_once() {
+        let dir = std::env::temp_dir().join(format!("ledger-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        append(&dir, Format::Csv, &entry()).unwrap();
+        append(&dir, Format::Csv, &entry()).unwrap();
+        append(&dir, Format::Jsonl, &entry()).unwrap();
+        let csv = std::fs::read_to_string(dir.join("spend.csv")).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+        assert!(csv.starts_with("timestamp,"));
+        let jsonl = std::fs::read_to_string(dir.join("spend.jsonl")).unwrap();
+        let v: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+        assert_eq!(v["latency_ms"], 850);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
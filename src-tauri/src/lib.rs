@@ -1,63 +1,107 @@
+pub mod api_schema;
+pub mod audio_device;
+pub mod backup;
+pub mod builtin_packs;
 pub mod paste;
+pub mod paste_confirm;
+pub mod circuit;
+pub mod code_mode;
+pub mod combo;
 pub mod config;
+pub mod config_profiles;
+pub mod connectivity;
+pub mod control_api;
+pub mod dbus_control;
+pub mod correction;
+pub mod cost;
+pub mod cues;
+pub mod deep_link;
+pub mod demo;
+pub mod denoise;
+pub mod diagnostics;
+pub mod dictionary;
+pub mod failures;
+pub mod feedback;
+pub mod focus_probe;
+pub mod focused_monitor;
+pub mod gain;
+pub mod history;
 pub mod hotkey;
+pub mod hotkey_capture;
+pub mod http_client;
+pub mod http_retry;
+pub mod hud_event;
+pub mod hud_position;
+pub mod hud_window;
+pub mod instance;
+pub mod jobs;
+pub mod journal;
+pub mod key_inspect;
+pub mod keymacro;
+pub mod language;
+pub mod last_audio;
+pub mod latency;
+pub mod learning;
+pub mod ledger;
+pub mod locale;
+pub mod logging;
+pub mod maintenance;
+pub mod meeting;
+pub mod network_monitor;
+pub mod numbers;
+pub mod pack;
+pub mod pack_store;
+pub mod pii;
+pub mod pipeline;
+pub mod plugin;
+pub mod plugin_host;
+pub mod profiles;
 pub mod prompt;
+pub mod prompt_store;
+pub mod provider_usage;
+pub mod proxy;
+pub mod quota;
+pub mod recording;
+pub mod redact;
+pub mod scratchpad;
+pub mod script;
+pub mod session_metrics;
+pub mod settings_bundle;
+pub mod similarity;
+pub mod spell;
+pub mod startup;
+pub mod stt;
+pub mod stt_options;
+pub mod stream_inserter;
+pub mod subtitle;
 pub mod symbols;
+pub mod takes;
+pub mod transcript;
+pub mod tray_icon;
+pub mod typography;
+pub mod typing_rhythm;
+pub mod usage;
+pub mod vad;
+pub mod watch_folder;
+#[cfg(all(target_os = "linux", feature = "native-input"))]
+pub mod wayland_input;
+pub mod window_target;
 
 use std::time::{Duration, Instant};
-use std::sync::Mutex;
-use tauri::{Manager, menu::{Menu, MenuItem}, tray::{TrayIconBuilder, TrayIconEvent}, AppHandle, Emitter};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use tauri::{Manager, menu::{CheckMenuItem, IconMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu}, tray::{TrayIconBuilder, TrayIconEvent}, AppHandle};
 use tauri_plugin_store::StoreExt;
 use tauri_plugin_autostart::ManagerExt as _;
+use tauri_plugin_deep_link::DeepLinkExt;
 use serde::{Deserialize, Serialize};
+use hud_event::{emit_hud_event, HudEvent};
+use tracing::{debug, error, info, warn};
 
-// Helper for choosing which monitor the HUD should appear on.
-// On Windows, we try to use the monitor of the foreground window (focused app).
-// On other platforms, we fall back to Tauri's primary monitor.
-#[cfg(all(target_os = "windows", feature = "windows-monitor"))]
-mod focused_monitor {
-  use windows::Win32::Foundation::{POINT, RECT};
-  use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MonitorFromPoint, MONITOR_DEFAULTTONEAREST, MONITORINFO};
-  use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
-
-  /// Returns (left, top, width, height) of the work area of the monitor
-  /// containing the current mouse cursor, if available.
-  pub fn work_area_for_foreground_monitor() -> Option<(i32, i32, u32, u32)> {
-    unsafe {
-      // Use the current cursor position as a proxy for the "focused" monitor.
-      let mut pt = POINT { x: 0, y: 0 };
-      if GetCursorPos(&mut pt).is_err() {
-        return None;
-      }
-
-      let hmon = MonitorFromPoint(pt, MONITOR_DEFAULTTONEAREST);
-
-      let mut info = MONITORINFO {
-        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
-        ..Default::default()
-      };
-      if !GetMonitorInfoW(hmon, &mut info).as_bool() {
-        return None;
-      }
-
-      let RECT { left, top, right, bottom } = info.rcWork;
-      let width = (right - left) as u32;
-      let height = (bottom - top) as u32;
-      Some((left, top, width, height))
-    }
-  }
-}
-
-#[cfg(not(all(target_os = "windows", feature = "windows-monitor")))]
-mod focused_monitor {
-  pub fn work_area_for_foreground_monitor() -> Option<(i32, i32, u32, u32)> {
-    None
-  }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
 struct BehaviorPrefs {
   auto_paste: bool,
+  /// Stop after this many seconds of silence following speech (0 = never); needs the backend relay
   silence_secs: u32,
   stream_insert: bool,
   autostart: bool,
@@ -68,16 +112,222 @@ struct BehaviorPrefs {
   stt_provider: String, // "deepgram" | "elevenlabs"
   echo_cancellation: bool,
   noise_suppression: bool,
+  #[serde(default)]
+  code_mode: bool,
+  #[serde(default)]
+  spell_mode: bool,
+  /// Turn spoken "comma", "period", ... into punctuation. Line breaks and other symbols are
+  /// always converted.
+  #[serde(default = "default_true")]
+  spoken_punctuation: bool,
+  /// Turn spoken emoji names ("thumbs up", "fire emoji") into emoji
+  #[serde(default)]
+  emoji: bool,
+  /// Where the HUD appears on its monitor: one of hud_position::HUD_POSITIONS ("custom" is where
+  /// it was last dragged to on that monitor)
+  #[serde(default = "default_hud_position")]
+  hud_position: String,
+  /// Which monitor the HUD appears on: one of focused_monitor::POLICIES, or a monitor index
+  #[serde(default = "default_monitor_policy")]
+  monitor_policy: String,
+  /// Beep when the microphone goes live
+  #[serde(default)]
+  sound_start: bool,
+  /// Chime when recording stops
+  #[serde(default)]
+  sound_stop: bool,
+  /// Buzz when something fails (connection, refinement, paste)
+  #[serde(default)]
+  sound_error: bool,
+  /// Volume of the sound cues, 0-100
+  #[serde(default = "default_sound_volume")]
+  sound_volume: u32,
+  /// OS notification when something fails, e.g. the STT connection
+  #[serde(default)]
+  notify_errors: bool,
+  /// How spoken numbers, amounts and dates are written: one of numbers::NUMBER_MODES
+  #[serde(default = "default_numbers_mode")]
+  numbers_mode: String,
+  /// Stream audio through the backend STT relay instead of a WebSocket in the HUD
+  #[serde(default)]
+  stt_relay: bool,
+  /// Target language for translation mode (e.g. "Spanish"); None dictates as-is
+  #[serde(default)]
+  translate_to: Option<String>,
+  /// Scripted STT and refinement so the workflow can be tried without API keys
+  #[serde(default)]
+  demo_mode: bool,
+  /// Put the user's previous clipboard contents back after auto-paste
+  #[serde(default = "default_true")]
+  preserve_clipboard: bool,
+  /// Delay before restoring, so slow apps have read the pasted text
+  #[serde(default = "default_clipboard_restore_ms")]
+  clipboard_restore_ms: u32,
+  #[serde(default = "default_insert_mode")]
+  insert_mode: String, // one of INSERT_MODES
+  /// Delay between simulated keystrokes in type mode
+  #[serde(default = "default_type_delay_ms")]
+  type_delay_ms: u32,
+  /// Per-key delay range in human typing mode
+  #[serde(default = "default_human_min_ms")]
+  human_min_ms: u32,
+  #[serde(default = "default_human_max_ms")]
+  human_max_ms: u32,
+  /// Unload the HUD webview after this many idle seconds (0 keeps it loaded)
+  #[serde(default = "default_hud_idle_unload_secs")]
+  hud_idle_unload_secs: u32,
+  /// Replay the transcript word by word in the HUD after stopping, while refinement runs
+  #[serde(default)]
+  review_playback: bool,
+  /// Stop recording automatically after this many seconds (0 = no limit)
+  #[serde(default = "default_max_duration_secs")]
+  max_duration_secs: u32,
+  /// Watchdog: reset to Inactive if Starting lasts longer than this (0 disables)
+  #[serde(default = "default_watchdog_starting_secs")]
+  watchdog_starting_secs: u32,
+  /// Watchdog: reset to Inactive if Stopping (refinement + insertion) lasts longer than this (0 disables)
+  #[serde(default = "default_watchdog_stopping_secs")]
+  watchdog_stopping_secs: u32,
+  /// Wait for Enter before each of the first N auto-pastes (0 = never). Fresh installs start
+  /// with a few; prefs saved before this existed default to 0 so upgrades keep instant paste.
+  #[serde(default)]
+  confirm_first_pastes: u32,
+  /// Show the final text before every auto-paste: Enter pastes, Esc discards, E edits it first
+  #[serde(default)]
+  confirm_before_paste: bool,
+  /// Put focus back on the window a dictation started in before pasting, if it moved elsewhere
+  #[serde(default = "default_true")]
+  lock_target_window: bool,
+  /// Extra terminal process names (besides the built-in list) that paste with Ctrl+Shift+V
+  #[serde(default)]
+  terminal_apps: Vec<String>,
+  /// Categories masked before pasting (see `pii::CATEGORIES`); per-app profiles can override it
+  #[serde(default)]
+  redact: Vec<String>,
+  /// The user's find/replace rules, applied in order after refinement
+  #[serde(default)]
+  regex_rules: Vec<pipeline::RegexRule>,
+  /// Quote, ellipsis and em dash style of the refined text
+  #[serde(default)]
+  typography: typography::Typography,
+  /// Enabled plugins, by file name without `.wasm`
+  #[serde(default)]
+  plugins: Vec<String>,
+  /// "error" | "warn" | "info" | "debug" | "trace"
+  #[serde(default = "default_log_level")]
+  log_level: String,
+  /// Timeout for one refinement request; raise it for slow local models or long dictations
+  #[serde(default = "default_ai_timeout_secs")]
+  ai_timeout_secs: u32,
+  /// Least `max_tokens` sent with refinement requests, raised for long input (0 = provider
+  /// default)
+  #[serde(default = "default_ai_max_tokens")]
+  ai_max_tokens: u32,
+  /// Minimum word similarity (percent) between transcript and refinement; below it the AI output
+  /// is rejected as a rewrite. 0 disables the check
+  #[serde(default = "default_ai_min_similarity")]
+  ai_min_similarity: u32,
+  /// Tries per provider request, including the first (1 disables retries)
+  #[serde(default = "default_retry_attempts")]
+  retry_attempts: u32,
+  /// Wait before the first retry; doubles for each further one
+  #[serde(default = "default_retry_backoff_ms")]
+  retry_backoff_ms: u32,
+  /// Random spread around each wait, in percent
+  #[serde(default = "default_retry_jitter_pct")]
+  retry_jitter_pct: u32,
+  /// Append every AI request to a spend ledger in the app data dir: "off" | "csv" | "jsonl"
+  #[serde(default = "default_spend_ledger")]
+  spend_ledger: String,
+  /// Audio files dropped into this folder are transcribed automatically; None turns it off
+  #[serde(default)]
+  watch_folder: Option<String>,
+  /// Run watch-folder transcripts through refinement too
+  #[serde(default)]
+  watch_refine: bool,
+  /// "txt" | "md"
+  #[serde(default = "default_watch_output_format")]
+  watch_output_format: String,
+  /// File transcription jobs that run at the same time
+  #[serde(default = "default_transcription_concurrency")]
+  transcription_concurrency: u32,
+  /// Keep corrections reported through `report_correction` and suggest repeated ones
+  #[serde(default)]
+  learn_corrections: bool,
+  /// Tag each utterance with its language and refine each language on its own, for speakers who
+  /// switch languages mid-dictation (ignored in translation mode)
+  #[serde(default)]
+  mixed_language: bool,
+  /// Scale relayed audio toward a steady speech level (software gain stage, relay only)
+  #[serde(default)]
+  auto_gain: bool,
+  /// Suppress noise with RNNoise in the backend instead of the webview (audio goes through the
+  /// relay); only applies with `noise_suppression` on. Devices can override it
+  #[serde(default)]
+  rnnoise: bool,
+  /// Daily maintenance deletes history older than this many days (0 keeps it, up to the entry cap)
+  #[serde(default)]
+  history_retention_days: u32,
+  /// Daily maintenance also checks that the provider keys still work
+  #[serde(default)]
+  maintenance_health_ping: bool,
+  /// Serve the local HTTP control API (start/stop/toggle/status) on 127.0.0.1
+  #[serde(default)]
+  control_api: bool,
+  #[serde(default = "default_control_api_port")]
+  control_api_port: u16,
+  /// Append refined dictations to the journal file (`set_journal_target`): one of
+  /// journal::JOURNAL_MODES
+  #[serde(default = "default_journal_mode")]
+  journal_mode: String,
+  /// Warn when the Deepgram balance drops below this many dollars (0 = never)
+  #[serde(default = "default_low_credit_usd")]
+  low_credit_usd: u32,
+  /// Warn when less than this percentage of the ElevenLabs allowance is left (0 = never)
+  #[serde(default = "default_low_credit_percent")]
+  low_credit_percent: u32,
 }
 
 fn default_ai_provider() -> String { "openrouter".into() }
 fn default_stt_provider() -> String { "deepgram".into() }
+fn default_true() -> bool { true }
+fn default_clipboard_restore_ms() -> u32 { 1000 }
+fn default_sound_volume() -> u32 { 60 }
+fn default_hud_position() -> String { "bottom-center".into() }
+fn default_monitor_policy() -> String { "monitor-with-cursor".into() }
+fn default_insert_mode() -> String { "paste".into() }
+fn default_numbers_mode() -> String { "words".into() }
+/// paste: clipboard + Ctrl+V; type: simulated keystrokes; human: keystrokes at a randomized,
+/// human pace; macro: spoken key names become key presses
+const INSERT_MODES: &[&str] = &["paste", "type", "human", "macro"];
+fn default_type_delay_ms() -> u32 { 5 }
+fn default_human_min_ms() -> u32 { 40 }
+fn default_human_max_ms() -> u32 { 140 }
+fn default_hud_idle_unload_secs() -> u32 { 300 }
+fn default_max_duration_secs() -> u32 { 600 }
+fn default_watchdog_starting_secs() -> u32 { 30 }
+fn default_watchdog_stopping_secs() -> u32 { 90 }
+fn default_log_level() -> String { logging::DEFAULT_LEVEL.into() }
+fn default_ai_timeout_secs() -> u32 { 5 }
+fn default_ai_max_tokens() -> u32 { 2048 }
+fn default_ai_min_similarity() -> u32 { 50 }
+fn default_retry_attempts() -> u32 { http_retry::DEFAULT_POLICY.attempts }
+fn default_retry_backoff_ms() -> u32 { http_retry::DEFAULT_POLICY.backoff.as_millis() as u32 }
+fn default_retry_jitter_pct() -> u32 { http_retry::DEFAULT_POLICY.jitter_pct }
+fn default_spend_ledger() -> String { "off".into() }
+fn default_watch_output_format() -> String { "txt".into() }
+fn default_transcription_concurrency() -> u32 { jobs::DEFAULT_CONCURRENCY as u32 }
+fn default_control_api_port() -> u16 { control_api::DEFAULT_PORT }
+fn default_journal_mode() -> String { "off".into() }
+fn default_low_credit_usd() -> u32 { quota::DEFAULT_THRESHOLDS.usd }
+fn default_low_credit_percent() -> u32 { quota::DEFAULT_THRESHOLDS.percent }
+const DEFAULT_CONFIRM_FIRST_PASTES: u32 = 3;
 
 impl Default for BehaviorPrefs {
   fn default() -> Self {
     Self {
       auto_paste: true,
-      silence_secs: 2,
+      silence_secs: 0,
       stream_insert: false,
       autostart: false,
       ai_refine: true,
@@ -85,6 +335,63 @@ impl Default for BehaviorPrefs {
       stt_provider: default_stt_provider(),
       echo_cancellation: true,
       noise_suppression: true,
+      code_mode: false,
+      spell_mode: false,
+      spoken_punctuation: true,
+      emoji: false,
+      hud_position: default_hud_position(),
+      monitor_policy: default_monitor_policy(),
+      sound_start: false,
+      sound_stop: false,
+      sound_error: false,
+      sound_volume: default_sound_volume(),
+      notify_errors: false,
+      numbers_mode: default_numbers_mode(),
+      stt_relay: false,
+      translate_to: None,
+      demo_mode: false,
+      preserve_clipboard: true,
+      clipboard_restore_ms: default_clipboard_restore_ms(),
+      insert_mode: default_insert_mode(),
+      type_delay_ms: default_type_delay_ms(),
+      human_min_ms: default_human_min_ms(),
+      human_max_ms: default_human_max_ms(),
+      hud_idle_unload_secs: default_hud_idle_unload_secs(),
+      review_playback: false,
+      max_duration_secs: default_max_duration_secs(),
+      watchdog_starting_secs: default_watchdog_starting_secs(),
+      watchdog_stopping_secs: default_watchdog_stopping_secs(),
+      confirm_first_pastes: DEFAULT_CONFIRM_FIRST_PASTES,
+      confirm_before_paste: false,
+      lock_target_window: true,
+      terminal_apps: Vec::new(),
+      redact: Vec::new(),
+      regex_rules: Vec::new(),
+      typography: typography::Typography::default(),
+      plugins: Vec::new(),
+      log_level: default_log_level(),
+      ai_timeout_secs: default_ai_timeout_secs(),
+      ai_max_tokens: default_ai_max_tokens(),
+      ai_min_similarity: default_ai_min_similarity(),
+      retry_attempts: default_retry_attempts(),
+      retry_backoff_ms: default_retry_backoff_ms(),
+      retry_jitter_pct: default_retry_jitter_pct(),
+      spend_ledger: default_spend_ledger(),
+      watch_folder: None,
+      watch_refine: false,
+      watch_output_format: default_watch_output_format(),
+      transcription_concurrency: default_transcription_concurrency(),
+      learn_corrections: false,
+      mixed_language: false,
+      auto_gain: false,
+      rnnoise: false,
+      history_retention_days: 0,
+      maintenance_health_ping: false,
+      control_api: false,
+      control_api_port: default_control_api_port(),
+      journal_mode: default_journal_mode(),
+      low_credit_usd: default_low_credit_usd(),
+      low_credit_percent: default_low_credit_percent(),
     }
   }
 }
@@ -96,114 +403,206 @@ enum DictationState {
   Inactive,
   Starting,  // Microphone permission + WebSocket connecting
   Recording, // Actually recording
+  Paused,    // Recording on hold: the microphone stays open but its audio is dropped
   Stopping,  // Processing transcript + refinement
 }
 
 struct RecordingState {
   state: DictationState,
   start_time: Option<Instant>,
+  /// When the current state was entered, for the watchdog
+  since: Option<Instant>,
 }
 
 impl Default for RecordingState {
   fn default() -> Self {
-    Self { state: DictationState::Inactive, start_time: None }
+    Self { state: DictationState::Inactive, start_time: None, since: None }
+  }
+}
+
+impl DictationState {
+  /// "inactive" | "starting" | "recording" | "paused" | "stopping"
+  fn name(self) -> &'static str {
+    match self {
+      DictationState::Inactive => "inactive",
+      DictationState::Starting => "starting",
+      DictationState::Recording => "recording",
+      DictationState::Paused => "paused",
+      DictationState::Stopping => "stopping",
+    }
+  }
+}
+
+impl RecordingState {
+  fn set(&mut self, state: DictationState) {
+    if self.state != state {
+      dbus_control::state_changed(state.name());
+      feedback::state_changed(self.state.name(), state.name());
+      plugin_host::state_changed(self.state.name(), state.name());
+      sync_tray_dictation();
+      if state != DictationState::Inactive {
+        animate_tray();
+      }
+      match state {
+        // Resuming continues the same recording
+        DictationState::Recording if self.state != DictationState::Paused => session_metrics::recording(),
+        DictationState::Stopping => session_metrics::stopping(),
+        _ => {}
+      }
+    }
+    self.state = state;
+    self.since = Some(Instant::now());
   }
 }
 
-static RECORDING_STATE: Mutex<RecordingState> = Mutex::new(RecordingState { state: DictationState::Inactive, start_time: None });
+static RECORDING_STATE: Mutex<RecordingState> = Mutex::new(RecordingState { state: DictationState::Inactive, start_time: None, since: None });
+
+// Id of each dictation session, sent with the start event and carried in its transcript
+static NEXT_SESSION_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
 
 #[tauri::command]
 async fn start_dictation(app: AppHandle) -> Result<(), String> {
-  eprintln!("🚀🚀🚀 start_dictation COMMAND INVOKED 🚀🚀🚀");
+  debug!("start_dictation COMMAND INVOKED");
 
   // CRITICAL: Check if already starting/recording/stopping - prevent duplicates!
   {
     let state = RECORDING_STATE.lock().unwrap();
     match state.state {
       DictationState::Starting => {
-        eprintln!("⚠️ Already starting dictation, ignoring duplicate request");
+        warn!("Already starting dictation, ignoring duplicate request");
         return Err("already-starting".into());
       }
       DictationState::Recording => {
-        eprintln!("⚠️ Already recording, ignoring duplicate request");
+        warn!("Already recording, ignoring duplicate request");
         return Err("already-recording".into());
       }
+      DictationState::Paused => {
+        warn!("Dictation is paused, ignoring request");
+        return Err("currently-paused".into());
+      }
       DictationState::Stopping => {
-        eprintln!("⚠️ Currently stopping dictation, ignoring request");
+        warn!("Currently stopping dictation, ignoring request");
         return Err("currently-stopping".into());
       }
       DictationState::Inactive => {
-        eprintln!("✅ State is inactive, proceeding with start");
+        info!("State is inactive, proceeding with start");
       }
     }
   }
 
+  // The meeting window owns the microphone until its transcription is stopped
+  if meeting::is_active() {
+    emit_hud_event(&app, HudEvent::badge("Meeting transcription is running"));
+    return Err("meeting-running".into());
+  }
+
   // Set state to Starting IMMEDIATELY to prevent race conditions
   {
     let mut state = RECORDING_STATE.lock().unwrap();
-    state.state = DictationState::Starting;
-    eprintln!("🎯 State set to STARTING");
+    state.set(DictationState::Starting);
+    session_metrics::begin();
+    info!("State set to STARTING");
   }
 
+  // Resolve per-app overrides while the target app still has focus
+  profiles::begin_session(&app);
+
+  // Start reloading an unloaded HUD while the probe runs
+  hud_window::prewarm(&app);
+
   // Quick probe: optional. If not acceptable, emit badge and bail.
-  eprintln!("🔍 Probing if text field is accepting input...");
-  let can_paste = probe_text_accepting_impl(&app).await.unwrap_or(true);
-  eprintln!("Probe result: {}", if can_paste { "✅ can paste" } else { "❌ cannot paste" });
+  // With an explicit tray target the current focus is irrelevant; the target is activated at insertion time.
+  debug!("Probing if text field is accepting input...");
+  let focus = if window_target::get_target().is_some() {
+    focus_probe::FocusKind::Unknown
+  } else {
+    paste::probe_focus(&app).await.unwrap_or(focus_probe::FocusKind::Unknown)
+  };
+  debug!("Probe result: {}", if focus.accepts_text() { "can paste" } else { "cannot paste" });
+
+  if focus == focus_probe::FocusKind::Secure {
+    info!("Secure input field focused, refusing to dictate into it");
+    RECORDING_STATE.lock().unwrap().set(DictationState::Inactive);
+    emit_hud_event(&app, HudEvent::Badge { msg: "Secure field — dictation blocked".into(), kind: hud_event::BadgeKind::Secure });
+    failures::record(&app, failures::FailureKind::SecureField, "password field or secure keyboard entry");
+    return Err("secure-field".into());
+  }
 
-  if !can_paste {
-    eprintln!("❌ No text field focused, emitting badge and returning error");
+  if !focus.accepts_text() {
+    error!("No text field focused, emitting badge and returning error");
     // Reset state back to Inactive
     let mut state = RECORDING_STATE.lock().unwrap();
-    state.state = DictationState::Inactive;
-    app.emit_to("hud", "hud-badge", "No text field is focused").ok();
+    state.set(DictationState::Inactive);
+    emit_hud_event(&app, HudEvent::badge("No text field is focused"));
+    failures::record(&app, failures::FailureKind::NoFocus, "focus probe rejected start");
     return Err("no-focus".into());
   }
 
-  // Show HUD window
-  eprintln!("🪟 Getting HUD window...");
-  if let Some(win) = app.get_webview_window("hud") {
-    eprintln!("✅ HUD window found, positioning and showing it...");
-
-    // Position HUD at bottom-center of primary monitor
-    if let Ok(Some(monitor)) = win.primary_monitor() {
-      let monitor_size = monitor.size();
-      let hud_width = 600;
-      let hud_height = 120;
-      let x = (monitor_size.width as i32 - hud_width) / 2;
-      let y = monitor_size.height as i32 - hud_height - 60; // 60px from bottom
-      eprintln!("📍 Positioning HUD at x:{}, y:{} (monitor: {}x{})", x, y, monitor_size.width, monitor_size.height);
-      let _ = win.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
-    } else {
-      eprintln!("⚠️ Could not get primary monitor, using default position");
-    }
+  // Streaming insertion types into the focused field while the user speaks. Segments come through
+  // the backend relay; an explicit paste target or journal-only mode wait for the final text, and
+  // so do redaction and the paste preview, which need the final text before anything is typed
+  let behavior = effective_behavior(&app).await;
+  let streams = behavior.stream_insert
+    && behavior.stt_relay
+    && behavior.auto_paste
+    && behavior.journal_mode != "only"
+    && behavior.redact.is_empty()
+    && !behavior.confirm_before_paste;
+  match window_target::session_window().filter(|_| streams && window_target::get_target().is_none()) {
+    Some(window) => paste::begin_stream(window.id, Duration::from_millis(behavior.type_delay_ms as u64)),
+    None => paste::end_stream(&app),
+  }
 
-    // Try to reposition HUD based on the foreground (focused) window's monitor when available.
-    if let Some((left, top, width, height)) = focused_monitor::work_area_for_foreground_monitor() {
-      let hud_width = 600;
-      let hud_height = 60;
-      let x = left + ((width as i32 - hud_width) / 2);
-      let y = top + (height as i32 - hud_height - 60); // 60px from bottom of that monitor
-      eprintln!(
-        "?? Repositioning HUD to x:{}, y:{} (focused monitor work area: {}x{} at {},{})",
-        x, y, width, height, left, top
-      );
-      let _ = win.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+  // Show HUD window (recreated here if it was unloaded while idle)
+  info!("Getting HUD window...");
+  let hud = match hud_window::ensure_hud(&app).await {
+    Ok(win) => Some(win),
+    Err(e) => {
+      error!("Could not recreate HUD: {}", e);
+      None
     }
+  };
+  if let Some(win) = hud {
+    info!("HUD window found, positioning and showing it...");
 
+    // A hotkey press during move mode ends it without saving
+    if hud_window::is_moving() {
+      let _ = hud_window::finish_move(&app, false);
+    }
+    // On the monitor the monitor policy picks, else the primary one
+    let (position, policy) = get_behavior(app.clone())
+      .await
+      .map(|b| (b.hud_position, b.monitor_policy))
+      .unwrap_or_else(|_| (default_hud_position(), default_monitor_policy()));
+    let shown_in = focused_monitor::immediate_area(&app, &policy);
+    hud_window::place(&app, &win, &position, shown_in);
+    if focused_monitor::deferred(&policy) {
+      // Finding the focused window runs a helper process; move the HUD once it answers
+      let (app, win) = (app.clone(), win.clone());
+      tauri::async_runtime::spawn_blocking(move || {
+        let area = focused_monitor::work_area(&app, &policy);
+        if area.is_some() && area != shown_in && win.is_visible().unwrap_or(false) {
+          hud_window::place(&app, &win, &position, area);
+        }
+      });
+    }
 
     let _ = win.show();
+    session_metrics::hud_shown();
     let _ = win.set_always_on_top(true);
     // CRITICAL: DO NOT steal focus! User needs focus to stay on their text field
     // let _ = win.set_focus();
-    eprintln!("✅ HUD window shown, always on top (focus remains on text field)");
+    info!("HUD window shown, always on top (focus remains on text field)");
 
     // Emit start event immediately
-    eprintln!("🚀 Emitting dictation-start event...");
-    app.emit_to("hud", "dictation-start", ()).ok();
-    eprintln!("✅✅✅ start_dictation COMPLETED SUCCESSFULLY ✅✅✅");
+    let session_id = NEXT_SESSION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    info!("Emitting start event (session {})...", session_id);
+    emit_hud_event(&app, HudEvent::Start { session_id });
+    debug!("start_dictation COMPLETED SUCCESSFULLY");
     Ok(())
   } else {
-    eprintln!("❌ HUD window not found!");
+    error!("HUD window not found!");
+    RECORDING_STATE.lock().unwrap().set(DictationState::Inactive);
     return Err("hud-window-not-found".into());
   }
 }
@@ -214,122 +613,825 @@ async fn stop_dictation(app: AppHandle) -> Result<(), String> {
   if let Some(win) = app.get_webview_window("hud") {
     let _ = win.hide();
   }
+  hud_window::touch();
   Ok(())
 }
 
+/// The HUD page reports that its listeners are registered
+#[tauri::command]
+fn hud_ready() {
+  info!("HUD page ready");
+  hud_window::mark_ready();
+}
+
+/// Hotkey went down: start reloading the HUD before start_dictation needs it
+#[tauri::command]
+fn prewarm_hud(app: AppHandle) {
+  hud_window::prewarm(&app);
+}
+
+#[tauri::command]
+fn get_startup_metrics() -> startup::StartupMetrics {
+  startup::metrics()
+}
+
+/// The Settings webview registered the global hotkey (it owns registration)
+#[tauri::command]
+fn report_hotkey_registered(duration_ms: u64) {
+  if startup::metrics().hotkey_ready_ms.is_none() {
+    startup::record("hotkey_registration", Duration::from_millis(duration_ms));
+    startup::mark_hotkey_ready();
+  }
+}
+
+/// Work that doesn't need to block the first hotkey press: runs once the tray is up
+fn spawn_deferred_startup(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let started = Instant::now();
+    // The first fill of the "Dictate into…" menu, on every platform
+    let handle = app.clone();
+    let filled = tauri::async_runtime::spawn_blocking(move || refresh_target_menu(&handle)).await;
+    if let Ok(Err(e)) = filled { error!("Failed to refresh target menu: {}", e); }
+    TARGET_MENU_REFRESHED.store(history::now_millis(), std::sync::atomic::Ordering::Relaxed);
+    startup::record("window_list", started.elapsed());
+    spawn_hud_idle_unloader(app.clone());
+    spawn_state_watchdog(app.clone());
+    jobs::restore(&app);
+    watch_folder::spawn(app.clone());
+    maintenance::spawn(app.clone());
+    provider_usage::spawn(app.clone());
+    network_monitor::spawn(app.clone());
+    dbus_control::spawn(app.clone());
+    startup::mark_deferred_done();
+  });
+}
+
+/// Periodically unload the HUD webview once it has sat idle for the configured time
+fn spawn_hud_idle_unloader(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(Duration::from_secs(30)).await;
+      let idle_secs = get_behavior(app.clone()).await.map(|b| b.hud_idle_unload_secs).unwrap_or(0);
+      if idle_secs == 0 || RECORDING_STATE.lock().unwrap().state != DictationState::Inactive {
+        continue;
+      }
+      hud_window::unload_if_idle(&app, Duration::from_secs(idle_secs as u64));
+    }
+  });
+}
+
+/// Force the state machine back to Inactive when Starting or Stopping never finishes
+/// (e.g. the HUD crashed or hung), so the hotkey works again. Also enforces the maximum
+/// recording duration.
+fn spawn_state_watchdog(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(Duration::from_secs(2)).await;
+      let (state, elapsed) = {
+        let s = RECORDING_STATE.lock().unwrap();
+        (s.state, s.since.map(|t| t.elapsed()).unwrap_or_default())
+      };
+      if state == DictationState::Inactive {
+        continue;
+      }
+      let behavior = get_behavior(app.clone()).await.unwrap_or_default();
+      if state == DictationState::Recording {
+        enforce_max_duration(&app, behavior.max_duration_secs);
+        continue;
+      }
+      // A pause lasts until the user comes back to it
+      if state == DictationState::Paused {
+        continue;
+      }
+      // A paste waiting for confirmation (or an edit) has its own timeout
+      if state == DictationState::Stopping && paste_confirm::is_pending() {
+        continue;
+      }
+      let limit = match state {
+        DictationState::Starting => behavior.watchdog_starting_secs,
+        _ => behavior.watchdog_stopping_secs,
+      };
+      if limit == 0 || elapsed < Duration::from_secs(limit as u64) {
+        continue;
+      }
+      {
+        let mut s = RECORDING_STATE.lock().unwrap();
+        // The HUD may have finished while we loaded prefs
+        if s.state != state {
+          continue;
+        }
+        s.set(DictationState::Inactive);
+        s.start_time = None;
+      }
+      profiles::end_session();
+      paste::end_stream(&app);
+      let reason = format!("Dictation stuck in {:?} for {}s; reset", state, elapsed.as_secs());
+      info!("Watchdog: {}", reason);
+      if let Some(win) = app.get_webview_window("hud") {
+        let _ = win.hide();
+      }
+      hud_window::touch();
+      let code = if state == DictationState::Starting { "stuck_starting" } else { "stuck_stopping" };
+      emit_hud_event(&app, HudEvent::error(code, reason.as_str()));
+      failures::record(&app, failures::FailureKind::StuckState, &reason);
+    }
+  });
+}
+
+/// Stop a recording that ran past `max_duration_secs`, the same way the hotkey stops it
+fn enforce_max_duration(app: &AppHandle, max_secs: u32) {
+  if max_secs == 0 {
+    return;
+  }
+  let over = RECORDING_STATE.lock().unwrap().start_time.is_some_and(|t| t.elapsed() >= Duration::from_secs(max_secs as u64));
+  if over && begin_auto_stop() {
+    info!("Max recording duration ({}s) reached, stopping", max_secs);
+    emit_hud_event(app, HudEvent::badge("Max duration reached"));
+    emit_hud_event(app, HudEvent::Stop);
+  }
+}
+
+/// Stop a recording once the backend VAD has heard `silence_secs` of silence after speech. The
+/// relay has already been asked to finalize; the HUD finishes the session as for the hotkey.
+fn stop_after_silence(app: &AppHandle) {
+  if begin_auto_stop() {
+    info!("Silence detected, stopping");
+    emit_hud_event(app, HudEvent::Stop);
+  }
+}
+
+/// Move a running recording to Stopping; false if it wasn't recording
+fn begin_auto_stop() -> bool {
+  let mut s = RECORDING_STATE.lock().unwrap();
+  if s.state != DictationState::Recording {
+    return false;
+  }
+  // Stopping from here on, so a HUD that never reacts is caught by the Stopping watchdog
+  s.set(DictationState::Stopping);
+  true
+}
+
 #[tauri::command]
 fn is_dictation_active(_app: AppHandle) -> Result<bool, String> {
-  eprintln!("🔍 is_dictation_active COMMAND INVOKED");
+  debug!("is_dictation_active COMMAND INVOKED");
   let state = RECORDING_STATE.lock().unwrap();
   // CRITICAL: Return true for ANY non-Inactive state to prevent duplicate starts/stops
   // Starting: microphone initializing + WebSocket connecting
   // Recording: actively recording
+  // Paused: recording on hold
   // Stopping: processing transcript + refinement
   let is_active = !matches!(state.state, DictationState::Inactive);
-  eprintln!("Recording state: {:?} -> {}", state.state, if is_active { "🔴 ACTIVE" } else { "⚪ INACTIVE" });
+  debug!("Recording state: {:?} -> {}", state.state, if is_active { "active" } else { "inactive" });
   Ok(is_active)
 }
 
 #[tauri::command]
-fn set_recording_active(_app: AppHandle, new_state: String) -> Result<(), String> {
-  eprintln!("🎯 set_recording_active COMMAND INVOKED: {}", new_state);
+fn set_recording_active(app: AppHandle, new_state: String) -> Result<(), String> {
+  info!("set_recording_active COMMAND INVOKED: {}", new_state);
   let mut state = RECORDING_STATE.lock().unwrap();
-
   match new_state.as_str() {
     "recording" => {
-      state.state = DictationState::Recording;
+      state.set(DictationState::Recording);
       state.start_time = Some(Instant::now());
-      eprintln!("✅ State set to RECORDING");
+      info!("State set to RECORDING");
     }
     "stopping" => {
-      state.state = DictationState::Stopping;
-      eprintln!("✅ State set to STOPPING");
+      state.set(DictationState::Stopping);
+      info!("State set to STOPPING");
     }
     "inactive" => {
-      state.state = DictationState::Inactive;
+      state.set(DictationState::Inactive);
       state.start_time = None;
-      eprintln!("✅ State set to INACTIVE");
+      profiles::end_session();
+      paste::end_stream(&app);
+      // Canceled or discarded after recording: the audio was still streamed to the provider
+      if let Some((duration_ms, ai_usd)) = session_metrics::abandon() {
+        let app = app.clone();
+        tauri::async_runtime::spawn(async move {
+          let Ok(behavior) = get_behavior(app.clone()).await else { return };
+          let provider = if behavior.demo_mode { "demo" } else { behavior.stt_provider.as_str() };
+          let rate = stt_rate(&app, provider).await;
+          session_metrics::record_cost(&app, duration_ms, &cost::DictationCost::new(duration_ms, rate, ai_usd));
+        });
+      }
+      info!("State set to INACTIVE");
     }
     _ => {
-      eprintln!("❌ Invalid state: {}", new_state);
+      error!("Invalid state: {}", new_state);
       return Err(format!("Invalid state: {}", new_state));
     }
   }
-
   Ok(())
 }
 
 #[tauri::command]
 async fn trigger_stop_dictation(app: AppHandle) -> Result<(), String> {
-  eprintln!("🛑 trigger_stop_dictation COMMAND INVOKED");
+  info!("trigger_stop_dictation COMMAND INVOKED");
   // Emit event to HUD to trigger stop
-  app.emit_to("hud", "dictation-stop", ()).ok();
-  eprintln!("✅ dictation-stop event emitted to HUD");
+  emit_hud_event(&app, HudEvent::Stop);
+  info!("stop event emitted to HUD");
+  Ok(())
+}
+
+/// Ask the HUD to stop, if a session is running
+async fn stop_running_dictation(app: &AppHandle) -> Result<(), String> {
+  if !is_dictation_active(app.clone())? {
+    return Err("Not dictating".into());
+  }
+  trigger_stop_dictation(app.clone()).await
+}
+
+/// Start a session, or stop the running one, like the hotkey
+async fn toggle_dictation(app: AppHandle) -> Result<(), String> {
+  if is_dictation_active(app.clone())? {
+    trigger_stop_dictation(app).await
+  } else {
+    start_dictation(app).await
+  }
+}
+
+fn dictation_state_name() -> &'static str {
+  RECORDING_STATE.lock().unwrap().state.name()
+}
+
+/// Discard the utterance being dictated and start a fresh take (retake hotkey)
+#[tauri::command]
+async fn retake_dictation(app: AppHandle) -> Result<(), String> {
+  if RECORDING_STATE.lock().unwrap().state != DictationState::Recording {
+    return Err("Not dictating".into());
+  }
+  info!("Retake requested");
+  emit_hud_event(&app, HudEvent::Retake);
+  Ok(())
+}
+
+/// Put the recording on hold: audio is dropped (the provider connection stays open) until
+/// `resume_dictation`
+#[tauri::command]
+fn pause_dictation(app: AppHandle) -> Result<(), String> {
+  {
+    let mut s = RECORDING_STATE.lock().unwrap();
+    if s.state != DictationState::Recording {
+      return Err("Not recording".into());
+    }
+    s.set(DictationState::Paused);
+  }
+  stt::set_paused(true);
+  info!("Dictation paused");
+  emit_hud_event(&app, HudEvent::Paused { paused: true });
+  Ok(())
+}
+
+#[tauri::command]
+fn resume_dictation(app: AppHandle) -> Result<(), String> {
+  {
+    let mut s = RECORDING_STATE.lock().unwrap();
+    if s.state != DictationState::Paused {
+      return Err("Not paused".into());
+    }
+    // The pause doesn't count toward the recording time or the maximum duration
+    let paused_for = s.since.map(|t| t.elapsed()).unwrap_or_default();
+    s.start_time = s.start_time.map(|t| t + paused_for);
+    s.set(DictationState::Recording);
+  }
+  stt::set_paused(false);
+  info!("Dictation resumed");
+  emit_hud_event(&app, HudEvent::Paused { paused: false });
   Ok(())
 }
 
+/// Pause a recording or resume a paused one (pause hotkey); true if it is now paused
+#[tauri::command]
+fn toggle_pause_dictation(app: AppHandle) -> Result<bool, String> {
+  let state = RECORDING_STATE.lock().unwrap().state;
+  match state {
+    DictationState::Recording => pause_dictation(app).map(|_| true),
+    DictationState::Paused => resume_dictation(app).map(|_| false),
+    _ => Err("Not recording".into()),
+  }
+}
+
+/// Keep a take of the current session (set aside by a retake, or the one that was used)
+#[tauri::command]
+fn save_take(app: AppHandle, transcript: transcript::Transcript) -> usize {
+  let count = takes::record(transcript);
+  info!("Saved take {} of session", count);
+  if let Err(e) = refresh_takes_menu(&app) { error!("Failed to refresh takes menu: {}", e); }
+  count
+}
+
+#[tauri::command]
+fn list_takes() -> Vec<transcript::Transcript> {
+  takes::list()
+}
+
+/// Refine and insert an earlier take of the latest session
+#[tauri::command]
+async fn use_take(app: AppHandle, index: usize) -> Result<bool, String> {
+  let take = takes::get(index).ok_or_else(|| format!("No take {}", index + 1))?;
+  info!("Using take {}", index + 1);
+  let refined = refine_text(take, app.clone(), None, None, None).await?;
+  insert_text(app, refined, None).await
+}
+
+/// Run a transcript through the refinement pipeline; the result is stored in `refined`
 #[tauri::command]
 async fn refine_text(
+  mut transcript: transcript::Transcript,
+  app: AppHandle,
+  openrouter_key: Option<String>,
+  megallm_key: Option<String>,
+  provider: Option<String>,
+) -> Result<transcript::Transcript, String> {
+  info!(
+    "Transcript: {} segments from {}, confidence {:?}",
+    transcript.segments.len(),
+    transcript.provider,
+    transcript.confidence()
+  );
+  session_metrics::transcript_final();
+  let started = Instant::now();
+  let behavior = effective_behavior(&app).await;
+  let refined = if behavior.mixed_language && behavior.translate_to.is_none() {
+    // With "auto" the provider tags segments itself; untagged ones are then taken as English
+    let primary = main_language(&app, &behavior).await;
+    language::tag_segments(&mut transcript.segments, &primary);
+    let runs = language::runs(&transcript.segments);
+    info!("Mixed-language refinement: {:?}", runs.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>());
+    // Each run keeps its own language; the session's main language needs no hint
+    let mut parts = Vec::with_capacity(runs.len());
+    for (lang, text) in runs {
+      let hint = (lang != primary).then_some(lang.as_str());
+      parts.push(refine_impl_in(text, app.clone(), openrouter_key.clone(), megallm_key.clone(), provider.clone(), hint).await?);
+    }
+    parts.join(" ")
+  } else if let Some(detected) = detect_dictation_language(&app, &behavior, &transcript).await {
+    info!("Detected dictation language: {}", detected);
+    transcript.language.get_or_insert_with(|| detected.clone());
+    refine_impl_in(transcript.text(), app, openrouter_key, megallm_key, provider, Some(&detected)).await?
+  } else {
+    refine_impl(transcript.text(), app, openrouter_key, megallm_key, provider).await?
+  };
+  // Translation mode reports the spoken language
+  if transcript.language.is_none() {
+    transcript.language = LAST_TRANSLATION.lock().unwrap().as_ref().and_then(|t| t.source.clone());
+  }
+  // Last, so nothing the model writes gets past it
+  let refined = if behavior.redact.is_empty() { refined } else { pii::redact(&refined, &behavior.redact) };
+  transcript.refined = Some(refined);
+  session_metrics::refined(started.elapsed().as_millis() as u64);
+  Ok(transcript)
+}
+
+/// The session's main language: the STT language, or English when it is on "auto"
+async fn main_language(app: &AppHandle, behavior: &BehaviorPrefs) -> String {
+  Some(config::get_stt_language(app, &behavior.stt_provider).await)
+    .filter(|l| l != stt_options::AUTO_LANGUAGE)
+    .unwrap_or_else(|| "en".into())
+}
+
+async fn refine_impl(
   raw_text: String,
   app: AppHandle,
   openrouter_key: Option<String>,
   megallm_key: Option<String>,
   provider: Option<String>,
 ) -> Result<String, String> {
-  // Step 1: Symbol replacement layer (STT -> symbols)
-  let with_symbols = symbols::replace_symbols(&raw_text);
-  eprintln!("📝 After symbol replacement: \"{}\" -> \"{}\"", raw_text, with_symbols);
+  refine_impl_in(raw_text, app, openrouter_key, megallm_key, provider, None).await
+}
 
-  // Step 2: Check if AI refinement is enabled
-  let behavior = get_behavior(app.clone()).await.unwrap_or_default();
+/// With the STT language on "auto", the language of a dictation: what the provider reported, or
+/// failing that what its text looks like
+async fn detect_dictation_language(app: &AppHandle, behavior: &BehaviorPrefs, transcript: &transcript::Transcript) -> Option<String> {
+  if behavior.translate_to.is_some() || config::get_stt_language(app, &behavior.stt_provider).await != stt_options::AUTO_LANGUAGE {
+    return None;
+  }
+  transcript
+    .language
+    .as_deref()
+    .map(language::primary_subtag)
+    .or_else(|| language::dominant(&transcript.segments))
+    .or_else(|| language::detect(&transcript.text()).map(str::to_string))
+}
+
+/// `refine_impl` for text in `language` (a code such as "bn"), which the model keeps rather
+/// than translating into the session's main language; the language's own prompt and writing
+/// conventions apply
+async fn refine_impl_in(
+  raw_text: String,
+  app: AppHandle,
+  openrouter_key: Option<String>,
+  megallm_key: Option<String>,
+  provider: Option<String>,
+  language: Option<&str>,
+) -> Result<String, String> {
+  let mut trace = pipeline::Trace::new(&raw_text);
+  refine_traced(app, openrouter_key, megallm_key, provider, language, &mut trace).await
+}
+
+/// Run the pipeline stages over `trace`'s input, recording each stage's output in it
+async fn refine_traced(
+  app: AppHandle,
+  openrouter_key: Option<String>,
+  megallm_key: Option<String>,
+  provider: Option<String>,
+  language: Option<&str>,
+  trace: &mut pipeline::Trace,
+) -> Result<String, String> {
+  let behavior = effective_behavior(&app).await;
+  let active_pack = pack_store::active_pack(&app);
+  // Numbers and curly quotes follow the dictation's language; with the STT language on
+  // "auto" and nothing detected, the English way
+  let locale = match language {
+    Some(code) => code.to_string(),
+    None => main_language(&app, &behavior).await,
+  };
+  run_local_stages(&behavior, &active_pack, &locale, trace);
+  let refined = refine_with_model(&app, &behavior, &active_pack, openrouter_key, megallm_key, provider, language, trace).await?;
+  let plugged = run_plugin_stage(&app, &refined, trace);
+  let scripted = run_script_stage(&app, &plugged, language, trace);
+  let typeset = behavior.typography.apply(&scripted, Some(&locale));
+  if trace.record(pipeline::Stage::Typography, &typeset) {
+    debug!("After typography: \"{}\"", typeset);
+  }
+
+  // Last before redaction: the user's rules have the final word over what the model wrote
+  let ruled = pipeline::apply_rules(&typeset, &behavior.regex_rules);
+  if trace.record(pipeline::Stage::Rules, &ruled) {
+    debug!("After regex rules: \"{}\"", ruled);
+  }
+  Ok(ruled)
+}
+
+/// The plugins stage; a failing plugin is skipped
+fn run_plugin_stage(app: &AppHandle, text: &str, trace: &mut pipeline::Trace) -> String {
+  let (output, errors) = plugin_host::process_text(app, text);
+  if !errors.is_empty() {
+    emit_hud_event(app, HudEvent::badge(format!("{} plugin(s) failed and were skipped", errors.len())));
+  }
+  if trace.record(pipeline::Stage::Plugins, &output) {
+    debug!("After plugins: \"{}\"", output);
+  }
+  output
+}
+
+/// Plugins in the plugins folder, enabled (the `plugins` pref) or not
+#[tauri::command]
+fn list_plugins(app: AppHandle) -> Result<PluginList, String> {
+  Ok(PluginList { dir: plugin_host::plugins_dir(&app)?.to_string_lossy().into_owned(), plugins: plugin_host::list(&app)? })
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+struct PluginList {
+  /// Where plugin files go
+  dir: String,
+  plugins: Vec<plugin::PluginInfo>,
+}
+
+fn script_path(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+  Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join(script::FILE_NAME))
+}
+
+/// The script stage, when a script is in place; a failing script leaves the text as it was
+fn run_script_stage(app: &AppHandle, text: &str, language: Option<&str>, trace: &mut pipeline::Trace) -> String {
+  let Some(source) = script_path(app).ok().and_then(|p| std::fs::read_to_string(p).ok()) else {
+    return text.to_string();
+  };
+  let app_key = profiles::session_app();
+  let ctx = script::Context { app: app_key.as_deref(), language };
+  let output = match script::run(&source, text, &ctx) {
+    Ok(output) => output,
+    Err(e) => {
+      warn!("{}", e);
+      emit_hud_event(app, HudEvent::badge("Script failed, text left as is"));
+      text.to_string()
+    }
+  };
+  if trace.record(pipeline::Stage::Script, &output) {
+    debug!("After script: \"{}\"", output);
+  }
+  output
+}
+
+/// Show the HUD on its own to be dragged to a new spot; `finish_hud_move` saves or discards it
+#[tauri::command]
+async fn start_hud_move(app: AppHandle) -> Result<(), String> {
+  if RECORDING_STATE.lock().unwrap().state != DictationState::Inactive {
+    return Err("Can't move the HUD while dictating".into());
+  }
+  let behavior = get_behavior(app.clone()).await?;
+  hud_window::start_move(&app, &behavior.hud_position, focused_monitor::work_area(&app, &behavior.monitor_policy)).await
+}
+
+/// End HUD move mode; with `save`, the HUD's spot is kept for its monitor and `hud_position`
+/// becomes "custom"
+#[tauri::command]
+async fn finish_hud_move(app: AppHandle, save: bool) -> Result<(), String> {
+  if hud_window::finish_move(&app, save)?.is_some() {
+    set_behavior(app, serde_json::json!({ "hud_position": "custom" })).await?;
+  }
+  Ok(())
+}
+
+/// Connected monitors, for picking one to pin the HUD to
+#[tauri::command]
+fn list_monitors(app: AppHandle) -> Vec<focused_monitor::MonitorInfo> {
+  focused_monitor::list(&app)
+}
+
+/// Play a sound cue at the volume pref, enabled or not, so it can be tried in Settings
+#[tauri::command]
+fn preview_cue(app: AppHandle, cue: cues::Cue) {
+  feedback::preview(&app, cue);
+}
+
+/// Where the script stage's script goes, and whether it parses (None while there is none)
+#[tauri::command]
+fn get_script_status(app: AppHandle) -> Result<ScriptStatus, String> {
+  let path = script_path(&app)?;
+  let error = std::fs::read_to_string(&path).ok().map(|source| script::check(&source).err());
+  Ok(ScriptStatus { path: path.to_string_lossy().into_owned(), present: error.is_some(), error: error.flatten() })
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+struct ScriptStatus {
+  path: String,
+  present: bool,
+  /// Parse error of the script in place
+  error: Option<String>,
+}
+
+/// The stages before the model (pipeline::LOCAL_STAGES), in order
+fn run_local_stages(behavior: &BehaviorPrefs, active_pack: &pack::Pack, locale: &str, trace: &mut pipeline::Trace) {
+  use pipeline::Stage;
+  for &stage in pipeline::LOCAL_STAGES {
+    let text = trace.current();
+    let output = match stage {
+      // Spoken punctuation, symbols and the custom and enabled pack symbols
+      Stage::Symbols => {
+        let custom_symbols: Vec<(String, String)> = active_pack.symbols.iter().map(|m| (m.from.clone(), m.to.clone())).collect();
+        symbols::replace_symbols_custom(text, behavior.spoken_punctuation, &custom_symbols)
+      }
+      // Emoji names, with the custom and enabled pack ones
+      Stage::Emoji if behavior.emoji => {
+        let custom_emoji: Vec<(String, String)> = active_pack.emoji.iter().map(|m| (m.from.clone(), m.to.clone())).collect();
+        symbols::replace_emoji(text, &custom_emoji)
+      }
+      // "..., correction: <phrase>" replaces the closest earlier phrase; done here rather than by
+      // the model so it also works with refinement off
+      Stage::Correction => correction::apply_corrections(text),
+      // Letter-by-letter dictation becomes literal characters ("spell mode on ... spell mode off"
+      // regions, or everything when the pref is on)
+      Stage::Spell => spell::apply_spell_mode(text, behavior.spell_mode),
+      // Spoken casing commands become identifiers
+      Stage::Code if behavior.code_mode => code_mode::apply_casing_commands(text),
+      // Spoken numbers, amounts and dates in the locale's style, so the model gets them ready-made
+      Stage::Numbers if behavior.numbers_mode != "words" => numbers::format_numbers(text, &behavior.numbers_mode, locale),
+      // Snippets and rules from the custom and enabled packs
+      Stage::Packs => active_pack.apply_text(text),
+      _ => continue,
+    };
+    if trace.record(stage, &output) {
+      debug!("After {}: \"{}\"", stage.name(), output);
+    }
+  }
+}
+
+/// The refine and validate stages: the model's (or demo mode's) take on the locally processed text
+#[allow(clippy::too_many_arguments)]
+async fn refine_with_model(
+  app: &AppHandle,
+  behavior: &BehaviorPrefs,
+  active_pack: &pack::Pack,
+  openrouter_key: Option<String>,
+  megallm_key: Option<String>,
+  provider: Option<String>,
+  language: Option<&str>,
+  trace: &mut pipeline::Trace,
+) -> Result<String, String> {
+  use pipeline::Stage;
+  let with_symbols = trace.current().to_string();
+
+  // Demo mode: rule-based stand-in for the LLM, with a short delay so the HUD flow looks real
+  if behavior.demo_mode {
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let refined = demo::fake_refine(&with_symbols);
+    debug!("Demo refinement: \"{}\" -> \"{}\"", with_symbols, refined);
+    trace.record(Stage::Refine, &refined);
+    return Ok(refined);
+  }
 
-  if !behavior.ai_refine {
-    eprintln!("🔕 AI refinement DISABLED, returning symbol-replaced text");
+  // Check if AI refinement is enabled (translation needs the LLM even when refinement is off)
+  let translate_to = behavior.translate_to.clone();
+  if !behavior.ai_refine && translate_to.is_none() {
+    info!("AI refinement DISABLED, returning symbol-replaced text");
     return Ok(with_symbols);
   }
 
+  // Without a connection the request would only time out; clean up locally instead
+  if !network_monitor::is_online() {
+    info!("Offline, skipping AI refinement");
+    return Ok(offline_cleanup(app, &with_symbols, trace));
+  }
+
   let chosen_provider = provider
     .map(|p| p.to_lowercase())
     .unwrap_or_else(|| behavior.ai_provider.clone());
   let provider = if chosen_provider == "megallm" { "megallm" } else { "openrouter" };
 
-  eprintln!("🤖 AI refinement ENABLED using provider={}", provider);
+  info!("AI refinement ENABLED using provider={}", provider);
 
-  // Step 3: Send to AI for refinement
-  match provider {
-    "megallm" => refine_with_megallm(with_symbols, app, megallm_key).await,
-    _ => refine_with_openrouter(with_symbols, app, openrouter_key).await,
+  // Send to AI for refinement (and translation, in the same call)
+  let mut system_prompt = refinement_system_prompt(app, behavior.code_mode, language);
+  if let Some(suffix) = active_pack.prompt_suffix(&dictionary::words()) {
+    system_prompt.push_str(&suffix);
+  }
+  if let Some(target) = &translate_to {
+    info!("Translation mode: target={}", target);
+    system_prompt = prompt::build_translation_prompt(&system_prompt, target);
+  } else if let Some(code) = language {
+    system_prompt = prompt::build_language_prompt(&system_prompt, language::name(code));
+    if let Some(section) = locale::prompt_section(code, &main_language(app, behavior).await) {
+      system_prompt.push_str(&section);
+    }
+  }
+  // A provider that keeps failing is skipped for a while instead of costing a timeout each time
+  let provider_name = if provider == "megallm" { "MegaLLM" } else { "OpenRouter" };
+  if !circuit::allow(provider, Instant::now()) {
+    let wait = circuit::remaining(provider, Instant::now()).map(|d| d.as_secs()).unwrap_or(0);
+    info!("{} circuit open ({}s left), using local cleanup", provider, wait);
+    emit_hud_event(app, HudEvent::badge(format!("{} paused after failures, using basic cleanup", provider_name)));
+    let cleaned = basic_punctuation_cleanup(&with_symbols);
+    trace.record(Stage::Refine, &cleaned);
+    return Ok(cleaned);
+  }
+  let started = Instant::now();
+  let result = match provider {
+    "megallm" => refine_with_megallm(with_symbols.clone(), app.clone(), megallm_key, system_prompt, AiLimits::from_prefs(behavior)).await,
+    _ => refine_with_openrouter(with_symbols.clone(), app.clone(), openrouter_key, system_prompt, AiLimits::from_prefs(behavior)).await,
+  };
+  if !matches!(&result, Err(RefineError::MissingKey(_))) {
+    latency::record(provider, started.elapsed(), result.is_ok());
+    session_metrics::record_call(app, provider, result.is_ok());
+  }
+  let output = match result {
+    Ok(output) => {
+      circuit::record_success(provider);
+      output
+    }
+    // A missing key is a setup problem, not an outage
+    Err(e @ RefineError::MissingKey(_)) => return Err(e.into()),
+    Err(e) => {
+      // No answer at all: if the connection is gone, that's no fault of the provider's
+      if matches!(e, RefineError::Transport(_)) && network_monitor::request_failed(app).await {
+        warn!("{} unreachable and the network is down: {}", provider, e);
+        return Ok(offline_cleanup(app, &with_symbols, trace));
+      }
+      if circuit::record_failure(provider, Instant::now()) {
+        info!("{} failed {} times in a row, pausing it for {}s", provider, circuit::FAILURE_THRESHOLD, circuit::COOLDOWN.as_secs());
+        emit_hud_event(app, HudEvent::badge(format!("{} unreachable, pausing it for {} min", provider_name, circuit::COOLDOWN.as_secs() / 60)));
+      }
+      return Err(e.into());
+    }
+  };
+  trace.record(Stage::Refine, &output);
+
+  let Some(target) = translate_to else {
+    // Validate AI output - if it looks like a refusal/conversation, fall back to raw text
+    let validated = validate_ai_output(app, &output, &with_symbols, false, behavior.ai_min_similarity);
+    trace.record(Stage::Validate, &validated);
+    debug!("Refined: \"{}\" -> \"{}\"", with_symbols, validated);
+    return Ok(validated);
+  };
+  let (source, translated) = prompt::split_language_tag(&output);
+  let validated = validate_ai_output(app, translated, &with_symbols, true, behavior.ai_min_similarity);
+  trace.record(Stage::Validate, &validated);
+  debug!("Translated ({} -> {}): \"{}\" -> \"{}\"", source.as_deref().unwrap_or("?"), target, with_symbols, validated);
+  *LAST_TRANSLATION.lock().unwrap() = Some(Translation { original: with_symbols, translated: validated.clone(), source, target });
+  Ok(validated)
+}
+
+/// Run `sample_text` through the whole pipeline the way a dictation would (refinement included) and
+/// return what each stage made of it
+#[tauri::command]
+async fn test_pipeline(app: AppHandle, sample_text: String) -> Result<pipeline::Trace, String> {
+  let behavior = effective_behavior(&app).await;
+  let mut trace = pipeline::Trace::new(&sample_text);
+  let refined = refine_traced(app, None, None, None, None, &mut trace).await?;
+  if !behavior.redact.is_empty() {
+    trace.record(pipeline::Stage::Redact, &pii::redact(&refined, &behavior.redact));
+  }
+  Ok(trace)
+}
+
+/// Timed words of a transcript (estimated where the provider gave no word timing), for the HUD review
+#[tauri::command]
+fn transcript_words(transcript: transcript::Transcript) -> Vec<transcript::Word> {
+  transcript.words()
+}
+
+/// Next scripted transcript for the HUD's demo-mode fake STT
+#[tauri::command]
+fn demo_transcript() -> String {
+  demo::next_transcript().to_string()
+}
+
+/// Original and translated text of the last translation-mode refinement, for the HUD
+#[derive(Debug, Clone, Serialize)]
+struct Translation {
+  original: String,
+  translated: String,
+  /// Source language detected by the model (ISO 639-1), if it reported one
+  source: Option<String>,
+  target: String,
+}
+
+static LAST_TRANSLATION: Mutex<Option<Translation>> = Mutex::new(None);
+
+/// Returns (and clears) the translation produced by the last `refine_text` call
+#[tauri::command]
+fn take_last_translation() -> Option<Translation> {
+  LAST_TRANSLATION.lock().unwrap().take()
+}
+
+/// Pick the system prompt: app profile prompt > app profile's prompt profile > code mode > active prompt profile
+fn refinement_system_prompt(app: &AppHandle, code_mode: bool, language: Option<&str>) -> String {
+  let overrides = profiles::session_overrides();
+  if let Some(p) = overrides.prompt.filter(|p| !p.trim().is_empty()) {
+    return p;
+  }
+  if let Some(p) = overrides.prompt_profile.and_then(|id| prompt_store::profile_prompt(app, &id)) {
+    return p;
+  }
+  if code_mode {
+    return prompt::get_code_system_prompt().to_string();
+  }
+  if let Some(p) = language.and_then(|code| prompt_store::language_prompt(app, code)) {
+    return p;
   }
+  prompt_store::active_prompt(app)
 }
 
-fn refinement_system_prompt() -> &'static str {
-  prompt::get_system_prompt()
+fn retry_policy(b: &BehaviorPrefs) -> http_retry::Policy {
+  http_retry::Policy {
+    attempts: b.retry_attempts.max(1),
+    backoff: Duration::from_millis(b.retry_backoff_ms as u64),
+    jitter_pct: b.retry_jitter_pct,
+    ..http_retry::DEFAULT_POLICY
+  }
+}
+
+/// Global behavior prefs with the active app profile's overrides applied
+async fn effective_behavior(app: &AppHandle) -> BehaviorPrefs {
+  let mut prefs = get_behavior(app.clone()).await.unwrap_or_default();
+  let overrides = profiles::session_overrides();
+  if let Some(v) = overrides.auto_paste { prefs.auto_paste = v; }
+  if let Some(v) = overrides.ai_refine { prefs.ai_refine = v; }
+  if let Some(v) = overrides.ai_provider { prefs.ai_provider = v; }
+  if let Some(v) = overrides.code_mode { prefs.code_mode = v; }
+  if let Some(v) = overrides.confirm_before_paste { prefs.confirm_before_paste = v; }
+  if let Some(v) = overrides.redact { prefs.redact = v; }
+  if let Some(v) = overrides.typography.filter(|t| t.validate().is_ok()) { prefs.typography = v; }
+  if let Some(v) = overrides.insert_mode.filter(|m| INSERT_MODES.contains(&m.as_str())) { prefs.insert_mode = v; }
+  prefs
+}
+
+/// Refinement's stand-in while offline: basic cleanup, with the HUD saying why
+fn offline_cleanup(app: &AppHandle, text: &str, trace: &mut pipeline::Trace) -> String {
+  emit_hud_event(app, HudEvent::badge("Offline mode: AI cleanup skipped"));
+  let cleaned = basic_punctuation_cleanup(text);
+  trace.record(pipeline::Stage::Refine, &cleaned);
+  cleaned
 }
 
 /// Check if AI output looks like a refusal/conversation and should be rejected
 /// If rejected, we fall back to the raw STT text
-fn validate_ai_output(refined: &str, raw_text: &str) -> String {
+/// `translating` skips the similarity check, since a translation shares no words with its input;
+/// `min_similarity` is in percent (0 disables the check)
+fn validate_ai_output(app: &AppHandle, refined: &str, raw_text: &str, translating: bool, min_similarity: u32) -> String {
   // First sanitize any obvious AI additions
   let sanitized = prompt::sanitize_output(refined);
   
   // Check if it looks like an AI refusal/conversation
   if prompt::is_ai_refusal(&sanitized) {
-    eprintln!("⚠️ AI output detected as refusal/conversation, falling back to raw text");
-    eprintln!("   Rejected output: \"{}\"", sanitized);
+    warn!("AI output detected as refusal/conversation, falling back to raw text");
+    debug!("Rejected output: \"{}\"", sanitized);
+    failures::record(app, failures::FailureKind::RefusalFallback, "refusal pattern matched");
     // Return raw text with basic punctuation cleanup
     return basic_punctuation_cleanup(raw_text);
   }
   
   // Check if the output is suspiciously different from input
-  // (e.g., AI completely rewrote it or added lots of content)
-  let input_words: Vec<&str> = raw_text.split_whitespace().collect();
-  let output_words: Vec<&str> = sanitized.split_whitespace().collect();
-  
-  // If output is more than 2x the length of input, something is wrong
-  if output_words.len() > input_words.len() * 2 && input_words.len() > 3 {
-    eprintln!("⚠️ AI output suspiciously longer than input, falling back to raw text");
-    eprintln!("   Input words: {}, Output words: {}", input_words.len(), output_words.len());
-    return basic_punctuation_cleanup(raw_text);
+  // (e.g., AI completely rewrote it or added lots of content); very short inputs are too noisy to judge
+  if !translating && min_similarity > 0 && raw_text.split_whitespace().count() > 3 {
+    let score = similarity::word_similarity(raw_text, &sanitized);
+    if score * 100.0 < min_similarity as f64 {
+      warn!("AI output too different from input (similarity {:.0}% < {}%), falling back to raw text", score * 100.0, min_similarity);
+      debug!("Rejected output: \"{}\"", sanitized);
+      failures::record(app, failures::FailureKind::RefusalFallback, format!("output too different from input ({:.0}% similar)", score * 100.0));
+      return basic_punctuation_cleanup(raw_text);
+    }
   }
   
   sanitized
@@ -370,12 +1472,94 @@ fn strip_think_blocks(mut s: String) -> String {
   s.trim().to_string()
 }
 
-async fn refine_with_megallm(raw_text: String, app: AppHandle, megallm_key: Option<String>) -> Result<String, String> {
-  eprintln!("?? Refining text with MegaLLM...");
+/// Why a refinement request failed; decides whether it counts against the provider
+#[derive(Debug, Clone, PartialEq)]
+enum RefineError {
+  /// No key given or saved: a setup problem, not an outage
+  MissingKey(&'static str),
+  /// The provider answered with an error status
+  Http { status: u16, message: String },
+  /// No usable answer (connection failed, timed out, unreadable body)
+  Transport(String),
+}
+
+impl std::fmt::Display for RefineError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RefineError::MissingKey(provider) => write!(f, "Missing {} key", provider),
+      RefineError::Http { message, .. } => f.write_str(message),
+      RefineError::Transport(message) => f.write_str(message),
+    }
+  }
+}
+
+impl From<RefineError> for String {
+  fn from(e: RefineError) -> Self {
+    e.to_string()
+  }
+}
+
+/// Per-request limits for refinement calls, from behavior prefs
+#[derive(Debug, Clone, Copy)]
+struct AiLimits {
+  timeout: Duration,
+  /// Least `max_tokens`; 0 leaves the provider's default
+  max_tokens: u32,
+}
+
+/// Characters per token assumed when sizing the answer; low, so scripts that take more tokens
+/// per character (CJK, Cyrillic) still fit
+const CHARS_PER_TOKEN: usize = 3;
+
+impl AiLimits {
+  fn from_prefs(b: &BehaviorPrefs) -> Self {
+    Self { timeout: Duration::from_secs(b.ai_timeout_secs.max(1) as u64), max_tokens: b.ai_max_tokens }
+  }
+
+  /// The answer is the input rewritten (or translated, which can run longer), so the limit is
+  /// at least twice the input's estimated tokens plus room for the prompt's formatting
+  fn max_tokens_for(&self, input: &str) -> u32 {
+    let needed = (input.chars().count() / CHARS_PER_TOKEN * 2 + 256).min(128_000) as u32;
+    self.max_tokens.max(needed)
+  }
+
+  fn apply(&self, mut body: serde_json::Value, input: &str) -> serde_json::Value {
+    if self.max_tokens > 0 {
+      body["max_tokens"] = self.max_tokens_for(input).into();
+    }
+    body
+  }
+}
+
+/// Add a finished AI request to the spend ledger, when one is enabled
+fn record_spend(app: &AppHandle, provider: &str, model: &str, response: &serde_json::Value, started: Instant) {
+  let entry = ledger::Entry::from_response(history::now_secs(), provider, model, response, started.elapsed().as_millis() as u64);
+  session_metrics::ai_request(entry.cost_usd);
+  let Some(format) = ledger::format() else { return };
+  let written = app
+    .path()
+    .app_data_dir()
+    .map_err(|e| e.to_string())
+    .and_then(|dir| ledger::append(&dir, format, &entry).map_err(|e| e.to_string()));
+  if let Err(e) = written {
+    warn!("Failed to write spend ledger: {}", e);
+  }
+}
+
+/// Where the spend ledger is written; None while it is off
+#[tauri::command]
+fn spend_ledger_path(app: AppHandle) -> Result<Option<String>, String> {
+  let Some(format) = ledger::format() else { return Ok(None) };
+  let dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+  Ok(Some(dir.join(format.file_name()).to_string_lossy().into_owned()))
+}
+
+async fn refine_with_megallm(raw_text: String, app: AppHandle, megallm_key: Option<String>, system_prompt: String, limits: AiLimits) -> Result<String, RefineError> {
+  debug!("Refining text with MegaLLM...");
 
   let key = match megallm_key {
     Some(k) if !k.is_empty() => k,
-    _ => config::get_megallm_key(&app).await.ok_or("Missing MegaLLM key")?,
+    _ => config::get_megallm_key(&app).await.ok_or(RefineError::MissingKey("MegaLLM"))?,
   };
   let model = config::get_megallm_model(&app)
     .await
@@ -384,85 +1568,481 @@ async fn refine_with_megallm(raw_text: String, app: AppHandle, megallm_key: Opti
   let body = serde_json::json!({
     "model": model,
     "messages": [
-      {"role":"system","content":refinement_system_prompt()},
+      {"role":"system","content":system_prompt},
       {"role":"user","content": raw_text}
     ]
   });
-
-  let client = reqwest::Client::builder()
-    .timeout(Duration::from_secs(5))
-    .build()
-    .map_err(|e| e.to_string())?;
-
-  let resp = client
-    .post("https://ai.megallm.io/v1/chat/completions")
-    .header("content-type", "application/json")
-    .header("authorization", format!("Bearer {}", key))
-    .json(&body)
-    .send()
+  let body = limits.apply(body, &raw_text);
+
+  let build = || {
+    http_client::shared()
+      .post("https://ai.megallm.io/v1/chat/completions")
+      .timeout(limits.timeout)
+      .header("content-type", "application/json")
+      .header("authorization", format!("Bearer {}", key))
+      .json(&body)
+  };
+  let started = Instant::now();
+  let resp = http_retry::send("megallm", build, |r| emit_hud_event(&app, HudEvent::RefineRetrying(r.clone())))
     .await
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| RefineError::Transport(failures::record_request_error(&app, "megallm", e)))?;
 
   let status = resp.status();
-  let text_body = resp.text().await.map_err(|e| e.to_string())?;
+  let text_body = resp.text().await.map_err(|e| RefineError::Transport(e.to_string()))?;
   if !status.is_success() {
-    return Err(format!("MegaLLM HTTP {} - {}", status, text_body));
+    failures::record(&app, failures::FailureKind::ProviderHttp, format!("megallm: HTTP {}", status));
+    return Err(RefineError::Http { status: status.as_u16(), message: format!("MegaLLM HTTP {} - {}", status, text_body) });
   }
 
-  let v: serde_json::Value = serde_json::from_str(&text_body).map_err(|e| e.to_string())?;
+  let v: serde_json::Value = serde_json::from_str(&text_body).map_err(|e| RefineError::Transport(e.to_string()))?;
+  record_spend(&app, "megallm", &model, &v, started);
   let refined = v["choices"][0]["message"]["content"]
     .as_str()
     .unwrap_or("{}")
     .to_string();
-  let cleaned = strip_think_blocks(refined);
-  
-  // Validate AI output - if it looks like a refusal/conversation, fall back to raw text
-  let validated = validate_ai_output(&cleaned, &raw_text);
-  eprintln!("✅ MegaLLM refined: \"{}\" -> \"{}\"", raw_text, validated);
-  Ok(validated)
+  Ok(strip_think_blocks(refined))
 }
 
-async fn refine_with_openrouter(raw_text: String, app: AppHandle, openrouter_key: Option<String>) -> Result<String, String> {
-  eprintln!("?? Refining text with OpenRouter...");
+async fn refine_with_openrouter(raw_text: String, app: AppHandle, openrouter_key: Option<String>, system_prompt: String, limits: AiLimits) -> Result<String, RefineError> {
+  debug!("Refining text with OpenRouter...");
 
   let key = match openrouter_key {
     Some(k) if !k.is_empty() => k,
-    _ => config::get_openrouter_key(&app).await.ok_or("Missing OpenRouter key")?,
+    _ => config::get_openrouter_key(&app).await.ok_or(RefineError::MissingKey("OpenRouter"))?,
   };
   let model = config::get_model(&app).await.unwrap_or_else(|| "openai/gpt-oss-20b:free".into());
 
   let body = serde_json::json!({
     "model": model,
     "messages": [
-      {"role":"system","content":refinement_system_prompt()},
+      {"role":"system","content":system_prompt},
       {"role":"user","content": raw_text}
-    ]
+    ],
+    // Makes OpenRouter report the request's actual cost for the spend ledger
+    "usage": {"include": true}
   });
-  let client = reqwest::Client::builder().timeout(std::time::Duration::from_secs(5)).build().map_err(|e| e.to_string())?;
-  let resp = client
-    .post("https://openrouter.ai/api/v1/chat/completions")
-    .header("content-type","application/json")
-    .header("authorization", format!("Bearer {}", key))
-    .json(&body)
-    .send().await.map_err(|e| e.to_string())?;
-  if !resp.status().is_success() { return Err(format!("OpenRouter HTTP {}", resp.status())); }
-  let v: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
+  let body = limits.apply(body, &raw_text);
+  let build = || {
+    http_client::shared()
+      .post("https://openrouter.ai/api/v1/chat/completions")
+      .timeout(limits.timeout)
+      .header("content-type","application/json")
+      .header("authorization", format!("Bearer {}", key))
+      .json(&body)
+  };
+  let started = Instant::now();
+  let resp = http_retry::send("openrouter", build, |r| emit_hud_event(&app, HudEvent::RefineRetrying(r.clone())))
+    .await.map_err(|e| RefineError::Transport(failures::record_request_error(&app, "openrouter", e)))?;
+  if !resp.status().is_success() {
+    failures::record(&app, failures::FailureKind::ProviderHttp, format!("openrouter: HTTP {}", resp.status()));
+    return Err(RefineError::Http { status: resp.status().as_u16(), message: format!("OpenRouter HTTP {}", resp.status()) });
+  }
+  let v: serde_json::Value = resp.json().await.map_err(|e| RefineError::Transport(e.to_string()))?;
+  record_spend(&app, "openrouter", &model, &v, started);
   let refined = v["choices"][0]["message"]["content"].as_str().unwrap_or("{}").to_string();
-  let cleaned = strip_think_blocks(refined);
-  
-  // Validate AI output - if it looks like a refusal/conversation, fall back to raw text
-  let validated = validate_ai_output(&cleaned, &raw_text);
-  eprintln!("✅ OpenRouter refined: \"{}\" -> \"{}\"", raw_text, validated);
-  Ok(validated)
+  Ok(strip_think_blocks(refined))
 }
 
+/// Built-in refusal phrases, the user's additions and removals, and the merged list
 #[tauri::command]
-async fn save_keys_secure(app: AppHandle, openrouter: String, deepgram: String, megallm: String, elevenlabs: String) -> Result<(), String> {
-  if !openrouter.is_empty() { config::set_openrouter_key(&app, &openrouter).await.map_err(|e| e.to_string())?; }
-  if !deepgram.is_empty() { config::set_deepgram_key(&app, &deepgram).await.map_err(|e| e.to_string())?; }
-  if !megallm.is_empty() { config::set_megallm_key(&app, &megallm).await.map_err(|e| e.to_string())?; }
-  if !elevenlabs.is_empty() { config::set_elevenlabs_key(&app, &elevenlabs).await.map_err(|e| e.to_string())?; }
-  Ok(())
+fn get_refusal_patterns() -> prompt::RefusalPatterns {
+  prompt::describe_refusal_patterns()
+}
+
+/// Replace the user's refusal phrase additions and removals (removals name built-in phrases)
+#[tauri::command]
+fn set_refusal_patterns(app: AppHandle, added: Vec<String>, removed: Vec<String>) -> Result<prompt::RefusalPatterns, String> {
+  let overrides = prompt::RefusalOverrides { added, removed }.normalized();
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set("refusal_patterns", serde_json::to_value(&overrides).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  info!("Refusal patterns: +{} -{}", overrides.added.len(), overrides.removed.len());
+  prompt::set_refusal_overrides(overrides);
+  Ok(prompt::describe_refusal_patterns())
+}
+
+fn save_dictionary(app: &AppHandle, words: Vec<String>) -> Result<Vec<String>, String> {
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set("dictionary", serde_json::to_value(&words).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  dictionary::set_words(words.clone());
+  Ok(words)
+}
+
+/// Names and terms boosted in Deepgram and kept verbatim by refinement
+#[tauri::command]
+fn list_dictionary() -> Vec<String> {
+  dictionary::words()
+}
+
+fn load_learned(app: &AppHandle) -> Vec<learning::LearnedCorrection> {
+  app
+    .store("corrections.json")
+    .ok()
+    .and_then(|s| s.get("learned"))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+fn save_learned(app: &AppHandle, entries: &[learning::LearnedCorrection]) -> Result<(), String> {
+  let store = app.store("corrections.json").map_err(|e| e.to_string())?;
+  store.set("learned", serde_json::to_value(entries).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())
+}
+
+/// Diff a refined text against what the user kept and count the word replacements. Does nothing
+/// unless learning is on; returns how many corrections were recorded
+#[tauri::command]
+async fn report_correction(app: AppHandle, original: String, corrected: String) -> Result<usize, String> {
+  if !get_behavior(app.clone()).await?.learn_corrections {
+    return Ok(0);
+  }
+  let mut entries = load_learned(&app);
+  let found = learning::record(&mut entries, &original, &corrected, history::now_secs());
+  if found > 0 {
+    info!("Learned {} corrections", found);
+    save_learned(&app, &entries)?;
+  }
+  Ok(found)
+}
+
+/// Corrections repeated often enough to become dictionary entries or replacement rules
+#[tauri::command]
+fn get_correction_suggestions(app: AppHandle) -> Vec<learning::Suggestion> {
+  learning::suggestions(&load_learned(&app))
+}
+
+/// Turn a suggestion into a dictionary entry or a custom pack rule
+#[tauri::command]
+fn accept_correction_suggestion(app: AppHandle, from: String, to: String) -> Result<Vec<learning::Suggestion>, String> {
+  let mut entries = load_learned(&app);
+  let suggestion = learning::suggestions(&entries)
+    .into_iter()
+    .find(|s| s.from == from && s.to == to)
+    .ok_or("No such suggestion")?;
+  match suggestion.kind {
+    learning::SuggestionKind::Dictionary => {
+      let mut words = dictionary::words();
+      dictionary::add(&mut words, &to)?;
+      save_dictionary(&app, words)?;
+    }
+    learning::SuggestionKind::Rule => {
+      let mut pack = pack_store::custom_pack(&app);
+      let rule = pack::Pack { rules: vec![pack::Mapping::new(&from, &to)], ..pack::Pack::default() };
+      pack.merge(&rule, pack::OnConflict::Replace);
+      pack_store::set_custom_pack(&app, &pack)?;
+    }
+  }
+  info!("Accepted correction \"{}\" -> \"{}\"", from, to);
+  entries.retain(|e| !(e.from == from && e.to == to));
+  save_learned(&app, &entries)?;
+  Ok(learning::suggestions(&entries))
+}
+
+#[tauri::command]
+fn dismiss_correction_suggestion(app: AppHandle, from: String, to: String) -> Result<Vec<learning::Suggestion>, String> {
+  let mut entries = load_learned(&app);
+  for e in entries.iter_mut().filter(|e| e.from == from && e.to == to) {
+    e.dismissed = true;
+  }
+  save_learned(&app, &entries)?;
+  Ok(learning::suggestions(&entries))
+}
+
+/// Dictionary entries plus the vocabulary of the active packs, for Deepgram keywords
+#[tauri::command]
+fn stt_keywords(app: AppHandle) -> Vec<String> {
+  let mut words = dictionary::words();
+  for word in pack_store::active_pack(&app).vocabulary {
+    if !words.iter().any(|w| w.eq_ignore_ascii_case(&word)) {
+      words.push(word);
+    }
+  }
+  words
+}
+
+/// Input devices last reported by the HUD, with the one in use marked
+#[tauri::command]
+fn list_audio_devices(app: AppHandle) -> Vec<audio_device::AudioDevice> {
+  audio_device::list(&app)
+}
+
+/// Id of the chosen input device; null follows the system default
+#[tauri::command]
+fn get_audio_device(app: AppHandle) -> Option<String> {
+  audio_device::selected(&app)
+}
+
+#[tauri::command]
+fn set_audio_device(app: AppHandle, id: Option<String>) -> Result<(), String> {
+  audio_device::select(&app, id)
+}
+
+/// Sent by the HUD at startup and whenever devices are plugged in or removed
+#[tauri::command]
+fn report_audio_devices(devices: Vec<audio_device::AudioDevice>) {
+  audio_device::set_devices(devices);
+}
+
+#[tauri::command]
+fn audio_device_changed(app: AppHandle, label: String) {
+  audio_device::changed(&app, &label);
+}
+
+/// Noise suppression for the device in use: its own setting, else the global prefs
+#[tauri::command]
+async fn get_noise_mode(app: AppHandle) -> Result<audio_device::NoiseMode, String> {
+  let prefs = get_behavior(app.clone()).await?;
+  Ok(audio_device::noise_mode(&app, audio_device::NoiseMode::from_prefs(prefs.noise_suppression, prefs.rnnoise)))
+}
+
+#[tauri::command]
+fn set_device_noise_mode(app: AppHandle, id: String, mode: Option<audio_device::NoiseMode>) -> Result<(), String> {
+  audio_device::set_noise_mode(&app, &id, mode)
+}
+
+/// Built-in domain packs and where they are enabled
+#[tauri::command]
+fn list_packs(app: AppHandle) -> Vec<pack_store::PackInfo> {
+  pack_store::list(&app)
+}
+
+/// Enable a built-in pack for every app, or only for the app profile named by `app`
+#[tauri::command]
+fn enable_pack(handle: AppHandle, name: String, app: Option<String>) -> Result<Vec<pack_store::PackInfo>, String> {
+  pack_store::set_enabled(&handle, &name, app.as_deref(), true)?;
+  Ok(pack_store::list(&handle))
+}
+
+#[tauri::command]
+fn disable_pack(handle: AppHandle, name: String, app: Option<String>) -> Result<Vec<pack_store::PackInfo>, String> {
+  pack_store::set_enabled(&handle, &name, app.as_deref(), false)?;
+  Ok(pack_store::list(&handle))
+}
+
+#[tauri::command]
+fn add_dictionary_word(app: AppHandle, word: String) -> Result<Vec<String>, String> {
+  let mut words = dictionary::words();
+  dictionary::add(&mut words, &word)?;
+  info!("Dictionary: added \"{}\" ({} entries)", word.trim(), words.len());
+  save_dictionary(&app, words)
+}
+
+#[tauri::command]
+fn remove_dictionary_word(app: AppHandle, word: String) -> Result<Vec<String>, String> {
+  let mut words = dictionary::words();
+  if !dictionary::remove(&mut words, &word) {
+    return Err(format!("\"{}\" is not in the dictionary", word.trim()));
+  }
+  save_dictionary(&app, words)
+}
+
+/// The user's custom symbols, snippets, rules and prompt additions
+#[tauri::command]
+fn get_custom_pack(app: AppHandle) -> pack::Pack {
+  pack_store::custom_pack(&app)
+}
+
+#[tauri::command]
+fn set_custom_pack(app: AppHandle, pack: pack::Pack) -> Result<pack::Pack, String> {
+  let pack = pack.normalized()?;
+  pack_store::set_custom_pack(&app, &pack)?;
+  Ok(pack)
+}
+
+/// Write the custom pack to a shareable file (default: Downloads); returns the file path
+#[tauri::command]
+fn export_pack(app: AppHandle, path: Option<String>, name: Option<String>, description: Option<String>) -> Result<String, String> {
+  pack_store::export(&app, path.as_deref().map(Path::new), name.as_deref(), description.as_deref()).map(|p| p.display().to_string())
+}
+
+/// Merge a pack file into the custom pack. `on_conflict` is "ask" (default: change nothing and
+/// report conflicts), "keep" or "replace"
+#[tauri::command]
+fn import_pack(app: AppHandle, path: String, on_conflict: Option<pack::OnConflict>) -> Result<pack::ImportReport, String> {
+  pack_store::import(&app, Path::new(&path), on_conflict.unwrap_or(pack::OnConflict::Ask))
+}
+
+/// Back up the settings to one file (Downloads by default); a passphrase includes the API keys,
+/// encrypted with it
+#[tauri::command]
+fn export_settings(app: AppHandle, path: Option<String>, passphrase: Option<String>) -> Result<String, String> {
+  backup::export(&app, path.as_deref().map(Path::new), passphrase.as_deref()).map(|p| p.display().to_string())
+}
+
+const K_PROXY: &str = "proxy";
+const K_PROXY_PASSWORD: &str = "proxy_password";
+
+fn load_proxy(app: &AppHandle) -> (proxy::ProxyPrefs, Option<String>) {
+  let store = app.store("prefs.json").ok();
+  let prefs = store.as_ref().and_then(|s| s.get(K_PROXY)).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default();
+  let password = store.as_ref().and_then(|s| s.get(K_PROXY_PASSWORD)).and_then(|v| v.as_str().map(str::to_string)).filter(|p| !p.is_empty());
+  (prefs, password)
+}
+
+/// Route provider traffic per the saved proxy prefs
+fn apply_proxy(app: &AppHandle) {
+  let (prefs, password) = load_proxy(app);
+  if let Err(e) = http_client::configure(&prefs, password.as_deref()) {
+    warn!("Proxy prefs not applied ({}), connecting directly", e);
+    let _ = http_client::configure(&proxy::ProxyPrefs { mode: "off".into(), ..Default::default() }, None);
+  }
+}
+
+/// Put the stored prefs that live outside the store into effect: retries, spend ledger, jobs,
+/// control API, proxy, dictionary and refusal phrases. Runs at startup and after the prefs were
+/// replaced (backup restored, configuration profile switched).
+fn apply_stored_prefs(app: &AppHandle) {
+  let store = app.store("prefs.json").ok();
+  let read = |key: &str| store.as_ref().and_then(|s| s.get(key));
+  if let Some(b) = read("behavior").and_then(|v| serde_json::from_value::<BehaviorPrefs>(v).ok()) {
+    http_retry::configure(retry_policy(&b));
+    ledger::configure(ledger::Format::parse(&b.spend_ledger));
+    jobs::configure(b.transcription_concurrency as usize);
+    control_api::configure(app, b.control_api, b.control_api_port);
+  }
+  apply_proxy(app);
+  dictionary::set_words(read("dictionary").and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default());
+  prompt::set_refusal_overrides(read("refusal_patterns").and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default());
+}
+
+#[derive(Debug, Clone, Serialize, schemars::JsonSchema)]
+struct ProxyStatus {
+  #[serde(flatten)]
+  prefs: proxy::ProxyPrefs,
+  /// A password is saved (it is never sent back)
+  has_password: bool,
+}
+
+/// Estimated provider cost per month, newest first, for the last `months` months (all when omitted)
+#[tauri::command]
+fn get_cost_summary(app: AppHandle, months: Option<u32>) -> cost::CostSummary {
+  session_metrics::cost_summary(&app, months.unwrap_or(0) as usize)
+}
+
+/// Remaining credit at each STT provider with a key; warns about any below its threshold
+#[tauri::command]
+async fn get_provider_usage(app: AppHandle) -> Vec<quota::ProviderUsage> {
+  provider_usage::check(&app).await
+}
+
+/// Whether the providers can be reached, probing first with `recheck`
+#[tauri::command]
+async fn get_network_status(app: AppHandle, recheck: Option<bool>) -> connectivity::NetworkStatus {
+  if recheck.unwrap_or(false) {
+    return network_monitor::check(&app).await;
+  }
+  network_monitor::status()
+}
+
+#[tauri::command]
+fn get_proxy(app: AppHandle) -> ProxyStatus {
+  let (prefs, password) = load_proxy(&app);
+  ProxyStatus { prefs, has_password: password.is_some() }
+}
+
+/// Save and apply proxy prefs. `password`: None keeps the saved one, "" removes it
+#[tauri::command]
+fn set_proxy(app: AppHandle, proxy: proxy::ProxyPrefs, password: Option<String>) -> Result<ProxyStatus, String> {
+  proxy.validate()?;
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  match password.as_deref() {
+    Some("") => {
+      store.delete(K_PROXY_PASSWORD);
+    }
+    Some(p) => store.set(K_PROXY_PASSWORD, p),
+    None => {}
+  }
+  store.set(K_PROXY, serde_json::to_value(&proxy).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())?;
+  let (_, password) = load_proxy(&app);
+  http_client::configure(&proxy, password.as_deref())?;
+  Ok(ProxyStatus { prefs: proxy, has_password: password.is_some() })
+}
+
+/// Switch to the named configuration profile, created from the current settings if it is new
+#[tauri::command]
+async fn switch_profile(app: AppHandle, name: String) -> Result<config_profiles::ConfigProfiles, String> {
+  if RECORDING_STATE.lock().unwrap().state != DictationState::Inactive {
+    return Err("dictation-running".into());
+  }
+  config_profiles::switch(&app, &name)?;
+  apply_stored_prefs(&app);
+  let behavior = get_behavior(app.clone()).await?;
+  sync_tray_checks(&app, &behavior);
+  if let Err(e) = refresh_config_menu(&app) { error!("Failed to refresh configuration menu: {}", e); }
+  Ok(config_profiles::list(&app))
+}
+
+#[tauri::command]
+fn list_config_profiles(app: AppHandle) -> config_profiles::ConfigProfiles { config_profiles::list(&app) }
+
+#[tauri::command]
+fn delete_config_profile(app: AppHandle, name: String) -> Result<config_profiles::ConfigProfiles, String> {
+  config_profiles::delete(&app, &name)?;
+  if let Err(e) = refresh_config_menu(&app) { error!("Failed to refresh configuration menu: {}", e); }
+  Ok(config_profiles::list(&app))
+}
+
+/// Restore a settings backup over the current settings
+#[tauri::command]
+async fn import_settings(app: AppHandle, path: String, passphrase: Option<String>) -> Result<backup::ImportSummary, String> {
+  let summary = backup::import(&app, Path::new(&path), passphrase.as_deref())?;
+  apply_stored_prefs(&app);
+  let behavior = get_behavior(app.clone()).await?;
+  sync_tray_checks(&app, &behavior);
+  Ok(summary)
+}
+
+#[tauri::command]
+fn list_prompt_profiles(app: AppHandle) -> Vec<prompt_store::PromptProfile> { prompt_store::list_profiles(&app) }
+
+#[tauri::command]
+fn set_active_prompt_profile(app: AppHandle, id: String) -> Result<(), String> {
+  prompt_store::set_active_profile(&app, &id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_custom_prompt(app: AppHandle, prompt: String) -> Result<(), String> {
+  prompt_store::set_custom_prompt(&app, &prompt).map_err(|e| e.to_string())
+}
+
+/// Prompt used for dictations detected in `language` (STT language "auto"); empty removes it
+#[tauri::command]
+fn set_language_prompt(app: AppHandle, language: String, prompt: String) -> Result<(), String> {
+  prompt_store::set_language_prompt(&app, &language, &prompt).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_language_prompts(app: AppHandle) -> std::collections::BTreeMap<String, String> {
+  prompt_store::language_prompts(&app)
+}
+
+/// Check each non-empty key with a live request and store it unless the provider refuses it. A
+/// key that can't be checked (offline, provider outage) is stored and reported as unverified.
+/// Returns one result per key given.
+#[tauri::command]
+async fn save_keys_secure(app: AppHandle, openrouter: String, deepgram: String, megallm: String, elevenlabs: String) -> Result<Vec<key_inspect::KeyCheck>, String> {
+  let given: Vec<(&str, String)> = [("openrouter", openrouter), ("deepgram", deepgram), ("megallm", megallm), ("elevenlabs", elevenlabs)]
+    .into_iter()
+    .map(|(provider, key)| (provider, key.trim().to_string()))
+    .filter(|(_, key)| !key.is_empty())
+    .collect();
+  let mut checks = futures_util::future::join_all(given.iter().map(|(provider, key)| key_inspect::check_key(&app, provider, key))).await;
+  for (check, (provider, key)) in checks.iter_mut().zip(&given) {
+    if check.status == key_inspect::KeyCheckStatus::Unauthorized {
+      continue;
+    }
+    let saved = match *provider {
+      "openrouter" => config::set_openrouter_key(&app, key).await,
+      "deepgram" => config::set_deepgram_key(&app, key).await,
+      "megallm" => config::set_megallm_key(&app, key).await,
+      _ => config::set_elevenlabs_key(&app, key).await,
+    };
+    saved.map_err(|e| e.to_string())?;
+    check.saved = true;
+  }
+  Ok(checks)
 }
 
 #[tauri::command]
@@ -481,26 +2061,72 @@ async fn set_hotkey(app: AppHandle, combo: String) -> Result<(), String> { hotke
 #[tauri::command]
 async fn get_hotkey(app: AppHandle) -> Result<String, String> { Ok(hotkey::get_hotkey(&app)) }
 
+#[tauri::command]
+async fn set_code_mode_hotkey(app: AppHandle, combo: String) -> Result<(), String> { hotkey::set_code_mode_hotkey(&app, &combo) }
+
+/// "Press your combo now": listens for the next key combo pressed anywhere and returns it
+/// normalized, with registrability and reserved-combo warnings
+#[tauri::command]
+async fn capture_hotkey(app: AppHandle, timeout_ms: Option<u64>) -> Result<hotkey_capture::CapturedHotkey, String> {
+  hotkey_capture::capture(&app, Duration::from_millis(timeout_ms.unwrap_or(10_000))).await
+}
+
+#[tauri::command]
+fn validate_hotkey(app: AppHandle, combo: String) -> hotkey_capture::CapturedHotkey {
+  hotkey_capture::validate(&app, &combo)
+}
+
+#[tauri::command]
+async fn get_code_mode_hotkey(app: AppHandle) -> Result<String, String> { Ok(hotkey::get_code_mode_hotkey(&app)) }
+
+#[tauri::command]
+async fn set_retake_hotkey(app: AppHandle, combo: String) -> Result<(), String> { hotkey::set_retake_hotkey(&app, &combo) }
+
+#[tauri::command]
+async fn get_retake_hotkey(app: AppHandle) -> Result<String, String> { Ok(hotkey::get_retake_hotkey(&app)) }
+
+#[tauri::command]
+async fn set_pause_hotkey(app: AppHandle, combo: String) -> Result<(), String> { hotkey::set_pause_hotkey(&app, &combo) }
+
+#[tauri::command]
+async fn get_pause_hotkey(app: AppHandle) -> Result<String, String> { Ok(hotkey::get_pause_hotkey(&app)) }
+
+/// Flip code dictation mode (tray item and code-mode hotkey) where it applies: in the profile of
+/// the app being dictated into when that sets it, else globally. Returns the new value.
+#[tauri::command]
+async fn toggle_code_mode(app: AppHandle) -> Result<bool, String> {
+  if let Some((key, on)) = profiles::toggle_app_code_mode(&app).map_err(|e| e.to_string())? {
+    info!("Code mode {} for {}", if on { "ON" } else { "OFF" }, key);
+    emit_hud_event(&app, HudEvent::badge(format!("Code mode {} for {}", if on { "on" } else { "off" }, key)));
+    return Ok(on);
+  }
+  let current = get_behavior(app.clone()).await?.code_mode;
+  let prefs = set_behavior(app.clone(), serde_json::json!({ "code_mode": !current })).await?;
+  info!("Code mode {}", if prefs.code_mode { "ON" } else { "OFF" });
+  emit_hud_event(&app, HudEvent::badge(if prefs.code_mode { "Code mode on" } else { "Code mode off" }));
+  Ok(prefs.code_mode)
+}
+
 #[tauri::command]
 async fn set_autostart(app: AppHandle, enabled: bool) -> Result<(), String> {
-  eprintln!("⚙️ set_autostart called: enabled={}", enabled);
+  info!("set_autostart called: enabled={}", enabled);
   let autolaunch = app.autolaunch();
   if enabled { autolaunch.enable().map_err(|e| e.to_string())?; } else { autolaunch.disable().map_err(|e| e.to_string())?; }
   // Persist autostart flag in the Store directly (do not route through set_behavior so we don't drop the field)
   let store = app.store("prefs.json").map_err(|e| e.to_string())?;
   let mut prefs = if let Some(v) = store.get("behavior") {
-    eprintln!("set_autostart: existing behavior raw: {}", v);
+    debug!("set_autostart: existing behavior raw: {}", v);
     serde_json::from_value::<BehaviorPrefs>(v).unwrap_or_default()
   } else {
-    eprintln!("set_autostart: no existing behavior in store");
+    debug!("set_autostart: no existing behavior in store");
     BehaviorPrefs::default()
   };
   prefs.autostart = enabled;
   let val = serde_json::to_value(&prefs).map_err(|e| e.to_string())?;
   store.set("behavior", val);
   store.save().map_err(|e| e.to_string())?;
-  if let Some(v) = store.get("behavior") { eprintln!("set_autostart: after write behavior raw: {}", v); }
-  eprintln!("✅ set_autostart persisted: autostart={} (OS updated)", enabled);
+  if let Some(v) = store.get("behavior") { debug!("set_autostart: after write behavior raw: {}", v); }
+  info!("set_autostart persisted: autostart={} (OS updated)", enabled);
   Ok(())
 }
 
@@ -511,15 +2137,15 @@ fn get_autostart(app: AppHandle) -> Result<bool, String> {
 
 #[tauri::command]
 async fn set_behavior(app: AppHandle, args: serde_json::Value) -> Result<BehaviorPrefs, String> {
-  eprintln!("📝 set_behavior called with args: {}", args);
+  info!("set_behavior called with args: {}", args);
   let store = app.store("prefs.json").map_err(|e| e.to_string())?;
 
   // Start from existing prefs or defaults
   let mut prefs = if let Some(existing) = store.get("behavior") {
-    eprintln!("set_behavior: existing behavior raw: {}", existing);
+    debug!("set_behavior: existing behavior raw: {}", existing);
     serde_json::from_value::<BehaviorPrefs>(existing).unwrap_or_default()
   } else {
-    eprintln!("set_behavior: no existing behavior in store");
+    debug!("set_behavior: no existing behavior in store");
     BehaviorPrefs::default()
   };
 
@@ -552,11 +2178,158 @@ async fn set_behavior(app: AppHandle, args: serde_json::Value) -> Result<Behavio
   if let Some(v) = get_bool("echo_cancellation", "echoCancellation") { prefs.echo_cancellation = v; }
   if let Some(v) = get_bool("noise_suppression", "noiseSuppression") { prefs.noise_suppression = v; }
   if let Some(v) = get_u32("silence_secs", "silenceSecs") { prefs.silence_secs = v; }
+  if let Some(v) = get_bool("code_mode", "codeMode") { prefs.code_mode = v; }
+  if let Some(v) = get_bool("spell_mode", "spellMode") { prefs.spell_mode = v; }
+  if let Some(v) = get_bool("spoken_punctuation", "spokenPunctuation") { prefs.spoken_punctuation = v; }
+  if let Some(v) = get_bool("emoji", "emoji") { prefs.emoji = v; }
+  if let Some(v) = get_str("hud_position", "hudPosition") {
+    let normalized = v.to_lowercase();
+    if hud_position::HUD_POSITIONS.contains(&normalized.as_str()) {
+      prefs.hud_position = normalized;
+    }
+  }
+  if let Some(v) = get_str("monitor_policy", "monitorPolicy") {
+    let normalized = v.trim().to_lowercase();
+    if focused_monitor::is_valid_policy(&normalized) {
+      prefs.monitor_policy = normalized;
+    }
+  }
+  if let Some(v) = get_bool("sound_start", "soundStart") { prefs.sound_start = v; }
+  if let Some(v) = get_bool("sound_stop", "soundStop") { prefs.sound_stop = v; }
+  if let Some(v) = get_bool("sound_error", "soundError") { prefs.sound_error = v; }
+  if let Some(v) = get_u32("sound_volume", "soundVolume") { prefs.sound_volume = v.min(100); }
+  if let Some(v) = get_bool("notify_errors", "notifyErrors") { prefs.notify_errors = v; }
+  if let Some(v) = get_str("numbers_mode", "numbersMode") {
+    let normalized = v.to_lowercase();
+    if numbers::NUMBER_MODES.contains(&normalized.as_str()) {
+      prefs.numbers_mode = normalized;
+    }
+  }
+  if let Some(v) = get_bool("stt_relay", "sttRelay") { prefs.stt_relay = v; }
+  if let Some(v) = get_bool("demo_mode", "demoMode") { prefs.demo_mode = v; }
+  if let Some(v) = get_bool("preserve_clipboard", "preserveClipboard") { prefs.preserve_clipboard = v; }
+  if let Some(v) = get_u32("clipboard_restore_ms", "clipboardRestoreMs") { prefs.clipboard_restore_ms = v.min(30_000); }
+  if let Some(v) = get_str("insert_mode", "insertMode") {
+    let normalized = v.to_lowercase();
+    if INSERT_MODES.contains(&normalized.as_str()) {
+      prefs.insert_mode = normalized;
+    }
+  }
+  if let Some(v) = get_u32("type_delay_ms", "typeDelayMs") { prefs.type_delay_ms = v.min(500); }
+  if let Some(v) = get_u32("human_min_ms", "humanMinMs") { prefs.human_min_ms = v.min(1000); }
+  if let Some(v) = get_u32("human_max_ms", "humanMaxMs") { prefs.human_max_ms = v.min(1000); }
+  if prefs.human_min_ms > prefs.human_max_ms {
+    std::mem::swap(&mut prefs.human_min_ms, &mut prefs.human_max_ms);
+  }
+  if let Some(v) = get_u32("hud_idle_unload_secs", "hudIdleUnloadSecs") { prefs.hud_idle_unload_secs = v; }
+  if let Some(v) = get_bool("review_playback", "reviewPlayback") { prefs.review_playback = v; }
+  if let Some(v) = get_u32("max_duration_secs", "maxDurationSecs") { prefs.max_duration_secs = v; }
+  if let Some(v) = get_u32("watchdog_starting_secs", "watchdogStartingSecs") { prefs.watchdog_starting_secs = v; }
+  if let Some(v) = get_u32("watchdog_stopping_secs", "watchdogStoppingSecs") { prefs.watchdog_stopping_secs = v; }
+  if let Some(v) = get_u32("confirm_first_pastes", "confirmFirstPastes") { prefs.confirm_first_pastes = v.min(100); }
+  if let Some(v) = get_bool("confirm_before_paste", "confirmBeforePaste") { prefs.confirm_before_paste = v; }
+  if let Some(v) = get_bool("lock_target_window", "lockTargetWindow") { prefs.lock_target_window = v; }
+  if let Some(v) = args.get("terminal_apps").or_else(|| args.get("terminalApps")).and_then(|v| v.as_array()) {
+    prefs.terminal_apps = v
+      .iter()
+      .filter_map(|s| s.as_str())
+      .map(|s| s.trim().to_string())
+      .filter(|s| !s.is_empty())
+      .collect();
+  }
+  if let Some(v) = args.get("redact").and_then(|v| v.as_array()) {
+    prefs.redact = v
+      .iter()
+      .filter_map(|s| s.as_str())
+      .filter(|s| pii::CATEGORIES.contains(s))
+      .map(str::to_string)
+      .collect();
+  }
+  if let Some(v) = args.get("regex_rules").or_else(|| args.get("regexRules")) {
+    let mut rules: Vec<pipeline::RegexRule> = serde_json::from_value(v.clone()).map_err(|e| format!("Invalid regex rules: {}", e))?;
+    // Blank rows from "Add rule" are dropped rather than failing the whole save
+    rules.retain(|r| !r.find.is_empty());
+    pipeline::validate_rules(&rules)?;
+    prefs.regex_rules = rules;
+  }
+  if let Some(v) = args.get("typography") {
+    let style: typography::Typography = serde_json::from_value(v.clone()).map_err(|e| format!("Invalid typography: {}", e))?;
+    style.validate()?;
+    prefs.typography = style;
+  }
+  if let Some(v) = args.get("plugins").and_then(|v| v.as_array()) {
+    prefs.plugins = v
+      .iter()
+      .filter_map(|s| s.as_str())
+      .map(|s| s.trim().to_string())
+      .filter(|s| !s.is_empty())
+      .collect();
+  }
+  if let Some(v) = get_str("log_level", "logLevel") {
+    if logging::parse_level(&v).is_some() {
+      prefs.log_level = v.trim().to_lowercase();
+      logging::set_level(&prefs.log_level);
+    }
+  }
+  if let Some(v) = get_u32("ai_timeout_secs", "aiTimeoutSecs") { prefs.ai_timeout_secs = v.clamp(1, 300); }
+  if let Some(v) = get_u32("ai_max_tokens", "aiMaxTokens") { prefs.ai_max_tokens = v.min(128_000); }
+  if let Some(v) = get_u32("ai_min_similarity", "aiMinSimilarity") { prefs.ai_min_similarity = v.min(100); }
+  if let Some(v) = get_u32("retry_attempts", "retryAttempts") { prefs.retry_attempts = v.clamp(1, 6); }
+  if let Some(v) = get_u32("retry_backoff_ms", "retryBackoffMs") { prefs.retry_backoff_ms = v.min(10_000); }
+  if let Some(v) = get_u32("retry_jitter_pct", "retryJitterPct") { prefs.retry_jitter_pct = v.min(100); }
+  http_retry::configure(retry_policy(&prefs));
+  if let Some(v) = get_str("spend_ledger", "spendLedger") {
+    let normalized = v.trim().to_lowercase();
+    if ledger::LEDGER_FORMATS.contains(&normalized.as_str()) {
+      prefs.spend_ledger = normalized;
+    }
+  }
+  ledger::configure(ledger::Format::parse(&prefs.spend_ledger));
+  // null or "" stops watching
+  if let Some(v) = args.get("watch_folder").or_else(|| args.get("watchFolder")) {
+    prefs.watch_folder = v.as_str().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+  }
+  if let Some(v) = get_bool("watch_refine", "watchRefine") { prefs.watch_refine = v; }
+  if let Some(v) = get_bool("learn_corrections", "learnCorrections") { prefs.learn_corrections = v; }
+  if let Some(v) = get_bool("mixed_language", "mixedLanguage") { prefs.mixed_language = v; }
+  if let Some(v) = get_bool("auto_gain", "autoGain") { prefs.auto_gain = v; }
+  if let Some(v) = get_bool("rnnoise", "rnnoise") { prefs.rnnoise = v; }
+  if let Some(v) = get_u32("history_retention_days", "historyRetentionDays") { prefs.history_retention_days = v; }
+  if let Some(v) = get_u32("low_credit_usd", "lowCreditUsd") { prefs.low_credit_usd = v; }
+  if let Some(v) = get_u32("low_credit_percent", "lowCreditPercent") { prefs.low_credit_percent = v.min(100); }
+  if let Some(v) = get_bool("maintenance_health_ping", "maintenanceHealthPing") { prefs.maintenance_health_ping = v; }
+  if let Some(v) = get_bool("control_api", "controlApi") { prefs.control_api = v; }
+  if let Some(v) = get_u32("control_api_port", "controlApiPort") {
+    // Below 1024 needs elevated rights on most systems
+    if (1024..=65535).contains(&v) { prefs.control_api_port = v as u16; }
+  }
+  control_api::configure(&app, prefs.control_api, prefs.control_api_port);
+  if let Some(v) = get_str("journal_mode", "journalMode") {
+    let normalized = v.trim().to_lowercase();
+    if journal::JOURNAL_MODES.contains(&normalized.as_str()) {
+      prefs.journal_mode = normalized;
+    }
+  }
+  if let Some(v) = get_str("watch_output_format", "watchOutputFormat") {
+    let normalized = v.trim().to_lowercase();
+    if normalized == "txt" || normalized == "md" {
+      prefs.watch_output_format = normalized;
+    }
+  }
+  if let Some(v) = get_u32("transcription_concurrency", "transcriptionConcurrency") {
+    prefs.transcription_concurrency = v.clamp(1, jobs::MAX_CONCURRENCY as u32);
+  }
+  jobs::configure(prefs.transcription_concurrency as usize);
+  // null or "" turns translation off
+  if let Some(v) = args.get("translate_to").or_else(|| args.get("translateTo")) {
+    prefs.translate_to = v.as_str().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+  }
 
   let val = serde_json::to_value(&prefs).map_err(|e| e.to_string())?;
   store.set("behavior", val);
   store.save().map_err(|e| e.to_string())?;
-  eprintln!("set_behavior: saved prefs -> {:?}", prefs);
+  debug!("set_behavior: saved prefs -> {:?}", prefs);
+  sync_tray_checks(&app, &prefs);
   Ok(prefs)
 }
 
@@ -564,15 +2337,15 @@ async fn set_behavior(app: AppHandle, args: serde_json::Value) -> Result<Behavio
 async fn get_behavior(app: AppHandle) -> Result<BehaviorPrefs, String> {
   let store = app.store("prefs.json").map_err(|e| e.to_string())?;
   let mut prefs = if let Some(v) = store.get("behavior") {
-    eprintln!("get_behavior: behavior raw: {}", v);
+    debug!("get_behavior: behavior raw: {}", v);
     serde_json::from_value(v).unwrap_or_default()
   } else {
-    eprintln!("get_behavior: no behavior found, using defaults");
+    debug!("get_behavior: no behavior found, using defaults");
     BehaviorPrefs::default()
   };
   // Authoritative autostart value comes from the OS/plugin
   if let Ok(os_enabled) = app.autolaunch().is_enabled() { prefs.autostart = os_enabled; }
-  eprintln!("📦 get_behavior -> {:?}", prefs);
+  debug!("get_behavior -> {:?}", prefs);
   Ok(prefs)
 }
 
@@ -580,7 +2353,7 @@ async fn get_behavior(app: AppHandle) -> Result<BehaviorPrefs, String> {
 async fn probe_text_accepting(app: AppHandle) -> Result<bool, String> { probe_text_accepting_impl(&app).await }
 
 async fn probe_text_accepting_impl(app: &AppHandle) -> Result<bool, String> {
-  paste::quick_probe_can_paste(app).await
+  Ok(paste::probe_focus(app).await?.accepts_text())
 }
 
 #[tauri::command]
@@ -591,60 +2364,120 @@ async fn get_model(app: AppHandle) -> Result<String, String> { Ok(config::get_mo
 async fn set_megallm_model(app: AppHandle, name: String) -> Result<(), String> { config::set_megallm_model(&app, &name).await.map_err(|e| e.to_string()) }
 #[tauri::command]
 async fn get_megallm_model(app: AppHandle) -> Result<String, String> { Ok(config::get_megallm_model(&app).await.unwrap_or_else(|| "gpt-4".into())) }
+/// Set the language of an STT provider (the active one by default): "auto" or a code such as
+/// "en" or "en-US"; returns it as it will be sent
 #[tauri::command]
-async fn set_language(app: AppHandle, code: String) -> Result<(), String> { config::set_language(&app, &code).await.map_err(|e| e.to_string()) }
+async fn set_language(app: AppHandle, code: String, provider: Option<String>) -> Result<String, String> {
+  let provider = stt_provider_or_active(&app, provider).await?;
+  config::set_stt_language(&app, &provider, &code).await.map_err(|e| e.to_string())
+}
 #[tauri::command]
-async fn get_language(app: AppHandle) -> Result<String, String> { Ok(config::get_language(&app).await.unwrap_or_else(|| "en-US".into())) }
+async fn get_language(app: AppHandle, provider: Option<String>) -> Result<String, String> {
+  let provider = stt_provider_or_active(&app, provider).await?;
+  Ok(config::get_stt_language(&app, &provider).await)
+}
 
-#[tauri::command]
-async fn test_openrouter(app: AppHandle) -> Result<(), String> {
-  let _ = refine_text("ping".into(), app, None, None, Some("openrouter".into())).await?; Ok(())
+async fn stt_provider_or_active(app: &AppHandle, provider: Option<String>) -> Result<String, String> {
+  match provider {
+    Some(p) if p == "deepgram" || p == "elevenlabs" => Ok(p),
+    Some(p) => Err(format!("Unknown STT provider: {}", p)),
+    None => Ok(get_behavior(app.clone()).await?.stt_provider),
+  }
 }
 
 #[tauri::command]
-async fn test_deepgram(app: AppHandle) -> Result<(), String> {
-  // Browser-based test is better; here we just check presence of key.
-  if config::get_deepgram_key(&app).await.is_some() { Ok(()) } else { Err("Missing Deepgram key".into()) }
+async fn test_openrouter(app: AppHandle) -> Result<(), String> {
+  // A manual test should reach the provider even while its circuit is open
+  circuit::reset("openrouter");
+  // Straight to the provider: the pipeline answers on its own in demo mode, with refinement off
+  // and while offline
+  let behavior = effective_behavior(&app).await;
+  let system_prompt = refinement_system_prompt(&app, false, None);
+  let _ = refine_with_openrouter("ping".into(), app, None, system_prompt, AiLimits::from_prefs(&behavior)).await?; Ok(())
 }
 
+/// Check a Deepgram key (the given one or the saved one) against the hosted API or the
+/// self-hosted `endpoint` (the saved `stt_endpoint_override` when omitted)
 #[tauri::command]
-async fn test_megallm(app: AppHandle, api_key: Option<String>) -> Result<(), String> {
+async fn test_deepgram(app: AppHandle, api_key: Option<String>, endpoint: Option<String>) -> Result<(), String> {
+  let key = match api_key {
+    Some(k) if !k.is_empty() => k,
+    _ => config::get_deepgram_key(&app).await.ok_or("Missing Deepgram key")?,
+  };
+  // The endpoint being edited in Settings, or the saved one
+  let endpoint = match endpoint {
+    Some(e) => stt_options::normalize_endpoint(&e)?,
+    None => config::get_stt_options(&app).await.stt_endpoint_override,
+  };
+  let (base, _) = stt_options::deepgram_bases(&endpoint);
+  let resp = key_inspect::deepgram_request(&key, &endpoint)
+    .timeout(Duration::from_secs(5))
+    .send()
+    .await
+    .map_err(|e| format!("Could not reach {}: {}", base, e))?;
+  let status = resp.status();
+  if key_inspect::deepgram_accepted(&endpoint, status) {
+    Ok(())
+  } else if status == reqwest::StatusCode::UNAUTHORIZED {
+    Err("Deepgram rejected the key".into())
+  } else if status == reqwest::StatusCode::FORBIDDEN {
+    Err("Deepgram took the key but refused the projects list (HTTP 403); it may lack permissions".into())
+  } else {
+    Err(format!("{} answered HTTP {}", base, status))
+  }
+}
+
+#[tauri::command]
+async fn test_megallm(app: AppHandle, api_key: Option<String>) -> Result<(), String> {
   let _ = list_megallm_models(app, api_key).await?; Ok(())
 }
 
 #[tauri::command]
 async fn create_elevenlabs_token(app: AppHandle, api_key: Option<String>) -> Result<String, String> {
+  elevenlabs_token(&app, api_key).await.map(|(token, _)| token)
+}
+
+/// A single-use realtime token, with the response headers (they carry the plan's limits)
+async fn elevenlabs_token(app: &AppHandle, api_key: Option<String>) -> Result<(String, reqwest::header::HeaderMap), String> {
   let key = match api_key {
     Some(k) if !k.is_empty() => k,
-    _ => config::get_elevenlabs_key(&app).await.ok_or("Missing ElevenLabs key")?,
+    _ => config::get_elevenlabs_key(app).await.ok_or("Missing ElevenLabs key")?,
   };
-  let client = reqwest::Client::builder()
-    .timeout(Duration::from_secs(5))
-    .build()
-    .map_err(|e| e.to_string())?;
-  let resp = client
-    .post("https://api.elevenlabs.io/v1/single-use-token/realtime_scribe")
-    .header("xi-api-key", key)
-    .header("content-length", "0")
-    .body("")
-    .send()
-    .await
-    .map_err(|e| e.to_string())?;
+  let build = || {
+    http_client::shared()
+      .post("https://api.elevenlabs.io/v1/single-use-token/realtime_scribe")
+      .timeout(Duration::from_secs(5))
+      .header("xi-api-key", &key)
+      .header("content-length", "0")
+      .body("")
+  };
+  let resp = http_retry::send("elevenlabs", build, |_| {}).await.map_err(|e| e.to_string())?;
   let status = resp.status();
+  let headers = resp.headers().clone();
   let body = resp.text().await.map_err(|e| e.to_string())?;
   if !status.is_success() {
     return Err(format!("ElevenLabs HTTP {} - {}", status, body));
   }
   let v: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
   let token = v.get("token").and_then(|t| t.as_str()).ok_or("Missing token in ElevenLabs response")?;
-  Ok(token.to_string())
+  Ok((token.to_string(), headers))
 }
 
 #[tauri::command]
-async fn test_elevenlabs(app: AppHandle, api_key: Option<String>) -> Result<(), String> {
+async fn test_elevenlabs(app: AppHandle, api_key: Option<String>) -> Result<stt_options::ElevenLabsCheck, String> {
   // Generating a single-use token is a lightweight validity check.
-  let _ = create_elevenlabs_token(app, api_key).await?;
-  Ok(())
+  let (_, headers) = elevenlabs_token(&app, api_key).await?;
+  let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).and_then(|v| v.trim().parse().ok());
+  let options = config::get_stt_options(&app).await.elevenlabs;
+  let language = config::get_stt_language(&app, "elevenlabs").await;
+  Ok(stt_options::ElevenLabsCheck {
+    model: options.model,
+    language: stt_options::elevenlabs_language(&language).map(str::to_string),
+    max_concurrent_requests: header("maximum-concurrent-requests"),
+    current_concurrent_requests: header("current-concurrent-requests"),
+    token_ttl_secs: stt_options::SCRIBE_TOKEN_TTL_SECS,
+    max_batch_secs: (stt::BATCH_MAX_BYTES / (stt::SAMPLE_RATE as usize * 2)) as u64,
+  })
 }
 
 #[tauri::command]
@@ -653,12 +2486,9 @@ async fn list_megallm_models(app: AppHandle, api_key: Option<String>) -> Result<
     Some(k) if !k.is_empty() => k,
     _ => config::get_megallm_key(&app).await.ok_or("Missing MegaLLM key")?,
   };
-  let client = reqwest::Client::builder()
-    .timeout(Duration::from_secs(5))
-    .build()
-    .map_err(|e| e.to_string())?;
-  let resp = client
+  let resp = http_client::shared()
     .get("https://ai.megallm.io/v1/models")
+    .timeout(Duration::from_secs(5))
     .header("authorization", format!("Bearer {}", key))
     .send()
     .await
@@ -674,141 +2504,1336 @@ async fn list_megallm_models(app: AppHandle, api_key: Option<String>) -> Result<
 }
 
 #[tauri::command]
-async fn insert_text(app: AppHandle, text: String) -> Result<bool, String> { paste::copy_and_paste(&app, &text).await }
+async fn insert_text(app: AppHandle, transcript: transcript::Transcript, copy_only: Option<bool>) -> Result<bool, String> {
+  let text = redact_output(&app, transcript.output_text()).await;
+  // Streaming insertion typed the words as they came: turn that into the final text in place
+  if paste::is_streaming() {
+    let started = Instant::now();
+    let typed = paste::finish_stream(&text).await;
+    if let Err(e) = &typed {
+      warn!("Streaming insertion incomplete ({}), leaving the text on the clipboard", e);
+      paste::copy_only(&app, &text)?;
+      failures::record(&app, failures::FailureKind::PasteFailed, e.as_str());
+    }
+    record_dictation(&app, &transcript, typed.is_ok(), Some(started.elapsed().as_millis() as u64)).await;
+    return Ok(typed.is_ok());
+  }
+  if copy_only.unwrap_or(false) {
+    info!("Copy only (paste not confirmed)");
+    paste::copy_only(&app, &text)?;
+    record_dictation(&app, &transcript, false, None).await;
+    return Ok(false);
+  }
+  if !journal_dictation(&app, &text).await {
+    record_dictation(&app, &transcript, false, None).await;
+    return Ok(false);
+  }
+  // The window this dictation started in closed while refinement ran: don't paste into
+  // whatever has focus now. Keep the text on the clipboard and offer the scratchpad.
+  if effective_behavior(&app).await.auto_paste {
+    if let Some(closed) = window_target::closed_session_window() {
+      info!("Target window closed mid-dictation ({}), not pasting", closed.menu_label());
+      paste::copy_only(&app, &text)?;
+      scratchpad::stash(&text);
+      if window_target::get_target().is_some_and(|t| t.id == closed.id) {
+        window_target::set_target(None);
+        let _ = refresh_target_menu(&app);
+      }
+      record_dictation(&app, &transcript, false, None).await;
+      failures::record(&app, failures::FailureKind::TargetClosed, closed.menu_label());
+      if let Some(win) = app.get_webview_window("hud") {
+        let _ = win.show();
+      }
+      emit_hud_event(&app, HudEvent::TargetClosed { window: closed.menu_label() });
+      return Ok(false);
+    }
+  }
+  let started = Instant::now();
+  let pasted = insert_text_impl(&app, &text).await?;
+  let paste_ms = started.elapsed().as_millis() as u64;
+  if !pasted && effective_behavior(&app).await.auto_paste {
+    failures::record(&app, failures::FailureKind::PasteFailed, "paste keystroke failed, text left on clipboard");
+  }
+  record_dictation(&app, &transcript, pasted, Some(paste_ms)).await;
+  Ok(pasted)
+}
+
+/// STT list price per minute for the dictations `provider` transcribes with the saved options
+async fn stt_rate(app: &AppHandle, provider: &str) -> Option<f64> {
+  let options = config::get_stt_options(app).await;
+  match provider {
+    // A self-hosted server isn't billed per minute
+    "deepgram" if !options.stt_endpoint_override.is_empty() => None,
+    "deepgram" => cost::stt_rate(provider, &options.deepgram.model),
+    "elevenlabs" => cost::stt_rate(provider, &options.elevenlabs.model),
+    _ => cost::stt_rate(provider, ""),
+  }
+}
+
+/// Keep a finished dictation in history, with the metrics (and estimated cost) of its session
+async fn record_dictation(app: &AppHandle, transcript: &transcript::Transcript, pasted: bool, paste_ms: Option<u64>) {
+  let words = transcript.text().split_whitespace().count() as u64;
+  // A failed paste took time but pasted nothing; keep it out of the paste timings
+  let paste_ms = paste_ms.filter(|_| pasted);
+  let metrics = session_metrics::finish(&transcript.provider, words, paste_ms, stt_rate(app, &transcript.provider).await);
+  if let Some(m) = &metrics {
+    if let Some(timing) = m.timing {
+      session_metrics::emit_timing(app, &timing);
+    }
+    if let Some(cost) = &m.cost {
+      session_metrics::record_cost(app, m.duration_ms, cost);
+    }
+  }
+  // A streamed dictation that got this far is a successful STT request
+  if metrics.is_some() && !transcript.provider.is_empty() && transcript.provider != "demo" {
+    session_metrics::record_call(app, &transcript.provider, true);
+  }
+  if let Err(e) = history::record_transcript(app, transcript, profiles::session_app(), pasted, metrics) {
+    warn!("Failed to record history entry: {}", e);
+  }
+}
+
+/// Path template of the journal file, if one is set
+fn journal_target(app: &AppHandle) -> Option<String> {
+  app.store("prefs.json").ok()?.get("journal_target")?.as_str().map(str::to_string).filter(|t| !t.is_empty())
+}
+
+fn journal_path(app: &AppHandle, template: &str) -> Result<std::path::PathBuf, String> {
+  let home = app.path().home_dir().map_err(|e| e.to_string())?;
+  journal::expand_path(template, &chrono::Local::now().naive_local(), &home)
+}
+
+fn journal_templates(app: &AppHandle) -> journal::Templates {
+  app
+    .store("prefs.json")
+    .ok()
+    .and_then(|s| s.get("journal_templates"))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+/// Write a dictation into the journal file; returns the file
+fn write_journal(app: &AppHandle, template: &str, text: &str) -> Result<std::path::PathBuf, String> {
+  let path = journal_path(app, template)?;
+  let app_key = profiles::session_app();
+  let entry = journal::Entry { text, app: app_key.as_deref(), now: chrono::Local::now().naive_local() };
+  let (block, front_matter) = journal_templates(app).render(&entry)?;
+  journal::append(&path, &block, front_matter.as_deref()).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+  Ok(path)
+}
+
+/// Append a dictation to the journal when journal mode is on. Returns whether it should still be
+/// pasted: not in "only" mode, unless the journal couldn't be written and the text would be lost
+async fn journal_dictation(app: &AppHandle, text: &str) -> bool {
+  let mode = effective_behavior(app).await.journal_mode;
+  if mode == "off" {
+    return true;
+  }
+  let Some(template) = journal_target(app) else {
+    warn!("Journal mode is {} but no journal file is set", mode);
+    return true;
+  };
+  match write_journal(app, &template, text) {
+    Ok(path) => {
+      info!("Appended dictation to journal {}", path.display());
+      if mode == "only" {
+        emit_hud_event(app, HudEvent::badge("Added to journal"));
+        return false;
+      }
+    }
+    Err(e) => {
+      warn!("Journal append failed: {}", e);
+      emit_hud_event(app, HudEvent::badge("Could not write the journal"));
+    }
+  }
+  true
+}
+
+/// Set the journal file as a path template (`{{date}}`, `{{date:%Y-%m}}`, `{{time}}`, `~`);
+/// empty clears it. Returns the file today's dictations would go to
+#[tauri::command]
+fn set_journal_target(app: AppHandle, path_template: Option<String>) -> Result<Option<String>, String> {
+  let template = path_template.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+  let path = match &template {
+    Some(t) => Some(journal_path(&app, t)?.display().to_string()),
+    None => None,
+  };
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  match &template {
+    Some(t) => store.set("journal_target", serde_json::json!(t)),
+    None => { store.delete("journal_target"); }
+  }
+  store.save().map_err(|e| e.to_string())?;
+  info!("Journal target set to {:?}", template);
+  Ok(path)
+}
+
+#[tauri::command]
+fn get_journal_target(app: AppHandle) -> Option<String> {
+  journal_target(&app)
+}
+
+/// Set the block template (`{{date}}`, `{{time}}`, `{{app}}`, `{{text}}`) and the front matter for
+/// new journal files; empty restores the default block or drops the front matter
+#[tauri::command]
+fn set_journal_template(app: AppHandle, template: Option<String>, front_matter: Option<String>) -> Result<(), String> {
+  let non_empty = |t: Option<String>| t.filter(|t| !t.trim().is_empty());
+  let templates = journal::Templates { block: non_empty(template), front_matter: non_empty(front_matter) };
+  // Reject placeholders that would fail at dictation time
+  let sample = journal::Entry { text: "", app: None, now: chrono::Local::now().naive_local() };
+  templates.render(&sample)?;
+  let store = app.store("prefs.json").map_err(|e| e.to_string())?;
+  store.set("journal_templates", serde_json::to_value(&templates).map_err(|e| e.to_string())?);
+  store.save().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_journal_template(app: AppHandle) -> journal::Templates {
+  journal_templates(&app)
+}
+
+/// Render what a dictation of `text` would add to the journal now, with the given templates or
+/// the saved ones, without writing anything
+#[tauri::command]
+fn test_journal_template(app: AppHandle, text: Option<String>, template: Option<String>, front_matter: Option<String>) -> Result<journal::Preview, String> {
+  let saved = journal_templates(&app);
+  let templates = journal::Templates { block: template.or(saved.block), front_matter: front_matter.or(saved.front_matter) };
+  let text = text.unwrap_or_else(|| "This is what a dictation looks like.".into());
+  let app_key = profiles::session_app();
+  let entry = journal::Entry { text: &text, app: app_key.as_deref(), now: chrono::Local::now().naive_local() };
+  let (block, front_matter) = templates.render(&entry)?;
+  let path = journal_target(&app).map(|t| journal_path(&app, &t)).transpose()?;
+  let existing = path.as_ref().and_then(|p| std::fs::read_to_string(p).ok());
+  Ok(journal::Preview {
+    path: path.map(|p| p.display().to_string()),
+    addition: journal::addition(existing.as_deref(), &block, front_matter.as_deref()),
+  })
+}
+
+/// Target-window locking: if focus drifted away from the window the dictation started in (while
+/// recording or refining), raise that window again so the text doesn't land in another app.
+/// Returns false when it couldn't be raised: the text must not be pasted where focus is now
+async fn refocus_session_window(app: &AppHandle) -> bool {
+  let Some(window) = window_target::session_window() else { return true };
+  if window_target::foreground_window().is_some_and(|w| w.id == window.id) {
+    return true;
+  }
+  info!("Focus moved during dictation, refocusing {}", window.menu_label());
+  if !window_target::raise(&window) {
+    warn!("Could not refocus {}, leaving the text on the clipboard", window.menu_label());
+    emit_hud_event(app, HudEvent::badge(format!("Couldn't return to {}; the text is on the clipboard", window.menu_label())));
+    return false;
+  }
+  tokio::time::sleep(Duration::from_millis(150)).await;
+  true
+}
+
+/// Mask the redaction categories in text about to leave the app. Refinement already masks its
+/// output, but a raw transcript (refinement off or failed) or a transcribed file comes here unmasked
+async fn redact_output(app: &AppHandle, text: String) -> String {
+  let categories = effective_behavior(app).await.redact;
+  if categories.is_empty() { text } else { pii::redact(&text, &categories) }
+}
+
+async fn insert_text_impl(app: &AppHandle, text: &str) -> Result<bool, String> {
+  let behavior = effective_behavior(app).await;
+  // Editing a preview left the HUD focused: that always goes back to the session window
+  let edited = paste_confirm::take_edited();
+  if !behavior.auto_paste {
+    info!("Auto-paste disabled, copying to clipboard only");
+    paste::copy_only(app, text)?;
+    return Ok(false);
+  }
+  if let Some(target) = window_target::get_target() {
+    info!("Activating paste target: {}", target.menu_label());
+    if window_target::activate(target.id) {
+      // Give the OS a moment to move keyboard focus before the paste keystroke
+      tokio::time::sleep(Duration::from_millis(150)).await;
+    } else {
+      warn!("Paste target no longer available, clearing it");
+      window_target::set_target(None);
+      let _ = refresh_target_menu(app);
+      emit_hud_event(app, HudEvent::badge("Paste target window is gone"));
+    }
+  } else if (edited || behavior.lock_target_window) && !refocus_session_window(app).await {
+    paste::copy_only(app, text)?;
+    return Ok(false);
+  }
+  if behavior.insert_mode == "macro" {
+    let key_macro = keymacro::parse(text);
+    match save_macro(app, &key_macro) {
+      Ok(path) => info!("Keystroke macro with {} steps saved to {}", key_macro.steps.len(), path.display()),
+      Err(e) => warn!("Could not save keystroke macro: {}", e),
+    }
+    match paste::play_macro(key_macro.steps, Duration::from_millis(behavior.type_delay_ms as u64)).await {
+      Ok(()) => return Ok(true),
+      Err(e) if !e.sent => warn!("Macro replay failed ({}), falling back to paste", e),
+      Err(e) => return stopped_partway(app, text, "Macro replay", e),
+    }
+  }
+  if behavior.insert_mode == "type" {
+    info!("Typing {} chars ({}ms per char)", text.chars().count(), behavior.type_delay_ms);
+    match paste::type_text(text, Duration::from_millis(behavior.type_delay_ms as u64)).await {
+      Ok(()) => return Ok(true),
+      Err(e) if !e.sent => warn!("Type-out failed ({}), falling back to paste", e),
+      Err(e) => return stopped_partway(app, text, "Type-out", e),
+    }
+  }
+  if behavior.insert_mode == "human" {
+    info!("Typing {} chars at a human pace ({}-{}ms per key)", text.chars().count(), behavior.human_min_ms, behavior.human_max_ms);
+    match paste::type_human(text, behavior.human_min_ms, behavior.human_max_ms).await {
+      Ok(()) => return Ok(true),
+      Err(e) if !e.sent => warn!("Human typing failed ({}), falling back to paste", e),
+      Err(e) => return stopped_partway(app, text, "Human typing", e),
+    }
+  }
+  paste::copy_and_paste(app, text, clipboard_restore_delay(&behavior), &behavior.terminal_apps).await
+}
+
+/// Typing broke off after part of the text went out: pasting now would insert that part twice,
+/// so the whole text goes on the clipboard instead
+fn stopped_partway(app: &AppHandle, text: &str, what: &str, e: paste::InputError) -> Result<bool, String> {
+  warn!("{} stopped partway ({}), leaving the text on the clipboard", what, e);
+  paste::copy_only(app, text)?;
+  emit_hud_event(app, HudEvent::badge("Typing stopped partway; the full text is on the clipboard"));
+  failures::record(app, failures::FailureKind::PasteFailed, format!("{}: {}", what.to_lowercase(), e));
+  Err(e.into())
+}
+
+/// Saved macros beyond this many are deleted, oldest first
+const MAX_SAVED_MACROS: usize = 50;
+
+fn macro_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+  Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("macros"))
+}
+
+/// The saved macros, oldest first (macro-<millis>.json: name order is age order)
+fn saved_macros(dir: &Path) -> Vec<std::path::PathBuf> {
+  let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+  let mut files: Vec<_> = entries
+    .filter_map(|e| e.ok().map(|e| e.path()))
+    .filter(|p| p.extension().is_some_and(|x| x == "json"))
+    .collect();
+  files.sort();
+  files
+}
+
+/// Keep a macro as JSON so it can be replayed later with `replay_macro`, and only the latest
+/// `MAX_SAVED_MACROS` of them
+fn save_macro(app: &AppHandle, key_macro: &keymacro::KeyMacro) -> Result<std::path::PathBuf, String> {
+  let dir = macro_dir(app)?;
+  std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+  let path = dir.join(format!("macro-{}.json", history::now_millis()));
+  let json = serde_json::to_string_pretty(key_macro).map_err(|e| e.to_string())?;
+  std::fs::write(&path, json).map_err(|e| e.to_string())?;
+  let saved = saved_macros(&dir);
+  for old in &saved[..saved.len().saturating_sub(MAX_SAVED_MACROS)] {
+    if let Err(e) = std::fs::remove_file(old) {
+      warn!("Could not delete old macro {}: {}", old.display(), e);
+    }
+  }
+  Ok(path)
+}
+
+/// Replay a saved keystroke macro (default: the newest) into the pinned paste target or the
+/// focused window. `path` is a file name or path in the macros folder; files elsewhere are refused,
+/// so the command can't be used to type out arbitrary files
+#[tauri::command]
+async fn replay_macro(app: AppHandle, path: Option<String>) -> Result<(), String> {
+  let dir = macro_dir(&app)?;
+  let path = match path {
+    Some(p) => {
+      let path = dir.join(p).canonicalize().map_err(|e| format!("No such macro: {}", e))?;
+      let inside = dir.canonicalize().is_ok_and(|d| path.parent() == Some(d.as_path()));
+      if !inside || path.extension().is_none_or(|x| x != "json") {
+        return Err(format!("Not a saved macro: {}", path.display()));
+      }
+      path
+    }
+    None => saved_macros(&dir).pop().ok_or("No saved macros")?,
+  };
+  let json = std::fs::read_to_string(&path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+  let key_macro: keymacro::KeyMacro = serde_json::from_str(&json).map_err(|e| format!("Not a keystroke macro: {}", e))?;
+  if key_macro.version > keymacro::MACRO_VERSION {
+    return Err(format!("Macro version {} is newer than this app supports", key_macro.version));
+  }
+  if let Some(target) = window_target::get_target() {
+    if window_target::activate(target.id) {
+      tokio::time::sleep(Duration::from_millis(150)).await;
+    }
+  }
+  info!("Replaying macro {} ({} steps)", path.display(), key_macro.steps.len());
+  let behavior = effective_behavior(&app).await;
+  paste::play_macro(key_macro.steps, Duration::from_millis(behavior.type_delay_ms as u64)).await.map_err(String::from)
+}
+
+/// What the focused element accepts, per the platform accessibility probe
+#[tauri::command]
+async fn get_focus_kind(app: AppHandle) -> Result<focus_probe::FocusKind, String> {
+  paste::probe_focus(&app).await
+}
+
+/// Event and command schema for external clients and alternative frontends
+#[tauri::command]
+fn get_api_schema() -> serde_json::Value {
+  api_schema::document()
+}
+
+/// Open the scratchpad with the last dictation that couldn't be pasted
+#[tauri::command]
+fn open_scratchpad(app: AppHandle) -> Result<(), String> {
+  scratchpad::open(&app)
+}
+
+#[tauri::command]
+fn get_scratchpad_text() -> Option<String> {
+  scratchpad::text()
+}
+
+/// Whether the next auto-paste needs a confirmation keypress (training wheels)
+#[tauri::command]
+async fn get_paste_confirmation(app: AppHandle) -> paste_confirm::ConfirmStatus {
+  let behavior = effective_behavior(&app).await;
+  // Streamed text is already in the target, so there is nothing left to confirm
+  let asks = behavior.auto_paste && !paste::is_streaming();
+  let limit = if asks { behavior.confirm_first_pastes } else { 0 };
+  paste_confirm::status(&app, limit, asks && behavior.confirm_before_paste)
+}
+
+/// Wait for Enter (paste) or Esc (copy only, or discard when previewing); the HUD shows the
+/// prompt, and in preview mode the text, meanwhile
+#[tauri::command]
+async fn confirm_paste(app: AppHandle) -> Result<paste_confirm::Decision, String> {
+  let behavior = effective_behavior(&app).await;
+  let preview = behavior.auto_paste && behavior.confirm_before_paste;
+  // Reading the text back takes longer than a glance at the prompt
+  let timeout = Duration::from_secs(if preview { 60 } else { 20 });
+  paste_confirm::ask(&app, timeout, preview).await
+}
+
+/// Answer a pending paste confirmation from the HUD: paste, discard, or edit the preview first
+#[tauri::command]
+fn answer_paste_confirmation(answer: paste_confirm::Reply) -> Result<(), String> {
+  paste_confirm::resolve(answer)
+}
+
+fn clipboard_restore_delay(prefs: &BehaviorPrefs) -> Option<Duration> {
+  prefs.preserve_clipboard.then(|| Duration::from_millis(prefs.clipboard_restore_ms as u64))
+}
+
+#[tauri::command]
+fn list_windows() -> Vec<window_target::WindowInfo> { window_target::list_windows() }
+
+#[tauri::command]
+fn set_paste_target(app: AppHandle, id: Option<u64>) -> Result<Option<window_target::WindowInfo>, String> {
+  let target = match id {
+    Some(id) => Some(window_target::find_listed(id).ok_or("Unknown window id; call list_windows first")?),
+    None => None,
+  };
+  window_target::set_target(target.clone());
+  let _ = refresh_target_menu(&app);
+  Ok(target)
+}
+
+#[tauri::command]
+fn get_active_app() -> Option<profiles::ActiveApp> { profiles::get_active_app() }
+
+#[tauri::command]
+fn list_app_profiles(app: AppHandle) -> std::collections::BTreeMap<String, profiles::AppOverrides> { profiles::load_profiles(&app) }
+
+// `app` is the application name here (as the frontend sends it), so the handle gets a different name
+#[tauri::command]
+fn set_app_profile(
+  handle: AppHandle,
+  app: String,
+  overrides: Option<profiles::AppOverrides>,
+) -> Result<std::collections::BTreeMap<String, profiles::AppOverrides>, String> {
+  profiles::set_app_profile(&handle, &app, overrides).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn set_app_profiles_bulk(
+  app: AppHandle,
+  apps: Vec<String>,
+  overrides: profiles::AppOverrides,
+  merge: Option<bool>,
+) -> Result<std::collections::BTreeMap<String, profiles::AppOverrides>, String> {
+  profiles::set_app_profiles_bulk(&app, &apps, &overrides, merge.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+/// Apps dictated into most (from history), flagged with whether they already have a profile
+#[tauri::command]
+fn get_app_usage(app: AppHandle, limit: Option<usize>) -> Vec<history::AppUsage> {
+  let profiled: Vec<String> = profiles::load_profiles(&app).into_keys().collect();
+  let mut usage = history::app_usage(&history::load_entries(&app), &profiled);
+  if let Some(n) = limit { usage.truncate(n); }
+  usage
+}
+
+#[tauri::command]
+fn get_paste_target() -> Option<window_target::WindowInfo> { window_target::get_target() }
 
 #[tauri::command]
 async fn runtime_keys(app: AppHandle) -> Result<(Option<String>, Option<String>, Option<String>, Option<String>), String> {
-  eprintln!("dY\"`dY\"` runtime_keys COMMAND INVOKED dY\"`dY\"`");
   let or = config::get_openrouter_key(&app).await;
   let dg = config::get_deepgram_key(&app).await;
   let mg = config::get_megallm_key(&app).await;
   let el = config::get_elevenlabs_key(&app).await;
-  eprintln!("Returning keys - OpenRouter: {}, Deepgram: {}, MegaLLM: {}, ElevenLabs: {}",
-    if or.is_some() { "? present" } else { "? missing" },
-    if dg.is_some() { "? present" } else { "? missing" },
-    if mg.is_some() { "? present" } else { "? missing" },
-    if el.is_some() { "? present" } else { "? missing" }
+  info!("Returning keys - OpenRouter: {}, Deepgram: {}, MegaLLM: {}, ElevenLabs: {}",
+    if or.is_some() { "present" } else { "missing" },
+    if dg.is_some() { "present" } else { "missing" },
+    if mg.is_some() { "present" } else { "missing" },
+    if el.is_some() { "present" } else { "missing" }
   );
   Ok((or, dg, mg, el))
 }
 
+#[tauri::command]
+fn get_failure_stats(app: AppHandle) -> failures::FailureStats { failures::stats(&app) }
+
+#[tauri::command]
+fn reset_failure_stats(app: AppHandle) -> Result<(), String> { failures::reset(&app).map_err(|e| e.to_string()) }
+
+/// Lets the HUD count failures that happen on its side (e.g. STT WebSocket errors)
+#[tauri::command]
+async fn report_failure(app: AppHandle, kind: String, detail: Option<String>) -> Result<(), String> {
+  let kind = failures::FailureKind::parse(&kind).ok_or_else(|| format!("Unknown failure kind: {}", kind))?;
+  failures::record(&app, kind, detail.unwrap_or_default());
+  if kind == failures::FailureKind::SttConnection {
+    session_metrics::record_call(&app, &get_behavior(app.clone()).await?.stt_provider, false);
+  }
+  Ok(())
+}
+
+/// Totals for the stats page over "today", "7d", "30d", "90d" or "all"
+#[tauri::command]
+fn get_usage_stats(app: AppHandle, range: Option<String>) -> Result<usage::UsageStats, String> {
+  session_metrics::stats(&app, range.as_deref().unwrap_or("30d"))
+}
+
+/// The HUD showed the first words of the session
+#[tauri::command]
+fn mark_first_partial() {
+  session_metrics::first_partial();
+}
+
+/// Open a backend relay session to the configured STT provider. Returns the session id
+/// carried by the relay transcript and status events.
+#[tauri::command]
+async fn stt_relay_start(app: AppHandle, meeting: Option<bool>) -> Result<u64, String> {
+  let prefs = effective_behavior(&app).await;
+  let noise = audio_device::noise_mode(&app, audio_device::NoiseMode::from_prefs(prefs.noise_suppression, prefs.rnnoise));
+  let meeting = meeting.unwrap_or(false);
+  let cfg = stt_config(&app)
+    .await?
+    // Meetings have long pauses and run until stopped by hand
+    .with_silence_stop(if meeting { 0 } else { prefs.silence_secs })
+    .with_auto_gain(prefs.auto_gain)
+    .with_denoise(noise == audio_device::NoiseMode::Rnnoise)
+    .with_meeting(meeting)
+    .with_redaction(prefs.redact);
+  Ok(stt::start(&app, cfg))
+}
+
+/// Begin meeting transcription; returns the transcript file path
+#[tauri::command]
+fn start_meeting(app: AppHandle) -> Result<String, String> {
+  meeting::start(&app).map(|p| p.display().to_string())
+}
+
+/// End meeting transcription; returns the transcript file path
+#[tauri::command]
+fn stop_meeting() -> Option<String> {
+  meeting::stop().map(|p| p.display().to_string())
+}
+
+#[tauri::command]
+fn get_meeting_status() -> meeting::MeetingStatus {
+  meeting::status()
+}
+
+#[tauri::command]
+fn open_meeting_window(app: AppHandle) -> Result<(), String> {
+  meeting::open_window(&app)
+}
+
+/// Provider, key and language for backend transcription, from prefs and stored keys
+async fn stt_config(app: &AppHandle) -> Result<stt::RelayConfig, String> {
+  let prefs = get_behavior(app.clone()).await?;
+  stt_config_for(app, &prefs.stt_provider).await
+}
+
+/// `stt_config` with an explicit provider, checked to be one the app supports
+async fn stt_config_with(app: &AppHandle, provider: Option<&str>) -> Result<stt::RelayConfig, String> {
+  match provider {
+    Some(p) if p == "deepgram" || p == "elevenlabs" => stt_config_for(app, p).await,
+    Some(p) => Err(format!("Unknown STT provider: {}", p)),
+    None => stt_config(app).await,
+  }
+}
+
+async fn stt_config_for(app: &AppHandle, provider: &str) -> Result<stt::RelayConfig, String> {
+  let language = config::get_stt_language(app, provider).await;
+  if provider == "elevenlabs" {
+    let key = config::get_elevenlabs_key(app).await.ok_or("ElevenLabs API key not set")?;
+    Ok(stt::RelayConfig::elevenlabs(&key, &language).with_elevenlabs_options(config::get_stt_options(app).await.elevenlabs))
+  } else {
+    let key = config::get_deepgram_key(app).await.ok_or("Deepgram API key not set")?;
+    let options = config::get_stt_options(app).await;
+    Ok(
+      stt::RelayConfig::deepgram(&key, &language)
+        .with_keywords(stt_keywords(app.clone()))
+        .with_endpoint(&options.stt_endpoint_override)
+        .with_deepgram_options(options.deepgram),
+    )
+  }
+}
+
+/// Save provider connection options (Deepgram model, formatting and endpointing, ElevenLabs
+/// model and language hint); used from the next connection on
+#[tauri::command]
+async fn set_stt_options(app: AppHandle, mut options: stt_options::SttOptions) -> Result<(), String> {
+  options.stt_endpoint_override = stt_options::normalize_endpoint(&options.stt_endpoint_override)?;
+  options.validate()?;
+  config::set_stt_options(&app, &options).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+async fn get_stt_options(app: AppHandle) -> stt_options::SttOptions {
+  config::get_stt_options(&app).await
+}
+
+/// Newest dictations first, without their word timing; `query` keeps those containing its words
+#[tauri::command]
+fn list_history(app: AppHandle, limit: Option<usize>, query: Option<String>) -> Vec<history::HistoryItem> {
+  history::search(&app, query.as_deref().unwrap_or(""), limit.unwrap_or(100))
+}
+
+/// Delete history entries; returns how many were removed
+#[tauri::command]
+fn delete_history(app: AppHandle, ids: Vec<u64>) -> Result<usize, String> {
+  history::delete(&app, &ids).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn open_history_window(app: AppHandle) -> Result<(), String> {
+  history::open_window(&app)
+}
+
+/// Export a history entry as txt, md, json, srt or vtt to `path`, or to the Downloads folder
+#[tauri::command]
+fn export_transcript(app: AppHandle, id: u64, format: String, path: Option<String>) -> Result<String, String> {
+  history::export(&app, id, &format.to_lowercase(), path.as_deref().map(Path::new)).map(|p| p.display().to_string())
+}
+
+/// Token for the local control API, created on first use
+#[tauri::command]
+fn get_control_api_token(app: AppHandle) -> Result<String, String> {
+  control_api::token(&app)
+}
+
+#[tauri::command]
+fn regenerate_control_api_token(app: AppHandle) -> Result<String, String> {
+  control_api::regenerate_token(&app)
+}
+
+/// Schedule, last run and last report of the daily maintenance task
+#[tauri::command]
+fn get_maintenance_status(app: AppHandle) -> maintenance::MaintenanceStatus {
+  maintenance::status(&app)
+}
+
+/// Run maintenance now instead of waiting for the schedule
+#[tauri::command]
+async fn run_maintenance(app: AppHandle) -> Result<maintenance::MaintenanceReport, String> {
+  maintenance::run(&app).await.ok_or_else(|| "Maintenance is already running".to_string())
+}
+
+#[tauri::command]
+fn watch_folder_status() -> watch_folder::WatchStatus {
+  watch_folder::status()
+}
+
+/// File transcription jobs, oldest first
+#[tauri::command]
+fn list_jobs() -> Vec<jobs::Job> {
+  jobs::list()
+}
+
+#[tauri::command]
+fn cancel_job(app: AppHandle, id: u64) -> Result<jobs::Job, String> {
+  jobs::cancel(&app, id)
+}
+
+/// Queue every audio file in a folder as one batch; transcripts are written next to the files.
+/// Whether `path` is a folder, so a drop can tell folders from files whatever their names
+#[tauri::command]
+fn is_folder(path: String) -> bool {
+  Path::new(&path).is_dir()
+}
+
+/// `refine` defaults to the AI refinement setting, `output_format` ("txt" | "md") to the watch
+/// folder's
+#[tauri::command]
+async fn transcribe_folder(app: AppHandle, path: String, refine: Option<bool>, output_format: Option<String>, recursive: Option<bool>) -> Result<jobs::BatchProgress, String> {
+  let prefs = get_behavior(app.clone()).await?;
+  let format = output_format.unwrap_or(prefs.watch_output_format);
+  if format != "txt" && format != "md" {
+    return Err(format!("Unknown output format: {}", format));
+  }
+  jobs::enqueue_folder(&app, Path::new(&path), refine.unwrap_or(prefs.ai_refine), &format, recursive.unwrap_or(false))
+}
+
+/// Raw linear16 PCM bytes from the HUD's audio graph
+#[tauri::command]
+fn stt_relay_audio(request: tauri::ipc::Request<'_>) -> Result<(), String> {
+  match request.body() {
+    tauri::ipc::InvokeBody::Raw(bytes) => stt::push_audio(bytes.clone()),
+    _ => Err("Expected raw audio bytes".into()),
+  }
+}
+
+#[tauri::command]
+fn stt_relay_finalize() -> Result<(), String> { stt::finalize() }
+
+/// Direct mode: the HUD starts a new recording of the audio it streams to the provider
+#[tauri::command]
+fn record_audio_start() { last_audio::begin() }
+
+/// Direct mode: a copy of the linear16 PCM streamed to the provider, kept for `save_last_audio`
+#[tauri::command]
+fn record_audio(request: tauri::ipc::Request<'_>) -> Result<(), String> {
+  match request.body() {
+    tauri::ipc::InvokeBody::Raw(bytes) => {
+      last_audio::push(bytes);
+      Ok(())
+    }
+    _ => Err("Expected raw audio bytes".into()),
+  }
+}
+
+#[tauri::command]
+fn last_audio_info() -> Option<last_audio::LastAudioInfo> { last_audio::info() }
+
+/// Write the last dictation's audio as WAV to `path`, or to the Downloads folder
+#[tauri::command]
+fn save_last_audio(app: AppHandle, path: Option<String>) -> Result<String, String> {
+  last_audio::save(&app, path.as_deref().map(Path::new)).map(|p| p.display().to_string())
+}
+
+/// Transcribe the last dictation's audio again, with `provider` ("deepgram" | "elevenlabs") or
+/// the configured one; the text is added to history
+#[tauri::command]
+async fn retranscribe_last_audio(app: AppHandle, provider: Option<String>) -> Result<String, String> {
+  let cfg = stt_config_with(&app, provider.as_deref()).await?;
+  last_audio::retranscribe(&app, cfg).await
+}
+
+/// Transcribe an audio file with `provider` ("deepgram" | "elevenlabs") or the configured one,
+/// refined when AI refinement is on. The text is added to history; with `paste` it is inserted
+/// like a dictation (or copied, with auto-paste off)
+#[tauri::command]
+async fn transcribe_file(app: AppHandle, path: String, provider: Option<String>, paste: Option<bool>) -> Result<String, String> {
+  let cfg = stt_config_with(&app, provider.as_deref()).await?;
+  let raw = jobs::transcribe_path(Path::new(&path), &cfg).await?;
+  info!("Transcribed {} with {} ({} chars)", path, cfg.provider.as_str(), raw.len());
+  let text = if get_behavior(app.clone()).await?.ai_refine {
+    refine_impl(raw.clone(), app.clone(), None, None, None).await.unwrap_or_else(|e| {
+      warn!("Refinement failed for {}, keeping the raw transcript: {}", path, e);
+      raw
+    })
+  } else {
+    raw
+  };
+  let pasted = if paste.unwrap_or(false) { insert_text_impl(&app, &redact_output(&app, text.clone()).await).await? } else { false };
+  if let Err(e) = history::record(&app, &text, None, pasted) {
+    warn!("Failed to record history entry: {}", e);
+  }
+  Ok(text)
+}
+
+#[tauri::command]
+fn stt_relay_stop() { stt::stop() }
+
+#[derive(Debug, Clone, Serialize)]
+struct SelectionAction {
+  id: String,
+  name: String,
+  hotkey: String,
+}
+
+#[tauri::command]
+fn list_selection_actions(app: AppHandle) -> Vec<SelectionAction> {
+  let hotkeys = hotkey::get_selection_hotkeys(&app);
+  prompt::SELECTION_ACTIONS
+    .iter()
+    .map(|(id, name, _)| SelectionAction {
+      id: id.to_string(),
+      name: name.to_string(),
+      hotkey: hotkeys.get(*id).cloned().unwrap_or_default(),
+    })
+    .collect()
+}
+
+#[tauri::command]
+fn set_selection_hotkey(app: AppHandle, action: String, combo: String) -> Result<(), String> {
+  hotkey::set_selection_hotkey(&app, &action, &combo)
+}
+
+/// Copy the current selection, run it through an AI action (summarize, rewrite, fix grammar)
+/// and paste the result over it. Returns the pasted text.
+#[tauri::command]
+async fn run_selection_action(app: AppHandle, action: String) -> Result<String, String> {
+  let (_, name, instructions) = prompt::SELECTION_ACTIONS
+    .iter()
+    .find(|(id, _, _)| *id == action)
+    .ok_or_else(|| format!("Unknown selection action: {}", action))?;
+  info!("Selection action: {}", name);
+
+  // Let the user's hotkey modifiers come up so they don't combine with our Ctrl+C
+  tokio::time::sleep(Duration::from_millis(150)).await;
+  let behavior = get_behavior(app.clone()).await.unwrap_or_default();
+  let selected = paste::copy_selection(&app, &behavior.terminal_apps).await?.ok_or("No text is selected")?;
+  info!("Selected {} chars", selected.len());
+
+  let system_prompt = prompt::build_selection_prompt(instructions);
+  let output = match behavior.ai_provider.as_str() {
+    "megallm" => refine_with_megallm(selected.clone(), app.clone(), None, system_prompt, AiLimits::from_prefs(&behavior)).await?,
+    _ => refine_with_openrouter(selected.clone(), app.clone(), None, system_prompt, AiLimits::from_prefs(&behavior)).await?,
+  };
+  let output = prompt::sanitize_output(&output);
+  if output.trim().is_empty() || prompt::is_ai_refusal(&output) {
+    failures::record(&app, failures::FailureKind::RefusalFallback, format!("selection action {}", action));
+    return Err("AI returned no usable text; selection left unchanged".into());
+  }
+
+  // The selection is still active, so pasting replaces it
+  let pasted = paste::copy_and_paste(&app, &output, clipboard_restore_delay(&behavior), &behavior.terminal_apps).await?;
+  if !pasted {
+    failures::record(&app, failures::FailureKind::PasteFailed, format!("selection action {}", action));
+  }
+  info!("Selection action {} done (pasted={})", action, pasted);
+  Ok(output)
+}
+
 #[tauri::command]
 fn log_to_terminal(message: String) {
-  eprintln!("[FRONTEND] {}", message);
-}
-
-#[tauri::command]
-async fn export_test_keys(app: AppHandle) -> Result<(), String> {
-  let or_key = config::get_openrouter_key(&app).await.unwrap_or_else(|| "NOT_FOUND".into());
-  let dg_key = config::get_deepgram_key(&app).await.unwrap_or_else(|| "NOT_FOUND".into());
-  let mg_key = config::get_megallm_key(&app).await.unwrap_or_else(|| "NOT_FOUND".into());
-  let el_key = config::get_elevenlabs_key(&app).await.unwrap_or_else(|| "NOT_FOUND".into());
-
-  let sep = "=".repeat(60);
-  eprintln!("
-{}", sep);
-  eprintln!("?? API KEYS FOR TESTING:");
-  eprintln!("{}", sep);
-  eprintln!("DEEPGRAM_KEY={}", dg_key);
-  eprintln!("OPENROUTER_KEY={}", or_key);
-  eprintln!("MEGALLM_API_KEY={}", mg_key);
-  eprintln!("ELEVENLABS_API_KEY={}", el_key);
-  eprintln!("{}", sep);
-  eprintln!("
-Run the test with:");
-  eprintln!("node test-apis.mjs {} {}", dg_key, or_key);
-  eprintln!("");
+  info!(target: logging::FRONTEND_TARGET, "{}", message);
+}
+
+/// The last `lines` log lines (default 500), for attaching to bug reports
+#[tauri::command]
+fn get_recent_logs(app: AppHandle, lines: Option<usize>) -> Result<String, String> {
+  logging::recent(&app, lines.unwrap_or(500).min(20_000))
+}
+
+/// Zip logs, redacted settings and system info into the Downloads folder; returns the file path
+#[tauri::command]
+fn export_diagnostics(app: AppHandle) -> Result<String, String> {
+  diagnostics::export(&app).map(|p| p.display().to_string())
+}
+
+/// Which key each provider uses (prefix and length only) and whether a live test accepts it
+#[tauri::command]
+async fn inspect_keys(app: AppHandle) -> Vec<key_inspect::KeyInspection> {
+  key_inspect::inspect_all(&app).await
+}
+
+
+// Tray check items mirrored from behavior prefs
+struct TrayChecks {
+  code_mode: CheckMenuItem<tauri::Wry>,
+  auto_paste: CheckMenuItem<tauri::Wry>,
+  ai_refine: CheckMenuItem<tauri::Wry>,
+  stt_deepgram: CheckMenuItem<tauri::Wry>,
+  stt_elevenlabs: CheckMenuItem<tauri::Wry>,
+}
+
+fn sync_tray_checks(app: &AppHandle, prefs: &BehaviorPrefs) {
+  if let Some(checks) = app.try_state::<TrayChecks>() {
+    let _ = checks.code_mode.set_checked(prefs.code_mode);
+    let _ = checks.auto_paste.set_checked(prefs.auto_paste);
+    let _ = checks.ai_refine.set_checked(prefs.ai_refine);
+    let _ = checks.stt_deepgram.set_checked(prefs.stt_provider == "deepgram");
+    let _ = checks.stt_elevenlabs.set_checked(prefs.stt_provider == "elevenlabs");
+  }
+}
+
+/// Set a behavior pref from a tray check item; the checks are re-synced with the saved prefs
+fn handle_tray_pref(app: &AppHandle, args: serde_json::Value) {
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = set_behavior(app.clone(), args).await {
+      error!("Tray set_behavior FAILED: {}", e);
+      if let Ok(prefs) = get_behavior(app.clone()).await { sync_tray_checks(&app, &prefs); }
+    }
+  });
+}
+
+// The tray's Start/Stop item, relabeled on every dictation state change
+static TRAY_DICTATION: OnceLock<MenuItem<tauri::Wry>> = OnceLock::new();
+
+fn tray_dictation_label(state: DictationState) -> (&'static str, bool) {
+  match state {
+    DictationState::Inactive => ("Start Dictation", true),
+    DictationState::Starting => ("Starting…", false),
+    DictationState::Recording | DictationState::Paused => ("Stop Dictation", true),
+    DictationState::Stopping => ("Processing…", false),
+  }
+}
+
+/// Called with the recording state locked, so the menu is updated off this thread. The task
+/// reads the state when it runs, under a lock of its own: tasks for quick successive changes may
+/// run in any order, but the last one to apply sees the latest state
+fn sync_tray_dictation() {
+  static APPLYING: Mutex<()> = Mutex::new(());
+  let Some(item) = TRAY_DICTATION.get() else { return };
+  tauri::async_runtime::spawn(async move {
+    let _applying = APPLYING.lock().unwrap();
+    let (label, enabled) = tray_dictation_label(RECORDING_STATE.lock().unwrap().state);
+    let _ = item.set_text(label);
+    let _ = item.set_enabled(enabled);
+  });
+}
+
+// Handle to the tray "Recover take" submenu, filled with the latest session's takes
+struct TakesMenu(Submenu<tauri::Wry>);
+
+fn refresh_takes_menu(app: &AppHandle) -> tauri::Result<()> {
+  let Some(menu) = app.try_state::<TakesMenu>() else { return Ok(()) };
+  let submenu = &menu.0;
+  for item in submenu.items()? { submenu.remove(&item)?; }
+  let saved = takes::list();
+  if saved.is_empty() {
+    submenu.append(&MenuItem::with_id(app, "take:none", "No takes yet", false, None::<&str>)?)?;
+  }
+  for (i, take) in saved.iter().enumerate() {
+    submenu.append(&MenuItem::with_id(app, format!("take:{}", i), takes::label(i, take), true, None::<&str>)?)?;
+  }
+  Ok(())
+}
+
+fn handle_takes_menu_event(app: &AppHandle, id: &str) {
+  let Ok(index) = id.parse::<usize>() else {
+    warn!("Tray: unknown take {}", id);
+    return;
+  };
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = use_take(app, index).await { error!("Tray use_take FAILED: {}", e); }
+  });
+}
+
+// Handle to the tray "Configuration" submenu: one check item per configuration profile
+struct ConfigMenu(Submenu<tauri::Wry>);
+
+fn refresh_config_menu(app: &AppHandle) -> tauri::Result<()> {
+  let Some(menu) = app.try_state::<ConfigMenu>() else { return Ok(()) };
+  let submenu = &menu.0;
+  for item in submenu.items()? { submenu.remove(&item)?; }
+  let profiles = config_profiles::list(app);
+  for name in &profiles.names {
+    submenu.append(&CheckMenuItem::with_id(app, format!("config:{}", name), name, true, *name == profiles.active, None::<&str>)?)?;
+  }
+  Ok(())
+}
+
+fn handle_config_menu_event(app: &AppHandle, name: &str) {
+  let app = app.clone();
+  let name = name.to_string();
+  tauri::async_runtime::spawn(async move {
+    if let Err(e) = switch_profile(app.clone(), name).await {
+      error!("Tray switch_profile FAILED: {}", e);
+      // Undo the check the click toggled
+      let _ = refresh_config_menu(&app);
+    }
+  });
+}
+
+// Handle to the tray "Dictate into…" submenu so it can be repopulated with the current window list
+struct TargetMenu(Submenu<tauri::Wry>);
+
+/// Rebuild the "Dictate into…" submenu from the currently open windows
+fn refresh_target_menu(app: &AppHandle) -> tauri::Result<()> {
+  fill_target_menu(app, window_target::list_windows())
+}
 
+/// When the window list was last enumerated for the tray menu (ms since the Unix epoch)
+static TARGET_MENU_REFRESHED: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+/// Hovering the tray refreshes the list at most this often
+const TARGET_MENU_DEBOUNCE_MS: u64 = 5_000;
+
+/// `refresh_target_menu` on a blocking thread, since enumerating windows and their icons is
+/// slow and the tray events arrive on the event loop. With `debounce`, skipped when the list was
+/// refreshed moments ago.
+fn spawn_target_menu_refresh(app: &AppHandle, debounce: bool) {
+  use std::sync::atomic::Ordering;
+  let now = history::now_millis();
+  let last = TARGET_MENU_REFRESHED.load(Ordering::Relaxed);
+  if debounce && now.saturating_sub(last) < TARGET_MENU_DEBOUNCE_MS {
+    return;
+  }
+  // Another event got there first
+  if TARGET_MENU_REFRESHED.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+    return;
+  }
+  let app = app.clone();
+  tauri::async_runtime::spawn_blocking(move || {
+    if let Err(e) = refresh_target_menu(&app) { error!("Failed to refresh target menu: {}", e); }
+  });
+}
+
+fn fill_target_menu(app: &AppHandle, windows: Vec<window_target::WindowInfo>) -> tauri::Result<()> {
+  let Some(menu) = app.try_state::<TargetMenu>() else { return Ok(()) };
+  let submenu = &menu.0;
+  for item in submenu.items()? { submenu.remove(&item)?; }
+
+  let current = window_target::get_target();
+  let focused_label = if current.is_none() { "● Focused window (default)" } else { "Focused window (default)" };
+  submenu.append(&MenuItem::with_id(app, "target:focused", focused_label, true, None::<&str>)?)?;
+  submenu.append(&PredefinedMenuItem::separator(app)?)?;
+
+  for w in windows {
+    let selected = current.as_ref().map(|t| t.id == w.id).unwrap_or(false);
+    let label = if selected { format!("● {}", w.menu_label()) } else { w.menu_label() };
+    let icon = w.icon.clone().map(|(rgba, width, height)| tauri::image::Image::new_owned(rgba, width, height));
+    submenu.append(&IconMenuItem::with_id(app, format!("target:{}", w.id), label, true, icon, None::<&str>)?)?;
+  }
+
+  submenu.append(&PredefinedMenuItem::separator(app)?)?;
+  submenu.append(&MenuItem::with_id(app, "target:refresh", "Refresh window list", true, None::<&str>)?)?;
   Ok(())
 }
 
+fn handle_target_menu_event(app: &AppHandle, id: &str) {
+  match id {
+    "refresh" => {}
+    "focused" => {
+      info!("Tray: paste target cleared, using focused window");
+      window_target::set_target(None);
+    }
+    raw => match raw.parse::<u64>().ok().and_then(window_target::find_listed) {
+      Some(w) => {
+        info!("Tray: paste target set to {}", w.menu_label());
+        window_target::set_target(Some(w));
+      }
+      None => warn!("Tray: unknown paste target {}", raw),
+    },
+  }
+  spawn_target_menu_refresh(app, false);
+}
+
+// Tray icon for each dictation state, built once when the tray is
+struct TrayIcons {
+  app: AppHandle,
+  idle: tauri::image::Image<'static>,
+  starting: tauri::image::Image<'static>,
+  recording: tauri::image::Image<'static>,
+  paused: tauri::image::Image<'static>,
+  spinner: Vec<tauri::image::Image<'static>>,
+}
+
+static TRAY_ICONS: OnceLock<TrayIcons> = OnceLock::new();
+// Whether the tray animation task runs; only changed with the recording state locked
+static TRAY_ANIMATING: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn init_tray_icons(app: &AppHandle) {
+  let Some(base) = app.default_window_icon() else { return };
+  let (width, height) = (base.width(), base.height());
+  let icon = |rgba: Vec<u8>| tauri::image::Image::new_owned(rgba, width, height);
+  let _ = TRAY_ICONS.set(TrayIcons {
+    app: app.clone(),
+    idle: icon(base.rgba().to_vec()),
+    starting: icon(tray_icon::badged(base.rgba(), width, height, tray_icon::Badge::Starting)),
+    recording: icon(tray_icon::badged(base.rgba(), width, height, tray_icon::Badge::Recording)),
+    paused: icon(tray_icon::badged(base.rgba(), width, height, tray_icon::Badge::Paused)),
+    spinner: (0..tray_icon::SPINNER_FRAMES)
+      .map(|f| icon(tray_icon::badged(base.rgba(), width, height, tray_icon::Badge::Processing(f))))
+      .collect(),
+  });
+}
+
+/// Keep the tray icon and tooltip in step with the dictation state: a badge per state, a turning
+/// spinner while processing and the recording time in the tooltip. Called with the recording
+/// state locked when dictation becomes active; the task runs until it's inactive again and then
+/// clears the flag under the same lock, so a dictation starting right then starts a new task.
+fn animate_tray() {
+  let Some(icons) = TRAY_ICONS.get() else { return };
+  if TRAY_ANIMATING.swap(true, std::sync::atomic::Ordering::Relaxed) {
+    return;
+  }
+  tauri::async_runtime::spawn(async move {
+    let Some(tray) = icons.app.tray_by_id("main") else {
+      TRAY_ANIMATING.store(false, std::sync::atomic::Ordering::Relaxed);
+      return;
+    };
+    let name = instance::display_name();
+    let (mut shown, mut tooltip, mut frame) = (None, String::new(), 0);
+    loop {
+      let (state, started, since) = {
+        let s = RECORDING_STATE.lock().unwrap();
+        if s.state == DictationState::Inactive {
+          TRAY_ANIMATING.store(false, std::sync::atomic::Ordering::Relaxed);
+        }
+        (s.state, s.start_time, s.since)
+      };
+      let key = (state, if state == DictationState::Stopping { frame } else { 0 });
+      if shown != Some(key) {
+        let image = match state {
+          DictationState::Inactive => icons.idle.clone(),
+          DictationState::Starting => icons.starting.clone(),
+          DictationState::Recording => icons.recording.clone(),
+          DictationState::Paused => icons.paused.clone(),
+          DictationState::Stopping => icons.spinner[frame].clone(),
+        };
+        let _ = tray.set_icon(Some(image));
+        shown = Some(key);
+      }
+      frame = (frame + 1) % tray_icon::SPINNER_FRAMES;
+      let text = match state {
+        DictationState::Inactive => name.clone(),
+        DictationState::Starting => format!("{} — Starting…", name),
+        DictationState::Recording => {
+          let secs = started.map(|t| t.elapsed().as_secs()).unwrap_or(0);
+          format!("{} — Recording {}", name, tray_icon::elapsed_label(secs))
+        }
+        // Time recorded before the pause
+        DictationState::Paused => {
+          let secs = started.zip(since).map(|(t, p)| p.saturating_duration_since(t).as_secs()).unwrap_or(0);
+          format!("{} — Paused {}", name, tray_icon::elapsed_label(secs))
+        }
+        DictationState::Stopping => format!("{} — Processing…", name),
+      };
+      if text != tooltip {
+        let _ = tray.set_tooltip(Some(&text));
+        tooltip = text;
+      }
+      if state == DictationState::Inactive {
+        break;
+      }
+      tokio::time::sleep(Duration::from_millis(125)).await;
+    }
+  });
+}
 
 fn build_tray(app: &tauri::App) -> tauri::Result<()> {
   let menu = Menu::new(app)?;
   let settings = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
-  let start = MenuItem::with_id(app, "start", "Start Dictation", true, None::<&str>)?;
-  let stop = MenuItem::with_id(app, "stop", "Stop Dictation", true, None::<&str>)?;
+  let (label, enabled) = tray_dictation_label(RECORDING_STATE.lock().unwrap().state);
+  let dictation = MenuItem::with_id(app, "dictation", label, enabled, None::<&str>)?;
+  let meeting = MenuItem::with_id(app, "meeting", "Start Meeting Transcription", true, None::<&str>)?;
+  let history_item = MenuItem::with_id(app, "history", "History", true, None::<&str>)?;
+  let targets = Submenu::with_id(app, "targets", "Dictate into…", true)?;
+  let takes_menu = Submenu::with_id(app, "takes", "Recover take", true)?;
+  let config_menu = Submenu::with_id(app, "config", "Configuration", true)?;
+  let prefs = app
+    .store("prefs.json")
+    .ok()
+    .and_then(|s| s.get("behavior"))
+    .and_then(|v| serde_json::from_value::<BehaviorPrefs>(v).ok())
+    .unwrap_or_default();
+  let auto_paste = CheckMenuItem::with_id(app, "auto_paste", "Auto-paste", true, prefs.auto_paste, None::<&str>)?;
+  let ai_refine = CheckMenuItem::with_id(app, "ai_refine", "AI Refine", true, prefs.ai_refine, None::<&str>)?;
+  let code_mode = CheckMenuItem::with_id(app, "code_mode", "Code Mode", true, prefs.code_mode, None::<&str>)?;
+  let stt_deepgram = CheckMenuItem::with_id(app, "stt:deepgram", "Deepgram", true, prefs.stt_provider == "deepgram", None::<&str>)?;
+  let stt_elevenlabs = CheckMenuItem::with_id(app, "stt:elevenlabs", "ElevenLabs", true, prefs.stt_provider == "elevenlabs", None::<&str>)?;
+  let stt_menu = Submenu::with_id_and_items(app, "stt", "Speech-to-Text", true, &[&stt_deepgram, &stt_elevenlabs])?;
   let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+  if let Some(profile) = instance::profile() {
+    let label = MenuItem::with_id(app, "profile", format!("Profile: {}", profile), false, None::<&str>)?;
+    let _ = menu.append(&label)?;
+    let _ = menu.append(&PredefinedMenuItem::separator(app)?)?;
+  }
   let _ = menu.append(&settings)?;
-  let _ = menu.append(&start)?;
-  let _ = menu.append(&stop)?;
+  let _ = menu.append(&dictation)?;
+  let _ = menu.append(&meeting)?;
+  let _ = menu.append(&history_item)?;
+  let _ = menu.append(&targets)?;
+  let _ = menu.append(&takes_menu)?;
+  let _ = menu.append(&PredefinedMenuItem::separator(app)?)?;
+  let _ = menu.append(&auto_paste)?;
+  let _ = menu.append(&ai_refine)?;
+  let _ = menu.append(&code_mode)?;
+  let _ = menu.append(&stt_menu)?;
+  let _ = menu.append(&config_menu)?;
+  let _ = menu.append(&PredefinedMenuItem::separator(app)?)?;
   let _ = menu.append(&quit)?;
+  app.manage(TargetMenu(targets));
+  app.manage(TakesMenu(takes_menu));
+  refresh_takes_menu(app.handle())?;
+  app.manage(ConfigMenu(config_menu));
+  refresh_config_menu(app.handle())?;
+  app.manage(TrayChecks { code_mode, auto_paste, ai_refine, stt_deepgram, stt_elevenlabs });
+  let _ = TRAY_DICTATION.set(dictation);
+  // Window enumeration (with icons) is slow; the list is filled in after startup
+  fill_target_menu(app.handle(), Vec::new())?;
     let _tray = TrayIconBuilder::with_id("main")
-      .tooltip("Dictation HUD")
+      .tooltip(instance::display_name())
       .icon(app.default_window_icon().unwrap().clone())
       .menu(&menu)
     .on_menu_event(|app, event| {
-      eprintln!("🎯🎯🎯 TRAY MENU EVENT: {}", event.id.as_ref());
+      debug!("TRAY MENU EVENT: {}", event.id.as_ref());
       match event.id.as_ref() {
         "settings" => {
-          eprintln!("📝 Tray: Opening settings window...");
+          info!("Tray: Opening settings window...");
           if let Some(w) = app.get_webview_window("settings") { let _ = w.show(); let _ = w.set_focus(); }
         },
-        "start" => {
-          eprintln!("🚀🚀🚀 Tray: Start Dictation clicked! 🚀🚀🚀");
+        // One item that starts or stops depending on the state; disabled while starting or processing
+        "dictation" => {
+          let state = RECORDING_STATE.lock().unwrap().state;
           let app_clone = app.clone();
-          tauri::async_runtime::spawn(async move {
-            eprintln!("⚡ Spawning async task for start_dictation...");
-            match start_dictation(app_clone).await {
-              Ok(_) => eprintln!("✅ Tray start_dictation completed successfully"),
-              Err(e) => eprintln!("❌ Tray start_dictation FAILED: {}", e),
+          match state {
+            DictationState::Inactive => {
+              debug!("Tray: Start Dictation clicked!");
+              tauri::async_runtime::spawn(async move {
+                info!("Spawning async task for start_dictation...");
+                match start_dictation(app_clone).await {
+                  Ok(_) => info!("Tray start_dictation completed successfully"),
+                  Err(e) => error!("Tray start_dictation FAILED: {}", e),
+                }
+              });
             }
-          });
+            DictationState::Recording | DictationState::Paused => {
+              info!("Tray: Stop Dictation clicked!");
+              tauri::async_runtime::spawn(async move {
+                info!("Spawning async task for stop_dictation...");
+                match stop_dictation(app_clone).await {
+                  Ok(_) => info!("Tray stop_dictation completed successfully"),
+                  Err(e) => error!("Tray stop_dictation FAILED: {}", e),
+                }
+              });
+            }
+            other => info!("Tray: dictation item ignored while {}", other.name()),
+          }
         },
-        "stop" => {
-          eprintln!("⏹️ Tray: Stop Dictation clicked!");
+        "meeting" => {
+          info!("Tray: Opening meeting transcription window...");
+          if let Err(e) = meeting::open_window(app) {
+            error!("Failed to open meeting window: {}", e);
+          }
+        },
+        "history" => {
+          info!("Tray: Opening history window...");
+          if let Err(e) = history::open_window(app) {
+            error!("Failed to open history window: {}", e);
+          }
+        },
+        "quit" => {
+          info!("Tray: Quit clicked, exiting app...");
+          logging::flush();
+          app.exit(0);
+        },
+        "code_mode" => {
           let app_clone = app.clone();
           tauri::async_runtime::spawn(async move {
-            eprintln!("⚡ Spawning async task for stop_dictation...");
-            match stop_dictation(app_clone).await {
-              Ok(_) => eprintln!("✅ Tray stop_dictation completed successfully"),
-              Err(e) => eprintln!("❌ Tray stop_dictation FAILED: {}", e),
-            }
+            if let Err(e) = toggle_code_mode(app_clone).await { error!("Tray toggle_code_mode FAILED: {}", e); }
           });
         },
-        "quit" => {
-          eprintln!("👋 Tray: Quit clicked, exiting app...");
-          app.exit(0);
+        "auto_paste" => {
+          let on = app.state::<TrayChecks>().auto_paste.is_checked().unwrap_or(false);
+          handle_tray_pref(app, serde_json::json!({ "auto_paste": on }));
+        },
+        "ai_refine" => {
+          let on = app.state::<TrayChecks>().ai_refine.is_checked().unwrap_or(false);
+          handle_tray_pref(app, serde_json::json!({ "ai_refine": on }));
         },
+        id if id.starts_with("stt:") => handle_tray_pref(app, serde_json::json!({ "stt_provider": &id["stt:".len()..] })),
+        id if id.starts_with("target:") => handle_target_menu_event(app, &id["target:".len()..]),
+        id if id.starts_with("take:") => handle_takes_menu_event(app, &id["take:".len()..]),
+        id if id.starts_with("config:") => handle_config_menu_event(app, &id["config:".len()..]),
         _ => {
-          eprintln!("⚠️ Unknown tray menu event: {}", event.id.as_ref());
+          warn!("Unknown tray menu event: {}", event.id.as_ref());
         }
       }
     })
-    .on_tray_icon_event(|_app, _ev: TrayIconEvent| {})
+    .on_tray_icon_event(|tray, ev: TrayIconEvent| {
+      // Keep the window list fresh right before the user is likely to open the menu
+      // (not emitted on Linux, where the list is filled at startup and by "Refresh window list")
+      if let TrayIconEvent::Enter { .. } = ev {
+        spawn_target_menu_refresh(tray.app_handle(), true);
+      }
+    })
     .build(app)?;
+  init_tray_icons(app.handle());
   Ok(())
 }
 
-pub fn run(context: tauri::Context<tauri::Wry>) -> tauri::Result<()> {
+pub fn run(mut context: tauri::Context<tauri::Wry>) -> tauri::Result<()> {
+  startup::begin();
+  logging::init();
+  // A named profile gets its own identifier: separate stores, webview data and single-instance lock
+  instance::init_from_args();
+  let identifier = instance::scoped_identifier(&context.config().identifier);
+  context.config_mut().identifier = identifier;
+  let autostart_args = instance::profile().map(|p| vec!["--profile", p]);
+  let plugins_started = Instant::now();
+
   tauri::Builder::default()
-    .plugin(tauri_plugin_single_instance::init(|app, _args, _cwd| {
+    .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+      // A launch for a dictation-hud:// link reaches the deep-link handler instead
+      if args.iter().any(|a| deep_link::is_link(a)) { return; }
       if let Some(w) = app.get_webview_window("settings") { let _ = w.show(); let _ = w.set_focus(); }
     }))
+    .plugin(tauri_plugin_deep_link::init())
     .plugin(tauri_plugin_store::Builder::default().build())
-    .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, None))
+    .plugin(tauri_plugin_autostart::init(tauri_plugin_autostart::MacosLauncher::LaunchAgent, autostart_args))
     .plugin(tauri_plugin_global_shortcut::Builder::new().build())
     .plugin(tauri_plugin_clipboard_manager::init())
     .plugin(tauri_plugin_updater::Builder::new().build())
     .plugin(tauri_plugin_process::init())
-    .setup(|app| {
+    .plugin(tauri_plugin_notification::init())
+    .setup(move |app| {
+      // Plugins are initialized by the time setup runs
+      startup::record("plugin_init", plugins_started.elapsed());
       // ensure windows exist & hidden by default
       if let Some(s) = app.get_webview_window("settings") { let _ = s.hide(); }
-      if let Some(h) = app.get_webview_window("hud") { let _ = h.hide(); let _ = h.set_decorations(false); let _ = h.set_always_on_top(true); }
-      build_tray(app)?;
-      let _ = hotkey::ensure_default_hotkey(app.handle().clone());
+      if let Some(h) = app.get_webview_window("hud") { hud_window::apply_flags(&h); }
+      hud_window::touch();
+      feedback::init(app.handle().clone());
+      plugin_host::init(app.handle().clone());
+      // The first access loads prefs.json from disk
+      if let Err(e) = startup::phase("store_load", || app.store("prefs.json")) {
+        warn!("Failed to load prefs store: {}", e);
+      }
+      logging::attach(app.handle());
+      apply_stored_prefs(app.handle());
+      let _ = startup::phase("hotkey_prefs", || hotkey::ensure_default_hotkey(app.handle().clone()));
+      startup::phase("tray", || build_tray(app))?;
+      // Installers register the scheme on macOS and Windows; Linux and portable builds register it
+      // at runtime. Named profiles leave it to the default instance
+      #[cfg(any(target_os = "linux", target_os = "windows"))]
+      if instance::profile().is_none() {
+        if let Err(e) = app.deep_link().register_all() { warn!("Failed to register dictation-hud:// links: {}", e); }
+      }
+      let handle = app.handle().clone();
+      app.deep_link().on_open_url(move |event| {
+        for url in event.urls() { deep_link::handle(&handle, url.as_str()); }
+      });
+      // Launched by a link
+      if let Ok(Some(urls)) = app.deep_link().get_current() {
+        for url in urls { deep_link::handle(app.handle(), url.as_str()); }
+      }
+      startup::mark_tray_ready();
+      spawn_deferred_startup(app.handle().clone());
       Ok(())
     })
     .invoke_handler(tauri::generate_handler![
-      start_dictation, stop_dictation, is_dictation_active, set_recording_active, trigger_stop_dictation,
-      refine_text,
+      start_dictation, stop_dictation, hud_ready, prewarm_hud, get_startup_metrics, report_hotkey_registered, is_dictation_active, set_recording_active, trigger_stop_dictation,
+      retake_dictation, pause_dictation, resume_dictation, toggle_pause_dictation, save_take, list_takes, use_take,
+      refine_text, transcript_words, take_last_translation, demo_transcript, list_prompt_profiles, set_active_prompt_profile, set_custom_prompt, set_language_prompt, get_language_prompts, test_pipeline, get_script_status, list_plugins, preview_cue, start_hud_move, finish_hud_move, list_monitors, get_refusal_patterns, set_refusal_patterns, get_custom_pack, set_custom_pack, export_pack, import_pack, export_settings, import_settings, switch_profile, list_config_profiles, delete_config_profile, get_proxy, set_proxy, get_provider_usage, get_cost_summary, get_network_status, list_dictionary, add_dictionary_word, remove_dictionary_word, stt_keywords, list_audio_devices, get_audio_device, set_audio_device, report_audio_devices, audio_device_changed, get_noise_mode, set_device_noise_mode, list_packs, enable_pack, disable_pack, report_correction, get_correction_suggestions, accept_correction_suggestion, dismiss_correction_suggestion,
       save_keys_secure, get_keys_secure,
-      set_hotkey, get_hotkey,
+      set_hotkey, get_hotkey, set_code_mode_hotkey, get_code_mode_hotkey, toggle_code_mode, set_retake_hotkey, get_retake_hotkey, set_pause_hotkey, get_pause_hotkey,
+      list_selection_actions, set_selection_hotkey, run_selection_action,
+      capture_hotkey, validate_hotkey,
       set_autostart, set_behavior, get_behavior,
       probe_text_accepting,
       set_model, get_model, set_megallm_model, get_megallm_model, set_language, get_language,
       test_openrouter, test_deepgram, test_megallm, test_elevenlabs, list_megallm_models, create_elevenlabs_token,
-      insert_text, runtime_keys, log_to_terminal, export_test_keys, get_autostart
+      insert_text, get_focus_kind, get_api_schema, get_paste_confirmation, confirm_paste, answer_paste_confirmation, open_scratchpad, get_scratchpad_text, runtime_keys, log_to_terminal, get_recent_logs, export_diagnostics, inspect_keys, spend_ledger_path, watch_folder_status, get_maintenance_status, run_maintenance, list_jobs, cancel_job, is_folder, transcribe_folder, replay_macro, get_autostart,
+      list_windows, set_paste_target, get_paste_target,
+      get_active_app, list_app_profiles, set_app_profile, set_app_profiles_bulk, get_app_usage,
+      get_failure_stats, reset_failure_stats, report_failure, get_usage_stats, mark_first_partial, set_stt_options, get_stt_options,
+      stt_relay_start, stt_relay_audio, stt_relay_finalize, stt_relay_stop, record_audio_start, record_audio, last_audio_info, save_last_audio, retranscribe_last_audio, transcribe_file, list_history, delete_history, open_history_window, export_transcript, start_meeting, stop_meeting, get_meeting_status, open_meeting_window, get_control_api_token, regenerate_control_api_token, set_journal_target, get_journal_target, set_journal_template, get_journal_template, test_journal_template
     ])
     .run(context)
 }
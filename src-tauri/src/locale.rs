@@ -0,0 +1,87 @@
+// Writing conventions of a language that refinement should follow: which quotation marks to use
+// and how numbers, amounts and percentages are written. Used when the dictation language is
+// detected rather than set, so a German dictation gets „…“ and 3,5 while an English one keeps
+// "…" and 3.5, and by the number formatter (numbers.rs).
+
+/// Conventions of one language
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocaleRules {
+    /// Opening and closing quotation marks
+    pub quotes: (&'static str, &'static str),
+    /// Decimal separator; the other of '.' and ',' (or a space) groups thousands
+    pub decimal: char,
+    pub thousands: char,
+    /// Currency symbol after the amount ("23,50 €") rather than before ("$23.50")
+    pub currency_after: bool,
+    /// A space before "%"
+    pub percent_space: bool,
+}
+
+const RULES: &[(&str, LocaleRules)] = &[
+    ("en", LocaleRules { quotes: ("“", "”"), decimal: '.', thousands: ',', currency_after: false, percent_space: false }),
+    ("de", LocaleRules { quotes: ("„", "“"), decimal: ',', thousands: '.', currency_after: true, percent_space: true }),
+    ("fr", LocaleRules { quotes: ("« ", " »"), decimal: ',', thousands: ' ', currency_after: true, percent_space: true }),
+    ("es", LocaleRules { quotes: ("«", "»"), decimal: ',', thousands: '.', currency_after: true, percent_space: true }),
+    ("it", LocaleRules { quotes: ("«", "»"), decimal: ',', thousands: '.', currency_after: true, percent_space: false }),
+    ("pt", LocaleRules { quotes: ("“", "”"), decimal: ',', thousands: '.', currency_after: true, percent_space: false }),
+    ("ru", LocaleRules { quotes: ("«", "»"), decimal: ',', thousands: ' ', currency_after: true, percent_space: true }),
+    ("ja", LocaleRules { quotes: ("「", "」"), decimal: '.', thousands: ',', currency_after: false, percent_space: false }),
+    ("zh", LocaleRules { quotes: ("“", "”"), decimal: '.', thousands: ',', currency_after: false, percent_space: false }),
+    ("bn", LocaleRules { quotes: ("“", "”"), decimal: '.', thousands: ',', currency_after: false, percent_space: false }),
+    ("hi", LocaleRules { quotes: ("“", "”"), decimal: '.', thousands: ',', currency_after: false, percent_space: false }),
+];
+
+/// Rules for a language code ("de", "de-AT"); None for languages without an entry
+pub fn rules(code: &str) -> Option<LocaleRules> {
+    let primary = crate::language::primary_subtag(code);
+    RULES.iter().find(|(c, _)| *c == primary).map(|(_, r)| *r)
+}
+
+fn example(rules: &LocaleRules) -> String {
+    let thousands = if rules.thousands == ' ' { "\u{202F}".to_string() } else { rules.thousands.to_string() };
+    format!("1{}250{}5", thousands, rules.decimal)
+}
+
+/// The base prompt is written for English, so English needs no LOCALE section
+const BASE_LANGUAGE: &str = "en";
+
+/// Prompt section asking the model to follow the conventions of `code`; None for English and for
+/// `main`, the session's main language, whose conventions the model already follows
+pub fn prompt_section(code: &str, main: &str) -> Option<String> {
+    let primary = crate::language::primary_subtag(code);
+    if primary == BASE_LANGUAGE || primary == crate::language::primary_subtag(main) {
+        return None;
+    }
+    let rules = rules(code)?;
+    Some(format!(
+        "\n\n# LOCALE\n\nUse {}…{} for quotations, and write numbers the local way, e.g. {} (\"{}\" is the decimal separator). Leave code, URLs, version numbers and times as spoken.",
+        rules.quotes.0,
+        rules.quotes.1,
+        example(&rules),
+        rules.decimal
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        assert_eq!(rules("de-AT").map(|r| r.quotes), Some(("„", "“")));
+        assert_eq!(rules("en").map(|r| r.decimal), Some('.'));
+        assert_eq!(rules("xx"), None);
+    }
+
+    #[test]
+    fn test_prompt_section() {
+        let de = prompt_section("de", "en").unwrap();
+        assert!(de.contains("„…“"));
+        assert!(de.contains("1.250,5"));
+        assert!(prompt_section("fr", "en").unwrap().contains("1\u{202F}250,5"));
+        assert_eq!(prompt_section("xx", "en"), None);
+        // Nothing to say for English or the main language
+        assert_eq!(prompt_section("en-GB", "de"), None);
+        assert_eq!(prompt_section("de-AT", "de"), None);
+    }
+}
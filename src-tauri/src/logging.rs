@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::level_filters::LevelFilter;
+use tracing::warn;
+use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::writer::OptionalWriter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, Registry};
+
+// Logs go to stderr and to daily files in <app data>/logs (a week is kept), so users can attach
+// them to bug reports. Our own targets log at the `log_level` pref; dependencies only report
+// warnings. Events from before the app data dir is known (early startup) only reach stderr.
+
+const FILE_PREFIX: &str = "dictation-hud";
+const FILE_SUFFIX: &str = "log";
+const KEEP_FILES: usize = 7;
+pub const DEFAULT_LEVEL: &str = "info";
+/// Target of messages forwarded from the webviews
+pub const FRONTEND_TARGET: &str = "frontend";
+
+static FILTER: OnceLock<reload::Handle<Targets, Registry>> = OnceLock::new();
+static FILE_WRITER: OnceLock<NonBlocking> = OnceLock::new();
+/// Flushes buffered lines when dropped
+static GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+struct FileSink;
+
+impl<'a> MakeWriter<'a> for FileSink {
+  type Writer = OptionalWriter<NonBlocking>;
+
+  fn make_writer(&'a self) -> Self::Writer {
+    FILE_WRITER.get().cloned().into()
+  }
+}
+
+fn targets(level: LevelFilter) -> Targets {
+  Targets::new()
+    .with_default(LevelFilter::WARN)
+    .with_target(env!("CARGO_CRATE_NAME"), level)
+    .with_target(FRONTEND_TARGET, level)
+}
+
+/// "error" | "warn" | "info" | "debug" | "trace"
+pub fn parse_level(level: &str) -> Option<LevelFilter> {
+  match level.trim().to_lowercase().as_str() {
+    "error" => Some(LevelFilter::ERROR),
+    "warn" => Some(LevelFilter::WARN),
+    "info" => Some(LevelFilter::INFO),
+    "debug" => Some(LevelFilter::DEBUG),
+    "trace" => Some(LevelFilter::TRACE),
+    _ => None,
+  }
+}
+
+/// Install the global subscriber; call once, before anything logs
+pub fn init() {
+  let (filter, handle) = reload::Layer::new(targets(LevelFilter::INFO));
+  let result = tracing_subscriber::registry()
+    .with(filter)
+    .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+    .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(FileSink))
+    .try_init();
+  if result.is_ok() {
+    let _ = FILTER.set(handle);
+  }
+}
+
+pub fn set_level(level: &str) {
+  let Some(filter) = parse_level(level) else { return };
+  if let Some(handle) = FILTER.get() {
+    if let Err(e) = handle.reload(targets(filter)) {
+      warn!("Could not change log level: {}", e);
+    }
+  }
+}
+
+pub fn log_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join("logs"))
+}
+
+/// Start writing log files and apply the stored level
+pub fn attach(app: &AppHandle) {
+  if let Some(level) = stored_level(app) {
+    set_level(&level);
+  }
+  let appender = log_dir(app).and_then(|dir| {
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    RollingFileAppender::builder()
+      .rotation(Rotation::DAILY)
+      .filename_prefix(FILE_PREFIX)
+      .filename_suffix(FILE_SUFFIX)
+      .max_log_files(KEEP_FILES)
+      .build(dir)
+      .map_err(|e| e.to_string())
+  });
+  match appender {
+    Ok(appender) => {
+      let (writer, guard) = tracing_appender::non_blocking(appender);
+      if FILE_WRITER.set(writer).is_ok() {
+        *GUARD.lock().unwrap() = Some(guard);
+      }
+    }
+    Err(e) => warn!("Log files disabled: {}", e),
+  }
+}
+
+fn stored_level(app: &AppHandle) -> Option<String> {
+  let store = app.store("prefs.json").ok()?;
+  let behavior = store.get("behavior")?;
+  behavior.get("log_level")?.as_str().map(|s| s.to_string())
+}
+
+/// Write out buffered lines, e.g. before exiting
+pub fn flush() {
+  GUARD.lock().unwrap().take();
+}
+
+fn log_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+  let mut files: Vec<PathBuf> = fs::read_dir(dir)
+    .map_err(|e| format!("No logs in {}: {}", dir.display(), e))?
+    .filter_map(|entry| entry.ok().map(|e| e.path()))
+    .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(FILE_PREFIX)))
+    .collect();
+  // Daily files are named <prefix>.<yyyy-mm-dd>.log, so name order is age order
+  files.sort();
+  Ok(files)
+}
+
+/// Delete all but the newest `KEEP_FILES` log files; returns how many were removed. The appender
+/// only prunes when it rolls over, so files pile up when the app runs for a short time each day.
+pub fn prune(app: &AppHandle) -> Result<usize, String> {
+  let files = log_files(&log_dir(app)?)?;
+  let excess = files.len().saturating_sub(KEEP_FILES);
+  let mut removed = 0;
+  for file in &files[..excess] {
+    match fs::remove_file(file) {
+      Ok(()) => removed += 1,
+      Err(e) => warn!("Could not remove {}: {}", file.display(), e),
+    }
+  }
+  Ok(removed)
+}
+
+/// The last `lines` lines across the newest log files
+pub fn recent(app: &AppHandle, lines: usize) -> Result<String, String> {
+  let files = log_files(&log_dir(app)?)?;
+  let mut collected: Vec<String> = Vec::new();
+  for file in files.iter().rev() {
+    let Ok(bytes) = fs::read(file) else { continue };
+    let mut chunk: Vec<String> = String::from_utf8_lossy(&bytes).lines().map(|l| l.to_string()).collect();
+    chunk.append(&mut collected);
+    collected = chunk;
+    if collected.len() >= lines {
+      break;
+    }
+  }
+  let start = collected.len().saturating_sub(lines);
+  Ok(collected[start..].join("\n"))
+}
@@ -0,0 +1,256 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::key_inspect::{self, KeyInspection, KeyStatus};
+use crate::{history, logging, DictationState, RECORDING_STATE};
+
+// Housekeeping that runs once a day in the background: history older than the retention setting
+// is purged, surplus log files are removed, old files in the app's own cache folder are trimmed
+// (the rest of the cache dir belongs to the webview and is left alone), the JSON data
+// files are checked for damage and, when enabled, provider keys are pinged. It waits while a
+// dictation is running. The last report is kept so `get_maintenance_status` can show it.
+
+const MAINTENANCE_STORE: &str = "maintenance.json";
+const K_LAST_RUN: &str = "last_run";
+const K_LAST_REPORT: &str = "last_report";
+pub const INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+/// Wait after launch before the first run, so it doesn't slow down startup
+const FIRST_RUN_DELAY: Duration = Duration::from_secs(10 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Cache files untouched for longer than this are removed
+const CACHE_MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+/// The app's folder in the cache dir; WebView2 and WebKitGTK keep their caches next to it
+const OWN_CACHE_DIR: &str = "dictation-hud";
+/// Data files the app keeps; the app has no local model files, so these are what gets checked
+const DATA_FILES: &[&str] = &["prefs.json", "history.json", "stats.json", "jobs.json", "packs.json", "corrections.json", MAINTENANCE_STORE];
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct MaintenanceReport {
+  /// Unix timestamp (seconds)
+  pub started_at: u64,
+  pub duration_ms: u64,
+  /// History entries older than the retention setting
+  pub history_purged: usize,
+  pub logs_removed: usize,
+  pub cache_files_removed: usize,
+  pub cache_bytes_freed: u64,
+  /// Data files that exist but no longer parse; they are left in place for inspection
+  pub damaged_files: Vec<String>,
+  /// Key checks, when the provider health ping is on
+  #[serde(default)]
+  pub providers: Option<Vec<KeyInspection>>,
+  /// Steps that failed
+  pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MaintenanceStatus {
+  pub interval_hours: u64,
+  /// Unix timestamps (seconds)
+  pub last_run: Option<u64>,
+  /// None before the first run, which happens shortly after launch
+  pub next_run: Option<u64>,
+  pub running: bool,
+  pub last_report: Option<MaintenanceReport>,
+}
+
+fn last_run(app: &AppHandle) -> Option<u64> {
+  app.store(MAINTENANCE_STORE).ok()?.get(K_LAST_RUN)?.as_u64()
+}
+
+pub fn status(app: &AppHandle) -> MaintenanceStatus {
+  let last_run = last_run(app);
+  let last_report = app
+    .store(MAINTENANCE_STORE)
+    .ok()
+    .and_then(|s| s.get(K_LAST_REPORT))
+    .and_then(|v| serde_json::from_value(v).ok());
+  MaintenanceStatus {
+    interval_hours: INTERVAL.as_secs() / 3600,
+    last_run,
+    next_run: last_run.map(|t| t + INTERVAL.as_secs()),
+    running: RUNNING.load(Ordering::Relaxed),
+    last_report,
+  }
+}
+
+/// Where the app keeps cache files of its own; the only part of the cache dir that is trimmed
+pub fn cache_dir(app: &AppHandle) -> Result<std::path::PathBuf, String> {
+  Ok(app.path().app_cache_dir().map_err(|e| e.to_string())?.join(OWN_CACHE_DIR))
+}
+
+/// The oldest history timestamp kept with `retention_days` (0 keeps everything)
+fn retention_cutoff(now: u64, retention_days: u32) -> Option<u64> {
+  (retention_days > 0).then(|| now.saturating_sub(retention_days as u64 * 24 * 60 * 60))
+}
+
+/// Remove files under `dir` not modified for `max_age`; returns (files, bytes)
+fn trim_dir(dir: &Path, max_age: Duration, now: SystemTime) -> (usize, u64) {
+  let Ok(entries) = std::fs::read_dir(dir) else { return (0, 0) };
+  let (mut files, mut bytes) = (0, 0);
+  for entry in entries.flatten() {
+    let path = entry.path();
+    let Ok(meta) = entry.metadata() else { continue };
+    if meta.is_dir() {
+      let (f, b) = trim_dir(&path, max_age, now);
+      files += f;
+      bytes += b;
+      continue;
+    }
+    let old = meta.modified().ok().and_then(|m| now.duration_since(m).ok()).is_some_and(|age| age > max_age);
+    if old && std::fs::remove_file(&path).is_ok() {
+      files += 1;
+      bytes += meta.len();
+    }
+  }
+  (files, bytes)
+}
+
+fn damaged_files(dir: &Path) -> Vec<String> {
+  DATA_FILES
+    .iter()
+    .filter(|name| match std::fs::read(dir.join(name)) {
+      Ok(bytes) => serde_json::from_slice::<serde_json::Value>(&bytes).is_err(),
+      Err(_) => false,
+    })
+    .map(|name| name.to_string())
+    .collect()
+}
+
+/// Run every step now and store the report; returns None if a run is already in progress
+pub async fn run(app: &AppHandle) -> Option<MaintenanceReport> {
+  if RUNNING.swap(true, Ordering::SeqCst) {
+    return None;
+  }
+  let started = Instant::now();
+  let prefs = crate::get_behavior(app.clone()).await.unwrap_or_default();
+  let mut report = MaintenanceReport { started_at: history::now_secs(), ..Default::default() };
+
+  if let Some(cutoff) = retention_cutoff(report.started_at, prefs.history_retention_days) {
+    match history::purge_before(app, cutoff) {
+      Ok(n) => report.history_purged = n,
+      Err(e) => report.errors.push(format!("History purge: {}", e)),
+    }
+  }
+  match logging::prune(app) {
+    Ok(n) => report.logs_removed = n,
+    Err(e) => report.errors.push(format!("Log rotation: {}", e)),
+  }
+  match cache_dir(app) {
+    Ok(dir) => (report.cache_files_removed, report.cache_bytes_freed) = trim_dir(&dir, CACHE_MAX_AGE, SystemTime::now()),
+    Err(e) => report.errors.push(format!("Cache trimming: {}", e)),
+  }
+  match app.path().app_data_dir() {
+    Ok(dir) => report.damaged_files = damaged_files(&dir),
+    Err(e) => report.errors.push(format!("Integrity check: {}", e)),
+  }
+  if prefs.maintenance_health_ping {
+    let providers = key_inspect::inspect_all(app).await;
+    for p in providers.iter().filter(|p| p.status == KeyStatus::Invalid) {
+      report.errors.push(format!("{} key check failed: {}", p.provider, p.detail.as_deref().unwrap_or("unknown error")));
+    }
+    report.providers = Some(providers);
+  }
+  report.duration_ms = started.elapsed().as_millis() as u64;
+
+  info!(
+    "Maintenance: {} history entries purged, {} logs removed, {} cache files ({} bytes) removed, damaged files {:?}, {} errors",
+    report.history_purged,
+    report.logs_removed,
+    report.cache_files_removed,
+    report.cache_bytes_freed,
+    report.damaged_files,
+    report.errors.len()
+  );
+  match app.store(MAINTENANCE_STORE) {
+    Ok(store) => {
+      store.set(K_LAST_RUN, report.started_at);
+      store.set(K_LAST_REPORT, serde_json::to_value(&report).unwrap_or_default());
+      if let Err(e) = store.save() {
+        warn!("Could not save maintenance status: {}", e);
+      }
+    }
+    Err(e) => warn!("Could not save maintenance status: {}", e),
+  }
+  RUNNING.store(false, Ordering::SeqCst);
+  Some(report)
+}
+
+/// Run maintenance once a day for the lifetime of the app
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(FIRST_RUN_DELAY).await;
+    loop {
+      let due = last_run(&app).map(|t| history::now_secs() >= t + INTERVAL.as_secs()).unwrap_or(true);
+      let idle = RECORDING_STATE.lock().unwrap().state == DictationState::Inactive;
+      if due && idle {
+        run(&app).await;
+      }
+      tokio::time::sleep(POLL_INTERVAL).await;
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::fs;
+
+  const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+  fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("dictation-hud-maintenance-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  fn touch(path: &Path, contents: &str, modified: SystemTime) {
+    fs::write(path, contents).unwrap();
+    fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+  }
+
+  #[test]
+  fn test_trim_dir_removes_only_old_files() {
+    let dir = temp_dir("trim");
+    let now = SystemTime::now();
+    fs::create_dir(dir.join("nested")).unwrap();
+    touch(&dir.join("old.bin"), "12345", now - 40 * DAY);
+    touch(&dir.join("nested").join("old.bin"), "123", now - 31 * DAY);
+    touch(&dir.join("fresh.bin"), "1", now - DAY);
+    assert_eq!(trim_dir(&dir, CACHE_MAX_AGE, now), (2, 8));
+    assert!(dir.join("fresh.bin").exists());
+    assert!(!dir.join("old.bin").exists());
+    assert!(dir.join("nested").is_dir());
+    // Nothing left to trim, and a missing folder is not an error
+    assert_eq!(trim_dir(&dir, CACHE_MAX_AGE, now), (0, 0));
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(trim_dir(&dir, CACHE_MAX_AGE, now), (0, 0));
+  }
+
+  #[test]
+  fn test_damaged_files() {
+    let dir = temp_dir("damaged");
+    fs::write(dir.join("prefs.json"), "{\"behavior\": {}}").unwrap();
+    fs::write(dir.join("history.json"), "{\"entries\": [").unwrap();
+    fs::write(dir.join("notes.json"), "not ours").unwrap();
+    assert_eq!(damaged_files(&dir), vec!["history.json"]);
+    fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_retention_cutoff() {
+    let now = 1_700_000_000;
+    assert_eq!(retention_cutoff(now, 0), None);
+    assert_eq!(retention_cutoff(now, 30), Some(now - 30 * 24 * 60 * 60));
+    assert_eq!(retention_cutoff(1_000, 1), Some(0));
+  }
+}
@@ -0,0 +1,148 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder};
+use tracing::{info, warn};
+
+use crate::history;
+use crate::transcript::Segment;
+
+// Meeting transcription: a long session in its own window, with speakers told apart, no silence
+// stop and no paste. Final segments become speaker-labeled paragraphs, shown live in the meeting
+// window and rewritten to a Markdown file as they arrive so a crash keeps what was said.
+
+const LABEL: &str = "meeting";
+pub const EVENT_NAME: &str = "meeting-transcript";
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Paragraph {
+  /// Speaker number from diarization (0 is the first voice heard); None when unknown
+  pub speaker: Option<u32>,
+  pub text: String,
+}
+
+/// A paragraph that was added or grew
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct MeetingUpdate {
+  pub index: usize,
+  pub paragraph: Paragraph,
+}
+
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct MeetingStatus {
+  pub active: bool,
+  /// Transcript file of the current or last meeting
+  pub file: Option<PathBuf>,
+  pub started_at: Option<u64>,
+  pub paragraphs: Vec<Paragraph>,
+}
+
+static MEETING: Mutex<Option<MeetingStatus>> = Mutex::new(None);
+
+pub fn is_active() -> bool {
+  MEETING.lock().unwrap().as_ref().is_some_and(|m| m.active)
+}
+
+pub fn status() -> MeetingStatus {
+  MEETING.lock().unwrap().clone().unwrap_or_default()
+}
+
+/// Begin a meeting transcript in Documents/Dictation HUD; returns the file path
+pub fn start(app: &AppHandle) -> Result<PathBuf, String> {
+  if crate::RECORDING_STATE.lock().unwrap().state != crate::DictationState::Inactive {
+    return Err("Finish the current dictation first".into());
+  }
+  let mut meeting = MEETING.lock().unwrap();
+  if let Some(m) = meeting.as_ref().filter(|m| m.active) {
+    return m.file.clone().ok_or_else(|| "Meeting already running".into());
+  }
+  let dir = app
+    .path()
+    .document_dir()
+    .or_else(|_| app.path().home_dir())
+    .map_err(|e| e.to_string())?
+    .join("Dictation HUD");
+  std::fs::create_dir_all(&dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+  let started_at = history::now_secs();
+  let file = dir.join(format!("meeting-{}.md", started_at));
+  std::fs::write(&file, render(&[])).map_err(|e| format!("Could not write {}: {}", file.display(), e))?;
+  info!("Meeting transcription started: {}", file.display());
+  *meeting = Some(MeetingStatus { active: true, file: Some(file.clone()), started_at: Some(started_at), paragraphs: Vec::new() });
+  Ok(file)
+}
+
+/// End the meeting; returns the transcript file
+pub fn stop() -> Option<PathBuf> {
+  let mut meeting = MEETING.lock().unwrap();
+  let m = meeting.as_mut()?;
+  m.active = false;
+  info!("Meeting transcription stopped ({} paragraphs)", m.paragraphs.len());
+  m.file.clone()
+}
+
+fn speaker_label(speaker: Option<u32>) -> String {
+  match speaker {
+    Some(n) => format!("Speaker {}", n + 1),
+    None => "Speaker".into(),
+  }
+}
+
+fn render(paragraphs: &[Paragraph]) -> String {
+  let mut out = String::from("# Meeting transcript\n\n");
+  for p in paragraphs {
+    out.push_str(&format!("**{}:** {}\n\n", speaker_label(p.speaker), p.text));
+  }
+  out
+}
+
+/// Add a final segment: consecutive speech by the same speaker joins one paragraph
+pub fn add_segment(app: &AppHandle, segment: &Segment) {
+  let mut meeting = MEETING.lock().unwrap();
+  let Some(m) = meeting.as_mut().filter(|m| m.active) else { return };
+  let mut updates = Vec::new();
+  for (speaker, text) in segment.speaker_runs() {
+    if text.is_empty() {
+      continue;
+    }
+    match m.paragraphs.last_mut() {
+      Some(last) if last.speaker == speaker => {
+        last.text.push(' ');
+        last.text.push_str(&text);
+      }
+      _ => m.paragraphs.push(Paragraph { speaker, text }),
+    }
+    let index = m.paragraphs.len() - 1;
+    updates.retain(|u: &MeetingUpdate| u.index != index);
+    updates.push(MeetingUpdate { index, paragraph: m.paragraphs[index].clone() });
+  }
+  if updates.is_empty() {
+    return;
+  }
+  if let Some(file) = &m.file {
+    if let Err(e) = std::fs::write(file, render(&m.paragraphs)) {
+      warn!("Could not write meeting transcript {}: {}", file.display(), e);
+    }
+  }
+  for update in updates {
+    if let Err(e) = app.emit_to(LABEL, EVENT_NAME, &update) {
+      warn!("Failed to emit meeting update: {}", e);
+    }
+  }
+}
+
+/// Show the meeting window, creating it on first use
+pub fn open_window(app: &AppHandle) -> Result<(), String> {
+  if let Some(win) = app.get_webview_window(LABEL) {
+    let _ = win.show();
+    let _ = win.set_focus();
+    return Ok(());
+  }
+  WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("index.html#/meeting".into()))
+    .title("Meeting Transcription")
+    .inner_size(640.0, 560.0)
+    .center()
+    .build()
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
@@ -0,0 +1,86 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::connectivity::{Monitor, NetworkStatus};
+use crate::{history, http_client};
+
+// Watches whether the providers can be reached (the state is in connectivity.rs): probes them
+// every minute (every few seconds while offline, to notice the connection coming back) and
+// after a provider request fails, and emits `network-status` when that changes. While offline
+// refinement is skipped for local cleanup, and the HUD says so instead of showing HTTP errors.
+
+pub const EVENT_NAME: &str = "network-status";
+
+/// Any HTTP answer from one of these counts as being online; they go through the proxy
+const PROBE_URLS: &[&str] = &["https://openrouter.ai/api/v1/models", "https://api.deepgram.com"];
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+static MONITOR: Mutex<Option<Monitor>> = Mutex::new(None);
+/// Read on every dictation, so kept outside the lock
+static ONLINE: AtomicBool = AtomicBool::new(true);
+
+pub fn is_online() -> bool {
+  ONLINE.load(Ordering::Relaxed)
+}
+
+pub fn status() -> NetworkStatus {
+  MONITOR.lock().unwrap().get_or_insert_with(|| Monitor::new(history::now_secs())).status()
+}
+
+async fn probe() -> bool {
+  let client = http_client::shared();
+  for url in PROBE_URLS {
+    match client.head(*url).timeout(PROBE_TIMEOUT).send().await {
+      Ok(_) => return true,
+      Err(e) => info!("Network probe of {} failed: {}", url, e),
+    }
+  }
+  false
+}
+
+/// Count one probe result and tell the windows when it changed whether we're online
+fn record(app: &AppHandle, reachable: bool) -> Monitor {
+  let now = history::now_secs();
+  let (monitor, changed) = {
+    let mut guard = MONITOR.lock().unwrap();
+    let monitor = guard.get_or_insert_with(|| Monitor::new(now));
+    let changed = monitor.record(reachable, now);
+    (*monitor, changed)
+  };
+  let status = monitor.status();
+  ONLINE.store(status.online, Ordering::Relaxed);
+  if changed {
+    info!("Network is {}", if status.online { "back online" } else { "offline" });
+    if let Err(e) = app.emit(EVENT_NAME, status) {
+      warn!("Could not emit {}: {}", EVENT_NAME, e);
+    }
+  }
+  monitor
+}
+
+/// Probe now and return the result
+pub async fn check(app: &AppHandle) -> NetworkStatus {
+  let reachable = probe().await;
+  record(app, reachable).status()
+}
+
+/// A provider request failed without an HTTP answer: count it as a failed probe and probe right
+/// away rather than waiting for the next round. Returns whether we're now offline.
+pub async fn request_failed(app: &AppHandle) -> bool {
+  record(app, false);
+  !check(app).await.online
+}
+
+/// Probe for the lifetime of the app
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      let reachable = probe().await;
+      let interval = record(&app, reachable).interval();
+      tokio::time::sleep(interval).await;
+    }
+  });
+}
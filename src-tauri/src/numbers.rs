@@ -0,0 +1,426 @@
+// Spoken numbers, amounts and dates written the way the dictation's locale writes them, before
+// refinement so the model doesn't have to guess: "twenty three point five percent" becomes 23.5%
+// (23,5 % in German), "forty dollars and fifty cents" $40.50 and "march third twenty twenty four"
+// March 3, 2024. The `numbers_mode` pref picks the style: "digits" converts every number, "auto"
+// keeps one to nine as words unless they come with decimals or a unit, and "words" leaves the
+// text as spoken.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::locale::{self, LocaleRules};
+
+pub const NUMBER_MODES: &[&str] = &["words", "digits", "auto"];
+
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november", "december",
+];
+const UNITS: [&str; 10] = ["zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine"];
+const TEENS: [&str; 10] = ["ten", "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen", "eighteen", "nineteen"];
+const TENS: [&str; 8] = ["twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety"];
+const ORDINALS: [&str; 19] = [
+    "first", "second", "third", "fourth", "fifth", "sixth", "seventh", "eighth", "ninth", "tenth", "eleventh", "twelfth",
+    "thirteenth", "fourteenth", "fifteenth", "sixteenth", "seventeenth", "eighteenth", "nineteenth",
+];
+/// (singular, plural, symbol)
+const CURRENCIES: &[(&str, &str, &str)] =
+    &[("dollar", "dollars", "$"), ("euro", "euros", "€"), ("pound", "pounds", "£"), ("yen", "yen", "¥"), ("rupee", "rupees", "₹")];
+/// Words after which "one" is a pronoun ("no one", "which one") and stays a word
+const PRONOUN_ONE: &[&str] = &["no", "any", "every", "each", "which", "the", "this", "that"];
+
+/// Conventions used for languages without their own rules
+const DEFAULT_RULES: &str = "en";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Unit(u64),
+    Teen(u64),
+    Tens(u64),
+    Hundred,
+    Scale(u64),
+}
+
+fn kind(word: &str) -> Option<Kind> {
+    if let Some(i) = UNITS.iter().position(|w| *w == word) {
+        return Some(Kind::Unit(i as u64));
+    }
+    if let Some(i) = TEENS.iter().position(|w| *w == word) {
+        return Some(Kind::Teen(10 + i as u64));
+    }
+    if let Some(i) = TENS.iter().position(|w| *w == word) {
+        return Some(Kind::Tens(20 + 10 * i as u64));
+    }
+    match word {
+        "hundred" => Some(Kind::Hundred),
+        "thousand" => Some(Kind::Scale(1_000)),
+        "million" => Some(Kind::Scale(1_000_000)),
+        "billion" => Some(Kind::Scale(1_000_000_000)),
+        _ => None,
+    }
+}
+
+fn is_small(k: Option<Kind>) -> bool {
+    matches!(k, Some(Kind::Unit(_) | Kind::Teen(_) | Kind::Tens(_)))
+}
+
+/// Years said in two halves: "nineteen eighty four", "twenty twenty", "twenty oh five"
+fn parse_year_pair(words: &[String]) -> Option<(u64, usize)> {
+    let century = match words.first()?.as_str() {
+        "nineteen" => 19,
+        "twenty" => 20,
+        _ => return None,
+    };
+    let second = words.get(1)?.as_str();
+    let next_unit = || match words.get(2).and_then(|w| kind(w)) {
+        Some(Kind::Unit(u)) if u > 0 => Some(u),
+        _ => None,
+    };
+    let (rest, used) = match kind(second) {
+        Some(Kind::Teen(v)) => (v, 2),
+        Some(Kind::Tens(v)) => next_unit().map(|u| (v + u, 3)).unwrap_or((v, 2)),
+        _ if second == "oh" => (next_unit()?, 3),
+        _ => return None,
+    };
+    Some((century * 100 + rest, used))
+}
+
+/// A whole number at the start of `words` and how many words it took
+fn parse_integer(words: &[String]) -> Option<(u64, usize)> {
+    if let Some(year) = parse_year_pair(words) {
+        return Some(year);
+    }
+    let (mut total, mut current) = (0u64, 0u64);
+    let mut last: Option<Kind> = None;
+    let mut last_scale = u64::MAX;
+    let (mut n, mut consumed) = (0, 0);
+    while let Some(word) = words.get(n) {
+        let next = words.get(n + 1).and_then(|w| kind(w));
+        // "one hundred and five"
+        if word == "and" && matches!(last, Some(Kind::Hundred | Kind::Scale(_))) && is_small(next) {
+            n += 1;
+            continue;
+        }
+        // "a hundred", "a thousand"
+        if word == "a" && last.is_none() && matches!(next, Some(Kind::Hundred | Kind::Scale(_))) {
+            current = 1;
+            last = Some(Kind::Unit(1));
+            n += 1;
+            continue;
+        }
+        let Some(k) = kind(word) else { break };
+        match k {
+            Kind::Unit(v) | Kind::Teen(v) => {
+                let after_tens = matches!(last, Some(Kind::Tens(_)));
+                if matches!(last, Some(Kind::Unit(_) | Kind::Teen(_))) || (after_tens && v >= 10) || (v == 0 && last.is_some()) {
+                    break;
+                }
+                current += v;
+            }
+            Kind::Tens(v) => {
+                if is_small(last) {
+                    break;
+                }
+                current += v;
+            }
+            Kind::Hundred => {
+                if current == 0 || current >= 100 || !is_small(last) {
+                    break;
+                }
+                current *= 100;
+            }
+            Kind::Scale(s) => {
+                if current == 0 || s >= last_scale {
+                    break;
+                }
+                total += current * s;
+                current = 0;
+                last_scale = s;
+            }
+        }
+        last = Some(k);
+        n += 1;
+        consumed = n;
+    }
+    (consumed > 0).then_some((total + current, consumed))
+}
+
+/// Digits after "point": "point five", "point oh seven"
+fn parse_decimals(words: &[String]) -> Option<(String, usize)> {
+    if words.first()? != "point" {
+        return None;
+    }
+    let digits: String = words[1..]
+        .iter()
+        .map_while(|w| if w == "oh" { Some('0') } else { UNITS.iter().position(|u| u == w).map(|d| (b'0' + d as u8) as char) })
+        .collect();
+    (!digits.is_empty()).then(|| (digits.clone(), 1 + digits.len()))
+}
+
+fn parse_ordinal(words: &[String]) -> Option<(u64, usize)> {
+    let ordinal = |w: &str| ORDINALS.iter().position(|o| *o == w).map(|i| i as u64 + 1);
+    let first = words.first()?.as_str();
+    match first {
+        "twentieth" => return Some((20, 1)),
+        "thirtieth" => return Some((30, 1)),
+        _ => {}
+    }
+    if let Some(v) = ordinal(first) {
+        return Some((v, 1));
+    }
+    let Some(Kind::Tens(tens)) = kind(first) else { return None };
+    let unit = ordinal(words.get(1)?).filter(|v| *v < 10)?;
+    Some((tens + unit, 2))
+}
+
+fn month(word: &str) -> Option<&'static str> {
+    MONTHS.iter().find(|m| **m == word).copied()
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+fn parse_year(words: &[String]) -> Option<(u64, usize)> {
+    parse_integer(words).filter(|(y, _)| (1000..3000).contains(y))
+}
+
+fn format_date(month: &str, day: u64, year: Option<u64>, language: &str) -> String {
+    let month = capitalized(month);
+    match (crate::language::primary_subtag(language) == "en", year) {
+        (true, Some(y)) => format!("{} {}, {}", month, day, y),
+        (true, None) => format!("{} {}", month, day),
+        (false, Some(y)) => format!("{} {} {}", day, month, y),
+        (false, None) => format!("{} {}", day, month),
+    }
+}
+
+/// "march third [twenty twenty four]", "[the] third of march [twenty twenty four]"
+fn parse_date(words: &[String], language: &str) -> Option<(String, usize)> {
+    let (start, the) = if words.first()? == "the" { (1, 1) } else { (0, 0) };
+    let rest = &words[start..];
+    if let Some(m) = month(rest.first()?) {
+        if the == 1 {
+            return None;
+        }
+        let after = &rest[1..];
+        let (day, day_len, ordinal) = match parse_ordinal(after) {
+            Some((d, n)) => (d, n, true),
+            None => {
+                // A cardinal day ("march three") only with a year after it; "may one" is too common otherwise
+                let (d, n) = parse_integer(after).filter(|(_, n)| *n <= 2)?;
+                (d, n, false)
+            }
+        };
+        if !(1..=31).contains(&day) {
+            return None;
+        }
+        let year = parse_year(&after[day_len..]);
+        if !ordinal && year.is_none() {
+            return None;
+        }
+        let used = 1 + day_len + year.map(|(_, n)| n).unwrap_or(0);
+        return Some((format_date(m, day, year.map(|(y, _)| y), language), used));
+    }
+    let (day, day_len) = parse_ordinal(rest).filter(|(d, _)| (1..=31).contains(d))?;
+    if rest.get(day_len)? != "of" {
+        return None;
+    }
+    let m = month(rest.get(day_len + 1)?)?;
+    let year = parse_year(&rest[day_len + 2..]);
+    let used = the + day_len + 2 + year.map(|(_, n)| n).unwrap_or(0);
+    Some((format_date(m, day, year.map(|(y, _)| y), language), used))
+}
+
+enum Suffix {
+    None,
+    Percent,
+    Currency(&'static str),
+}
+
+struct Amount {
+    int: u64,
+    decimals: Option<String>,
+    suffix: Suffix,
+    consumed: usize,
+}
+
+fn parse_amount(words: &[String]) -> Option<Amount> {
+    let (int, mut n) = parse_integer(words)?;
+    let mut decimals = parse_decimals(&words[n..]).map(|(d, used)| {
+        n += used;
+        d
+    });
+    let word = |i: usize| words.get(i).map(String::as_str);
+    let suffix = if word(n) == Some("percent") {
+        n += 1;
+        Suffix::Percent
+    } else if word(n) == Some("per") && word(n + 1) == Some("cent") {
+        n += 2;
+        Suffix::Percent
+    } else if let Some(&(_, _, symbol)) = CURRENCIES.iter().find(|(one, many, _)| word(n) == Some(one) || word(n) == Some(many)) {
+        n += 1;
+        // "... dollars and fifty cents"
+        let skip = usize::from(word(n) == Some("and"));
+        if decimals.is_none() {
+            if let Some((cents, used)) = parse_integer(&words[(n + skip).min(words.len())..]).filter(|(c, _)| *c < 100) {
+                if matches!(word(n + skip + used), Some("cents" | "cent")) {
+                    decimals = Some(format!("{:02}", cents));
+                    n += skip + used + 1;
+                }
+            }
+        }
+        Suffix::Currency(symbol)
+    } else {
+        Suffix::None
+    };
+    Some(Amount { int, decimals, suffix, consumed: n })
+}
+
+fn format_number(int: u64, decimals: Option<&str>, rules: &LocaleRules) -> String {
+    let digits = int.to_string();
+    let mut out = String::new();
+    // Four-digit numbers (years, PINs) stay ungrouped
+    if int >= 10_000 {
+        let sep = if rules.thousands == ' ' { '\u{202F}' } else { rules.thousands };
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                out.push(sep);
+            }
+            out.push(c);
+        }
+    } else {
+        out = digits;
+    }
+    if let Some(d) = decimals {
+        out.push(rules.decimal);
+        out.push_str(d);
+    }
+    out
+}
+
+fn format_amount(amount: &Amount, rules: &LocaleRules) -> String {
+    let number = format_number(amount.int, amount.decimals.as_deref(), rules);
+    match amount.suffix {
+        Suffix::None => number,
+        Suffix::Percent if rules.percent_space => format!("{} %", number),
+        Suffix::Percent => format!("{}%", number),
+        Suffix::Currency(symbol) if rules.currency_after => format!("{} {}", number, symbol),
+        Suffix::Currency(symbol) => format!("{}{}", symbol, number),
+    }
+}
+
+fn tokens_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\p{L}+|[^\p{L}]+").unwrap())
+}
+
+/// "twenty-three" -> "twenty three", so compound numbers read as separate words
+fn hyphens_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\b(twenty|thirty|forty|fifty|sixty|seventy|eighty|ninety)-(\p{L})").unwrap())
+}
+
+/// `text` with its spoken numbers and dates written per `mode` and the conventions of `language`
+pub fn format_numbers(text: &str, mode: &str, language: &str) -> String {
+    if mode != "digits" && mode != "auto" {
+        return text.to_string();
+    }
+    let rules = locale::rules(language).or_else(|| locale::rules(DEFAULT_RULES)).expect("rules for the default language");
+    let text = hyphens_re().replace_all(text, "$1 $2");
+    let tokens: Vec<&str> = tokens_re().find_iter(&text).map(|m| m.as_str()).collect();
+    let is_word = |t: &str| t.chars().next().is_some_and(char::is_alphabetic);
+    let mut out = String::new();
+    let mut previous_word = String::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if !is_word(tokens[i]) {
+            out.push_str(tokens[i]);
+            i += 1;
+            continue;
+        }
+        // The words from here on, as long as only spaces separate them
+        let mut positions = vec![i];
+        while positions.len() < 16 {
+            let last = *positions.last().unwrap();
+            match (tokens.get(last + 1), tokens.get(last + 2)) {
+                (Some(sep), Some(next)) if sep.chars().all(|c| c == ' ' || c == '\t') && is_word(next) => positions.push(last + 2),
+                _ => break,
+            }
+        }
+        let words: Vec<String> = positions.iter().map(|&p| tokens[p].to_lowercase()).collect();
+
+        let replacement = parse_date(&words, language).or_else(|| {
+            let amount = parse_amount(&words)?;
+            let single = amount.consumed == 1 && amount.decimals.is_none() && matches!(amount.suffix, Suffix::None);
+            if single && words[0] == "one" && PRONOUN_ONE.contains(&previous_word.as_str()) {
+                return None;
+            }
+            if mode == "auto" && single && amount.int < 10 {
+                return None;
+            }
+            Some((format_amount(&amount, &rules), amount.consumed))
+        });
+        match replacement {
+            Some((formatted, used)) => {
+                out.push_str(&formatted);
+                previous_word = words[used - 1].clone();
+                i = positions[used - 1] + 1;
+            }
+            None => {
+                out.push_str(tokens[i]);
+                previous_word = words[0].clone();
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auto(text: &str) -> String {
+        format_numbers(text, "auto", "en")
+    }
+
+    #[test]
+    fn test_numbers_and_amounts() {
+        assert_eq!(auto("growth was twenty three point five percent"), "growth was 23.5%");
+        assert_eq!(auto("it costs forty dollars and fifty cents"), "it costs $40.50");
+        assert_eq!(auto("one hundred and five people, twelve thousand five hundred views"), "105 people, 12,500 views");
+        assert_eq!(auto("a thousand and one nights"), "1001 nights");
+        assert_eq!(auto("Seventy-two hours"), "72 hours");
+        assert_eq!(auto("point oh seven"), "point oh seven");
+        assert_eq!(auto("three point one four"), "3.14");
+    }
+
+    #[test]
+    fn test_modes() {
+        // One to nine stay words in auto unless they carry a unit
+        assert_eq!(auto("I have three ideas and five percent left"), "I have three ideas and 5% left");
+        assert_eq!(format_numbers("I have three ideas", "digits", "en"), "I have 3 ideas");
+        assert_eq!(format_numbers("no one said one thing", "digits", "en"), "no one said 1 thing");
+        assert_eq!(format_numbers("twenty three", "words", "en"), "twenty three");
+        // Numbers that follow each other stay apart
+        assert_eq!(format_numbers("one two three", "digits", "en"), "1 2 3");
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(auto("due march third twenty twenty four, ok"), "due March 3, 2024, ok");
+        assert_eq!(auto("on the twenty-first of june"), "on June 21");
+        assert_eq!(auto("born in nineteen eighty four"), "born in 1984");
+        assert_eq!(auto("you may one day"), "you may one day");
+        assert_eq!(format_numbers("am third of may two thousand and five", "auto", "de"), "am 3 May 2005");
+    }
+
+    #[test]
+    fn test_locales() {
+        assert_eq!(format_numbers("twenty three point five percent", "auto", "de"), "23,5 %");
+        assert_eq!(format_numbers("forty euros and fifty cents", "auto", "fr-FR"), "40,50 €");
+        assert_eq!(format_numbers("twelve thousand five hundred", "auto", "fr"), "12\u{202F}500");
+        assert_eq!(format_numbers("fifteen thousand", "auto", "xx"), "15,000");
+    }
+}
@@ -0,0 +1,332 @@
+// Packs: shareable bundles of vocabulary, symbol mappings, snippets, replacement rules and prompt
+// additions, so a team can hand around its dictation conventions (medical, legal, ...) as one
+// JSON file. The user's own customizations are kept as a pack too; importing merges another
+// pack into it, with conflicts on the same spoken phrase either kept, replaced or reported.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Version of the pack file format; newer files are refused rather than half-read
+pub const PACK_FORMAT: u32 = 1;
+
+fn default_format() -> u32 {
+    PACK_FORMAT
+}
+
+/// A spoken phrase and what it becomes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Mapping {
+    pub from: String,
+    pub to: String,
+}
+
+impl Mapping {
+    pub fn new(from: &str, to: &str) -> Self {
+        Mapping { from: from.to_string(), to: to.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Pack {
+    #[serde(default = "default_format")]
+    pub format: u32,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Names and terms boosted in speech recognition and kept verbatim, like dictionary entries
+    #[serde(default)]
+    pub vocabulary: Vec<String>,
+    /// Spoken symbol names, applied with the built-in ones ("check mark" -> "✓")
+    #[serde(default)]
+    pub symbols: Vec<Mapping>,
+    /// Spoken emoji names, applied with the built-in ones when emoji are on ("ship it" -> "🚢")
+    #[serde(default)]
+    pub emoji: Vec<Mapping>,
+    /// Spoken triggers that expand to longer text ("sign off" -> "Best regards, ...")
+    #[serde(default)]
+    pub snippets: Vec<Mapping>,
+    /// Words or phrases always written a certain way ("java script" -> "JavaScript")
+    #[serde(default)]
+    pub rules: Vec<Mapping>,
+    /// Extra instructions appended to the refinement prompt
+    #[serde(default)]
+    pub prompt_additions: Vec<String>,
+}
+
+impl Default for Pack {
+    fn default() -> Self {
+        Pack {
+            format: PACK_FORMAT,
+            name: String::new(),
+            description: String::new(),
+            vocabulary: Vec::new(),
+            symbols: Vec::new(),
+            emoji: Vec::new(),
+            snippets: Vec::new(),
+            rules: Vec::new(),
+            prompt_additions: Vec::new(),
+        }
+    }
+}
+
+/// What to do when an imported entry has the same spoken phrase as an existing one
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Change nothing if there are conflicts; report them so the user can choose
+    Ask,
+    /// Keep the existing entries
+    Keep,
+    /// Use the imported entries
+    Replace,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct Conflict {
+    /// "symbols" | "emoji" | "snippets" | "rules"
+    pub section: String,
+    pub from: String,
+    pub current: String,
+    pub incoming: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, JsonSchema)]
+pub struct ImportReport {
+    /// False when `ask` found conflicts and nothing was changed
+    pub applied: bool,
+    pub added: usize,
+    pub replaced: usize,
+    pub skipped: usize,
+    pub conflicts: Vec<Conflict>,
+}
+
+fn clean_mappings(list: &mut Vec<Mapping>) {
+    for m in list.iter_mut() {
+        m.from = m.from.trim().to_string();
+    }
+    list.retain(|m| !m.from.is_empty());
+}
+
+impl Pack {
+    /// Parse a pack file
+    pub fn from_json(json: &str) -> Result<Pack, String> {
+        let pack: Pack = serde_json::from_str(json).map_err(|e| format!("Not a valid pack: {}", e))?;
+        pack.normalized()
+    }
+
+    /// Refuse newer formats and drop entries without a spoken phrase
+    pub fn normalized(mut self) -> Result<Pack, String> {
+        if self.format > PACK_FORMAT {
+            return Err(format!("Pack format {} is newer than this app supports ({})", self.format, PACK_FORMAT));
+        }
+        clean_mappings(&mut self.symbols);
+        clean_mappings(&mut self.emoji);
+        clean_mappings(&mut self.snippets);
+        clean_mappings(&mut self.rules);
+        self.vocabulary = self.vocabulary.iter().map(|w| w.trim().to_string()).filter(|w| !w.is_empty()).collect();
+        self.prompt_additions.retain(|p| !p.trim().is_empty());
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vocabulary.is_empty() && self.symbols.is_empty() && self.emoji.is_empty() && self.snippets.is_empty() && self.rules.is_empty() && self.prompt_additions.is_empty()
+    }
+
+    /// Merge `incoming` into this pack
+    pub fn merge(&mut self, incoming: &Pack, on_conflict: OnConflict) -> ImportReport {
+        let mut report = ImportReport::default();
+        let sections = [("symbols", &incoming.symbols, &self.symbols), ("emoji", &incoming.emoji, &self.emoji), ("snippets", &incoming.snippets, &self.snippets), ("rules", &incoming.rules, &self.rules)];
+        for (section, theirs, ours) in sections {
+            for m in theirs {
+                if let Some(existing) = ours.iter().find(|e| e.from.eq_ignore_ascii_case(&m.from) && e.to != m.to) {
+                    report.conflicts.push(Conflict {
+                        section: section.to_string(),
+                        from: m.from.clone(),
+                        current: existing.to.clone(),
+                        incoming: m.to.clone(),
+                    });
+                }
+            }
+        }
+        if on_conflict == OnConflict::Ask && !report.conflicts.is_empty() {
+            return report;
+        }
+
+        let replace = on_conflict == OnConflict::Replace;
+        for (theirs, ours) in [(&incoming.symbols, &mut self.symbols), (&incoming.emoji, &mut self.emoji), (&incoming.snippets, &mut self.snippets), (&incoming.rules, &mut self.rules)] {
+            for m in theirs {
+                match ours.iter_mut().find(|e| e.from.eq_ignore_ascii_case(&m.from)) {
+                    None => {
+                        ours.push(m.clone());
+                        report.added += 1;
+                    }
+                    Some(e) if e.to == m.to => report.skipped += 1,
+                    Some(e) if replace => {
+                        *e = m.clone();
+                        report.replaced += 1;
+                    }
+                    Some(_) => report.skipped += 1,
+                }
+            }
+        }
+        for w in &incoming.vocabulary {
+            if self.vocabulary.iter().any(|e| e.eq_ignore_ascii_case(w)) {
+                report.skipped += 1;
+            } else {
+                self.vocabulary.push(w.clone());
+                report.added += 1;
+            }
+        }
+        for p in &incoming.prompt_additions {
+            if self.prompt_additions.iter().any(|e| e.trim() == p.trim()) {
+                report.skipped += 1;
+            } else {
+                self.prompt_additions.push(p.clone());
+                report.added += 1;
+            }
+        }
+        report.applied = true;
+        report
+    }
+
+    /// Expand snippets and apply rules; longer phrases first so they win over their parts
+    pub fn apply_text(&self, text: &str) -> String {
+        let mut mappings: Vec<&Mapping> = self.snippets.iter().chain(&self.rules).collect();
+        mappings.sort_by_key(|m| std::cmp::Reverse(m.from.len()));
+        mappings.iter().fold(text.to_string(), |acc, m| replace_phrase(&acc, &m.from, &m.to))
+    }
+
+    /// Text appended to the refinement prompt, if the pack adds instructions or spellings. The
+    /// personal `dictionary` goes in the same list of spellings, each spelling once.
+    pub fn prompt_suffix(&self, dictionary: &[String]) -> Option<String> {
+        let lines: Vec<String> = self.prompt_additions.iter().map(|p| format!("- {}", p.trim())).collect();
+        let mut out = String::new();
+        if !lines.is_empty() {
+            out.push_str("\n\n# ADDITIONAL INSTRUCTIONS\n");
+            out.push_str(&lines.join("\n"));
+        }
+        // Rules are applied before the model sees the text; say so, or it may "fix" them back
+        let mut spellings: Vec<&str> = Vec::new();
+        for spelling in dictionary.iter().chain(&self.vocabulary).map(String::as_str).chain(self.rules.iter().map(|r| r.to.as_str())) {
+            if !spellings.contains(&spelling) {
+                spellings.push(spelling);
+            }
+        }
+        if !spellings.is_empty() {
+            out.push_str(&format!("\n\nKeep these spellings exactly as written: {}", spellings.join(", ")));
+        }
+        (!out.is_empty()).then_some(out)
+    }
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric())
+}
+
+/// Replace whole-word, case-insensitive occurrences of `phrase`
+pub fn replace_phrase(text: &str, phrase: &str, replacement: &str) -> String {
+    let phrase: Vec<char> = phrase.to_lowercase().chars().collect();
+    if phrase.is_empty() {
+        return text.to_string();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let end = i + phrase.len();
+        let matches = end <= chars.len()
+            && lower[i..end] == phrase[..]
+            && !is_word_char(i.checked_sub(1).map(|p| chars[p]))
+            && !is_word_char(chars.get(end).copied());
+        if matches {
+            out.push_str(replacement);
+            i = end;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack() -> Pack {
+        Pack {
+            vocabulary: vec!["Tanjim".into()],
+            symbols: vec![Mapping::new("check mark", "✓")],
+            snippets: vec![Mapping::new("sign off", "Best regards,\nTanjim")],
+            rules: vec![Mapping::new("java script", "JavaScript"), Mapping::new("js", "JS")],
+            prompt_additions: vec!["Use British spelling.".into()],
+            ..Pack::default()
+        }
+    }
+
+    #[test]
+    fn test_replace_phrase_whole_words() {
+        assert_eq!(replace_phrase("I like Java Script a lot", "java script", "JavaScript"), "I like JavaScript a lot");
+        assert_eq!(replace_phrase("jsx and js", "js", "JS"), "jsx and JS");
+        assert_eq!(replace_phrase("café js", "js", "JS"), "café JS");
+    }
+
+    #[test]
+    fn test_apply_text() {
+        assert_eq!(pack().apply_text("write java script then sign off"), "write JavaScript then Best regards,\nTanjim");
+    }
+
+    #[test]
+    fn test_prompt_suffix() {
+        let suffix = pack().prompt_suffix(&[]).unwrap();
+        assert!(suffix.contains("- Use British spelling."));
+        assert!(suffix.contains("Tanjim, JavaScript, JS"));
+        assert_eq!(Pack::default().prompt_suffix(&[]), None);
+
+        // Dictionary words join the same list, once, with no second list
+        let dictionary = vec!["MegaLLM".to_string(), "Tanjim".to_string()];
+        let suffix = pack().prompt_suffix(&dictionary).unwrap();
+        assert!(suffix.ends_with("Keep these spellings exactly as written: MegaLLM, Tanjim, JavaScript, JS"));
+        assert_eq!(suffix.matches("spellings").count(), 1);
+        assert!(Pack::default().prompt_suffix(&dictionary).unwrap().ends_with("MegaLLM, Tanjim"));
+    }
+
+    #[test]
+    fn test_merge_conflicts() {
+        let incoming = Pack {
+            vocabulary: vec!["HIPAA".into()],
+            symbols: vec![Mapping::new("Check Mark", "✔"), Mapping::new("cross mark", "✗")],
+            prompt_additions: vec!["Use British spelling.".into(), "No emoji.".into()],
+            ..Pack::default()
+        };
+
+        let mut ours = pack();
+        let report = ours.merge(&incoming, OnConflict::Ask);
+        assert!(!report.applied);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].current, "✓");
+        assert_eq!(ours, pack());
+
+        let report = ours.merge(&incoming, OnConflict::Keep);
+        assert!(report.applied);
+        assert_eq!((report.added, report.replaced, report.skipped), (3, 0, 2));
+        assert_eq!(ours.symbols[0].to, "✓");
+
+        let mut ours = pack();
+        let report = ours.merge(&incoming, OnConflict::Replace);
+        assert_eq!((report.added, report.replaced, report.skipped), (3, 1, 1));
+        assert_eq!(ours.symbols[0].to, "✔");
+        assert_eq!(ours.prompt_additions.len(), 2);
+    }
+
+    #[test]
+    fn test_from_json() {
+        let pack = Pack::from_json(r#"{"name": "Legal", "rules": [{"from": " voir dire ", "to": "voir dire"}, {"from": "", "to": "x"}]}"#).unwrap();
+        assert_eq!(pack.format, PACK_FORMAT);
+        assert_eq!(pack.rules, vec![Mapping::new("voir dire", "voir dire")]);
+        assert!(Pack::from_json(r#"{"format": 99}"#).is_err());
+        assert!(Pack::from_json("not json").is_err());
+    }
+}
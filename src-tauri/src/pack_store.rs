@@ -0,0 +1,193 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+
+use crate::pack::{ImportReport, OnConflict, Pack};
+use crate::{builtin_packs, history, profiles};
+
+// The user's custom symbols, snippets, rules and prompt additions, kept as one pack in its own
+// store. Exporting writes it to a file others can import; importing merges a file into it.
+// Built-in packs (builtin_packs.rs) are enabled globally or per app profile; the pack used for a
+// dictation is the enabled built-ins with the custom pack on top.
+
+const PACKS_STORE: &str = "packs.json";
+const K_CUSTOM: &str = "custom";
+const K_ENABLED: &str = "enabled";
+
+/// Extension of exported pack files
+pub const PACK_EXTENSION: &str = "dhpack.json";
+
+/// The last active pack with the built-in ids it was built from, so a dictation doesn't rebuild it
+static ACTIVE: Mutex<Option<(Vec<String>, Pack)>> = Mutex::new(None);
+
+/// Drop the cached active pack; call after packs.json changes
+pub fn invalidate() {
+  *ACTIVE.lock().unwrap() = None;
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PackInfo {
+  pub id: String,
+  pub name: String,
+  pub description: String,
+  /// Enabled for every app
+  pub enabled: bool,
+  /// App profiles it is enabled for
+  pub apps: Vec<String>,
+}
+
+pub fn custom_pack(app: &AppHandle) -> Pack {
+  app
+    .store(PACKS_STORE)
+    .ok()
+    .and_then(|s| s.get(K_CUSTOM))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+pub fn set_custom_pack(app: &AppHandle, pack: &Pack) -> Result<(), String> {
+  let store = app.store(PACKS_STORE).map_err(|e| e.to_string())?;
+  store.set(K_CUSTOM, serde_json::to_value(pack).map_err(|e| e.to_string())?);
+  invalidate();
+  store.save().map_err(|e| e.to_string())
+}
+
+fn file_stem(name: &str) -> String {
+  let stem: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect();
+  let stem = stem.trim_matches('-').to_lowercase();
+  if stem.is_empty() { "dictation-hud-pack".into() } else { stem }
+}
+
+/// Write the custom pack to `path`, or to the Downloads folder; returns the file path
+pub fn export(app: &AppHandle, path: Option<&Path>, name: Option<&str>, description: Option<&str>) -> Result<PathBuf, String> {
+  let mut pack = custom_pack(app);
+  if pack.is_empty() {
+    return Err("Nothing to export: add symbols, snippets, rules or prompt additions first".into());
+  }
+  if let Some(name) = name.filter(|n| !n.trim().is_empty()) {
+    pack.name = name.trim().to_string();
+  }
+  if let Some(description) = description {
+    pack.description = description.trim().to_string();
+  }
+  let path = match path {
+    Some(p) => p.to_path_buf(),
+    None => {
+      let dir = app.path().download_dir().or_else(|_| app.path().home_dir()).map_err(|e| e.to_string())?;
+      let stem = if pack.name.is_empty() { format!("dictation-hud-pack-{}", history::now_secs()) } else { file_stem(&pack.name) };
+      dir.join(format!("{}.{}", stem, PACK_EXTENSION))
+    }
+  };
+  let json = serde_json::to_string_pretty(&pack).map_err(|e| e.to_string())?;
+  std::fs::write(&path, json).map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+  info!("Exported pack \"{}\" to {}", pack.name, path.display());
+  Ok(path)
+}
+
+/// Merge the pack file at `path` into the custom pack
+pub fn import(app: &AppHandle, path: &Path, on_conflict: OnConflict) -> Result<ImportReport, String> {
+  let json = std::fs::read_to_string(path).map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+  let incoming = Pack::from_json(&json)?;
+  let mut pack = custom_pack(app);
+  let report = pack.merge(&incoming, on_conflict);
+  if report.applied {
+    set_custom_pack(app, &pack)?;
+    info!(
+      "Imported pack \"{}\" from {}: {} added, {} replaced, {} skipped",
+      incoming.name,
+      path.display(),
+      report.added,
+      report.replaced,
+      report.skipped
+    );
+  }
+  Ok(report)
+}
+
+/// Ids of the built-in packs enabled for every app
+pub fn enabled_packs(app: &AppHandle) -> Vec<String> {
+  app
+    .store(PACKS_STORE)
+    .ok()
+    .and_then(|s| s.get(K_ENABLED))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+fn toggle(list: &mut Vec<String>, id: &str, enabled: bool) {
+  list.retain(|p| p != id);
+  if enabled {
+    list.push(id.to_string());
+  }
+}
+
+/// Enable or disable a built-in pack for every app, or (with `app_name`) for one app profile
+pub fn set_enabled(app: &AppHandle, id: &str, app_name: Option<&str>, enabled: bool) -> Result<(), String> {
+  let builtin = builtin_packs::find(id).ok_or_else(|| format!("Unknown pack: {}", id))?;
+  match app_name {
+    Some(name) => {
+      let mut overrides = profiles::load_profiles(app).remove(&profiles::app_key(name)).unwrap_or_default();
+      let mut packs = overrides.packs.take().unwrap_or_default();
+      toggle(&mut packs, builtin.id, enabled);
+      overrides.packs = (!packs.is_empty()).then_some(packs);
+      profiles::set_app_profile(app, name, Some(overrides)).map_err(|e| e.to_string())?;
+    }
+    None => {
+      let mut packs = enabled_packs(app);
+      toggle(&mut packs, builtin.id, enabled);
+      let store = app.store(PACKS_STORE).map_err(|e| e.to_string())?;
+      store.set(K_ENABLED, serde_json::to_value(&packs).map_err(|e| e.to_string())?);
+      invalidate();
+      store.save().map_err(|e| e.to_string())?;
+    }
+  }
+  info!("Pack \"{}\" {} for {}", builtin.id, if enabled { "enabled" } else { "disabled" }, app_name.unwrap_or("all apps"));
+  Ok(())
+}
+
+pub fn list(app: &AppHandle) -> Vec<PackInfo> {
+  let enabled = enabled_packs(app);
+  let profiles = profiles::load_profiles(app);
+  builtin_packs::BUILTIN_PACKS
+    .iter()
+    .map(|b| PackInfo {
+      id: b.id.to_string(),
+      name: b.name.to_string(),
+      description: b.description.to_string(),
+      enabled: enabled.iter().any(|e| e == b.id),
+      apps: profiles
+        .iter()
+        .filter(|(_, o)| o.packs.as_ref().is_some_and(|p| p.iter().any(|e| e == b.id)))
+        .map(|(key, _)| key.clone())
+        .collect(),
+    })
+    .collect()
+}
+
+/// The pack for the running dictation: enabled built-ins (global and the session app's), then the
+/// custom pack, whose entries win on conflicts. Cached until the enabled packs or the custom
+/// pack change
+pub fn active_pack(app: &AppHandle) -> Pack {
+  let mut ids = enabled_packs(app);
+  for id in profiles::session_overrides().packs.unwrap_or_default() {
+    if !ids.contains(&id) {
+      ids.push(id);
+    }
+  }
+  if let Some((cached_ids, pack)) = ACTIVE.lock().unwrap().as_ref() {
+    if *cached_ids == ids {
+      return pack.clone();
+    }
+  }
+  let mut pack = Pack::default();
+  for builtin in ids.iter().filter_map(|id| builtin_packs::find(id)) {
+    pack.merge(&builtin.to_pack(), OnConflict::Keep);
+  }
+  pack.merge(&custom_pack(app), OnConflict::Replace);
+  *ACTIVE.lock().unwrap() = Some((ids, pack.clone()));
+  pack
+}
@@ -1,65 +1,313 @@
+use std::sync::Mutex;
 use tauri::AppHandle;
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::focus_probe::{self, FocusKind};
+use crate::hud_event::{emit_hud_event, HudEvent};
+use crate::keymacro::{MacroKey, Step};
+use crate::stream_inserter::StreamInserter;
+use crate::typing_rhythm::Rhythm;
+use crate::{profiles, window_target};
+
+/// Terminals (process names as profile keys: lowercase, no ".exe") where Ctrl+V is a control
+/// character and paste/copy need Shift. Users can add more via the `terminal_apps` pref.
+const TERMINAL_APPS: &[&str] = &[
+  // Windows
+  "windowsterminal", "openconsole", "conhost", "cmd", "powershell", "pwsh", "mintty", "tabby", "hyper",
+  // Linux
+  "gnome-terminal-server", "gnome-terminal", "konsole", "xfce4-terminal", "mate-terminal", "lxterminal",
+  "tilix", "terminator", "xterm", "uxterm", "urxvt", "rxvt", "st", "foot", "kgx", "ptyxis", "terminology",
+  // Cross-platform
+  "alacritty", "kitty", "wezterm", "wezterm-gui", "ghostty", "warp",
+];
+
+/// Whether an executable/application name is a known terminal or in the user's extra list
+pub fn is_terminal_app(app: &str, extra: &[String]) -> bool {
+  let key = profiles::app_key(app);
+  !key.is_empty() && (TERMINAL_APPS.contains(&key.as_str()) || extra.iter().any(|t| profiles::app_key(t) == key))
+}
+
+/// Terminal apps need Shift added to Ctrl+V / Ctrl+C. macOS terminals take Cmd+V/Cmd+C like any app.
+fn terminal_in_foreground(extra: &[String]) -> bool {
+  if cfg!(target_os = "macos") {
+    return false;
+  }
+  match window_target::foreground_window() {
+    Some(w) if is_terminal_app(&w.app, extra) => {
+      info!("Terminal in foreground ({}), adding Shift to the shortcut", w.app);
+      true
+    }
+    _ => false,
+  }
+}
 
 #[cfg(feature = "native-input")]
-fn send_paste() -> anyhow::Result<()> {
-  #[cfg(target_os="macos")] {
-    use enigo::*;
-    let mut e = Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+fn send_shortcut(letter: char, shift: bool) -> anyhow::Result<()> {
+  use enigo::*;
+  #[cfg(target_os = "linux")]
+  if let Some(result) = crate::wayland_input::send_shortcut(letter, shift) {
+    return result;
+  }
+  let mut e = Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  // Cmd on macOS, Control elsewhere
+  let modifier = if cfg!(target_os = "macos") { Key::Meta } else { Key::Control };
 
-    // Press and hold Cmd
-    e.key(Key::Meta, Direction::Press).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
-    std::thread::sleep(std::time::Duration::from_millis(20));
+  // Press and hold the modifier
+  e.key(modifier, Direction::Press).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  if shift {
+    e.key(Key::Shift, Direction::Press).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  }
+  std::thread::sleep(std::time::Duration::from_millis(20));
 
-    // Press V while holding Cmd
-    e.key(Key::Unicode('v'), Direction::Click).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
-    std::thread::sleep(std::time::Duration::from_millis(20));
+  // Press the letter while holding the modifier
+  e.key(Key::Unicode(letter), Direction::Click).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  std::thread::sleep(std::time::Duration::from_millis(20));
 
-    // Release Cmd
-    e.key(Key::Meta, Direction::Release).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  // Release the modifiers
+  if shift {
+    e.key(Key::Shift, Direction::Release).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  }
+  e.key(modifier, Direction::Release).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
 
-    return Ok(());
+  Ok(())
+}
+
+#[cfg(not(feature = "native-input"))]
+fn send_shortcut(_letter: char, _shift: bool) -> anyhow::Result<()> { Err(anyhow::anyhow!("native input not enabled")) }
+
+/// Ctrl+V (Cmd+V on macOS), or Ctrl+Shift+V when a terminal has focus
+fn send_paste(terminal_apps: &[String]) -> anyhow::Result<()> {
+  send_shortcut('v', terminal_in_foreground(terminal_apps))
+}
+
+/// Typing that stopped partway; `sent` tells whether any keystroke reached the target before it
+/// did, in which case pasting the whole text after it would insert it twice
+#[derive(Debug)]
+pub struct InputError {
+  pub sent: bool,
+  pub message: String,
+}
+
+impl std::fmt::Display for InputError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl From<InputError> for String {
+  fn from(e: InputError) -> Self {
+    e.message
   }
-  #[cfg(not(target_os="macos"))] {
-    use enigo::*;
-    let mut e = Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+}
+
+/// Run blocking keystroke work that sets its `sent` flag once something went out
+async fn run_input(work: impl FnOnce(&mut bool) -> anyhow::Result<()> + Send + 'static) -> Result<(), InputError> {
+  let joined = tokio::task::spawn_blocking(move || {
+    let mut sent = false;
+    work(&mut sent).map_err(|e| InputError { sent, message: e.to_string() })
+  })
+  .await;
+  joined.map_err(|e| InputError { sent: true, message: e.to_string() })?
+}
 
-    // Press and hold Control
-    e.key(Key::Control, Direction::Press).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
-    std::thread::sleep(std::time::Duration::from_millis(20));
+/// Type `text`; `sent` is set once a character went out. The Wayland tools type a whole string
+/// in one process, so they count as all or nothing.
+#[cfg(feature = "native-input")]
+fn type_chars(text: &str, per_char_delay: std::time::Duration, sent: &mut bool) -> anyhow::Result<()> {
+  #[cfg(target_os = "linux")]
+  if let Some(result) = crate::wayland_input::type_text(text, per_char_delay) {
+    *sent |= result.is_ok();
+    return result;
+  }
+  enigo_type(text, |_| per_char_delay, sent)
+}
 
-    // Press V while holding Control
-    e.key(Key::Unicode('v'), Direction::Click).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
-    std::thread::sleep(std::time::Duration::from_millis(20));
+/// Type through enigo, sleeping `pause(ch)` after each character
+#[cfg(feature = "native-input")]
+fn enigo_type(text: &str, mut pause: impl FnMut(char) -> std::time::Duration, sent: &mut bool) -> anyhow::Result<()> {
+  use enigo::*;
+  let mut e = Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  for ch in text.chars() {
+    match ch {
+      // Some targets (terminals, RDP) handle a real Return better than a typed "\n"
+      '\n' => e.key(Key::Return, Direction::Click),
+      '\r' => continue,
+      '\t' => e.key(Key::Tab, Direction::Click),
+      _ => e.text(&ch.to_string()),
+    }
+    .map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+    *sent = true;
+    let delay = pause(ch);
+    if !delay.is_zero() {
+      std::thread::sleep(delay);
+    }
+  }
+  Ok(())
+}
 
-    // Release Control
-    e.key(Key::Control, Direction::Release).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+#[cfg(not(feature = "native-input"))]
+fn type_chars(_text: &str, _per_char_delay: std::time::Duration, _sent: &mut bool) -> anyhow::Result<()> { Err(anyhow::anyhow!("native input not enabled")) }
 
+#[cfg(feature = "native-input")]
+fn type_like_human(text: &str, mut rhythm: Rhythm, sent: &mut bool) -> anyhow::Result<()> {
+  #[cfg(target_os = "linux")]
+  if crate::wayland_input::backend().is_some() {
+    // wtype/ydotool only take a fixed delay, and one process per key is too slow; type a word at a
+    // time at a random speed and pause between words
+    for word in text.split_inclusive(char::is_whitespace) {
+      let last = word.chars().last().unwrap_or(' ');
+      let per_char = rhythm.delay_after('a');
+      crate::wayland_input::type_text(word, per_char).unwrap_or(Ok(()))?;
+      *sent = true;
+      std::thread::sleep(rhythm.delay_after(last));
+    }
     return Ok(());
   }
+  enigo_type(text, |ch| rhythm.delay_after(ch), sent)
 }
 
 #[cfg(not(feature = "native-input"))]
-fn send_paste() -> anyhow::Result<()> { Err(anyhow::anyhow!("native input not enabled")) }
+fn type_like_human(_text: &str, _rhythm: Rhythm, _sent: &mut bool) -> anyhow::Result<()> { Err(anyhow::anyhow!("native input not enabled")) }
+
+#[cfg(feature = "native-input")]
+fn press_key(key: MacroKey) -> anyhow::Result<()> {
+  use enigo::*;
+  #[cfg(target_os = "linux")]
+  if let Some(result) = crate::wayland_input::press_key(key) {
+    return result;
+  }
+  let mut e = Enigo::new(&Settings::default()).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))?;
+  let k = match key {
+    MacroKey::Enter => Key::Return,
+    MacroKey::Tab => Key::Tab,
+    MacroKey::Escape => Key::Escape,
+    MacroKey::Backspace => Key::Backspace,
+    MacroKey::Delete => Key::Delete,
+    MacroKey::Up => Key::UpArrow,
+    MacroKey::Down => Key::DownArrow,
+    MacroKey::Left => Key::LeftArrow,
+    MacroKey::Right => Key::RightArrow,
+    MacroKey::Home => Key::Home,
+    MacroKey::End => Key::End,
+    MacroKey::PageUp => Key::PageUp,
+    MacroKey::PageDown => Key::PageDown,
+    MacroKey::Space => Key::Space,
+  };
+  e.key(k, Direction::Click).map_err(|e| anyhow::anyhow!(format!("{:?}", e)))
+}
+
+#[cfg(not(feature = "native-input"))]
+fn press_key(_key: MacroKey) -> anyhow::Result<()> { Err(anyhow::anyhow!("native input not enabled")) }
+
+/// Replay macro steps into the focused window: text is typed, keys are pressed
+pub async fn play_macro(steps: Vec<Step>, per_char_delay: std::time::Duration) -> Result<(), InputError> {
+  run_input(move |sent| {
+    for step in &steps {
+      match step {
+        Step::Text(text) => type_chars(text, per_char_delay, sent)?,
+        Step::Key(key) => {
+          press_key(*key)?;
+          *sent = true;
+        }
+      }
+      // Let the target react (focus moves on Tab, forms submit on Enter) before the next step
+      std::thread::sleep(std::time::Duration::from_millis(40));
+    }
+    Ok(())
+  })
+  .await
+}
+
+/// Type with randomized delays between `min_ms` and `max_ms` per key and longer pauses at word and
+/// sentence breaks, for web forms that reject synthetic pastes or throttle bursts of input
+pub async fn type_human(text: &str, min_ms: u32, max_ms: u32) -> Result<(), InputError> {
+  let text = text.to_string();
+  let seed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+  let rhythm = Rhythm::new(min_ms, max_ms, seed);
+  run_input(move |sent| type_like_human(&text, rhythm, sent)).await
+}
+
+/// Insert text by simulating keystrokes, for targets that reject Ctrl+V
+/// (terminals, remote desktops, password managers). Leaves the clipboard untouched.
+pub async fn type_text(text: &str, per_char_delay: std::time::Duration) -> Result<(), InputError> {
+  let text = text.to_string();
+  // Typing blocks for the whole string; keep it off the async runtime
+  run_input(move |sent| type_chars(&text, per_char_delay, sent)).await
+}
 
-pub async fn quick_probe_can_paste(app: &AppHandle) -> Result<bool, String> {
+pub async fn probe_focus(app: &AppHandle) -> Result<FocusKind, String> {
+  // Ask the accessibility API what has focus; this never touches the clipboard
+  if focus_probe::available() {
+    // A hung app can stall accessibility queries; don't hold up the hotkey for it
+    let probe = tokio::task::spawn_blocking(focus_probe::focused_element);
+    let kind = match tokio::time::timeout(std::time::Duration::from_millis(750), probe).await {
+      Ok(joined) => joined.map_err(|e| e.to_string())?,
+      Err(_) => {
+        warn!("Focus probe timed out");
+        FocusKind::Unknown
+      }
+    };
+    info!("Focus probe: {:?}", kind);
+    return Ok(kind);
+  }
   // Try writing to clipboard; we avoid actually pasting content into user apps by sending an Undo immediately is not feasible without full simulation.
   let cb = app.clipboard();
   let original = cb.read_text().ok();
   let sentinel = "__DICTATION_HUD_SENTINEL__".to_string();
   cb.write_text(sentinel.clone()).map_err(|e| e.to_string())?;
   // If native-input is not enabled, treat probe as passed (optional check)
-  if let Err(_) = send_paste() {
+  if let Err(_) = send_paste(&[]) {
     if let Some(t) = original { let _ = cb.write_text(t); }
-    return Ok(true);
+    return Ok(FocusKind::Unknown);
   }
-  let ok = true;
   // try to restore clipboard
   if let Some(t) = original { let _ = cb.write_text(t); }
-  Ok(ok)
+  Ok(FocusKind::Unknown)
+}
+
+/// Put text on the clipboard without sending a paste keystroke
+pub fn copy_only(app: &AppHandle, text: &str) -> Result<(), String> {
+  app.clipboard().write_text(text.to_string()).map_err(|e| e.to_string())
+}
+
+/// Clipboard contents saved before a paste so they can be put back afterwards
+enum SavedClipboard {
+  Text(String),
+  Image(tauri::image::Image<'static>),
+  Empty,
+}
+
+fn save_clipboard(app: &AppHandle) -> SavedClipboard {
+  let cb = app.clipboard();
+  if let Ok(t) = cb.read_text() {
+    return SavedClipboard::Text(t);
+  }
+  match cb.read_image() {
+    Ok(img) => SavedClipboard::Image(img.to_owned()),
+    Err(_) => SavedClipboard::Empty,
+  }
 }
 
-pub async fn copy_and_paste(app: &AppHandle, text: &str) -> Result<bool, String> {
+fn restore_clipboard(app: &AppHandle, saved: SavedClipboard) {
+  let cb = app.clipboard();
+  let result = match saved {
+    SavedClipboard::Text(t) => cb.write_text(t),
+    SavedClipboard::Image(img) => cb.write_image(&img),
+    SavedClipboard::Empty => cb.clear(),
+  };
+  if let Err(e) = result {
+    warn!("Failed to restore clipboard: {}", e);
+  }
+}
+
+/// Paste `text` via the clipboard. With `restore_after`, the previous clipboard contents
+/// (text or image) are put back after that delay, unless the user copied something new
+/// in the meantime or the paste keystroke failed (the text is then left for manual paste).
+/// `terminal_apps` extends the built-in list of terminals that paste with Ctrl+Shift+V.
+pub async fn copy_and_paste(app: &AppHandle, text: &str, restore_after: Option<std::time::Duration>, terminal_apps: &[String]) -> Result<bool, String> {
+  let saved = restore_after.map(|_| save_clipboard(app));
   let cb = app.clipboard();
   cb.write_text(text.to_string()).map_err(|e| e.to_string())?;
 
@@ -67,10 +315,161 @@ pub async fn copy_and_paste(app: &AppHandle, text: &str) -> Result<bool, String>
   tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
   // Attempt paste; if it fails (e.g., native input disabled), return false
-  let result = send_paste().is_ok();
+  let result = send_paste(terminal_apps).is_ok();
 
   // Allow the OS to process paste before any subsequent UI actions
   tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+  if let (Some(delay), Some(saved), true) = (restore_after, saved, result) {
+    let app = app.clone();
+    let text = text.to_string();
+    // Restore in the background; the target app may read the clipboard late
+    tauri::async_runtime::spawn(async move {
+      tokio::time::sleep(delay).await;
+      if app.clipboard().read_text().map(|t| t == text).unwrap_or(false) {
+        restore_clipboard(&app, saved);
+        info!("Clipboard restored");
+      }
+    });
+  }
   Ok(result)
 }
 
+/// Copy the focused app's current selection by simulating Ctrl+C (Cmd+C on macOS).
+/// Returns None when nothing was selected. The user's clipboard is restored afterwards.
+/// In terminals Ctrl+Shift+C is used, since Ctrl+C would interrupt the running program.
+pub async fn copy_selection(app: &AppHandle, terminal_apps: &[String]) -> Result<Option<String>, String> {
+  let cb = app.clipboard();
+  let original = cb.read_text().ok();
+  // A sentinel tells "nothing copied" apart from "selection equals old clipboard"
+  let sentinel = "__DICTATION_HUD_SELECTION__".to_string();
+  cb.write_text(sentinel.clone()).map_err(|e| e.to_string())?;
+
+  let result = match send_shortcut('c', terminal_in_foreground(terminal_apps)) {
+    Ok(()) => {
+      let mut copied = None;
+      // Apps update the clipboard asynchronously; poll briefly
+      for _ in 0..12 {
+        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        match cb.read_text() {
+          Ok(t) if t != sentinel => { copied = Some(t); break; }
+          _ => {}
+        }
+      }
+      Ok(copied.filter(|t| !t.trim().is_empty()))
+    }
+    Err(e) => Err(e.to_string()),
+  };
+
+  match original {
+    Some(t) => { let _ = cb.write_text(t); }
+    None => { let _ = cb.write_text(String::new()); }
+  }
+  result
+}
+
+// Streaming insertion: one StreamInserter per dictation, fed segments by the STT relay. The
+// keystrokes go to a single typing task so segments land in order without holding up the relay.
+// Every batch first checks that the dictation's window still has focus; once the user has
+// switched away nothing more is typed, so no other app gets keystrokes or backspaces.
+struct Stream {
+  inserter: StreamInserter,
+  tx: mpsc::UnboundedSender<Vec<Step>>,
+  typer: tauri::async_runtime::JoinHandle<bool>,
+}
+
+static STREAM: Mutex<Option<Stream>> = Mutex::new(None);
+
+/// Type and backspace without the pauses `play_macro` leaves between steps
+fn play_steps(steps: &[Step], per_char_delay: std::time::Duration) -> anyhow::Result<()> {
+  let mut sent = false;
+  for step in steps {
+    match step {
+      Step::Text(text) => type_chars(text, per_char_delay, &mut sent)?,
+      Step::Key(key) => press_key(*key)?,
+    }
+  }
+  Ok(())
+}
+
+/// Type a batch only while window `window` has focus
+fn play_in_window(steps: &[Step], window: u64, per_char_delay: std::time::Duration) -> anyhow::Result<()> {
+  match window_target::foreground_window() {
+    Some(w) if w.id == window => play_steps(steps, per_char_delay),
+    _ => anyhow::bail!("focus left the dictation's window"),
+  }
+}
+
+/// Type the running dictation into `window` (the session window, focused now) as it is
+/// transcribed, replacing any earlier stream
+pub fn begin_stream(window: u64, per_char_delay: std::time::Duration) {
+  let (tx, mut rx) = mpsc::unbounded_channel::<Vec<Step>>();
+  let typer = tauri::async_runtime::spawn(async move {
+    let mut ok = true;
+    while let Some(steps) = rx.recv().await {
+      // After a failed keystroke or a focus change the typed text is unknown; stop rather than
+      // type over it
+      if !ok {
+        continue;
+      }
+      let played = tokio::task::spawn_blocking(move || play_in_window(&steps, window, per_char_delay)).await;
+      if let Err(e) = played.map_err(|e| e.to_string()).and_then(|r| r.map_err(|e| e.to_string())) {
+        warn!("Streaming insertion stopped: {}", e);
+        ok = false;
+      }
+    }
+    ok
+  });
+  info!("Streaming insertion on for this dictation");
+  *STREAM.lock().unwrap() = Some(Stream { inserter: StreamInserter::new(), tx, typer });
+}
+
+pub fn is_streaming() -> bool {
+  STREAM.lock().unwrap().is_some()
+}
+
+/// A transcript segment arrived; type it (or its revision) when a stream is running
+pub fn stream_segment(text: &str, is_final: bool) {
+  let mut guard = STREAM.lock().unwrap();
+  let Some(stream) = guard.as_mut() else { return };
+  let steps = stream.inserter.update(text, is_final);
+  if !steps.is_empty() {
+    let _ = stream.tx.send(steps);
+  }
+}
+
+/// End the stream by turning what was typed into `text`, the dictation's final text. Errors when
+/// a keystroke failed or focus moved along the way, leaving the typed text incomplete.
+pub async fn finish_stream(text: &str) -> Result<(), String> {
+  let Some(mut stream) = STREAM.lock().unwrap().take() else { return Err("No streaming insertion running".into()) };
+  let steps = stream.inserter.finish(text);
+  if !steps.is_empty() {
+    let _ = stream.tx.send(steps);
+  }
+  drop(stream.tx);
+  match stream.typer.await {
+    Ok(true) => Ok(()),
+    Ok(false) => Err("typing stopped during streaming insertion".into()),
+    Err(e) => Err(e.to_string()),
+  }
+}
+
+/// The dictation was abandoned: backspace over what the stream typed, while its window still has
+/// focus. When that isn't possible the HUD says the partial text was left in place.
+pub fn end_stream(app: &AppHandle) {
+  let Some(mut stream) = STREAM.lock().unwrap().take() else { return };
+  info!("Streaming insertion ended without a final text");
+  if stream.inserter.typed().is_empty() {
+    return;
+  }
+  let steps = stream.inserter.finish("");
+  let _ = stream.tx.send(steps);
+  drop(stream.tx);
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move {
+    if !matches!(stream.typer.await, Ok(true)) {
+      warn!("Could not erase the partially typed dictation");
+      emit_hud_event(&app, HudEvent::badge("Dictation canceled: its partial text is still in the window"));
+    }
+  });
+}
@@ -0,0 +1,171 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tauri_plugin_store::StoreExt;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::hud_event::{emit_hud_event, HudEvent};
+
+// "Training wheels" for new users: the first few auto-pastes wait for Enter (Esc copies only),
+// so an early dictation doesn't land in the wrong window. Enter and Esc are registered as global
+// shortcuts only while a confirmation is pending, which also keeps the keypress out of the
+// target app. Once enough pastes were confirmed, pasting is instant.
+//
+// Preview mode (`confirm_before_paste`) asks before every paste and shows the final text: Enter
+// pastes it and Esc discards it. Editing starts in the HUD window (E there, or its Edit button),
+// which releases the keys and answers with `answer_paste_confirmation` when done. E is never a
+// global shortcut: it would swallow every "e" typed while a preview is up.
+
+/// How long an edit in the HUD may take before the text is kept on the clipboard instead
+const EDIT_TIMEOUT: Duration = Duration::from_secs(300);
+
+const K_CONFIRMED: &str = "confirmed_pastes";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+  Paste,
+  Cancel,
+  Timeout,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ConfirmStatus {
+  /// The next auto-paste waits for a keypress
+  pub required: bool,
+  pub confirmed: u32,
+  pub limit: u32,
+  /// Every paste is confirmed with its final text shown, and can be edited first
+  pub preview: bool,
+}
+
+/// An answer to a pending confirmation, from a key or from the HUD
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Reply {
+  Paste,
+  Cancel,
+  /// Preview mode: let go of the keys while the text is edited in the HUD
+  Edit,
+}
+
+static PENDING: Mutex<Option<mpsc::UnboundedSender<Reply>>> = Mutex::new(None);
+/// The last preview was edited in the HUD, which has had focus since
+static EDITED: AtomicBool = AtomicBool::new(false);
+
+pub fn confirmed_count(app: &AppHandle) -> u32 {
+  let Ok(store) = app.store("prefs.json") else { return 0 };
+  store.get(K_CONFIRMED).and_then(|v| v.as_u64()).unwrap_or(0) as u32
+}
+
+fn set_confirmed_count(app: &AppHandle, count: u32) {
+  if let Ok(store) = app.store("prefs.json") {
+    store.set(K_CONFIRMED, serde_json::json!(count));
+    let _ = store.save();
+  }
+}
+
+pub fn status(app: &AppHandle, limit: u32, preview: bool) -> ConfirmStatus {
+  let confirmed = confirmed_count(app);
+  ConfirmStatus { required: preview || confirmed < limit, confirmed, limit, preview }
+}
+
+/// A confirmation is waiting for an answer
+pub fn is_pending() -> bool {
+  PENDING.lock().unwrap().is_some()
+}
+
+fn send(reply: Reply) {
+  if let Some(tx) = PENDING.lock().unwrap().as_ref() {
+    let _ = tx.send(reply);
+  }
+}
+
+/// Answer the pending confirmation from the HUD
+pub fn resolve(reply: Reply) -> Result<(), String> {
+  if !is_pending() {
+    return Err("No paste is waiting for confirmation".into());
+  }
+  send(reply);
+  Ok(())
+}
+
+/// Whether the preview just answered was edited in the HUD; clears it
+pub fn take_edited() -> bool {
+  EDITED.swap(false, Ordering::SeqCst)
+}
+
+/// Wait for Enter (paste) or Esc (copy only, or discard in preview mode), or the HUD's answer.
+/// Confirmed pastes count towards the limit. If the keys can't be grabbed, the paste goes ahead
+/// rather than blocking the user, or in preview mode the HUD takes the answer instead.
+pub async fn ask(app: &AppHandle, timeout: Duration, preview: bool) -> Result<Decision, String> {
+  let gs = app.global_shortcut();
+  let keys: Vec<(Shortcut, Reply)> = vec![
+    ("Enter".parse::<Shortcut>().map_err(|e| e.to_string())?, Reply::Paste),
+    ("Escape".parse::<Shortcut>().map_err(|e| e.to_string())?, Reply::Cancel),
+  ];
+
+  let (tx, mut rx) = mpsc::unbounded_channel();
+  *PENDING.lock().unwrap() = Some(tx);
+  EDITED.store(false, Ordering::SeqCst);
+  let mut registered = Vec::new();
+  for &(shortcut, reply) in &keys {
+    let result = gs.on_shortcut(shortcut, move |_app, _shortcut, event| {
+      if event.state() == ShortcutState::Pressed {
+        send(reply);
+      }
+    });
+    match result {
+      Ok(()) => registered.push(shortcut),
+      Err(e) if preview => {
+        warn!("Could not grab {:?} for the paste preview ({}), editing in the HUD", shortcut, e);
+        send(Reply::Edit);
+        break;
+      }
+      Err(e) => {
+        warn!("Could not grab {:?} for paste confirmation ({}), pasting without it", shortcut, e);
+        send(Reply::Paste);
+        break;
+      }
+    }
+  }
+
+  let mut wait = timeout;
+  let decision = loop {
+    match tokio::time::timeout(wait, rx.recv()).await {
+      Ok(Some(Reply::Paste)) => break Decision::Paste,
+      Ok(Some(Reply::Cancel)) => break Decision::Cancel,
+      Ok(Some(Reply::Edit)) if preview => {
+        // Enter and Esc are typed into the HUD while editing
+        for shortcut in registered.drain(..) {
+          let _ = gs.unregister(shortcut);
+        }
+        info!("Editing the text before pasting");
+        EDITED.store(true, Ordering::SeqCst);
+        emit_hud_event(app, HudEvent::PasteEdit);
+        wait = EDIT_TIMEOUT;
+      }
+      Ok(Some(Reply::Edit)) => {}
+      Ok(None) => break Decision::Cancel,
+      Err(_) => break Decision::Timeout,
+    }
+  };
+  PENDING.lock().unwrap().take();
+  for shortcut in registered {
+    let _ = gs.unregister(shortcut);
+  }
+
+  if decision == Decision::Paste {
+    let count = confirmed_count(app) + 1;
+    set_confirmed_count(app, count);
+    info!("Paste confirmed ({} so far{})", count, if EDITED.load(Ordering::SeqCst) { ", edited" } else { "" });
+  } else {
+    info!("Paste not confirmed ({:?})", decision);
+  }
+  Ok(decision)
+}
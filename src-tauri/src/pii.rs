@@ -0,0 +1,175 @@
+// Masking of sensitive text in a dictation before it is pasted: profanity, email addresses, phone
+// numbers and card numbers. Which categories apply is a pref, overridable per app (always redact in
+// Slack, never in a private notes app). Deepgram can also do part of it itself, so its connection
+// asks for the matching redaction flags.
+
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// Categories accepted in the `redact` pref
+pub const CATEGORIES: &[&str] = &["profanity", "email", "phone", "card"];
+
+/// Masked with their first letter kept ("f***")
+const PROFANITY: &[&str] = &[
+    "fuck", "fucking", "fucked", "shit", "shitty", "bullshit", "bitch", "bastard", "asshole", "dick", "crap", "damn", "piss", "cunt",
+    "motherfucker",
+];
+
+fn email_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap())
+}
+
+/// Runs of 13-19 digits, optionally grouped by spaces or dashes
+fn card_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").unwrap())
+}
+
+/// An optional country code and area code, then digit groups
+fn phone_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,3}\b").unwrap())
+}
+
+/// Dates share the shape of short phone numbers: "2024-05-31", "31.05.2024", "5/31/24", with one
+/// separator throughout and a month (1-12) in first or second place
+fn is_date(s: &str) -> bool {
+    let Some(sep) = s.chars().find(|c| matches!(c, '-' | '.' | '/')) else { return false };
+    let parts: Vec<&str> = s.split(sep).collect();
+    if parts.len() != 3 || parts.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit())) {
+        return false;
+    }
+    let n = |i: usize| parts[i].parse::<u32>().unwrap_or(0);
+    let (day, month) = (1..=31, 1..=12);
+    match (parts[0].len(), parts[1].len(), parts[2].len()) {
+        (4, 1..=2, 1..=2) => month.contains(&n(1)) && day.contains(&n(2)),
+        (1..=2, 1..=2, 2 | 4) => {
+            (day.contains(&n(0)) && month.contains(&n(1))) || (month.contains(&n(0)) && day.contains(&n(1)))
+        }
+        _ => false,
+    }
+}
+
+fn word_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b[\p{L}']+\b").unwrap())
+}
+
+fn digits(s: &str) -> Vec<u32> {
+    s.chars().filter_map(|c| c.to_digit(10)).collect()
+}
+
+/// Card numbers pass the Luhn check, which tells them apart from other long numbers
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+pub fn validate(categories: &[String]) -> Result<(), String> {
+    match categories.iter().find(|c| !CATEGORIES.contains(&c.as_str())) {
+        Some(unknown) => Err(format!("Unknown redaction category: {} (use {})", unknown, CATEGORIES.join(", "))),
+        None => Ok(()),
+    }
+}
+
+/// `text` with the given categories masked
+pub fn redact(text: &str, categories: &[String]) -> String {
+    let on = |c: &str| categories.iter().any(|x| x == c);
+    let mut out = text.to_string();
+    if on("email") {
+        out = email_re().replace_all(&out, "[email]").into_owned();
+    }
+    // Before phone numbers, which would otherwise match parts of a card number
+    if on("card") {
+        out = card_re()
+            .replace_all(&out, |c: &regex::Captures| if luhn(&digits(&c[0])) { "[card]".to_string() } else { c[0].to_string() })
+            .into_owned();
+    }
+    if on("phone") {
+        out = phone_re()
+            .replace_all(&out, |c: &regex::Captures| {
+                let n = digits(&c[0]).len();
+                if (7..=15).contains(&n) && !is_date(&c[0]) { "[phone]".to_string() } else { c[0].to_string() }
+            })
+            .into_owned();
+    }
+    if on("profanity") {
+        out = word_re()
+            .replace_all(&out, |c: &regex::Captures| {
+                let word = &c[0];
+                if PROFANITY.contains(&word.to_lowercase().as_str()) {
+                    let mut chars = word.chars();
+                    let first = chars.next().map(String::from).unwrap_or_default();
+                    format!("{}{}", first, "*".repeat(chars.count()))
+                } else {
+                    word.to_string()
+                }
+            })
+            .into_owned();
+    }
+    out
+}
+
+/// Deepgram flags doing the same on the provider side (`&...`, empty for none)
+pub fn deepgram_params(categories: &[String]) -> String {
+    let mut params = String::new();
+    if categories.iter().any(|c| c == "profanity") {
+        params.push_str("&profanity_filter=true");
+    }
+    if categories.iter().any(|c| c == "card") {
+        params.push_str("&redact=pci");
+    }
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Vec<String> {
+        CATEGORIES.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_redact_categories() {
+        let text = "Mail jane.doe@example.com or call +1 (415) 555-0132, card 4111 1111 1111 1111. Shit, that's it.";
+        assert_eq!(redact(text, &all()), "Mail [email] or call [phone], card [card]. S***, that's it.");
+        assert_eq!(redact(text, &["email".to_string()]), text.replace("jane.doe@example.com", "[email]"));
+        assert_eq!(redact(text, &[]), text);
+    }
+
+    #[test]
+    fn test_leaves_ordinary_numbers() {
+        let text = "We shipped 250 units in 2024, order 1234 5678 9012 3456 is pending";
+        // Not a valid card number, and years or counts aren't phone numbers
+        assert_eq!(redact(text, &["card".to_string()]), text);
+        assert_eq!(redact("Version 2.1 ships on 12.05", &all()), "Version 2.1 ships on 12.05");
+        assert_eq!(redact("Scunthorpe United", &["profanity".to_string()]), "Scunthorpe United");
+    }
+
+    #[test]
+    fn test_leaves_dates() {
+        let phone = vec!["phone".to_string()];
+        for date in ["2024-05-31", "31.05.2024", "12.05.2024", "5/31/2024", "31/05/24"] {
+            let text = format!("Due on {}, call 555-0132-44", date);
+            assert_eq!(redact(&text, &phone), format!("Due on {}, call [phone]", date));
+        }
+        // Not a date: no month in either place, or mixed separators
+        assert_eq!(redact("call 44-55-6677", &phone), "call [phone]");
+        assert_eq!(redact("call 2024-55.31", &phone), "call [phone]");
+    }
+
+    #[test]
+    fn test_validate_and_params() {
+        assert!(validate(&["email".to_string(), "card".to_string()]).is_ok());
+        assert!(validate(&["ssn".to_string()]).is_err());
+        assert_eq!(deepgram_params(&all()), "&profanity_filter=true&redact=pci");
+        assert_eq!(deepgram_params(&["email".to_string()]), "");
+    }
+}
@@ -0,0 +1,186 @@
+// The post-processing pipeline a dictation goes through between the final transcript and the paste,
+// as an ordered list of named stages. Each run can keep a trace of what every stage turned the text
+// into, which `test_pipeline` returns so a surprising result can be pinned on the stage causing it.
+// The user's own regex find/replace rules are one of the stages.
+
+use regex::{Regex, RegexBuilder};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    /// Spoken emoji names, when the emoji pref is on
+    Emoji,
+    /// Spoken punctuation and symbol mappings
+    Symbols,
+    /// "..., correction: <phrase>"
+    Correction,
+    /// Letter-by-letter spelling
+    Spell,
+    /// Spoken casing commands, in code mode
+    Code,
+    /// Spoken numbers, amounts and dates, per the numbers_mode pref
+    Numbers,
+    /// Snippets and replacement rules from the enabled packs
+    Packs,
+    /// The model's output (or its stand-in in demo mode and while the provider is paused)
+    Refine,
+    /// Model output checked for refusals and rewrites
+    Validate,
+    /// The enabled WebAssembly plugins
+    Plugins,
+    /// The user's Rhai script
+    Script,
+    /// Quotes, ellipses and em dashes in the typography pref's style
+    Typography,
+    /// The user's regex rules
+    Rules,
+    /// Masking of the `redact` categories
+    Redact,
+}
+
+impl Stage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Stage::Emoji => "emoji",
+            Stage::Symbols => "symbols",
+            Stage::Correction => "correction",
+            Stage::Spell => "spell",
+            Stage::Code => "code",
+            Stage::Numbers => "numbers",
+            Stage::Packs => "packs",
+            Stage::Refine => "refine",
+            Stage::Validate => "validate",
+            Stage::Plugins => "plugins",
+            Stage::Script => "script",
+            Stage::Typography => "typography",
+            Stage::Rules => "rules",
+            Stage::Redact => "redact",
+        }
+    }
+}
+
+/// Stages run on the transcript before it goes to the model, in order. Emoji names go before
+/// symbols, which would otherwise take the "star" out of "star emoji"
+pub const LOCAL_STAGES: &[Stage] = &[Stage::Emoji, Stage::Symbols, Stage::Correction, Stage::Spell, Stage::Code, Stage::Numbers, Stage::Packs];
+
+/// A find/replace rule; `replace` can refer to groups as `$1` or `${name}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RegexRule {
+    pub find: String,
+    #[serde(default)]
+    pub replace: String,
+    #[serde(default)]
+    pub ignore_case: bool,
+}
+
+impl RegexRule {
+    fn compile(&self) -> Result<Regex, String> {
+        if self.find.is_empty() {
+            return Err("A rule needs a pattern to find".into());
+        }
+        RegexBuilder::new(&self.find)
+            .case_insensitive(self.ignore_case)
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| format!("Invalid pattern {:?}: {}", self.find, e))
+    }
+}
+
+pub fn validate_rules(rules: &[RegexRule]) -> Result<(), String> {
+    rules.iter().try_for_each(|r| r.compile().map(|_| ()))
+}
+
+/// `text` with each rule applied in turn; a rule that doesn't compile is skipped
+pub fn apply_rules(text: &str, rules: &[RegexRule]) -> String {
+    let mut out = text.to_string();
+    for rule in rules {
+        if let Ok(re) = rule.compile() {
+            out = re.replace_all(&out, rule.replace.as_str()).into_owned();
+        }
+    }
+    out
+}
+
+/// What one stage turned the text into
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StageOutput {
+    pub stage: Stage,
+    pub text: String,
+    /// Whether the stage changed the text it was given
+    pub changed: bool,
+}
+
+/// Outputs of the stages that ran, in order
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub struct Trace {
+    pub input: String,
+    pub stages: Vec<StageOutput>,
+}
+
+impl Trace {
+    pub fn new(input: &str) -> Self {
+        Trace { input: input.to_string(), stages: Vec::new() }
+    }
+
+    /// Text the next stage gets
+    pub fn current(&self) -> &str {
+        self.stages.last().map(|s| s.text.as_str()).unwrap_or(&self.input)
+    }
+
+    /// Note a stage's output; true when it changed the text
+    pub fn record(&mut self, stage: Stage, text: &str) -> bool {
+        let changed = text != self.current();
+        self.stages.push(StageOutput { stage, text: text.to_string(), changed });
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(find: &str, replace: &str, ignore_case: bool) -> RegexRule {
+        RegexRule { find: find.into(), replace: replace.into(), ignore_case }
+    }
+
+    #[test]
+    fn test_apply_rules_in_order() {
+        let rules = vec![
+            rule(r"\bjira (\d+)\b", "PROJ-$1", true),
+            rule(r"\bPROJ-(\d+)\b", "[PROJ-$1]", false),
+            rule("(", "never", false),
+        ];
+        assert_eq!(apply_rules("Fixed in Jira 42 today", &rules), "Fixed in [PROJ-42] today");
+        assert_eq!(apply_rules("Nothing here", &rules), "Nothing here");
+        assert!(validate_rules(&rules[..2]).is_ok());
+        assert!(validate_rules(&rules).is_err());
+        assert!(validate_rules(&[rule("", "x", false)]).is_err());
+    }
+
+    #[test]
+    fn test_local_stage_order() {
+        let mut trace = Trace::new("good job star emoji, 2 star 3");
+        for &stage in LOCAL_STAGES {
+            let output = match stage {
+                Stage::Emoji => crate::symbols::replace_emoji(trace.current(), &[]),
+                Stage::Symbols => crate::symbols::replace_symbols(trace.current()),
+                _ => continue,
+            };
+            trace.record(stage, &output);
+        }
+        assert_eq!(trace.current(), "good job ⭐, 2* 3");
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut trace = Trace::new("hello comma world");
+        assert!(trace.record(Stage::Symbols, "hello, world"));
+        assert!(!trace.record(Stage::Rules, "hello, world"));
+        assert_eq!(trace.current(), "hello, world");
+        let json = serde_json::to_value(&trace).unwrap();
+        assert_eq!(json["stages"][1]["stage"], "rules");
+        assert_eq!(json["stages"][1]["changed"], false);
+    }
+}
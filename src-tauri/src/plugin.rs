@@ -0,0 +1,212 @@
+// WebAssembly plugins: third-party extensions (Jira links, CRM formatting, ...) shipped as `.wasm`
+// files in the plugins folder, without forking the app. A plugin is a core WebAssembly module with
+// no imports, so it can't reach files, the network or the host beyond the ABI below, and every call
+// runs on a fuel budget and a memory cap so a broken plugin fails instead of hanging dictation.
+//
+// ABI version 1; a plugin exports `memory` and any of:
+// - `abi_version() -> i32`: must return 1 when present
+// - `alloc(len: i32) -> i32`: a buffer of `len` bytes for the host to write input into
+// - `process_text(ptr: i32, len: i32) -> i64`: gets the dictation's UTF-8 text, returns the new
+//   text as `(ptr << 32) | len`, or -1 to keep it (needs `alloc`)
+// - `on_dictation_start()` and `on_dictation_stop()`: recording started, and ended in any way
+//   (stopped, canceled or failed); a pause in between is not a stop
+//
+// An instance lives as long as its plugin stays loaded, so a plugin can keep state between calls.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use wasmtime::{Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+pub const ABI_VERSION: i32 = 1;
+/// File extension of plugins in the plugins folder
+pub const EXTENSION: &str = "wasm";
+
+/// Instructions one call may run, roughly tens of milliseconds
+const FUEL_PER_CALL: u64 = 50_000_000;
+const MAX_MEMORY_BYTES: usize = 32 * 1024 * 1024;
+/// Longest text a plugin may hand back
+const MAX_OUTPUT_BYTES: usize = 1024 * 1024;
+
+/// What the plugins page shows for a plugin file
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PluginInfo {
+    /// File name without the extension; what the `plugins` pref lists
+    pub name: String,
+    pub enabled: bool,
+    /// Exported hooks: "process_text", "on_dictation_start", "on_dictation_stop"
+    pub hooks: Vec<String>,
+    /// Why the plugin couldn't be loaded
+    pub error: Option<String>,
+}
+
+/// The engine plugins are compiled with; fuel metering bounds every call
+pub fn engine() -> Result<Engine, String> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config).map_err(|e| e.to_string())
+}
+
+pub struct Plugin {
+    pub name: String,
+    store: Store<StoreLimits>,
+    memory: Memory,
+    alloc: Option<TypedFunc<i32, i32>>,
+    process_text: Option<TypedFunc<(i32, i32), i64>>,
+    on_start: Option<TypedFunc<(), ()>>,
+    on_stop: Option<TypedFunc<(), ()>>,
+}
+
+impl Plugin {
+    /// Compile and instantiate a plugin from its `.wasm` (or `.wat`) bytes
+    pub fn load(engine: &Engine, name: &str, bytes: &[u8]) -> Result<Plugin, String> {
+        let module = Module::new(engine, bytes).map_err(|e| format!("Not a valid plugin: {}", e))?;
+        if let Some(import) = module.imports().next() {
+            return Err(format!("Plugins can't import anything ({}::{})", import.module(), import.name()));
+        }
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).instances(1).build();
+        let mut store = Store::new(engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())?;
+        let instance = Linker::new(engine).instantiate(&mut store, &module).map_err(|e| format!("Plugin failed to start: {}", e))?;
+        let memory = instance.get_memory(&mut store, "memory").ok_or("Plugin exports no memory")?;
+        if let Ok(version) = instance.get_typed_func::<(), i32>(&mut store, "abi_version") {
+            let version = version.call(&mut store, ()).map_err(|e| e.to_string())?;
+            if version != ABI_VERSION {
+                return Err(format!("Plugin is for ABI version {}, this app speaks {}", version, ABI_VERSION));
+            }
+        }
+        let alloc = typed(&instance, &mut store, "alloc")?;
+        let process_text = typed(&instance, &mut store, "process_text")?;
+        if process_text.is_some() && alloc.is_none() {
+            return Err("Plugin exports process_text without alloc".into());
+        }
+        Ok(Plugin {
+            name: name.to_string(),
+            memory,
+            alloc,
+            process_text,
+            on_start: typed(&instance, &mut store, "on_dictation_start")?,
+            on_stop: typed(&instance, &mut store, "on_dictation_stop")?,
+            store,
+        })
+    }
+
+    pub fn hooks(&self) -> Vec<String> {
+        [
+            ("process_text", self.process_text.is_some()),
+            ("on_dictation_start", self.on_start.is_some()),
+            ("on_dictation_stop", self.on_stop.is_some()),
+        ]
+        .iter()
+        .filter(|(_, present)| *present)
+        .map(|(name, _)| name.to_string())
+        .collect()
+    }
+
+    fn refuel(&mut self) -> Result<(), String> {
+        self.store.set_fuel(FUEL_PER_CALL).map_err(|e| e.to_string())
+    }
+
+    /// The plugin's version of `text`; None when it keeps the text or has no `process_text`
+    pub fn process_text(&mut self, text: &str) -> Result<Option<String>, String> {
+        let (Some(alloc), Some(process)) = (self.alloc.clone(), self.process_text.clone()) else {
+            return Ok(None);
+        };
+        self.refuel()?;
+        let len = i32::try_from(text.len()).map_err(|_| "Text too long for a plugin")?;
+        let ptr = alloc.call(&mut self.store, len).map_err(|e| format!("{}: alloc failed: {}", self.name, e))?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, text.as_bytes())
+            .map_err(|_| format!("{}: alloc returned a buffer outside its memory", self.name))?;
+        let packed = process.call(&mut self.store, (ptr, len)).map_err(|e| format!("{}: process_text failed: {}", self.name, e))?;
+        if packed < 0 {
+            return Ok(None);
+        }
+        let (out_ptr, out_len) = ((packed >> 32) as u32 as usize, (packed & 0xffff_ffff) as usize);
+        if out_len > MAX_OUTPUT_BYTES {
+            return Err(format!("{}: returned {} bytes, more than the {} allowed", self.name, out_len, MAX_OUTPUT_BYTES));
+        }
+        let mut out = vec![0u8; out_len];
+        self.memory
+            .read(&self.store, out_ptr, &mut out)
+            .map_err(|_| format!("{}: returned text outside its memory", self.name))?;
+        String::from_utf8(out).map(Some).map_err(|_| format!("{}: returned text that isn't UTF-8", self.name))
+    }
+
+    pub fn on_dictation_start(&mut self) -> Result<(), String> {
+        self.call_hook(self.on_start.clone(), "on_dictation_start")
+    }
+
+    pub fn on_dictation_stop(&mut self) -> Result<(), String> {
+        self.call_hook(self.on_stop.clone(), "on_dictation_stop")
+    }
+
+    fn call_hook(&mut self, hook: Option<TypedFunc<(), ()>>, name: &str) -> Result<(), String> {
+        let Some(hook) = hook else { return Ok(()) };
+        self.refuel()?;
+        hook.call(&mut self.store, ()).map_err(|e| format!("{}: {} failed: {}", self.name, name, e))
+    }
+}
+
+/// An export with the given signature, None when the plugin doesn't have it
+fn typed<P: wasmtime::WasmParams, R: wasmtime::WasmResults>(
+    instance: &Instance,
+    store: &mut Store<StoreLimits>,
+    name: &str,
+) -> Result<Option<TypedFunc<P, R>>, String> {
+    match instance.get_func(&mut *store, name) {
+        None => Ok(None),
+        Some(func) => func.typed(&*store).map(Some).map_err(|e| format!("Export {} has the wrong signature: {}", name, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Upper-cases ASCII letters in place and counts dictations in a global
+    const UPPER: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (global $starts (mut i32) (i32.const 0))
+          (func (export "abi_version") (result i32) (i32.const 1))
+          (func (export "alloc") (param $len i32) (result i32) (i32.const 1024))
+          (func (export "on_dictation_start") (global.set $starts (i32.add (global.get $starts) (i32.const 1))))
+          (func (export "starts") (result i32) (global.get $starts))
+          (func (export "process_text") (param $ptr i32) (param $len i32) (result i64)
+            (local $i i32) (local $c i32)
+            (block $done
+              (loop $next
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $c (i32.load8_u (i32.add (local.get $ptr) (local.get $i))))
+                (if (i32.and (i32.ge_u (local.get $c) (i32.const 97)) (i32.le_u (local.get $c) (i32.const 122)))
+                  (then (i32.store8 (i32.add (local.get $ptr) (local.get $i)) (i32.sub (local.get $c) (i32.const 32)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $next)))
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len)))))
+    "#;
+
+    #[test]
+    fn test_process_text_and_hooks() {
+        let engine = engine().unwrap();
+        let mut plugin = Plugin::load(&engine, "upper", UPPER.as_bytes()).unwrap();
+        assert_eq!(plugin.hooks(), vec!["process_text", "on_dictation_start"]);
+        assert_eq!(plugin.process_text("ship it, ok?").unwrap().as_deref(), Some("SHIP IT, OK?"));
+        plugin.on_dictation_start().unwrap();
+        plugin.on_dictation_stop().unwrap();
+    }
+
+    #[test]
+    fn test_rejects_unsafe_plugins() {
+        let engine = engine().unwrap();
+        let imports = r#"(module (import "env" "open" (func)) (memory (export "memory") 1))"#;
+        assert!(Plugin::load(&engine, "x", imports.as_bytes()).err().unwrap().contains("import"));
+        let old = r#"(module (memory (export "memory") 1) (func (export "abi_version") (result i32) (i32.const 0)))"#;
+        assert!(Plugin::load(&engine, "x", old.as_bytes()).err().unwrap().contains("ABI"));
+        let spin = r#"(module (memory (export "memory") 1) (func (export "on_dictation_stop") (loop $l (br $l))))"#;
+        let mut plugin = Plugin::load(&engine, "spin", spin.as_bytes()).unwrap();
+        assert!(plugin.on_dictation_stop().is_err());
+        // Still usable after running out of fuel
+        assert!(plugin.on_dictation_start().is_ok());
+    }
+}
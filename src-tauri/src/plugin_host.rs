@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex, OnceLock};
+use std::time::SystemTime;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::plugin::{self, Plugin, PluginInfo};
+
+// Runs the WebAssembly plugins (plugin.rs) found in the app data `plugins` folder and enabled in
+// the `plugins` pref. Loaded instances are kept between dictations, so plugins keep their state,
+// and a plugin is reloaded when its file changes. The dictation start/stop hooks run in order on
+// a thread of their own, so a slow plugin doesn't hold up a state change.
+
+const PLUGINS_DIR: &str = "plugins";
+
+struct Slot {
+  name: String,
+  modified: Option<SystemTime>,
+  plugin: Result<Plugin, String>,
+}
+
+static LOADED: Mutex<Vec<Slot>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Hook {
+  Start,
+  Stop,
+}
+
+static HOOKS: OnceLock<mpsc::Sender<Hook>> = OnceLock::new();
+
+fn engine() -> Result<&'static wasmtime::Engine, String> {
+  static ENGINE: OnceLock<Result<wasmtime::Engine, String>> = OnceLock::new();
+  ENGINE.get_or_init(plugin::engine).as_ref().map_err(|e| e.clone())
+}
+
+pub fn plugins_dir(app: &AppHandle) -> Result<PathBuf, String> {
+  Ok(app.path().app_data_dir().map_err(|e| e.to_string())?.join(PLUGINS_DIR))
+}
+
+/// Names of the enabled plugins, from the behavior prefs
+fn enabled(app: &AppHandle) -> Vec<String> {
+  let Some(behavior) = app.store("prefs.json").ok().and_then(|s| s.get("behavior")) else { return Vec::new() };
+  behavior
+    .get("plugins")
+    .and_then(|v| v.as_array())
+    .map(|names| names.iter().filter_map(|n| n.as_str()).map(str::to_string).collect())
+    .unwrap_or_default()
+}
+
+/// Plugin files in `dir` as (name, path, modified), by name
+fn files(dir: &Path) -> Vec<(String, PathBuf, Option<SystemTime>)> {
+  let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+  let mut files: Vec<_> = entries
+    .filter_map(|e| e.ok())
+    .map(|e| e.path())
+    .filter(|p| p.extension().and_then(|x| x.to_str()) == Some(plugin::EXTENSION))
+    .filter_map(|p| {
+      let name = p.file_stem()?.to_str()?.to_string();
+      let modified = std::fs::metadata(&p).and_then(|m| m.modified()).ok();
+      Some((name, p, modified))
+    })
+    .collect();
+  files.sort_by(|a, b| a.0.cmp(&b.0));
+  files
+}
+
+fn load(name: &str, path: &Path) -> Result<Plugin, String> {
+  let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+  let plugin = Plugin::load(engine()?, name, &bytes)?;
+  info!("Loaded plugin {} ({})", name, plugin.hooks().join(", "));
+  Ok(plugin)
+}
+
+/// Bring the loaded plugins in line with the enabled list and the files on disk, then run `f` on them
+fn with_plugins<T>(app: &AppHandle, f: impl FnOnce(&mut Vec<Slot>) -> T) -> T {
+  let enabled = enabled(app);
+  let wanted: Vec<_> = match plugins_dir(app) {
+    Ok(dir) => files(&dir).into_iter().filter(|(name, _, _)| enabled.contains(name)).collect(),
+    Err(_) => Vec::new(),
+  };
+  let mut loaded = LOADED.lock().unwrap();
+  let mut previous = std::mem::take(&mut *loaded);
+  for (name, path, modified) in wanted {
+    let slot = match previous.iter().position(|s| s.name == name && s.modified == modified) {
+      Some(i) => previous.swap_remove(i),
+      None => {
+        let plugin = load(&name, &path);
+        if let Err(e) = &plugin {
+          warn!("Plugin {} not loaded: {}", name, e);
+        }
+        Slot { name, modified, plugin }
+      }
+    };
+    loaded.push(slot);
+  }
+  f(&mut loaded)
+}
+
+/// `text` after every enabled plugin's `process_text`, in name order, and the errors of those that
+/// failed (their output is skipped)
+pub fn process_text(app: &AppHandle, text: &str) -> (String, Vec<String>) {
+  with_plugins(app, |slots| {
+    let mut out = text.to_string();
+    let mut errors = Vec::new();
+    for plugin in slots.iter_mut().filter_map(|s| s.plugin.as_mut().ok()) {
+      match plugin.process_text(&out) {
+        Ok(Some(processed)) => out = processed,
+        Ok(None) => {}
+        Err(e) => {
+          warn!("Plugin error: {}", e);
+          errors.push(e);
+        }
+      }
+    }
+    (out, errors)
+  })
+}
+
+fn run_hook(app: &AppHandle, hook: Hook) {
+  with_plugins(app, |slots| {
+    for plugin in slots.iter_mut().filter_map(|s| s.plugin.as_mut().ok()) {
+      let result = match hook {
+        Hook::Start => plugin.on_dictation_start(),
+        Hook::Stop => plugin.on_dictation_stop(),
+      };
+      if let Err(e) = result {
+        warn!("Plugin error: {}", e);
+      }
+    }
+  })
+}
+
+/// Start the thread the dictation hooks run on
+pub fn init(app: AppHandle) {
+  let (tx, rx) = mpsc::channel();
+  if HOOKS.set(tx).is_err() {
+    return;
+  }
+  let spawned = std::thread::Builder::new().name("plugin-hooks".into()).spawn(move || {
+    for hook in rx {
+      run_hook(&app, hook);
+    }
+  });
+  if let Err(e) = spawned {
+    warn!("Could not start the plugin hook thread: {}", e);
+  }
+}
+
+/// A dictation is under way while recording or paused
+fn is_dictating(state: &str) -> bool {
+  matches!(state, "recording" | "paused")
+}
+
+/// `on_dictation_start` when a dictation gets under way, `on_dictation_stop` on any way out of
+/// one: stopping normally, a cancel straight to inactive, or an error
+fn hook(from: &str, to: &str) -> Option<Hook> {
+  match (is_dictating(from), is_dictating(to)) {
+    (false, true) => Some(Hook::Start),
+    (true, false) => Some(Hook::Stop),
+    _ => None,
+  }
+}
+
+/// Queue the hook for a change of the dictation state (`DictationState::name`) without waiting for it
+pub fn state_changed(from: &str, to: &str) {
+  if let (Some(hook), Some(hooks)) = (hook(from, to), HOOKS.get()) {
+    let _ = hooks.send(hook);
+  }
+}
+
+/// Every plugin file, enabled or not, with its hooks or load error
+pub fn list(app: &AppHandle) -> Result<Vec<PluginInfo>, String> {
+  let dir = plugins_dir(app)?;
+  with_plugins(app, |slots| {
+    Ok(
+      files(&dir)
+        .into_iter()
+        .map(|(name, path, _)| {
+          // Disabled plugins are loaded just to be described
+          let loaded = slots.iter().find(|s| s.name == name).map(|s| s.plugin.as_ref().map(|p| p.hooks()).map_err(|e| e.clone()));
+          let enabled = loaded.is_some();
+          let described = loaded.unwrap_or_else(|| load(&name, &path).map(|p| p.hooks()));
+          match described {
+            Ok(hooks) => PluginInfo { name, enabled, hooks, error: None },
+            Err(e) => PluginInfo { name, enabled, hooks: Vec::new(), error: Some(e) },
+          }
+        })
+        .collect(),
+    )
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_hooks_for_state_changes() {
+    assert_eq!(hook("starting", "recording"), Some(Hook::Start));
+    assert_eq!(hook("recording", "stopping"), Some(Hook::Stop));
+    // Canceled or failed while recording or paused
+    assert_eq!(hook("recording", "inactive"), Some(Hook::Stop));
+    assert_eq!(hook("paused", "inactive"), Some(Hook::Stop));
+    assert_eq!(hook("paused", "stopping"), Some(Hook::Stop));
+    // Pausing and resuming are the same dictation
+    assert_eq!(hook("recording", "paused"), None);
+    assert_eq!(hook("paused", "recording"), None);
+    assert_eq!(hook("inactive", "starting"), None);
+    assert_eq!(hook("starting", "inactive"), None);
+    assert_eq!(hook("stopping", "inactive"), None);
+  }
+
+  #[test]
+  fn test_files_by_name() {
+    let dir = std::env::temp_dir().join(format!("dictation-hud-plugins-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    for file in ["zeta.wasm", "alpha.wasm", "notes.txt"] {
+      std::fs::write(dir.join(file), b"").unwrap();
+    }
+    let names: Vec<String> = files(&dir).into_iter().map(|(name, _, _)| name).collect();
+    assert_eq!(names, vec!["alpha", "zeta"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(files(&dir).is_empty());
+  }
+}
@@ -0,0 +1,182 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use tracing::info;
+
+use crate::window_target;
+
+const K_APP_PROFILES: &str = "app_profiles";
+
+/// Per-application overrides layered on top of the global behavior prefs.
+/// Unset fields fall through to the global value.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppOverrides {
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub auto_paste: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ai_refine: Option<bool>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub ai_provider: Option<String>,
+  /// Code dictation mode, e.g. on for editors and IDEs
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub code_mode: Option<bool>,
+  /// Preview each paste before it goes in, e.g. for mail clients
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub confirm_before_paste: Option<bool>,
+  /// Prompt profile id to use for this app (e.g. "email" for a mail client)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt_profile: Option<String>,
+  /// Replaces the refinement system prompt for this app
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub prompt: Option<String>,
+  /// "paste" or "type", e.g. type for terminals and remote desktops
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub insert_mode: Option<String>,
+  /// Built-in packs enabled for this app, in addition to the globally enabled ones
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub packs: Option<Vec<String>>,
+  /// Redaction categories for this app, replacing the global ones (e.g. all of them for Slack)
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub redact: Option<Vec<String>>,
+  /// Typography for this app, e.g. straight quotes for a LaTeX editor and curly ones for Word
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub typography: Option<crate::typography::Typography>,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ActiveApp {
+  /// Normalized lookup key, e.g. "code" for "Code.exe"
+  pub key: String,
+  pub name: String,
+  pub title: String,
+}
+
+/// Normalize an executable/application name into a profile key
+pub fn app_key(name: &str) -> String {
+  let lower = name.trim().to_lowercase();
+  lower.strip_suffix(".exe").unwrap_or(&lower).to_string()
+}
+
+/// The application that currently owns the foreground window
+pub fn get_active_app() -> Option<ActiveApp> {
+  let w = window_target::foreground_window()?;
+  if w.app.is_empty() { return None; }
+  Some(ActiveApp { key: app_key(&w.app), name: w.app, title: w.title })
+}
+
+pub fn load_profiles(app: &AppHandle) -> BTreeMap<String, AppOverrides> {
+  let Ok(store) = app.store("prefs.json") else { return BTreeMap::new() };
+  store
+    .get(K_APP_PROFILES)
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+/// Create, replace or (with `None`) remove the overrides for one application
+pub fn set_app_profile(app: &AppHandle, name: &str, overrides: Option<AppOverrides>) -> anyhow::Result<BTreeMap<String, AppOverrides>> {
+  let mut profiles = load_profiles(app);
+  let key = app_key(name);
+  match overrides {
+    Some(o) => { profiles.insert(key, o); }
+    None => { profiles.remove(&key); }
+  }
+  let store = app.store("prefs.json")?;
+  store.set(K_APP_PROFILES, serde_json::to_value(&profiles)?);
+  store.save()?;
+  Ok(profiles)
+}
+
+// App and overrides captured when the dictation session started, so the HUD stealing focus
+// (or the user switching apps while refinement runs) doesn't change the active profile.
+struct SessionApp {
+  key: String,
+  overrides: AppOverrides,
+}
+
+static SESSION_APP: Mutex<Option<SessionApp>> = Mutex::new(None);
+
+/// Capture the window and app (and its profile) that will receive this dictation
+pub fn begin_session(app: &AppHandle) {
+  let window = window_target::get_target().or_else(window_target::foreground_window);
+  window_target::set_session_window(window.clone());
+  let target_app = window.map(|w| w.app);
+  let captured = target_app.filter(|name| !name.is_empty()).map(|name| {
+    let key = app_key(&name);
+    let overrides = load_profiles(app).remove(&key);
+    if overrides.is_some() {
+      info!("Using app profile for \"{}\"", key);
+    }
+    SessionApp { key, overrides: overrides.unwrap_or_default() }
+  });
+  *SESSION_APP.lock().unwrap() = captured;
+}
+
+pub fn end_session() {
+  *SESSION_APP.lock().unwrap() = None;
+  window_target::set_session_window(None);
+}
+
+/// Overrides for the running session (empty when no profile matched)
+pub fn session_overrides() -> AppOverrides {
+  SESSION_APP.lock().unwrap().as_ref().map(|s| s.overrides.clone()).unwrap_or_default()
+}
+
+/// Profile key of the app the running session dictates into
+pub fn session_app() -> Option<String> {
+  SESSION_APP.lock().unwrap().as_ref().map(|s| s.key.clone())
+}
+
+/// Flip code mode in the profile of the app being dictated into (the running session's, else the
+/// focused one) when that profile sets it, since the global value doesn't apply there. Returns
+/// the app's key and its new value; None when the app has no code mode of its own.
+pub fn toggle_app_code_mode(app: &AppHandle) -> anyhow::Result<Option<(String, bool)>> {
+  let Some(key) = session_app().or_else(|| get_active_app().map(|a| a.key)) else { return Ok(None) };
+  let mut profiles = load_profiles(app);
+  let Some(overrides) = profiles.get_mut(&key) else { return Ok(None) };
+  let Some(on) = overrides.code_mode.map(|v| !v) else { return Ok(None) };
+  overrides.code_mode = Some(on);
+  let store = app.store("prefs.json")?;
+  store.set(K_APP_PROFILES, serde_json::to_value(&profiles)?);
+  store.save()?;
+  if let Some(session) = SESSION_APP.lock().unwrap().as_mut().filter(|s| s.key == key) {
+    session.overrides.code_mode = Some(on);
+  }
+  Ok(Some((key, on)))
+}
+
+/// Apply the same overrides to many apps at once (e.g. from the usage list).
+/// With `merge`, only the fields set in `overrides` replace existing values.
+pub fn set_app_profiles_bulk(
+  app: &AppHandle,
+  names: &[String],
+  overrides: &AppOverrides,
+  merge: bool,
+) -> anyhow::Result<BTreeMap<String, AppOverrides>> {
+  let mut profiles = load_profiles(app);
+  for name in names {
+    let key = app_key(name);
+    let entry = profiles.entry(key).or_default();
+    if merge {
+      if overrides.auto_paste.is_some() { entry.auto_paste = overrides.auto_paste; }
+      if overrides.ai_refine.is_some() { entry.ai_refine = overrides.ai_refine; }
+      if overrides.ai_provider.is_some() { entry.ai_provider = overrides.ai_provider.clone(); }
+      if overrides.code_mode.is_some() { entry.code_mode = overrides.code_mode; }
+      if overrides.confirm_before_paste.is_some() { entry.confirm_before_paste = overrides.confirm_before_paste; }
+      if overrides.prompt_profile.is_some() { entry.prompt_profile = overrides.prompt_profile.clone(); }
+      if overrides.prompt.is_some() { entry.prompt = overrides.prompt.clone(); }
+      if overrides.insert_mode.is_some() { entry.insert_mode = overrides.insert_mode.clone(); }
+      if overrides.packs.is_some() { entry.packs = overrides.packs.clone(); }
+      if overrides.redact.is_some() { entry.redact = overrides.redact.clone(); }
+      if overrides.typography.is_some() { entry.typography = overrides.typography.clone(); }
+    } else {
+      *entry = overrides.clone();
+    }
+  }
+  let store = app.store("prefs.json")?;
+  store.set(K_APP_PROFILES, serde_json::to_value(&profiles)?);
+  store.save()?;
+  Ok(profiles)
+}
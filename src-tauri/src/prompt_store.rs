@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::prompt;
+
+// Named refinement prompt profiles. The built-ins live in `prompt::PROMPT_PROFILES`;
+// "default" is the hardcoded prompt and can never be edited. "custom" holds the user's own prompt.
+// Prompts can also be set per language, used when that language is detected in a dictation.
+
+const K_ACTIVE_PROFILE: &str = "prompt_profile";
+const K_CUSTOM_PROMPT: &str = "custom_prompt";
+/// Language code -> prompt
+const K_LANGUAGE_PROMPTS: &str = "language_prompts";
+pub const DEFAULT_PROFILE: &str = "default";
+pub const CUSTOM_PROFILE: &str = "custom";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PromptProfile {
+  pub id: String,
+  pub name: String,
+  pub editable: bool,
+  pub active: bool,
+  pub prompt: String,
+}
+
+pub fn get_custom_prompt(app: &AppHandle) -> Option<String> {
+  let store = app.store("prefs.json").ok()?;
+  store.get(K_CUSTOM_PROMPT).and_then(|v| v.as_str().map(|s| s.to_string())).filter(|s| !s.trim().is_empty())
+}
+
+pub fn get_active_profile(app: &AppHandle) -> String {
+  app
+    .store("prefs.json")
+    .ok()
+    .and_then(|s| s.get(K_ACTIVE_PROFILE))
+    .and_then(|v| v.as_str().map(|s| s.to_string()))
+    .unwrap_or_else(|| DEFAULT_PROFILE.into())
+}
+
+/// Full prompt text for a profile id, or None if the id is unknown (or custom is empty)
+pub fn profile_prompt(app: &AppHandle, id: &str) -> Option<String> {
+  if id == CUSTOM_PROFILE {
+    return get_custom_prompt(app);
+  }
+  prompt::PROMPT_PROFILES
+    .iter()
+    .find(|(pid, _, _)| *pid == id)
+    .map(|(_, _, rules)| prompt::build_profile_prompt(rules))
+}
+
+/// Prompt for the active profile, falling back to the immutable default
+pub fn active_prompt(app: &AppHandle) -> String {
+  profile_prompt(app, &get_active_profile(app)).unwrap_or_else(|| prompt::get_system_prompt().to_string())
+}
+
+pub fn list_profiles(app: &AppHandle) -> Vec<PromptProfile> {
+  let active = get_active_profile(app);
+  let mut list: Vec<PromptProfile> = prompt::PROMPT_PROFILES
+    .iter()
+    .map(|(id, name, rules)| PromptProfile {
+      id: id.to_string(),
+      name: name.to_string(),
+      editable: false,
+      active: active == *id,
+      prompt: prompt::build_profile_prompt(rules),
+    })
+    .collect();
+  list.push(PromptProfile {
+    id: CUSTOM_PROFILE.into(),
+    name: "Custom".into(),
+    editable: true,
+    active: active == CUSTOM_PROFILE,
+    prompt: get_custom_prompt(app).unwrap_or_default(),
+  });
+  list
+}
+
+pub fn set_active_profile(app: &AppHandle, id: &str) -> anyhow::Result<()> {
+  let known = id == CUSTOM_PROFILE || prompt::PROMPT_PROFILES.iter().any(|(pid, _, _)| *pid == id);
+  if !known {
+    anyhow::bail!("Unknown prompt profile: {}", id);
+  }
+  if id == CUSTOM_PROFILE && get_custom_prompt(app).is_none() {
+    anyhow::bail!("Custom prompt is empty; set it with set_custom_prompt first");
+  }
+  let store = app.store("prefs.json")?;
+  store.set(K_ACTIVE_PROFILE, id);
+  store.save()?;
+  Ok(())
+}
+
+/// Save the user's custom prompt. An empty prompt clears it and, if custom was active, reverts to default.
+pub fn set_custom_prompt(app: &AppHandle, text: &str) -> anyhow::Result<()> {
+  let store = app.store("prefs.json")?;
+  if text.trim().is_empty() {
+    store.delete(K_CUSTOM_PROMPT);
+    if get_active_profile(app) == CUSTOM_PROFILE {
+      store.set(K_ACTIVE_PROFILE, DEFAULT_PROFILE);
+    }
+  } else {
+    store.set(K_CUSTOM_PROMPT, text);
+  }
+  store.save()?;
+  Ok(())
+}
+
+pub fn language_prompts(app: &AppHandle) -> BTreeMap<String, String> {
+  app
+    .store("prefs.json")
+    .ok()
+    .and_then(|s| s.get(K_LANGUAGE_PROMPTS))
+    .and_then(|v| serde_json::from_value(v).ok())
+    .unwrap_or_default()
+}
+
+/// The prompt set for a language code ("de", "de-AT"), if any
+pub fn language_prompt(app: &AppHandle, code: &str) -> Option<String> {
+  language_prompts(app).remove(&crate::language::primary_subtag(code))
+}
+
+/// Set the prompt for a language; an empty prompt removes it
+pub fn set_language_prompt(app: &AppHandle, code: &str, text: &str) -> anyhow::Result<()> {
+  let code = crate::language::primary_subtag(code);
+  if code.is_empty() {
+    anyhow::bail!("Language code is empty");
+  }
+  let mut prompts = language_prompts(app);
+  if text.trim().is_empty() {
+    prompts.remove(&code);
+  } else {
+    prompts.insert(code, text.to_string());
+  }
+  let store = app.store("prefs.json")?;
+  store.set(K_LANGUAGE_PROMPTS, serde_json::to_value(&prompts)?);
+  store.save()?;
+  Ok(())
+}
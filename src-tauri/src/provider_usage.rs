@@ -0,0 +1,162 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+use tauri_plugin_store::StoreExt;
+use tracing::{info, warn};
+
+use crate::quota::{self, ProviderUsage, Thresholds};
+use crate::{config, http_client, stt_options, DictationState, RECORDING_STATE};
+
+// Checks the STT providers' remaining credit (the reading and thresholds are in quota.rs) when
+// Settings asks and every few hours in the background, and shows an OS notification when a
+// provider drops below its threshold. Each provider is warned about once until its credit is
+// back above the threshold, across restarts (the warned providers are kept in stats.json).
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// Wait after launch before the first check, so it doesn't slow down startup
+const FIRST_CHECK_DELAY: Duration = Duration::from_secs(5 * 60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const STATS_STORE: &str = "stats.json";
+const K_WARNED: &str = "low_credit_warned";
+
+/// Providers already warned about; None until read from the store
+static WARNED: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+fn load_warned(app: &AppHandle) -> Vec<String> {
+  let Ok(store) = app.store(STATS_STORE) else { return Vec::new() };
+  store.get(K_WARNED).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+fn save_warned(app: &AppHandle, warned: &[String]) {
+  let result = (|| -> anyhow::Result<()> {
+    let store = app.store(STATS_STORE)?;
+    store.set(K_WARNED, serde_json::to_value(warned)?);
+    store.save()?;
+    Ok(())
+  })();
+  if let Err(e) = result {
+    warn!("Failed to save low credit warnings: {}", e);
+  }
+}
+
+/// The thresholds in the stored behavior prefs, read raw so this works outside the command layer
+fn thresholds(app: &AppHandle) -> Thresholds {
+  let behavior = app.store("prefs.json").ok().and_then(|s| s.get("behavior")).unwrap_or_default();
+  let get = |key: &str, default: u32| behavior.get(key).and_then(|v| v.as_u64()).map(|v| v as u32).unwrap_or(default);
+  let defaults = quota::DEFAULT_THRESHOLDS;
+  Thresholds { usd: get("low_credit_usd", defaults.usd), percent: get("low_credit_percent", defaults.percent) }
+}
+
+/// What to do about a key `provider` won't give the usage to
+fn forbidden_message(provider: &str) -> &'static str {
+  match provider {
+    "deepgram" => "This key isn't allowed to read usage (Deepgram needs an Owner or Admin key)",
+    "elevenlabs" => "This key isn't allowed to read the subscription (give the ElevenLabs key the User read permission)",
+    _ => "This key isn't allowed to read usage",
+  }
+}
+
+async fn get_json(provider: &str, request: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+  let resp = request.timeout(REQUEST_TIMEOUT).send().await.map_err(|e| e.to_string())?;
+  let status = resp.status();
+  if !status.is_success() {
+    return Err(match status.as_u16() {
+      401 | 403 => forbidden_message(provider).into(),
+      _ => format!("HTTP {}", status),
+    });
+  }
+  resp.json().await.map_err(|e| e.to_string())
+}
+
+/// Deepgram's prepaid balance, summed over the key's projects
+async fn deepgram(app: &AppHandle, key: &str) -> ProviderUsage {
+  if !config::get_stt_options(app).await.stt_endpoint_override.is_empty() {
+    return ProviderUsage::failed("deepgram", "Not available for a self-hosted endpoint");
+  }
+  let base = stt_options::DEEPGRAM_API;
+  let client = http_client::shared();
+  let auth = format!("Token {}", key);
+  let projects = match get_json("deepgram", client.get(format!("{}/v1/projects", base)).header("authorization", &auth)).await {
+    Ok(body) => quota::deepgram_projects(&body),
+    Err(e) => return ProviderUsage::failed("deepgram", e),
+  };
+  let mut total = 0.0;
+  let mut unit = "usd".to_string();
+  for project in projects {
+    match get_json("deepgram", client.get(format!("{}/v1/projects/{}/balances", base, project)).header("authorization", &auth)).await {
+      Ok(body) => {
+        if let Some((amount, u)) = quota::deepgram_balance(&body) {
+          total += amount;
+          unit = u;
+        }
+      }
+      Err(e) => return ProviderUsage::failed("deepgram", e),
+    }
+  }
+  ProviderUsage { provider: "deepgram".into(), remaining: Some(total), limit: None, unit, resets_at: None, low: false, error: None }
+}
+
+async fn elevenlabs(key: &str) -> ProviderUsage {
+  let request = http_client::shared().get("https://api.elevenlabs.io/v1/user/subscription").header("xi-api-key", key);
+  match get_json("elevenlabs", request).await {
+    Ok(body) => quota::elevenlabs_usage(&body).unwrap_or_else(|| ProviderUsage::failed("elevenlabs", "Unexpected subscription response")),
+    Err(e) => ProviderUsage::failed("elevenlabs", e),
+  }
+}
+
+/// Notify about providers that just went low, and forget the ones that recovered
+fn warn_low(app: &AppHandle, usage: &[ProviderUsage]) {
+  let mut guard = WARNED.lock().unwrap();
+  let warned = guard.get_or_insert_with(|| load_warned(app));
+  let before = warned.clone();
+  for u in usage.iter().filter(|u| u.error.is_none()) {
+    let was_warned = warned.contains(&u.provider);
+    if !u.low {
+      warned.retain(|p| *p != u.provider);
+    } else if !was_warned {
+      let message = quota::warning(u);
+      info!("Low provider credit: {}", message);
+      let shown = app.notification().builder().title("Transcription credit is running low").body(&message).show();
+      if let Err(e) = shown {
+        warn!("Could not show a notification: {}", e);
+      }
+      warned.push(u.provider.clone());
+    }
+  }
+  if *warned != before {
+    save_warned(app, warned);
+  }
+}
+
+/// Usage of each STT provider with a key, with `low` set per the thresholds; warns about
+/// providers that just dropped below them
+pub async fn check(app: &AppHandle) -> Vec<ProviderUsage> {
+  let thresholds = thresholds(app);
+  let (deepgram_key, elevenlabs_key) = tokio::join!(config::get_deepgram_key(app), config::get_elevenlabs_key(app));
+  let mut usage = Vec::new();
+  if let Some(key) = deepgram_key {
+    usage.push(deepgram(app, &key).await);
+  }
+  if let Some(key) = elevenlabs_key {
+    usage.push(elevenlabs(&key).await);
+  }
+  for u in &mut usage {
+    u.low = quota::is_low(u, thresholds);
+  }
+  warn_low(app, &usage);
+  usage
+}
+
+/// Check every few hours for the lifetime of the app, skipping checks while dictating
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    tokio::time::sleep(FIRST_CHECK_DELAY).await;
+    loop {
+      if RECORDING_STATE.lock().unwrap().state == DictationState::Inactive {
+        check(&app).await;
+      }
+      tokio::time::sleep(CHECK_INTERVAL).await;
+    }
+  });
+}
@@ -0,0 +1,127 @@
+// Remaining credit at the STT providers, so a dictation doesn't fail halfway through the month
+// because the balance ran out. Deepgram reports a prepaid balance per project (in dollars);
+// ElevenLabs reports the credits used out of the plan's allowance for the current period. This
+// reads those responses and decides when the remaining credit is low; fetching them is in
+// provider_usage.rs.
+
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ProviderUsage {
+    pub provider: String,
+    /// Credit left: Deepgram's balance in `unit`, ElevenLabs' credits left this period
+    pub remaining: Option<f64>,
+    /// The period's allowance (ElevenLabs only; a Deepgram balance has no total)
+    pub limit: Option<f64>,
+    /// "usd" for Deepgram balances, "credits" for ElevenLabs
+    pub unit: String,
+    /// When the allowance resets (Unix seconds, ElevenLabs only)
+    pub resets_at: Option<u64>,
+    /// Below the warning threshold set in the prefs
+    pub low: bool,
+    /// Why the usage couldn't be read
+    pub error: Option<String>,
+}
+
+impl ProviderUsage {
+    pub fn failed(provider: &str, error: impl Into<String>) -> Self {
+        ProviderUsage { provider: provider.into(), remaining: None, limit: None, unit: String::new(), resets_at: None, low: false, error: Some(error.into()) }
+    }
+}
+
+/// Warning thresholds from the behavior prefs; 0 turns a warning off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    /// Deepgram balance, in dollars
+    pub usd: u32,
+    /// ElevenLabs credits left, as a percentage of the allowance
+    pub percent: u32,
+}
+
+pub const DEFAULT_THRESHOLDS: Thresholds = Thresholds { usd: 5, percent: 10 };
+
+/// Project ids from Deepgram's `GET /v1/projects`
+pub fn deepgram_projects(body: &Value) -> Vec<String> {
+    body["projects"].as_array().map(|projects| projects.iter().filter_map(|p| p["project_id"].as_str().map(str::to_string)).collect()).unwrap_or_default()
+}
+
+/// Total and unit of the balances in Deepgram's `GET /v1/projects/{id}/balances`
+pub fn deepgram_balance(body: &Value) -> Option<(f64, String)> {
+    let balances = body["balances"].as_array()?;
+    let unit = balances.first().and_then(|b| b["units"].as_str()).unwrap_or("usd").to_lowercase();
+    let total = balances.iter().filter(|b| b["units"].as_str().is_none_or(|u| u.eq_ignore_ascii_case(&unit))).filter_map(|b| b["amount"].as_f64()).sum();
+    Some((total, unit))
+}
+
+/// Usage from ElevenLabs' `GET /v1/user/subscription`
+pub fn elevenlabs_usage(body: &Value) -> Option<ProviderUsage> {
+    let used = body["character_count"].as_f64()?;
+    let limit = body["character_limit"].as_f64()?;
+    Some(ProviderUsage {
+        provider: "elevenlabs".into(),
+        remaining: Some((limit - used).max(0.0)),
+        limit: Some(limit),
+        unit: "credits".into(),
+        resets_at: body["next_character_count_reset_unix"].as_u64(),
+        low: false,
+        error: None,
+    })
+}
+
+/// Whether `usage` is below its threshold
+pub fn is_low(usage: &ProviderUsage, thresholds: Thresholds) -> bool {
+    let Some(remaining) = usage.remaining else { return false };
+    match usage.limit {
+        Some(limit) => thresholds.percent > 0 && limit > 0.0 && remaining * 100.0 < limit * thresholds.percent as f64,
+        None => thresholds.usd > 0 && usage.unit == "usd" && remaining < thresholds.usd as f64,
+    }
+}
+
+/// The notification text for low credit
+pub fn warning(usage: &ProviderUsage) -> String {
+    let name = if usage.provider == "elevenlabs" { "ElevenLabs" } else { "Deepgram" };
+    match (usage.remaining, usage.limit) {
+        (Some(remaining), Some(limit)) => format!("{} has {:.0} of {:.0} credits left this period", name, remaining, limit),
+        (Some(remaining), None) if usage.unit == "usd" => format!("{} balance is down to ${:.2}", name, remaining),
+        (Some(remaining), None) => format!("{} balance is down to {:.2} {}", name, remaining, usage.unit),
+        _ => format!("{} credit is low", name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const THRESHOLDS: Thresholds = DEFAULT_THRESHOLDS;
+
+    #[test]
+    fn test_deepgram() {
+        let projects = json!({ "projects": [{ "project_id": "p1", "name": "Main" }, { "name": "broken" }] });
+        assert_eq!(deepgram_projects(&projects), vec!["p1"]);
+        let balances = json!({ "balances": [{ "balance_id": "b1", "amount": 3.5, "units": "usd" }, { "balance_id": "b2", "amount": 1.0, "units": "usd" }] });
+        assert_eq!(deepgram_balance(&balances), Some((4.5, "usd".into())));
+        assert_eq!(deepgram_balance(&json!({ "balances": [] })), Some((0.0, "usd".into())));
+        assert_eq!(deepgram_balance(&json!({ "err_code": "FORBIDDEN" })), None);
+
+        let usage = |remaining: f64| ProviderUsage { remaining: Some(remaining), unit: "usd".into(), ..ProviderUsage::failed("deepgram", "") };
+        assert!(is_low(&usage(4.5), THRESHOLDS));
+        assert!(!is_low(&usage(5.0), THRESHOLDS));
+        assert!(!is_low(&usage(0.5), Thresholds { usd: 0, ..THRESHOLDS }));
+        assert_eq!(warning(&usage(4.5)), "Deepgram balance is down to $4.50");
+    }
+
+    #[test]
+    fn test_elevenlabs() {
+        let body = json!({ "tier": "creator", "character_count": 95_500, "character_limit": 100_000, "next_character_count_reset_unix": 1_767_225_600u64 });
+        let usage = elevenlabs_usage(&body).unwrap();
+        assert_eq!((usage.remaining, usage.limit, usage.resets_at), (Some(4_500.0), Some(100_000.0), Some(1_767_225_600)));
+        assert!(is_low(&usage, THRESHOLDS));
+        assert!(!is_low(&usage, Thresholds { percent: 4, ..THRESHOLDS }));
+        assert_eq!(warning(&usage), "ElevenLabs has 4500 of 100000 credits left this period");
+        assert!(elevenlabs_usage(&json!({ "detail": "invalid key" })).is_none());
+        assert!(!is_low(&ProviderUsage::failed("elevenlabs", "offline"), THRESHOLDS));
+    }
+}
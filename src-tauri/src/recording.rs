@@ -0,0 +1,117 @@
+// A capped buffer of 16-bit PCM (what last_audio.rs keeps) and the WAV container it is saved in.
+// Past the cap the oldest audio is dropped, so a very long dictation keeps its end; the buffer is
+// a ring, so dropping it costs no more than the chunk that pushed it out.
+
+use std::collections::VecDeque;
+
+/// Cap on kept audio: 10 minutes of 16kHz mono linear16
+pub const MAX_BYTES: usize = 16_000 * 2 * 600;
+
+#[derive(Debug, Clone)]
+pub struct Recording {
+    pcm: VecDeque<u8>,
+    max_bytes: usize,
+    /// Audio was dropped from the start to stay under the cap
+    truncated: bool,
+}
+
+impl Recording {
+    pub const fn new(max_bytes: usize) -> Self {
+        Recording { pcm: VecDeque::new(), max_bytes, truncated: false }
+    }
+
+    pub fn clear(&mut self) {
+        self.pcm.clear();
+        self.truncated = false;
+    }
+
+    /// Add little-endian 16-bit PCM, dropping the oldest samples past the cap
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.pcm.extend(bytes);
+        if self.pcm.len() > self.max_bytes {
+            // Whole samples only, so the rest stays aligned
+            let excess = (self.pcm.len() - self.max_bytes).next_multiple_of(2).min(self.pcm.len());
+            self.pcm.drain(..excess);
+            self.truncated = true;
+        }
+    }
+
+    /// The kept audio in one piece
+    pub fn pcm(&mut self) -> &[u8] {
+        self.pcm.make_contiguous()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pcm.is_empty()
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn duration_ms(&self, sample_rate: u32) -> u64 {
+        (self.pcm.len() / 2) as u64 * 1000 / sample_rate as u64
+    }
+}
+
+/// Wrap mono 16-bit PCM in a WAV (RIFF) container
+pub fn wav(pcm: &[u8], sample_rate: u32) -> Vec<u8> {
+    let data_len = pcm.len() as u32;
+    let mut out = Vec::with_capacity(44 + pcm.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes()); // fmt chunk size
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&1u16.to_le_bytes()); // mono
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * 2).to_le_bytes()); // byte rate
+    out.extend_from_slice(&2u16.to_le_bytes()); // block align
+    out.extend_from_slice(&16u16.to_le_bytes()); // bits per sample
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(pcm);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_keeps_newest_audio() {
+        let mut rec = Recording::new(8);
+        rec.push(&[1, 0, 2, 0, 3, 0]);
+        assert!(!rec.is_truncated());
+        rec.push(&[4, 0, 5, 0, 6]);
+        assert_eq!(rec.pcm(), &[3, 0, 4, 0, 5, 0, 6]);
+        assert!(rec.is_truncated());
+        // Wrapping around many times over keeps the newest samples
+        rec.clear();
+        for i in 7..100u8 {
+            rec.push(&[i, 0]);
+        }
+        assert_eq!(rec.pcm(), &[96, 0, 97, 0, 98, 0, 99, 0]);
+        // A chunk bigger than the cap leaves its end
+        rec.push(&[1, 0, 2, 0, 3, 0, 4, 0, 5, 0]);
+        assert_eq!(rec.pcm(), &[2, 0, 3, 0, 4, 0, 5, 0]);
+        rec.clear();
+        assert!(rec.is_empty() && !rec.is_truncated());
+    }
+
+    #[test]
+    fn test_wav_header() {
+        let pcm = vec![0u8; 32_000];
+        let wav = wav(&pcm, 16_000);
+        assert_eq!(wav.len(), 44 + pcm.len());
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(&wav[8..16], b"WAVEfmt ");
+        assert_eq!(u32::from_le_bytes(wav[24..28].try_into().unwrap()), 16_000);
+        assert_eq!(u32::from_le_bytes(wav[40..44].try_into().unwrap()), 32_000);
+
+        let mut rec = Recording::new(MAX_BYTES);
+        rec.push(&pcm);
+        assert_eq!(rec.duration_ms(16_000), 1000);
+    }
+}
@@ -0,0 +1,79 @@
+//! Masking of secrets (API keys, tokens) in settings before they leave the machine,
+//! e.g. in a diagnostics bundle.
+
+use serde_json::Value;
+
+/// Whether a settings key holds a secret. Hotkeys are not secrets.
+pub fn is_secret_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    (name.ends_with("key") && !name.ends_with("hotkey"))
+        || name.contains("token")
+        || name.contains("secret")
+        || name.contains("password")
+}
+
+/// Replace every non-empty string stored under a secret name, at any depth
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (name, v) in map.iter_mut() {
+                match v {
+                    Value::String(s) if is_secret_name(name) && !s.is_empty() => {
+                        *v = Value::String(format!("[redacted, {} chars]", s.chars().count()));
+                    }
+                    _ => redact_secrets(v),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Characters of a key that are safe to show: enough to tell keys apart, not enough to use one
+pub const PREFIX_CHARS: usize = 6;
+
+/// The first `PREFIX_CHARS` characters of a key, or all of it when shorter
+pub fn key_prefix(key: &str) -> String {
+    key.chars().take(PREFIX_CHARS).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_names() {
+        assert!(is_secret_name("openrouter_key"));
+        assert!(is_secret_name("apiKey"));
+        assert!(is_secret_name("elevenlabs_token"));
+        assert!(!is_secret_name("hotkey"));
+        assert!(!is_secret_name("code_mode_hotkey"));
+        assert!(!is_secret_name("auto_paste"));
+    }
+
+    #[test]
+    fn test_redacts_nested() {
+        let mut v = json!({
+            "deepgram_key": "dg-123456",
+            "hotkey": "Ctrl+Shift+Alt+H",
+            "empty_key": "",
+            "profiles": [{ "name": "work", "api_key": "abc" }],
+            "behavior": { "auto_paste": true }
+        });
+        redact_secrets(&mut v);
+        assert_eq!(v["deepgram_key"], "[redacted, 9 chars]");
+        assert_eq!(v["hotkey"], "Ctrl+Shift+Alt+H");
+        assert_eq!(v["empty_key"], "");
+        assert_eq!(v["profiles"][0]["api_key"], "[redacted, 3 chars]");
+        assert_eq!(v["behavior"]["auto_paste"], true);
+    }
+
+    #[test]
+    fn test_key_prefix() {
+        assert_eq!(key_prefix("sk-or-v1-abcdef"), "sk-or-");
+        assert_eq!(key_prefix("abc"), "abc");
+        assert_eq!(key_prefix(""), "");
+    }
+}
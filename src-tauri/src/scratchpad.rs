@@ -0,0 +1,36 @@
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, WebviewUrl, WebviewWindowBuilder};
+
+// A small editable window holding a dictation that had nowhere safe to go (its target window
+// closed while refinement ran), so the text can be fixed up and copied by hand.
+
+const LABEL: &str = "scratchpad";
+
+static TEXT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keep text for the scratchpad without opening it
+pub fn stash(text: &str) {
+  *TEXT.lock().unwrap() = Some(text.to_string());
+}
+
+pub fn text() -> Option<String> {
+  TEXT.lock().unwrap().clone()
+}
+
+/// Show the scratchpad window, creating it on first use
+pub fn open(app: &AppHandle) -> Result<(), String> {
+  if let Some(win) = app.get_webview_window(LABEL) {
+    // An open scratchpad reloads its text when it regains focus
+    let _ = win.show();
+    let _ = win.set_focus();
+    return Ok(());
+  }
+  WebviewWindowBuilder::new(app, LABEL, WebviewUrl::App("index.html#/scratchpad".into()))
+    .title("Dictation Scratchpad")
+    .inner_size(520.0, 360.0)
+    .center()
+    .always_on_top(true)
+    .build()
+    .map_err(|e| e.to_string())?;
+  Ok(())
+}
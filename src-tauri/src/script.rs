@@ -0,0 +1,91 @@
+// The script stage: a Rhai script the user drops into the app data folder (`transform.rhai`)
+// gets each refined dictation and returns the text to use instead, for formatting needs too
+// specific to build in. The script sees `text`, `app` (profile key of the target app, "" when
+// unknown) and `language` ("" when unknown) and its last expression is the result; returning
+// nothing keeps the text. Rhai has no file, network or process access, and the engine caps
+// operations, string sizes and nesting so a runaway script fails instead of hanging a dictation.
+
+use rhai::{Dynamic, Engine, Scope, AST};
+
+/// Name of the script in the app data folder
+pub const FILE_NAME: &str = "transform.rhai";
+
+/// Enough for string work on a long dictation, far short of a noticeable delay
+const MAX_OPERATIONS: u64 = 200_000;
+const MAX_STRING_SIZE: usize = 256 * 1024;
+
+/// What the script knows about the dictation besides its text
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Context<'a> {
+    pub app: Option<&'a str>,
+    pub language: Option<&'a str>,
+}
+
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+    engine.disable_symbol("eval");
+    engine
+}
+
+fn compile(engine: &Engine, script: &str) -> Result<AST, String> {
+    engine.compile(script).map_err(|e| format!("Script error: {}", e))
+}
+
+/// Check that `script` parses
+pub fn check(script: &str) -> Result<(), String> {
+    compile(&engine(), script).map(|_| ())
+}
+
+/// `text` as transformed by `script`
+pub fn run(script: &str, text: &str, ctx: &Context) -> Result<String, String> {
+    let engine = engine();
+    let ast = compile(&engine, script)?;
+    let mut scope = Scope::new();
+    scope.push("text", text.to_string());
+    scope.push_constant("app", ctx.app.unwrap_or("").to_string());
+    scope.push_constant("language", ctx.language.unwrap_or("").to_string());
+    let result: Dynamic = engine.eval_ast_with_scope(&mut scope, &ast).map_err(|e| format!("Script failed: {}", e))?;
+    if result.is_unit() {
+        return Ok(text.to_string());
+    }
+    result
+        .into_string()
+        .map_err(|kind| format!("Script must return a string, not {}", kind))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_context() {
+        let script = r#"
+            if app == "slack" { text.replace("TODO", ":memo:"); }
+            if language == "de" { text = text + " (DE)"; }
+            text
+        "#;
+        let slack = Context { app: Some("slack"), language: Some("de") };
+        assert_eq!(run(script, "TODO call Sam", &slack).unwrap(), ":memo: call Sam (DE)");
+        assert_eq!(run(script, "TODO call Sam", &Context::default()).unwrap(), "TODO call Sam");
+        // No result keeps the text
+        assert_eq!(run("let x = 1;", "keep me", &Context::default()).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn test_errors_and_limits() {
+        assert!(check("text.to_upper(").is_err());
+        assert!(run("42", "x", &Context::default()).unwrap_err().contains("string"));
+        assert!(run("loop { }", "x", &Context::default()).is_err());
+        assert!(run(r#"eval("text")"#, "x", &Context::default()).is_err());
+        assert!(run(r#"let s = "x"; loop { s += s; }"#, "x", &Context::default()).is_err());
+    }
+}
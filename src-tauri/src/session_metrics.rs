@@ -0,0 +1,221 @@
+use chrono::{Local, TimeZone};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_store::StoreExt;
+use tracing::{debug, warn};
+
+use crate::cost::{self, DictationCost, MonthlyCosts};
+use crate::history;
+use crate::usage::{self, DailyCalls, PipelineTiming, SessionMetrics, UsageStats};
+
+// Timing of the dictation in progress, turned into SessionMetrics for its history entry and the
+// `pipeline-timing` event, and the per-day provider call counts behind the error rates on the
+// stats page (kept in stats.json next to the failure counters). The estimated cost of each
+// dictation (cost.rs) is added to the running monthly totals kept there too.
+
+/// Emitted app-wide with the PipelineTiming of each finished dictation
+pub const TIMING_EVENT: &str = "pipeline-timing";
+const STATS_STORE: &str = "stats.json";
+const K_CALLS: &str = "provider_calls";
+const K_COSTS: &str = "monthly_costs";
+
+struct Marks {
+  /// The hotkey (or whatever started the dictation)
+  started: Instant,
+  hud_shown: Option<Instant>,
+  first_partial: Option<Instant>,
+  transcript_final: Option<Instant>,
+  refined: Option<Instant>,
+  recording: Option<Instant>,
+  stopping: Option<Instant>,
+  stt_ms: Option<u64>,
+  refine_ms: Option<u64>,
+  /// Estimated cost of the session's AI requests so far
+  ai_usd: Option<f64>,
+}
+
+static SESSION: Mutex<Option<Marks>> = Mutex::new(None);
+
+/// Held around every read-modify-write of the counters in stats.json (here and in failures.rs):
+/// they are updated from concurrent tasks, and two loads before either saves lose a count
+pub fn lock_stats() -> MutexGuard<'static, ()> {
+  static STATS: Mutex<()> = Mutex::new(());
+  STATS.lock().unwrap()
+}
+
+/// A new dictation starts
+pub fn begin() {
+  *SESSION.lock().unwrap() = Some(Marks {
+    started: Instant::now(),
+    hud_shown: None,
+    first_partial: None,
+    transcript_final: None,
+    refined: None,
+    recording: None,
+    stopping: None,
+    stt_ms: None,
+    refine_ms: None,
+    ai_usd: None,
+  });
+}
+
+pub fn hud_shown() {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.hud_shown.get_or_insert_with(Instant::now);
+  }
+}
+
+/// The HUD put the first words of the session on screen
+pub fn first_partial() {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.first_partial.get_or_insert_with(Instant::now);
+  }
+}
+
+pub fn recording() {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.recording = Some(Instant::now());
+  }
+}
+
+pub fn stopping() {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.stopping.get_or_insert_with(Instant::now);
+  }
+}
+
+/// The final transcript reached refinement
+pub fn transcript_final() {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.transcript_final.get_or_insert_with(Instant::now);
+    if let (Some(stopping), None) = (m.stopping, m.stt_ms) {
+      m.stt_ms = Some(stopping.elapsed().as_millis() as u64);
+    }
+  }
+}
+
+pub fn refined(elapsed_ms: u64) {
+  if let Some(m) = SESSION.lock().unwrap().as_mut() {
+    m.refine_ms = Some(elapsed_ms);
+    m.refined = Some(Instant::now());
+  }
+}
+
+/// An AI request of the session finished; `usd` is its cost when known
+pub fn ai_request(usd: Option<f64>) {
+  if let (Some(m), Some(usd)) = (SESSION.lock().unwrap().as_mut(), usd) {
+    *m.ai_usd.get_or_insert(0.0) += usd;
+  }
+}
+
+/// End a session that stopped without inserting its text (canceled, or its preview discarded).
+/// Returns the audio it recorded (ms) and what its AI requests cost when it got as far as
+/// recording, since the providers bill that all the same.
+pub fn abandon() -> Option<(u64, Option<f64>)> {
+  let m = SESSION.lock().unwrap().take()?;
+  let start = m.recording?;
+  let stop = m.stopping.unwrap_or_else(Instant::now);
+  Some((stop.saturating_duration_since(start).as_millis() as u64, m.ai_usd))
+}
+
+/// Metrics of the session that just inserted its text, ending it; None outside a dictation (a
+/// take or file transcription). `paste_ms` is None when the text wasn't pasted; `stt_per_min` is
+/// the STT list price, see `cost::stt_rate`.
+pub fn finish(provider: &str, words: u64, paste_ms: Option<u64>, stt_per_min: Option<f64>) -> Option<SessionMetrics> {
+  let m = SESSION.lock().unwrap().take()?;
+  let duration_ms = match (m.recording, m.stopping) {
+    (Some(start), Some(stop)) => stop.saturating_duration_since(start).as_millis() as u64,
+    _ => 0,
+  };
+  let since_start = |at: Option<Instant>| at.map(|t| t.saturating_duration_since(m.started).as_millis() as u64);
+  let timing = PipelineTiming {
+    hud_shown_ms: since_start(m.hud_shown),
+    first_partial_ms: since_start(m.first_partial),
+    final_ms: since_start(m.transcript_final),
+    refined_ms: since_start(m.refined),
+    pasted_ms: paste_ms.and(since_start(Some(Instant::now()))),
+  };
+  Some(SessionMetrics {
+    duration_ms,
+    words,
+    provider: provider.to_string(),
+    stt_ms: m.stt_ms,
+    refine_ms: m.refine_ms,
+    paste_ms,
+    timing: Some(timing),
+    cost: Some(DictationCost::new(duration_ms, stt_per_min, m.ai_usd)),
+  })
+}
+
+pub fn emit_timing(app: &AppHandle, timing: &PipelineTiming) {
+  if let Some((step, ms)) = timing.slowest_step() {
+    debug!("Pipeline timing {:?}, slowest step: {} ({}ms)", timing, step, ms);
+  }
+  if let Err(e) = app.emit(TIMING_EVENT, timing) {
+    warn!("Failed to emit pipeline timing: {}", e);
+  }
+}
+
+fn load_calls(app: &AppHandle) -> DailyCalls {
+  let Ok(store) = app.store(STATS_STORE) else { return DailyCalls::new() };
+  store.get(K_CALLS).and_then(|v| serde_json::from_value(v).ok()).unwrap_or_default()
+}
+
+/// Count a provider request for today
+pub fn record_call(app: &AppHandle, provider: &str, ok: bool) {
+  let _stats = lock_stats();
+  let mut calls = load_calls(app);
+  let count = calls.entry(usage::day_key(Local::now().date_naive())).or_default().entry(provider.to_string()).or_default();
+  count.requests += 1;
+  if !ok {
+    count.errors += 1;
+  }
+  let result = (|| -> anyhow::Result<()> {
+    let store = app.store(STATS_STORE)?;
+    store.set(K_CALLS, serde_json::to_value(&calls)?);
+    store.save()?;
+    Ok(())
+  })();
+  if let Err(e) = result {
+    warn!("Failed to save provider call counts: {}", e);
+  }
+}
+
+fn load_costs(app: &AppHandle) -> MonthlyCosts {
+  let Ok(store) = app.store(STATS_STORE) else { return MonthlyCosts::new() };
+  store.get(K_COSTS).map(cost::parse_costs).unwrap_or_default()
+}
+
+/// Add a dictation with `duration_ms` of audio to this month's cost totals
+pub fn record_cost(app: &AppHandle, duration_ms: u64, dictation: &DictationCost) {
+  let _stats = lock_stats();
+  let mut costs = load_costs(app);
+  costs.entry(cost::month_key(Local::now().date_naive())).or_default().add(duration_ms, dictation);
+  let result = (|| -> anyhow::Result<()> {
+    let store = app.store(STATS_STORE)?;
+    store.set(K_COSTS, serde_json::to_value(&costs)?);
+    store.save()?;
+    Ok(())
+  })();
+  if let Err(e) = result {
+    warn!("Failed to save cost totals: {}", e);
+  }
+}
+
+/// Cost totals of the newest `months` months (all for 0)
+pub fn cost_summary(app: &AppHandle, months: usize) -> cost::CostSummary {
+  cost::summarize(&load_costs(app), months)
+}
+
+pub fn stats(app: &AppHandle, range: &str) -> Result<UsageStats, String> {
+  let entries = history::load_entries(app);
+  let sessions: Vec<usage::Session> = entries
+    .iter()
+    .filter_map(|e| {
+      let day = Local.timestamp_opt(e.created_at as i64, 0).single()?.date_naive();
+      Some(usage::Session { day, words: e.text.split_whitespace().count() as u64, metrics: e.metrics.as_ref() })
+    })
+    .collect();
+  usage::aggregate(range, Local::now().date_naive(), &sessions, &load_calls(app))
+}
@@ -0,0 +1,183 @@
+// Settings backups: one JSON file with the prefs (behavior, prompts, dictionary, app profiles,
+// hotkeys), the packs (custom symbols, snippets and rules, enabled built-ins) and learned
+// corrections, to move to another machine or restore after a reinstall. API keys are left out
+// unless a passphrase is given; they are then sealed with ChaCha20-Poly1305 under a key derived
+// from the passphrase with Argon2id. Reading and writing the stores is in backup.rs.
+
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+pub const FORMAT: &str = "dictation-hud-settings";
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Prefs holding API keys (and the proxy password): only exported sealed with a passphrase
+pub const SECRET_KEYS: &[&str] = &["openrouter_key", "deepgram_key", "megallm_key", "elevenlabs_key", "proxy_password"];
+
+/// Prefs that belong to this machine (its devices and monitors, its control API token) and don't
+/// carry over
+pub const LOCAL_KEYS: &[&str] = &["audio_device", "device_noise", "hud_positions", "control_api_token"];
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Bundle {
+    pub format: String,
+    pub version: u32,
+    pub exported_at: u64,
+    pub app_version: String,
+    pub prefs: Map<String, Value>,
+    #[serde(default)]
+    pub packs: Map<String, Value>,
+    #[serde(default)]
+    pub corrections: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keys: Option<SealedKeys>,
+}
+
+/// API keys encrypted with a passphrase; all fields base64
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SealedKeys {
+    pub salt: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl Bundle {
+    /// A bundle of `prefs` (all of prefs.json: secrets and machine-local entries are taken out),
+    /// `packs` and `corrections`
+    pub fn new(app_version: &str, exported_at: u64, prefs: Map<String, Value>, packs: Map<String, Value>, corrections: Map<String, Value>) -> Self {
+        let (prefs, _) = split_prefs(prefs);
+        Bundle { format: FORMAT.into(), version: BUNDLE_VERSION, exported_at, app_version: app_version.into(), prefs, packs, corrections, keys: None }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let bundle: Bundle = serde_json::from_str(json).map_err(|e| format!("Not a settings backup: {}", e))?;
+        if bundle.format != FORMAT {
+            return Err("Not a settings backup".into());
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(format!("Backup version {} is newer than this app supports", bundle.version));
+        }
+        Ok(bundle)
+    }
+}
+
+/// Split prefs into (shareable prefs, API keys), dropping machine-local entries
+pub fn split_prefs(prefs: Map<String, Value>) -> (Map<String, Value>, Map<String, Value>) {
+    let mut shared = Map::new();
+    let mut secrets = Map::new();
+    for (key, value) in prefs {
+        if SECRET_KEYS.contains(&key.as_str()) {
+            if value.as_str().is_some_and(|v| !v.is_empty()) {
+                secrets.insert(key, value);
+            }
+        } else if !LOCAL_KEYS.contains(&key.as_str()) {
+            shared.insert(key, value);
+        }
+    }
+    (shared, secrets)
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key, String> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default().hash_password_into(passphrase.as_bytes(), salt, &mut key).map_err(|e| e.to_string())?;
+    Ok(Key::from(key))
+}
+
+/// Encrypt `secrets` with `passphrase`
+pub fn seal(secrets: &Map<String, Value>, passphrase: &str) -> Result<SealedKeys, String> {
+    if passphrase.is_empty() {
+        return Err("A passphrase is needed to include API keys".into());
+    }
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    getrandom::getrandom(&mut salt).map_err(|e| e.to_string())?;
+    getrandom::getrandom(&mut nonce).map_err(|e| e.to_string())?;
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plain = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plain.as_slice()).map_err(|_| "Could not encrypt the API keys".to_string())?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Ok(SealedKeys { salt: b64.encode(salt), nonce: b64.encode(nonce), ciphertext: b64.encode(ciphertext) })
+}
+
+/// Decrypt sealed API keys; a wrong passphrase and a damaged file look the same
+pub fn open(sealed: &SealedKeys, passphrase: &str) -> Result<Map<String, Value>, String> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let damaged = |_| "The API keys in this backup are damaged".to_string();
+    let salt = b64.decode(&sealed.salt).map_err(damaged)?;
+    let nonce = b64.decode(&sealed.nonce).map_err(damaged)?;
+    let ciphertext = b64.decode(&sealed.ciphertext).map_err(damaged)?;
+    if nonce.len() != NONCE_LEN {
+        return Err("The API keys in this backup are damaged".into());
+    }
+    let cipher = ChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let plain = cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice()).map_err(|_| "Wrong passphrase for the API keys".to_string())?;
+    let secrets: Map<String, Value> = serde_json::from_slice(&plain).map_err(|e| e.to_string())?;
+    // Only ever restore API keys from the sealed part
+    Ok(secrets.into_iter().filter(|(k, _)| SECRET_KEYS.contains(&k.as_str())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn prefs() -> Map<String, Value> {
+        json!({
+            "behavior": { "auto_paste": true },
+            "hotkey": "Ctrl+Shift+Space",
+            "deepgram_key": "dg-secret",
+            "openrouter_key": "",
+            "audio_device": "USB Mic",
+            "hud_positions": {},
+            "control_api_token": "local-token",
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    #[test]
+    fn test_split_prefs() {
+        let (shared, secrets) = split_prefs(prefs());
+        assert_eq!(shared.keys().collect::<Vec<_>>(), vec!["behavior", "hotkey"]);
+        assert_eq!(secrets, json!({ "deepgram_key": "dg-secret" }).as_object().unwrap().clone());
+    }
+
+    #[test]
+    fn test_control_api_token_stays_local() {
+        let (shared, secrets) = split_prefs(prefs());
+        assert!(!shared.contains_key("control_api_token"));
+        assert!(!secrets.contains_key("control_api_token"));
+        let json = serde_json::to_string(&Bundle::new("1.0.1", 0, prefs(), Map::new(), Map::new())).unwrap();
+        assert!(!json.contains("local-token"));
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let mut bundle = Bundle::new("1.0.1", 1_700_000_000, prefs(), Map::new(), Map::new());
+        assert!(!bundle.prefs.contains_key("deepgram_key"));
+        let (_, secrets) = split_prefs(prefs());
+        bundle.keys = Some(seal(&secrets, "correct horse").unwrap());
+        let json = serde_json::to_string(&bundle).unwrap();
+        assert!(!json.contains("dg-secret"));
+        let read = Bundle::from_json(&json).unwrap();
+        assert_eq!(read, bundle);
+        assert_eq!(open(read.keys.as_ref().unwrap(), "correct horse").unwrap(), secrets);
+        assert!(open(read.keys.as_ref().unwrap(), "wrong").unwrap_err().contains("Wrong passphrase"));
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(Bundle::from_json("{\"name\": \"legal\"}").is_err());
+        let mut bundle = Bundle::new("9.0.0", 0, Map::new(), Map::new(), Map::new());
+        bundle.version = BUNDLE_VERSION + 1;
+        assert!(Bundle::from_json(&serde_json::to_string(&bundle).unwrap()).unwrap_err().contains("newer"));
+        assert!(seal(&Map::new(), "").is_err());
+    }
+}
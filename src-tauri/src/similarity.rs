@@ -0,0 +1,119 @@
+// Word-level similarity between the raw transcript and the AI's refinement, used to reject
+// outputs that rewrote the text or added to it. Words are compared after dropping case,
+// punctuation and filler words and expanding contractions, so the edits refinement is supposed
+// to make ("um I do not know" -> "I don't know.") barely move the score.
+
+/// Dropped from both sides before comparing, since refinement removes them
+const FILLERS: &[&str] = &["um", "umm", "uh", "uhh", "er", "erm", "ah", "hmm", "mm"];
+
+/// Contractions whose stem isn't simply the word minus the suffix, or whose suffix is ambiguous
+const IRREGULAR: &[(&str, &str, &str)] = &[
+    ("can't", "can", "not"),
+    ("won't", "will", "not"),
+    ("it's", "it", "is"),
+    ("that's", "that", "is"),
+    ("what's", "what", "is"),
+    ("there's", "there", "is"),
+    ("he's", "he", "is"),
+    ("she's", "she", "is"),
+];
+
+/// Contraction suffixes and what they expand to
+const CONTRACTIONS: &[(&str, &str)] = &[
+    ("n't", "not"),
+    ("'re", "are"),
+    ("'ll", "will"),
+    ("'ve", "have"),
+    ("'m", "am"),
+    ("'d", "would"),
+];
+
+fn push_word(out: &mut Vec<String>, word: &str) {
+    let cleaned: String = word.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+    if !cleaned.is_empty() && !FILLERS.contains(&cleaned.as_str()) {
+        out.push(cleaned);
+    }
+}
+
+/// Comparable words of `text`
+pub fn normalize_words(text: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for raw in text.split_whitespace() {
+        let word = raw.to_lowercase().replace('\u{2019}', "'");
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'');
+        let (stem, expansion) = IRREGULAR
+            .iter()
+            .find(|(w, _, _)| *w == word)
+            .map(|(_, stem, rest)| (*stem, Some(*rest)))
+            .or_else(|| {
+                CONTRACTIONS
+                    .iter()
+                    .find(|(suffix, _)| word.len() > suffix.len() && word.ends_with(suffix))
+                    .map(|(suffix, rest)| (&word[..word.len() - suffix.len()], Some(*rest)))
+            })
+            .unwrap_or((word, None));
+        push_word(&mut out, stem);
+        if let Some(rest) = expansion {
+            push_word(&mut out, rest);
+        }
+    }
+    out
+}
+
+fn edit_distance(a: &[String], b: &[String]) -> usize {
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, wa) in a.iter().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, wb) in b.iter().enumerate() {
+            let cost = if wa == wb { 0 } else { 1 };
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// 1.0 when both texts have the same words, 0.0 when none line up (normalized word Levenshtein)
+pub fn word_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (normalize_words(a), normalize_words(b));
+    let len = a.len().max(b.len());
+    if len == 0 {
+        return 1.0;
+    }
+    1.0 - edit_distance(&a, &b) as f64 / len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_expands_contractions_and_drops_fillers() {
+        assert_eq!(normalize_words("Um, I don't know."), vec!["i", "do", "not", "know"]);
+        assert_eq!(normalize_words("It's fine, we'll see"), vec!["it", "is", "fine", "we", "will", "see"]);
+        assert_eq!(normalize_words("I can't"), vec!["i", "can", "not"]);
+    }
+
+    #[test]
+    fn test_cleanup_keeps_high_score() {
+        let raw = "um so i do not think we should uh ship it on friday";
+        let refined = "So I don't think we should ship it on Friday.";
+        assert_eq!(word_similarity(raw, refined), 1.0);
+        // A misheard word or two still scores well
+        assert!(word_similarity("their going to the store later today", "They're going to the store later today.") > 0.7);
+    }
+
+    #[test]
+    fn test_rewrite_and_additions_score_low() {
+        let raw = "send the report to john by friday";
+        assert!(word_similarity(raw, "Here is a polished email you can send to your manager about the quarterly numbers.") < 0.3);
+        let padded = "Send the report to John by Friday. Let me know if you need anything else, I am happy to help!";
+        assert!(word_similarity(raw, padded) < 0.5);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(word_similarity("", "um"), 1.0);
+        assert_eq!(word_similarity("hello", ""), 0.0);
+    }
+}
@@ -0,0 +1,238 @@
+// Spell mode formatter.
+// Converts letter-by-letter dictation ("alpha bravo capital charlie one two") into
+// literal characters ("abC12"). Useful for serial numbers, usernames and email addresses.
+// Runs after symbol replacement, so "at sign" and "dot" are already "@" and ".".
+
+/// NATO phonetic alphabet: (spoken word, letter)
+pub const NATO_ALPHABET: &[(&str, char)] = &[
+    ("alpha", 'a'),
+    ("alfa", 'a'),
+    ("bravo", 'b'),
+    ("charlie", 'c'),
+    ("delta", 'd'),
+    ("echo", 'e'),
+    ("foxtrot", 'f'),
+    ("golf", 'g'),
+    ("hotel", 'h'),
+    ("india", 'i'),
+    ("juliet", 'j'),
+    ("juliett", 'j'),
+    ("kilo", 'k'),
+    ("lima", 'l'),
+    ("mike", 'm'),
+    ("november", 'n'),
+    ("oscar", 'o'),
+    ("papa", 'p'),
+    ("quebec", 'q'),
+    ("romeo", 'r'),
+    ("sierra", 's'),
+    ("tango", 't'),
+    ("uniform", 'u'),
+    ("victor", 'v'),
+    ("whiskey", 'w'),
+    ("whisky", 'w'),
+    ("xray", 'x'),
+    ("yankee", 'y'),
+    ("zulu", 'z'),
+];
+
+/// Spoken digits: (word, digit)
+pub const DIGIT_WORDS: &[(&str, char)] = &[
+    ("zero", '0'),
+    ("oh", '0'),
+    ("one", '1'),
+    ("two", '2'),
+    ("three", '3'),
+    ("four", '4'),
+    ("five", '5'),
+    ("six", '6'),
+    ("seven", '7'),
+    ("eight", '8'),
+    ("nine", '9'),
+];
+
+/// Words that uppercase the next letter ("capital b" -> "B")
+const CAPITAL_PREFIXES: &[&str] = &["capital", "cap", "uppercase", "upper"];
+
+const SPELL_ON: &str = "spell mode on";
+const SPELL_OFF: &str = "spell mode off";
+
+fn lookup(table: &[(&str, char)], word: &str) -> Option<char> {
+    table.iter().find(|(w, _)| *w == word).map(|(_, c)| *c)
+}
+
+/// Spell out a whole region: every recognized word becomes one character, spaces are dropped.
+pub fn spell_out(text: &str) -> String {
+    let mut out = String::new();
+    let mut capitalize_next = false;
+
+    for token in text.split_whitespace() {
+        // STT providers sprinkle commas between spelled letters; they are never meant literally
+        let token = token.replace(',', "");
+        let lower = token.to_lowercase();
+
+        if CAPITAL_PREFIXES.contains(&lower.as_str()) {
+            capitalize_next = true;
+            continue;
+        }
+        if lower == "space" {
+            out.push(' ');
+            continue;
+        }
+        // "x-ray" contains a hyphen, so handle it before splitting on symbols
+        let mut token = token.as_str();
+        if lower.starts_with("x-ray") {
+            push_letter(&mut out, 'x', &mut capitalize_next);
+            token = &token["x-ray".len()..];
+        }
+
+        // Split into alphanumeric runs and symbol characters ("bravo@" -> "bravo", "@")
+        let mut run = String::new();
+        for ch in token.chars().chain(std::iter::once(' ')) {
+            if ch.is_alphanumeric() {
+                run.push(ch);
+                continue;
+            }
+            if !run.is_empty() {
+                push_word(&mut out, &run, &mut capitalize_next);
+                run.clear();
+            }
+            if ch != ' ' {
+                out.push(ch);
+            }
+        }
+    }
+
+    // A sentence-final period/question mark added by STT is noise at the end of a spelled run
+    while out.ends_with(['.', '?', '!']) {
+        out.pop();
+    }
+    out
+}
+
+fn push_letter(out: &mut String, c: char, capitalize_next: &mut bool) {
+    if *capitalize_next {
+        out.extend(c.to_uppercase());
+        *capitalize_next = false;
+    } else {
+        out.push(c);
+    }
+}
+
+fn push_word(out: &mut String, word: &str, capitalize_next: &mut bool) {
+    let lower = word.to_lowercase();
+    if let Some(c) = lookup(NATO_ALPHABET, &lower) {
+        push_letter(out, c, capitalize_next);
+    } else if let Some(d) = lookup(DIGIT_WORDS, &lower) {
+        out.push(d);
+    } else if word.chars().count() == 1 {
+        // Bare letters ("A", "b") come back capitalized from STT; only "capital" uppercases
+        let c = lower.chars().next().unwrap_or_default();
+        push_letter(out, c, capitalize_next);
+    } else {
+        // Digits and unknown words pass through literally ("com", "42")
+        if *capitalize_next {
+            out.push_str(&capitalize(word));
+            *capitalize_next = false;
+        } else {
+            out.push_str(word);
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Find `phrase` case-insensitively as whole words ("spell mode online" is not a command),
+/// tolerating STT commas/periods after it. Returns (start, end) byte offsets in `text`.
+fn find_command(text: &str, phrase: &str) -> Option<(usize, usize)> {
+    // ASCII lowercase keeps byte offsets identical to the original text
+    let lower = text.to_ascii_lowercase();
+    let is_word = |c: char| c.is_alphanumeric() || c == '\'';
+    let (start, _) = lower.match_indices(phrase).find(|&(i, _)| {
+        !lower[..i].chars().next_back().is_some_and(is_word)
+            && !lower[i + phrase.len()..].chars().next().is_some_and(is_word)
+    })?;
+    let mut end = start + phrase.len();
+    while end < text.len() && matches!(text.as_bytes()[end], b',' | b'.') {
+        end += 1;
+    }
+    Some((start, end))
+}
+
+/// Apply spell mode to a transcript.
+/// With `always` set (spell mode pref) the whole transcript is spelled; otherwise only
+/// regions between "spell mode on" and "spell mode off" (or the end of the text) are.
+pub fn apply_spell_mode(text: &str, always: bool) -> String {
+    if always {
+        return spell_out(text);
+    }
+
+    let mut result = String::new();
+    let mut remaining = text;
+    while let Some((on_start, on_end)) = find_command(remaining, SPELL_ON) {
+        result.push_str(remaining[..on_start].trim_end_matches([' ', ',']));
+        let after_on = &remaining[on_end..];
+        let (region, rest) = match find_command(after_on, SPELL_OFF) {
+            Some((off_start, off_end)) => (&after_on[..off_start], &after_on[off_end..]),
+            None => (after_on, ""),
+        };
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.push_str(&spell_out(region));
+        remaining = rest;
+    }
+    result.push_str(remaining);
+    result.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nato_letters() {
+        assert_eq!(spell_out("alpha bravo charlie"), "abc");
+        assert_eq!(spell_out("Alpha, Bravo, X-ray."), "abx");
+    }
+
+    #[test]
+    fn test_capitals_and_digits() {
+        assert_eq!(spell_out("capital bravo one two three"), "B123");
+        assert_eq!(spell_out("capital B a 4 2"), "Ba42");
+    }
+
+    #[test]
+    fn test_email_after_symbol_layer() {
+        // "at sign" and "dot" have already been replaced by the symbols layer
+        assert_eq!(spell_out("juliet delta@ example. com"), "jd@example.com");
+    }
+
+    #[test]
+    fn test_voice_command_regions() {
+        assert_eq!(
+            apply_spell_mode("my code is spell mode on kilo nine nine spell mode off thanks", false),
+            "my code is k99 thanks"
+        );
+        assert_eq!(apply_spell_mode("Spell mode on, alpha, bravo.", false), "ab");
+        assert_eq!(apply_spell_mode("no commands here", false), "no commands here");
+        assert_eq!(apply_spell_mode("lima mike", true), "lm");
+    }
+
+    #[test]
+    fn test_commands_need_word_boundaries() {
+        assert_eq!(find_command("spell mode online", SPELL_ON), None);
+        assert_eq!(find_command("misspell mode on", SPELL_ON), None);
+        assert_eq!(find_command("misspell mode on, then spell mode on. x", SPELL_ON), Some((23, 37)));
+        assert_eq!(
+            apply_spell_mode("the spell mode online docs spell mode on alpha", false),
+            "the spell mode online docs a"
+        );
+    }
+}
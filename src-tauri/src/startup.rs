@@ -0,0 +1,87 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+// Startup timing: how long each phase took and when the app became usable.
+// Phases are measured in the backend, except hotkey registration, which happens in the
+// Settings webview and is reported back via `report_hotkey_registered`.
+
+static PROCESS_START: OnceLock<Instant> = OnceLock::new();
+static METRICS: Mutex<StartupMetrics> = Mutex::new(StartupMetrics { phases: Vec::new(), tray_ready_ms: None, hotkey_ready_ms: None, deferred_done_ms: None });
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Phase {
+  pub name: String,
+  /// Offset from process start
+  pub start_ms: u64,
+  pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StartupMetrics {
+  pub phases: Vec<Phase>,
+  /// Tray built and menu responsive
+  pub tray_ready_ms: Option<u64>,
+  /// Global hotkey registered, i.e. the first hotkey press works
+  pub hotkey_ready_ms: Option<u64>,
+  /// Deferred, non-critical startup work finished
+  pub deferred_done_ms: Option<u64>,
+}
+
+/// Mark process start; call first thing in `run`
+pub fn begin() {
+  let _ = PROCESS_START.set(Instant::now());
+}
+
+fn since_start(at: Instant) -> u64 {
+  PROCESS_START.get().map(|s| at.saturating_duration_since(*s).as_millis() as u64).unwrap_or(0)
+}
+
+/// Milliseconds since process start
+pub fn elapsed_ms() -> u64 {
+  since_start(Instant::now())
+}
+
+/// Record a phase that ended now and took `duration`
+pub fn record(name: &str, duration: Duration) {
+  let end = elapsed_ms();
+  let duration_ms = duration.as_millis() as u64;
+  info!("Startup phase {}: {}ms (done at {}ms)", name, duration_ms, end);
+  METRICS.lock().unwrap().phases.push(Phase { name: name.to_string(), start_ms: end.saturating_sub(duration_ms), duration_ms });
+}
+
+/// Run `f` and record it as a startup phase
+pub fn phase<T>(name: &str, f: impl FnOnce() -> T) -> T {
+  let started = Instant::now();
+  let out = f();
+  record(name, started.elapsed());
+  out
+}
+
+pub fn mark_tray_ready() {
+  let now = elapsed_ms();
+  info!("Tray ready at {}ms", now);
+  METRICS.lock().unwrap().tray_ready_ms = Some(now);
+}
+
+/// Only the first registration counts; re-registering from Settings later is not startup
+pub fn mark_hotkey_ready() {
+  let now = elapsed_ms();
+  let mut metrics = METRICS.lock().unwrap();
+  if metrics.hotkey_ready_ms.is_none() {
+    info!("Hotkey ready at {}ms", now);
+    metrics.hotkey_ready_ms = Some(now);
+  }
+}
+
+pub fn mark_deferred_done() {
+  let now = elapsed_ms();
+  info!("Deferred startup work done at {}ms", now);
+  METRICS.lock().unwrap().deferred_done_ms = Some(now);
+}
+
+pub fn metrics() -> StartupMetrics {
+  METRICS.lock().unwrap().clone()
+}
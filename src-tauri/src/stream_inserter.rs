@@ -0,0 +1,122 @@
+// Streaming insertion (`stream_insert`): the transcript is typed into the target while the user
+// is still speaking. Final segments are committed as they arrive; the current interim segment is
+// typed after them and, when the provider revises it, backspaced over only as far as it changed.
+// When the dictation ends the whole typed text is reconciled with the final (refined) text the
+// same way. This module tracks what has been typed and plans the keystrokes; typing is up to the
+// caller.
+
+use crate::keymacro::{MacroKey, Step};
+
+#[derive(Debug, Default)]
+pub struct StreamInserter {
+    /// Final segments typed so far
+    committed: String,
+    /// Interim text typed after them, which the provider may still revise
+    interim: String,
+}
+
+/// Keystrokes turning `typed` into `wanted`: backspace over everything after their common prefix,
+/// then type the rest of `wanted`
+pub fn plan(typed: &str, wanted: &str) -> Vec<Step> {
+    let common = typed.chars().zip(wanted.chars()).take_while(|(a, b)| a == b).count();
+    let erase = typed.chars().count() - common;
+    let mut steps = vec![Step::Key(MacroKey::Backspace); erase];
+    let rest: String = wanted.chars().skip(common).collect();
+    if !rest.is_empty() {
+        steps.push(Step::Text(rest));
+    }
+    steps
+}
+
+impl StreamInserter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything typed into the target so far
+    pub fn typed(&self) -> String {
+        format!("{}{}", self.committed, self.interim)
+    }
+
+    /// `text` as typed after the committed text: separated by a space, except before punctuation
+    fn spaced(&self, text: &str) -> String {
+        let text = text.trim();
+        let needs_space = !self.committed.is_empty()
+            && !self.committed.ends_with(char::is_whitespace)
+            && !text.starts_with(|c: char| ",.;:!?)".contains(c));
+        if text.is_empty() {
+            String::new()
+        } else if needs_space {
+            format!(" {}", text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// A segment arrived from the provider: the keystrokes that bring the interim text in line
+    /// with it. A final segment is committed and won't be revised again.
+    pub fn update(&mut self, text: &str, is_final: bool) -> Vec<Step> {
+        let wanted = self.spaced(text);
+        let steps = plan(&self.interim, &wanted);
+        if is_final {
+            self.committed.push_str(&wanted);
+            self.interim.clear();
+        } else {
+            self.interim = wanted;
+        }
+        steps
+    }
+
+    /// The dictation ended with `text`: the keystrokes that turn everything typed into it
+    pub fn finish(&mut self, text: &str) -> Vec<Step> {
+        let steps = plan(&self.typed(), text);
+        self.committed = text.to_string();
+        self.interim.clear();
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backspaces(n: usize) -> Vec<Step> {
+        vec![Step::Key(MacroKey::Backspace); n]
+    }
+
+    #[test]
+    fn test_plan() {
+        assert_eq!(plan("", "hello"), vec![Step::Text("hello".into())]);
+        assert_eq!(plan("hello", "hello"), vec![]);
+        assert_eq!(plan("hello word", "hello world"), [backspaces(1), vec![Step::Text("ld".into())]].concat());
+        assert_eq!(plan("café", "cafe"), [backspaces(1), vec![Step::Text("e".into())]].concat());
+        assert_eq!(plan("abc", ""), backspaces(3));
+    }
+
+    #[test]
+    fn test_interim_revisions_and_finals() {
+        let mut s = StreamInserter::new();
+        assert_eq!(s.update("hello", false), vec![Step::Text("hello".into())]);
+        // Revised interim: only the changed tail is retyped
+        assert_eq!(s.update("hello there", false), vec![Step::Text(" there".into())]);
+        assert_eq!(s.update("hello their", false), [backspaces(2), vec![Step::Text("ir".into())]].concat());
+        assert_eq!(s.update("hello there", true), [backspaces(2), vec![Step::Text("re".into())]].concat());
+        assert_eq!(s.typed(), "hello there");
+        // The next segment starts after a space, except for punctuation
+        assert_eq!(s.update("how", false), vec![Step::Text(" how".into())]);
+        assert_eq!(s.update("", false), backspaces(4));
+        assert_eq!(s.update(", friend", true), vec![Step::Text(", friend".into())]);
+        assert_eq!(s.typed(), "hello there, friend");
+    }
+
+    #[test]
+    fn test_finish_reconciles_with_refined_text() {
+        let mut s = StreamInserter::new();
+        s.update("so um the meeting is at three", true);
+        let steps = s.finish("So the meeting is at 3.");
+        assert_eq!(steps[..29], backspaces(29)[..]);
+        assert_eq!(steps[29], Step::Text("So the meeting is at 3.".into()));
+        assert_eq!(s.typed(), "So the meeting is at 3.");
+        assert_eq!(s.finish("So the meeting is at 3."), vec![]);
+    }
+}
@@ -1,2 +1,943 @@
-// Optional Deepgram helpers could live here if moved from frontend.
+// Backend STT relay: the HUD captures PCM audio and hands it to Rust, which owns the provider
+// WebSocket. Owning the socket here lets us keep it alive during long pauses, notice half-open
+// connections quickly (no frames or pongs for HEARTBEAT_TIMEOUT), and reconnect transparently,
+// replaying audio the provider may not have received. It also watches link quality (ping RTT,
+// send stalls, queued audio) and steps down to 8kHz audio, then to buffered batch transcription,
+// rather than producing gap-ridden transcripts on a poor connection.
 
+use base64::Engine as _;
+use futures_util::{SinkExt, StreamExt};
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{info, warn};
+
+use crate::{dictionary, gain, http_client, http_retry, pii};
+use crate::stt_options::{self, DeepgramOptions, ElevenLabsOptions};
+use crate::denoise::Denoiser;
+use crate::hud_event::{emit_hud_event, HudEvent};
+use crate::transcript::{Segment, Word};
+use crate::vad::Vad;
+
+/// Deepgram closes idle streams after ~10s without data, so send KeepAlive well before that
+const KEEPALIVE_IDLE: Duration = Duration::from_secs(4);
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+/// No frames at all (including pongs) for this long means the connection is half-open
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(12);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for final results after the HUD asks to finish
+const FINALIZE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RECONNECTS: u32 = 5;
+/// Audio kept for replay after a reconnect: 10s of 16kHz mono linear16
+const RESUME_BUFFER_BYTES: usize = 16_000 * 2 * 10;
+/// Rate the HUD captures at
+pub const SAMPLE_RATE: u32 = 16_000;
+/// Rate used on a degraded connection (half the bandwidth)
+pub const DEGRADED_SAMPLE_RATE: u32 = 8_000;
+/// Ping RTT above this counts as a bad sample
+const RTT_DEGRADED_MS: f64 = 800.0;
+/// A 4096-sample chunk is 256ms of audio; taking longer than this to send means we fall behind
+const SEND_STALL_MS: f64 = 250.0;
+/// More than 2s of audio waiting to be sent
+const BACKLOG_DEGRADED_BYTES: usize = 16_000 * 2 * 2;
+/// Consecutive bad seconds before dropping to 8kHz, and then to batch mode
+const DEGRADE_AFTER_BAD_TICKS: u32 = 3;
+const BATCH_AFTER_BAD_TICKS: u32 = 6;
+/// Consecutive good seconds on 8kHz before returning to full quality
+const RECOVER_AFTER_GOOD_TICKS: u32 = 30;
+/// Cap on audio held for batch transcription: 10 minutes
+pub const BATCH_MAX_BYTES: usize = 16_000 * 2 * 600;
+const BATCH_TIMEOUT: Duration = Duration::from_secs(60);
+/// Recorded files can be long; the provider needs time to get through them
+const FILE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Audio file types the prerecorded endpoints accept, by extension
+pub const AUDIO_FILE_TYPES: &[(&str, &str)] = &[
+  ("wav", "audio/wav"),
+  ("mp3", "audio/mpeg"),
+  ("m4a", "audio/mp4"),
+  ("mp4", "audio/mp4"),
+  ("ogg", "audio/ogg"),
+  ("opus", "audio/ogg"),
+  ("flac", "audio/flac"),
+  ("webm", "audio/webm"),
+  ("aac", "audio/aac"),
+];
+
+/// MIME type of an audio file, None for files that aren't audio
+pub fn audio_content_type(path: &std::path::Path) -> Option<&'static str> {
+  let ext = path.extension()?.to_str()?.to_lowercase();
+  AUDIO_FILE_TYPES.iter().find(|(e, _)| *e == ext).map(|(_, mime)| *mime)
+}
+
+/// What is being sent to a prerecorded endpoint
+#[derive(Clone, Copy)]
+enum BatchAudio<'a> {
+  /// Raw linear16 PCM at SAMPLE_RATE, as captured by the relay
+  Pcm,
+  /// An encoded file (wav, mp3, ...)
+  File { content_type: &'a str, file_name: &'a str },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SttProvider {
+  Deepgram,
+  ElevenLabs,
+}
+
+impl SttProvider {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      SttProvider::Deepgram => "deepgram",
+      SttProvider::ElevenLabs => "elevenlabs",
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub struct RelayConfig {
+  pub provider: SttProvider,
+  pub key: String,
+  /// "auto" or a primary subtag, see `stt_options::normalize_language`
+  pub language: String,
+  /// Dictionary words to boost (Deepgram only)
+  pub keywords: Vec<String>,
+  /// Stop the dictation after this much silence following speech (0 = never)
+  pub silence_stop_ms: u32,
+  /// Scale the relayed audio toward a steady speech level
+  pub auto_gain: bool,
+  /// Run the relayed audio through RNNoise
+  pub denoise: bool,
+  /// Meeting transcription: speakers are told apart (Deepgram) and final segments go to the
+  /// meeting transcript
+  pub meeting: bool,
+  /// Model and formatting (Deepgram only)
+  pub deepgram: DeepgramOptions,
+  /// Realtime model (ElevenLabs only)
+  pub elevenlabs: ElevenLabsOptions,
+  /// Categories Deepgram masks itself, on top of the masking before paste
+  pub redact: Vec<String>,
+  /// Self-hosted Deepgram base URL, see `stt_options::normalize_endpoint`; empty for the hosted API
+  pub endpoint: String,
+}
+
+impl RelayConfig {
+  pub fn deepgram(key: &str, language: &str) -> Self {
+    Self { provider: SttProvider::Deepgram, key: key.to_string(), language: language.to_string(), keywords: Vec::new(), silence_stop_ms: 0, auto_gain: false, denoise: false, meeting: false, deepgram: DeepgramOptions::default(), elevenlabs: ElevenLabsOptions::default(), redact: Vec::new(), endpoint: String::new() }
+  }
+
+  pub fn elevenlabs(key: &str, language: &str) -> Self {
+    Self { provider: SttProvider::ElevenLabs, key: key.to_string(), language: language.to_string(), keywords: Vec::new(), silence_stop_ms: 0, auto_gain: false, denoise: false, meeting: false, deepgram: DeepgramOptions::default(), elevenlabs: ElevenLabsOptions::default(), redact: Vec::new(), endpoint: String::new() }
+  }
+
+  pub fn with_keywords(mut self, keywords: Vec<String>) -> Self {
+    self.keywords = keywords;
+    self
+  }
+
+  pub fn with_silence_stop(mut self, secs: u32) -> Self {
+    self.silence_stop_ms = secs.saturating_mul(1000);
+    self
+  }
+
+  pub fn with_auto_gain(mut self, auto_gain: bool) -> Self {
+    self.auto_gain = auto_gain;
+    self
+  }
+
+  pub fn with_denoise(mut self, denoise: bool) -> Self {
+    self.denoise = denoise;
+    self
+  }
+
+  pub fn with_meeting(mut self, meeting: bool) -> Self {
+    self.meeting = meeting;
+    self
+  }
+
+  pub fn with_deepgram_options(mut self, options: DeepgramOptions) -> Self {
+    self.deepgram = options;
+    self
+  }
+
+  pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+    self.endpoint = endpoint.to_string();
+    self
+  }
+
+  pub fn with_elevenlabs_options(mut self, options: ElevenLabsOptions) -> Self {
+    self.elevenlabs = options;
+    self
+  }
+
+  pub fn with_redaction(mut self, categories: Vec<String>) -> Self {
+    self.redact = categories;
+    self
+  }
+
+  fn stream_url(&self, sample_rate: u32) -> String {
+    match self.provider {
+      SttProvider::Deepgram => format!(
+        "{}/v1/listen?{}&{}&encoding=linear16&sample_rate={}&channels=1{}{}{}",
+        stt_options::deepgram_bases(&self.endpoint).1,
+        self.deepgram.query(true),
+        stt_options::deepgram_language_param(&self.language, true),
+        sample_rate,
+        dictionary::deepgram_params(&self.keywords),
+        if self.meeting { "&diarize=true" } else { "" },
+        pii::deepgram_params(&self.redact)
+      ),
+      SttProvider::ElevenLabs => format!(
+        "wss://api.elevenlabs.io/v1/speech-to-text/realtime?{}&commit_strategy=vad&audio_format=pcm_{}",
+        self.elevenlabs.realtime_query(&self.language),
+        sample_rate
+      ),
+    }
+  }
+
+  fn auth_header(&self) -> (&'static str, String) {
+    match self.provider {
+      SttProvider::Deepgram => ("authorization", format!("Token {}", self.key)),
+      SttProvider::ElevenLabs => ("xi-api-key", self.key.clone()),
+    }
+  }
+}
+
+/// Link quality the relay is currently operating at
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+  /// Streaming at 16kHz
+  Good,
+  /// Streaming at 8kHz to save bandwidth
+  Degraded,
+  /// Streaming abandoned; audio is buffered and transcribed in one request on finalize
+  Batch,
+}
+
+impl Quality {
+  fn sample_rate(self) -> u32 {
+    if self == Quality::Good { SAMPLE_RATE } else { DEGRADED_SAMPLE_RATE }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct QualityEvent {
+  pub session_id: u64,
+  pub quality: Quality,
+  pub rtt_ms: Option<u64>,
+}
+
+/// Rolling link measurements for one socket
+#[derive(Debug, Default)]
+struct LinkStats {
+  rtt_ms: Option<f64>,
+  send_ms: Option<f64>,
+  bad_ticks: u32,
+  good_ticks: u32,
+}
+
+impl LinkStats {
+  fn ewma(prev: Option<f64>, sample: f64) -> Option<f64> {
+    Some(prev.map(|p| p * 0.7 + sample * 0.3).unwrap_or(sample))
+  }
+
+  fn record_rtt(&mut self, d: Duration) { self.rtt_ms = Self::ewma(self.rtt_ms, d.as_secs_f64() * 1000.0); }
+
+  fn record_send(&mut self, d: Duration) { self.send_ms = Self::ewma(self.send_ms, d.as_secs_f64() * 1000.0); }
+
+  /// Score the last second; `outstanding_ping` is how long an unanswered ping has been waiting
+  fn assess(&mut self, backlog_bytes: usize, outstanding_ping: Option<Duration>) {
+    let slow_pong = outstanding_ping.map(|d| d.as_secs_f64() * 1000.0 > RTT_DEGRADED_MS).unwrap_or(false);
+    let bad = self.rtt_ms.map(|r| r > RTT_DEGRADED_MS).unwrap_or(false)
+      || self.send_ms.map(|s| s > SEND_STALL_MS).unwrap_or(false)
+      || backlog_bytes > BACKLOG_DEGRADED_BYTES
+      || slow_pong;
+    if bad {
+      self.bad_ticks += 1;
+      self.good_ticks = 0;
+    } else {
+      self.good_ticks += 1;
+      self.bad_ticks = 0;
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TranscriptEvent {
+  pub session_id: u64,
+  pub text: String,
+  pub is_final: bool,
+  /// Timing relative to the current provider connection (it restarts after a reconnect)
+  pub start_ms: Option<u64>,
+  pub end_ms: Option<u64>,
+  pub confidence: Option<f32>,
+  pub words: Vec<Word>,
+}
+
+impl TranscriptEvent {
+  fn new(session_id: u64, segment: Segment, is_final: bool) -> Self {
+    TranscriptEvent {
+      session_id,
+      text: segment.text,
+      is_final,
+      start_ms: segment.start_ms,
+      end_ms: segment.end_ms,
+      confidence: segment.confidence,
+      words: segment.words,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct StatusEvent {
+  pub session_id: u64,
+  /// "connecting" | "open" | "reconnecting" | "closed" | "error"
+  pub status: &'static str,
+  pub detail: Option<String>,
+}
+
+enum RelayCmd {
+  Audio(Vec<u8>),
+  Finalize,
+  Close,
+}
+
+struct RelayHandle {
+  session_id: u64,
+  tx: mpsc::UnboundedSender<RelayCmd>,
+  app: AppHandle,
+  /// Level and voice activity of the relayed audio
+  vad: Vad,
+  /// Clipping and low-level checks, and the auto-gain stage
+  levels: gain::LevelCheck,
+  /// RNNoise stage, when on
+  denoiser: Option<Denoiser>,
+  silence_stop_ms: u32,
+  /// Finalize was sent (by the HUD or after silence); later requests are ignored
+  finalizing: bool,
+}
+
+static RELAY: Mutex<Option<RelayHandle>> = Mutex::new(None);
+/// Audio handed to the relay but not yet taken off the queue by the session task
+static QUEUED_BYTES: AtomicUsize = AtomicUsize::new(0);
+/// The dictation is paused: audio from the HUD is dropped and the socket only kept alive
+static PAUSED: AtomicBool = AtomicBool::new(false);
+/// What keeps an ElevenLabs stream open while paused, as it has no KeepAlive message: 100ms of silence
+const SILENCE_CHUNK_BYTES: usize = SAMPLE_RATE as usize / 10 * 2;
+
+type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+fn emit_status(app: &AppHandle, session_id: u64, status: &'static str, detail: Option<String>) {
+  info!("STT relay [{}] {}{}", session_id, status, detail.as_ref().map(|d| format!(": {}", d)).unwrap_or_default());
+  emit_hud_event(app, HudEvent::RelayStatus(StatusEvent { session_id, status, detail }));
+}
+
+/// Start a relay session, replacing any running one. Returns the session id used in events.
+pub fn start(app: &AppHandle, cfg: RelayConfig) -> u64 {
+  stop();
+  QUEUED_BYTES.store(0, Ordering::Relaxed);
+  PAUSED.store(false, Ordering::Relaxed);
+  crate::last_audio::begin();
+  let session_id = crate::history::now_millis();
+  let (tx, rx) = mpsc::unbounded_channel();
+  *RELAY.lock().unwrap() = Some(RelayHandle {
+    session_id,
+    tx,
+    app: app.clone(),
+    vad: Vad::default(),
+    levels: gain::LevelCheck::new(cfg.auto_gain),
+    denoiser: cfg.denoise.then(Denoiser::default),
+    silence_stop_ms: cfg.silence_stop_ms,
+    finalizing: false,
+  });
+  let app = app.clone();
+  tauri::async_runtime::spawn(async move { run_session(app, session_id, cfg, rx).await });
+  session_id
+}
+
+fn send_cmd(cmd: RelayCmd) -> Result<(), String> {
+  let guard = RELAY.lock().unwrap();
+  let handle = guard.as_ref().ok_or("No STT relay session running")?;
+  handle.tx.send(cmd).map_err(|_| "STT relay session has ended".to_string())
+}
+
+/// Queue linear16 PCM audio for the provider (through the RNNoise and auto-gain stages, when on),
+/// reporting its level, voice activity and level problems to the HUD
+pub fn push_audio(bytes: Vec<u8>) -> Result<(), String> {
+  // Nothing said while paused reaches the provider, the recording or the silence stop
+  if PAUSED.load(Ordering::Relaxed) {
+    return Ok(());
+  }
+  let (app, was_speech, frames, warning, bytes, silence_stop) = {
+    let mut guard = RELAY.lock().unwrap();
+    let handle = guard.as_mut().ok_or("No STT relay session running")?;
+    // Kept as captured, so a retry or a saved file doesn't depend on the processing below
+    crate::last_audio::push(&bytes);
+    // Denoise first so the VAD and the level checks see what the provider will hear
+    let bytes = match handle.denoiser.as_mut() {
+      Some(denoiser) => denoiser.process(&bytes),
+      None => bytes,
+    };
+    let was_speech = handle.vad.is_speech();
+    let frames = handle.vad.push(&bytes);
+    let warning = handle.levels.observe(&bytes, &frames).map(|w| (w.code(), w.message(handle.levels.is_auto())));
+    let gain = handle.levels.gain();
+    let bytes = if gain == 1.0 { bytes } else { gain::apply_gain(&bytes, gain) };
+    // Silence only counts once the user has said something, so a slow start isn't cut off
+    let silence_stop = handle.silence_stop_ms > 0
+      && !handle.finalizing
+      && handle.vad.heard_speech()
+      && handle.vad.silence_ms() >= handle.silence_stop_ms;
+    (handle.app.clone(), was_speech, frames, warning, bytes, silence_stop)
+  };
+  // The denoiser holds back partial frames; an empty message would close a Deepgram stream
+  if !bytes.is_empty() {
+    let len = bytes.len();
+    send_cmd(RelayCmd::Audio(bytes))?;
+    QUEUED_BYTES.fetch_add(len, Ordering::Relaxed);
+  }
+
+  let mut speech = was_speech;
+  for frame in frames {
+    emit_hud_event(&app, HudEvent::Level { db: frame.db });
+    if frame.speech != speech {
+      speech = frame.speech;
+      emit_hud_event(&app, HudEvent::SpeechActive { active: speech });
+    }
+  }
+  if let Some((code, message)) = warning {
+    warn!("Input level: {}", message);
+    emit_hud_event(&app, HudEvent::LevelWarning { code: code.to_string(), message: message.to_string() });
+  }
+
+  // Auto-stop happens here rather than in the HUD, so it works even if the webview stalls
+  if silence_stop {
+    finalize()?;
+    crate::stop_after_silence(&app);
+  }
+  Ok(())
+}
+
+fn dequeued(len: usize) {
+  let _ = QUEUED_BYTES.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |q| Some(q.saturating_sub(len)));
+}
+
+fn emit_quality(app: &AppHandle, session_id: u64, quality: Quality, rtt_ms: Option<f64>) {
+  info!("STT relay [{}] quality -> {:?} (rtt {:?}ms)", session_id, quality, rtt_ms.map(|r| r as u64));
+  emit_hud_event(app, HudEvent::RelayQuality(QualityEvent { session_id, quality, rtt_ms: rtt_ms.map(|r| r as u64) }));
+}
+
+/// Ask the provider to flush final results, then close
+pub fn finalize() -> Result<(), String> {
+  {
+    let mut guard = RELAY.lock().unwrap();
+    let handle = guard.as_mut().ok_or("No STT relay session running")?;
+    if std::mem::replace(&mut handle.finalizing, true) {
+      return Ok(());
+    }
+  }
+  send_cmd(RelayCmd::Finalize)
+}
+
+/// Close the running session immediately without waiting for results
+pub fn stop() {
+  if let Some(handle) = RELAY.lock().unwrap().take() {
+    let _ = handle.tx.send(RelayCmd::Close);
+  }
+}
+
+/// Drop relayed audio until resumed, keeping the provider connection open
+pub fn set_paused(paused: bool) {
+  PAUSED.store(paused, Ordering::Relaxed);
+}
+
+pub fn is_running() -> bool {
+  RELAY.lock().unwrap().is_some()
+}
+
+async fn connect(cfg: &RelayConfig, sample_rate: u32) -> Result<WsStream, String> {
+  let mut req = cfg.stream_url(sample_rate).into_client_request().map_err(|e| e.to_string())?;
+  let (name, value) = cfg.auth_header();
+  let value = HeaderValue::from_str(&value).map_err(|e| e.to_string())?;
+  req.headers_mut().insert(HeaderName::from_static(name), value);
+  let host = req.uri().host().unwrap_or_default().to_string();
+  let port = req.uri().port_u16().unwrap_or(if req.uri().scheme_str() == Some("ws") { 80 } else { 443 });
+  // Through the proxy prefs' proxy when one applies to this host
+  let connecting = async {
+    match http_client::tunnel(&host, port).await? {
+      Some(stream) => tokio_tungstenite::client_async_tls(req, stream).await.map_err(|e| e.to_string()),
+      None => tokio_tungstenite::connect_async(req).await.map_err(|e| e.to_string()),
+    }
+  };
+  let (ws, _) = tokio::time::timeout(CONNECT_TIMEOUT, connecting)
+    .await
+    .map_err(|_| "connect timed out".to_string())??;
+  Ok(ws)
+}
+
+/// Halve 16kHz linear16 to 8kHz by averaging sample pairs (a cheap low-pass before decimating)
+pub fn downsample_half(bytes: &[u8]) -> Vec<u8> {
+  let samples: Vec<i16> = bytes.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]])).collect();
+  samples
+    .chunks(2)
+    .flat_map(|pair| {
+      let avg = pair.iter().map(|&s| s as i32).sum::<i32>() / pair.len() as i32;
+      (avg as i16).to_le_bytes()
+    })
+    .collect()
+}
+
+fn audio_message(provider: SttProvider, bytes: &[u8], sample_rate: u32) -> Message {
+  let resampled;
+  let bytes = if sample_rate == SAMPLE_RATE { bytes } else { resampled = downsample_half(bytes); &resampled };
+  match provider {
+    SttProvider::Deepgram => Message::Binary(bytes.to_vec()),
+    SttProvider::ElevenLabs => Message::Text(
+      serde_json::json!({
+        "message_type": "input_audio_chunk",
+        "audio_base_64": base64::engine::general_purpose::STANDARD.encode(bytes),
+        "sample_rate": sample_rate,
+        "commit": false,
+      })
+      .to_string(),
+    ),
+  }
+}
+
+fn finalize_messages(provider: SttProvider, sample_rate: u32) -> Vec<Message> {
+  match provider {
+    SttProvider::Deepgram => vec![
+      Message::Text(r#"{"type":"Finalize"}"#.into()),
+      Message::Text(r#"{"type":"CloseStream"}"#.into()),
+    ],
+    SttProvider::ElevenLabs => vec![Message::Text(
+      serde_json::json!({ "message_type": "input_audio_chunk", "audio_base_64": "", "sample_rate": sample_rate, "commit": true }).to_string(),
+    )],
+  }
+}
+
+/// Extract a segment (with timing/confidence where the provider sends them) and is_final from a provider message
+pub fn parse_transcript(provider: SttProvider, raw: &str) -> Option<(Segment, bool)> {
+  let v: serde_json::Value = serde_json::from_str(raw).ok()?;
+  let (segment, is_final) = match provider {
+    SttProvider::Deepgram => {
+      if v["type"] != "Results" { return None; }
+      let alt = &v["channel"]["alternatives"][0];
+      let start = v["start"].as_f64();
+      let end = start.zip(v["duration"].as_f64()).map(|(s, d)| s + d);
+      let segment = Segment {
+        text: alt["transcript"].as_str()?.to_string(),
+        start_ms: start.map(|s| (s * 1000.0) as u64),
+        end_ms: end.map(|e| (e * 1000.0) as u64),
+        confidence: alt["confidence"].as_f64().map(|c| c as f32),
+        words: alt["words"].as_array().map(|words| words.iter().filter_map(parse_deepgram_word).collect()).unwrap_or_default(),
+        // Only with language=multi (code-switching) or detect_language
+        language: alt["languages"][0].as_str().or(v["channel"]["detected_language"].as_str()).map(str::to_string),
+      };
+      // Match the HUD client: a segment is final once Deepgram detects the end of speech
+      (segment, v["speech_final"].as_bool().unwrap_or(false))
+    }
+    SttProvider::ElevenLabs => {
+      let is_final = match v["message_type"].as_str()? {
+        "partial_transcript" => false,
+        "committed_transcript" => true,
+        _ => return None,
+      };
+      let segment = Segment {
+        text: v["text"].as_str()?.to_string(),
+        language: v["language_code"].as_str().map(str::to_string),
+        ..Default::default()
+      };
+      (segment, is_final)
+    }
+  };
+  if segment.text.trim().is_empty() { None } else { Some((segment, is_final)) }
+}
+
+/// Deepgram word entry: {"word", "punctuated_word", "start", "end", "confidence", "speaker"}
+/// (seconds; speaker only with diarization)
+fn parse_deepgram_word(w: &serde_json::Value) -> Option<Word> {
+  let text = w["punctuated_word"].as_str().or_else(|| w["word"].as_str())?;
+  Some(Word {
+    text: text.to_string(),
+    start_ms: (w["start"].as_f64()? * 1000.0) as u64,
+    end_ms: (w["end"].as_f64()? * 1000.0) as u64,
+    confidence: w["confidence"].as_f64().map(|c| c as f32),
+    estimated: false,
+    speaker: w["speaker"].as_u64().map(|s| s as u32),
+  })
+}
+
+/// Transcribe buffered 16kHz linear16 audio in one request (used when streaming is not viable)
+async fn transcribe_batch(cfg: &RelayConfig, audio: Vec<u8>) -> Result<String, String> {
+  transcribe_prerecorded(cfg, audio, BatchAudio::Pcm).await
+}
+
+/// Transcribe an audio file (see AUDIO_FILE_TYPES) in one request
+pub async fn transcribe_file(cfg: &RelayConfig, audio: Vec<u8>, content_type: &str, file_name: &str) -> Result<String, String> {
+  transcribe_prerecorded(cfg, audio, BatchAudio::File { content_type, file_name }).await
+}
+
+async fn transcribe_prerecorded(cfg: &RelayConfig, audio: Vec<u8>, input: BatchAudio<'_>) -> Result<String, String> {
+  let client = http_client::shared();
+  let (name, value) = cfg.auth_header();
+  let res = match cfg.provider {
+    SttProvider::Deepgram => {
+      let (url, content_type, timeout) = match input {
+        BatchAudio::Pcm => (
+          format!(
+            "{}/v1/listen?{}&{}&encoding=linear16&sample_rate={}&channels=1{}",
+            stt_options::deepgram_bases(&cfg.endpoint).0,
+            cfg.deepgram.query(false), stt_options::deepgram_language_param(&cfg.language, false), SAMPLE_RATE, dictionary::deepgram_params(&cfg.keywords)
+          ),
+          "application/octet-stream",
+          BATCH_TIMEOUT,
+        ),
+        // Deepgram reads the container's own format
+        BatchAudio::File { content_type, .. } => (
+          format!(
+            "{}/v1/listen?{}&{}{}",
+            stt_options::deepgram_bases(&cfg.endpoint).0,
+            cfg.deepgram.query(false),
+            stt_options::deepgram_language_param(&cfg.language, false),
+            dictionary::deepgram_params(&cfg.keywords)
+          ),
+          content_type,
+          FILE_TIMEOUT,
+        ),
+      };
+      let build = || client.post(&url).timeout(timeout).header(name, &value).header("Content-Type", content_type).body(audio.clone());
+      http_retry::send(cfg.provider.as_str(), build, |_| {}).await
+    }
+    SttProvider::ElevenLabs => {
+      // A multipart form is consumed by sending, so every attempt builds its own
+      let build = || {
+        let mut form = reqwest::multipart::Form::new().text("model_id", "scribe_v1");
+        if let Some(lang) = stt_options::elevenlabs_language(&cfg.language) {
+          form = form.text("language_code", lang.to_string());
+        }
+        let (form, file_name, timeout) = match input {
+          BatchAudio::Pcm => (form.text("file_format", "pcm_s16le_16"), "audio.pcm", BATCH_TIMEOUT),
+          BatchAudio::File { file_name, .. } => (form, file_name, FILE_TIMEOUT),
+        };
+        let form = form.part("file", reqwest::multipart::Part::bytes(audio.clone()).file_name(file_name.to_string()));
+        client.post("https://api.elevenlabs.io/v1/speech-to-text").timeout(timeout).header(name, &value).multipart(form)
+      };
+      http_retry::send(cfg.provider.as_str(), build, |_| {}).await
+    }
+  }
+  .map_err(|e| e.to_string())?;
+
+  if !res.status().is_success() {
+    let status = res.status();
+    let body = res.text().await.unwrap_or_default();
+    return Err(format!("batch transcription failed: {} {}", status, body));
+  }
+  let v: serde_json::Value = res.json().await.map_err(|e| e.to_string())?;
+  let text = match cfg.provider {
+    SttProvider::Deepgram => v["results"]["channels"][0]["alternatives"][0]["transcript"].as_str(),
+    SttProvider::ElevenLabs => v["text"].as_str(),
+  };
+  Ok(text.unwrap_or_default().trim().to_string())
+}
+
+/// Add audio to the replay buffer, dropping the oldest bytes past the cap
+fn buffer_audio(pending: &mut Vec<u8>, bytes: &[u8]) {
+  pending.extend_from_slice(bytes);
+  if pending.len() > RESUME_BUFFER_BYTES {
+    let excess = pending.len() - RESUME_BUFFER_BYTES;
+    pending.drain(..excess);
+  }
+}
+
+enum SocketEnd {
+  /// Session is over (closed by the HUD or finished after finalize)
+  Done,
+  /// Connection died; reconnect and resume
+  Lost(String),
+  /// Link quality changed; reconnect at the new level (or switch to batch)
+  Requalify(Quality),
+}
+
+async fn run_session(app: AppHandle, session_id: u64, cfg: RelayConfig, mut rx: mpsc::UnboundedReceiver<RelayCmd>) {
+  // Audio the provider may not have committed: everything sent since the last final result,
+  // plus anything captured while disconnected. Replayed after a reconnect.
+  let mut pending: Vec<u8> = Vec::new();
+  let mut finalize_requested = false;
+  let mut attempt: u32 = 0;
+  let mut quality = Quality::Good;
+
+  loop {
+    if quality == Quality::Batch {
+      run_batch(&app, session_id, &cfg, &mut rx, pending, finalize_requested).await;
+      break;
+    }
+    emit_status(&app, session_id, if attempt == 0 { "connecting" } else { "reconnecting" }, None);
+    let ws = match connect(&cfg, quality.sample_rate()).await {
+      Ok(ws) => ws,
+      Err(e) => {
+        attempt += 1;
+        if attempt > MAX_RECONNECTS {
+          crate::failures::record(&app, crate::failures::FailureKind::SttConnection, e.clone());
+          if pending.is_empty() && !finalize_requested {
+            emit_status(&app, session_id, "error", Some(e));
+            break;
+          }
+          // Audio is waiting; a single upload may still get through where streaming could not
+          quality = Quality::Batch;
+          emit_quality(&app, session_id, quality, None);
+          continue;
+        }
+        let backoff = Duration::from_millis(500 * 2u64.pow(attempt.min(4)));
+        warn!("STT relay connect failed ({}), retrying in {:?}", e, backoff);
+        // Keep buffering audio while we wait
+        let deadline = tokio::time::Instant::now() + backoff;
+        loop {
+          match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(RelayCmd::Audio(bytes))) => {
+              dequeued(bytes.len());
+              buffer_audio(&mut pending, &bytes);
+            }
+            Ok(Some(RelayCmd::Finalize)) => finalize_requested = true,
+            Ok(Some(RelayCmd::Close)) | Ok(None) => {
+              emit_status(&app, session_id, "closed", None);
+              return;
+            }
+            Err(_) => break,
+          }
+        }
+        continue;
+      }
+    };
+
+    emit_status(&app, session_id, "open", if attempt > 0 { Some(format!("resumed after {} attempt(s)", attempt)) } else { None });
+    attempt = 0;
+    match drive_socket(&app, session_id, &cfg, ws, quality, &mut rx, &mut pending, &mut finalize_requested).await {
+      SocketEnd::Done => break,
+      SocketEnd::Requalify(next) => {
+        quality = next;
+      }
+      SocketEnd::Lost(reason) => {
+        attempt += 1;
+        warn!("STT relay connection lost: {} (replaying {} bytes after reconnect)", reason, pending.len());
+        if attempt > MAX_RECONNECTS {
+          crate::failures::record(&app, crate::failures::FailureKind::SttConnection, reason.clone());
+          emit_status(&app, session_id, "error", Some(reason));
+          break;
+        }
+      }
+    }
+  }
+
+  {
+    let mut guard = RELAY.lock().unwrap();
+    if guard.as_ref().map(|h| h.session_id == session_id).unwrap_or(false) {
+      *guard = None;
+    }
+  }
+  emit_status(&app, session_id, "closed", None);
+}
+
+/// Buffer the rest of the session and transcribe it in one request when the HUD finalizes
+async fn run_batch(
+  app: &AppHandle,
+  session_id: u64,
+  cfg: &RelayConfig,
+  rx: &mut mpsc::UnboundedReceiver<RelayCmd>,
+  mut audio: Vec<u8>,
+  mut finalize_requested: bool,
+) {
+  info!("STT relay [{}] buffering audio for batch transcription", session_id);
+  while !finalize_requested {
+    match rx.recv().await {
+      Some(RelayCmd::Audio(bytes)) => {
+        dequeued(bytes.len());
+        if audio.len() + bytes.len() <= BATCH_MAX_BYTES {
+          audio.extend_from_slice(&bytes);
+        }
+      }
+      Some(RelayCmd::Finalize) => finalize_requested = true,
+      Some(RelayCmd::Close) | None => return,
+    }
+  }
+  if audio.is_empty() {
+    return;
+  }
+  let started = Instant::now();
+  let result = transcribe_batch(cfg, audio).await;
+  crate::latency::record(cfg.provider.as_str(), started.elapsed(), result.is_ok());
+  match result {
+    Ok(text) if !text.is_empty() => {
+      let segment = Segment { text, ..Default::default() };
+      if cfg.meeting {
+        crate::meeting::add_segment(app, &segment);
+      } else {
+        crate::paste::stream_segment(&segment.text, true);
+      }
+      emit_hud_event(app, HudEvent::transcript(TranscriptEvent::new(session_id, segment, true)));
+    }
+    Ok(_) => {}
+    Err(e) => {
+      crate::failures::record(app, crate::failures::FailureKind::SttConnection, e.clone());
+      emit_status(app, session_id, "error", Some(e));
+    }
+  }
+}
+
+async fn drive_socket(
+  app: &AppHandle,
+  session_id: u64,
+  cfg: &RelayConfig,
+  ws: WsStream,
+  quality: Quality,
+  rx: &mut mpsc::UnboundedReceiver<RelayCmd>,
+  pending: &mut Vec<u8>,
+  finalize_requested: &mut bool,
+) -> SocketEnd {
+  let (mut sink, mut stream) = ws.split();
+  let rate = quality.sample_rate();
+
+  // Resume: replay audio the previous connection may have dropped
+  for chunk in pending.chunks(8192) {
+    if let Err(e) = sink.send(audio_message(cfg.provider, chunk, rate)).await {
+      return SocketEnd::Lost(e.to_string());
+    }
+  }
+  let mut finalize_deadline: Option<Instant> = None;
+  if *finalize_requested {
+    for m in finalize_messages(cfg.provider, rate) { let _ = sink.send(m).await; }
+    finalize_deadline = Some(Instant::now() + FINALIZE_TIMEOUT);
+  }
+
+  let mut last_frame = Instant::now();
+  let mut last_audio = Instant::now();
+  let mut last_ping = Instant::now();
+  let mut ping_sent: Option<Instant> = None;
+  let mut link = LinkStats::default();
+  let mut tick = tokio::time::interval(Duration::from_secs(1));
+
+  loop {
+    tokio::select! {
+      cmd = rx.recv() => match cmd {
+        Some(RelayCmd::Audio(bytes)) => {
+          dequeued(bytes.len());
+          buffer_audio(pending, &bytes);
+          last_audio = Instant::now();
+          if let Err(e) = sink.send(audio_message(cfg.provider, &bytes, rate)).await {
+            return SocketEnd::Lost(e.to_string());
+          }
+          link.record_send(last_audio.elapsed());
+        }
+        Some(RelayCmd::Finalize) => {
+          *finalize_requested = true;
+          for m in finalize_messages(cfg.provider, rate) {
+            if let Err(e) = sink.send(m).await { return SocketEnd::Lost(e.to_string()); }
+          }
+          finalize_deadline = Some(Instant::now() + FINALIZE_TIMEOUT);
+        }
+        Some(RelayCmd::Close) | None => {
+          let _ = sink.send(Message::Close(None)).await;
+          return SocketEnd::Done;
+        }
+      },
+      msg = stream.next() => match msg {
+        Some(Ok(Message::Text(raw))) => {
+          last_frame = Instant::now();
+          if let Some((segment, is_final)) = parse_transcript(cfg.provider, &raw) {
+            // Interim frames can be revised and are lost with the connection; only a final
+            // result means the audio sent so far is committed and needn't be replayed
+            if is_final {
+              pending.clear();
+            }
+            if is_final && cfg.meeting {
+              crate::meeting::add_segment(app, &segment);
+            }
+            if !cfg.meeting {
+              crate::paste::stream_segment(&segment.text, is_final);
+            }
+            emit_hud_event(app, HudEvent::transcript(TranscriptEvent::new(session_id, segment, is_final)));
+            if is_final && *finalize_requested && cfg.provider == SttProvider::ElevenLabs {
+              let _ = sink.send(Message::Close(None)).await;
+              return SocketEnd::Done;
+            }
+          }
+        }
+        Some(Ok(Message::Close(frame))) => {
+          if *finalize_requested { return SocketEnd::Done; }
+          return SocketEnd::Lost(format!("closed by provider: {:?}", frame));
+        }
+        Some(Ok(Message::Pong(_))) => {
+          last_frame = Instant::now();
+          if let Some(sent) = ping_sent.take() { link.record_rtt(sent.elapsed()); }
+        }
+        Some(Ok(_)) => last_frame = Instant::now(), // ping/binary also prove liveness
+        Some(Err(e)) => {
+          if *finalize_requested { return SocketEnd::Done; }
+          return SocketEnd::Lost(e.to_string());
+        }
+        None => {
+          if *finalize_requested { return SocketEnd::Done; }
+          return SocketEnd::Lost("stream ended".into());
+        }
+      },
+      _ = tick.tick() => {
+        let now = Instant::now();
+        if finalize_deadline.map(|d| now > d).unwrap_or(false) {
+          warn!("STT relay finalize timed out, closing");
+          let _ = sink.send(Message::Close(None)).await;
+          return SocketEnd::Done;
+        }
+        if now.duration_since(last_frame) > HEARTBEAT_TIMEOUT {
+          return SocketEnd::Lost(format!("no frames for {:?} (half-open connection)", HEARTBEAT_TIMEOUT));
+        }
+        if now.duration_since(last_audio) > KEEPALIVE_IDLE {
+          let keepalive = match cfg.provider {
+            SttProvider::Deepgram => Some(Message::Text(r#"{"type":"KeepAlive"}"#.into())),
+            SttProvider::ElevenLabs if PAUSED.load(Ordering::Relaxed) => {
+              Some(audio_message(cfg.provider, &[0; SILENCE_CHUNK_BYTES], rate))
+            }
+            SttProvider::ElevenLabs => None,
+          };
+          if let Some(message) = keepalive {
+            if let Err(e) = sink.send(message).await {
+              return SocketEnd::Lost(e.to_string());
+            }
+            last_audio = now;
+          }
+        }
+        if now.duration_since(last_ping) > PING_INTERVAL {
+          if let Err(e) = sink.send(Message::Ping(Vec::new())).await {
+            return SocketEnd::Lost(e.to_string());
+          }
+          last_ping = now;
+          ping_sent = Some(now);
+        }
+
+        // Adapt to link quality; once finalizing, just let the session finish
+        if finalize_deadline.is_none() {
+          link.assess(QUEUED_BYTES.load(Ordering::Relaxed), ping_sent.map(|p| now.duration_since(p)));
+          let next = match quality {
+            Quality::Good if link.bad_ticks >= DEGRADE_AFTER_BAD_TICKS => Some(Quality::Degraded),
+            Quality::Degraded if link.bad_ticks >= BATCH_AFTER_BAD_TICKS => Some(Quality::Batch),
+            Quality::Degraded if link.good_ticks >= RECOVER_AFTER_GOOD_TICKS => Some(Quality::Good),
+            _ => None,
+          };
+          if let Some(next) = next {
+            emit_quality(app, session_id, next, link.rtt_ms);
+            let _ = sink.send(Message::Close(None)).await;
+            return SocketEnd::Requalify(next);
+          }
+        }
+      }
+    }
+  }
+}
@@ -0,0 +1,264 @@
+// Connection options for the STT providers, kept as one structured pref (`stt_options`) and turned
+// into request parameters wherever a connection is built: the backend relay, prerecorded and batch
+// requests, and the HUD's own streams. Each provider also has its own language, since their codes
+// differ: "auto" lets the provider detect it, otherwise it is a primary subtag ("en", "bn").
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Deepgram models offered in Settings
+pub const DEEPGRAM_MODELS: &[&str] = &["nova-2", "nova-3"];
+/// Deepgram rejects longer endpointing windows
+pub const MAX_ENDPOINTING_MS: u32 = 10_000;
+/// ElevenLabs realtime model used unless another variant is set
+pub const DEFAULT_SCRIBE_MODEL: &str = "scribe_v2_realtime";
+/// Language setting that leaves detection to the provider
+pub const AUTO_LANGUAGE: &str = "auto";
+/// ElevenLabs single-use tokens expire after 15 minutes
+pub const SCRIBE_TOKEN_TTL_SECS: u64 = 15 * 60;
+/// The hosted Deepgram API, used unless `stt_endpoint_override` is set
+pub const DEEPGRAM_API: &str = "https://api.deepgram.com";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct DeepgramOptions {
+    /// One of DEEPGRAM_MODELS
+    pub model: String,
+    /// Numbers, dates and paragraphs formatted by Deepgram
+    pub smart_format: bool,
+    pub punctuate: bool,
+    /// Silence that ends an utterance while streaming; None keeps Deepgram's default, 0 turns
+    /// endpointing off
+    pub endpointing_ms: Option<u32>,
+    /// Stream partial transcripts while speaking
+    pub interim_results: bool,
+}
+
+impl Default for DeepgramOptions {
+    fn default() -> Self {
+        Self { model: "nova-2".into(), smart_format: true, punctuate: true, endpointing_ms: None, interim_results: true }
+    }
+}
+
+impl DeepgramOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        if !DEEPGRAM_MODELS.contains(&self.model.as_str()) {
+            return Err(format!("Unknown Deepgram model: {} (use {})", self.model, DEEPGRAM_MODELS.join(", ")));
+        }
+        if self.endpointing_ms.is_some_and(|ms| ms > MAX_ENDPOINTING_MS) {
+            return Err(format!("Endpointing can be at most {}ms", MAX_ENDPOINTING_MS));
+        }
+        Ok(())
+    }
+
+    /// Query parameters, without a leading `&`; endpointing and interim results only apply to
+    /// streaming connections
+    pub fn query(&self, streaming: bool) -> String {
+        let mut params = vec![
+            format!("model={}", self.model),
+            format!("smart_format={}", self.smart_format),
+            format!("punctuate={}", self.punctuate),
+        ];
+        if streaming {
+            params.push(format!("interim_results={}", self.interim_results));
+            match self.endpointing_ms {
+                Some(0) => params.push("endpointing=false".into()),
+                Some(ms) => params.push(format!("endpointing={}", ms)),
+                None => {}
+            }
+        }
+        params.join("&")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ElevenLabsOptions {
+    /// Realtime Scribe model variant
+    pub model: String,
+}
+
+impl Default for ElevenLabsOptions {
+    fn default() -> Self {
+        Self { model: DEFAULT_SCRIBE_MODEL.into() }
+    }
+}
+
+impl ElevenLabsOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        // Goes into the connection URL as is
+        if self.model.is_empty() || !self.model.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("Invalid ElevenLabs model: {:?}", self.model));
+        }
+        Ok(())
+    }
+
+    /// Query parameters of a realtime connection in `language`, without a leading `&`
+    pub fn realtime_query(&self, language: &str) -> String {
+        if detected(language) {
+            format!("model_id={}", self.model)
+        } else {
+            format!("model_id={}&language_code={}", self.model, language)
+        }
+    }
+}
+
+fn detected(language: &str) -> bool {
+    language.is_empty() || language == AUTO_LANGUAGE
+}
+
+/// Check a language setting and bring it to the form the providers take: "auto" (also for an
+/// empty value and Deepgram's "multi") or the primary subtag, so "en-US" becomes "en"
+pub fn normalize_language(code: &str) -> Result<String, String> {
+    let code = code.trim().to_lowercase();
+    if detected(&code) || code == "multi" {
+        return Ok(AUTO_LANGUAGE.into());
+    }
+    let primary = code.split(['-', '_']).next().unwrap_or("");
+    if !(2..=3).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(format!("Not a language code: {:?} (use e.g. \"en\", \"bn\" or \"auto\")", code));
+    }
+    Ok(primary.to_string())
+}
+
+/// Deepgram's language parameter: "auto" streams with code-switching detection ("multi") and has
+/// prerecorded audio detect its one language
+pub fn deepgram_language_param(language: &str, streaming: bool) -> String {
+    match (detected(language), streaming) {
+        (true, true) => "language=multi".into(),
+        (true, false) => "detect_language=true".into(),
+        (false, _) => format!("language={}", language),
+    }
+}
+
+/// ElevenLabs' language_code, None to let Scribe detect it
+pub fn elevenlabs_language(language: &str) -> Option<&str> {
+    (!detected(language)).then_some(language)
+}
+
+/// What `test_elevenlabs` reports about a working key
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct ElevenLabsCheck {
+    /// Model and language hint the next connection will use
+    pub model: String,
+    pub language: Option<String>,
+    /// Transcriptions the plan allows at once, when ElevenLabs reports it
+    pub max_concurrent_requests: Option<u32>,
+    pub current_concurrent_requests: Option<u32>,
+    /// A single-use realtime token must be used within this
+    pub token_ttl_secs: u64,
+    /// Longest recording sent in one batch request when streaming falls back
+    pub max_batch_secs: u64,
+}
+
+/// Everything set through `set_stt_options`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SttOptions {
+    pub deepgram: DeepgramOptions,
+    pub elevenlabs: ElevenLabsOptions,
+    /// Base URL of a self-hosted Deepgram or a compatible proxy (https://dg.corp:8443), as
+    /// normalized by `normalize_endpoint`; empty for the hosted API
+    pub stt_endpoint_override: String,
+}
+
+impl SttOptions {
+    pub fn validate(&self) -> Result<(), String> {
+        self.deepgram.validate()?;
+        self.elevenlabs.validate()?;
+        normalize_endpoint(&self.stt_endpoint_override).map(|_| ())
+    }
+}
+
+/// A Deepgram base URL as saved: an http or https scheme (ws and wss are taken as their
+/// counterparts), no trailing slash and no /v1 or /v1/listen path, which the requests add.
+/// Empty stays empty.
+pub fn normalize_endpoint(url: &str) -> Result<String, String> {
+    let url = url.trim();
+    if url.is_empty() {
+        return Ok(String::new());
+    }
+    let (scheme, rest) = url.split_once("://").ok_or("The Deepgram endpoint needs http:// or https://")?;
+    let scheme = match scheme.to_lowercase().as_str() {
+        "https" | "wss" => "https",
+        "http" | "ws" => "http",
+        other => return Err(format!("Unsupported scheme \"{}\" for the Deepgram endpoint", other)),
+    };
+    let mut rest = rest.trim_end_matches('/');
+    for suffix in ["/v1/listen", "/v1"] {
+        rest = rest.strip_suffix(suffix).unwrap_or(rest);
+    }
+    let host = rest.split('/').next().unwrap_or_default();
+    if host.is_empty() || rest.contains(['?', '#']) || rest.contains(char::is_whitespace) {
+        return Err(format!("Not a valid Deepgram endpoint: {}", url));
+    }
+    Ok(format!("{}://{}", scheme, rest))
+}
+
+/// Base URLs for Deepgram HTTP requests and WebSocket streams, from a normalized endpoint
+pub fn deepgram_bases(endpoint: &str) -> (String, String) {
+    let http = if endpoint.is_empty() { DEEPGRAM_API } else { endpoint };
+    (http.to_string(), http.replacen("http", "ws", 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deepgram_query() {
+        let defaults = DeepgramOptions::default();
+        assert_eq!(defaults.query(true), "model=nova-2&smart_format=true&punctuate=true&interim_results=true");
+        assert_eq!(defaults.query(false), "model=nova-2&smart_format=true&punctuate=true");
+        let tuned = DeepgramOptions { model: "nova-3".into(), smart_format: false, endpointing_ms: Some(300), interim_results: false, ..defaults.clone() };
+        assert_eq!(tuned.query(true), "model=nova-3&smart_format=false&punctuate=true&interim_results=false&endpointing=300");
+        assert_eq!(DeepgramOptions { endpointing_ms: Some(0), ..defaults }.query(true), "model=nova-2&smart_format=true&punctuate=true&interim_results=true&endpointing=false");
+    }
+
+    #[test]
+    fn test_validate_and_partial_prefs() {
+        let opts: SttOptions = serde_json::from_str(r#"{"deepgram":{"model":"nova-3"}}"#).unwrap();
+        assert_eq!(opts.deepgram, DeepgramOptions { model: "nova-3".into(), ..Default::default() });
+        assert!(opts.validate().is_ok());
+        assert!(DeepgramOptions { model: "whisper".into(), ..Default::default() }.validate().is_err());
+        assert!(DeepgramOptions { endpointing_ms: Some(60_000), ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_endpoint_override() {
+        assert_eq!(normalize_endpoint("  ").unwrap(), "");
+        assert_eq!(normalize_endpoint("https://dg.corp:8443/").unwrap(), "https://dg.corp:8443");
+        assert_eq!(normalize_endpoint("wss://dg.corp/v1/listen").unwrap(), "https://dg.corp");
+        assert_eq!(normalize_endpoint("http://10.0.0.5:8080/deepgram/v1").unwrap(), "http://10.0.0.5:8080/deepgram");
+        assert!(normalize_endpoint("dg.corp").is_err());
+        assert!(normalize_endpoint("ftp://dg.corp").is_err());
+        assert!(normalize_endpoint("https://").is_err());
+        assert!(normalize_endpoint("https://dg.corp/?model=x").is_err());
+        assert_eq!(deepgram_bases(""), ("https://api.deepgram.com".into(), "wss://api.deepgram.com".into()));
+        assert_eq!(deepgram_bases("http://dg.corp:8080"), ("http://dg.corp:8080".into(), "ws://dg.corp:8080".into()));
+        let opts: SttOptions = serde_json::from_str(r#"{"stt_endpoint_override":"dg.corp"}"#).unwrap();
+        assert!(opts.validate().is_err());
+    }
+
+    #[test]
+    fn test_elevenlabs_options() {
+        let defaults = ElevenLabsOptions::default();
+        assert_eq!(defaults.realtime_query("auto"), "model_id=scribe_v2_realtime");
+        assert_eq!(defaults.realtime_query("bn"), "model_id=scribe_v2_realtime&language_code=bn");
+        assert!(ElevenLabsOptions { model: "scribe&x=1".into() }.validate().is_err());
+    }
+
+    #[test]
+    fn test_languages() {
+        assert_eq!(normalize_language("en-US").unwrap(), "en");
+        assert_eq!(normalize_language(" BN ").unwrap(), "bn");
+        assert_eq!(normalize_language("").unwrap(), "auto");
+        assert_eq!(normalize_language("multi").unwrap(), "auto");
+        assert!(normalize_language("english").is_err());
+        assert_eq!(deepgram_language_param("auto", true), "language=multi");
+        assert_eq!(deepgram_language_param("auto", false), "detect_language=true");
+        assert_eq!(deepgram_language_param("de", false), "language=de");
+        assert_eq!(elevenlabs_language("auto"), None);
+        assert_eq!(elevenlabs_language("ja"), Some("ja"));
+    }
+}
@@ -0,0 +1,131 @@
+// Subtitles from timed words: words are grouped into cues that read comfortably (a couple of short
+// lines, a few seconds each, broken at sentence ends and pauses) and written as SRT or WebVTT.
+
+use crate::transcript::Word;
+
+/// Characters per line and lines per cue, the usual broadcast limits
+const LINE_CHARS: usize = 42;
+const MAX_LINES: usize = 2;
+const MAX_CUE_MS: u64 = 6_000;
+/// A pause this long starts a new cue
+const PAUSE_MS: u64 = 1_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Lines of the cue, at most MAX_LINES
+    pub lines: Vec<String>,
+}
+
+fn ends_sentence(word: &str) -> bool {
+    word.ends_with(['.', '?', '!'])
+}
+
+/// Break text into lines of at most LINE_CHARS (a longer single word gets its own line)
+fn wrap(words: &[&str]) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= LINE_CHARS => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+/// Group words into cues
+pub fn cues(words: &[Word]) -> Vec<Cue> {
+    let mut cues = Vec::new();
+    let mut current: Vec<&Word> = Vec::new();
+    let flush = |current: &mut Vec<&Word>, cues: &mut Vec<Cue>| {
+        if let (Some(first), Some(last)) = (current.first(), current.last()) {
+            let texts: Vec<&str> = current.iter().map(|w| w.text.as_str()).collect();
+            cues.push(Cue { start_ms: first.start_ms, end_ms: last.end_ms.max(first.start_ms), lines: wrap(&texts) });
+        }
+        current.clear();
+    };
+    for word in words.iter().filter(|w| !w.text.trim().is_empty()) {
+        if let (Some(first), Some(last)) = (current.first(), current.last()) {
+            let mut texts: Vec<&str> = current.iter().map(|w| w.text.as_str()).collect();
+            texts.push(&word.text);
+            let too_long = wrap(&texts).len() > MAX_LINES || word.end_ms.saturating_sub(first.start_ms) > MAX_CUE_MS;
+            let pause = word.start_ms.saturating_sub(last.end_ms) >= PAUSE_MS;
+            if too_long || pause || ends_sentence(&last.text) {
+                flush(&mut current, &mut cues);
+            }
+        }
+        current.push(word);
+    }
+    flush(&mut current, &mut cues);
+    cues
+}
+
+/// `HH:MM:SS` plus milliseconds after `separator` ("," for SRT, "." for VTT)
+fn timestamp(ms: u64, separator: char) -> String {
+    format!("{:02}:{:02}:{:02}{}{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, separator, ms % 1000)
+}
+
+pub fn srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        out.push_str(&format!("{}\n{} --> {}\n{}\n\n", i + 1, timestamp(cue.start_ms, ','), timestamp(cue.end_ms, ','), cue.lines.join("\n")));
+    }
+    out
+}
+
+pub fn vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!("{} --> {}\n{}\n\n", timestamp(cue.start_ms, '.'), timestamp(cue.end_ms, '.'), cue.lines.join("\n")));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(text: &str, start_ms: u64, end_ms: u64) -> Word {
+        Word { text: text.into(), start_ms, end_ms, ..Default::default() }
+    }
+
+    #[test]
+    fn test_cues_break_at_sentences_and_pauses() {
+        let words = vec![
+            word("Hello", 0, 400),
+            word("there.", 400, 800),
+            word("This", 900, 1100),
+            word("is", 1100, 1200),
+            word("narration", 1200, 1800),
+            word("Later", 4000, 4500),
+        ];
+        let cues = cues(&words);
+        assert_eq!(cues.len(), 3);
+        assert_eq!(cues[0].lines, vec!["Hello there."]);
+        assert_eq!((cues[1].start_ms, cues[1].end_ms), (900, 1800));
+        assert_eq!(cues[2].lines, vec!["Later"]);
+    }
+
+    #[test]
+    fn test_long_cues_split_and_wrap() {
+        let words: Vec<Word> = (0..30).map(|i| word("word", i * 200, i * 200 + 200)).collect();
+        let cues = cues(&words);
+        assert!(cues.len() > 1);
+        for cue in &cues {
+            assert!(cue.lines.len() <= MAX_LINES);
+            assert!(cue.lines.iter().all(|l| l.chars().count() <= LINE_CHARS));
+            assert!(cue.end_ms - cue.start_ms <= MAX_CUE_MS);
+        }
+    }
+
+    #[test]
+    fn test_srt_and_vtt() {
+        let cues = vec![Cue { start_ms: 1_500, end_ms: 3_723_004, lines: vec!["Hi".into(), "there".into()] }];
+        assert_eq!(srt(&cues), "1\n00:00:01,500 --> 01:02:03,004\nHi\nthere\n\n");
+        assert_eq!(vtt(&cues), "WEBVTT\n\n00:00:01.500 --> 01:02:03.004\nHi\nthere\n\n");
+    }
+}
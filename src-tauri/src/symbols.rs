@@ -25,20 +25,6 @@ pub const SYMBOL_MAPPINGS: &[(&str, &str)] = &[
     ("dash", "-"),
     ("hyphen", "-"),
     
-    // Punctuation (for users who say them explicitly)
-    ("full stop", "."),
-    ("period", "."),
-    ("dot", "."),
-    ("comma", ","),
-    ("colon", ":"),
-    ("semicolon", ";"),
-    ("semi colon", ";"),
-    ("question mark", "?"),
-    ("exclamation mark", "!"),
-    ("exclamation point", "!"),
-    ("ellipsis", "..."),
-    ("triple dot", "..."),
-    
     // Quotes and brackets
     ("open quote", "\""),
     ("close quote", "\""),
@@ -114,13 +100,140 @@ pub const SYMBOL_MAPPINGS: &[(&str, &str)] = &[
     ("trademark", "™"),
 ];
 
-/// Replace spoken symbol names with actual symbols.
-/// Processes longer phrases first to avoid partial matches.
+/// Punctuation for users who say it explicitly. Kept apart from `SYMBOL_MAPPINGS` because these
+/// words also occur in ordinary speech ("the trial period", "a comma splice") and can be turned off.
+pub const PUNCTUATION_MAPPINGS: &[(&str, &str)] = &[
+    ("full stop", "."),
+    ("period", "."),
+    ("dot", "."),
+    ("comma", ","),
+    ("colon", ":"),
+    ("semicolon", ";"),
+    ("semi colon", ";"),
+    ("question mark", "?"),
+    ("exclamation mark", "!"),
+    ("exclamation point", "!"),
+    ("ellipsis", "..."),
+    ("triple dot", "..."),
+];
+
+/// Emoji by spoken name, used when the emoji pref is on. Names that are also ordinary words need
+/// "emoji" after them ("fire emoji"); any name may be followed by "emoji" ("thumbs up emoji").
+pub const EMOJI_MAPPINGS: &[(&str, &str)] = &[
+    // Faces
+    ("smiley face", "😊"),
+    ("smiling face", "😊"),
+    ("winking face", "😉"),
+    ("wink emoji", "😉"),
+    ("laughing face", "😂"),
+    ("tears of joy", "😂"),
+    ("crying face", "😢"),
+    ("sad face", "😞"),
+    ("thinking face", "🤔"),
+    ("heart eyes", "😍"),
+    ("shrug emoji", "🤷"),
+
+    // Hands
+    ("thumbs up", "👍"),
+    ("thumbs down", "👎"),
+    ("clapping hands", "👏"),
+    ("raised hands", "🙌"),
+    ("folded hands", "🙏"),
+    ("waving hand", "👋"),
+    ("wave emoji", "👋"),
+    ("ok hand", "👌"),
+
+    // Things
+    ("fire emoji", "🔥"),
+    ("heart emoji", "❤️"),
+    ("red heart", "❤️"),
+    ("party popper", "🎉"),
+    ("party emoji", "🎉"),
+    ("rocket emoji", "🚀"),
+    ("star emoji", "⭐"),
+    ("sparkles emoji", "✨"),
+    ("eyes emoji", "👀"),
+    ("hundred points", "💯"),
+    ("check mark emoji", "✅"),
+    ("cross mark emoji", "❌"),
+    ("warning emoji", "⚠️"),
+    ("light bulb emoji", "💡"),
+];
+
+/// Replace spoken emoji names with emoji, the user's own (spoken, emoji) mappings first. Unlike
+/// symbols, the spacing around the name is kept ("great job thumbs up" -> "great job 👍").
+pub fn replace_emoji(text: &str, custom: &[(String, String)]) -> String {
+    let mut mappings: Vec<(String, &str)> = custom
+        .iter()
+        .map(|(spoken, emoji)| (normalize_spoken(spoken), emoji.as_str()))
+        .chain(EMOJI_MAPPINGS.iter().map(|&(spoken, emoji)| (spoken.to_string(), emoji)))
+        .filter(|(spoken, _)| !spoken.is_empty())
+        .collect();
+    if mappings.is_empty() {
+        return text.to_string();
+    }
+    // Longer names first so "thumbs up emoji" isn't cut short; stable, so custom ones stay ahead
+    mappings.sort_by_key(|m| std::cmp::Reverse(m.0.len()));
+    let names: Vec<String> = mappings.iter().map(|(spoken, _)| regex::escape(spoken).replace(' ', r"\s+")).collect();
+    let Ok(re) = regex::Regex::new(&format!(r"(?i)\b({})(?:\s+emoji)?\b", names.join("|"))) else {
+        return text.to_string();
+    };
+    re.replace_all(text, |c: &regex::Captures| {
+        let spoken = normalize_spoken(&c[1]);
+        mappings.iter().find(|(s, _)| *s == spoken).map(|(_, e)| e.to_string()).unwrap_or_else(|| c[0].to_string())
+    })
+    .into_owned()
+}
+
+/// Lowercase with single spaces, how emoji names are compared
+fn normalize_spoken(spoken: &str) -> String {
+    spoken.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// Byte range in `haystack` of the first occurrence of `needle` (lowercase), ignoring case.
+/// Compares char by char on the original text: its lowercase can be longer ("İ" becomes two
+/// chars), so offsets found in a lowercased copy don't fit the original.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut want = needle.chars().peekable();
+        for (i, c) in haystack[start..].char_indices() {
+            if !c.to_lowercase().all(|lc| want.next() == Some(lc)) {
+                return None;
+            }
+            if want.peek().is_none() {
+                return Some((start, start + i + c.len_utf8()));
+            }
+        }
+        None
+    })
+}
+
+/// Replace spoken symbol names (including punctuation) with actual symbols.
 pub fn replace_symbols(text: &str) -> String {
+    replace_symbols_with(text, true)
+}
+
+/// Replace spoken symbol names with actual symbols; punctuation words only when
+/// `spoken_punctuation` is set. Processes longer phrases first to avoid partial matches.
+pub fn replace_symbols_with(text: &str, spoken_punctuation: bool) -> String {
+    replace_symbols_custom(text, spoken_punctuation, &[])
+}
+
+/// Like `replace_symbols_with`, plus the user's own (spoken, symbol) mappings, which win over
+/// built-in ones with the same spoken name.
+pub fn replace_symbols_custom(text: &str, spoken_punctuation: bool, custom: &[(String, String)]) -> String {
     let mut result = text.to_string();
     
-    // Sort by length descending so longer phrases match first
-    let mut mappings: Vec<_> = SYMBOL_MAPPINGS.iter().collect();
+    // Sort by length descending so longer phrases match first (stable, so custom ones stay ahead)
+    let punctuation = if spoken_punctuation { PUNCTUATION_MAPPINGS } else { &[] };
+    let mut mappings: Vec<(&str, &str)> = custom
+        .iter()
+        .map(|(spoken, symbol)| (spoken.as_str(), symbol.as_str()))
+        .chain(SYMBOL_MAPPINGS.iter().chain(punctuation).copied())
+        .collect();
     mappings.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
     
     for (spoken, symbol) in mappings {
@@ -129,11 +242,9 @@ pub fn replace_symbols(text: &str) -> String {
         let mut remaining = result.as_str();
         
         while !remaining.is_empty() {
-            if let Some(pos) = remaining.to_lowercase().find(&pattern) {
+            if let Some((pos, after_pos)) = find_ignore_case(remaining, &pattern) {
                 // Check word boundaries
-                let before_ok = pos == 0 || 
-                    !remaining.chars().nth(pos - 1).map(|c| c.is_alphanumeric()).unwrap_or(false);
-                let after_pos = pos + spoken.len();
+                let before_ok = !remaining[..pos].chars().next_back().is_some_and(|c| c.is_alphanumeric());
                 let after_ok = after_pos >= remaining.len() ||
                     !remaining[after_pos..].chars().next().map(|c| c.is_alphanumeric()).unwrap_or(false);
                 
@@ -154,8 +265,9 @@ pub fn replace_symbols(text: &str) -> String {
                     }
                 } else {
                     // Not a word boundary match, skip past this occurrence
-                    new_result.push_str(&remaining[..pos + 1]);
-                    remaining = &remaining[pos + 1..];
+                    let next = pos + remaining[pos..].chars().next().map_or(1, char::len_utf8);
+                    new_result.push_str(&remaining[..next]);
+                    remaining = &remaining[next..];
                 }
             } else {
                 new_result.push_str(remaining);
@@ -205,4 +317,40 @@ mod tests {
             "This is important! Please call"
         );
     }
+
+    #[test]
+    fn test_spoken_punctuation_off() {
+        assert_eq!(
+            replace_symbols_with("the trial period ends, comma, soon new line bye", false),
+            "the trial period ends, comma, soon\nbye"
+        );
+        assert_eq!(replace_symbols_with("wait question mark", true), "wait?");
+    }
+
+    #[test]
+    fn test_replace_emoji() {
+        assert_eq!(replace_emoji("great job thumbs up", &[]), "great job 👍");
+        assert_eq!(replace_emoji("Smiley  face, see you", &[]), "😊, see you");
+        assert_eq!(replace_emoji("that's fire emoji and thumbs up emoji", &[]), "that's 🔥 and 👍");
+        // Plain words stay words
+        assert_eq!(replace_emoji("the fire alarm went off", &[]), "the fire alarm went off");
+        let custom = vec![("ship it".to_string(), "🚢".to_string()), ("thumbs up".to_string(), "👌".to_string())];
+        assert_eq!(replace_emoji("ship it thumbs up", &custom), "🚢 👌");
+    }
+
+    #[test]
+    fn test_custom_symbols() {
+        let custom = vec![("check mark".to_string(), "✓".to_string()), ("em dash".to_string(), "--".to_string())];
+        assert_eq!(replace_symbols_custom("done check mark", true, &custom), "done✓");
+        assert_eq!(replace_symbols_custom("a em dash b", true, &custom), "a-- b");
+    }
+
+    #[test]
+    fn test_non_ascii_text() {
+        // "İ" lowercases to two chars, which used to shift every offset after it
+        assert_eq!(replace_symbols("İİİ new line İstanbul"), "İİİ\nİstanbul");
+        assert_eq!(replace_symbols("Ärger em dash Öl"), "Ärger— Öl");
+        assert_eq!(replace_symbols("İdash İ"), "İdash İ");
+        assert_eq!(replace_symbols("über, NEW LINE, straße"), "über\nstraße");
+    }
 }
@@ -0,0 +1,83 @@
+// Takes of the latest dictation session. Saying "retake" (or the retake hotkey) sets the current
+// utterance aside and starts a fresh take; the set-aside takes are kept here, next to the one
+// that was used, so any of them can still be refined and inserted afterwards.
+
+use std::sync::Mutex;
+
+use crate::transcript::Transcript;
+
+/// Takes kept per session; the oldest are dropped first
+pub const MAX_TAKES: usize = 10;
+const LABEL_CHARS: usize = 48;
+
+static TAKES: Mutex<Vec<Transcript>> = Mutex::new(Vec::new());
+
+/// Add a take; a take from another session replaces the previous session's. Returns how many
+/// takes the session has now.
+pub fn record(take: Transcript) -> usize {
+    let mut takes = TAKES.lock().unwrap();
+    if takes.first().is_some_and(|t| t.session_id != take.session_id) {
+        takes.clear();
+    }
+    if takes.len() == MAX_TAKES {
+        takes.remove(0);
+    }
+    takes.push(take);
+    takes.len()
+}
+
+/// Takes of the latest session, oldest first
+pub fn list() -> Vec<Transcript> {
+    TAKES.lock().unwrap().clone()
+}
+
+pub fn get(index: usize) -> Option<Transcript> {
+    TAKES.lock().unwrap().get(index).cloned()
+}
+
+/// Short one-line form of a take, for menus
+pub fn label(index: usize, take: &Transcript) -> String {
+    let text = take.text();
+    let short: String = text.chars().take(LABEL_CHARS).collect();
+    let ellipsis = if text.chars().count() > LABEL_CHARS { "…" } else { "" };
+    format!("Take {}: {}{}", index + 1, short, ellipsis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(session: u64, text: &str) -> Transcript {
+        Transcript { session_id: Some(session), ..Transcript::from_text(text, "test") }
+    }
+
+    // One test, since the takes are process-wide
+    #[test]
+    fn test_takes_per_session() {
+        assert_eq!(record(take(1, "first")), 1);
+        assert_eq!(record(take(1, "second")), 2);
+        assert_eq!(get(0).unwrap().text(), "first");
+
+        // A new session starts over
+        assert_eq!(record(take(2, "other")), 1);
+        assert_eq!(list().len(), 1);
+
+        for i in 0..MAX_TAKES + 3 {
+            record(take(3, &format!("take {}", i)));
+        }
+        let takes = list();
+        assert_eq!(takes.len(), MAX_TAKES);
+        assert_eq!(takes[0].text(), "take 3");
+        assert!(get(MAX_TAKES).is_none());
+    }
+
+    #[test]
+    fn test_label() {
+        let long = "word ".repeat(20);
+        let t = Transcript::from_text(&long, "test");
+        let label = label(1, &t);
+        assert!(label.starts_with("Take 2: word word"));
+        assert!(label.ends_with('…'));
+        assert_eq!(super::label(0, &Transcript::from_text("hi", "test")), "Take 1: hi");
+    }
+}
@@ -0,0 +1,234 @@
+// Structured dictation transcript passed between the HUD and the backend.
+// Keeps the provider's segments with their timing and confidence next to the text, so later
+// steps (refinement, insertion, history) can use timestamps and compare raw vs refined text
+// without re-parsing a flat string.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Average speaking rate used to place words the provider didn't time (~150 words per minute)
+const ESTIMATED_MS_PER_CHAR: u64 = 60;
+
+/// A word with its position in the recording, for karaoke-style review and subtitle export
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Word {
+    pub text: String,
+    pub start_ms: u64,
+    pub end_ms: u64,
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// Timing was interpolated from the segment (or speaking rate), not reported by the provider
+    #[serde(default)]
+    pub estimated: bool,
+    /// Speaker index from diarization (meeting mode)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<u32>,
+}
+
+/// One finalized chunk of speech as reported by the STT provider
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Segment {
+    pub text: String,
+    /// Offset from the start of the recording
+    #[serde(default)]
+    pub start_ms: Option<u64>,
+    #[serde(default)]
+    pub end_ms: Option<u64>,
+    /// Provider confidence, 0.0-1.0
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// Per-word timing, when the provider reports it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<Word>,
+    /// Language of this segment, when the provider detected it or it was tagged for mixed-language
+    /// refinement (see language.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Transcript {
+    /// Dictation session this transcript belongs to
+    #[serde(default)]
+    pub session_id: Option<u64>,
+    /// STT provider ("deepgram", "elevenlabs", "demo", ...)
+    #[serde(default)]
+    pub provider: String,
+    /// Spoken language, when the provider (or translation) detected one
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub segments: Vec<Segment>,
+    /// Text after the refinement pipeline; None until `refine_text` ran
+    #[serde(default)]
+    pub refined: Option<String>,
+}
+
+impl Segment {
+    /// The segment split where the diarized speaker changes, as (speaker, text) in order. Without
+    /// word timing (or speakers) it is one run with no speaker.
+    pub fn speaker_runs(&self) -> Vec<(Option<u32>, String)> {
+        if self.words.is_empty() {
+            return vec![(None, self.text.trim().to_string())];
+        }
+        let mut runs: Vec<(Option<u32>, String)> = Vec::new();
+        for w in &self.words {
+            match runs.last_mut() {
+                Some((speaker, text)) if *speaker == w.speaker => {
+                    text.push(' ');
+                    text.push_str(&w.text);
+                }
+                _ => runs.push((w.speaker, w.text.clone())),
+            }
+        }
+        runs
+    }
+}
+
+impl Transcript {
+    /// A single untimed segment, for text that didn't come from a streaming provider
+    pub fn from_text(text: &str, provider: &str) -> Self {
+        Transcript {
+            provider: provider.to_string(),
+            segments: vec![Segment { text: text.to_string(), ..Default::default() }],
+            ..Default::default()
+        }
+    }
+
+    /// Raw transcript text: the segments joined with single spaces
+    pub fn text(&self) -> String {
+        self.segments
+            .iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// What gets inserted: the refined text if there is one, otherwise the raw text
+    pub fn output_text(&self) -> String {
+        self.refined.clone().unwrap_or_else(|| self.text())
+    }
+
+    /// Mean segment confidence, weighted by segment length in characters
+    pub fn confidence(&self) -> Option<f32> {
+        let (sum, weight) = self
+            .segments
+            .iter()
+            .filter_map(|s| s.confidence.map(|c| (c, s.text.trim().chars().count().max(1) as f32)))
+            .fold((0.0, 0.0), |(sum, weight), (c, w)| (sum + c * w, weight + w));
+        if weight > 0.0 { Some(sum / weight) } else { None }
+    }
+
+    /// Span from the first segment start to the last segment end
+    pub fn duration_ms(&self) -> Option<u64> {
+        let start = self.segments.iter().filter_map(|s| s.start_ms).min()?;
+        let end = self.segments.iter().filter_map(|s| s.end_ms).max()?;
+        Some(end.saturating_sub(start))
+    }
+
+    /// Timed words for the whole transcript. Segments without provider word timing are split
+    /// by word length across the segment's span, or placed at an average speaking rate after
+    /// the previous segment when the segment isn't timed either.
+    pub fn words(&self) -> Vec<Word> {
+        let mut out: Vec<Word> = Vec::new();
+        let mut cursor = 0;
+        for seg in &self.segments {
+            if !seg.words.is_empty() {
+                out.extend(seg.words.iter().cloned());
+                cursor = cursor.max(seg.words.iter().map(|w| w.end_ms).max().unwrap_or(0));
+                continue;
+            }
+            let tokens: Vec<&str> = seg.text.split_whitespace().collect();
+            if tokens.is_empty() {
+                continue;
+            }
+            let total_chars: u64 = tokens.iter().map(|t| t.chars().count() as u64).sum();
+            let start = seg.start_ms.unwrap_or(cursor);
+            let end = seg.end_ms.filter(|e| *e > start).unwrap_or(start + total_chars * ESTIMATED_MS_PER_CHAR);
+            let mut word_start = start;
+            let mut chars_done = 0;
+            for token in tokens {
+                chars_done += token.chars().count() as u64;
+                let word_end = start + (end - start) * chars_done / total_chars;
+                out.push(Word {
+                    text: token.to_string(),
+                    start_ms: word_start,
+                    end_ms: word_end,
+                    confidence: seg.confidence,
+                    estimated: true,
+                    speaker: None,
+                });
+                word_start = word_end;
+            }
+            cursor = end;
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seg(text: &str, start: u64, end: u64, confidence: f32) -> Segment {
+        Segment { text: text.into(), start_ms: Some(start), end_ms: Some(end), confidence: Some(confidence), ..Default::default() }
+    }
+
+    #[test]
+    fn test_text_and_output() {
+        let mut t = Transcript {
+            segments: vec![seg(" hello there ", 0, 900, 0.9), seg("", 900, 950, 0.1), seg("general kenobi", 1200, 2400, 0.8)],
+            ..Default::default()
+        };
+        assert_eq!(t.text(), "hello there general kenobi");
+        assert_eq!(t.output_text(), "hello there general kenobi");
+        t.refined = Some("Hello there, General Kenobi.".into());
+        assert_eq!(t.output_text(), "Hello there, General Kenobi.");
+        assert_eq!(t.duration_ms(), Some(2400));
+    }
+
+    #[test]
+    fn test_confidence_weighted_by_length() {
+        let t = Transcript { segments: vec![seg("aaaa", 0, 1, 1.0), seg("b", 1, 2, 0.5)], ..Default::default() };
+        assert!((t.confidence().unwrap() - 0.9).abs() < 1e-6);
+        assert_eq!(Transcript::from_text("hi", "demo").confidence(), None);
+    }
+
+    #[test]
+    fn test_deserialize_minimal() {
+        let t: Transcript = serde_json::from_str(r#"{"segments":[{"text":"hi"}]}"#).unwrap();
+        assert_eq!(t.text(), "hi");
+        assert_eq!(t.refined, None);
+    }
+
+    #[test]
+    fn test_speaker_runs() {
+        let word = |text: &str, speaker| Word { text: text.into(), speaker, ..Default::default() };
+        let seg = Segment {
+            text: "Hi there. Hello.".into(),
+            words: vec![word("Hi", Some(0)), word("there.", Some(0)), word("Hello.", Some(1))],
+            ..Default::default()
+        };
+        assert_eq!(seg.speaker_runs(), vec![(Some(0), "Hi there.".to_string()), (Some(1), "Hello.".to_string())]);
+        assert_eq!(Segment { text: " Untimed ".into(), ..Default::default() }.speaker_runs(), vec![(None, "Untimed".to_string())]);
+    }
+
+    #[test]
+    fn test_words_uses_provider_timing_and_estimates_the_rest() {
+        let timed = Segment {
+            text: "hello world".into(),
+            words: vec![
+                Word { text: "Hello".into(), start_ms: 100, end_ms: 400, ..Default::default() },
+                Word { text: "world.".into(), start_ms: 450, end_ms: 900, ..Default::default() },
+            ],
+            ..Default::default()
+        };
+        let t = Transcript { segments: vec![timed, seg("ab abcd", 1000, 1600, 0.9), Segment { text: "x".into(), ..Default::default() }], ..Default::default() };
+        let words = t.words();
+        let spans: Vec<(u64, u64, bool)> = words.iter().map(|w| (w.start_ms, w.end_ms, w.estimated)).collect();
+        // "ab abcd" is split 2:4 across 1000-1600; "x" follows at the average rate
+        assert_eq!(spans, vec![(100, 400, false), (450, 900, false), (1000, 1200, true), (1200, 1600, true), (1600, 1660, true)]);
+        assert_eq!(words[2].confidence, Some(0.9));
+    }
+}
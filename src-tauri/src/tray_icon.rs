@@ -0,0 +1,144 @@
+// Tray icon variants for the dictation states, drawn over the app icon at startup: an amber dot
+// while starting, a red dot while the microphone is live, pause bars while a recording is paused
+// and a spinner while the transcript is processed, so the tray shows whether the mic is hot even
+// with the HUD hidden.
+
+/// Frames of the processing spinner, one per dot
+pub const SPINNER_FRAMES: usize = 8;
+
+const STARTING: [u8; 3] = [0xF5, 0xA6, 0x23];
+const RECORDING: [u8; 3] = [0xE5, 0x39, 0x35];
+const PAUSED: [u8; 3] = [0x75, 0x75, 0x75];
+const WHITE: [u8; 3] = [0xFF, 0xFF, 0xFF];
+const DARK: [u8; 3] = [0x20, 0x20, 0x20];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Badge {
+    Starting,
+    Recording,
+    Paused,
+    /// A spinner frame, 0..SPINNER_FRAMES
+    Processing(usize),
+}
+
+/// Blend `color` at `alpha` (0..=1) over the pixel at (x, y)
+fn blend(rgba: &mut [u8], width: u32, x: u32, y: u32, color: [u8; 3], alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    let i = ((y * width + x) * 4) as usize;
+    let under = rgba[i + 3] as f32 / 255.0;
+    let out = alpha + under * (1.0 - alpha);
+    for c in 0..3 {
+        let mixed = (color[c] as f32 * alpha + rgba[i + c] as f32 * under * (1.0 - alpha)) / out;
+        rgba[i + c] = mixed.round() as u8;
+    }
+    rgba[i + 3] = (out * 255.0).round() as u8;
+}
+
+/// Fill a disc, with a one pixel soft edge
+fn disc(rgba: &mut [u8], width: u32, height: u32, center: (f32, f32), radius: f32, color: [u8; 3], alpha: f32) {
+    let (x0, x1) = (((center.0 - radius - 1.0).max(0.0)) as u32, ((center.0 + radius + 1.0) as u32).min(width - 1));
+    let (y0, y1) = (((center.1 - radius - 1.0).max(0.0)) as u32, ((center.1 + radius + 1.0) as u32).min(height - 1));
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let d = ((x as f32 + 0.5 - center.0).powi(2) + (y as f32 + 0.5 - center.1).powi(2)).sqrt();
+            blend(rgba, width, x, y, color, alpha * (radius + 0.5 - d).clamp(0.0, 1.0));
+        }
+    }
+}
+
+/// Fill the rectangle from (x0, y0) to (x1, y1), edges included
+fn rect(rgba: &mut [u8], width: u32, (x0, y0): (u32, u32), (x1, y1): (u32, u32), color: [u8; 3]) {
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            blend(rgba, width, x, y, color, 1.0);
+        }
+    }
+}
+
+/// `rgba` (`width` x `height`) with `badge` in its lower right corner
+pub fn badged(rgba: &[u8], width: u32, height: u32, badge: Badge) -> Vec<u8> {
+    let mut out = rgba.to_vec();
+    let size = width.min(height) as f32;
+    let radius = size * 0.22;
+    let center = (width as f32 - radius - size * 0.04, height as f32 - radius - size * 0.04);
+    let ring = (size / 32.0).max(1.0);
+    match badge {
+        Badge::Starting | Badge::Recording => {
+            let color = if badge == Badge::Starting { STARTING } else { RECORDING };
+            disc(&mut out, width, height, center, radius + ring, WHITE, 1.0);
+            disc(&mut out, width, height, center, radius, color, 1.0);
+        }
+        Badge::Paused => {
+            disc(&mut out, width, height, center, radius + ring, WHITE, 1.0);
+            disc(&mut out, width, height, center, radius, PAUSED, 1.0);
+            // Two bars, each a fifth of the badge wide with a gap of the same between them
+            let (bar_w, bar_h) = ((radius * 0.4).max(1.0), radius * 0.9);
+            let top = (center.1 - bar_h / 2.0) as u32;
+            let bottom = (center.1 + bar_h / 2.0) as u32;
+            for left in [center.0 - bar_w * 1.5, center.0 + bar_w * 0.5] {
+                rect(&mut out, width, (left as u32, top), ((left + bar_w) as u32 - 1, bottom), WHITE);
+            }
+        }
+        Badge::Processing(frame) => {
+            disc(&mut out, width, height, center, radius + ring, DARK, 0.85);
+            let orbit = radius * 0.62;
+            let dot = (radius * 0.22).max(1.0);
+            for i in 0..SPINNER_FRAMES {
+                let angle = i as f32 / SPINNER_FRAMES as f32 * std::f32::consts::TAU;
+                // The dot at `frame` is brightest, the ones behind it fade out
+                let behind = (frame + SPINNER_FRAMES - i) % SPINNER_FRAMES;
+                let alpha = 1.0 - behind as f32 / SPINNER_FRAMES as f32;
+                let at = (center.0 + orbit * angle.sin(), center.1 - orbit * angle.cos());
+                disc(&mut out, width, height, at, dot, WHITE, alpha);
+            }
+        }
+    }
+    out
+}
+
+/// Recording time for the tooltip: "0:42", "12:05", "1:02:03"
+pub fn elapsed_label(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if h > 0 {
+        format!("{}:{:02}:{:02}", h, m, s)
+    } else {
+        format!("{}:{:02}", m, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> &[u8] {
+        let i = ((y * width + x) * 4) as usize;
+        &rgba[i..i + 4]
+    }
+
+    #[test]
+    fn test_badges() {
+        let (w, h) = (32, 32);
+        let base = vec![0u8; (w * h * 4) as usize];
+        let recording = badged(&base, w, h, Badge::Recording);
+        // Red in the badge, the rest of the icon untouched
+        assert_eq!(pixel(&recording, w, 24, 24), &[0xE5, 0x39, 0x35, 0xFF]);
+        assert_eq!(pixel(&recording, w, 4, 4), &[0, 0, 0, 0]);
+        assert_ne!(badged(&base, w, h, Badge::Starting), recording);
+        // White bars on grey
+        let paused = badged(&base, w, h, Badge::Paused);
+        assert_eq!(pixel(&paused, w, 20, 24), &[0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(pixel(&paused, w, 23, 24)[..3], PAUSED);
+        let frames: Vec<_> = (0..SPINNER_FRAMES).map(|f| badged(&base, w, h, Badge::Processing(f))).collect();
+        assert_ne!(frames[0], frames[1]);
+        assert_eq!(frames[0], badged(&base, w, h, Badge::Processing(0)));
+    }
+
+    #[test]
+    fn test_elapsed_label() {
+        assert_eq!(elapsed_label(42), "0:42");
+        assert_eq!(elapsed_label(725), "12:05");
+        assert_eq!(elapsed_label(3723), "1:02:03");
+    }
+}
@@ -0,0 +1,74 @@
+// Inter-key delays for "human" typing: random within a configured range, with longer pauses
+// after spaces and sentence punctuation, so apps that reject pastes or throttle sudden bursts of
+// input see something that looks like a person typing.
+
+use std::time::Duration;
+
+pub struct Rhythm {
+    min_ms: u64,
+    max_ms: u64,
+    state: u64,
+}
+
+impl Rhythm {
+    /// `min_ms` and `max_ms` may come in either order
+    pub fn new(min_ms: u32, max_ms: u32, seed: u64) -> Self {
+        let (min_ms, max_ms) = if min_ms <= max_ms { (min_ms, max_ms) } else { (max_ms, min_ms) };
+        // xorshift must not start at zero
+        Rhythm { min_ms: min_ms as u64, max_ms: max_ms as u64, state: seed | 1 }
+    }
+
+    fn next(&mut self) -> u64 {
+        // xorshift64: plenty for jitter, no dependency needed
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn between(&mut self, lo: u64, hi: u64) -> u64 {
+        lo + self.next() % (hi - lo + 1)
+    }
+
+    /// Pause to leave after typing `ch`
+    pub fn delay_after(&mut self, ch: char) -> Duration {
+        let base = self.between(self.min_ms, self.max_ms);
+        let ms = match ch {
+            ' ' | '\t' => base + self.between(0, self.max_ms / 2),
+            '.' | '!' | '?' | '\n' => base + self.between(self.max_ms, self.max_ms * 3),
+            ',' | ';' | ':' => base + self.between(0, self.max_ms),
+            _ => base,
+        };
+        Duration::from_millis(ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_letters_stay_in_range() {
+        let mut r = Rhythm::new(30, 90, 42);
+        for _ in 0..1000 {
+            let d = r.delay_after('a').as_millis() as u64;
+            assert!((30..=90).contains(&d), "{} out of range", d);
+        }
+    }
+
+    #[test]
+    fn test_sentence_end_pauses_longer() {
+        let mut r = Rhythm::new(30, 90, 7);
+        let letters: u128 = (0..200).map(|_| r.delay_after('a').as_millis()).sum();
+        let stops: u128 = (0..200).map(|_| r.delay_after('.').as_millis()).sum();
+        assert!(stops > letters * 2);
+    }
+
+    #[test]
+    fn test_swapped_and_fixed_range() {
+        let mut r = Rhythm::new(90, 30, 1);
+        assert!((30..=90).contains(&(r.delay_after('x').as_millis() as u64)));
+        let mut r = Rhythm::new(50, 50, 0);
+        assert_eq!(r.delay_after('x'), Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,154 @@
+// Typography stage: quotes, ellipses and em dashes made consistent after refinement, since the
+// model writes whichever it likes and target apps want different ones. LaTeX sources and code
+// editors want straight quotes and three dots, a Word document curly quotes and "…". Curly
+// quotes use the dictation language's quotation marks (locale.rs).
+
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::locale;
+
+pub const QUOTE_STYLES: &[&str] = &["keep", "straight", "curly"];
+pub const ELLIPSIS_STYLES: &[&str] = &["keep", "character", "dots"];
+pub const DASH_STYLES: &[&str] = &["keep", "spaced", "closed"];
+
+/// Typography preferences; "keep" leaves that part of the text as refinement wrote it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct Typography {
+    /// One of QUOTE_STYLES: "straight" for "..." and '...', "curly" for the language's own marks
+    #[serde(default = "keep")]
+    pub quotes: String,
+    /// One of ELLIPSIS_STYLES: "character" for "…", "dots" for "..."
+    #[serde(default = "keep")]
+    pub ellipsis: String,
+    /// One of DASH_STYLES: "spaced" for "a — b", "closed" for "a—b"
+    #[serde(default = "keep")]
+    pub em_dash: String,
+}
+
+fn keep() -> String {
+    "keep".into()
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Typography { quotes: keep(), ellipsis: keep(), em_dash: keep() }
+    }
+}
+
+impl Typography {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value, allowed) in [
+            ("quotes", &self.quotes, QUOTE_STYLES),
+            ("ellipsis", &self.ellipsis, ELLIPSIS_STYLES),
+            ("em_dash", &self.em_dash, DASH_STYLES),
+        ] {
+            if !allowed.contains(&value.as_str()) {
+                return Err(format!("Unknown {} style {:?}, expected one of {}", name, value, allowed.join(", ")));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_keep(&self) -> bool {
+        *self == Typography::default()
+    }
+
+    /// `text` in this style; `language` picks the curly quotation marks
+    pub fn apply(&self, text: &str, language: Option<&str>) -> String {
+        let mut out = match self.quotes.as_str() {
+            "straight" => straighten_quotes(text),
+            "curly" => curl_quotes(text, language),
+            _ => text.to_string(),
+        };
+        match self.ellipsis.as_str() {
+            "character" => out = out.replace("...", "…").replace(". . .", "…"),
+            "dots" => out = out.replace('…', "..."),
+            _ => {}
+        }
+        match self.em_dash.as_str() {
+            "spaced" => out = em_dash_re().replace_all(&out, " — ").into_owned(),
+            "closed" => out = em_dash_re().replace_all(&out, "—").into_owned(),
+            _ => {}
+        }
+        out
+    }
+}
+
+fn em_dash_re() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"[ \t\u{00A0}]*—[ \t\u{00A0}]*").unwrap())
+}
+
+fn straighten_quotes(text: &str) -> String {
+    // Guillemets go with the spaces French puts inside them
+    let text = text.replace("« ", "\"").replace(" »", "\"").replace("\u{00A0}»", "\"").replace("«\u{00A0}", "\"");
+    text.chars()
+        .map(|c| match c {
+            '“' | '”' | '„' | '‟' | '«' | '»' => '"',
+            '‘' | '’' | '‚' | '‛' => '\'',
+            c => c,
+        })
+        .collect()
+}
+
+/// Whether a quote after `prev` opens a quotation
+fn opens(prev: Option<char>) -> bool {
+    prev.is_none_or(|p| p.is_whitespace() || "([{—–-/".contains(p))
+}
+
+fn curl_quotes(text: &str, language: Option<&str>) -> String {
+    let (open, close) = language.and_then(locale::rules).map(|r| r.quotes).unwrap_or(("“", "”"));
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    for (i, &c) in chars.iter().enumerate() {
+        let prev = i.checked_sub(1).map(|j| chars[j]);
+        let next = chars.get(i + 1).copied();
+        match c {
+            '"' if opens(prev) => out.push_str(open),
+            '"' => out.push_str(close),
+            // Apostrophes ("don't", "'90s") as well as closing single quotes
+            '\'' if opens(prev) && !next.is_some_and(|n| n.is_ascii_digit()) => out.push('‘'),
+            '\'' => out.push('’'),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(quotes: &str, ellipsis: &str, em_dash: &str) -> Typography {
+        Typography { quotes: quotes.into(), ellipsis: ellipsis.into(), em_dash: em_dash.into() }
+    }
+
+    #[test]
+    fn test_word_style() {
+        let word = style("curly", "character", "spaced");
+        let text = r#"She said "don't wait"... then left—for good. 'Back in the '90s,' he said"#;
+        assert_eq!(word.apply(text, None), "She said “don’t wait”… then left — for good. ‘Back in the ’90s,’ he said");
+        assert_eq!(word.apply(r#"Er sagte "nein""#, Some("de")), "Er sagte „nein“");
+        assert_eq!(word.apply(r#"Il a dit "non""#, Some("fr-FR")), "Il a dit « non »");
+    }
+
+    #[test]
+    fn test_plain_style() {
+        let latex = style("straight", "dots", "closed");
+        assert_eq!(latex.apply("“Wait…” — she’s «here»", None), "\"Wait...\"—she's \"here\"");
+        assert_eq!(latex.apply("Il a dit « non »", None), "Il a dit \"non\"");
+        assert_eq!(Typography::default().apply("“a”... — b", None), "“a”... — b");
+        assert!(Typography::default().is_keep());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(style("curly", "dots", "keep").validate().is_ok());
+        assert!(style("smart", "dots", "keep").validate().unwrap_err().contains("quotes"));
+        let parsed: Typography = serde_json::from_str(r#"{"quotes": "straight"}"#).unwrap();
+        assert_eq!(parsed, style("straight", "keep", "keep"));
+    }
+}
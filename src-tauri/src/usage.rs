@@ -0,0 +1,314 @@
+// Usage statistics for the stats page: per-session metrics kept with each history entry, provider
+// call counts kept per day, and the aggregates over a date range (words dictated, speaking rate,
+// daily streaks, provider error rates). Dates are local calendar days.
+
+use chrono::{Duration, NaiveDate};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::cost::DictationCost;
+
+/// Ranges accepted by `get_usage_stats`
+pub const RANGES: &[&str] = &["today", "7d", "30d", "90d", "all"];
+
+/// What one dictation took
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SessionMetrics {
+    /// Time spent recording
+    pub duration_ms: u64,
+    /// Words spoken (the raw transcript, before refinement)
+    pub words: u64,
+    /// STT provider
+    pub provider: String,
+    /// From stopping the recording to the final transcript
+    #[serde(default)]
+    pub stt_ms: Option<u64>,
+    /// AI refinement (and translation)
+    #[serde(default)]
+    pub refine_ms: Option<u64>,
+    /// Inserting the text into the target app
+    #[serde(default)]
+    pub paste_ms: Option<u64>,
+    /// When each pipeline step was reached
+    #[serde(default)]
+    pub timing: Option<PipelineTiming>,
+    /// Estimated provider cost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<DictationCost>,
+}
+
+/// Milliseconds from the hotkey to each step of one dictation; None for steps it didn't reach
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PipelineTiming {
+    pub hud_shown_ms: Option<u64>,
+    /// First words on screen
+    pub first_partial_ms: Option<u64>,
+    pub final_ms: Option<u64>,
+    pub refined_ms: Option<u64>,
+    pub pasted_ms: Option<u64>,
+}
+
+impl PipelineTiming {
+    /// The step that took longest after the one before it, with its duration; the time spent
+    /// speaking (first partial to final) doesn't count as a delay
+    pub fn slowest_step(&self) -> Option<(&'static str, u64)> {
+        let steps = [
+            ("hud_shown", self.hud_shown_ms),
+            ("first_partial", self.first_partial_ms),
+            ("final", self.final_ms),
+            ("refined", self.refined_ms),
+            ("pasted", self.pasted_ms),
+        ];
+        let mut prev: (&str, u64) = ("hotkey", 0);
+        let mut slowest: Option<(&'static str, u64)> = None;
+        for (name, at) in steps {
+            let Some(at) = at else { continue };
+            let took = at.saturating_sub(prev.1);
+            if !(prev.0 == "first_partial" && name == "final") && slowest.is_none_or(|(_, ms)| took > ms) {
+                slowest = Some((name, took));
+            }
+            prev = (name, at);
+        }
+        slowest
+    }
+}
+
+impl SessionMetrics {
+    /// Speaking rate; None for recordings too short to tell
+    pub fn wpm(&self) -> Option<f64> {
+        (self.duration_ms >= 1000 && self.words > 0).then(|| self.words as f64 * 60_000.0 / self.duration_ms as f64)
+    }
+}
+
+/// Requests to one provider on one day
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CallCount {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+/// Day ("YYYY-MM-DD") -> provider -> calls
+pub type DailyCalls = BTreeMap<String, BTreeMap<String, CallCount>>;
+
+pub fn day_key(day: NaiveDate) -> String {
+    day.format("%Y-%m-%d").to_string()
+}
+
+/// One history entry as the stats see it
+pub struct Session<'a> {
+    pub day: NaiveDate,
+    /// Words in the inserted text, for entries recorded before metrics existed
+    pub words: u64,
+    pub metrics: Option<&'a SessionMetrics>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct DailyUsage {
+    /// "YYYY-MM-DD"
+    pub date: String,
+    pub sessions: u64,
+    pub words: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct ProviderUsage {
+    pub provider: String,
+    /// Dictations transcribed by this provider
+    pub sessions: u64,
+    pub requests: u64,
+    pub errors: u64,
+    /// errors / requests; None without requests
+    pub error_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, JsonSchema)]
+pub struct UsageStats {
+    pub range: String,
+    /// First day counted; None for "all" without any history
+    pub from: Option<String>,
+    pub to: String,
+    pub sessions: u64,
+    pub total_words: u64,
+    pub total_duration_ms: u64,
+    /// Mean speaking rate over the timed sessions
+    pub avg_wpm: Option<f64>,
+    pub avg_stt_ms: Option<u64>,
+    pub avg_refine_ms: Option<u64>,
+    pub avg_paste_ms: Option<u64>,
+    /// Consecutive days with a dictation, up to today (or yesterday, before today's first one)
+    pub current_streak_days: u64,
+    pub longest_streak_days: u64,
+    /// Days with at least one dictation, oldest first
+    pub daily: Vec<DailyUsage>,
+    pub providers: Vec<ProviderUsage>,
+}
+
+/// First day of `range` ending on `today`; None for "all"
+pub fn range_start(range: &str, today: NaiveDate) -> Result<Option<NaiveDate>, String> {
+    let days = match range {
+        "today" => 1,
+        "7d" => 7,
+        "30d" => 30,
+        "90d" => 90,
+        "all" => return Ok(None),
+        other => return Err(format!("Unknown range: {} (use {})", other, RANGES.join(", "))),
+    };
+    Ok(Some(today - Duration::days(days - 1)))
+}
+
+/// (current, longest) runs of consecutive days in `days`
+fn streaks(days: &BTreeSet<NaiveDate>, today: NaiveDate) -> (u64, u64) {
+    let (mut longest, mut run, mut prev): (u64, u64, Option<NaiveDate>) = (0, 0, None);
+    for &day in days {
+        run = if prev.is_some_and(|p| p + Duration::days(1) == day) { run + 1 } else { 1 };
+        longest = longest.max(run);
+        prev = Some(day);
+    }
+    // The streak is still alive until a whole day passes without dictating
+    let alive = prev.is_some_and(|last| last == today || last + Duration::days(1) == today);
+    (if alive { run } else { 0 }, longest)
+}
+
+fn mean(values: impl Iterator<Item = u64>) -> Option<u64> {
+    let (sum, n) = values.fold((0u64, 0u64), |(s, n), v| (s + v, n + 1));
+    (n > 0).then(|| sum / n)
+}
+
+pub fn aggregate(range: &str, today: NaiveDate, sessions: &[Session], calls: &DailyCalls) -> Result<UsageStats, String> {
+    let start = range_start(range, today)?;
+    let in_range = |day: NaiveDate| start.is_none_or(|s| day >= s) && day <= today;
+    let picked: Vec<&Session> = sessions.iter().filter(|s| in_range(s.day)).collect();
+    let words = |s: &Session| s.metrics.map_or(s.words, |m| m.words);
+
+    let mut daily: BTreeMap<NaiveDate, DailyUsage> = BTreeMap::new();
+    for s in &picked {
+        let d = daily.entry(s.day).or_insert_with(|| DailyUsage { date: day_key(s.day), sessions: 0, words: 0 });
+        d.sessions += 1;
+        d.words += words(s);
+    }
+
+    let metrics: Vec<&SessionMetrics> = picked.iter().filter_map(|s| s.metrics).collect();
+    let timed: Vec<&&SessionMetrics> = metrics.iter().filter(|m| m.wpm().is_some()).collect();
+    let timed_words: u64 = timed.iter().map(|m| m.words).sum();
+    let timed_ms: u64 = timed.iter().map(|m| m.duration_ms).sum();
+
+    let mut providers: BTreeMap<String, ProviderUsage> = BTreeMap::new();
+    fn usage<'p>(providers: &'p mut BTreeMap<String, ProviderUsage>, name: &str) -> &'p mut ProviderUsage {
+        providers.entry(name.to_string()).or_insert_with(|| ProviderUsage { provider: name.to_string(), sessions: 0, requests: 0, errors: 0, error_rate: None })
+    }
+    for m in &metrics {
+        usage(&mut providers, &m.provider).sessions += 1;
+    }
+    for (day, by_provider) in calls {
+        let Ok(day) = NaiveDate::parse_from_str(day, "%Y-%m-%d") else { continue };
+        if !in_range(day) {
+            continue;
+        }
+        for (name, count) in by_provider {
+            let p = usage(&mut providers, name);
+            p.requests += count.requests;
+            p.errors += count.errors;
+        }
+    }
+    for p in providers.values_mut() {
+        p.error_rate = (p.requests > 0).then(|| p.errors as f64 / p.requests as f64);
+    }
+
+    let all_days: BTreeSet<NaiveDate> = sessions.iter().map(|s| s.day).filter(|d| *d <= today).collect();
+    let (current_streak_days, longest_streak_days) = streaks(&all_days, today);
+
+    Ok(UsageStats {
+        range: range.to_string(),
+        from: start.or_else(|| picked.iter().map(|s| s.day).min()).map(day_key),
+        to: day_key(today),
+        sessions: picked.len() as u64,
+        total_words: picked.iter().map(|s| words(s)).sum(),
+        total_duration_ms: metrics.iter().map(|m| m.duration_ms).sum(),
+        avg_wpm: (timed_ms > 0).then(|| timed_words as f64 * 60_000.0 / timed_ms as f64),
+        avg_stt_ms: mean(metrics.iter().filter_map(|m| m.stt_ms)),
+        avg_refine_ms: mean(metrics.iter().filter_map(|m| m.refine_ms)),
+        avg_paste_ms: mean(metrics.iter().filter_map(|m| m.paste_ms)),
+        current_streak_days,
+        longest_streak_days,
+        daily: daily.into_values().collect(),
+        providers: providers.into_values().collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, d).unwrap()
+    }
+
+    fn metrics(words: u64, duration_ms: u64, provider: &str) -> SessionMetrics {
+        SessionMetrics { words, duration_ms, provider: provider.into(), refine_ms: Some(400), ..Default::default() }
+    }
+
+    #[test]
+    fn test_streaks() {
+        let days: BTreeSet<NaiveDate> = [1, 2, 3, 7, 8].into_iter().map(day).collect();
+        assert_eq!(streaks(&days, day(8)), (2, 3));
+        // Yesterday still counts until today is over
+        assert_eq!(streaks(&days, day(9)), (2, 3));
+        assert_eq!(streaks(&days, day(10)), (0, 3));
+        assert_eq!(streaks(&BTreeSet::new(), day(10)), (0, 0));
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let (a, b, c) = (metrics(100, 60_000, "deepgram"), metrics(60, 20_000, "elevenlabs"), metrics(30, 30_000, "deepgram"));
+        let sessions = vec![
+            Session { day: day(1), words: 0, metrics: Some(&a) },
+            Session { day: day(9), words: 0, metrics: Some(&b) },
+            Session { day: day(10), words: 0, metrics: Some(&c) },
+            // Recorded before metrics existed
+            Session { day: day(10), words: 12, metrics: None },
+        ];
+        let mut calls = DailyCalls::new();
+        calls.entry("2024-05-10".into()).or_default().insert("openrouter".into(), CallCount { requests: 4, errors: 1 });
+        calls.entry("2024-05-01".into()).or_default().insert("openrouter".into(), CallCount { requests: 10, errors: 10 });
+
+        let week = aggregate("7d", day(10), &sessions, &calls).unwrap();
+        assert_eq!(week.from.as_deref(), Some("2024-05-04"));
+        assert_eq!((week.sessions, week.total_words), (3, 102));
+        // 90 words over 50 seconds
+        assert_eq!(week.avg_wpm.map(|w| w.round()), Some(108.0));
+        assert_eq!(week.avg_refine_ms, Some(400));
+        assert_eq!(week.daily.iter().map(|d| (d.date.as_str(), d.sessions)).collect::<Vec<_>>(), vec![("2024-05-09", 1), ("2024-05-10", 2)]);
+        assert_eq!((week.current_streak_days, week.longest_streak_days), (2, 2));
+        let openrouter = week.providers.iter().find(|p| p.provider == "openrouter").unwrap();
+        assert_eq!((openrouter.requests, openrouter.error_rate), (4, Some(0.25)));
+        let deepgram = week.providers.iter().find(|p| p.provider == "deepgram").unwrap();
+        assert_eq!((deepgram.sessions, deepgram.error_rate), (1, None));
+
+        let all = aggregate("all", day(10), &sessions, &calls).unwrap();
+        assert_eq!((all.sessions, all.from.as_deref()), (4, Some("2024-05-01")));
+        assert!(aggregate("year", day(10), &sessions, &calls).is_err());
+    }
+
+    #[test]
+    fn test_slowest_step() {
+        let timing = PipelineTiming {
+            hud_shown_ms: Some(40),
+            first_partial_ms: Some(900),
+            final_ms: Some(9_000),
+            refined_ms: Some(10_200),
+            pasted_ms: Some(10_350),
+        };
+        assert_eq!(timing.slowest_step(), Some(("refined", 1_200)));
+        // A missing step is measured from the last one reached
+        let no_partial = PipelineTiming { first_partial_ms: None, final_ms: Some(1_000), refined_ms: None, ..timing };
+        assert_eq!(no_partial.slowest_step(), Some(("pasted", 9_350)));
+        assert_eq!(PipelineTiming::default().slowest_step(), None);
+    }
+
+    #[test]
+    fn test_wpm_needs_a_real_recording() {
+        assert_eq!(metrics(3, 500, "deepgram").wpm(), None);
+        assert_eq!(metrics(30, 20_000, "deepgram").wpm(), Some(90.0));
+    }
+}
@@ -0,0 +1,161 @@
+// Input level and voice activity for the PCM the HUD relays to the backend. Audio is cut into
+// 50ms frames; each gets an RMS level in dBFS and a speech/no-speech decision against an adaptive
+// noise floor. Speech starts after a short run of loud frames and ends after a longer run of quiet
+// ones, so single clicks don't count and pauses between words don't end it.
+
+/// Length of one analysis frame
+pub const FRAME_MS: u32 = 50;
+/// Samples per frame at the relay's 16kHz
+const FRAME_SAMPLES: usize = 16_000 * FRAME_MS as usize / 1000;
+/// Level reported for digital silence
+pub const FLOOR_DB: f32 = -90.0;
+/// Quieter than this is never speech, however low the noise floor
+const MIN_SPEECH_DB: f32 = -50.0;
+/// How far above the noise floor speech has to be
+const SPEECH_MARGIN_DB: f32 = 12.0;
+/// Loud frames in a row before speech starts (100ms)
+const ONSET_FRAMES: u32 = 2;
+/// Quiet frames in a row before speech ends (400ms)
+const HANGOVER_FRAMES: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frame {
+    /// RMS level in dBFS
+    pub db: f32,
+    /// Speech is active after this frame
+    pub speech: bool,
+}
+
+/// RMS level of little-endian 16-bit PCM, in dBFS
+pub fn rms_db(pcm: &[u8]) -> f32 {
+    let samples = pcm.len() / 2;
+    if samples == 0 {
+        return FLOOR_DB;
+    }
+    let sum: f64 = pcm
+        .chunks_exact(2)
+        .map(|b| {
+            let s = i16::from_le_bytes([b[0], b[1]]) as f64 / i16::MAX as f64;
+            s * s
+        })
+        .sum();
+    let rms = (sum / samples as f64).sqrt();
+    if rms <= 0.0 {
+        FLOOR_DB
+    } else {
+        (20.0 * rms.log10()).max(FLOOR_DB as f64) as f32
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vad {
+    /// Bytes of an incomplete frame, kept for the next push
+    partial: Vec<u8>,
+    noise_floor: f32,
+    speech: bool,
+    loud_run: u32,
+    quiet_run: u32,
+    heard_speech: bool,
+}
+
+impl Default for Vad {
+    fn default() -> Self {
+        Vad { partial: Vec::new(), noise_floor: -60.0, speech: false, loud_run: 0, quiet_run: 0, heard_speech: false }
+    }
+}
+
+impl Vad {
+    /// Analyze more audio; returns one entry per completed frame
+    pub fn push(&mut self, pcm: &[u8]) -> Vec<Frame> {
+        self.partial.extend_from_slice(pcm);
+        let frame_bytes = FRAME_SAMPLES * 2;
+        let complete = self.partial.len() / frame_bytes * frame_bytes;
+        let levels: Vec<f32> = self.partial[..complete].chunks(frame_bytes).map(rms_db).collect();
+        self.partial.drain(..complete);
+        levels.into_iter().map(|db| self.frame(db)).collect()
+    }
+
+    fn frame(&mut self, db: f32) -> Frame {
+        let loud = db >= MIN_SPEECH_DB.max(self.noise_floor + SPEECH_MARGIN_DB);
+        if loud {
+            self.loud_run += 1;
+            self.quiet_run = 0;
+        } else {
+            self.quiet_run += 1;
+            self.loud_run = 0;
+            // Follow the background down at once, up slowly (so speech doesn't raise it)
+            self.noise_floor = if db < self.noise_floor { db } else { self.noise_floor * 0.95 + db * 0.05 };
+        }
+        if !self.speech && self.loud_run >= ONSET_FRAMES {
+            self.speech = true;
+            self.heard_speech = true;
+        } else if self.speech && self.quiet_run >= HANGOVER_FRAMES {
+            self.speech = false;
+        }
+        Frame { db, speech: self.speech }
+    }
+
+    pub fn is_speech(&self) -> bool {
+        self.speech
+    }
+
+    /// Whether speech was detected at any point
+    pub fn heard_speech(&self) -> bool {
+        self.heard_speech
+    }
+
+    /// How long the input has been quiet, in milliseconds (0 while speaking)
+    pub fn silence_ms(&self) -> u32 {
+        if self.speech { 0 } else { self.quiet_run * FRAME_MS }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(amplitude: i16, ms: usize) -> Vec<u8> {
+        (0..16 * ms).flat_map(|i| (if i % 2 == 0 { amplitude } else { -amplitude }).to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn test_rms_db() {
+        assert_eq!(rms_db(&[]), FLOOR_DB);
+        assert_eq!(rms_db(&tone(0, 50)), FLOOR_DB);
+        assert!(rms_db(&tone(i16::MAX, 50)).abs() < 0.01);
+        assert!((rms_db(&tone(i16::MAX / 10, 50)) + 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_frames_span_pushes() {
+        let mut vad = Vad::default();
+        let audio = tone(100, 120);
+        assert_eq!(vad.push(&audio[..1000]).len(), 0);
+        assert_eq!(vad.push(&audio[1000..]).len(), 2);
+    }
+
+    #[test]
+    fn test_speech_onset_and_hangover() {
+        let mut vad = Vad::default();
+        vad.push(&tone(30, 500));
+        assert!(!vad.is_speech());
+        assert_eq!(vad.silence_ms(), 500);
+
+        // A single loud frame is a click, not speech
+        vad.push(&tone(8000, 50));
+        vad.push(&tone(30, 50));
+        assert!(!vad.heard_speech());
+
+        let frames = vad.push(&tone(8000, 300));
+        assert!(!frames[0].speech && frames[1].speech);
+        assert_eq!(vad.silence_ms(), 0);
+
+        // A short pause between words keeps speech active, a long one ends it
+        vad.push(&tone(30, 200));
+        assert!(vad.is_speech());
+        vad.push(&tone(30, 300));
+        assert!(!vad.is_speech());
+        assert_eq!(vad.silence_ms(), 500);
+        assert!(vad.heard_speech());
+    }
+}
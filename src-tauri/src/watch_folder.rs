@@ -0,0 +1,89 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::AppHandle;
+use tracing::info;
+
+use crate::{jobs, stt};
+
+// Watch-folder transcription: audio files dropped into the configured folder are queued as
+// transcription jobs (see jobs.rs), which write the text next to them as `<name>.txt` or
+// `<name>.md`. The folder is polled rather than watched through OS notifications; a file is
+// picked up once its size has stopped changing, so a recorder still writing it is left alone. A
+// file that already has its output next to it, or a job, is not queued again.
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct WatchStatus {
+  /// The watched folder; None while watching is off
+  pub folder: Option<String>,
+}
+
+#[derive(Default)]
+struct State {
+  folder: Option<PathBuf>,
+  /// Size seen on the previous poll, to tell finished files from ones still being written
+  sizes: HashMap<PathBuf, u64>,
+}
+
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> R {
+  let mut guard = STATE.lock().unwrap();
+  f(guard.get_or_insert_with(State::default))
+}
+
+pub fn status() -> WatchStatus {
+  with_state(|s| WatchStatus { folder: s.folder.as_ref().map(|f| f.to_string_lossy().into_owned()) })
+}
+
+/// Audio files in `folder` that are complete and not transcribed or queued yet
+fn ready_files(folder: &Path) -> Vec<PathBuf> {
+  let Ok(entries) = std::fs::read_dir(folder) else { return Vec::new() };
+  let mut sizes = HashMap::new();
+  for entry in entries.flatten() {
+    let path = entry.path();
+    if stt::audio_content_type(&path).is_none() || jobs::has_output(&path) || jobs::has_job_for(&path) {
+      continue;
+    }
+    if let Ok(meta) = entry.metadata() {
+      if meta.is_file() && meta.len() > 0 {
+        sizes.insert(path, meta.len());
+      }
+    }
+  }
+  with_state(|s| {
+    let ready = sizes.iter().filter(|(path, size)| s.sizes.get(*path) == Some(*size)).map(|(path, _)| path.clone()).collect();
+    s.sizes = sizes;
+    ready
+  })
+}
+
+/// Poll the configured folder for the lifetime of the app
+pub fn spawn(app: AppHandle) {
+  tauri::async_runtime::spawn(async move {
+    loop {
+      tokio::time::sleep(POLL_INTERVAL).await;
+      let prefs = crate::get_behavior(app.clone()).await.unwrap_or_default();
+      let folder = prefs.watch_folder.as_ref().map(PathBuf::from);
+      let changed = with_state(|s| {
+        if s.folder == folder {
+          return false;
+        }
+        *s = State { folder: folder.clone(), ..State::default() };
+        true
+      });
+      let Some(folder) = folder else { continue };
+      if changed {
+        info!("Watching {} for audio files", folder.display());
+      }
+      for audio in ready_files(&folder) {
+        jobs::enqueue(&app, &audio, prefs.watch_refine, &prefs.watch_output_format);
+      }
+    }
+  });
+}
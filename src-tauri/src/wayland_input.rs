@@ -0,0 +1,123 @@
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::{info, warn};
+
+// Text injection for Wayland sessions, where enigo's X11 path only reaches XWayland windows
+// (and often not even those). Prefers wtype, which speaks the virtual-keyboard protocol
+// natively (wlroots compositors, KDE), and falls back to ydotool, which goes through uinput and
+// works on any compositor but needs the ydotoold daemon running.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+  Wtype,
+  Ydotool,
+}
+
+static BACKEND: OnceLock<Option<Backend>> = OnceLock::new();
+
+/// Whether this process runs in a Wayland session
+pub fn is_wayland_session() -> bool {
+  let session_is_wayland = std::env::var("XDG_SESSION_TYPE").map(|t| t.eq_ignore_ascii_case("wayland")).unwrap_or(false);
+  session_is_wayland || std::env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+fn tool_available(cmd: &str, probe_arg: &str) -> bool {
+  Command::new(cmd)
+    .arg(probe_arg)
+    .stdout(Stdio::null())
+    .stderr(Stdio::null())
+    .status()
+    .is_ok()
+}
+
+/// Injection backend for this session, detected once. None outside Wayland or without a tool.
+pub fn backend() -> Option<Backend> {
+  *BACKEND.get_or_init(|| {
+    if !is_wayland_session() {
+      return None;
+    }
+    let found = if tool_available("wtype", "-h") {
+      Some(Backend::Wtype)
+    } else if tool_available("ydotool", "help") {
+      Some(Backend::Ydotool)
+    } else {
+      None
+    };
+    match found {
+      Some(b) => info!("Wayland session: injecting input via {:?}", b),
+      None => warn!("Wayland session without wtype or ydotool; falling back to enigo (may only reach XWayland apps)"),
+    }
+    found
+  })
+}
+
+fn run(cmd: &str, args: &[String]) -> anyhow::Result<()> {
+  let out = Command::new(cmd).args(args).stdout(Stdio::null()).output()?;
+  if !out.status.success() {
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    return Err(anyhow::anyhow!("{} failed: {}", cmd, stderr.trim()));
+  }
+  Ok(())
+}
+
+/// Linux evdev keycode for a letter (KEY_A = 30, ...), as ydotool expects
+fn evdev_letter(letter: char) -> Option<u16> {
+  const ROWS: &[(&str, u16)] = &[("qwertyuiop", 16), ("asdfghjkl", 30), ("zxcvbnm", 44)];
+  let letter = letter.to_ascii_lowercase();
+  ROWS.iter().find_map(|(row, first)| row.find(letter).map(|i| first + i as u16))
+}
+
+const KEY_LEFTCTRL: u16 = 29;
+const KEY_LEFTSHIFT: u16 = 42;
+
+/// Send Ctrl+letter (plus Shift) through the Wayland backend.
+/// Returns None when no backend is available so the caller can use enigo instead.
+pub fn send_shortcut(letter: char, shift: bool) -> Option<anyhow::Result<()>> {
+  let result = match backend()? {
+    Backend::Wtype => {
+      let mut args: Vec<String> = vec!["-M".into(), "ctrl".into()];
+      if shift { args.extend(["-M".into(), "shift".into()]); }
+      args.extend(["-k".into(), letter.to_ascii_lowercase().to_string()]);
+      if shift { args.extend(["-m".into(), "shift".into()]); }
+      args.extend(["-m".into(), "ctrl".into()]);
+      run("wtype", &args)
+    }
+    Backend::Ydotool => {
+      let Some(code) = evdev_letter(letter) else {
+        return Some(Err(anyhow::anyhow!("no keycode for {:?}", letter)));
+      };
+      let mut mods = vec![KEY_LEFTCTRL];
+      if shift { mods.push(KEY_LEFTSHIFT); }
+      let mut args: Vec<String> = vec!["key".into()];
+      args.extend(mods.iter().map(|m| format!("{}:1", m)));
+      args.push(format!("{}:1", code));
+      args.push(format!("{}:0", code));
+      args.extend(mods.iter().rev().map(|m| format!("{}:0", m)));
+      run("ydotool", &args)
+    }
+  };
+  Some(result)
+}
+
+/// Type text through the Wayland backend; None when no backend is available
+pub fn type_text(text: &str, per_char_delay: Duration) -> Option<anyhow::Result<()>> {
+  let delay_ms = per_char_delay.as_millis().to_string();
+  let result = match backend()? {
+    Backend::Wtype => run("wtype", &["-d".into(), delay_ms, "--".into(), text.to_string()]),
+    Backend::Ydotool => run("ydotool", &["type".into(), "--key-delay".into(), delay_ms, "--".into(), text.to_string()]),
+  };
+  Some(result)
+}
+
+/// Press a single named key through the Wayland backend; None when no backend is available
+pub fn press_key(key: crate::keymacro::MacroKey) -> Option<anyhow::Result<()>> {
+  let result = match backend()? {
+    Backend::Wtype => run("wtype", &["-k".into(), key.xkb_name().into()]),
+    Backend::Ydotool => {
+      let code = key.evdev_code();
+      run("ydotool", &["key".into(), format!("{}:1", code), format!("{}:0", code)])
+    }
+  };
+  Some(result)
+}
@@ -0,0 +1,392 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::sync::Mutex;
+
+// Explicit paste target chosen from the tray "Dictate into…" menu.
+// When set, insertion activates this window first instead of trusting whatever has focus.
+// Window ids are platform handles: HWND on Windows, X11 window id on Linux, pid on macOS (where
+// the title tells the app's windows apart).
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct WindowInfo {
+  pub id: u64,
+  pub title: String,
+  pub app: String,
+  /// RGBA icon pixels with width/height, when the platform can provide one
+  #[serde(skip)]
+  pub icon: Option<(Vec<u8>, u32, u32)>,
+}
+
+impl WindowInfo {
+  /// Label used in the tray menu, e.g. "chrome.exe — Inbox (3)"
+  pub fn menu_label(&self) -> String {
+    let title: String = if self.title.chars().count() > 60 {
+      self.title.chars().take(57).collect::<String>() + "..."
+    } else {
+      self.title.clone()
+    };
+    if self.app.is_empty() || self.app == title { title } else { format!("{} — {}", self.app, title) }
+  }
+}
+
+static PASTE_TARGET: Mutex<Option<WindowInfo>> = Mutex::new(None);
+static LAST_LISTED: Mutex<Vec<WindowInfo>> = Mutex::new(Vec::new());
+// Window that had focus (or the explicit target) when the running dictation started
+static SESSION_WINDOW: Mutex<Option<WindowInfo>> = Mutex::new(None);
+
+pub fn set_target(target: Option<WindowInfo>) {
+  *PASTE_TARGET.lock().unwrap() = target;
+}
+
+pub fn get_target() -> Option<WindowInfo> {
+  PASTE_TARGET.lock().unwrap().clone()
+}
+
+pub fn set_session_window(window: Option<WindowInfo>) {
+  *SESSION_WINDOW.lock().unwrap() = window;
+}
+
+pub fn session_window() -> Option<WindowInfo> {
+  SESSION_WINDOW.lock().unwrap().clone()
+}
+
+/// The window the running dictation started in, if it has been closed since
+pub fn closed_session_window() -> Option<WindowInfo> {
+  session_window().filter(|w| !window_exists(w.id))
+}
+
+/// Enumerate visible top-level windows and remember them so a later menu click can resolve its id.
+pub fn list_windows() -> Vec<WindowInfo> {
+  let windows = platform::list_windows();
+  *LAST_LISTED.lock().unwrap() = windows.clone();
+  windows
+}
+
+/// Look up a window from the most recent enumeration
+pub fn find_listed(id: u64) -> Option<WindowInfo> {
+  LAST_LISTED.lock().unwrap().iter().find(|w| w.id == id).cloned()
+}
+
+pub fn foreground_window() -> Option<WindowInfo> {
+  platform::foreground_window()
+}
+
+pub fn window_exists(id: u64) -> bool {
+  platform::window_exists(id)
+}
+
+/// Bring the window to the foreground. Returns false if it no longer exists or could not be raised.
+pub fn activate(id: u64) -> bool {
+  platform::activate(id)
+}
+
+/// Like `activate`, but on macOS (where the id is the app's pid) also raise the app's window with
+/// this title rather than whichever of its windows was last in front
+pub fn raise(window: &WindowInfo) -> bool {
+  platform::raise(window)
+}
+
+#[cfg(all(target_os = "windows", feature = "windows-focus"))]
+mod platform {
+  use super::WindowInfo;
+  use windows::core::PWSTR;
+  use windows::Win32::Foundation::{BOOL, CloseHandle, HWND, LPARAM, WPARAM};
+  use windows::Win32::Graphics::Gdi::{
+    DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC, BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+  };
+  use windows::Win32::System::Threading::{OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
+  use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetForegroundWindow, GetIconInfo, GetWindowLongW, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+    IsWindow, IsWindowVisible, SendMessageTimeoutW, SetForegroundWindow, ShowWindow, GWL_EXSTYLE, HICON, ICONINFO,
+    ICON_BIG, ICON_SMALL2, SMTO_ABORTIFHUNG, SW_RESTORE, WM_GETICON, WS_EX_TOOLWINDOW,
+  };
+
+  fn hwnd(id: u64) -> HWND { HWND(id as usize as *mut core::ffi::c_void) }
+
+  fn title(h: HWND) -> String {
+    let mut buf = [0u16; 512];
+    let len = unsafe { GetWindowTextW(h, &mut buf) };
+    String::from_utf16_lossy(&buf[..len.max(0) as usize])
+  }
+
+  fn process_id(h: HWND) -> u32 {
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(h, Some(&mut pid)); }
+    pid
+  }
+
+  fn exe_name(pid: u32) -> String {
+    unsafe {
+      let Ok(handle) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else { return String::new() };
+      let mut buf = [0u16; 260];
+      let mut len = buf.len() as u32;
+      let ok = QueryFullProcessImageNameW(handle, PROCESS_NAME_WIN32, PWSTR(buf.as_mut_ptr()), &mut len).is_ok();
+      let _ = CloseHandle(handle);
+      if !ok { return String::new(); }
+      let path = String::from_utf16_lossy(&buf[..len as usize]);
+      path.rsplit(['\\', '/']).next().unwrap_or("").to_string()
+    }
+  }
+
+  /// Read the window's small icon and convert it to RGBA
+  fn icon_rgba(h: HWND) -> Option<(Vec<u8>, u32, u32)> {
+    unsafe {
+      let mut result: usize = 0;
+      for kind in [ICON_SMALL2, ICON_BIG] {
+        SendMessageTimeoutW(h, WM_GETICON, WPARAM(kind as usize), LPARAM(0), SMTO_ABORTIFHUNG, 50, Some(&mut result));
+        if result != 0 { break; }
+      }
+      if result == 0 { return None; }
+      let hicon = HICON(result as *mut core::ffi::c_void);
+
+      let mut info = ICONINFO::default();
+      GetIconInfo(hicon, &mut info).ok()?;
+      let mut bmp = BITMAP::default();
+      let got = GetObjectW(info.hbmColor, std::mem::size_of::<BITMAP>() as i32, Some(&mut bmp as *mut _ as *mut core::ffi::c_void));
+      let (width, height) = (bmp.bmWidth, bmp.bmHeight);
+      let mut pixels = vec![0u8; (width.max(0) * height.max(0) * 4) as usize];
+      let mut ok = got != 0 && width > 0 && height > 0;
+      if ok {
+        let mut bmi = BITMAPINFO {
+          bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            biHeight: -height, // top-down rows
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+          },
+          ..Default::default()
+        };
+        let hdc = GetDC(HWND::default());
+        ok = GetDIBits(hdc, info.hbmColor, 0, height as u32, Some(pixels.as_mut_ptr() as *mut _), &mut bmi, DIB_RGB_COLORS) != 0;
+        ReleaseDC(HWND::default(), hdc);
+      }
+      let _ = DeleteObject(info.hbmColor);
+      let _ = DeleteObject(info.hbmMask);
+      if !ok { return None; }
+
+      // BGRA -> RGBA
+      for px in pixels.chunks_exact_mut(4) { px.swap(0, 2); }
+      Some((pixels, width as u32, height as u32))
+    }
+  }
+
+  fn describe(h: HWND, with_icon: bool) -> WindowInfo {
+    let pid = process_id(h);
+    WindowInfo {
+      id: h.0 as usize as u64,
+      title: title(h),
+      app: exe_name(pid),
+      icon: if with_icon { icon_rgba(h) } else { None },
+    }
+  }
+
+  unsafe extern "system" fn collect(h: HWND, lparam: LPARAM) -> BOOL {
+    let out = &mut *(lparam.0 as *mut Vec<HWND>);
+    out.push(h);
+    BOOL(1)
+  }
+
+  pub fn list_windows() -> Vec<WindowInfo> {
+    let mut handles: Vec<HWND> = Vec::new();
+    unsafe { let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut _ as isize)); }
+    let own_pid = std::process::id();
+    handles
+      .into_iter()
+      .filter(|h| unsafe {
+        IsWindowVisible(*h).as_bool() && (GetWindowLongW(*h, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW.0) == 0
+      })
+      .filter(|h| process_id(*h) != own_pid)
+      .map(|h| describe(h, true))
+      .filter(|w| !w.title.trim().is_empty() && w.title != "Program Manager")
+      .collect()
+  }
+
+  pub fn foreground_window() -> Option<WindowInfo> {
+    let h = unsafe { GetForegroundWindow() };
+    if h.0.is_null() { return None; }
+    Some(describe(h, false))
+  }
+
+  pub fn window_exists(id: u64) -> bool {
+    unsafe { IsWindow(hwnd(id)).as_bool() }
+  }
+
+  pub fn activate(id: u64) -> bool {
+    let h = hwnd(id);
+    unsafe {
+      if !IsWindow(h).as_bool() { return false; }
+      if IsIconic(h).as_bool() { let _ = ShowWindow(h, SW_RESTORE); }
+      SetForegroundWindow(h).as_bool()
+    }
+  }
+
+  pub fn raise(window: &WindowInfo) -> bool {
+    activate(window.id)
+  }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+  use super::WindowInfo;
+  use std::process::Command;
+
+  // macOS exposes per-window titles only through the Accessibility API, so targets are applications keyed by pid.
+  fn osascript(script: &str) -> Option<String> {
+    let out = Command::new("osascript").arg("-e").arg(script).output().ok()?;
+    if !out.status.success() { return None; }
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+  }
+
+  fn parse_pairs(raw: &str) -> Vec<WindowInfo> {
+    // One "pid<TAB>name" line per process; the pid comes first so names may hold commas and tabs
+    raw
+      .lines()
+      .filter_map(|line| {
+        let (pid, name) = line.split_once('\t')?;
+        let pid = pid.trim().parse::<u64>().ok()?;
+        let name = name.trim().to_string();
+        Some(WindowInfo { id: pid, title: name.clone(), app: name, icon: None })
+      })
+      .collect()
+  }
+
+  pub fn list_windows() -> Vec<WindowInfo> {
+    let own_pid = std::process::id() as u64;
+    osascript(
+      "tell application \"System Events\"
+        set out to \"\"
+        repeat with p in (every application process whose background only is false)
+          set out to out & ((unix id of p) as text) & tab & (name of p) & linefeed
+        end repeat
+        return out
+      end tell",
+    )
+    .map(|raw| parse_pairs(&raw))
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|w| w.id != own_pid)
+    .collect()
+  }
+
+  /// An AppleScript string literal
+  fn quoted(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+  }
+
+  pub fn foreground_window() -> Option<WindowInfo> {
+    // One field per line; the front window's title is empty for apps without windows
+    let raw = osascript(
+      "tell application \"System Events\"
+        set p to first application process whose frontmost is true
+        set t to \"\"
+        try
+          set t to name of front window of p
+        end try
+        return (name of p) & linefeed & (unix id of p) & linefeed & t
+      end tell",
+    )?;
+    let mut lines = raw.splitn(3, '\n');
+    let name = lines.next()?.to_string();
+    let pid = lines.next()?.trim().parse::<u64>().ok()?;
+    let title = lines.next().map(str::trim).filter(|t| !t.is_empty()).unwrap_or(&name).to_string();
+    Some(WindowInfo { id: pid, title, app: name, icon: None })
+  }
+
+  pub fn window_exists(id: u64) -> bool {
+    Command::new("kill").arg("-0").arg(id.to_string()).status().map(|s| s.success()).unwrap_or(false)
+  }
+
+  pub fn activate(id: u64) -> bool {
+    osascript(&format!(
+      "tell application \"System Events\" to set frontmost of (first process whose unix id is {}) to true",
+      id
+    ))
+    .is_some()
+  }
+
+  pub fn raise(window: &WindowInfo) -> bool {
+    if window.title == window.app {
+      return activate(window.id);
+    }
+    // AXRaise fails (and so does the whole script) when no window has that title any more
+    osascript(&format!(
+      "tell application \"System Events\" to tell (first process whose unix id is {})
+        perform action \"AXRaise\" of (first window whose name is {})
+        set frontmost to true
+      end tell",
+      window.id,
+      quoted(&window.title)
+    ))
+    .is_some()
+  }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+  use super::WindowInfo;
+  use std::process::Command;
+
+  // X11 via wmctrl/xdotool; on Wayland sessions these tools only see XWayland windows.
+  fn run(cmd: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(cmd).args(args).output().ok()?;
+    if !out.status.success() { return None; }
+    Some(String::from_utf8_lossy(&out.stdout).to_string())
+  }
+
+  fn process_name(pid: &str) -> String {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid)).map(|s| s.trim().to_string()).unwrap_or_default()
+  }
+
+  pub fn list_windows() -> Vec<WindowInfo> {
+    let own_pid = std::process::id().to_string();
+    // Format: "0x03a00007  0 12345 hostname Window title"
+    run("wmctrl", &["-lp"])
+      .unwrap_or_default()
+      .lines()
+      .filter_map(|line| {
+        let mut cols = line.split_whitespace();
+        let id = u64::from_str_radix(cols.next()?.trim_start_matches("0x"), 16).ok()?;
+        let desktop = cols.next()?;
+        let pid = cols.next()?;
+        let _host = cols.next()?;
+        let title = cols.collect::<Vec<_>>().join(" ");
+        if desktop == "-1" || pid == own_pid || title.is_empty() { return None; }
+        Some(WindowInfo { id, title, app: process_name(pid), icon: None })
+      })
+      .collect()
+  }
+
+  pub fn foreground_window() -> Option<WindowInfo> {
+    let id_raw = run("xdotool", &["getactivewindow"])?;
+    let id = id_raw.trim().parse::<u64>().ok()?;
+    let title = run("xdotool", &["getwindowname", id_raw.trim()]).unwrap_or_default().trim().to_string();
+    let pid = run("xdotool", &["getwindowpid", id_raw.trim()]).unwrap_or_default();
+    Some(WindowInfo { id, title, app: process_name(pid.trim()), icon: None })
+  }
+
+  pub fn window_exists(id: u64) -> bool {
+    run("xdotool", &["getwindowname", &id.to_string()]).is_some()
+  }
+
+  pub fn activate(id: u64) -> bool {
+    run("wmctrl", &["-ia", &format!("0x{:08x}", id)]).is_some()
+  }
+
+  pub fn raise(window: &WindowInfo) -> bool {
+    activate(window.id)
+  }
+}
+
+#[cfg(not(any(all(target_os = "windows", feature = "windows-focus"), target_os = "macos", target_os = "linux")))]
+mod platform {
+  use super::WindowInfo;
+
+  pub fn list_windows() -> Vec<WindowInfo> { Vec::new() }
+  pub fn foreground_window() -> Option<WindowInfo> { None }
+  pub fn window_exists(_id: u64) -> bool { false }
+  pub fn activate(_id: u64) -> bool { false }
+  pub fn raise(_window: &WindowInfo) -> bool { false }
+}
@@ -1,14 +1,20 @@
 import { motion } from 'framer-motion';
+import type { BadgeKind } from '../lib/hudEvents';
 
-export function Badge({ text }: { text: string }) {
+export function Badge({ text, kind = 'error' }: { text: string; kind?: BadgeKind }) {
+  const secure = kind === 'secure';
   return (
     <motion.div className="px-3 py-1 rounded-full border shadow-sm"
-      style={{
+      style={secure ? {
+        borderColor: 'var(--badge-secure-border, #6b4f00)',
+        background: 'var(--badge-secure-bg, #fff4d6)',
+        color: 'var(--badge-secure-text, #6b4f00)'
+      } : {
         borderColor: 'var(--badge-border, #7a1f1f)',
         background: 'var(--badge-bg, #fbe9e9)',
         color: 'var(--badge-text, #7a1f1f)'
       }}>
-      <span className="text-xs">{text}</span>
+      <span className="text-xs">{secure ? '🔒 ' : ''}{text}</span>
     </motion.div>
   );
 }
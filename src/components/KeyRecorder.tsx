@@ -1,6 +1,9 @@
 import { useState, useEffect, useRef } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
 import { Edit3 } from 'lucide-react';
+import { invoke } from '@tauri-apps/api/core';
+
+type CapturedHotkey = { combo: string; registrable: boolean; conflict: string | null; warnings: string[] };
 
 interface KeyRecorderProps {
   value: string;
@@ -45,6 +48,9 @@ export function KeyRecorder({ value, onChange, onSave }: KeyRecorderProps) {
   const recordedKeysRef = useRef<string[]>([]);
   const containerRef = useRef<HTMLDivElement>(null);
   const keyUpTimeoutRef = useRef<number | null>(null);
+  // True while the backend raw-key capture is running; DOM events then only drive the preview
+  const backendCaptureRef = useRef(false);
+  const [notes, setNotes] = useState<{ text: string; error: boolean }[]>([]);
 
   // Set up event listeners once on mount
   useEffect(() => {
@@ -85,6 +91,8 @@ export function KeyRecorder({ value, onChange, onSave }: KeyRecorderProps) {
         clearTimeout(keyUpTimeoutRef.current);
       }
 
+      if (backendCaptureRef.current) return;
+
       keyUpTimeoutRef.current = window.setTimeout(() => {
         console.log('[KeyRecorder] Finalizing combo, recorded keys:', recordedKeysRef.current);
         if (recordedKeysRef.current.length >= 2) {
@@ -135,7 +143,40 @@ export function KeyRecorder({ value, onChange, onSave }: KeyRecorderProps) {
     setRecordedKeys([]);
     recordingRef.current = true;
     setIsRecording(true);
+    setNotes([]);
     console.log('[KeyRecorder] Recording state set, recordingRef:', recordingRef.current, 'isRecording will update on next render');
+
+    // Prefer the backend capture: it sees keys the webview never gets (e.g. Super) and
+    // validates the combo. If it's unavailable, the DOM listeners above finish the job.
+    backendCaptureRef.current = true;
+    const finish = () => {
+      backendCaptureRef.current = false;
+      setIsRecording(false);
+      recordingRef.current = false;
+    };
+    invoke<CapturedHotkey>('capture_hotkey', { timeoutMs: 10000 })
+      .then((res) => {
+        console.log('[KeyRecorder] Backend capture:', res);
+        onChange(res.combo);
+        const next = res.warnings.map((text) => ({ text, error: false }));
+        if (res.conflict) next.unshift({ text: res.conflict, error: !res.registrable });
+        setNotes(next);
+        finish();
+      })
+      .catch((e) => {
+        const msg = String(e);
+        console.log('[KeyRecorder] Backend capture ended without a combo:', msg);
+        if (msg.includes('cancelled') || msg.includes('No key combo')) {
+          finish();
+          return;
+        }
+        // Capture unavailable: fall back to window key events (use any combo already typed)
+        backendCaptureRef.current = false;
+        if (recordedKeysRef.current.length >= 2) {
+          onChange(recordedKeysRef.current.join('+'));
+          finish();
+        }
+      });
   };
 
   const displayKeys = isRecording ? recordedKeys : (value ? value.split('+') : []);
@@ -143,6 +184,7 @@ export function KeyRecorder({ value, onChange, onSave }: KeyRecorderProps) {
   console.log('[KeyRecorder] Render - isRecording:', isRecording, 'recordedKeys:', recordedKeys, 'displayKeys:', displayKeys, 'value:', value);
 
   return (
+    <div>
     <div ref={containerRef} className="flex gap-2 items-center">
       <div
         className={`flex-1 px-3 py-2 bg-neutral-900 rounded border min-h-[42px] flex items-center gap-1.5 ${
@@ -219,5 +261,9 @@ export function KeyRecorder({ value, onChange, onSave }: KeyRecorderProps) {
         <span className="text-sm font-medium">Save</span>
       </button>
     </div>
+    {notes.map((n, i) => (
+      <div key={i} className={`text-xs mt-1 ${n.error ? 'text-red-400' : 'text-muted'}`}>{n.text}</div>
+    ))}
+    </div>
   );
 }
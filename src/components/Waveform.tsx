@@ -1,6 +1,8 @@
-import { useEffect, useRef } from 'react';
+import { useEffect, useRef, type MutableRefObject } from 'react';
 
-export function Waveform({ analyser }: { analyser: AnalyserNode }) {
+// `level`: linear RMS (0..1) measured by the backend for relayed audio; used instead of the
+// analyser while it is set
+export function Waveform({ analyser, level }: { analyser: AnalyserNode; level?: MutableRefObject<number | null> }) {
   const canvasRef = useRef<HTMLCanvasElement | null>(null);
   const rafRef = useRef(0);
 
@@ -16,14 +18,17 @@ export function Waveform({ analyser }: { analyser: AnalyserNode }) {
     const draw = () => {
       rafRef.current = requestAnimationFrame(draw);
 
-      // Use time‑domain data to capture overall loudness (more responsive to quiet speech)
-      analyser.getByteTimeDomainData(dataArray);
-      let sum = 0;
-      for (let i = 0; i < bufferLength; i++) {
-        const centered = dataArray[i] - 128;
-        sum += centered * centered;
+      let rms = level?.current ?? null;
+      if (rms === null) {
+        // Use time‑domain data to capture overall loudness (more responsive to quiet speech)
+        analyser.getByteTimeDomainData(dataArray);
+        let sum = 0;
+        for (let i = 0; i < bufferLength; i++) {
+          const centered = dataArray[i] - 128;
+          sum += centered * centered;
+        }
+        rms = Math.sqrt(sum / bufferLength) / 128; // 0..1
       }
-      const rms = Math.sqrt(sum / bufferLength) / 128; // 0..1
 
       // Boost quiet input aggressively and clamp to 1 (works even when echo/NS are off)
       const boosted = Math.min(1, rms * 5 + 0.08); // +floor keeps idle motion to show it's live
@@ -55,7 +60,7 @@ export function Waveform({ analyser }: { analyser: AnalyserNode }) {
     };
     rafRef.current = requestAnimationFrame(draw);
     return () => cancelAnimationFrame(rafRef.current);
-  }, [analyser]);
+  }, [analyser, level]);
 
   return <canvas ref={canvasRef} className="w-full h-full" />;
 }
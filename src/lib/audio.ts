@@ -10,3 +10,27 @@ export async function getMicAnalyser() {
   return { stream, analyser };
 }
 
+
+/** Feed a stream's audio as 16kHz linear16 PCM chunks (4096 samples, ~256ms); returns a stop function */
+export function capturePcm(stream: MediaStream, onChunk: (pcm: Uint8Array) => void) {
+  const audioContext = new AudioContext({ sampleRate: 16000 });
+  const source = audioContext.createMediaStreamSource(stream);
+  const processor = audioContext.createScriptProcessor(4096, 1, 1);
+  processor.onaudioprocess = (e) => {
+    const inputData = e.inputBuffer.getChannelData(0);
+    const int16Array = new Int16Array(inputData.length);
+    for (let i = 0; i < inputData.length; i++) {
+      const s = Math.max(-1, Math.min(1, inputData[i]));
+      int16Array[i] = s < 0 ? s * 0x8000 : s * 0x7FFF;
+    }
+    onChunk(new Uint8Array(int16Array.buffer));
+  };
+  source.connect(processor);
+  processor.connect(audioContext.destination);
+  return () => {
+    processor.onaudioprocess = null;
+    processor.disconnect();
+    source.disconnect();
+    audioContext.close().catch(() => {});
+  };
+}
@@ -4,9 +4,10 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import type { SegmentMeta, Word } from './transcript';
 
 type Handlers = {
-  onTranscript?: (text: string, isFinal: boolean) => void;
+  onTranscript?: (text: string, isFinal: boolean, meta?: SegmentMeta) => void;
   onError?: (err: any) => void;
   onOpen?: () => void;
   onClose?: () => void;
@@ -18,29 +19,50 @@ function log(msg: string) {
   invoke('log_to_terminal', { message: msg }).catch(() => {});
 }
 
-export async function startDeepgramStream(apiKey: string, stream: MediaStream, handlers: Handlers = {}) {
+// Connection options saved with `set_stt_options`
+export type DeepgramOptions = {
+  model: string;
+  smart_format: boolean;
+  punctuate: boolean;
+  endpointing_ms: number | null;
+  interim_results: boolean;
+};
+
+const DEFAULT_OPTIONS: DeepgramOptions = { model: 'nova-2', smart_format: true, punctuate: true, endpointing_ms: null, interim_results: true };
+
+// `keywords`: personal dictionary entries Deepgram should favor (names, jargon)
+// `language`: the provider's language setting; "auto" streams with code-switching detection
+// `endpoint`: a self-hosted Deepgram's base URL (`stt_endpoint_override`, saved normalized); empty for the hosted API
+export async function startDeepgramStream(apiKey: string, stream: MediaStream, handlers: Handlers = {}, keywords: string[] = [], options: DeepgramOptions = DEFAULT_OPTIONS, language = 'en', endpoint = '') {
   // CRITICAL: Use Web Audio API to send RAW PCM audio, not WebM containers
   // Deepgram's WebSocket API expects raw linear16 PCM audio
   // MediaRecorder sends WebM which Deepgram WebSocket doesn't parse correctly
 
   const params = new URLSearchParams({
-    model: 'nova-2',
-    language: 'en',
-    smart_format: 'true',
-    interim_results: 'true',
-    punctuate: 'true',
+    model: options.model,
+    language: language === 'auto' ? 'multi' : language,
+    smart_format: String(options.smart_format),
+    interim_results: String(options.interim_results),
+    punctuate: String(options.punctuate),
     encoding: 'linear16',  // Tell Deepgram we're sending raw PCM
     sample_rate: '16000',  // 16kHz sample rate
     channels: '1'          // Mono audio
   });
+  // 0 turns endpointing off; unset keeps Deepgram's default
+  if (options.endpointing_ms !== null) params.set('endpointing', options.endpointing_ms === 0 ? 'false' : String(options.endpointing_ms));
+  // Deepgram caps the URL length; the backend sends the same first 100
+  for (const k of keywords.slice(0, 100)) params.append('keywords', k);
 
   const key = (apiKey || '').trim();
-  const url = `wss://api.deepgram.com/v1/listen?${params.toString()}`;
+  const base = endpoint ? endpoint.replace(/^http/, 'ws') : 'wss://api.deepgram.com';
+  const url = `${base}/v1/listen?${params.toString()}`;
   const ws = new WebSocket(url, ['token', key]);
   ws.binaryType = 'arraybuffer';
 
   let keepAliveInterval: number | null = null;
   let canceled = false;
+  // Paused dictation: audio is dropped and the KeepAlive below holds the stream open
+  let paused = false;
 
   ws.onopen = () => {
     if (canceled) {
@@ -101,14 +123,30 @@ export async function startDeepgramStream(apiKey: string, stream: MediaStream, h
       if (msg.type === 'Results') {
         // Extract transcript from the response
         // Response structure: msg.channel.alternatives[0].transcript
-        const transcript = msg?.channel?.alternatives?.[0]?.transcript;
+        const alt = msg?.channel?.alternatives?.[0];
+        const transcript = alt?.transcript;
 
         if (transcript && transcript.trim().length > 0) {
           // speech_final: true means this segment naturally ended (pause detected)
           // is_final: true means Deepgram won't send more updates for this time span
           const isFinal = !!msg?.speech_final;
           log(`[Deepgram] Transcript (final=${isFinal}): "${transcript}"`);
-          handlers.onTranscript?.(transcript, isFinal);
+          // start/duration are seconds from the start of the stream
+          const start = typeof msg?.start === 'number' ? msg.start : null;
+          const meta: SegmentMeta = {
+            start_ms: start !== null ? Math.round(start * 1000) : null,
+            end_ms: start !== null && typeof msg?.duration === 'number' ? Math.round((start + msg.duration) * 1000) : null,
+            confidence: typeof alt?.confidence === 'number' ? alt.confidence : null,
+            words: (alt?.words || []).map((w: any): Word => ({
+              text: w.punctuated_word || w.word,
+              start_ms: Math.round(w.start * 1000),
+              end_ms: Math.round(w.end * 1000),
+              confidence: w.confidence ?? null,
+            })),
+            // Only with language=multi (code-switching) or detect_language
+            language: alt?.languages?.[0] ?? msg?.channel?.detected_language ?? null,
+          };
+          handlers.onTranscript?.(transcript, isFinal, meta);
         }
       }
     } catch (e) {
@@ -133,7 +171,7 @@ export async function startDeepgramStream(apiKey: string, stream: MediaStream, h
 
   let audioChunkCount = 0;
   processor.onaudioprocess = (e) => {
-    if (canceled || ws.readyState !== WebSocket.OPEN) {
+    if (canceled || paused || ws.readyState !== WebSocket.OPEN) {
       return;
     }
 
@@ -169,6 +207,7 @@ export async function startDeepgramStream(apiKey: string, stream: MediaStream, h
   log('[Deepgram] Audio pipeline connected: mic -> processor -> speakers');
 
   return {
+    setPaused: (p: boolean) => { paused = p; },
     stop: () => {
       log('[Deepgram] Stopping recording and closing WebSocket');
       canceled = true;
@@ -1,7 +1,9 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { SegmentMeta } from './transcript';
 
 type Handlers = {
-  onTranscript?: (text: string, isFinal: boolean) => void;
+  // The realtime API sends no per-segment timing, so meta is never set here
+  onTranscript?: (text: string, isFinal: boolean, meta?: SegmentMeta) => void;
   onError?: (err: any) => void;
   onOpen?: () => void;
   onClose?: () => void;
@@ -22,21 +24,29 @@ function toBase64(buffer: ArrayBuffer): string {
   return btoa(binary);
 }
 
-export async function startElevenLabsStream(token: string, stream: MediaStream, handlers: Handlers = {}) {
+// Connection options saved with `set_stt_options`
+export type ElevenLabsOptions = { model: string };
+
+// `language`: the provider's language setting; "auto" lets Scribe detect it
+export async function startElevenLabsStream(token: string, stream: MediaStream, handlers: Handlers = {}, options: ElevenLabsOptions = { model: 'scribe_v2_realtime' }, language = 'auto') {
   const params = new URLSearchParams({
-    model_id: 'scribe_v2_realtime',
+    model_id: options.model,
     // Use VAD-based committing by default so segments are finalized automatically.
     // We still send a manual commit on stop as an extra guarantee.
     commit_strategy: 'vad',
     audio_format: 'pcm_16000',
     token: token,
   });
+  if (language !== 'auto') params.set('language_code', language);
   const wsUrl = `wss://api.elevenlabs.io/v1/speech-to-text/realtime?${params.toString()}`;
 
   const ws = new WebSocket(wsUrl);
   ws.binaryType = 'arraybuffer';
 
   let stopSending = false;
+  // Paused dictation: audio is dropped; ElevenLabs has no KeepAlive, so a little silence keeps it open
+  let paused = false;
+  let silenceInterval: number | null = null;
   let audioContext: AudioContext | null = null;
   let source: MediaStreamAudioSourceNode | null = null;
   let processor: ScriptProcessorNode | null = null;
@@ -90,7 +100,7 @@ export async function startElevenLabsStream(token: string, stream: MediaStream,
   processor = audioContext.createScriptProcessor(4096, 1, 1);
 
   processor.onaudioprocess = (e) => {
-    if (stopSending || ws.readyState !== WebSocket.OPEN) return;
+    if (stopSending || paused || ws.readyState !== WebSocket.OPEN) return;
     const input = e.inputBuffer.getChannelData(0);
     const int16 = new Int16Array(input.length);
     for (let i = 0; i < input.length; i++) {
@@ -109,9 +119,27 @@ export async function startElevenLabsStream(token: string, stream: MediaStream,
   source.connect(processor);
   processor.connect(audioContext.destination);
 
+  const sendSilence = () => {
+    if (stopSending || ws.readyState !== WebSocket.OPEN) return;
+    // 100ms at 16kHz
+    ws.send(JSON.stringify({
+      message_type: 'input_audio_chunk',
+      audio_base_64: toBase64(new Int16Array(1600).buffer),
+      sample_rate: 16000,
+      commit: false,
+    }));
+  };
+
   return {
+    setPaused: (p: boolean) => {
+      paused = p;
+      if (silenceInterval) window.clearInterval(silenceInterval);
+      silenceInterval = p ? window.setInterval(sendSilence, 4000) : null;
+    },
     stop: async () => {
       stopSending = true;
+      if (silenceInterval) window.clearInterval(silenceInterval);
+      silenceInterval = null;
       try {
         if (processor && source) {
           processor.disconnect();
@@ -6,6 +6,19 @@ let isProcessingHotkey = false;
 let lastHotkeyTime = 0;
 const HOTKEY_COOLDOWN_MS = 500; // Minimum time between hotkey actions
 
+// Resolves once the main hotkey works, so non-critical startup work (update check) can wait for it
+let resolveHotkeyReady: () => void = () => {};
+const hotkeyReady = new Promise<void>((resolve) => { resolveHotkeyReady = resolve; });
+
+export function whenHotkeyReady(timeoutMs = 10000): Promise<void> {
+  return Promise.race([hotkeyReady, new Promise<void>((resolve) => setTimeout(resolve, timeoutMs))]);
+}
+
+function markHotkeyReady(startedAt: number) {
+  invoke('report_hotkey_registered', { durationMs: Math.round(performance.now() - startedAt) }).catch(() => {});
+  resolveHotkeyReady();
+}
+
 export async function initGlobalHotkey(combo: string): Promise<boolean> {
   const logMsg = (msg: string) => {
     console.log(msg);
@@ -13,6 +26,7 @@ export async function initGlobalHotkey(combo: string): Promise<boolean> {
   };
 
   logMsg('=== initGlobalHotkey START ===');
+  const startedAt = performance.now();
   logMsg('Combo to register: ' + combo);
 
   if (!combo || combo.trim() === '') {
@@ -31,7 +45,9 @@ export async function initGlobalHotkey(combo: string): Promise<boolean> {
 
     if (!already) {
       logMsg('📝 Attempting to register hotkey: ' + combo);
-      await register(combo, async () => {
+      await register(combo, async (event: any) => {
+        // Reload an unloaded HUD as early as possible
+        if (event?.state === 'Pressed') invoke('prewarm_hud').catch(() => {});
         logMsg('🔥🔥🔥 GLOBAL HOTKEY PRESSED 🔥🔥🔥');
         logMsg('Combo that was pressed: ' + combo);
 
@@ -81,6 +97,11 @@ export async function initGlobalHotkey(combo: string): Promise<boolean> {
     } else {
       logMsg('ℹ️ Hotkey already registered: ' + combo);
     }
+    markHotkeyReady(startedAt);
+    await registerCodeModeHotkey(logMsg);
+    await registerRetakeHotkey(logMsg);
+    await registerPauseHotkey(logMsg);
+    await registerSelectionHotkeys(logMsg);
     logMsg('=== initGlobalHotkey END (success) ===');
     return true;
   } catch (e) {
@@ -89,6 +110,7 @@ export async function initGlobalHotkey(combo: string): Promise<boolean> {
     // If the hotkey is already registered (e.g., due to React StrictMode double-mount), treat as success
     if (errStr.includes('HotKey already registered')) {
       logMsg('ℹ️ Hotkey already registered (likely due to concurrent mount), treating as success');
+      markHotkeyReady(startedAt);
       logMsg('=== initGlobalHotkey END (already registered) ===');
       return true;
     }
@@ -105,6 +127,85 @@ export async function initGlobalHotkey(combo: string): Promise<boolean> {
   }
 }
 
+// Secondary shortcut that flips code dictation mode; failures here never block the main hotkey
+async function registerCodeModeHotkey(logMsg: (msg: string) => void) {
+  try {
+    const combo = await invoke<string>('get_code_mode_hotkey');
+    if (!combo || (await isRegistered(combo))) return;
+    await register(combo, async (event: any) => {
+      if (event?.state && event.state !== 'Pressed') return;
+      const enabled = await invoke<boolean>('toggle_code_mode');
+      logMsg('💻 Code mode toggled via hotkey: ' + (enabled ? 'ON' : 'OFF'));
+    });
+    logMsg('✅ Code mode hotkey registered: ' + combo);
+  } catch (e) {
+    logMsg('⚠️ Code mode hotkey registration failed: ' + String(e));
+  }
+}
+
+// Discards the utterance being dictated and starts a fresh take; the HUD keeps the old one
+async function registerRetakeHotkey(logMsg: (msg: string) => void) {
+  try {
+    const combo = await invoke<string>('get_retake_hotkey');
+    if (!combo || (await isRegistered(combo))) return;
+    await register(combo, async (event: any) => {
+      if (event?.state && event.state !== 'Pressed') return;
+      try {
+        await invoke('retake_dictation');
+        logMsg('🔁 Retake via hotkey');
+      } catch (e) {
+        logMsg('ℹ️ Retake ignored: ' + String(e));
+      }
+    });
+    logMsg('✅ Retake hotkey registered: ' + combo);
+  } catch (e) {
+    logMsg('⚠️ Retake hotkey registration failed: ' + String(e));
+  }
+}
+
+// Puts the recording on hold, or picks it up again; the HUD shows it is paused
+async function registerPauseHotkey(logMsg: (msg: string) => void) {
+  try {
+    const combo = await invoke<string>('get_pause_hotkey');
+    if (!combo || (await isRegistered(combo))) return;
+    await register(combo, async (event: any) => {
+      if (event?.state && event.state !== 'Pressed') return;
+      try {
+        const paused = await invoke<boolean>('toggle_pause_dictation');
+        logMsg(paused ? '⏸️ Paused via hotkey' : '▶️ Resumed via hotkey');
+      } catch (e) {
+        logMsg('ℹ️ Pause ignored: ' + String(e));
+      }
+    });
+    logMsg('✅ Pause hotkey registered: ' + combo);
+  } catch (e) {
+    logMsg('⚠️ Pause hotkey registration failed: ' + String(e));
+  }
+}
+
+// Shortcuts that run an AI action on the selected text (summarize, rewrite, fix grammar)
+async function registerSelectionHotkeys(logMsg: (msg: string) => void) {
+  try {
+    const actions = await invoke<{ id: string; name: string; hotkey: string }[]>('list_selection_actions');
+    for (const action of actions) {
+      if (!action.hotkey || (await isRegistered(action.hotkey))) continue;
+      await register(action.hotkey, async (event: any) => {
+        // Act on release so the held modifiers don't leak into the simulated copy
+        if (event?.state && event.state !== 'Released') return;
+        try {
+          await invoke('run_selection_action', { action: action.id });
+          logMsg('✂️ Selection action done: ' + action.name);
+        } catch (e) {
+          logMsg('⚠️ Selection action ' + action.name + ' failed: ' + String(e));
+        }
+      });
+      logMsg('✅ Selection hotkey registered: ' + action.hotkey + ' -> ' + action.name);
+    }
+  } catch (e) {
+    logMsg('⚠️ Selection hotkey registration failed: ' + String(e));
+  }
+}
+
 export async function setupGlobalHotkey(combo: string) {
   await initGlobalHotkey(combo);
 }
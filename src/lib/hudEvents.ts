@@ -0,0 +1,43 @@
+/* Typed events from the backend (mirrors src-tauri/src/hud_event.rs).
+ * Everything arrives as one `hud-event` whose payload is tagged by `type`.
+ */
+
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type { SegmentMeta } from './transcript';
+
+export type Quality = 'good' | 'degraded' | 'batch';
+export type BadgeKind = 'error' | 'secure';
+
+export type TranscriptEvent = { session_id: number; text: string; is_final: boolean } & SegmentMeta;
+export type QualityEvent = { session_id: number; quality: Quality; rtt_ms: number | null };
+export type Retry = { provider: string; attempt: number; max_attempts: number; delay_ms: number; reason: string };
+export type StatusEvent = { session_id: number; status: 'connecting' | 'open' | 'reconnecting' | 'closed' | 'error'; detail: string | null };
+
+export type HudEvent =
+  | { type: 'start'; session_id: number }
+  | { type: 'stop' }
+  | { type: 'badge'; msg: string; kind: BadgeKind }
+  | { type: 'level'; db: number }
+  | { type: 'speech_active'; active: boolean }
+  | { type: 'level_warning'; code: 'clipping' | 'too_quiet'; message: string }
+  | { type: 'audio_device'; id: string | null }
+  | ({ type: 'partial' } & TranscriptEvent)
+  | ({ type: 'final' } & TranscriptEvent)
+  | { type: 'error'; code: string; message: string }
+  | { type: 'target_closed'; window: string }
+  | ({ type: 'relay_status' } & StatusEvent)
+  | ({ type: 'relay_quality' } & QualityEvent)
+  | ({ type: 'refine_retrying' } & Retry)
+  | { type: 'retake' }
+  | { type: 'paused'; paused: boolean }
+  | { type: 'paste_edit' }
+  | { type: 'move_mode'; active: boolean };
+
+export type HudEventOf<T extends HudEvent['type']> = Extract<HudEvent, { type: T }>;
+
+/** Listen for one kind of backend event */
+export function onHudEvent<T extends HudEvent['type']>(type: T, handler: (ev: HudEventOf<T>) => void): Promise<UnlistenFn> {
+  return listen<HudEvent & { v: number }>('hud-event', (e) => {
+    if (e.payload.type === type) handler(e.payload as HudEventOf<T>);
+  });
+}
@@ -0,0 +1,141 @@
+/* Microphone input that survives device changes.
+ * The stream handed to the STT clients comes out of a WebAudio destination node, so the device
+ * behind it can be swapped (docking, unplugging, a new choice in Settings) without restarting the
+ * provider session. Input devices are reported to the backend, which keeps the user's choice
+ * (mirrors src-tauri/src/audio_device.rs).
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { capturePcm } from './audio';
+
+export type NoiseMode = 'off' | 'browser' | 'rnnoise';
+
+export type AudioDevice = { id: string; label: string; is_default: boolean; selected?: boolean; noise_mode?: NoiseMode | null };
+
+type Options = {
+  echoCancellation: boolean;
+  noiseSuppression: boolean;
+  /** The running stream moved to another device */
+  onDeviceChange?: (label: string) => void;
+  /** Send a copy of the audio to the backend, which keeps the last dictation for saving or
+   * re-transcribing (relay sessions keep it on their own) */
+  keepAudio?: boolean;
+};
+
+function log(msg: string) {
+  console.log(msg);
+  invoke('log_to_terminal', { message: msg }).catch(() => {});
+}
+
+export async function listInputDevices(): Promise<AudioDevice[]> {
+  const inputs = (await navigator.mediaDevices.enumerateDevices()).filter(d => d.kind === 'audioinput');
+  // Chromium lists the default as a "default" pseudo-device sharing the real one's groupId;
+  // WebKit lists the default first
+  const pseudo = inputs.find(d => d.deviceId === 'default');
+  const real = inputs.filter(d => d.deviceId !== 'default' && d.deviceId !== 'communications');
+  return real.map((d, i) => ({
+    id: d.deviceId,
+    label: d.label || `Microphone ${i + 1}`,
+    is_default: pseudo ? pseudo.groupId === d.groupId : i === 0,
+  }));
+}
+
+export async function reportAudioDevices() {
+  try {
+    await invoke('report_audio_devices', { devices: await listInputDevices() });
+  } catch (e) {
+    log('[Mic] Could not report audio devices: ' + String(e));
+  }
+}
+
+export type Mic = Awaited<ReturnType<typeof openMic>>;
+
+/** Open the chosen input (or the default) and follow device changes until closed */
+export async function openMic(opts: Options) {
+  let chosen: string | null = await invoke<string | null>('get_audio_device').catch(() => null);
+  const ctx = new AudioContext();
+  const dest = ctx.createMediaStreamDestination();
+  let raw: MediaStream | null = null;
+  let source: MediaStreamAudioSourceNode | null = null;
+  // Device the current stream was opened for ('' when the browser hides ids)
+  let openedFor: string | null = null;
+  let label = '';
+  let closed = false;
+  let switching = false;
+
+  // The chosen device while it is plugged in, otherwise the default
+  async function target(): Promise<AudioDevice | null> {
+    const devices = await listInputDevices().catch(() => [] as AudioDevice[]);
+    return devices.find(d => d.id === chosen) ?? devices.find(d => d.is_default) ?? null;
+  }
+
+  async function connect(device: AudioDevice | null) {
+    const next = await navigator.mediaDevices.getUserMedia({
+      audio: {
+        echoCancellation: opts.echoCancellation,
+        noiseSuppression: opts.noiseSuppression,
+        channelCount: 1,
+        ...(device?.id ? { deviceId: { exact: device.id } } : {}),
+      },
+    });
+    source?.disconnect();
+    raw?.getTracks().forEach(t => t.stop());
+    raw = next;
+    source = ctx.createMediaStreamSource(next);
+    source.connect(dest);
+    openedFor = device?.id ?? '';
+    const track = next.getAudioTracks()[0];
+    label = track?.label || device?.label || 'Microphone';
+    // Unplugging the device in use ends its track
+    track?.addEventListener('ended', () => { void follow(); });
+    log(`[Mic] Using ${label}`);
+  }
+
+  async function follow() {
+    if (closed || switching) return;
+    switching = true;
+    try {
+      const device = await target();
+      const live = raw?.getAudioTracks()[0]?.readyState === 'live';
+      if (live && (device?.id ?? '') === openedFor) return;
+      await connect(device);
+      opts.onDeviceChange?.(label);
+    } catch (e) {
+      log('[Mic] Switching device failed: ' + String(e));
+    } finally {
+      switching = false;
+    }
+  }
+
+  const onDeviceChange = () => {
+    void reportAudioDevices();
+    void follow();
+  };
+
+  await connect(await target());
+  navigator.mediaDevices.addEventListener('devicechange', onDeviceChange);
+  let stopCopy: (() => void) | null = null;
+  if (opts.keepAudio) {
+    await invoke('record_audio_start').catch(() => {});
+    stopCopy = capturePcm(dest.stream, (pcm) => { invoke('record_audio', pcm).catch(() => {}); });
+  }
+
+  return {
+    stream: dest.stream,
+    label: () => label,
+    /** Switch to another device (null: the default), e.g. after a change in Settings */
+    setDevice: (id: string | null) => {
+      chosen = id;
+      void follow();
+    },
+    close: () => {
+      if (closed) return;
+      closed = true;
+      navigator.mediaDevices.removeEventListener('devicechange', onDeviceChange);
+      stopCopy?.();
+      source?.disconnect();
+      raw?.getTracks().forEach(t => t.stop());
+      ctx.close().catch(() => {});
+    },
+  };
+}
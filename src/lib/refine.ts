@@ -1,7 +1,7 @@
 import { invoke } from '@tauri-apps/api/core';
+import type { Transcript } from './transcript';
 
-export async function refineText(rawText: string): Promise<string> {
-  const refined = await invoke<string>('refine_text', { rawText });
+export async function refineText(transcript: Transcript): Promise<Transcript> {
+  const refined = await invoke<Transcript>('refine_text', { transcript });
   return refined;
 }
-
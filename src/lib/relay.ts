@@ -0,0 +1,113 @@
+/* Backend STT relay client.
+ * Captures raw linear16 PCM like the direct clients, but hands it to the Rust backend, which owns
+ * the provider WebSocket (keep-alives, heartbeat and transparent reconnect live there).
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import type { UnlistenFn } from '@tauri-apps/api/event';
+import { capturePcm } from './audio';
+import { onHudEvent, type Quality, type StatusEvent, type TranscriptEvent } from './hudEvents';
+import type { SegmentMeta } from './transcript';
+
+type Handlers = {
+  onTranscript?: (text: string, isFinal: boolean, meta?: SegmentMeta) => void;
+  onError?: (err: any) => void;
+  onOpen?: () => void;
+  onReconnecting?: () => void;
+  onResumed?: () => void;
+  onQuality?: (quality: Quality) => void;
+  onClose?: () => void;
+};
+
+type Options = {
+  /** Meeting transcription: speakers are told apart, no silence stop, finals go to the meeting file */
+  meeting?: boolean;
+};
+
+function log(msg: string) {
+  console.log(msg);
+  invoke('log_to_terminal', { message: msg }).catch(() => {});
+}
+
+export async function startRelayStream(stream: MediaStream, handlers: Handlers = {}, opts: Options = {}) {
+  let sessionId: number | null = null;
+  let opened = false;
+  let canceled = false;
+  let closed = false;
+  let lastStatus: StatusEvent['status'] | null = null;
+  let resolveClosed: () => void = () => {};
+  const closedPromise = new Promise<void>((resolve) => { resolveClosed = resolve; });
+  const unlisten: UnlistenFn[] = [];
+
+  // Listen before starting so no early status event is missed
+  const onTranscript = (ev: TranscriptEvent) => {
+    if (ev.session_id !== sessionId) return;
+    const { start_ms, end_ms, confidence, words } = ev;
+    handlers.onTranscript?.(ev.text, ev.is_final, { start_ms, end_ms, confidence, words });
+  };
+  unlisten.push(await onHudEvent('partial', onTranscript));
+  unlisten.push(await onHudEvent('final', onTranscript));
+  unlisten.push(await onHudEvent('relay_status', (ev) => {
+    if (ev.session_id !== sessionId) return;
+    const { status, detail } = ev;
+    log(`[Relay] status: ${status}${detail ? ' (' + detail + ')' : ''}`);
+    const wasReconnecting = lastStatus === 'reconnecting';
+    lastStatus = status;
+    if (status === 'open') {
+      // Reopening after a quality change is not a recovery; keep the quality badge
+      if (opened) { if (wasReconnecting) handlers.onResumed?.(); }
+      else { opened = true; handlers.onOpen?.(); }
+    } else if (status === 'reconnecting') {
+      handlers.onReconnecting?.();
+    } else if (status === 'error') {
+      handlers.onError?.(detail || 'relay error');
+    } else if (status === 'closed') {
+      closed = true;
+      unlisten.forEach(u => u());
+      handlers.onClose?.();
+      resolveClosed();
+    }
+  }));
+  unlisten.push(await onHudEvent('relay_quality', (ev) => {
+    if (ev.session_id !== sessionId) return;
+    log(`[Relay] link quality: ${ev.quality} (rtt ${ev.rtt_ms ?? '?'}ms)`);
+    handlers.onQuality?.(ev.quality);
+  }));
+
+  try {
+    sessionId = await invoke<number>('stt_relay_start', { meeting: !!opts.meeting });
+  } catch (e) {
+    unlisten.forEach(u => u());
+    throw e;
+  }
+  log(`[Relay] session ${sessionId} started`);
+
+  const stopCapture = capturePcm(stream, (pcm) => {
+    if (canceled) return;
+    // Audio is buffered in the backend while the socket connects or reconnects
+    invoke('stt_relay_audio', pcm).catch(() => {});
+  });
+
+  return {
+    // Resolves once the backend has delivered final results (a batch upload can take a while)
+    stop: async () => {
+      log('[Relay] Stopping recording and finalizing');
+      canceled = true;
+      try {
+        stopCapture();
+      } catch (e) {
+        log('[Relay] Error stopping audio processing: ' + String(e));
+      }
+      // The backend flushes final results, then reports "closed"
+      if (closed) return;
+      try {
+        await invoke('stt_relay_finalize');
+      } catch {
+        invoke('stt_relay_stop').catch(() => {});
+        return;
+      }
+      const timeout = new Promise<void>((resolve) => setTimeout(resolve, 65000));
+      await Promise.race([closedPromise, timeout]);
+    },
+  };
+}
@@ -0,0 +1,53 @@
+/* Structured transcript shared with the backend (mirrors src-tauri/src/transcript.rs).
+ * Segments keep the provider's timing and confidence next to the text.
+ */
+
+/** A word with its position in the recording (milliseconds) */
+export type Word = {
+  text: string;
+  start_ms: number;
+  end_ms: number;
+  confidence?: number | null;
+  /** Interpolated rather than reported by the provider */
+  estimated?: boolean;
+};
+
+export type Segment = {
+  text: string;
+  /** Offset from the start of the recording */
+  start_ms?: number | null;
+  end_ms?: number | null;
+  /** Provider confidence, 0-1 */
+  confidence?: number | null;
+  words?: Word[];
+  /** Language of this segment, when detected or tagged for mixed-language refinement */
+  language?: string | null;
+};
+
+/** Timing and confidence reported with a transcript message, when the provider sends them */
+export type SegmentMeta = Omit<Segment, 'text'>;
+
+export type Transcript = {
+  session_id: number | null;
+  provider: string;
+  language: string | null;
+  segments: Segment[];
+  /** Text after the refinement pipeline; set by refine_text */
+  refined?: string | null;
+};
+
+export function transcriptText(t: Transcript): string {
+  return t.segments.map(s => s.text.trim()).filter(Boolean).join(' ');
+}
+
+/** Whether a final segment ends with the spoken "retake" command: the word on its own, or after
+ * a sentence break ("... never mind. Retake.") so that "we need a retake of the photo" is kept. */
+export function endsWithRetake(text: string): boolean {
+  const m = text.trim().match(/(^|[.!?,]\s+)retake[.!?]?$/i);
+  return m !== null;
+}
+
+/** The segment's text with a trailing "retake" command removed */
+export function stripRetake(text: string): string {
+  return text.trim().replace(/(^|[.!?,]\s+)retake[.!?]?$/i, '$1').trim();
+}
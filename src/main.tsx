@@ -3,6 +3,9 @@ import ReactDOM from 'react-dom/client';
 import './styles/globals.css';
 import { Settings } from './windows/Settings';
 import { Hud } from './windows/Hud';
+import { Scratchpad } from './windows/Scratchpad';
+import { Meeting } from './windows/Meeting';
+import { History } from './windows/History';
 import { invoke } from '@tauri-apps/api/core';
 
 // Patch getUserMedia so audio echo/noise flags follow Settings behavior toggles.
@@ -50,6 +53,9 @@ invoke('log_to_terminal', { message: '🚀🚀🚀 MAIN.TSX LOADING 🚀🚀🚀
 function Root() {
   const hash = typeof window !== 'undefined' ? window.location.hash : '';
   const isHud = hash.includes('hud');
+  const isScratchpad = hash.includes('scratchpad');
+  const isMeeting = hash.includes('meeting');
+  const isHistory = hash.includes('history');
   console.log('Root component rendering, hash:', hash, 'isHud:', isHud);
   invoke('log_to_terminal', { message: `Root rendering - hash: ${hash}, isHud: ${isHud}` }).catch(() => {});
 
@@ -58,6 +64,9 @@ function Root() {
     document.body.setAttribute('data-window', isHud ? 'hud' : 'settings');
   }, [isHud]);
 
+  if (isScratchpad) return <Scratchpad />;
+  if (isMeeting) return <Meeting />;
+  if (isHistory) return <History />;
   return isHud ? <Hud /> : <Settings />;
 }
 
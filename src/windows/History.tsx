@@ -0,0 +1,103 @@
+import { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+
+type HistoryItem = { id: number; created_at: number; app: string | null; text: string; pasted: boolean; timed: boolean; cost_usd: number | null };
+type HistoryUpdate = { kind: 'added'; item: HistoryItem } | { kind: 'removed'; ids: number[] };
+
+const LIMIT = 500;
+
+const matches = (item: HistoryItem, query: string) => {
+  const haystack = `${item.text} ${item.app ?? ''}`.toLowerCase();
+  return query.toLowerCase().split(/\s+/).filter(Boolean).every(t => haystack.includes(t));
+};
+
+// Past dictations in a window of their own: search, copy and delete, kept current by history-updated
+export function History() {
+  const [items, setItems] = useState<HistoryItem[]>([]);
+  const [query, setQuery] = useState('');
+  const [copied, setCopied] = useState<number | null>(null);
+  const [error, setError] = useState<string | null>(null);
+  const queryRef = useRef('');
+
+  useEffect(() => {
+    queryRef.current = query;
+    // Searching runs on the backend over the whole history, not just what is loaded
+    const timer = setTimeout(() => {
+      invoke<HistoryItem[]>('list_history', { limit: LIMIT, query: query || null }).then(setItems).catch(e => setError(String(e)));
+    }, 150);
+    return () => clearTimeout(timer);
+  }, [query]);
+
+  useEffect(() => {
+    const unlisten = listen<HistoryUpdate>('history-updated', (e) => {
+      const update = e.payload;
+      if (update.kind === 'added') {
+        if (matches(update.item, queryRef.current)) setItems(prev => [update.item, ...prev].slice(0, LIMIT));
+      } else {
+        setItems(prev => prev.filter(i => !update.ids.includes(i.id)));
+      }
+    });
+    return () => { unlisten.then(u => u()); };
+  }, []);
+
+  async function copy(item: HistoryItem) {
+    await navigator.clipboard.writeText(item.text);
+    setCopied(item.id);
+    setTimeout(() => setCopied(c => (c === item.id ? null : c)), 1500);
+  }
+
+  async function remove(ids: number[]) {
+    try {
+      // The list updates from the history-updated event
+      await invoke<number>('delete_history', { ids });
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  function deleteShown() {
+    if (items.length && window.confirm(`Delete ${items.length === 1 ? 'this dictation' : `these ${items.length} dictations`}?`)) {
+      remove(items.map(i => i.id));
+    }
+  }
+
+  return (
+    <div className="h-full flex flex-col gap-3 p-4 bg-[var(--bg)]">
+      <div className="flex items-center gap-2">
+        <input
+          type="search"
+          aria-label="Search history"
+          placeholder="Search dictations"
+          value={query}
+          onChange={e => setQuery(e.target.value)}
+          className="flex-1 px-3 py-1.5 bg-neutral-900 border border-neutral-700 rounded text-sm"
+        />
+        <button type="button" disabled={!items.length} onClick={deleteShown} className="px-3 py-1.5 rounded border border-neutral-700 text-sm hover:bg-neutral-800 disabled:opacity-50">
+          {query ? 'Delete matches' : 'Delete all'}
+        </button>
+      </div>
+      {error && <div className="text-xs text-red-400">{error}</div>}
+      <div className="flex-1 overflow-y-auto space-y-2">
+        {items.length === 0 && <div className="text-muted text-xs">{query ? 'No dictation matches.' : 'Nothing dictated yet.'}</div>}
+        {items.map(item => (
+          <div key={item.id} className="px-3 py-2 bg-neutral-900 border border-neutral-700 rounded">
+            <div className="flex items-center justify-between gap-2 text-xs text-muted">
+              <span>
+                {new Date(item.created_at * 1000).toLocaleString()}
+                {item.app && ` · ${item.app}`}
+                {!item.pasted && ' · not pasted'}
+                {item.cost_usd != null && item.cost_usd > 0 && ` · ~$${item.cost_usd.toFixed(4)}`}
+              </span>
+              <span className="flex gap-2">
+                <button type="button" onClick={() => copy(item)} className="hover:text-[var(--accent)]">{copied === item.id ? 'Copied' : 'Copy'}</button>
+                <button type="button" onClick={() => remove([item.id])} className="hover:text-red-400">Delete</button>
+              </span>
+            </div>
+            <div className="mt-1 text-sm whitespace-pre-wrap select-text">{item.text}</div>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -4,20 +4,70 @@ import { Waveform } from '../components/Waveform';
 import { Badge } from '../components/Badge';
 import { invoke } from '@tauri-apps/api/core';
 import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { LogicalSize, PhysicalPosition } from '@tauri-apps/api/dpi';
+import { onHudEvent, type BadgeKind } from '../lib/hudEvents';
+import { openMic, reportAudioDevices, type Mic } from '../lib/mic';
+import { endsWithRetake, stripRetake, transcriptText, type Segment, type SegmentMeta, type Transcript, type Word } from '../lib/transcript';
+import type { DeepgramOptions } from '../lib/deepgram';
+import type { ElevenLabsOptions } from '../lib/elevenlabs';
+
+// Longer recordings are replayed faster so the review never holds up insertion for long
+const REVIEW_MAX_MS = 4000;
+// Room the paste preview needs above the pill
+const PREVIEW_EXTRA_PX = 160;
+
+type NetworkStatus = { online: boolean; since: number; checked_at: number };
+type PasteDecision = 'paste' | 'cancel' | 'timeout';
+
+// Grow (or with a negative `extra`, shrink) the HUD window upward, so the pill stays put
+async function growHud(extra: number) {
+  const win = getCurrentWindow();
+  const scale = await win.scaleFactor();
+  const size = (await win.innerSize()).toLogical(scale);
+  const pos = await win.outerPosition();
+  await win.setSize(new LogicalSize(size.width, size.height + extra));
+  await win.setPosition(new PhysicalPosition(pos.x, pos.y - Math.round(extra * scale)));
+}
 
 export function Hud() {
   const [show, setShow] = useState(false);
   const [seconds, setSeconds] = useState(0);
   const [badge, setBadge] = useState<string | null>(null);
+  const [badgeKind, setBadgeKind] = useState<BadgeKind>('error');
+  const [translation, setTranslation] = useState<{ original: string; translated: string; source: string | null; target: string } | null>(null);
+  const [review, setReview] = useState<{ words: Word[]; index: number } | null>(null);
+  const [pasteConfirm, setPasteConfirm] = useState<{ confirmed: number; limit: number } | null>(null);
+  // confirm_before_paste: the final text, waiting for Enter / Esc, or being edited in the HUD
+  const [preview, setPreview] = useState<{ text: string; editing: boolean } | null>(null);
+  const [previewEdit, setPreviewEdit] = useState('');
+  // The edit as last typed, read once the confirmation is answered (null: not edited)
+  const previewEditRef = useRef<string | null>(null);
+  const [targetClosed, setTargetClosed] = useState<string | null>(null);
+  const [moveMode, setMoveMode] = useState(false);
   const [analyser, setAnalyser] = useState<AnalyserNode | null>(null);
+  // Input level from the backend (relay sessions), as linear RMS for the waveform
+  const backendLevelRef = useRef<number | null>(null);
   const [isRecording, setIsRecording] = useState(false);
   const [isConnecting, setIsConnecting] = useState(false);
+  // Recording on hold (pause hotkey); the timer stops and the pill shows it
+  const [paused, setPaused] = useState(false);
+  const pausedRef = useRef(false);
   const timerRef = useRef<number | null>(null);
-  const recRef = useRef<{ stop: () => Promise<void> | void } | null>(null);
-  const partialRef = useRef<string[]>([]);
+  // Direct streams drop audio themselves while paused; the relay's is dropped in the backend
+  const recRef = useRef<{ stop: () => Promise<void> | void; setPaused?: (paused: boolean) => void } | null>(null);
+  const micRef = useRef<Mic | null>(null);
+  const partialRef = useRef<Segment[]>([]);
   const latestTranscriptRef = useRef<string>(''); // Store latest transcript even if not final
+  const firstTextRef = useRef(false); // Reported the session's first words yet
+  const latestMetaRef = useRef<SegmentMeta>({});
+  const sessionRef = useRef<number | null>(null);
+  const providerRef = useRef<string>('deepgram');
+  const reviewRef = useRef(false);
   const wsRef = useRef<WebSocket | null>(null);
   const isReadyRef = useRef(false); // Track if WebSocket is actually open
+  // From the backend's connectivity monitor; offline, refinement is skipped and errors say why
+  const offlineRef = useRef(false);
 
   // Log when HUD component mounts
   useEffect(() => {
@@ -27,6 +77,8 @@ export function Hud() {
     };
 
     log('🚀🚀🚀 HUD COMPONENT MOUNTED 🚀🚀🚀');
+    // Settings lists the devices the HUD can see
+    void reportAudioDevices();
   }, []);
 
   useEffect(() => {
@@ -35,22 +87,114 @@ export function Hud() {
       invoke('log_to_terminal', { message: msg }).catch(() => {});
     };
 
-    log('📡 Setting up hud-badge listener...');
+    log('📡 Setting up badge listener...');
     let unsub: any;
     (async () => {
-      unsub = await listen('hud-badge', (e:any)=>{
-        log('📩 Received hud-badge event: ' + e?.payload);
-        setBadge(String(e?.payload||'No text field is focused'));
+      unsub = await onHudEvent('badge', (e)=>{
+        log('📩 Received badge event: ' + JSON.stringify(e));
+        setBadgeKind(e.kind);
+        setBadge(e.msg || 'No text field is focused');
         setTimeout(()=> setBadge(null), 3000);
       });
-      log('✅ hud-badge listener registered');
+      log('✅ badge listener registered');
+    })();
+    // Refinement hit a transient error (rate limit, timeout); say so instead of looking stuck
+    let unretry: any;
+    (async () => {
+      unretry = await onHudEvent('refine_retrying', (e)=>{
+        log(`🔁 refine_retrying ${e.provider}: ${e.reason}, attempt ${e.attempt}/${e.max_attempts}`);
+        setBadgeKind('error');
+        setBadge(`Retrying… (${e.attempt}/${e.max_attempts})`);
+        setTimeout(()=> setBadge(null), e.delay_ms + 3000);
+      });
+    })();
+    // Relay sessions: the backend measures the level of the audio it receives
+    let unlevel: any;
+    (async () => {
+      unlevel = await onHudEvent('level', (e) => { backendLevelRef.current = Math.pow(10, e.db / 20); });
+    })();
+    // A different microphone was chosen in Settings; move a running recording to it
+    let undevice: any;
+    (async () => {
+      undevice = await onHudEvent('audio_device', (e) => { micRef.current?.setDevice(e.id); });
+    })();
+    // Persistent clipping or very quiet speech, with what to change
+    let unlevelwarn: any;
+    (async () => {
+      unlevelwarn = await onHudEvent('level_warning', (e) => {
+        log(`🎚️ level_warning ${e.code}: ${e.message}`);
+        setBadgeKind('error');
+        setBadge(e.message);
+        setTimeout(()=> setBadge(null), 5000);
+      });
     })();
     return () => {
-      log('🧹 Cleaning up hud-badge listener');
+      log('🧹 Cleaning up badge listener');
       unsub?.();
+      unretry?.();
+      unlevel?.();
+      unlevelwarn?.();
+      undevice?.();
     };
   }, []);
 
+  useEffect(() => {
+    invoke<NetworkStatus>('get_network_status').then(s => { offlineRef.current = !s.online; }).catch(() => {});
+    const unlisten = listen<NetworkStatus>('network-status', (e) => { offlineRef.current = !e.payload.online; });
+    return () => { unlisten.then(u => u()); };
+  }, []);
+
+  // The stream broke or never opened: say "offline" rather than a bare network error when the
+  // connection is gone
+  function showNetworkError() {
+    const message = (offline: boolean) => offline
+      ? 'Offline mode. Your audio was kept: re-transcribe it in Settings once back online'
+      : 'Network error. Your audio was kept: re-transcribe it in Settings';
+    setBadge(message(offlineRef.current));
+    invoke<NetworkStatus>('get_network_status', { recheck: true }).then(s => {
+      offlineRef.current = !s.online;
+      if (!s.online) setBadge(message(true));
+    }).catch(() => {});
+  }
+
+  // A final segment ending in the spoken "retake" command starts a fresh take
+  function addFinal(text: string, meta?: SegmentMeta) {
+    if (endsWithRetake(text)) {
+      const before = stripRetake(text);
+      retake(before ? { text: before, ...meta } : null);
+      return;
+    }
+    partialRef.current.push({ text, ...meta });
+  }
+
+  // The first words of a session mark the "first partial" step of its pipeline timing
+  function noteFirstText() {
+    if (firstTextRef.current) return;
+    firstTextRef.current = true;
+    invoke('mark_first_partial').catch(() => {});
+  }
+
+  // Set the current utterance aside (the backend keeps it as a recoverable take) and start over
+  function retake(last: Segment | null = null) {
+    const segments = [...partialRef.current];
+    if (last) segments.push(last);
+    const latest = latestTranscriptRef.current.trim();
+    if (!last && latest && !segments.some(s => s.text.includes(latest))) {
+      segments.push({ text: latest, ...latestMetaRef.current });
+    }
+    partialRef.current = [];
+    latestTranscriptRef.current = '';
+    latestMetaRef.current = {};
+    invoke('log_to_terminal', { message: '🔁 Retake: set aside ' + segments.length + ' segments' }).catch(() => {});
+    if (segments.length > 0) {
+      const take: Transcript = { session_id: sessionRef.current, provider: providerRef.current, language: null, segments, refined: null };
+      invoke('save_take', { transcript: take }).catch(() => {});
+    }
+    setBadgeKind('error');
+    setBadge('Retake: starting fresh');
+    setTimeout(()=> setBadge(null), 2000);
+  }
+
   async function begin() {
     const log = (msg: string) => {
       console.log(msg);
@@ -65,6 +209,8 @@ export function Hud() {
       recRef.current.stop();
       recRef.current = null;
     }
+    micRef.current?.close();
+    micRef.current = null;
 
     // CRITICAL: Clear any existing timer to prevent multiple timers
     if (timerRef.current) {
@@ -75,21 +221,32 @@ export function Hud() {
 
     // Reset state
     partialRef.current = [];
+    firstTextRef.current = false;
     latestTranscriptRef.current = '';
+    latestMetaRef.current = {};
     setSeconds(0);
+    pausedRef.current = false;
+    setPaused(false);
     setAnalyser(null);
+    backendLevelRef.current = null;
     setIsRecording(false); // Not recording yet, just connecting
     setIsConnecting(true);
     isReadyRef.current = false;
-    setBadge('Initializing microphone...');
+    setBadgeKind('error');
+    setBadge(offlineRef.current ? 'Offline mode: AI cleanup skipped' : 'Initializing microphone...');
     setShow(true); // Show HUD immediately with "Initializing" badge
 
     try {
       // Load behavior prefs for audio + providers
       const behavior = await invoke<any>('get_behavior').catch(() => ({}));
       const echoCancellation = behavior?.echo_cancellation !== false;
-      const noiseSuppression = behavior?.noise_suppression !== false;
+      // Per-device setting or the global one; RNNoise runs in the backend, so it needs the relay
+      const noiseMode = await invoke<'off' | 'browser' | 'rnnoise'>('get_noise_mode').catch(() => (behavior?.noise_suppression !== false ? 'browser' : 'off'));
+      const noiseSuppression = noiseMode === 'browser';
+      const useRelay = !behavior?.demo_mode && (!!behavior?.stt_relay || noiseMode === 'rnnoise');
       const sttProvider = (behavior?.stt_provider || 'deepgram') as string;
+      providerRef.current = behavior?.demo_mode ? 'demo' : sttProvider;
+      reviewRef.current = !!behavior?.review_playback;
 
       if (sttProvider !== 'deepgram' && sttProvider !== 'elevenlabs') {
         log('?? Unsupported STT provider selected: ' + sttProvider);
@@ -101,11 +258,16 @@ export function Hud() {
 
       // Request microphone access
       log('📱 Requesting microphone access...');
-      const stream = await navigator.mediaDevices.getUserMedia({
-        audio: { echoCancellation, noiseSuppression, channelCount: 1 }
+      // The stream stays the same when the device behind it changes (docking, unplugging)
+      const mic = await openMic({
+        echoCancellation,
+        noiseSuppression,
+        onDeviceChange: (label) => invoke('audio_device_changed', { label }).catch(() => {}),
+        keepAudio: !behavior?.demo_mode && !useRelay,
       });
-      log('✅ Microphone access granted');
-      log('Stream tracks: ' + JSON.stringify(stream.getTracks().map(t => ({ kind: t.kind, label: t.label, enabled: t.enabled }))));
+      micRef.current = mic;
+      const stream = mic.stream;
+      log('✅ Microphone access granted: ' + mic.label());
 
       // Inline loading indicator is shown in the pill while connecting
 
@@ -124,11 +286,90 @@ export function Hud() {
       const [or, dg, mg, el] = await invoke<[string|null,string|null,string|null,string|null]>('runtime_keys');
       log('Keys retrieved - OpenRouter: ' + (or ? 'present' : 'missing') + ', Deepgram: ' + (dg ? 'present' : 'missing') + ', MegaLLM: ' + (mg ? 'present' : 'missing') + ', ElevenLabs: ' + (el ? 'present' : 'missing'));
 
-      if (sttProvider === 'elevenlabs') {
+      if (behavior?.demo_mode) {
+        // Demo mode: scripted transcript revealed word by word, no provider or keys involved
+        const phrase = await invoke<string>('demo_transcript');
+        log('🎭 Demo mode transcript: "' + phrase + '"');
+        isReadyRef.current = true;
+        setIsConnecting(false);
+        setIsRecording(true);
+        invoke('set_recording_active', { newState: 'recording' }).catch(() => {});
+        setBadge('Demo mode: no API keys needed');
+        timerRef.current = window.setInterval(()=> setSeconds(s => pausedRef.current ? s : s + 1), 1000);
+
+        const words = phrase.split(' ');
+        let shown = 0;
+        let wordTimer: number | null = null;
+        const startDelay = window.setTimeout(() => {
+          setBadge(null);
+          wordTimer = window.setInterval(() => {
+            shown++;
+            noteFirstText();
+            latestTranscriptRef.current = words.slice(0, shown).join(' ');
+            if (shown >= words.length) {
+              partialRef.current.push({ text: phrase });
+              latestTranscriptRef.current = '';
+              if (wordTimer) clearInterval(wordTimer);
+              wordTimer = null;
+            }
+          }, 250);
+        }, 800);
+        recRef.current = {
+          stop: () => {
+            clearTimeout(startDelay);
+            if (wordTimer) clearInterval(wordTimer);
+            mic.close();
+          },
+        };
+      } else if (useRelay) {
+        log('Starting backend STT relay (' + sttProvider + (noiseMode === 'rnnoise' ? ', RNNoise' : '') + ')...');
+        const { startRelayStream } = await import('../lib/relay');
+        const rec = await startRelayStream(stream, {
+          onTranscript: (t, final, meta) => {
+            log('[Relay] Transcript received - final: ' + final + ', text: ' + t);
+            if (t) {
+              noteFirstText();
+              latestTranscriptRef.current = t;
+              latestMetaRef.current = meta || {};
+            }
+            if (t && final) {
+              addFinal(t, meta);
+            }
+          },
+          onOpen: () => {
+            isReadyRef.current = true;
+            setIsConnecting(false);
+            setIsRecording(true);
+            invoke('set_recording_active', { newState: 'recording' }).catch(() => {});
+            setBadge(null);
+            timerRef.current = window.setInterval(()=> setSeconds(s => pausedRef.current ? s : s + 1), 1000);
+          },
+          // Audio keeps buffering in the backend, so recording continues through a reconnect
+          onReconnecting: () => setBadge('Reconnecting…'),
+          onResumed: () => setBadge(null),
+          onQuality: (q) => {
+            if (q === 'degraded') setBadge('Degraded connection');
+            else if (q === 'batch') setBadge('Degraded connection: transcribing when you stop');
+            else setBadge(null);
+          },
+          onError: (e) => {
+            log('[Relay] ERROR: ' + String(e));
+            showNetworkError();
+            setIsConnecting(false);
+            setIsRecording(false);
+            invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
+          },
+          onClose: () => {
+            isReadyRef.current = false;
+            invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
+          }
+        });
+        recRef.current = rec;
+      } else if (sttProvider === 'elevenlabs') {
         if (!el) {
           log('No ElevenLabs key found');
           setBadge('ElevenLabs key not configured');
-          stream.getTracks().forEach(t => t.stop());
+          mic.close();
           setIsRecording(false);
           setIsConnecting(false);
           await invoke('set_recording_active', { newState: 'inactive' });
@@ -138,15 +379,19 @@ export function Hud() {
         log('Starting ElevenLabs stream with scribe_v1...');
         log('Starting ElevenLabs stream with scribe_v2_realtime (realtime)...');
         const token = await invoke<string>('create_elevenlabs_token', { api_key: null });
+        const sttOptions = await invoke<{ elevenlabs: ElevenLabsOptions }>('get_stt_options').catch(() => null);
+        const language = await invoke<string>('get_language', { provider: 'elevenlabs' }).catch(() => 'auto');
         const { startElevenLabsStream } = await import('../lib/elevenlabs');
         const rec = await startElevenLabsStream(token, stream, {
-          onTranscript: (t, final) => {
+          onTranscript: (t, final, meta) => {
             log('[EL] Transcript received - final: ' + final + ', text: ' + t);
             if (t) {
+              noteFirstText();
               latestTranscriptRef.current = t;
+              latestMetaRef.current = meta || {};
             }
             if (t && final) {
-              partialRef.current.push(t);
+              addFinal(t, meta);
               log('[EL] Added to partials, total: ' + partialRef.current.length);
             }
           },
@@ -157,11 +402,12 @@ export function Hud() {
             setIsRecording(true);
             invoke('set_recording_active', { newState: 'recording' }).catch(() => {});
             setBadge(null);
-            timerRef.current = window.setInterval(()=> setSeconds(s=>s+1), 1000);
+            timerRef.current = window.setInterval(()=> setSeconds(s => pausedRef.current ? s : s + 1), 1000);
           },
           onError: (e) => {
             log('[EL] WebSocket ERROR: ' + String(e));
-            setBadge('Network error. Try again');
+            invoke('report_failure', { kind: 'stt_connection', detail: 'elevenlabs: ' + String(e) }).catch(() => {});
+            showNetworkError();
             setIsConnecting(false);
             setIsRecording(false);
             invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
@@ -171,14 +417,14 @@ export function Hud() {
             isReadyRef.current = false;
             invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
           }
-        });
+        }, sttOptions?.elevenlabs, language);
         recRef.current = rec;
         log('ElevenLabs recorder stored in ref');
       } else {
         if (!dg) {
           log('No Deepgram key found');
           setBadge('Deepgram key not configured');
-          stream.getTracks().forEach(t => t.stop());
+          mic.close();
           setIsRecording(false);
           setIsConnecting(false);
           await invoke('set_recording_active', { newState: 'inactive' });
@@ -187,14 +433,19 @@ export function Hud() {
 
         log('Starting Deepgram stream with key: ' + (dg as string).substring(0, 10) + '...');
         const { startDeepgramStream } = await import('../lib/deepgram');
+        const keywords = await invoke<string[]>('stt_keywords').catch(() => [] as string[]);
+        const sttOptions = await invoke<{ deepgram: DeepgramOptions; stt_endpoint_override: string }>('get_stt_options').catch(() => null);
+        const language = await invoke<string>('get_language', { provider: 'deepgram' }).catch(() => 'en');
         const rec = await startDeepgramStream(dg as string, stream, {
-          onTranscript: (t, final) => {
+          onTranscript: (t, final, meta) => {
             log('[DG] Transcript received - final: ' + final + ', text: ' + t);
             if (t) {
+              noteFirstText();
               latestTranscriptRef.current = t;
+              latestMetaRef.current = meta || {};
             }
             if (t && final) {
-              partialRef.current.push(t);
+              addFinal(t, meta);
               log('[DG] Added to partials, total: ' + partialRef.current.length);
             }
           },
@@ -209,11 +460,12 @@ export function Hud() {
               log('[DG] Failed to set backend state: ' + String(e));
             });
             setBadge(null);
-            timerRef.current = window.setInterval(()=> setSeconds(s=>s+1), 1000);
+            timerRef.current = window.setInterval(()=> setSeconds(s => pausedRef.current ? s : s + 1), 1000);
           },
           onError: (e) => {
             log('[DG] WebSocket ERROR: ' + String(e));
-            setBadge('Network error. Try again');
+            invoke('report_failure', { kind: 'stt_connection', detail: 'deepgram: ' + String(e) }).catch(() => {});
+            showNetworkError();
             setIsConnecting(false);
             setIsRecording(false);
             invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
@@ -223,7 +475,7 @@ export function Hud() {
             isReadyRef.current = false;
             invoke('set_recording_active', { newState: 'inactive' }).catch(() => {});
           }
-        });
+        }, keywords, sttOptions?.deepgram, language, sttOptions?.stt_endpoint_override || '');
         recRef.current = rec;
         log('Deepgram recorder stored in ref');
       }
@@ -236,6 +488,8 @@ export function Hud() {
       log('Error message: ' + (e instanceof Error ? e.message : String(e)));
       log('Error stack: ' + (e instanceof Error ? e.stack : 'no stack'));
 
+      micRef.current?.close();
+      micRef.current = null;
       setIsRecording(false);
       setIsConnecting(false);
       isReadyRef.current = false;
@@ -256,18 +510,21 @@ export function Hud() {
       invoke('log_to_terminal', { message: msg }).catch(() => {});
     };
 
-    log('📡 Setting up dictation-start listener...');
+    log('📡 Setting up start listener...');
     let unstart: any;
     (async () => {
-      unstart = await listen('dictation-start', ()=> {
-        log('🎯🎯🎯 RECEIVED dictation-start EVENT 🎯🎯🎯');
+      unstart = await onHudEvent('start', (e)=> {
+        log('🎯🎯🎯 RECEIVED start EVENT 🎯🎯🎯');
+        sessionRef.current = e.session_id;
         log('Calling begin()...');
         begin();
       });
-      log('✅ dictation-start listener registered');
+      log('✅ start listener registered');
+      // Lets the backend emit the start event to a freshly recreated HUD
+      invoke('hud_ready').catch(() => {});
     })();
     return () => {
-      log('🧹 Cleaning up dictation-start listener');
+      log('🧹 Cleaning up start listener');
       unstart?.();
     };
   }, []);
@@ -278,25 +535,183 @@ export function Hud() {
       invoke('log_to_terminal', { message: msg }).catch(() => {});
     };
 
-    log('📡 Setting up dictation-stop listener...');
+    log('📡 Setting up stop listener...');
     let unstop: any;
     (async () => {
-      unstop = await listen('dictation-stop', ()=> {
-        log('🎯🎯🎯 RECEIVED dictation-stop EVENT 🎯🎯🎯');
+      unstop = await onHudEvent('stop', ()=> {
+        log('🎯🎯🎯 RECEIVED stop EVENT 🎯🎯🎯');
         log('Calling stop()...');
         stop();
       });
-      log('✅ dictation-stop listener registered');
+      log('✅ stop listener registered');
     })();
     return () => {
-      log('🧹 Cleaning up dictation-stop listener');
+      log('🧹 Cleaning up stop listener');
       unstop?.();
     };
   }, []);
 
+  useEffect(() => {
+    let unretake: any;
+    (async () => {
+      unretake = await onHudEvent('retake', () => retake());
+    })();
+    return () => { unretake?.(); };
+  }, []);
+
+  useEffect(() => {
+    let unpause: any;
+    (async () => {
+      unpause = await onHudEvent('paused', (e) => {
+        invoke('log_to_terminal', { message: e.paused ? '⏸️ Paused' : '▶️ Resumed' }).catch(() => {});
+        pausedRef.current = e.paused;
+        setPaused(e.paused);
+        recRef.current?.setPaused?.(e.paused);
+      });
+    })();
+    return () => { unpause?.(); };
+  }, []);
+
+  // Move mode: the HUD is shown on its own to be dragged; Enter keeps the spot, Esc discards it
+  useEffect(() => {
+    let unmove: any;
+    (async () => {
+      unmove = await onHudEvent('move_mode', (e) => setMoveMode(e.active));
+    })();
+    return () => { unmove?.(); };
+  }, []);
+
+  useEffect(() => {
+    if (!moveMode) return;
+    const onKey = (e: KeyboardEvent) => {
+      if (e.key === 'Enter') finishMove(true);
+      else if (e.key === 'Escape') finishMove(false);
+    };
+    window.addEventListener('keydown', onKey);
+    return () => window.removeEventListener('keydown', onKey);
+  }, [moveMode]);
+
+  function finishMove(save: boolean) {
+    invoke('finish_hud_move', { save }).catch((e) => invoke('log_to_terminal', { message: '⚠️ finish_hud_move: ' + String(e) }).catch(() => {}));
+  }
+
+  // Editing a paste preview: the backend let go of the keys, so take focus and edit in place
+  useEffect(() => {
+    let unedit: any;
+    (async () => {
+      unedit = await onHudEvent('paste_edit', () => {
+        setPreview(p => {
+          if (p) {
+            setPreviewEdit(p.text);
+            previewEditRef.current = p.text;
+          }
+          return p && { ...p, editing: true };
+        });
+        getCurrentWindow().setFocus().catch(() => {});
+      });
+    })();
+    return () => { unedit?.(); };
+  }, []);
+
+  // E edits a preview only while the HUD itself has focus (clicked), never as a global key
+  useEffect(() => {
+    if (!preview || preview.editing) return;
+    const onKey = (e: KeyboardEvent) => {
+      if (e.key.toLowerCase() === 'e' && !e.ctrlKey && !e.metaKey && !e.altKey) {
+        e.preventDefault();
+        answerPreview('edit');
+      }
+    };
+    window.addEventListener('keydown', onKey);
+    return () => window.removeEventListener('keydown', onKey);
+  }, [preview]);
+
+  function answerPreview(answer: 'paste' | 'cancel' | 'edit') {
+    invoke('answer_paste_confirmation', { answer }).catch(() => {});
+  }
+
+  // Target window closed before insertion: the backend kept the text; offer the scratchpad
+  useEffect(() => {
+    let unclosed: any;
+    let hideTimer: number | undefined;
+    (async () => {
+      unclosed = await onHudEvent('target_closed', (e) => {
+        invoke('log_to_terminal', { message: '🚪 target_closed: ' + e.window }).catch(() => {});
+        setTargetClosed(e.window || 'The target window');
+        window.clearTimeout(hideTimer);
+        hideTimer = window.setTimeout(() => {
+          setTargetClosed(null);
+          invoke('stop_dictation').catch(() => {});
+        }, 8000);
+      });
+    })();
+    return () => { unclosed?.(); window.clearTimeout(hideTimer); };
+  }, []);
+
+  async function openScratchpad() {
+    setTargetClosed(null);
+    await invoke('stop_dictation').catch(() => {});
+    await invoke('open_scratchpad').catch(() => {});
+  }
+
+  // Backend watchdog reset a stuck session: drop it without inserting anything
+  useEffect(() => {
+    let unerror: any;
+    (async () => {
+      unerror = await onHudEvent('error', (e) => {
+        invoke('log_to_terminal', { message: `🐕 error ${e.code}: ${e.message}` }).catch(() => {});
+        sessionRef.current = null;
+        const recorder = recRef.current;
+        recRef.current = null;
+        Promise.resolve(recorder?.stop()).catch(() => {});
+        if (timerRef.current) {
+          clearInterval(timerRef.current);
+          timerRef.current = null;
+        }
+        partialRef.current = [];
+        latestTranscriptRef.current = '';
+        latestMetaRef.current = {};
+        isReadyRef.current = false;
+        pausedRef.current = false;
+        setPaused(false);
+        setIsRecording(false);
+        setIsConnecting(false);
+        setPasteConfirm(null);
+        setShow(false);
+      });
+    })();
+    return () => { unerror?.(); };
+  }, []);
+
   const mm = String(Math.floor(seconds/60)).padStart(2,'0');
   const ss = String(seconds % 60).padStart(2,'0');
 
+  // Karaoke-style replay: highlight each word at the time it was spoken
+  async function playReview(words: Word[]) {
+    if (words.length === 0) return;
+    const first = words[0].start_ms;
+    const last = words[words.length - 1].end_ms;
+    const speed = Math.max(1, (last - first) / REVIEW_MAX_MS);
+    setReview({ words, index: 0 });
+    const startedAt = performance.now();
+    await new Promise<void>((resolve) => {
+      const tick = () => {
+        const at = first + (performance.now() - startedAt) * speed;
+        const index = words.findIndex(w => w.end_ms > at);
+        if (index === -1) {
+          setReview({ words, index: words.length - 1 });
+          resolve();
+          return;
+        }
+        setReview(r => (r && r.index !== index ? { words, index } : r));
+        requestAnimationFrame(tick);
+      };
+      requestAnimationFrame(tick);
+    });
+    await new Promise((r) => setTimeout(r, 300));
+    setReview(null);
+  }
+
   async function stop() {
     const log = (msg: string) => {
       console.log(msg);
@@ -329,10 +744,14 @@ export function Hud() {
         log('?? Error while stopping recorder: ' + String(err));
       }
     }
+    micRef.current?.close();
+    micRef.current = null;
 
     setIsRecording(false);
     setIsConnecting(false);
     isReadyRef.current = false;
+    pausedRef.current = false;
+    setPaused(false);
 
     // After stopping, check again if we captured anything (final or latest interim)
     const hasAnyTranscript =
@@ -355,12 +774,14 @@ export function Hud() {
       log('? Backend state set to STOPPING');
 
       // Collect transcript - use partials if available, otherwise use latest non-final transcript
-      let raw = partialRef.current.join(' ').trim();
+      const segments = [...partialRef.current];
+      let raw = segments.map(s => s.text).join(' ').trim();
       const latest = latestTranscriptRef.current.trim();
       if (raw && latest) {
         if (!raw.includes(latest)) {
           log('?? Appending latest non-final transcript to raw: "' + latest + '"');
           raw = `${raw} ${latest}`.trim();
+          segments.push({ text: latest, ...latestMetaRef.current });
         } else {
           log('?? Latest non-final transcript already included in raw, not appending');
         }
@@ -368,9 +789,11 @@ export function Hud() {
       if (!raw && latest) {
         log('?? No final transcripts, using latest non-final: "' + latest + '"');
         raw = latest;
+        segments.push({ text: latest, ...latestMetaRef.current });
       }
       partialRef.current = [];
       latestTranscriptRef.current = '';
+      latestMetaRef.current = {};
       log('Raw transcript: "' + raw + '"');
 
       if (!raw) {
@@ -382,17 +805,71 @@ export function Hud() {
         return;
       }
 
+      let transcript: Transcript = {
+        session_id: sessionRef.current,
+        provider: providerRef.current,
+        language: null,
+        segments,
+        refined: null,
+      };
+      // The used take sits next to any set aside by a retake, so another can be picked afterwards
+      invoke('save_take', { transcript }).catch(() => {});
+
       // Refine text using OpenRouter (with fast timeout)
       log('?? Refining text with OpenRouter...');
-      let refined = raw; // Default to raw text
+      const refining = invoke<Transcript>('refine_text', { transcript, openrouterKey: null });
+      refining.catch(() => {}); // handled below; avoid an unhandled rejection while the review plays
+      if (reviewRef.current) {
+        const words = await invoke<Word[]>('transcript_words', { transcript }).catch(() => [] as Word[]);
+        log('🎞️ Reviewing ' + words.length + ' words');
+        await playReview(words);
+      }
       try {
-        refined = await invoke<string>('refine_text', { rawText: raw, openrouterKey: null });
-        log('? Refined text: "' + refined + '"');
+        transcript = await refining;
+        log('? Refined text: "' + transcript.refined + '"');
+        // Translation mode: briefly show the original next to the translation before inserting
+        const t = await invoke<any>('take_last_translation').catch(() => null);
+        if (t) {
+          setTranslation(t);
+          await new Promise((r) => setTimeout(r, 1500));
+          setTranslation(null);
+        }
       } catch (e) {
         log('?? OpenRouter failed or timed out, using raw text: ' + String(e));
         // Use raw text as fallback
       }
 
+      // Training wheels: the first few auto-pastes wait for Enter (Esc copies only). Preview mode
+      // shows the final text before every paste: Enter pastes, Esc discards, E edits it first
+      let copyOnly = false;
+      let discard = false;
+      const confirm = await invoke<{ required: boolean; confirmed: number; limit: number; preview: boolean }>('get_paste_confirmation').catch(() => null);
+      if (confirm?.preview) {
+        const text = transcript.refined ?? transcriptText(transcript);
+        log('👀 Previewing before paste');
+        previewEditRef.current = null;
+        setPreview({ text, editing: false });
+        await growHud(PREVIEW_EXTRA_PX).catch(() => {});
+        const decision = await invoke<PasteDecision>('confirm_paste').catch((): PasteDecision => 'timeout');
+        setPreview(null);
+        await growHud(-PREVIEW_EXTRA_PX).catch(() => {});
+        const edited = previewEditRef.current?.trim();
+        if (decision === 'paste' && edited && edited !== text) {
+          log('✏️ Pasting the edited text');
+          invoke('report_correction', { original: text, corrected: edited }).catch(() => {});
+          transcript = { ...transcript, refined: edited };
+        }
+        // A preview left alone keeps the text on the clipboard rather than losing it
+        discard = decision === 'cancel' || (decision === 'paste' && edited === '');
+        copyOnly = decision === 'timeout';
+      } else if (confirm?.required) {
+        log('✋ Waiting for paste confirmation (' + (confirm.confirmed + 1) + '/' + confirm.limit + ')');
+        setPasteConfirm({ confirmed: confirm.confirmed, limit: confirm.limit });
+        const decision = await invoke<PasteDecision>('confirm_paste').catch(() => 'paste');
+        setPasteConfirm(null);
+        copyOnly = decision !== 'paste';
+      }
+
       // Hide HUD BEFORE pasting to ensure focus returns to the target app
       // Clicking the Stop button focuses the HUD window; if we paste while HUD is focused,
       // the OS key events can be ignored. Hiding first reliably returns focus.
@@ -402,9 +879,20 @@ export function Hud() {
       // Small settle delay so the OS can restore focus to the previous window
       await new Promise((r) => setTimeout(r, 250));
 
+      // The watchdog may have abandoned this session while refinement hung
+      if (sessionRef.current !== transcript.session_id) {
+        log('🐕 Session was reset while stopping, not inserting');
+        return;
+      }
+      if (discard) {
+        log('🗑️ Preview discarded, nothing inserted');
+        await invoke('set_recording_active', { newState: 'inactive' });
+        return;
+      }
+
       // Now insert text
       log('?? Inserting text into focused field...');
-      const pasted: boolean = await invoke('insert_text', { text: refined });
+      const pasted: boolean = await invoke('insert_text', { transcript, copyOnly });
       log('Insert result: ' + (pasted ? '? pasted successfully' : '? paste failed, copied to clipboard'));
 
       // CRITICAL: Set state back to INACTIVE after everything is done
@@ -432,7 +920,7 @@ export function Hud() {
             {/* Timer - no background, fade from left */}
             <motion.div
               initial={{ x: -10, opacity: 0 }}
-              animate={{ x: 0, opacity: 0.9 }}
+              animate={{ x: 0, opacity: paused ? 0.5 : 0.9 }}
               exit={{ x: -10, opacity: 0 }}
               transition={{ duration: 0.2, ease: 'easeOut' }}
               className="text-[#f2f1ea] font-mono text-sm tracking-wider select-none pointer-events-none"
@@ -450,8 +938,14 @@ export function Hud() {
             >
               {isConnecting ? (
                 <div className="w-3 h-3 border-2 border-white/30 border-t-white/80 rounded-full animate-spin" aria-hidden="true" />
+              ) : paused ? (
+                <div className="flex items-center gap-1.5 text-[11px] text-[#f2f1ea]/80 select-none" aria-label="Paused">
+                  <span className="w-[3px] h-[10px] bg-[#f2f1ea]/80 rounded-sm" />
+                  <span className="w-[3px] h-[10px] bg-[#f2f1ea]/80 rounded-sm -ml-0.5" />
+                  Paused
+                </div>
               ) : (
-                analyser && <Waveform analyser={analyser} />
+                analyser && <Waveform analyser={analyser} level={backendLevelRef} />
               )}
             </motion.div>
 
@@ -470,6 +964,115 @@ export function Hud() {
         )}
       </AnimatePresence>
 
+      {moveMode && (
+        <div
+          onMouseDown={(e) => { if (e.button === 0 && (e.target as HTMLElement).tagName !== 'BUTTON') getCurrentWindow().startDragging().catch(() => {}); }}
+          className="fixed inset-0 m-2 rounded-xl border-2 border-dashed border-white/40 bg-[#0f0f0f]/80 text-xs text-[#f2f1ea] flex items-center justify-center gap-3 cursor-move select-none"
+        >
+          <span>Drag to move the HUD · <b>Enter</b> saves · <b>Esc</b> cancels</span>
+          <button type="button" onClick={() => finishMove(true)} className="px-2 py-0.5 rounded bg-white/10 hover:bg-white/20">Save</button>
+          <button type="button" onClick={() => finishMove(false)} className="px-2 py-0.5 rounded bg-white/10 hover:bg-white/20">Cancel</button>
+        </div>
+      )}
+
+      <AnimatePresence>
+        {targetClosed && (
+          <motion.div
+            initial={{ y: 8, opacity: 0 }}
+            animate={{ y: 0, opacity: 1 }}
+            exit={{ y: 8, opacity: 0 }}
+            transition={{ duration: 0.18 }}
+            className="fixed left-1/2 -translate-x-1/2 bottom-[70px] max-w-[560px] px-3 py-2 rounded-lg bg-[#0f0f0f]/95 border border-white/5 text-xs text-[#f2f1ea] flex items-center gap-3"
+          >
+            <span className="truncate">{targetClosed} closed · text copied to clipboard</span>
+            <button type="button" onClick={openScratchpad} className="shrink-0 px-2 py-0.5 rounded bg-white/10 hover:bg-white/20">
+              Paste into scratchpad
+            </button>
+          </motion.div>
+        )}
+      </AnimatePresence>
+
+      <AnimatePresence>
+        {pasteConfirm && (
+          <motion.div
+            initial={{ y: 8, opacity: 0 }}
+            animate={{ y: 0, opacity: 1 }}
+            exit={{ y: 8, opacity: 0 }}
+            transition={{ duration: 0.18 }}
+            className="fixed left-1/2 -translate-x-1/2 bottom-[70px] px-3 py-2 rounded-lg bg-[#0f0f0f]/95 border border-white/5 text-xs text-[#f2f1ea] select-none"
+          >
+            Press <b>Enter</b> to paste · <b>Esc</b> copies only
+            <span className="opacity-60"> ({pasteConfirm.confirmed + 1} of {pasteConfirm.limit})</span>
+          </motion.div>
+        )}
+      </AnimatePresence>
+
+      <AnimatePresence>
+        {preview && (
+          <motion.div
+            initial={{ y: 8, opacity: 0 }}
+            animate={{ y: 0, opacity: 1 }}
+            exit={{ y: 8, opacity: 0 }}
+            transition={{ duration: 0.18 }}
+            className="fixed left-1/2 -translate-x-1/2 bottom-[70px] w-[560px] px-3 py-2 rounded-lg bg-[#0f0f0f]/95 border border-white/5 text-xs text-[#f2f1ea] pointer-events-auto"
+          >
+            {preview.editing ? (
+              <textarea
+                autoFocus
+                value={previewEdit}
+                onChange={(e) => { setPreviewEdit(e.target.value); previewEditRef.current = e.target.value; }}
+                onKeyDown={(e) => {
+                  if (e.key === 'Enter' && !e.shiftKey) { e.preventDefault(); answerPreview('paste'); }
+                  else if (e.key === 'Escape') { e.preventDefault(); answerPreview('cancel'); }
+                }}
+                rows={5}
+                className="w-full px-2 py-1 bg-black/40 border border-white/10 rounded text-sm resize-none focus:outline-none"
+              />
+            ) : (
+              <div className="max-h-[120px] overflow-y-auto text-sm whitespace-pre-wrap select-none">{preview.text}</div>
+            )}
+            <div className="mt-1.5 opacity-60 select-none">
+              {preview.editing
+                ? <><b>Enter</b> pastes · <b>Shift+Enter</b> new line · <b>Esc</b> discards</>
+                : <><b>Enter</b> pastes · <b>Esc</b> discards · <button type="button" onClick={() => answerPreview('edit')} className="underline pointer-events-auto"><b>E</b>dit</button></>}
+            </div>
+          </motion.div>
+        )}
+      </AnimatePresence>
+
+      <AnimatePresence>
+        {review && (
+          <motion.div
+            initial={{ y: 8, opacity: 0 }}
+            animate={{ y: 0, opacity: 1 }}
+            exit={{ y: 8, opacity: 0 }}
+            transition={{ duration: 0.18 }}
+            className="fixed left-1/2 -translate-x-1/2 bottom-[70px] max-w-[560px] px-3 py-2 rounded-lg bg-[#0f0f0f]/95 border border-white/5 text-xs text-[#f2f1ea] select-none whitespace-nowrap overflow-hidden"
+          >
+            {review.words.slice(Math.max(0, review.index - 6), review.index + 5).map((w, i, shown) => {
+              const index = Math.max(0, review.index - 6) + i;
+              const cls = index === review.index ? 'text-white font-medium' : index < review.index ? 'opacity-80' : 'opacity-30';
+              return <span key={index} className={cls}>{w.text}{i < shown.length - 1 ? ' ' : ''}</span>;
+            })}
+          </motion.div>
+        )}
+      </AnimatePresence>
+
+      <AnimatePresence>
+        {translation && (
+          <motion.div
+            initial={{ y: 8, opacity: 0 }}
+            animate={{ y: 0, opacity: 1 }}
+            exit={{ y: 8, opacity: 0 }}
+            transition={{ duration: 0.18 }}
+            className="fixed left-1/2 -translate-x-1/2 bottom-[70px] max-w-[560px] px-3 py-2 rounded-lg bg-[#0f0f0f]/95 border border-white/5 text-xs text-[#f2f1ea] select-none"
+          >
+            <div className="opacity-60 truncate">{translation.source ? translation.source.toUpperCase() + ': ' : ''}{translation.original}</div>
+            <div className="truncate">{translation.target}: {translation.translated}</div>
+          </motion.div>
+        )}
+      </AnimatePresence>
+
       <AnimatePresence>
         {badge && (
           <motion.div
@@ -479,7 +1082,7 @@ export function Hud() {
             transition={{ duration: 0.18 }}
             className="fixed left-1/2 -translate-x-1/2 bottom-[70px]"
           >
-            <Badge text={badge} />
+            <Badge text={badge} kind={badgeKind} />
           </motion.div>
         )}
       </AnimatePresence>
@@ -0,0 +1,117 @@
+import { useEffect, useRef, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+import { openMic, type Mic } from '../lib/mic';
+import { startRelayStream } from '../lib/relay';
+
+type Paragraph = { speaker: number | null; text: string };
+type MeetingStatus = { active: boolean; file: string | null; started_at: number | null; paragraphs: Paragraph[] };
+type Recorder = Awaited<ReturnType<typeof startRelayStream>>;
+
+const speakerLabel = (speaker: number | null) => (speaker === null ? 'Speaker' : `Speaker ${speaker + 1}`);
+
+// Long-form transcription of a meeting: speaker-labeled paragraphs, written to a file as they come
+export function Meeting() {
+  const [paragraphs, setParagraphs] = useState<Paragraph[]>([]);
+  const [partial, setPartial] = useState('');
+  const [file, setFile] = useState<string | null>(null);
+  const [running, setRunning] = useState(false);
+  const [busy, setBusy] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+  const micRef = useRef<Mic | null>(null);
+  const recRef = useRef<Recorder | null>(null);
+  const bottomRef = useRef<HTMLDivElement | null>(null);
+
+  useEffect(() => {
+    invoke<MeetingStatus>('get_meeting_status').then(s => { setParagraphs(s.paragraphs); setFile(s.file); }).catch(() => {});
+    const unlisten = listen<{ index: number; paragraph: Paragraph }>('meeting-transcript', (e) => {
+      const { index, paragraph } = e.payload;
+      setParagraphs(prev => { const next = prev.slice(); next[index] = paragraph; return next; });
+      setPartial('');
+    });
+    // Closing the window ends the meeting rather than leaving the mic open
+    const unlistenClose = getCurrentWindow().onCloseRequested(async () => { await stop(); });
+    return () => {
+      unlisten.then(u => u());
+      unlistenClose.then(u => u());
+    };
+  }, []);
+
+  useEffect(() => { bottomRef.current?.scrollIntoView({ block: 'end' }); }, [paragraphs, partial]);
+
+  async function start() {
+    setBusy(true);
+    setError(null);
+    try {
+      const path = await invoke<string>('start_meeting');
+      setFile(path);
+      setParagraphs([]);
+      const behavior = await invoke<any>('get_behavior').catch(() => null);
+      const noiseMode = await invoke<'off' | 'browser' | 'rnnoise'>('get_noise_mode').catch(() => 'browser');
+      const mic = await openMic({ echoCancellation: behavior?.echo_cancellation !== false, noiseSuppression: noiseMode === 'browser' });
+      micRef.current = mic;
+      recRef.current = await startRelayStream(mic.stream, {
+        onTranscript: (text, isFinal) => { if (!isFinal) setPartial(text); },
+        onError: (e) => setError(String(e)),
+        onReconnecting: () => setError('Connection lost, reconnecting…'),
+        onResumed: () => setError(null),
+      }, { meeting: true });
+      setRunning(true);
+    } catch (e) {
+      setError(String(e));
+      micRef.current?.close();
+      micRef.current = null;
+      await invoke('stop_meeting').catch(() => {});
+    } finally {
+      setBusy(false);
+    }
+  }
+
+  async function stop() {
+    if (!recRef.current && !micRef.current) return;
+    setBusy(true);
+    try {
+      // Finals still in flight land in the transcript before the meeting ends
+      await recRef.current?.stop();
+      micRef.current?.close();
+      recRef.current = null;
+      micRef.current = null;
+      const path = await invoke<string | null>('stop_meeting');
+      if (path) setFile(path);
+    } finally {
+      setPartial('');
+      setRunning(false);
+      setBusy(false);
+    }
+  }
+
+  return (
+    <div className="h-full flex flex-col gap-3 p-4 bg-[var(--bg)]">
+      <div className="flex items-center justify-between">
+        <div className="text-sm font-medium">{running ? 'Transcribing meeting…' : 'Meeting transcription'}</div>
+        {running ? (
+          <button type="button" disabled={busy} onClick={stop} className="px-3 py-1.5 rounded border border-neutral-700 text-sm hover:bg-neutral-800">
+            Stop
+          </button>
+        ) : (
+          <button type="button" disabled={busy} onClick={start} className="px-3 py-1.5 rounded bg-[var(--accent)] text-black text-sm">
+            Start
+          </button>
+        )}
+      </div>
+      {error && <div className="text-xs text-red-400">{error}</div>}
+      <div className="flex-1 overflow-y-auto px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-sm space-y-3">
+        {paragraphs.length === 0 && !partial && (
+          <div className="text-muted text-xs">Speakers are told apart with Deepgram. Nothing is pasted; the transcript is saved as you go.</div>
+        )}
+        {paragraphs.map((p, i) => (
+          <p key={i}><span className="font-semibold">{speakerLabel(p.speaker)}:</span> {p.text}</p>
+        ))}
+        {partial && <p className="text-muted italic">{partial}</p>}
+        <div ref={bottomRef} />
+      </div>
+      {file && <div className="text-xs text-muted break-all">Saved to {file}</div>}
+    </div>
+  );
+}
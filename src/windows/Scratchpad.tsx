@@ -0,0 +1,48 @@
+import { useEffect, useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { getCurrentWindow } from '@tauri-apps/api/window';
+
+// Holds a dictation whose target window closed before it could be pasted
+export function Scratchpad() {
+  const [text, setText] = useState('');
+  // As dictated, to learn from the user's edits
+  const [original, setOriginal] = useState('');
+  const [copied, setCopied] = useState(false);
+
+  useEffect(() => {
+    const load = () => invoke<string | null>('get_scratchpad_text').then(t => { setText(t || ''); setOriginal(t || ''); }).catch(() => {});
+    load();
+    // Reopening an existing scratchpad brings the newest stashed text
+    const onFocus = () => load();
+    window.addEventListener('focus', onFocus);
+    return () => window.removeEventListener('focus', onFocus);
+  }, []);
+
+  async function copy() {
+    await navigator.clipboard.writeText(text);
+    if (original && text !== original) {
+      invoke('report_correction', { original, corrected: text }).catch(() => {});
+    }
+    setCopied(true);
+    setTimeout(() => setCopied(false), 1500);
+  }
+
+  return (
+    <div className="h-full flex flex-col gap-3 p-4 bg-[var(--bg)]">
+      <div className="text-xs text-muted">The window you were dictating into closed. Your text is kept here and on the clipboard.</div>
+      <textarea
+        value={text}
+        onChange={(e) => setText(e.target.value)}
+        className="flex-1 w-full px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-sm resize-none"
+      />
+      <div className="flex justify-end gap-2">
+        <button type="button" onClick={() => getCurrentWindow().close()} className="px-3 py-1.5 rounded border border-neutral-700 text-sm hover:bg-neutral-800">
+          Close
+        </button>
+        <button type="button" onClick={copy} className="px-3 py-1.5 rounded bg-[var(--accent)] text-black text-sm">
+          {copied ? 'Copied' : 'Copy'}
+        </button>
+      </div>
+    </div>
+  );
+}
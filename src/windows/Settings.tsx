@@ -1,10 +1,11 @@
 import { useEffect, useMemo, useState, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { getVersion } from '@tauri-apps/api/app';
 import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import { check, Update, type DownloadEvent } from '@tauri-apps/plugin-updater';
 import { relaunch } from '@tauri-apps/plugin-process';
-import { initGlobalHotkey } from '../lib/hotkey';
+import { initGlobalHotkey, whenHotkeyReady } from '../lib/hotkey';
 import { motion, AnimatePresence } from 'framer-motion';
 import { Check, X, Loader2, Minus, X as XIcon, Download, RefreshCw } from 'lucide-react';
 // open external link using the browser; avoids requiring shell plugin here
@@ -12,8 +13,76 @@ import { Switch } from '../components/Switch';
 import { ChevronRight, ChevronDown, KeyRound, Rocket, Save, Settings as SettingsIcon } from 'lucide-react';
 import { log, error as logError, warn as logWarn } from '../lib/log';
 import { KeyRecorder } from '../components/KeyRecorder';
+import type { AudioDevice, NoiseMode } from '../lib/mic';
+import type { DeepgramOptions } from '../lib/deepgram';
+import type { ElevenLabsOptions } from '../lib/elevenlabs';
+
+type SttOptions = { deepgram: DeepgramOptions; elevenlabs: ElevenLabsOptions; stt_endpoint_override: string };
 
 type KeysPresent = { openrouter: boolean; deepgram: boolean; megallm: boolean; elevenlabs: boolean };
+// Mirrors key_inspect::KeyInspection in src-tauri/src/key_inspect.rs
+type ProviderUsage = { provider: string; remaining: number | null; limit: number | null; unit: string; resets_at: number | null; low: boolean; error: string | null };
+type MonthCost = { month: string; dictations: number; audio_ms: number; stt_usd: number; ai_usd: number; unpriced: number; total_usd: number };
+type CostSummary = { months: MonthCost[]; total_usd: number };
+type KeyCheck = { provider: 'openrouter' | 'deepgram' | 'megallm' | 'elevenlabs'; status: 'ok' | 'unauthorized' | 'network_error' | 'provider_error'; saved: boolean; detail: string | null };
+type KeyInspection = { provider: string; prefix: string | null; length: number; status: 'missing' | 'valid' | 'invalid'; detail: string | null };
+// Mirrors jobs::Job in src-tauri/src/jobs.rs
+type TranscriptionJob = {
+  id: number;
+  file: string;
+  refine: boolean;
+  output_format: 'txt' | 'md';
+  status: 'queued' | 'transcribing' | 'refining' | 'done' | 'failed' | 'cancelled';
+  output: string | null;
+  error: string | null;
+  created_at: number;
+  batch?: number | null;
+};
+
+// Mirrors jobs::BatchProgress in src-tauri/src/jobs.rs
+type BatchProgress = {
+  id: number;
+  total: number;
+  queued: number;
+  running: number;
+  done: number;
+  failed: number;
+  cancelled: number;
+  finished: boolean;
+};
+
+// Mirrors maintenance::MaintenanceStatus in src-tauri/src/maintenance.rs
+type MaintenanceReport = {
+  started_at: number;
+  duration_ms: number;
+  history_purged: number;
+  logs_removed: number;
+  cache_files_removed: number;
+  cache_bytes_freed: number;
+  damaged_files: string[];
+  providers: KeyInspection[] | null;
+  errors: string[];
+};
+type MaintenanceStatus = { interval_hours: number; last_run: number | null; next_run: number | null; running: boolean; last_report: MaintenanceReport | null };
+
+// Mirrors INSERT_MODES in src-tauri/src/lib.rs
+const INSERT_MODES = ['paste', 'type', 'human', 'macro'] as const;
+type InsertMode = typeof INSERT_MODES[number];
+type RegexRule = { find: string; replace: string; ignore_case: boolean };
+type Typography = { quotes: 'keep' | 'straight' | 'curly'; ellipsis: 'keep' | 'character' | 'dots'; em_dash: 'keep' | 'spaced' | 'closed' };
+const DEFAULT_TYPOGRAPHY: Typography = { quotes: 'keep', ellipsis: 'keep', em_dash: 'keep' };
+type PluginList = { dir: string; plugins: { name: string; enabled: boolean; hooks: string[]; error: string | null }[] };
+type MonitorInfo = { index: number; name: string | null; width: number; height: number; primary: boolean };
+type ScriptStatus = { path: string; present: boolean; error: string | null };
+type PipelineTrace = { input: string; stages: { stage: string; text: string; changed: boolean }[] };
+
+// Mirrors CATEGORIES in src-tauri/src/pii.rs
+const REDACT_CATEGORIES = [
+  { id: 'profanity', label: 'Profanity' },
+  { id: 'email', label: 'Emails' },
+  { id: 'phone', label: 'Phone numbers' },
+  { id: 'card', label: 'Card numbers' },
+];
 
 type UpdateStatus = 'idle' | 'checking' | 'available' | 'downloading' | 'ready' | 'error' | 'uptodate';
 
@@ -148,13 +217,14 @@ function TitleBar({ version }: { version: string }) {
   const win = getCurrentWebviewWindow();
   const updateState = useUpdateState();
 
-  // Check for updates on mount
+  // Check for updates on mount, but only once the hotkey works: the check is not urgent
   useEffect(() => {
-    // Small delay to let the window fully initialize
-    const timer = setTimeout(() => {
-      checkForUpdatesGlobal();
-    }, 500);
-    return () => clearTimeout(timer);
+    let timer: ReturnType<typeof setTimeout> | undefined;
+    let canceled = false;
+    whenHotkeyReady().then(() => {
+      if (!canceled) timer = setTimeout(checkForUpdatesGlobal, 2000);
+    });
+    return () => { canceled = true; if (timer) clearTimeout(timer); };
   }, []);
 
   const handleMinimize = async () => {
@@ -422,6 +492,13 @@ function UpdateSection({ onToast }: { onToast: (text: string, kind: 'ok' | 'err'
   );
 }
 
+type PackConflict = { section: string; from: string; current: string; incoming: string };
+type CorrectionSuggestion = { from: string; to: string; count: number; kind: 'dictionary' | 'rule' };
+type PackInfo = { id: string; name: string; description: string; enabled: boolean; apps: string[] };
+type ProxyPrefs = { mode: 'off' | 'system' | 'manual'; url: string; username: string; bypass: string[] };
+type ProxyStatus = ProxyPrefs & { has_password: boolean };
+type PackImportReport = { applied: boolean; added: number; replaced: number; skipped: number; conflicts: PackConflict[] };
+
 function Toast({ text, kind }: { text: string; kind: 'ok'|'err' }) {
   return (
     <motion.div initial={{ y: 8, opacity: 0 }} animate={{ y: 0, opacity: 1 }} exit={{ y: 8, opacity: 0 }} className={`fixed bottom-4 right-4 px-3 py-2 rounded-md shadow border ${kind==='ok'?'bg-neutral-800 border-neutral-700':'bg-[var(--badge-bg)] border-[var(--badge-border)] text-[var(--badge-text)]'}`}>
@@ -434,12 +511,107 @@ export function Settings() {
   const [version, setVersion] = useState('');
   const [autoPaste, setAutoPaste] = useState(true);
   const [streamInsert, setStreamInsert] = useState(false);
+  const [silenceSecs, setSilenceSecs] = useState(0);
   const [autostart, setAutostart] = useState(false);
   const [aiRefine, setAiRefine] = useState(true);
+  const [spokenPunctuation, setSpokenPunctuation] = useState(true);
+  const [emoji, setEmoji] = useState(false);
+  const [soundStart, setSoundStart] = useState(false);
+  const [soundStop, setSoundStop] = useState(false);
+  const [soundError, setSoundError] = useState(false);
+  const [soundVolume, setSoundVolume] = useState(60);
+  const [notifyErrors, setNotifyErrors] = useState(false);
+  const [numbersMode, setNumbersMode] = useState<'words' | 'digits' | 'auto'>('words');
+  const [aiTimeoutSecs, setAiTimeoutSecs] = useState(5);
+  const [aiMaxTokens, setAiMaxTokens] = useState(2048);
+  const [aiMinSimilarity, setAiMinSimilarity] = useState(50);
+  // One phrase per line
+  const [refusalAdded, setRefusalAdded] = useState('');
+  const [refusalRemoved, setRefusalRemoved] = useState('');
+  const [dictionary, setDictionary] = useState<string[]>([]);
+  const [dictionaryWord, setDictionaryWord] = useState('');
+  // Custom pack (symbols, snippets, rules, prompt additions) edited as JSON
+  const [packJson, setPackJson] = useState('');
+  const [packImportPath, setPackImportPath] = useState('');
+  const [backupPassphrase, setBackupPassphrase] = useState('');
+  const [configProfiles, setConfigProfiles] = useState<{ active: string; names: string[] }>({ active: 'Default', names: ['Default'] });
+  const [newProfileName, setNewProfileName] = useState('');
+  const [backupImportPath, setBackupImportPath] = useState('');
+  const [proxy, setProxy] = useState<ProxyPrefs>({ mode: 'system', url: '', username: '', bypass: [] });
+  const [proxyPassword, setProxyPassword] = useState('');
+  const [proxyHasPassword, setProxyHasPassword] = useState(false);
+  const [packConflicts, setPackConflicts] = useState<PackConflict[]>([]);
+  const [builtinPacks, setBuiltinPacks] = useState<PackInfo[]>([]);
+  const [spendLedger, setSpendLedger] = useState<'off' | 'csv' | 'jsonl'>('off');
+  const [ledgerPath, setLedgerPath] = useState<string | null>(null);
   const [aiProvider, setAiProvider] = useState<'openrouter' | 'megallm'>('openrouter');
   const [sttProvider, setSttProvider] = useState<'deepgram' | 'elevenlabs'>('deepgram');
   const [echoCancellation, setEchoCancellation] = useState(true);
   const [noiseSuppression, setNoiseSuppression] = useState(true);
+  const [autoGain, setAutoGain] = useState(false);
+  const [rnnoise, setRnnoise] = useState(false);
+  const [audioDevices, setAudioDevices] = useState<AudioDevice[]>([]);
+  const [audioDevice, setAudioDevice] = useState('');
+  const deviceInUse = audioDevices.find(d => d.selected);
+  const [historyRetentionDays, setHistoryRetentionDays] = useState(0);
+  const [lowCreditUsd, setLowCreditUsd] = useState(5);
+  const [lowCreditPercent, setLowCreditPercent] = useState(10);
+  const [providerUsage, setProviderUsage] = useState<ProviderUsage[] | null>(null);
+  const [checkingUsage, setCheckingUsage] = useState(false);
+  const [costSummary, setCostSummary] = useState<CostSummary | null>(null);
+  const [maintenanceHealthPing, setMaintenanceHealthPing] = useState(false);
+  const [controlApi, setControlApi] = useState(false);
+  const [controlApiPort, setControlApiPort] = useState(47821);
+  const [controlApiToken, setControlApiToken] = useState('');
+  const [journalMode, setJournalMode] = useState<'off' | 'also' | 'only'>('off');
+  const [journalTarget, setJournalTarget] = useState('');
+  const [journalBlock, setJournalBlock] = useState('');
+  const [journalFrontMatter, setJournalFrontMatter] = useState('');
+  const [journalPreview, setJournalPreview] = useState<{ path: string | null; addition: string } | null>(null);
+  const [sttOptions, setSttOptions] = useState<SttOptions | null>(null);
+  const [maintenance, setMaintenance] = useState<MaintenanceStatus | null>(null);
+  const [lastAudio, setLastAudio] = useState<{ duration_ms: number; truncated: boolean } | null>(null);
+  const [retranscribing, setRetranscribing] = useState(false);
+  const [dropActive, setDropActive] = useState(false);
+  const [exportFormat, setExportFormat] = useState<'txt' | 'md' | 'json' | 'srt' | 'vtt'>('srt');
+  const [batchFolder, setBatchFolder] = useState('');
+  const [batch, setBatch] = useState<BatchProgress | null>(null);
+  const [translateTo, setTranslateTo] = useState('');
+  const [mixedLanguage, setMixedLanguage] = useState(false);
+  const [watchFolder, setWatchFolder] = useState('');
+  const [watchRefine, setWatchRefine] = useState(false);
+  const [learnCorrections, setLearnCorrections] = useState(false);
+  const [correctionSuggestions, setCorrectionSuggestions] = useState<CorrectionSuggestion[]>([]);
+  const [watchOutputFormat, setWatchOutputFormat] = useState<'txt' | 'md'>('txt');
+  const [transcriptionConcurrency, setTranscriptionConcurrency] = useState(2);
+  const [jobs, setJobs] = useState<TranscriptionJob[]>([]);
+  const [demoMode, setDemoMode] = useState(false);
+  const [preserveClipboard, setPreserveClipboard] = useState(true);
+  const [insertMode, setInsertMode] = useState<InsertMode>('paste');
+  const [humanMinMs, setHumanMinMs] = useState(40);
+  const [humanMaxMs, setHumanMaxMs] = useState(140);
+  const [hudIdleSecs, setHudIdleSecs] = useState(300);
+  const [hudPosition, setHudPosition] = useState<'bottom-center' | 'top-center' | 'bottom-right' | 'custom'>('bottom-center');
+  const [monitorPolicy, setMonitorPolicy] = useState('monitor-with-cursor');
+  const [monitors, setMonitors] = useState<MonitorInfo[]>([]);
+  const [terminalApps, setTerminalApps] = useState('');
+  const [redact, setRedact] = useState<string[]>([]);
+  const [regexRules, setRegexRules] = useState<RegexRule[]>([]);
+  const [typography, setTypography] = useState<Typography>(DEFAULT_TYPOGRAPHY);
+  const [pipelineSample, setPipelineSample] = useState('');
+  const [pipelineTrace, setPipelineTrace] = useState<PipelineTrace | null>(null);
+  const [scriptStatus, setScriptStatus] = useState<ScriptStatus | null>(null);
+  const [plugins, setPlugins] = useState<string[]>([]);
+  const [pluginList, setPluginList] = useState<PluginList | null>(null);
+  const [reviewPlayback, setReviewPlayback] = useState(false);
+  const [confirmPastes, setConfirmPastes] = useState(0);
+  const [confirmBeforePaste, setConfirmBeforePaste] = useState(false);
+  const [lockTargetWindow, setLockTargetWindow] = useState(true);
+  const [maxDurationSecs, setMaxDurationSecs] = useState(600);
+  const [logLevel, setLogLevel] = useState('info');
+  const [logsCopied, setLogsCopied] = useState(false);
+  const [keyInspections, setKeyInspections] = useState<KeyInspection[] | null>(null);
+  const [inspectingKeys, setInspectingKeys] = useState(false);
   const [hotkey, setHotkey] = useState('');
   const [keysPresent, setKeysPresent] = useState<KeysPresent>({ openrouter: false, deepgram: false, megallm: false, elevenlabs: false });
   const [openrouterKey, setOpenrouterKey] = useState('');
@@ -448,11 +620,14 @@ export function Settings() {
   const [elevenlabsKey, setElevenlabsKey] = useState('');
   const [model, setModel] = useState('openai/gpt-oss-20b:free');
   const [megallmModel, setMegallmModel] = useState('gpt-4');
-  const [lang, setLang] = useState('en-US');
+  // Each STT provider has its own language ("auto" lets it detect)
+  const [languages, setLanguages] = useState<Record<'deepgram' | 'elevenlabs', string>>({ deepgram: 'en', elevenlabs: 'auto' });
   const [testing, setTesting] = useState<'dg'|'or'|'el'|null>(null);
   const [testingMega, setTestingMega] = useState<boolean>(false);
   const [toast, setToast] = useState<{text:string, kind:'ok'|'err'}|null>(null);
   const [valid, setValid] = useState<{or:boolean, dg:boolean, mg:boolean, el:boolean}>({or:false, dg:false, mg:false, el:false});
+  // Results of the live check run when keys are saved, by provider
+  const [keyChecks, setKeyChecks] = useState<Partial<Record<KeyCheck['provider'], KeyCheck>>>({});
   const [modelList, setModelList] = useState<string[]>([]);
   const [modelMenuOpen, setModelMenuOpen] = useState(false);
   const [modelLoading, setModelLoading] = useState(false);
@@ -473,13 +648,7 @@ export function Settings() {
   useEffect(() => {
     console.log('🔄 Loading settings from backend...');
 
-    invoke<[boolean, boolean, boolean, boolean]>('get_keys_secure')
-      .then(([okOr, okDg, okMg, okEl]) => {
-        console.log('Keys present - OpenRouter:', okOr, 'Deepgram:', okDg, 'MegaLLM:', okMg, 'ElevenLabs:', okEl);
-        setKeysPresent({ openrouter: okOr, deepgram: okDg, megallm: okMg, elevenlabs: okEl });
-      })
-      .catch((e) => console.error('Failed to get keys:', e));
-
+    // Hotkey first: registering it is what makes the app usable after launch
     invoke<string>('get_hotkey')
       .then((combo) => {
         log('📌 Retrieved hotkey from backend:', combo);
@@ -503,6 +672,13 @@ export function Settings() {
         console.error('Failed to get hotkey:', e);
       });
 
+    invoke<[boolean, boolean, boolean, boolean]>('get_keys_secure')
+      .then(([okOr, okDg, okMg, okEl]) => {
+        console.log('Keys present - OpenRouter:', okOr, 'Deepgram:', okDg, 'MegaLLM:', okMg, 'ElevenLabs:', okEl);
+        setKeysPresent({ openrouter: okOr, deepgram: okDg, megallm: okMg, elevenlabs: okEl });
+      })
+      .catch((e) => console.error('Failed to get keys:', e));
+
     invoke<any>('get_behavior')
       .then((b: any) => {
         log('✅ get_behavior returned:', b);
@@ -510,6 +686,19 @@ export function Settings() {
         setStreamInsert(!!b?.stream_insert);
         setAutostart(!!b?.autostart);
         setAiRefine(b?.ai_refine !== false); // Default to true if not set
+        setSpokenPunctuation(b?.spoken_punctuation !== false);
+        setEmoji(!!b?.emoji);
+        setSoundStart(!!b?.sound_start);
+        setSoundStop(!!b?.sound_stop);
+        setSoundError(!!b?.sound_error);
+        setSoundVolume(typeof b?.sound_volume === 'number' ? b.sound_volume : 60);
+        setNotifyErrors(!!b?.notify_errors);
+        if (b?.numbers_mode === 'digits' || b?.numbers_mode === 'auto') setNumbersMode(b.numbers_mode);
+        if (typeof b?.ai_timeout_secs === 'number') setAiTimeoutSecs(b.ai_timeout_secs);
+        if (typeof b?.ai_max_tokens === 'number') setAiMaxTokens(b.ai_max_tokens);
+        if (typeof b?.ai_min_similarity === 'number') setAiMinSimilarity(b.ai_min_similarity);
+        if (b?.spend_ledger === 'csv' || b?.spend_ledger === 'jsonl') setSpendLedger(b.spend_ledger);
+        invoke<string | null>('spend_ledger_path').then(setLedgerPath).catch(() => {});
         if (b?.ai_provider === 'megallm' || b?.ai_provider === 'openrouter') {
           setAiProvider(b.ai_provider);
         }
@@ -518,6 +707,42 @@ export function Settings() {
         }
         if (typeof b?.echo_cancellation === 'boolean') setEchoCancellation(b.echo_cancellation);
         if (typeof b?.noise_suppression === 'boolean') setNoiseSuppression(b.noise_suppression);
+        setTranslateTo(b?.translate_to || '');
+        setMixedLanguage(!!b?.mixed_language);
+        setAutoGain(!!b?.auto_gain);
+        setRnnoise(!!b?.rnnoise);
+        if (typeof b?.silence_secs === 'number') setSilenceSecs(b.silence_secs);
+        if (typeof b?.history_retention_days === 'number') setHistoryRetentionDays(b.history_retention_days);
+        if (typeof b?.low_credit_usd === 'number') setLowCreditUsd(b.low_credit_usd);
+        if (typeof b?.low_credit_percent === 'number') setLowCreditPercent(b.low_credit_percent);
+        setMaintenanceHealthPing(!!b?.maintenance_health_ping);
+        setControlApi(!!b?.control_api);
+        if (b?.journal_mode === 'also' || b?.journal_mode === 'only') setJournalMode(b.journal_mode);
+        if (typeof b?.control_api_port === 'number') setControlApiPort(b.control_api_port);
+        setWatchFolder(b?.watch_folder || '');
+        setWatchRefine(!!b?.watch_refine);
+        setLearnCorrections(!!b?.learn_corrections);
+        if (b?.watch_output_format === 'md') setWatchOutputFormat('md');
+        if (typeof b?.transcription_concurrency === 'number') setTranscriptionConcurrency(b.transcription_concurrency);
+        setDemoMode(!!b?.demo_mode);
+        setPreserveClipboard(b?.preserve_clipboard !== false);
+        if (INSERT_MODES.includes(b?.insert_mode)) setInsertMode(b.insert_mode);
+        if (typeof b?.human_min_ms === 'number') setHumanMinMs(b.human_min_ms);
+        if (typeof b?.human_max_ms === 'number') setHumanMaxMs(b.human_max_ms);
+        if (typeof b?.hud_idle_unload_secs === 'number') setHudIdleSecs(b.hud_idle_unload_secs);
+        if (b?.hud_position) setHudPosition(b.hud_position);
+        if (b?.monitor_policy) setMonitorPolicy(b.monitor_policy);
+        setTerminalApps((b?.terminal_apps || []).join(', '));
+        setRedact(b?.redact || []);
+        setRegexRules(b?.regex_rules || []);
+        setTypography({ ...DEFAULT_TYPOGRAPHY, ...(b?.typography || {}) });
+        setPlugins(b?.plugins || []);
+        setReviewPlayback(!!b?.review_playback);
+        if (typeof b?.confirm_first_pastes === 'number') setConfirmPastes(b.confirm_first_pastes);
+        if (typeof b?.confirm_before_paste === 'boolean') setConfirmBeforePaste(b.confirm_before_paste);
+        if (typeof b?.lock_target_window === 'boolean') setLockTargetWindow(b.lock_target_window);
+        if (typeof b?.max_duration_secs === 'number') setMaxDurationSecs(b.max_duration_secs);
+        if (typeof b?.log_level === 'string') setLogLevel(b.log_level);
       })
       .catch((e) => logError('Failed to get behavior:', e));
 
@@ -535,12 +760,11 @@ export function Settings() {
       })
       .catch((e) => console.error('Failed to get megallm model:', e));
 
-    invoke<string>('get_language')
-      .then(l => {
-        console.log('Language:', l);
-        setLang(l);
-      })
-      .catch((e) => console.error('Failed to get language:', e));
+    for (const provider of ['deepgram', 'elevenlabs'] as const) {
+      invoke<string>('get_language', { provider })
+        .then(l => setLanguages(prev => ({ ...prev, [provider]: l })))
+        .catch((e) => console.error('Failed to get language:', e));
+    }
   }, []);
 
   // Load echo cancellation / noise suppression flags so Behavior toggles reflect stored prefs
@@ -613,11 +837,37 @@ export function Settings() {
 async function saveKeys() {
     const { orOk, dgOk, mgOk, elOk } = validateKeys(openrouterKey, deepgramKey, megallmKey, elevenlabsKey);
     if (!(orOk && dgOk && mgOk && elOk)) { setToast({ text: 'Invalid keys', kind: 'err' }); return; }
-    await invoke('save_keys_secure', { openrouter: openrouterKey || '', deepgram: deepgramKey || '', megallm: megallmKey || '', elevenlabs: elevenlabsKey || '' });
+    let checks: KeyCheck[];
+    try {
+      checks = await invoke<KeyCheck[]>('save_keys_secure', { openrouter: openrouterKey || '', deepgram: deepgramKey || '', megallm: megallmKey || '', elevenlabs: elevenlabsKey || '' });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' }); setTimeout(()=> setToast(null), 3000);
+      return;
+    }
+    setKeyChecks(Object.fromEntries(checks.map(c => [c.provider, c])));
     const [okOr, okDg, okMg, okEl] = await invoke<[boolean, boolean, boolean, boolean]>('get_keys_secure');
     setKeysPresent({ openrouter: okOr, deepgram: okDg, megallm: okMg, elevenlabs: okEl });
-    setOpenrouterKey(''); setDeepgramKey(''); setMegallmKey(''); setElevenlabsKey('');
-    setToast({ text: 'Saved successfully', kind: 'ok' }); setTimeout(()=> setToast(null), 2000);
+    // Refused keys stay in their fields to be fixed
+    const saved = (provider: KeyCheck['provider']) => checks.some(c => c.provider === provider && c.saved);
+    if (saved('openrouter')) setOpenrouterKey('');
+    if (saved('deepgram')) setDeepgramKey('');
+    if (saved('megallm')) setMegallmKey('');
+    if (saved('elevenlabs')) setElevenlabsKey('');
+    const refused = checks.filter(c => !c.saved).length;
+    setToast(refused ? { text: `${refused} key${refused > 1 ? 's' : ''} refused, not saved`, kind: 'err' } : { text: 'Saved successfully', kind: 'ok' });
+    setTimeout(()=> setToast(null), 3000);
+  }
+
+  // What to do about a key the save check didn't accept
+  function keyCheckMessage(provider: KeyCheck['provider'], name: string) {
+    const check = keyChecks[provider];
+    if (!check || check.status === 'ok') return null;
+    const text = check.status === 'unauthorized'
+      ? `${name} refused this key, so it was not saved. Check it was copied whole and hasn't been revoked`
+      : check.status === 'network_error'
+        ? `Saved, but ${name} couldn't be reached to verify it. Check your connection or proxy`
+        : `Saved, but ${name} answered with an error: ${check.detail ?? 'unknown'}`;
+    return <div className={`text-xs mt-1 ${check.saved ? 'text-yellow-400' : 'text-red-400'}`}>{text}</div>;
   }
 
   async function testDeepgram() {
@@ -631,15 +881,10 @@ async function saveKeys() {
         setTimeout(() => setToast(null), 2000);
         return;
       }
-      // Browser test via WS; Deepgram accepts token in subprotocol
-      await new Promise<void>((resolve, reject) => {
-        const ws = new WebSocket('wss://api.deepgram.com/v1/listen', ['token', keyToTest]);
-        const to = setTimeout(()=> { try { ws.close(); } catch {} reject(new Error('timeout')); }, 2500);
-        ws.onopen = () => { clearTimeout(to); try { ws.close(); } catch {}; resolve(); };
-        ws.onerror = () => { clearTimeout(to); reject(new Error('error')); };
-      });
+      // Checked by the backend against the endpoint being edited (self-hosted or the hosted API)
+      await invoke('test_deepgram', { apiKey: keyToTest, endpoint: sttOptions?.stt_endpoint_override ?? null });
       setToast({ text: 'Deepgram key valid ✅', kind: 'ok' });
-    } catch { setToast({ text: 'Deepgram key invalid ❌', kind: 'err' }); }
+    } catch (e) { setToast({ text: `Deepgram key invalid ❌ ${String(e)}`, kind: 'err' }); }
     finally { setTesting(null); setTimeout(()=> setToast(null), 2000); }
   }
   async function testOpenRouter() {
@@ -722,8 +967,9 @@ async function saveKeys() {
         setTimeout(() => setToast(null), 2000);
         return;
       }
-      await invoke('test_elevenlabs', { api_key: keyToTest });
-      setToast({ text: 'ElevenLabs key valid ✅', kind: 'ok' });
+      const check = await invoke<{ model: string; language: string | null; max_concurrent_requests: number | null; current_concurrent_requests: number | null; token_ttl_secs: number; max_batch_secs: number }>('test_elevenlabs', { api_key: keyToTest });
+      const concurrency = check.max_concurrent_requests !== null ? `, ${check.current_concurrent_requests ?? 0}/${check.max_concurrent_requests} concurrent` : '';
+      setToast({ text: `ElevenLabs key valid ✅ (${check.model}${check.language ? ', ' + check.language : ''}${concurrency}; tokens last ${Math.round(check.token_ttl_secs / 60)} min, batch up to ${Math.round(check.max_batch_secs / 60)} min)`, kind: 'ok' });
     } catch (e) {
       const msg = e instanceof Error ? e.message : String(e);
       setToast({ text: `ElevenLabs test failed: ${msg}`, kind: 'err' });
@@ -740,12 +986,35 @@ async function persistBehavior() {
       const payload: any = {
         auto_paste: autoPaste,
         autoPaste,
-        silence_secs: 2,
-        silenceSecs: 2,
+        silence_secs: silenceSecs,
+        silenceSecs,
         stream_insert: streamInsert,
         streamInsert,
         ai_refine: aiRefine,
         aiRefine,
+        spoken_punctuation: spokenPunctuation,
+        spokenPunctuation,
+        emoji,
+        sound_start: soundStart,
+        soundStart,
+        sound_stop: soundStop,
+        soundStop,
+        sound_error: soundError,
+        soundError,
+        sound_volume: soundVolume,
+        soundVolume,
+        notify_errors: notifyErrors,
+        notifyErrors,
+        numbers_mode: numbersMode,
+        numbersMode,
+        ai_timeout_secs: aiTimeoutSecs,
+        aiTimeoutSecs,
+        ai_max_tokens: aiMaxTokens,
+        aiMaxTokens,
+        ai_min_similarity: aiMinSimilarity,
+        aiMinSimilarity,
+        spend_ledger: spendLedger,
+        spendLedger,
         ai_provider: aiProvider,
         aiProvider,
         stt_provider: sttProvider,
@@ -754,20 +1023,140 @@ async function persistBehavior() {
         echoCancellation,
         noise_suppression: noiseSuppression,
         noiseSuppression,
+        auto_gain: autoGain,
+        autoGain,
+        rnnoise,
+        history_retention_days: historyRetentionDays,
+        historyRetentionDays,
+        low_credit_usd: lowCreditUsd,
+        lowCreditUsd,
+        low_credit_percent: lowCreditPercent,
+        lowCreditPercent,
+        maintenance_health_ping: maintenanceHealthPing,
+        maintenanceHealthPing,
+        control_api: controlApi,
+        controlApi,
+        control_api_port: controlApiPort,
+        controlApiPort,
+        journal_mode: journalMode,
+        journalMode,
+        translate_to: translateTo.trim() || null,
+        mixed_language: mixedLanguage,
+        mixedLanguage,
+        watch_folder: watchFolder.trim() || null,
+        watch_refine: watchRefine,
+        watchRefine,
+        learn_corrections: learnCorrections,
+        learnCorrections,
+        watch_output_format: watchOutputFormat,
+        watchOutputFormat,
+        transcription_concurrency: transcriptionConcurrency,
+        transcriptionConcurrency,
+        demo_mode: demoMode,
+        demoMode,
+        preserve_clipboard: preserveClipboard,
+        preserveClipboard,
+        insert_mode: insertMode,
+        insertMode,
+        human_min_ms: humanMinMs,
+        humanMinMs,
+        human_max_ms: humanMaxMs,
+        humanMaxMs,
+        hud_idle_unload_secs: hudIdleSecs,
+        hudIdleUnloadSecs: hudIdleSecs,
+        hud_position: hudPosition,
+        hudPosition,
+        monitor_policy: monitorPolicy,
+        monitorPolicy,
+        terminal_apps: terminalApps.split(',').map(s => s.trim()).filter(Boolean),
+        redact,
+        // A rule added but never filled in isn't an error, just nothing to save
+        regex_rules: regexRules.filter(r => r.find !== ''),
+        regexRules: regexRules.filter(r => r.find !== ''),
+        typography,
+        plugins,
+        review_playback: reviewPlayback,
+        reviewPlayback,
+        confirm_first_pastes: confirmPastes,
+        confirmFirstPastes: confirmPastes,
+        confirm_before_paste: confirmBeforePaste,
+        confirmBeforePaste,
+        lock_target_window: lockTargetWindow,
+        lockTargetWindow,
+        max_duration_secs: maxDurationSecs,
+        maxDurationSecs,
+        log_level: logLevel,
+        logLevel,
       };
       log('➡️ set_behavior payload:', payload);
       // Persist behavior and get the saved struct back
       // Backend expects a single parameter named `args` (serde_json::Value)
       const saved = await invoke<any>('set_behavior', { args: payload });
       log('✅ set_behavior returned:', saved);
+      const lines = (text: string) => text.split('\n').map(l => l.trim()).filter(Boolean);
+      const refusals = await invoke<{ added: string[]; removed: string[] }>('set_refusal_patterns', {
+        added: lines(refusalAdded),
+        removed: lines(refusalRemoved),
+      });
+      setRefusalAdded(refusals.added.join('\n'));
+      setRefusalRemoved(refusals.removed.join('\n'));
       // Reflect saved values from backend immediately
       setAutoPaste(!!saved?.auto_paste);
       setStreamInsert(!!saved?.stream_insert);
       setAiRefine(saved?.ai_refine !== false);
+      setSpokenPunctuation(saved?.spoken_punctuation !== false);
+      setEmoji(!!saved?.emoji);
+      setSoundStart(!!saved?.sound_start);
+      setSoundStop(!!saved?.sound_stop);
+      setSoundError(!!saved?.sound_error);
+      setSoundVolume(typeof saved?.sound_volume === 'number' ? saved.sound_volume : 60);
+      setNotifyErrors(!!saved?.notify_errors);
+      setNumbersMode(saved?.numbers_mode === 'digits' || saved?.numbers_mode === 'auto' ? saved.numbers_mode : 'words');
+      if (typeof saved?.ai_timeout_secs === 'number') setAiTimeoutSecs(saved.ai_timeout_secs);
+      if (typeof saved?.ai_max_tokens === 'number') setAiMaxTokens(saved.ai_max_tokens);
+      if (typeof saved?.ai_min_similarity === 'number') setAiMinSimilarity(saved.ai_min_similarity);
+      if (typeof saved?.spend_ledger === 'string') setSpendLedger(saved.spend_ledger);
+      invoke<string | null>('spend_ledger_path').then(setLedgerPath).catch(() => setLedgerPath(null));
       if (saved?.ai_provider) setAiProvider(saved.ai_provider);
       if (saved?.stt_provider) setSttProvider(saved.stt_provider);
       if (typeof saved?.echo_cancellation === 'boolean') setEchoCancellation(saved.echo_cancellation);
       if (typeof saved?.noise_suppression === 'boolean') setNoiseSuppression(saved.noise_suppression);
+      setTranslateTo(saved?.translate_to || '');
+      setMixedLanguage(!!saved?.mixed_language);
+      setAutoGain(!!saved?.auto_gain);
+      setRnnoise(!!saved?.rnnoise);
+      if (typeof saved?.silence_secs === 'number') setSilenceSecs(saved.silence_secs);
+      if (typeof saved?.history_retention_days === 'number') setHistoryRetentionDays(saved.history_retention_days);
+      if (typeof saved?.low_credit_usd === 'number') setLowCreditUsd(saved.low_credit_usd);
+      if (typeof saved?.low_credit_percent === 'number') setLowCreditPercent(saved.low_credit_percent);
+      setMaintenanceHealthPing(!!saved?.maintenance_health_ping);
+      setControlApi(!!saved?.control_api);
+      setJournalMode(saved?.journal_mode === 'also' || saved?.journal_mode === 'only' ? saved.journal_mode : 'off');
+      if (typeof saved?.control_api_port === 'number') setControlApiPort(saved.control_api_port);
+      setWatchFolder(saved?.watch_folder || '');
+      setWatchRefine(!!saved?.watch_refine);
+      setLearnCorrections(!!saved?.learn_corrections);
+      if (saved?.watch_output_format) setWatchOutputFormat(saved.watch_output_format);
+      if (typeof saved?.transcription_concurrency === 'number') setTranscriptionConcurrency(saved.transcription_concurrency);
+      setDemoMode(!!saved?.demo_mode);
+      setPreserveClipboard(saved?.preserve_clipboard !== false);
+      if (INSERT_MODES.includes(saved?.insert_mode)) setInsertMode(saved.insert_mode);
+      if (typeof saved?.human_min_ms === 'number') setHumanMinMs(saved.human_min_ms);
+      if (typeof saved?.human_max_ms === 'number') setHumanMaxMs(saved.human_max_ms);
+      if (typeof saved?.hud_idle_unload_secs === 'number') setHudIdleSecs(saved.hud_idle_unload_secs);
+      setHudPosition(saved?.hud_position || 'bottom-center');
+      setMonitorPolicy(saved?.monitor_policy || 'monitor-with-cursor');
+      setTerminalApps((saved?.terminal_apps || []).join(', '));
+      setRedact(saved?.redact || []);
+      setRegexRules(saved?.regex_rules || []);
+      setTypography({ ...DEFAULT_TYPOGRAPHY, ...(saved?.typography || {}) });
+      setPlugins(saved?.plugins || []);
+      setReviewPlayback(!!saved?.review_playback);
+      if (typeof saved?.confirm_first_pastes === 'number') setConfirmPastes(saved.confirm_first_pastes);
+      if (typeof saved?.confirm_before_paste === 'boolean') setConfirmBeforePaste(saved.confirm_before_paste);
+      if (typeof saved?.lock_target_window === 'boolean') setLockTargetWindow(saved.lock_target_window);
+      if (typeof saved?.max_duration_secs === 'number') setMaxDurationSecs(saved.max_duration_secs);
+      if (typeof saved?.log_level === 'string') setLogLevel(saved.log_level);
 
       // Autostart is persisted via separate command and also controlled by OS
       let autostartOk = true;
@@ -796,201 +1185,1433 @@ async function persistBehavior() {
     }
   }
 
-  async function persistHotkey() {
-    if (!hotkey || hotkey.trim() === '') {
-      setToast({ text: 'Invalid hotkey', kind: 'err' });
+  async function copyRecentLogs() {
+    try {
+      const text = await invoke<string>('get_recent_logs', { lines: 500 });
+      await navigator.clipboard.writeText(text);
+      setLogsCopied(true);
+      setTimeout(() => setLogsCopied(false), 1500);
+    } catch (e) {
+      logError('Failed to copy logs:', e);
+      setToast({ text: 'No logs yet', kind: 'err' });
       setTimeout(() => setToast(null), 2000);
-      return;
     }
-    await invoke('set_hotkey', { combo: hotkey });
-    const success = await initGlobalHotkey(hotkey);
-    if (success) {
-      console.log('Hotkey saved and registered:', hotkey);
-      setToast({ text: 'Hotkey saved', kind: 'ok' });
-    } else {
-      setToast({ text: 'Hotkey registration failed', kind: 'err' });
+  }
+
+  async function exportDiagnostics() {
+    try {
+      const path = await invoke<string>('export_diagnostics');
+      log('📦 Diagnostics saved to', path);
+      setToast({ text: `Saved ${path}`, kind: 'ok' });
+    } catch (e) {
+      logError('Diagnostics export failed:', e);
+      setToast({ text: 'Diagnostics export failed', kind: 'err' });
     }
-    setTimeout(() => setToast(null), 1500);
+    setTimeout(() => setToast(null), 4000);
   }
 
-  const [savingProviderPrefs, setSavingProviderPrefs] = useState(false);
+  async function saveLastAudio() {
+    try {
+      const path = await invoke<string>('save_last_audio', {});
+      log('💾 Last recording saved to', path);
+      setToast({ text: `Saved ${path}`, kind: 'ok' });
+    } catch (e) {
+      logError('Saving last recording failed:', e);
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 4000);
+  }
 
-  async function persistProviderPrefs() {
-    setSavingProviderPrefs(true);
+  async function transcribeDroppedFile(path: string) {
+    const name = path.split(/[\\/]/).pop() || path;
+    log('🎧 Transcribing dropped file', path);
+    setToast({ text: `Transcribing ${name}…`, kind: 'ok' });
     try {
-      await invoke('set_model', { name: model });
-      await invoke('set_megallm_model', { name: megallmModel });
-      await invoke('set_language', { code: lang });
-      setToast({ text: 'Provider prefs saved', kind: 'ok' });
-      setTimeout(() => setToast(null), 2000);
+      const text = await invoke<string>('transcribe_file', { path });
+      await navigator.clipboard.writeText(text);
+      setToast({ text: `${name}: transcript copied and added to history`, kind: 'ok' });
     } catch (e) {
-      console.error('Failed to save provider prefs', e);
-      setToast({ text: 'Failed to save', kind: 'err' });
-      setTimeout(() => setToast(null), 2000);
+      logError('File transcription failed:', e);
+      setToast({ text: `${name}: ${String(e)}`, kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 4000);
+  }
+
+  async function transcribeFolder(path: string) {
+    log('📂 Transcribing folder', path);
+    try {
+      setBatch(await invoke<BatchProgress>('transcribe_folder', { path }));
+      setToast({ text: 'Transcribing the folder; transcripts are saved next to the files', kind: 'ok' });
+    } catch (e) {
+      logError('Folder transcription failed:', e);
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 4000);
+  }
+
+  async function exportLastTranscript() {
+    try {
+      const [last] = await invoke<{ id: number }[]>('list_history', { limit: 1 });
+      if (!last) throw new Error('No dictation in history yet');
+      const path = await invoke<string>('export_transcript', { id: last.id, format: exportFormat });
+      log('📝 Transcript exported to', path);
+      setToast({ text: `Saved ${path}`, kind: 'ok' });
+    } catch (e) {
+      logError('Transcript export failed:', e);
+      setToast({ text: e instanceof Error ? e.message : String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 4000);
+  }
+
+  async function retranscribeLastAudio() {
+    setRetranscribing(true);
+    try {
+      const text = await invoke<string>('retranscribe_last_audio', {});
+      await navigator.clipboard.writeText(text);
+      setToast({ text: 'Transcript copied and added to history', kind: 'ok' });
+    } catch (e) {
+      logError('Re-transcribing last recording failed:', e);
+      setToast({ text: String(e), kind: 'err' });
     } finally {
-      setSavingProviderPrefs(false);
+      setRetranscribing(false);
+      setTimeout(() => setToast(null), 4000);
     }
   }
 
-  return (
-    <div className="h-screen flex flex-col bg-[var(--bg)] text-[var(--fg)] font-geistmono">
-      <TitleBar version={version}/>
-      <div className="flex-1 overflow-y-auto mt-10">
-        <div className="w-full max-w-6xl mx-auto">
-          <main className="p-6">
-            <div className="flex flex-col md:flex-row items-start gap-4">
-              <div className="flex-1 flex flex-col gap-4 min-w-0">
-              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
-                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Keys</h2>
-                <div className="space-y-3">
-            <div>
-              <label htmlFor="hotkey" className="block text-xs text-muted mb-2">Global hotkey</label>
-              <KeyRecorder
-                value={hotkey}
-                onChange={setHotkey}
-                onSave={persistHotkey}
-              />
-            </div>
-            <div className="flex items-center justify-between">
-              <div>
-                <div className="text-sm">Autostart</div>
-                <div className="text-xs text-muted">Launch on login</div>
-              </div>
-              <Switch ariaLabel="Autostart" checked={autostart} onCheckedChange={(v)=>{ setAutostart(v); setToast({text: v?'Autostart on':'Autostart off', kind:'ok'}); setTimeout(()=> setToast(null),1200); }} />
-            </div>
-          </div>
-              </section>
+  useEffect(() => { invoke<MonitorInfo[]>('list_monitors').then(setMonitors).catch(() => {}); }, []);
+  useEffect(() => { invoke<string[]>('list_dictionary').then(setDictionary).catch(() => {}); }, []);
+  useEffect(() => { invoke<{ duration_ms: number; truncated: boolean } | null>('last_audio_info').then(setLastAudio).catch(() => {}); }, []);
+  // Dropping an audio file on this window transcribes it (and refines it, when AI refinement is on);
+  // a dropped folder is queued as a batch
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    getCurrentWebviewWindow().onDragDropEvent(async (event) => {
+      const p = event.payload;
+      if (p.type === 'enter' || p.type === 'over') { setDropActive(true); return; }
+      setDropActive(false);
+      if (p.type !== 'drop' || p.paths.length === 0) return;
+      for (const path of p.paths) {
+        // Folder names can have dots and files can lack extensions, so ask the backend
+        const folder = await invoke<boolean>('is_folder', { path }).catch(() => false);
+        if (folder) await transcribeFolder(path);
+        else await transcribeDroppedFile(path);
+      }
+    }).then(u => { unlisten = u; }).catch(e => logError('Drag and drop unavailable:', e));
+    return () => unlisten?.();
+  }, []);
+  useEffect(() => { invoke<MaintenanceStatus>('get_maintenance_status').then(setMaintenance).catch(() => {}); }, []);
+  // The HUD reports devices as they come and go; refresh while Settings is open
+  useEffect(() => {
+    const refresh = () => invoke<AudioDevice[]>('list_audio_devices').then(setAudioDevices).catch(() => {});
+    refresh();
+    invoke<string | null>('get_audio_device').then(id => setAudioDevice(id ?? '')).catch(() => {});
+    const t = window.setInterval(refresh, 5000);
+    return () => clearInterval(t);
+  }, []);
 
-              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
-                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Behavior</h2>
-          <div className="space-y-4">
-            <div className="flex items-center justify-between">
-              <div>
-                <div className="text-sm">Auto-paste into focused field</div>
-                <div className="text-xs text-muted">Falls back to manual paste if blocked</div>
-              </div>
-              <Switch checked={autoPaste} onCheckedChange={(v)=>{ log('🟢 Toggle autoPaste ->', v); setAutoPaste(v); }} />
-            </div>
-            <div className="flex items-center justify-between">
-              <div>
-                <div className="text-sm">AI refinement</div>
-                <div className="text-xs text-muted">Use the selected AI provider to refine dictation text</div>
-              </div>
-              <Switch checked={aiRefine} onCheckedChange={(v)=>{ log('🟢 Toggle aiRefine ->', v); setAiRefine(v); }} />
-            </div>
-            <motion.button
-              onClick={persistBehavior}
-              whileHover={{ scale: 1.02 }}
-              whileTap={{ scale: 0.96 }}
-              className="px-3 py-2 bg-accent text-black rounded hover:brightness-110 active:brightness-90 transition-all duration-150 shadow-sm hover:shadow-md"
-            >
-              Save Behavior
-            </motion.button>
-          </div>
-              </section>
+  async function chooseAudioDevice(id: string) {
+    log('🎙️ Audio device ->', id || 'default');
+    try {
+      await invoke('set_audio_device', { id: id || null });
+      setAudioDevice(id);
+      setAudioDevices(await invoke<AudioDevice[]>('list_audio_devices'));
+    } catch (e) {
+      logError('Setting audio device failed:', e);
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
 
-              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
-                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Audio behavior</h2>
-          <div className="space-y-4">
-            <div className="flex items-center justify-between">
-              <div>
-                <div className="text-sm">Echo cancellation</div>
-                <div className="text-xs text-muted">Enable echo control when available</div>
-              </div>
-              <Switch checked={echoCancellation} onCheckedChange={(v)=>{ log('🔊 Toggle echoCancellation ->', v); setEchoCancellation(v); }} />
-            </div>
-            <div className="flex items-center justify-between">
-              <div>
-                <div className="text-sm">Noise suppression</div>
-                <div className="text-xs text-muted">Enable noise filtering when available</div>
-              </div>
-              <Switch checked={noiseSuppression} onCheckedChange={(v)=>{ log('🔊 Toggle noiseSuppression ->', v); setNoiseSuppression(v); }} />
-            </div>
-          </div>
-              </section>
+  async function chooseDeviceNoise(id: string, mode: string) {
+    log('🔇 Noise suppression for', id, '->', mode || 'global');
+    try {
+      await invoke('set_device_noise_mode', { id, mode: mode || null });
+      setAudioDevices(await invoke<AudioDevice[]>('list_audio_devices'));
+    } catch (e) {
+      logError('Setting device noise suppression failed:', e);
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
 
-              <UpdateSection onToast={(text: string, kind: 'ok' | 'err') => { setToast({ text, kind }); setTimeout(() => setToast(null), 3000); }} />
-              </div>
+  async function runMaintenance() {
+    setMaintenance(m => m && { ...m, running: true });
+    try {
+      const report = await invoke<MaintenanceReport>('run_maintenance');
+      log('🧹 Maintenance report', report);
+      setToast({ text: report.errors.length ? `Maintenance finished with ${report.errors.length} problem(s)` : 'Maintenance finished', kind: report.errors.length ? 'err' : 'ok' });
+    } catch (e) {
+      logError('Maintenance failed:', e);
+      setToast({ text: String(e), kind: 'err' });
+    }
+    invoke<MaintenanceStatus>('get_maintenance_status').then(setMaintenance).catch(() => {});
+    setTimeout(() => setToast(null), 3000);
+  }
 
-              <div className="flex-1 min-w-0">
-                                                                      <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
-                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Providers</h2>
-          <div className="space-y-4">
-            <div className="grid md:grid-cols-2 gap-3">
-              <div>
-                <label className="block text-xs text-muted">AI provider</label>
-                <div className="relative">
-                  <button
-                    type="button"
-                    onClick={()=> setAiMenuOpen(o=>!o)}
-                    className="w-full px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-left flex items-center justify-between"
-                  >
-                    <span className="text-sm">{aiProvider === 'openrouter' ? 'OpenRouter' : 'MegaLLM'}</span>
-                    <ChevronDown size={14} className="text-muted" />
-                  </button>
-                  <AnimatePresence>
-                    {aiMenuOpen && (
-                      <motion.div
-                        initial={{ opacity: 0, y: 4 }}
-                        animate={{ opacity: 1, y: 0 }}
-                        exit={{ opacity: 0, y: 4 }}
-                        transition={{ duration: 0.12 }}
-                        className="absolute left-0 right-0 mt-1 rounded-lg border border-neutral-800 bg-neutral-900 shadow-xl z-30 overflow-hidden"
-                      >
-                        {['openrouter','megallm'].map(opt => (
-                          <button
-                            key={opt}
-                            type="button"
-                            className={`w-full text-left px-3 py-2 text-sm hover:bg-neutral-800 ${aiProvider===opt?'text-accent':'text-[var(--fg)]'}`}
-                            onMouseDown={() => { switchProvider(opt as any); setAiMenuOpen(false); }}
-                          >
-                            {opt === 'openrouter' ? 'OpenRouter' : 'MegaLLM'}
-                          </button>
-                        ))}
-                      </motion.div>
-                    )}
-                  </AnimatePresence>
-                </div>
-              </div>
-              <div>
-                <label className="block text-xs text-muted">STT provider</label>
-                <div className="relative">
-                <button
-                  type="button"
-                  onClick={()=> setSttMenuOpen(o=>!o)}
-                  className="w-full px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-left flex items-center justify-between"
-                >
-                    <span className="text-sm">{sttProvider === 'elevenlabs' ? 'ElevenLabs' : 'Deepgram'}</span>
-                    <ChevronDown size={14} className="text-muted" />
-                  </button>
-                  <AnimatePresence>
-                    {sttMenuOpen && (
-                      <motion.div
-                        initial={{ opacity: 0, y: 4 }}
-                        animate={{ opacity: 1, y: 0 }}
-                        exit={{ opacity: 0, y: 4 }}
-                        transition={{ duration: 0.12 }}
-                        className="absolute left-0 right-0 mt-1 rounded-lg border border-neutral-800 bg-neutral-900 shadow-xl z-30 overflow-hidden"
-                      >
-                        {['deepgram','elevenlabs'].map(opt => (
-                          <button
-                            key={opt}
-                            type="button"
-                            className={`w-full text-left px-3 py-2 text-sm hover:bg-neutral-800 ${sttProvider===opt?'text-accent':'text-[var(--fg)]'}`}
-                            onMouseDown={() => { switchSttProvider(opt as any); setSttMenuOpen(false); }}
-                          >
-                            {opt === 'deepgram' ? 'Deepgram' : 'ElevenLabs'}
-                          </button>
-                        ))}
-                      </motion.div>
-                    )}
-                  </AnimatePresence>
-                </div>
-              </div>
-            </div>
+  useEffect(() => {
+    invoke<string | null>('get_journal_target').then(t => setJournalTarget(t || '')).catch(() => {});
+    invoke<{ block: string | null; front_matter: string | null }>('get_journal_template').then(t => {
+      setJournalBlock(t.block || '');
+      setJournalFrontMatter(t.front_matter || '');
+    }).catch(() => {});
+  }, []);
 
-            {aiProvider === 'openrouter' ? (
-              <div className="space-y-1">
+  useEffect(() => {
+    invoke<SttOptions>('get_stt_options').then(setSttOptions).catch(() => {});
+  }, []);
+
+  const setDgOptions = (deepgram: DeepgramOptions) => setSttOptions(o => o && { ...o, deepgram });
+  const setElOptions = (elevenlabs: ElevenLabsOptions) => setSttOptions(o => o && { ...o, elevenlabs });
+
+  async function saveSttOptions() {
+    if (!sttOptions) return;
+    try {
+      await invoke('set_stt_options', { options: sttOptions });
+      // The endpoint comes back normalized
+      setSttOptions(await invoke<SttOptions>('get_stt_options'));
+      setToast({ text: 'Provider options saved', kind: 'ok' });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 3000);
+  }
+
+  function updateRule(index: number, patch: Partial<RegexRule>) {
+    setRegexRules(prev => prev.map((r, i) => i === index ? { ...r, ...patch } : r));
+  }
+
+  async function testPipeline() {
+    try {
+      setPipelineTrace(await invoke('test_pipeline', { sampleText: pipelineSample }));
+    } catch (e) {
+      setPipelineTrace(null);
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function loadPlugins() {
+    try {
+      setPluginList(await invoke('list_plugins'));
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function moveHud() {
+    try {
+      await invoke('start_hud_move');
+      // Saving a dragged spot switches the pref to "custom"; pick that up when Settings is back in front
+      window.addEventListener('focus', async () => {
+        const b: any = await invoke('get_behavior').catch(() => null);
+        if (b?.hud_position) setHudPosition(b.hud_position);
+      }, { once: true });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function previewCue(cue: 'start' | 'stop' | 'error') {
+    try {
+      await invoke('preview_cue', { cue });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function checkScript() {
+    try {
+      setScriptStatus(await invoke('get_script_status'));
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function previewJournal() {
+    try {
+      setJournalPreview(await invoke('test_journal_template', { template: journalBlock || null, frontMatter: journalFrontMatter || null }));
+    } catch (e) {
+      setJournalPreview(null);
+      setToast({ text: String(e), kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    }
+  }
+
+  async function saveJournalTemplate() {
+    try {
+      await invoke('set_journal_template', { template: journalBlock || null, frontMatter: journalFrontMatter || null });
+      setToast({ text: 'Journal template saved', kind: 'ok' });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 3000);
+  }
+
+  async function saveJournalTarget() {
+    try {
+      const path = await invoke<string | null>('set_journal_target', { pathTemplate: journalTarget.trim() || null });
+      setToast({ text: path ? `Today's dictations go to ${path}` : 'Journal file cleared', kind: 'ok' });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 3000);
+  }
+
+  // The token is only fetched (and created) once the API is turned on
+  useEffect(() => {
+    if (controlApi && !controlApiToken) invoke<string>('get_control_api_token').then(setControlApiToken).catch(() => {});
+  }, [controlApi]);
+
+  async function regenerateControlApiToken() {
+    try {
+      setControlApiToken(await invoke<string>('regenerate_control_api_token'));
+      setToast({ text: 'New token: update your scripts and buttons', kind: 'ok' });
+    } catch (e) {
+      setToast({ text: String(e), kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 3000);
+  }
+
+  useEffect(() => { invoke<CorrectionSuggestion[]>('get_correction_suggestions').then(setCorrectionSuggestions).catch(() => {}); }, []);
+
+  async function resolveSuggestion(s: CorrectionSuggestion, accept: boolean) {
+    try {
+      setCorrectionSuggestions(await invoke<CorrectionSuggestion[]>(accept ? 'accept_correction_suggestion' : 'dismiss_correction_suggestion', { from: s.from, to: s.to }));
+      if (accept && s.kind === 'dictionary') setDictionary(await invoke<string[]>('list_dictionary'));
+      if (accept && s.kind === 'rule') await loadCustomPack();
+    } catch (e) {
+      logError('Resolving correction suggestion failed:', e);
+    }
+  }
+
+  async function addDictionaryWord() {
+    if (!dictionaryWord.trim()) return;
+    try {
+      setDictionary(await invoke<string[]>('add_dictionary_word', { word: dictionaryWord }));
+      setDictionaryWord('');
+    } catch (e) {
+      logError('Adding dictionary word failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  async function removeDictionaryWord(word: string) {
+    try {
+      setDictionary(await invoke<string[]>('remove_dictionary_word', { word }));
+    } catch (e) {
+      logError('Removing dictionary word failed:', e);
+    }
+  }
+
+  async function loadCustomPack() {
+    const pack = await invoke<object>('get_custom_pack');
+    setPackJson(JSON.stringify(pack, null, 2));
+  }
+
+  useEffect(() => {
+    loadCustomPack().catch(() => {});
+    invoke<PackInfo[]>('list_packs').then(setBuiltinPacks).catch(() => {});
+  }, []);
+
+  async function toggleBuiltinPack(name: string, enabled: boolean) {
+    try {
+      setBuiltinPacks(await invoke<PackInfo[]>(enabled ? 'enable_pack' : 'disable_pack', { name }));
+    } catch (e) {
+      logError('Toggling pack failed:', e);
+    }
+  }
+
+  function showToast(text: string, kind: 'ok' | 'err') {
+    setToast({ text, kind });
+    setTimeout(() => setToast(null), 4000);
+  }
+
+  async function saveCustomPack() {
+    try {
+      const pack = await invoke<object>('set_custom_pack', { pack: JSON.parse(packJson) });
+      setPackJson(JSON.stringify(pack, null, 2));
+      showToast('Custom pack saved', 'ok');
+    } catch (e) {
+      logError('Saving custom pack failed:', e);
+      showToast(`Custom pack not saved: ${e}`, 'err');
+    }
+  }
+
+  async function exportPack() {
+    try {
+      const path = await invoke<string>('export_pack', {});
+      showToast(`Saved ${path}`, 'ok');
+    } catch (e) {
+      logError('Pack export failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  // First try without overwriting anything; on conflicts, let the user pick keep or replace
+  async function importPack(onConflict: 'ask' | 'keep' | 'replace') {
+    try {
+      const report = await invoke<PackImportReport>('import_pack', { path: packImportPath.trim(), onConflict });
+      if (!report.applied) {
+        setPackConflicts(report.conflicts);
+        return;
+      }
+      setPackConflicts([]);
+      await loadCustomPack();
+      showToast(`Imported: ${report.added} added, ${report.replaced} replaced, ${report.skipped} skipped`, 'ok');
+    } catch (e) {
+      logError('Pack import failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  // Switching (here or from the tray) swaps every pref; reloading re-reads them and re-registers the hotkeys
+  useEffect(() => {
+    invoke<{ active: string; names: string[] }>('list_config_profiles').then(setConfigProfiles).catch(() => {});
+    const unlisten = listen('config-profile-changed', () => window.location.reload());
+    return () => { unlisten.then(u => u()); };
+  }, []);
+
+  async function switchProfile(name: string) {
+    try {
+      await invoke('switch_profile', { name });
+      setNewProfileName('');
+    } catch (e) {
+      logError('Profile switch failed:', e);
+      showToast(e === 'dictation-running' ? 'Stop dictating before switching profiles' : String(e), 'err');
+    }
+  }
+
+  async function deleteProfile(name: string) {
+    if (!window.confirm(`Delete the "${name}" profile and its settings?`)) return;
+    try {
+      setConfigProfiles(await invoke<{ active: string; names: string[] }>('delete_config_profile', { name }));
+    } catch (e) {
+      showToast(String(e), 'err');
+    }
+  }
+
+  useEffect(() => {
+    invoke<ProxyStatus>('get_proxy').then(p => { setProxy(p); setProxyHasPassword(p.has_password); }).catch(() => {});
+  }, []);
+
+  // An empty password field keeps the saved password unless the username was cleared
+  async function saveProxy() {
+    const password = proxyPassword ? proxyPassword : proxy.username ? null : '';
+    try {
+      const bypass = proxy.bypass.map(h => h.trim()).filter(Boolean);
+      const saved = await invoke<ProxyStatus>('set_proxy', { proxy: { ...proxy, bypass }, password });
+      setProxy(saved);
+      setProxyHasPassword(saved.has_password);
+      setProxyPassword('');
+      showToast(saved.mode === 'off' ? 'Connecting directly' : 'Proxy saved', 'ok');
+    } catch (e) {
+      logError('Proxy save failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  async function exportSettings() {
+    try {
+      const path = await invoke<string>('export_settings', { passphrase: backupPassphrase || null });
+      showToast(`Saved ${path}${backupPassphrase ? ' (with API keys)' : ''}`, 'ok');
+    } catch (e) {
+      logError('Settings export failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  // Everything on this page may have changed; reloading re-reads it and re-registers the hotkeys
+  async function importSettings() {
+    try {
+      const summary = await invoke<{ prefs: number; keys: number; keys_skipped: boolean }>('import_settings', { path: backupImportPath.trim(), passphrase: backupPassphrase || null });
+      log('🗄️ Settings imported:', summary);
+      if (summary.keys_skipped) showToast('Settings restored; enter the passphrase to restore the API keys too', 'ok');
+      setTimeout(() => window.location.reload(), summary.keys_skipped ? 2500 : 0);
+    } catch (e) {
+      logError('Settings import failed:', e);
+      showToast(String(e), 'err');
+    }
+  }
+
+  useEffect(() => {
+    invoke<{ added: string[]; removed: string[] }>('get_refusal_patterns')
+      .then(p => { setRefusalAdded(p.added.join('\n')); setRefusalRemoved(p.removed.join('\n')); })
+      .catch(() => {});
+  }, []);
+
+  // File transcription jobs (watch folder, folder batches): the list is loaded once, then kept current by events
+  useEffect(() => {
+    invoke<TranscriptionJob[]>('list_jobs').then(setJobs).catch(() => {});
+    const unlisten = listen<TranscriptionJob>('job-progress', (e) => {
+      setJobs(prev => {
+        const i = prev.findIndex(j => j.id === e.payload.id);
+        if (i < 0) return [...prev, e.payload];
+        const next = [...prev];
+        next[i] = e.payload;
+        return next;
+      });
+    });
+    const unlistenBatch = listen<BatchProgress>('batch-progress', (e) => {
+      setBatch(prev => (!prev || prev.id === e.payload.id || prev.finished ? e.payload : prev));
+    });
+    return () => { unlisten.then(f => f()); unlistenBatch.then(f => f()); };
+  }, []);
+
+  async function cancelJob(id: number) {
+    try {
+      await invoke('cancel_job', { id });
+    } catch (e) {
+      logWarn('Cancel job failed:', e);
+    }
+  }
+
+  async function inspectKeys() {
+    setInspectingKeys(true);
+    try {
+      const results = await invoke<KeyInspection[]>('inspect_keys');
+      log('🔑 Key inspection:', results);
+      setKeyInspections(results);
+    } catch (e) {
+      logError('Key inspection failed:', e);
+      setToast({ text: 'Key inspection failed', kind: 'err' });
+      setTimeout(() => setToast(null), 3000);
+    } finally {
+      setInspectingKeys(false);
+    }
+  }
+
+  async function checkProviderUsage() {
+    setCheckingUsage(true);
+    try {
+      setProviderUsage(await invoke<ProviderUsage[]>('get_provider_usage'));
+    } catch (e) {
+      logError('Usage check failed:', e);
+    } finally {
+      setCheckingUsage(false);
+    }
+  }
+
+  async function loadCostSummary() {
+    try {
+      setCostSummary(await invoke<CostSummary>('get_cost_summary', { months: 6 }));
+    } catch (e) {
+      logError('Cost summary failed:', e);
+    }
+  }
+
+  function usageLabel(u: ProviderUsage) {
+    if (u.error) return u.error;
+    if (u.remaining === null) return '—';
+    const left = u.limit !== null ? `${Math.round(u.remaining).toLocaleString()} of ${Math.round(u.limit).toLocaleString()} credits left` : u.unit === 'usd' ? `$${u.remaining.toFixed(2)} left` : `${u.remaining.toFixed(2)} ${u.unit} left`;
+    return u.resets_at ? `${left}, resets ${new Date(u.resets_at * 1000).toLocaleDateString()}` : left;
+  }
+
+  async function persistHotkey() {
+    if (!hotkey || hotkey.trim() === '') {
+      setToast({ text: 'Invalid hotkey', kind: 'err' });
+      setTimeout(() => setToast(null), 2000);
+      return;
+    }
+    await invoke('set_hotkey', { combo: hotkey });
+    const success = await initGlobalHotkey(hotkey);
+    if (success) {
+      console.log('Hotkey saved and registered:', hotkey);
+      setToast({ text: 'Hotkey saved', kind: 'ok' });
+    } else {
+      setToast({ text: 'Hotkey registration failed', kind: 'err' });
+    }
+    setTimeout(() => setToast(null), 1500);
+  }
+
+  const [savingProviderPrefs, setSavingProviderPrefs] = useState(false);
+
+  async function persistProviderPrefs() {
+    setSavingProviderPrefs(true);
+    try {
+      await invoke('set_model', { name: model });
+      await invoke('set_megallm_model', { name: megallmModel });
+      const code = await invoke<string>('set_language', { code: languages[sttProvider], provider: sttProvider });
+      setLanguages(prev => ({ ...prev, [sttProvider]: code }));
+      setToast({ text: 'Provider prefs saved', kind: 'ok' });
+      setTimeout(() => setToast(null), 2000);
+    } catch (e) {
+      console.error('Failed to save provider prefs', e);
+      setToast({ text: 'Failed to save', kind: 'err' });
+      setTimeout(() => setToast(null), 2000);
+    } finally {
+      setSavingProviderPrefs(false);
+    }
+  }
+
+  return (
+    <div className="h-screen flex flex-col bg-[var(--bg)] text-[var(--fg)] font-geistmono">
+      <TitleBar version={version}/>
+      <div className="flex-1 overflow-y-auto mt-10">
+        <div className="w-full max-w-6xl mx-auto">
+          <main className="p-6">
+            <div className="flex flex-col md:flex-row items-start gap-4">
+              <div className="flex-1 flex flex-col gap-4 min-w-0">
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Keys</h2>
+                <div className="space-y-3">
+            <div>
+              <label htmlFor="hotkey" className="block text-xs text-muted mb-2">Global hotkey</label>
+              <KeyRecorder
+                value={hotkey}
+                onChange={setHotkey}
+                onSave={persistHotkey}
+              />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Autostart</div>
+                <div className="text-xs text-muted">Launch on login</div>
+              </div>
+              <Switch ariaLabel="Autostart" checked={autostart} onCheckedChange={(v)=>{ setAutostart(v); setToast({text: v?'Autostart on':'Autostart off', kind:'ok'}); setTimeout(()=> setToast(null),1200); }} />
+            </div>
+          </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Behavior</h2>
+          <div className="space-y-4">
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Auto-paste into focused field</div>
+                <div className="text-xs text-muted">Falls back to manual paste if blocked</div>
+              </div>
+              <Switch checked={autoPaste} onCheckedChange={(v)=>{ log('🟢 Toggle autoPaste ->', v); setAutoPaste(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Type while speaking</div>
+                <div className="text-xs text-muted">Streams text into the field as you talk, then corrects it to the refined text (needs the backend relay)</div>
+              </div>
+              <Switch checked={streamInsert} onCheckedChange={(v)=>{ log('🟢 Toggle streamInsert ->', v); setStreamInsert(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <label htmlFor="silence-secs" className="text-sm">Stop after silence</label>
+                <div className="text-xs text-muted">Seconds of silence after speech before dictation stops by itself, 0 = off (needs the backend relay)</div>
+              </div>
+              <input id="silence-secs" type="number" min={0} max={30} value={silenceSecs} onChange={e=>setSilenceSecs(Math.max(0, Number(e.target.value) || 0))} className="w-16 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Preserve clipboard</div>
+                <div className="text-xs text-muted">Restore what you had copied shortly after auto-paste</div>
+              </div>
+              <Switch checked={preserveClipboard} onCheckedChange={(v)=>{ log('📋 Toggle preserveClipboard ->', v); setPreserveClipboard(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Type instead of paste</div>
+                <div className="text-xs text-muted">Simulate keystrokes for terminals and remote desktops that block Ctrl+V</div>
+              </div>
+              <Switch checked={insertMode === 'type'} onCheckedChange={(v)=>{ log('⌨️ Toggle typeOut ->', v); setInsertMode(v ? 'type' : 'paste'); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Type like a human</div>
+                <div className="text-xs text-muted">Randomized key delays for web forms that reject pastes or throttle fast input</div>
+              </div>
+              <Switch checked={insertMode === 'human'} onCheckedChange={(v)=>{ log('🐢 Toggle humanTyping ->', v); setInsertMode(v ? 'human' : 'paste'); }} />
+            </div>
+            {insertMode === 'human' && (
+              <div className="flex items-center gap-2 text-sm">
+                <label htmlFor="human-min-ms">Delay per key</label>
+                <input id="human-min-ms" type="number" min={0} max={1000} value={humanMinMs} onChange={e=>setHumanMinMs(Number(e.target.value) || 0)} className="w-20 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+                <span>to</span>
+                <input id="human-max-ms" type="number" min={0} max={1000} value={humanMaxMs} onChange={e=>setHumanMaxMs(Number(e.target.value) || 0)} className="w-20 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" aria-label="Maximum delay per key" />
+                <span>ms</span>
+              </div>
+            )}
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Keyboard macro mode</div>
+                <div className="text-xs text-muted">Spoken keys become key presses ("tab tab John Smith enter"); "press up", "press end" for the others</div>
+              </div>
+              <Switch checked={insertMode === 'macro'} onCheckedChange={(v)=>{ log('🎹 Toggle macroMode ->', v); setInsertMode(v ? 'macro' : 'paste'); }} />
+            </div>
+            <div>
+              <label htmlFor="terminal-apps" className="block text-sm">Extra terminal apps</label>
+              <div className="text-xs text-muted mb-1">Comma-separated process names that paste with Ctrl+Shift+V (common terminals are detected already)</div>
+              <input id="terminal-apps" value={terminalApps} onChange={e=>setTerminalApps(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700" placeholder="e.g. my-term, rio" />
+            </div>
+            <div>
+              <div className="text-sm">Redact before pasting</div>
+              <div className="text-xs text-muted mb-1">Masked in every app unless its profile sets its own list (e.g. everything in Slack)</div>
+              <div className="flex flex-wrap gap-3">
+                {REDACT_CATEGORIES.map(c => (
+                  <label key={c.id} className="flex items-center gap-1 text-sm">
+                    <input type="checkbox" checked={redact.includes(c.id)} onChange={e=>setRedact(prev => e.target.checked ? [...prev, c.id] : prev.filter(x => x !== c.id))} />
+                    {c.label}
+                  </label>
+                ))}
+              </div>
+            </div>
+            <div>
+              <div className="text-sm">Typography</div>
+              <div className="text-xs text-muted mb-1">Quotes, ellipses and dashes of the refined text; app profiles can pick their own (straight quotes for LaTeX, curly for Word)</div>
+              <div className="flex flex-wrap gap-2">
+                <select aria-label="Quotes" value={typography.quotes} onChange={e=>setTypography(t => ({ ...t, quotes: e.target.value as Typography['quotes'] }))} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-xs">
+                  <option value="keep">Quotes as written</option>
+                  <option value="straight">"Straight" quotes</option>
+                  <option value="curly">“Curly” quotes</option>
+                </select>
+                <select aria-label="Ellipsis" value={typography.ellipsis} onChange={e=>setTypography(t => ({ ...t, ellipsis: e.target.value as Typography['ellipsis'] }))} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-xs">
+                  <option value="keep">Ellipsis as written</option>
+                  <option value="character">Ellipsis character …</option>
+                  <option value="dots">Three dots ...</option>
+                </select>
+                <select aria-label="Em dash" value={typography.em_dash} onChange={e=>setTypography(t => ({ ...t, em_dash: e.target.value as Typography['em_dash'] }))} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-xs">
+                  <option value="keep">Dashes as written</option>
+                  <option value="spaced">Spaced dash a — b</option>
+                  <option value="closed">Closed dash a—b</option>
+                </select>
+              </div>
+            </div>
+            <div className="space-y-2 text-sm">
+              <div>
+                <div className="text-sm">Find and replace rules</div>
+                <div className="text-xs text-muted">Regular expressions applied in order after refinement; use $1 for a captured group</div>
+              </div>
+              {regexRules.map((r, i) => (
+                <div key={i} className="flex items-center gap-2">
+                  <input aria-label="Find" value={r.find} onChange={e=>updateRule(i, { find: e.target.value })} placeholder="\bjira (\d+)" className="flex-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                  <input aria-label="Replace with" value={r.replace} onChange={e=>updateRule(i, { replace: e.target.value })} placeholder="PROJ-$1" className="flex-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                  <label className="flex items-center gap-1 text-xs"><input type="checkbox" checked={r.ignore_case} onChange={e=>updateRule(i, { ignore_case: e.target.checked })} />Aa</label>
+                  <button type="button" aria-label="Remove rule" onClick={()=>setRegexRules(prev => prev.filter((_, k) => k !== i))} className="px-2 py-1 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs">✕</button>
+                </div>
+              ))}
+              <button type="button" onClick={()=>setRegexRules(prev => [...prev, { find: '', replace: '', ignore_case: false }])} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Add rule</button>
+              <div className="flex items-center justify-between gap-2">
+                <div className="text-xs text-muted">A Rhai script named transform.rhai in the app data folder gets the refined text with app and language, and returns the text to paste</div>
+                <button type="button" onClick={checkScript} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm shrink-0">Check script</button>
+              </div>
+              {scriptStatus && (
+                <div className={`text-xs break-all ${scriptStatus.error ? 'text-red-400' : 'text-muted'}`}>
+                  {!scriptStatus.present ? `No script at ${scriptStatus.path}` : scriptStatus.error ?? `Script at ${scriptStatus.path} is ready`}
+                </div>
+              )}
+              <div className="flex items-center justify-between gap-2">
+                <div className="text-xs text-muted">WebAssembly plugins from the plugins folder process the refined text before the script</div>
+                <button type="button" onClick={loadPlugins} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm shrink-0">Show plugins</button>
+              </div>
+              {pluginList && (
+                <div className="space-y-1">
+                  <div className="text-xs text-muted break-all">{pluginList.plugins.length ? `In ${pluginList.dir}` : `No plugins in ${pluginList.dir}`}</div>
+                  {pluginList.plugins.map(p => (
+                    <div key={p.name} className="flex items-center justify-between">
+                      <div>
+                        <div className="text-sm font-mono">{p.name}</div>
+                        <div className={`text-xs ${p.error ? 'text-red-400' : 'text-muted'}`}>{p.error ?? (p.hooks.join(', ') || 'No hooks')}</div>
+                      </div>
+                      <Switch ariaLabel={`Plugin ${p.name}`} checked={plugins.includes(p.name)} onCheckedChange={(v)=>setPlugins(prev => v ? [...prev, p.name] : prev.filter(x => x !== p.name))} />
+                    </div>
+                  ))}
+                </div>
+              )}
+              <div className="flex items-center gap-2">
+                <input aria-label="Sample text" value={pipelineSample} onChange={e=>setPipelineSample(e.target.value)} placeholder="Try a sentence, e.g. fixed in jira 42 comma thanks" className="flex-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-xs" />
+                <button type="button" onClick={testPipeline} disabled={!pipelineSample.trim()} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Test pipeline</button>
+              </div>
+              <div className="text-xs text-muted">Uses the saved settings, and refinement calls your AI provider</div>
+              {pipelineTrace && (
+                <ol className="space-y-1">
+                  {pipelineTrace.stages.map((st, i) => (
+                    <li key={i} className={st.changed ? '' : 'text-muted'}>
+                      <span className="font-mono text-xs">{st.stage}</span>{st.changed ? '' : ' (unchanged)'}
+                      {st.changed && <pre className="mt-0.5 p-2 bg-neutral-900 rounded border border-neutral-700 text-xs whitespace-pre-wrap">{st.text}</pre>}
+                    </li>
+                  ))}
+                </ol>
+              )}
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Auto-stop long recordings</div>
+                <div className="text-xs text-muted">Stop a forgotten dictation after 10 minutes</div>
+              </div>
+              <Switch checked={maxDurationSecs > 0} onCheckedChange={(v)=>{ log('⏰ Toggle maxDuration ->', v); setMaxDurationSecs(v ? 600 : 0); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Confirm first pastes</div>
+                <div className="text-xs text-muted">The first few auto-pastes wait for Enter (Esc copies only), then paste instantly</div>
+              </div>
+              <Switch checked={confirmPastes > 0} onCheckedChange={(v)=>{ log('✋ Toggle confirmPastes ->', v); setConfirmPastes(v ? 3 : 0); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Preview before paste</div>
+                <div className="text-xs text-muted">Show the final text first: Enter pastes, Esc discards, E edits</div>
+              </div>
+              <Switch checked={confirmBeforePaste} onCheckedChange={(v)=>{ log('👀 Toggle confirmBeforePaste ->', v); setConfirmBeforePaste(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Lock to starting window</div>
+                <div className="text-xs text-muted">Paste into the window dictation started in, even if focus moved since</div>
+              </div>
+              <Switch checked={lockTargetWindow} onCheckedChange={(v)=>{ log('🔒 Toggle lockTargetWindow ->', v); setLockTargetWindow(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Review playback</div>
+                <div className="text-xs text-muted">After you stop, replay the words in the HUD as they were spoken</div>
+              </div>
+              <Switch checked={reviewPlayback} onCheckedChange={(v)=>{ log('🎞️ Toggle reviewPlayback ->', v); setReviewPlayback(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Verbose logging</div>
+                <div className="text-xs text-muted">Write debug details to the log files; turn on when reporting a bug</div>
+              </div>
+              <Switch checked={logLevel === 'debug' || logLevel === 'trace'} onCheckedChange={(v)=>{ log('🪵 Toggle verbose logging ->', v); setLogLevel(v ? 'debug' : 'info'); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Recent logs</div>
+                <div className="text-xs text-muted">Copy the latest log lines, or save a diagnostics zip (keys redacted) to Downloads for a bug report</div>
+              </div>
+              <div className="flex gap-2">
+                <button type="button" onClick={copyRecentLogs} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">{logsCopied ? 'Copied' : 'Copy logs'}</button>
+                <button type="button" onClick={exportDiagnostics} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Export diagnostics</button>
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Last recording</div>
+                <div className="text-xs text-muted">
+                  {lastAudio ? `${Math.round(lastAudio.duration_ms / 1000)}s kept${lastAudio.truncated ? ' (only the last 10 minutes)' : ''}. Save it as WAV or transcribe it again after a failed dictation` : 'The audio of your last dictation is kept until the next one'}
+                </div>
+              </div>
+              <div className="flex gap-2">
+                <button type="button" disabled={!lastAudio} onClick={saveLastAudio} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Save WAV</button>
+                <button type="button" disabled={!lastAudio || retranscribing} onClick={retranscribeLastAudio} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">{retranscribing ? 'Transcribing…' : 'Re-transcribe'}</button>
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">History</div>
+                <div className="text-xs text-muted">Browse, search, copy and delete past dictations in their own window</div>
+              </div>
+              <button type="button" onClick={()=>invoke('open_history_window').catch(e => logError('Could not open history:', e))} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Open history</button>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Export last dictation</div>
+                <div className="text-xs text-muted">Subtitles (SRT, VTT) are timed to your words, e.g. for video narration</div>
+              </div>
+              <div className="flex gap-2">
+                <select aria-label="Export format" value={exportFormat} onChange={e=>setExportFormat(e.target.value as typeof exportFormat)} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                  {(['txt', 'md', 'json', 'srt', 'vtt'] as const).map(f => <option key={f} value={f}>{f.toUpperCase()}</option>)}
+                </select>
+                <button type="button" onClick={exportLastTranscript} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Export</button>
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Maintenance</div>
+                <div className="text-xs text-muted">
+                  {maintenance?.last_run ? `Last run ${new Date(maintenance.last_run * 1000).toLocaleString()}` : 'Runs daily, first shortly after launch'}
+                  {maintenance?.last_report?.damaged_files.length ? ` · damaged: ${maintenance.last_report.damaged_files.join(', ')}` : ''}
+                  {maintenance?.last_report?.errors.length ? ` · ${maintenance.last_report.errors.length} problem(s)` : ''}
+                </div>
+              </div>
+              <button type="button" disabled={maintenance?.running} onClick={runMaintenance} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">{maintenance?.running ? 'Running…' : 'Run now'}</button>
+            </div>
+            <div className="flex items-center gap-2 text-sm">
+              <label htmlFor="history-retention">Keep history for</label>
+              <input id="history-retention" type="number" min={0} max={3650} value={historyRetentionDays} onChange={e=>setHistoryRetentionDays(Math.max(0, Number(e.target.value) || 0))} className="w-20 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+              <span className="text-xs text-muted">days (0 = keep)</span>
+            </div>
+            <div className="flex items-center gap-2 text-sm">
+              <label htmlFor="low-credit-usd">Warn when Deepgram credit is below $</label>
+              <input id="low-credit-usd" type="number" min={0} value={lowCreditUsd} onChange={e=>setLowCreditUsd(Math.max(0, Number(e.target.value) || 0))} className="w-16 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+              <label htmlFor="low-credit-percent">or ElevenLabs below</label>
+              <input id="low-credit-percent" type="number" min={0} max={100} value={lowCreditPercent} onChange={e=>setLowCreditPercent(Math.min(100, Math.max(0, Number(e.target.value) || 0)))} className="w-16 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+              <span className="text-xs text-muted">% (0 = never)</span>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Check keys during maintenance</div>
+                <div className="text-xs text-muted">Ping each provider once a day so a revoked key shows up before you dictate</div>
+              </div>
+              <Switch checked={maintenanceHealthPing} onCheckedChange={(v)=>{ log('🩺 Toggle maintenanceHealthPing ->', v); setMaintenanceHealthPing(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Journal</div>
+                <div className="text-xs text-muted">Append each dictation to a file under a time heading, e.g. a daily note</div>
+              </div>
+              <select aria-label="Journal mode" value={journalMode} onChange={e=>setJournalMode(e.target.value as typeof journalMode)} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                <option value="off">Off</option>
+                <option value="also">Paste and append</option>
+                <option value="only">Append instead of pasting</option>
+              </select>
+            </div>
+            {journalMode !== 'off' && (
+              <div className="flex items-center gap-2 text-sm">
+                <input aria-label="Journal file" value={journalTarget} onChange={e=>setJournalTarget(e.target.value)} placeholder="~/Vault/Daily/{{date}}.md" className="flex-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                <button type="button" onClick={saveJournalTarget} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Set</button>
+              </div>
+            )}
+            {journalMode !== 'off' && (
+              <div className="space-y-2 text-sm">
+                <div className="text-xs text-muted">Placeholders: {'{{date}} {{time}} {{app}} {{text}}'}; date and time take a strftime format, e.g. {'{{time:%H%M}}'}</div>
+                <textarea aria-label="Journal block template" value={journalBlock} onChange={e=>setJournalBlock(e.target.value)} placeholder={'## {{time}}\n\n{{text}}'} rows={3} className="w-full px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                <textarea aria-label="Front matter for new files" value={journalFrontMatter} onChange={e=>setJournalFrontMatter(e.target.value)} placeholder={'---\ndate: {{date}}\ntags: [dictation]\n---'} rows={3} className="w-full px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                <div className="flex justify-end gap-2">
+                  <button type="button" onClick={previewJournal} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Preview</button>
+                  <button type="button" onClick={saveJournalTemplate} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Save template</button>
+                </div>
+                {journalPreview && (
+                  <div>
+                    <div className="text-xs text-muted break-all">{journalPreview.path ? `Appends to ${journalPreview.path}` : 'No journal file set'}</div>
+                    <pre className="mt-1 p-2 bg-neutral-900 rounded border border-neutral-700 text-xs whitespace-pre-wrap">{journalPreview.addition}</pre>
+                  </div>
+                )}
+              </div>
+            )}
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Local control API</div>
+                <div className="text-xs text-muted">Start, stop or toggle dictation from Stream Deck, AutoHotkey or pedal software over HTTP on this computer</div>
+              </div>
+              <Switch checked={controlApi} onCheckedChange={(v)=>{ log('🎛️ Toggle controlApi ->', v); setControlApi(v); }} />
+            </div>
+            {controlApi && (
+              <div className="space-y-2 text-sm">
+                <div className="flex items-center gap-2">
+                  <label htmlFor="control-api-port">Port</label>
+                  <input id="control-api-port" type="number" min={1024} max={65535} value={controlApiPort} onChange={e=>setControlApiPort(Number(e.target.value) || 47821)} className="w-24 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+                </div>
+                <div className="flex items-center gap-2">
+                  <input aria-label="Control API token" readOnly value={controlApiToken} className="flex-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 font-mono text-xs" />
+                  <button type="button" onClick={() => navigator.clipboard.writeText(controlApiToken)} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Copy</button>
+                  <button type="button" onClick={regenerateControlApiToken} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">New token</button>
+                </div>
+                <div className="text-xs text-muted font-mono break-all">http://127.0.0.1:{controlApiPort}/toggle?token=…</div>
+              </div>
+            )}
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Low-memory HUD</div>
+                <div className="text-xs text-muted">Unload the HUD after 5 idle minutes; it reloads when you press the hotkey</div>
+              </div>
+              <Switch checked={hudIdleSecs > 0} onCheckedChange={(v)=>{ log('💤 Toggle low-memory HUD ->', v); setHudIdleSecs(v ? 300 : 0); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">HUD position</div>
+                <div className="text-xs text-muted">Where the HUD appears; "Move" lets you drag it anywhere, remembered per monitor</div>
+              </div>
+              <div className="flex items-center gap-2">
+                <select aria-label="HUD position" value={hudPosition} onChange={e=>setHudPosition(e.target.value as typeof hudPosition)} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                  <option value="bottom-center">Bottom center</option>
+                  <option value="top-center">Top center</option>
+                  <option value="bottom-right">Bottom right</option>
+                  <option value="custom">Where I dragged it</option>
+                </select>
+                <button type="button" onClick={moveHud} className="px-2 py-1 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs">Move…</button>
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">HUD monitor</div>
+                <div className="text-xs text-muted">Which screen the HUD shows up on</div>
+              </div>
+              <select aria-label="HUD monitor" value={monitorPolicy} onChange={e=>setMonitorPolicy(e.target.value)} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                <option value="monitor-with-cursor">Monitor with the mouse</option>
+                <option value="monitor-of-focused-window">Monitor of the focused window</option>
+                <option value="primary">Primary monitor</option>
+                {monitors.map(m => (
+                  <option key={m.index} value={String(m.index)}>{`Always ${m.name || `monitor ${m.index + 1}`} (${m.width}×${m.height}${m.primary ? ', primary' : ''})`}</option>
+                ))}
+                {/^\d+$/.test(monitorPolicy) && !monitors.some(m => String(m.index) === monitorPolicy) && (
+                  <option value={monitorPolicy}>{`Monitor ${Number(monitorPolicy) + 1} (not connected)`}</option>
+                )}
+              </select>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">AI refinement</div>
+                <div className="text-xs text-muted">Use the selected AI provider to refine dictation text</div>
+              </div>
+              <Switch checked={aiRefine} onCheckedChange={(v)=>{ log('🟢 Toggle aiRefine ->', v); setAiRefine(v); }} />
+            </div>
+            <div className="flex items-center gap-2 text-sm">
+              <label htmlFor="ai-timeout">AI timeout</label>
+              <input id="ai-timeout" type="number" min={1} max={300} value={aiTimeoutSecs} onChange={e=>setAiTimeoutSecs(Number(e.target.value) || 1)} className="w-20 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+              <span>s</span>
+              <label htmlFor="ai-max-tokens" className="ml-4">Max tokens</label>
+              <input id="ai-max-tokens" type="number" min={0} max={128000} value={aiMaxTokens} onChange={e=>setAiMaxTokens(Number(e.target.value) || 0)} className="w-24 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" title="Raised for long dictations and translations; 0 uses the provider's default" />
+            </div>
+            <div className="flex items-center gap-2 text-sm">
+              <label htmlFor="ai-min-similarity">Reject outputs less than</label>
+              <input id="ai-min-similarity" type="number" min={0} max={100} value={aiMinSimilarity} onChange={e=>setAiMinSimilarity(Number(e.target.value) || 0)} className="w-20 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" title="Word overlap with what you said; 0 turns the check off" />
+              <span>% similar to what you said</span>
+            </div>
+            <div className="grid grid-cols-2 gap-2">
+              <div>
+                <label htmlFor="refusal-added" className="block text-sm">Also reject replies containing</label>
+                <textarea id="refusal-added" rows={3} value={refusalAdded} onChange={e=>setRefusalAdded(e.target.value)} className="w-full px-2 py-1 text-xs bg-neutral-900 rounded border border-neutral-700" placeholder="One phrase per line" />
+              </div>
+              <div>
+                <label htmlFor="refusal-removed" className="block text-sm">Never reject for</label>
+                <textarea id="refusal-removed" rows={3} value={refusalRemoved} onChange={e=>setRefusalRemoved(e.target.value)} className="w-full px-2 py-1 text-xs bg-neutral-900 rounded border border-neutral-700" placeholder={'Built-in phrases, e.g.\nunfortunately,'} />
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Spend ledger</div>
+                <div className="text-xs text-muted">Append provider, model, tokens, estimated cost and latency of every AI request to a file you can audit with a spreadsheet</div>
+              </div>
+              <Switch checked={spendLedger !== 'off'} onCheckedChange={(v)=>{ log('🧾 Toggle spendLedger ->', v); setSpendLedger(v ? 'csv' : 'off'); }} />
+            </div>
+            {spendLedger !== 'off' && (
+              <div className="space-y-1 text-sm">
+                <div className="flex items-center gap-3">
+                  {(['csv', 'jsonl'] as const).map(f => (
+                    <label key={f} className="flex items-center gap-1">
+                      <input type="radio" name="spend-ledger" checked={spendLedger === f} onChange={()=>setSpendLedger(f)} />
+                      {f === 'csv' ? 'CSV' : 'JSON Lines'}
+                    </label>
+                  ))}
+                </div>
+                {ledgerPath && <div className="text-xs text-muted font-mono break-all">{ledgerPath}</div>}
+              </div>
+            )}
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Spoken punctuation</div>
+                <div className="text-xs text-muted">Turn "comma", "period", "question mark" into punctuation. Off keeps those words as spoken; "new line" still works</div>
+              </div>
+              <Switch checked={spokenPunctuation} onCheckedChange={(v)=>{ log('❓ Toggle spokenPunctuation ->', v); setSpokenPunctuation(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Emoji</div>
+                <div className="text-xs text-muted">Turn "thumbs up", "smiley face" or "fire emoji" into 👍 😊 🔥; add your own under "emoji" in your custom pack</div>
+              </div>
+              <Switch checked={emoji} onCheckedChange={(v)=>{ log('😊 Toggle emoji ->', v); setEmoji(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Start sound</div>
+                <div className="text-xs text-muted">Beep when the microphone goes live</div>
+              </div>
+              <div className="flex items-center gap-2">
+                <button type="button" onClick={()=>previewCue('start')} className="px-2 py-1 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs">Play</button>
+                <Switch checked={soundStart} onCheckedChange={(v)=>{ log('🔔 Toggle soundStart ->', v); setSoundStart(v); }} />
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Stop sound</div>
+                <div className="text-xs text-muted">Chime when recording stops</div>
+              </div>
+              <div className="flex items-center gap-2">
+                <button type="button" onClick={()=>previewCue('stop')} className="px-2 py-1 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs">Play</button>
+                <Switch checked={soundStop} onCheckedChange={(v)=>{ log('🔔 Toggle soundStop ->', v); setSoundStop(v); }} />
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Error sound</div>
+                <div className="text-xs text-muted">Buzz when the connection, refinement or paste fails</div>
+              </div>
+              <div className="flex items-center gap-2">
+                <button type="button" onClick={()=>previewCue('error')} className="px-2 py-1 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs">Play</button>
+                <Switch checked={soundError} onCheckedChange={(v)=>{ log('🔔 Toggle soundError ->', v); setSoundError(v); }} />
+              </div>
+            </div>
+            <div className="flex items-center justify-between">
+              <label htmlFor="sound-volume" className="text-sm">Sound volume</label>
+              <input id="sound-volume" type="range" min={0} max={100} value={soundVolume} onChange={e=>setSoundVolume(Number(e.target.value))} className="w-32" />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Error notifications</div>
+                <div className="text-xs text-muted">A system notification when something fails, e.g. "Speech-to-text connection failed", in case the HUD is on another screen</div>
+              </div>
+              <Switch checked={notifyErrors} onCheckedChange={(v)=>{ log('📣 Toggle notifyErrors ->', v); setNotifyErrors(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Numbers</div>
+                <div className="text-xs text-muted">Write spoken numbers, amounts and dates the way your language does: "twenty three point five percent" → 23.5%</div>
+              </div>
+              <select aria-label="Numbers" value={numbersMode} onChange={e=>setNumbersMode(e.target.value as typeof numbersMode)} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                <option value="words">As spoken</option>
+                <option value="auto">Digits from 10</option>
+                <option value="digits">Always digits</option>
+              </select>
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Demo mode</div>
+                <div className="text-xs text-muted">Try the hotkey, HUD and paste with scripted text, no API keys needed</div>
+              </div>
+              <Switch checked={demoMode} onCheckedChange={(v)=>{ log('🎭 Toggle demoMode ->', v); setDemoMode(v); }} />
+            </div>
+            <div>
+              <label htmlFor="translate-to" className="block text-sm">Translate to</label>
+              <div className="text-xs text-muted mb-1">Dictate in any language and insert this one. Leave empty to turn off</div>
+              <input id="translate-to" value={translateTo} onChange={e=>setTranslateTo(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700" placeholder="e.g. English, Spanish, Japanese" />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Mixed languages</div>
+                <div className="text-xs text-muted">When you switch languages mid-dictation (e.g. English and Bangla), refine each part in its own language instead of one. Off while translating</div>
+              </div>
+              <Switch checked={mixedLanguage} onCheckedChange={(v)=>{ log('🌐 Toggle mixedLanguage ->', v); setMixedLanguage(v); }} />
+            </div>
+            <div className="space-y-2">
+              <label htmlFor="watch-folder" className="block text-sm">Watch folder</label>
+              <div className="text-xs text-muted mb-1">Audio files dropped here (wav, mp3, m4a, ogg, flac, webm) are transcribed and the text is saved next to them. Leave empty to turn off</div>
+              <input id="watch-folder" value={watchFolder} onChange={e=>setWatchFolder(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700" placeholder="Full path, e.g. /Users/you/Voice Memos" />
+              {watchFolder.trim() && (
+                <>
+                  <div className="flex items-center justify-between">
+                    <div className="text-sm">Refine watch-folder transcripts</div>
+                    <Switch checked={watchRefine} onCheckedChange={(v)=>{ log('📂 Toggle watchRefine ->', v); setWatchRefine(v); }} />
+                  </div>
+                  <div className="flex items-center gap-3 text-sm">
+                    {(['txt', 'md'] as const).map(f => (
+                      <label key={f} className="flex items-center gap-1">
+                        <input type="radio" name="watch-output-format" checked={watchOutputFormat === f} onChange={()=>setWatchOutputFormat(f)} />
+                        {f === 'txt' ? 'Plain text (.txt)' : 'Markdown (.md)'}
+                      </label>
+                    ))}
+                  </div>
+                </>
+              )}
+              <div className="flex items-center gap-2 text-sm">
+                <label htmlFor="transcription-concurrency">Transcribe</label>
+                <input id="transcription-concurrency" type="number" min={1} max={8} value={transcriptionConcurrency} onChange={e=>setTranscriptionConcurrency(Number(e.target.value) || 1)} className="w-16 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+                <span>files at a time</span>
+              </div>
+              <div className="flex gap-2">
+                <input aria-label="Folder to transcribe" value={batchFolder} onChange={e=>setBatchFolder(e.target.value)} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="Transcribe a folder once (or drop it on this window)" />
+                <button type="button" disabled={!batchFolder.trim()} onClick={()=>transcribeFolder(batchFolder.trim())} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Transcribe</button>
+              </div>
+              {batch && (
+                <div className="text-xs text-muted">
+                  Folder batch: {batch.done} of {batch.total} done{batch.failed ? `, ${batch.failed} failed` : ''}{batch.cancelled ? `, ${batch.cancelled} cancelled` : ''}{batch.finished ? '' : '…'}
+                </div>
+              )}
+              {jobs.length > 0 && (
+                <ul className="text-xs space-y-1 max-h-40 overflow-y-auto">
+                  {[...jobs].reverse().slice(0, 20).map(j => (
+                    <li key={j.id} className="flex justify-between items-center gap-2" title={j.error ?? j.output ?? undefined}>
+                      <span className="truncate">{j.file.split(/[\\/]/).pop()}</span>
+                      <span className="flex items-center gap-2">
+                        <span className={j.status === 'failed' ? 'text-red-400' : 'text-muted'}>{j.status}</span>
+                        {(j.status === 'queued' || j.status === 'transcribing' || j.status === 'refining') && (
+                          <button onClick={()=>cancelJob(j.id)} className="text-muted hover:text-white" title="Cancel"><X size={12} /></button>
+                        )}
+                      </span>
+                    </li>
+                  ))}
+                </ul>
+              )}
+            </div>
+            <motion.button
+              onClick={persistBehavior}
+              whileHover={{ scale: 1.02 }}
+              whileTap={{ scale: 0.96 }}
+              className="px-3 py-2 bg-accent text-black rounded hover:brightness-110 active:brightness-90 transition-all duration-150 shadow-sm hover:shadow-md"
+            >
+              Save Behavior
+            </motion.button>
+          </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Dictionary</h2>
+                <div className="space-y-3">
+                  <div className="text-xs text-muted">Names, product names and acronyms to spell your way. Deepgram listens for them and refinement keeps them as written</div>
+                  <div className="flex gap-2">
+                    <input aria-label="Dictionary word" value={dictionaryWord} onChange={e=>setDictionaryWord(e.target.value)} onKeyDown={e=>{ if (e.key === 'Enter') addDictionaryWord(); }} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="e.g. Tanjim, MegaLLM, HIPAA" />
+                    <button type="button" disabled={!dictionaryWord.trim()} onClick={addDictionaryWord} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Add</button>
+                  </div>
+                  <div className="flex items-center justify-between">
+                    <div>
+                      <div className="text-sm">Learn from my corrections</div>
+                      <div className="text-xs text-muted">Remember words you fix after dictating (e.g. in the scratchpad) and suggest the ones you fix repeatedly</div>
+                    </div>
+                    <Switch checked={learnCorrections} onCheckedChange={(v)=>{ log('🎓 Toggle learnCorrections ->', v); setLearnCorrections(v); invoke('set_behavior', { args: { learn_corrections: v, learnCorrections: v } }).catch(e => logError('Saving learnCorrections failed:', e)); }} />
+                  </div>
+                  {correctionSuggestions.length > 0 && (
+                    <ul className="text-xs space-y-1">
+                      {correctionSuggestions.map(c => (
+                        <li key={`${c.from}:${c.to}`} className="flex justify-between items-center gap-2">
+                          <span>{c.kind === 'dictionary' ? `Add "${c.to}" to the dictionary` : `Always write "${c.from}" as "${c.to}"`} <span className="text-muted">({c.count}×)</span></span>
+                          <span className="flex gap-2">
+                            <button onClick={()=>resolveSuggestion(c, true)} className="text-accent hover:brightness-110">Add</button>
+                            <button onClick={()=>resolveSuggestion(c, false)} className="text-muted hover:text-white" title="Dismiss"><X size={12} /></button>
+                          </span>
+                        </li>
+                      ))}
+                    </ul>
+                  )}
+                  {dictionary.length > 0 && (
+                    <div className="flex flex-wrap gap-1">
+                      {dictionary.map(w => (
+                        <span key={w} className="flex items-center gap-1 px-2 py-0.5 text-xs bg-neutral-800 rounded border border-neutral-700">
+                          {w}
+                          <button onClick={()=>removeDictionaryWord(w)} className="text-muted hover:text-white" title="Remove"><X size={12} /></button>
+                        </span>
+                      ))}
+                    </div>
+                  )}
+                </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Packs</h2>
+                <div className="space-y-3">
+                  {builtinPacks.map(p => (
+                    <div key={p.id} className="flex items-center justify-between">
+                      <div>
+                        <div className="text-sm">{p.name}</div>
+                        <div className="text-xs text-muted">{p.description}{p.apps.length > 0 && ` · also on for ${p.apps.join(', ')}`}</div>
+                      </div>
+                      <Switch checked={p.enabled} onCheckedChange={(v)=>{ log('📦 Toggle pack', p.id, '->', v); toggleBuiltinPack(p.id, v); }} />
+                    </div>
+                  ))}
+                  <div className="text-sm pt-2">Custom pack</div>
+                  <div className="text-xs text-muted">Your own symbols, snippets, spelling rules and prompt additions, e.g. <code>{'{"from": "java script", "to": "JavaScript"}'}</code>. Export them as a file to share, or import a team's pack</div>
+                  <textarea aria-label="Custom pack" rows={8} value={packJson} onChange={e=>setPackJson(e.target.value)} spellCheck={false} className="w-full px-2 py-1 text-xs font-mono bg-neutral-900 rounded border border-neutral-700" />
+                  <div className="flex gap-2">
+                    <button type="button" onClick={saveCustomPack} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Save pack</button>
+                    <button type="button" onClick={exportPack} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Export pack</button>
+                  </div>
+                  <div className="flex gap-2">
+                    <input aria-label="Pack file to import" value={packImportPath} onChange={e=>{ setPackImportPath(e.target.value); setPackConflicts([]); }} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="Full path, e.g. /Users/you/Downloads/legal.dhpack.json" />
+                    <button type="button" disabled={!packImportPath.trim()} onClick={()=>importPack('ask')} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Import</button>
+                  </div>
+                  {packConflicts.length > 0 && (
+                    <div className="space-y-2 text-xs">
+                      <div>{packConflicts.length} entries already exist with a different value:</div>
+                      <ul className="space-y-1 max-h-32 overflow-y-auto font-mono">
+                        {packConflicts.map(c => (
+                          <li key={`${c.section}:${c.from}`}>{c.from}: {JSON.stringify(c.current)} → {JSON.stringify(c.incoming)}</li>
+                        ))}
+                      </ul>
+                      <div className="flex gap-2">
+                        <button type="button" onClick={()=>importPack('keep')} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Keep mine</button>
+                        <button type="button" onClick={()=>importPack('replace')} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Use theirs</button>
+                      </div>
+                    </div>
+                  )}
+                </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Configuration profiles</h2>
+                <div className="space-y-3">
+                  <div className="text-xs text-muted">Complete sets of keys, providers, prompts and behavior to switch between, e.g. Work and Personal. Also in the tray under Configuration</div>
+                  <div className="space-y-1">
+                    {configProfiles.names.map(name => (
+                      <div key={name} className="flex items-center justify-between text-sm">
+                        <span>{name === configProfiles.active ? `● ${name} (active)` : name}</span>
+                        {name !== configProfiles.active && (
+                          <span className="flex gap-3 text-xs">
+                            <button type="button" onClick={()=>switchProfile(name)} className="hover:text-[var(--accent)]">Switch</button>
+                            <button type="button" onClick={()=>deleteProfile(name)} className="text-muted hover:text-red-400">Delete</button>
+                          </span>
+                        )}
+                      </div>
+                    ))}
+                  </div>
+                  <div className="flex gap-2">
+                    <input aria-label="New profile name" value={newProfileName} onChange={e=>setNewProfileName(e.target.value)} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="New profile, e.g. Streaming" />
+                    <button type="button" disabled={!newProfileName.trim()} onClick={()=>switchProfile(newProfileName.trim())} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Create</button>
+                  </div>
+                  <div className="text-xs text-muted">A new profile starts as a copy of the current settings</div>
+                </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Network proxy</h2>
+                <div className="space-y-3">
+                  <div className="text-xs text-muted">Used for transcription, AI cleanup and key tests, including the live transcription connection</div>
+                  <select aria-label="Proxy mode" value={proxy.mode} onChange={e=>setProxy({ ...proxy, mode: e.target.value as ProxyPrefs['mode'] })} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                    <option value="system">Use system proxy</option>
+                    <option value="manual">Manual</option>
+                    <option value="off">No proxy</option>
+                  </select>
+                  {proxy.mode === 'manual' && (
+                    <>
+                      <input aria-label="Proxy URL" value={proxy.url} onChange={e=>setProxy({ ...proxy, url: e.target.value })} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="http://proxy.corp:3128 or socks5://127.0.0.1:1080" />
+                      <div className="flex gap-2">
+                        <input aria-label="Proxy username" value={proxy.username} onChange={e=>setProxy({ ...proxy, username: e.target.value })} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="Username (optional)" />
+                        <input type="password" aria-label="Proxy password" value={proxyPassword} onChange={e=>setProxyPassword(e.target.value)} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder={proxyHasPassword ? 'Saved' : 'Password'} />
+                      </div>
+                      <textarea aria-label="Hosts that skip the proxy" value={proxy.bypass.join('\n')} onChange={e=>setProxy({ ...proxy, bypass: e.target.value.split('\n') })} rows={3} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder={'Hosts that skip the proxy, one per line\nlocalhost\n.corp.example'} />
+                    </>
+                  )}
+                  {proxy.mode === 'system' && <div className="text-xs text-muted">Follows the OS proxy settings; the live connection uses HTTPS_PROXY, ALL_PROXY and NO_PROXY</div>}
+                  <button type="button" onClick={()=>saveProxy()} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Save proxy</button>
+                </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Backup</h2>
+                <div className="space-y-3">
+                  <div className="text-xs text-muted">Save your settings, prompts, packs, dictionary and app profiles to one file, to move to another machine or restore after a reinstall. API keys are only included with a passphrase, encrypted with it</div>
+                  <input type="password" aria-label="Backup passphrase" value={backupPassphrase} onChange={e=>setBackupPassphrase(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="Passphrase for API keys (optional)" />
+                  <button type="button" onClick={exportSettings} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Export settings</button>
+                  <div className="flex gap-2">
+                    <input aria-label="Settings backup to import" value={backupImportPath} onChange={e=>setBackupImportPath(e.target.value)} className="flex-1 px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm" placeholder="Full path, e.g. /Users/you/Downloads/dictation-hud-settings-2026-01-31.dhsettings.json" />
+                    <button type="button" disabled={!backupImportPath.trim()} onClick={importSettings} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm disabled:opacity-50">Restore</button>
+                  </div>
+                </div>
+              </section>
+
+              <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit mt-4">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Audio behavior</h2>
+          <div className="space-y-4">
+            <div>
+              <label htmlFor="audio-device" className="block text-sm">Microphone</label>
+              <div className="text-xs text-muted mb-1">When the system default changes (docking, headset) or your choice is unplugged, a running dictation moves to the new default</div>
+              <select id="audio-device" value={audioDevice} onChange={e=>chooseAudioDevice(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                <option value="">System default{audioDevices.find(d => d.is_default) ? ` (${audioDevices.find(d => d.is_default)!.label})` : ''}</option>
+                {audioDevices.map(d => <option key={d.id} value={d.id}>{d.label}{d.selected && audioDevice ? ' (in use)' : ''}</option>)}
+              </select>
+              {deviceInUse && (
+                <div className="mt-2 flex items-center justify-between gap-2">
+                  <label htmlFor="device-noise" className="text-xs text-muted">Noise suppression for {deviceInUse.label}</label>
+                  <select id="device-noise" value={deviceInUse.noise_mode ?? ''} onChange={e=>chooseDeviceNoise(deviceInUse.id, e.target.value as NoiseMode | '')} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-xs">
+                    <option value="">Global setting</option>
+                    <option value="off">Off</option>
+                    <option value="browser">Built-in</option>
+                    <option value="rnnoise">RNNoise</option>
+                  </select>
+                </div>
+              )}
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Echo cancellation</div>
+                <div className="text-xs text-muted">Enable echo control when available</div>
+              </div>
+              <Switch checked={echoCancellation} onCheckedChange={(v)=>{ log('🔊 Toggle echoCancellation ->', v); setEchoCancellation(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Noise suppression</div>
+                <div className="text-xs text-muted">Enable noise filtering when available</div>
+              </div>
+              <Switch checked={noiseSuppression} onCheckedChange={(v)=>{ log('🔊 Toggle noiseSuppression ->', v); setNoiseSuppression(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Use RNNoise</div>
+                <div className="text-xs text-muted">Suppress noise in the app instead of the system, the same on every platform and provider. Audio goes through the backend relay (needs Noise suppression on)</div>
+              </div>
+              <Switch checked={rnnoise} onCheckedChange={(v)=>{ log('🔊 Toggle rnnoise ->', v); setRnnoise(v); }} />
+            </div>
+            <div className="flex items-center justify-between">
+              <div>
+                <div className="text-sm">Auto-gain</div>
+                <div className="text-xs text-muted">Even out quiet or loud input before transcription (backend relay only). You're warned about clipping either way</div>
+              </div>
+              <Switch checked={autoGain} onCheckedChange={(v)=>{ log('🔊 Toggle autoGain ->', v); setAutoGain(v); }} />
+            </div>
+          </div>
+              </section>
+
+              <UpdateSection onToast={(text: string, kind: 'ok' | 'err') => { setToast({ text, kind }); setTimeout(() => setToast(null), 3000); }} />
+              </div>
+
+              <div className="flex-1 min-w-0">
+                                                                      <section className="bg-card rounded-xl p-5 border border-neutral-800 h-fit">
+                <h2 className="text-sm uppercase tracking-wider text-muted mb-3">Providers</h2>
+          <div className="space-y-4">
+            <div className="grid md:grid-cols-2 gap-3">
+              <div>
+                <label className="block text-xs text-muted">AI provider</label>
+                <div className="relative">
+                  <button
+                    type="button"
+                    onClick={()=> setAiMenuOpen(o=>!o)}
+                    className="w-full px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-left flex items-center justify-between"
+                  >
+                    <span className="text-sm">{aiProvider === 'openrouter' ? 'OpenRouter' : 'MegaLLM'}</span>
+                    <ChevronDown size={14} className="text-muted" />
+                  </button>
+                  <AnimatePresence>
+                    {aiMenuOpen && (
+                      <motion.div
+                        initial={{ opacity: 0, y: 4 }}
+                        animate={{ opacity: 1, y: 0 }}
+                        exit={{ opacity: 0, y: 4 }}
+                        transition={{ duration: 0.12 }}
+                        className="absolute left-0 right-0 mt-1 rounded-lg border border-neutral-800 bg-neutral-900 shadow-xl z-30 overflow-hidden"
+                      >
+                        {['openrouter','megallm'].map(opt => (
+                          <button
+                            key={opt}
+                            type="button"
+                            className={`w-full text-left px-3 py-2 text-sm hover:bg-neutral-800 ${aiProvider===opt?'text-accent':'text-[var(--fg)]'}`}
+                            onMouseDown={() => { switchProvider(opt as any); setAiMenuOpen(false); }}
+                          >
+                            {opt === 'openrouter' ? 'OpenRouter' : 'MegaLLM'}
+                          </button>
+                        ))}
+                      </motion.div>
+                    )}
+                  </AnimatePresence>
+                </div>
+              </div>
+              <div>
+                <label className="block text-xs text-muted">STT provider</label>
+                <div className="relative">
+                <button
+                  type="button"
+                  onClick={()=> setSttMenuOpen(o=>!o)}
+                  className="w-full px-3 py-2 bg-neutral-900 border border-neutral-700 rounded text-left flex items-center justify-between"
+                >
+                    <span className="text-sm">{sttProvider === 'elevenlabs' ? 'ElevenLabs' : 'Deepgram'}</span>
+                    <ChevronDown size={14} className="text-muted" />
+                  </button>
+                  <AnimatePresence>
+                    {sttMenuOpen && (
+                      <motion.div
+                        initial={{ opacity: 0, y: 4 }}
+                        animate={{ opacity: 1, y: 0 }}
+                        exit={{ opacity: 0, y: 4 }}
+                        transition={{ duration: 0.12 }}
+                        className="absolute left-0 right-0 mt-1 rounded-lg border border-neutral-800 bg-neutral-900 shadow-xl z-30 overflow-hidden"
+                      >
+                        {['deepgram','elevenlabs'].map(opt => (
+                          <button
+                            key={opt}
+                            type="button"
+                            className={`w-full text-left px-3 py-2 text-sm hover:bg-neutral-800 ${sttProvider===opt?'text-accent':'text-[var(--fg)]'}`}
+                            onMouseDown={() => { switchSttProvider(opt as any); setSttMenuOpen(false); }}
+                          >
+                            {opt === 'deepgram' ? 'Deepgram' : 'ElevenLabs'}
+                          </button>
+                        ))}
+                      </motion.div>
+                    )}
+                  </AnimatePresence>
+                </div>
+              </div>
+            </div>
+
+            {aiProvider === 'openrouter' ? (
+              <div className="space-y-1">
                 <label htmlFor="openrouter" className="block text-xs text-muted">OpenRouter API key</label>
                 <div className="flex gap-2 items-center">
                   <input
@@ -1006,6 +2627,7 @@ async function persistBehavior() {
                   <button aria-label="Test OpenRouter key" title="Test OpenRouter key" onClick={testOpenRouter} className="px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition disabled:opacity-50" disabled={testing==='or' || (!valid.or && openrouterKey!=='')}>{testing==='or'?'Testing…':'Test'}</button>
                 </div>
                 {!valid.or && openrouterKey!=='' && <div className="text-xs text-red-400 mt-1">Key format invalid</div>}
+                {keyCheckMessage('openrouter', 'OpenRouter')}
               </div>
             ) : (
               <div className="space-y-1">
@@ -1024,6 +2646,7 @@ async function persistBehavior() {
                   <button aria-label="Test MegaLLM key" title="Test MegaLLM key" onClick={testMegaLLM} className="px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition disabled:opacity-50" disabled={testingMega || (!valid.mg && megallmKey!=='')}>{testingMega?'Testing…':'Test'}</button>
                 </div>
                 {!valid.mg && megallmKey!=='' && <div className="text-xs text-red-400 mt-1">Key format invalid</div>}
+                {keyCheckMessage('megallm', 'MegaLLM')}
               </div>
             )}
 
@@ -1037,6 +2660,39 @@ async function persistBehavior() {
                   <button aria-label="Test Deepgram key" title="Test Deepgram key" onClick={testDeepgram} className="px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition disabled:opacity-50" disabled={testing==='dg' || (!valid.dg && deepgramKey!=='')}>{testing==='dg'?'Testing...':'Test'}</button>
                 </div>
                 {!valid.dg && deepgramKey!=='' && <div className="text-xs text-red-400 mt-1">Key format invalid</div>}
+                {keyCheckMessage('deepgram', 'Deepgram')}
+                {sttOptions && (
+                  <div className="mt-3 space-y-2">
+                    <div className="flex items-center justify-between">
+                      <span className="text-xs text-muted">Model</span>
+                      <select aria-label="Deepgram model" value={sttOptions.deepgram.model} onChange={e=>setDgOptions({ ...sttOptions.deepgram, model: e.target.value })} className="px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm">
+                        <option value="nova-2">Nova-2</option>
+                        <option value="nova-3">Nova-3</option>
+                      </select>
+                    </div>
+                    <div className="flex items-center justify-between">
+                      <span className="text-xs text-muted">Smart formatting (numbers, dates)</span>
+                      <Switch ariaLabel="Smart formatting" checked={sttOptions.deepgram.smart_format} onCheckedChange={(v)=> setDgOptions({ ...sttOptions.deepgram, smart_format: v })} />
+                    </div>
+                    <div className="flex items-center justify-between">
+                      <span className="text-xs text-muted">Punctuation</span>
+                      <Switch ariaLabel="Punctuation" checked={sttOptions.deepgram.punctuate} onCheckedChange={(v)=> setDgOptions({ ...sttOptions.deepgram, punctuate: v })} />
+                    </div>
+                    <div className="flex items-center justify-between">
+                      <span className="text-xs text-muted">Live partial transcripts</span>
+                      <Switch ariaLabel="Live partial transcripts" checked={sttOptions.deepgram.interim_results} onCheckedChange={(v)=> setDgOptions({ ...sttOptions.deepgram, interim_results: v })} />
+                    </div>
+                    <div className="flex items-center justify-between gap-2">
+                      <label htmlFor="dg-endpointing" className="text-xs text-muted">Endpointing (ms of silence; empty for default, 0 for off)</label>
+                      <input id="dg-endpointing" type="number" min={0} max={10000} value={sttOptions.deepgram.endpointing_ms ?? ''} onChange={e=>setDgOptions({ ...sttOptions.deepgram, endpointing_ms: e.target.value === '' ? null : Number(e.target.value) })} className="w-24 px-2 py-1 bg-neutral-900 rounded border border-neutral-700" />
+                    </div>
+                    <div>
+                      <label htmlFor="dg-endpoint" className="text-xs text-muted">Endpoint (self-hosted Deepgram or a compatible proxy; empty for api.deepgram.com)</label>
+                      <input id="dg-endpoint" value={sttOptions.stt_endpoint_override} onChange={e=>setSttOptions({ ...sttOptions, stt_endpoint_override: e.target.value })} placeholder="https://deepgram.corp.example:8443" className="w-full mt-1 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm" />
+                    </div>
+                    <button type="button" onClick={saveSttOptions} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Save Deepgram options</button>
+                  </div>
+                )}
               </div>
             ) : (
               <div>
@@ -1047,6 +2703,16 @@ async function persistBehavior() {
                   <button aria-label="Test ElevenLabs key" title="Test ElevenLabs key" onClick={testElevenLabs} className="px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition disabled:opacity-50" disabled={testing==='el' || (!valid.el && elevenlabsKey!=='')}>{testing==='el'?'Testing...':'Test'}</button>
                 </div>
                 {!valid.el && elevenlabsKey!=='' && <div className="text-xs text-red-400 mt-1">Key format invalid</div>}
+                {keyCheckMessage('elevenlabs', 'ElevenLabs')}
+                {sttOptions && (
+                  <div className="mt-3 space-y-2">
+                    <div className="flex items-center justify-between gap-2">
+                      <label htmlFor="el-model" className="text-xs text-muted">Realtime model</label>
+                      <input id="el-model" value={sttOptions.elevenlabs.model} onChange={e=>setElOptions({ ...sttOptions.elevenlabs, model: e.target.value.trim() })} placeholder="scribe_v2_realtime" className="w-48 px-2 py-1 bg-neutral-900 rounded border border-neutral-700 text-sm" />
+                    </div>
+                    <button type="button" onClick={saveSttOptions} className="px-3 py-1.5 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-sm">Save ElevenLabs options</button>
+                  </div>
+                )}
               </div>
             )}
 
@@ -1097,8 +2763,8 @@ async function persistBehavior() {
                 )}
               </div>
               <div>
-                <label htmlFor="language" className="block text-xs text-muted">Language</label>
-                <input id="language" value={lang} onChange={e=>setLang(e.target.value)} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700" placeholder="en-US" />
+                <label htmlFor="language" className="block text-xs text-muted">Language ({sttProvider === 'elevenlabs' ? 'ElevenLabs' : 'Deepgram'})</label>
+                <input id="language" value={languages[sttProvider]} onChange={e=>setLanguages(prev => ({ ...prev, [sttProvider]: e.target.value }))} className="w-full px-3 py-2 bg-neutral-900 rounded border border-neutral-700" placeholder="en, bn or auto" />
               </div>
               <motion.button
                 onClick={persistProviderPrefs}
@@ -1111,18 +2777,64 @@ async function persistBehavior() {
               </motion.button>
             </div>
             <div className="text-xs text-muted">Keys stored securely (Stronghold). We never upload audio.</div>
-            {((import.meta as any).env?.DEV) && (
-              <button
-                type="button"
-                onClick={async () => {
-                  await invoke('export_test_keys');
-                  setToast({ text: 'Keys exported to terminal - check console', kind: 'ok' });
-                  setTimeout(() => setToast(null), 3000);
-                }}
-                className="w-full mt-2 px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs"
-              >
-                🧪 Export Keys for Testing (check terminal)
-              </button>
+            <button
+              type="button"
+              onClick={inspectKeys}
+              disabled={inspectingKeys}
+              className="w-full mt-2 px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs disabled:opacity-50 flex items-center justify-center gap-2"
+            >
+              {inspectingKeys && <Loader2 className="animate-spin" size={14} />}
+              Check which keys are in use
+            </button>
+            {keyInspections && (
+              <ul className="text-xs space-y-1">
+                {keyInspections.map(k => (
+                  <li key={k.provider} className="flex justify-between gap-2" title={k.detail ?? undefined}>
+                    <span>{k.provider}</span>
+                    <span className="font-mono text-muted">{k.prefix ? `${k.prefix}… (${k.length} chars)` : '—'}</span>
+                    <span className={k.status === 'valid' ? 'text-green-400' : k.status === 'invalid' ? 'text-red-400' : 'text-muted'}>{k.status}</span>
+                  </li>
+                ))}
+              </ul>
+            )}
+            <button
+              type="button"
+              onClick={checkProviderUsage}
+              disabled={checkingUsage}
+              className="w-full mt-2 px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs disabled:opacity-50 flex items-center justify-center gap-2"
+            >
+              {checkingUsage && <Loader2 className="animate-spin" size={14} />}
+              Check remaining credit
+            </button>
+            {providerUsage && (
+              <ul className="text-xs space-y-1">
+                {providerUsage.length === 0 && <li className="text-muted">No speech-to-text keys saved</li>}
+                {providerUsage.map(u => (
+                  <li key={u.provider} className="flex justify-between gap-2">
+                    <span>{u.provider}</span>
+                    <span className={u.error ? 'text-muted' : u.low ? 'text-red-400' : 'text-green-400'}>{u.low ? '⚠ ' : ''}{usageLabel(u)}</span>
+                  </li>
+                ))}
+              </ul>
+            )}
+            <button
+              type="button"
+              onClick={loadCostSummary}
+              className="w-full mt-2 px-3 py-2 bg-neutral-800 rounded border border-neutral-700 hover:bg-neutral-700 transition text-xs"
+            >
+              Show estimated cost
+            </button>
+            {costSummary && (
+              <ul className="text-xs space-y-1">
+                {costSummary.months.length === 0 && <li className="text-muted">Nothing dictated yet</li>}
+                {costSummary.months.map(m => (
+                  <li key={m.month} className="flex justify-between gap-2" title={`Speech-to-text $${m.stt_usd.toFixed(2)}, AI $${m.ai_usd.toFixed(2)}`}>
+                    <span>{m.month}</span>
+                    <span className="text-muted">{m.dictations} dictations, {Math.round(m.audio_ms / 60_000)} min{m.unpriced ? `, ${m.unpriced} unpriced` : ''}</span>
+                    <span>~${m.total_usd.toFixed(2)}</span>
+                  </li>
+                ))}
+              </ul>
             )}
           </div>
         </section>
@@ -1131,6 +2843,11 @@ async function persistBehavior() {
           </main>
         </div>
       </div>
+      {dropActive && (
+        <div className="fixed inset-0 z-50 flex items-center justify-center bg-black/60 pointer-events-none">
+          <div className="px-5 py-3 rounded-xl border border-dashed border-neutral-500 bg-card text-sm">Drop an audio file or a folder to transcribe it</div>
+        </div>
+      )}
       <AnimatePresence>
         {toast && <Toast text={toast.text} kind={toast.kind}/>}
       </AnimatePresence>